@@ -22,4 +22,9 @@ mod HelloStarknet {
             self.balance.read()
         }
     }
+
+    #[l1_handler]
+    fn deposit(ref self: ContractState, from_address: felt252, amount: felt252) {
+        self.balance.write(self.balance.read() + amount);
+    }
 }
\ No newline at end of file
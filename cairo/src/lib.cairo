@@ -1,5 +1,6 @@
 
 pub mod erc20;
+pub mod erc721;
 pub mod udc;
 pub mod account;
 pub mod test_account;
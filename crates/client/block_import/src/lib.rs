@@ -39,21 +39,27 @@
 //! A signature verification mode should be added to allow the skipping of block validation entirely if the block is signed.
 
 use anyhow::Context;
-use mc_db::{MadaraBackend, MadaraStorageError};
+use mc_db::{BlockRevert, MadaraBackend, MadaraStorageError};
 use mc_metrics::MetricsRegistry;
 use metrics::BlockMetrics;
 use mp_class::{class_hash::ComputeClassHashError, compile::ClassCompilationError};
+use mp_keystore::StarknetSigner;
 use starknet_core::types::Felt;
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    sync::{Arc, Mutex},
+};
 
 mod metrics;
 mod pre_validate;
 mod rayon;
 pub mod tests;
+mod trie_rebuild;
 mod types;
 mod verify_apply;
 pub use pre_validate::*;
 pub use rayon::*;
+pub use trie_rebuild::TrieRebuildStatus;
 pub use types::*;
 pub use verify_apply::*;
 
@@ -101,6 +107,15 @@ pub enum BlockImportError {
     #[error("Global state root mismatch: expected {expected:#x}, got {got:#x}")]
     GlobalStateRoot { got: Felt, expected: Felt },
 
+    #[error("A trie rebuild is already running")]
+    TrieRebuildRunning,
+    #[error("The global tries are not maintained by this node, which runs with `--no-global-tries`")]
+    GlobalTriesDisabled,
+
+    /// Internal error, see [`BlockImportError::is_internal`]: the global tries are already updated when the block is
+    /// signed.
+    #[error("Block signing error: {0:#}")]
+    BlockSigning(anyhow::Error),
     /// Internal error, see [`BlockImportError::is_internal`].
     #[error("Internal database error while {context}: {error:#}")]
     InternalDb { context: Cow<'static, str>, error: MadaraStorageError },
@@ -112,7 +127,10 @@ pub enum BlockImportError {
 impl BlockImportError {
     /// Unrecoverable errors.
     pub fn is_internal(&self) -> bool {
-        matches!(self, BlockImportError::InternalDb { .. } | BlockImportError::Internal(_))
+        matches!(
+            self,
+            BlockImportError::InternalDb { .. } | BlockImportError::Internal(_) | BlockImportError::BlockSigning(_)
+        )
     }
 }
 pub struct BlockImporter {
@@ -121,6 +139,7 @@ pub struct BlockImporter {
     verify_apply: VerifyApply,
     metrics: BlockMetrics,
     always_force_flush: bool,
+    trie_rebuild: Mutex<Option<TrieRebuildStatus>>,
 }
 
 impl BlockImporter {
@@ -149,6 +168,7 @@ impl BlockImporter {
                 .context("Registering metrics for block import")?,
             backend,
             always_force_flush,
+            trie_rebuild: Default::default(),
         })
     }
 
@@ -175,7 +195,18 @@ impl BlockImporter {
         block: PreValidatedBlock,
         validation: BlockValidationContext,
     ) -> Result<BlockImportResult, BlockImportError> {
-        let result = self.verify_apply.verify_apply(block, validation).await?;
+        self.verify_apply_signed(block, validation, None).await
+    }
+
+    /// Same as [`BlockImporter::verify_apply`], signing the block hash with `signer` when set. The signature is stored
+    /// along with the block.
+    pub async fn verify_apply_signed(
+        &self,
+        block: PreValidatedBlock,
+        validation: BlockValidationContext,
+        signer: Option<Arc<dyn StarknetSigner>>,
+    ) -> Result<BlockImportResult, BlockImportError> {
+        let result = self.verify_apply.verify_apply(block, validation, signer).await?;
         // Flush step.
         let force = self.always_force_flush;
         self.backend
@@ -200,4 +231,19 @@ impl BlockImporter {
     ) -> Result<PendingBlockImportResult, BlockImportError> {
         self.verify_apply.verify_apply_pending(block, validation).await
     }
+
+    /// Waits for the block being imported, if any. An import keeps writing to the database after its future is dropped,
+    /// such as when the sync is aborted.
+    pub async fn wait_for_imports(&self) {
+        self.verify_apply.wait_for_writes().await
+    }
+
+    /// Reverts the chain to `block_n`, see [`revert_to_inner`]. Nothing can be imported meanwhile.
+    pub async fn revert_to(&self, block_n: u64) -> Result<BlockRevert, BlockImportError> {
+        let revert = self.verify_apply.revert_to(block_n).await?;
+        self.backend
+            .maybe_flush(true)
+            .map_err(|err| BlockImportError::Internal(format!("DB flushing error: {err:#}").into()))?;
+        Ok(revert)
+    }
 }
@@ -0,0 +1,134 @@
+//! Background rebuild of the global tries from the flat state, to recover from a corruption of the tries without
+//! resyncing the chain.
+//!
+//! Each trie is built from scratch in memory, and replaces the stored one when their roots differ. The storage tries
+//! are rebuilt by chunks of [`CHUNK_SIZE`] contracts, each taking the block import lock, with a pause of
+//! [`CHUNK_INTERVAL`] between chunks: blocks keep being imported and the database stays available for the RPC meanwhile.
+//! The contract and class tries are then rebuilt while holding the block import lock, and only replace the stored ones
+//! when the global state root they give matches the one of the latest block.
+//!
+//! Blocks imported while the tries are still corrupted will fail their state root check, or in block production mode
+//! commit to a wrong state root. Replacing a trie clears its trie logs: the chain cannot be reverted to a block before
+//! the rebuild.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Felt;
+
+use crate::{BlockImportError, BlockImporter};
+
+/// Number of contracts rebuilt at once, while holding the block import lock.
+const CHUNK_SIZE: usize = 256;
+/// Pause between two chunks, to leave room for the block import and the RPC.
+const CHUNK_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TrieRebuildStatus {
+    Running {
+        rebuilt_contracts: usize,
+        total_contracts: usize,
+    },
+    Done {
+        block_number: u64,
+        /// Global state root of the rebuilt tries, which is the one in the header of the block.
+        global_state_root: Felt,
+        /// Number of contract storage tries which differed from the flat state and were replaced.
+        replaced_storage_tries: usize,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl BlockImporter {
+    /// Starts rebuilding the storage tries of `contracts`, or of all the contracts by default, and the contract trie
+    /// from the latest flat state. The class trie is only rebuilt by default. The progress is reported by
+    /// [`Self::trie_rebuild_status`].
+    ///
+    /// The flat state of a forked chain does not hold the state from before the fork, its tries cannot be rebuilt.
+    pub fn start_trie_rebuild(self: &Arc<Self>, contracts: Option<Vec<Felt>>) -> Result<(), BlockImportError> {
+        if self
+            .backend
+            .get_global_tries_disabled_since()
+            .map_err(|error| BlockImportError::InternalDb { context: "getting the global tries mode".into(), error })?
+            .is_some()
+        {
+            return Err(BlockImportError::GlobalTriesDisabled);
+        }
+
+        let mut status = self.trie_rebuild.lock().expect("Poisoned lock");
+        if matches!(*status, Some(TrieRebuildStatus::Running { .. })) {
+            return Err(BlockImportError::TrieRebuildRunning);
+        }
+        *status = Some(TrieRebuildStatus::Running {
+            rebuilt_contracts: 0,
+            total_contracts: contracts.as_ref().map_or(0, Vec::len),
+        });
+        drop(status);
+
+        let importer = Arc::clone(self);
+        tokio::spawn(async move {
+            let status = match importer.rebuild_tries(contracts).await {
+                Ok(status) => status,
+                Err(err) => {
+                    log::error!("❗ Trie rebuild failed: {err:#}");
+                    TrieRebuildStatus::Failed { error: format!("{err:#}") }
+                }
+            };
+            *importer.trie_rebuild.lock().expect("Poisoned lock") = Some(status);
+        });
+        Ok(())
+    }
+
+    /// Status of the last trie rebuild, `None` if none was started since the node started.
+    pub fn trie_rebuild_status(&self) -> Option<TrieRebuildStatus> {
+        self.trie_rebuild.lock().expect("Poisoned lock").clone()
+    }
+
+    async fn rebuild_tries(&self, contracts: Option<Vec<Felt>>) -> Result<TrieRebuildStatus, BlockImportError> {
+        let rebuild_classes = contracts.is_none();
+        let contracts = match contracts {
+            Some(mut contracts) => {
+                contracts.sort_unstable();
+                contracts.dedup();
+                contracts
+            }
+            None => {
+                let backend = Arc::clone(&self.backend);
+                self.pool
+                    .spawn_rayon_task(move || backend.get_contract_addresses())
+                    .await
+                    .map_err(|error| BlockImportError::InternalDb { context: "listing contracts".into(), error })?
+            }
+        };
+        log::info!("🔨 Rebuilding the tries of {} contracts", contracts.len());
+
+        let mut replaced_storage_tries = 0;
+        for (i, chunk) in contracts.chunks(CHUNK_SIZE).enumerate() {
+            if i > 0 {
+                tokio::time::sleep(CHUNK_INTERVAL).await;
+            }
+            replaced_storage_tries += self.verify_apply.rebuild_contract_tries(chunk.to_vec()).await?;
+
+            let rebuilt_contracts = i * CHUNK_SIZE + chunk.len();
+            log::debug!("Rebuilt the tries of {rebuilt_contracts}/{} contracts", contracts.len());
+            *self.trie_rebuild.lock().expect("Poisoned lock") =
+                Some(TrieRebuildStatus::Running { rebuilt_contracts, total_contracts: contracts.len() });
+        }
+
+        // The storage tries replaced so far are flushed even when the global tries are not replaced.
+        let finished = self.verify_apply.finish_trie_rebuild(rebuild_classes).await;
+        self.backend
+            .maybe_flush(true)
+            .map_err(|err| BlockImportError::Internal(format!("DB flushing error: {err:#}").into()))?;
+        let (block_number, global_state_root) = finished?;
+
+        log::info!(
+            "🔨 Rebuilt the tries at block #{block_number}, replacing {replaced_storage_tries} contract storage tries"
+        );
+        Ok(TrieRebuildStatus::Done { block_number, global_state_root, replaced_storage_tries })
+    }
+}
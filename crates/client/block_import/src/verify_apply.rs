@@ -3,26 +3,31 @@ use crate::{
     PreValidatedPendingBlock, RayonPool, UnverifiedHeader, ValidatedCommitments,
 };
 use itertools::Itertools;
-use mc_db::{MadaraBackend, MadaraStorageError};
+use mc_db::{bonsai_identifier, BlockRevert, MadaraBackend, MadaraStorageError, MAX_SAVED_TRIE_LOGS};
 use mp_block::{
     header::PendingHeader, BlockId, BlockTag, Header, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock,
     MadaraMaybePendingBlockInfo, MadaraPendingBlockInfo,
 };
 use mp_convert::{FeltHexDisplay, ToFelt};
-use starknet_api::core::ChainId;
+use mp_keystore::StarknetSigner;
 use starknet_core::types::Felt;
 use starknet_types_core::hash::{Poseidon, StarkHash};
 use std::{borrow::Cow, sync::Arc};
 
+mod block_hash;
 mod classes;
 mod contracts;
 
+use block_hash::BlockHashQuirks;
+
 pub struct VerifyApply {
     pool: Arc<RayonPool>,
     pub(crate) backend: Arc<MadaraBackend>,
     // Only one thread at once can verify_apply. This is the update trie step cannot be parallelized over blocks, and in addition
     // our database does not support concurrent write access.
-    mutex: tokio::sync::Mutex<()>,
+    // The lock is held by the rayon task, so that it is only released once the database is written to, even when the
+    // importing future is dropped.
+    mutex: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl VerifyApply {
@@ -30,16 +35,30 @@ impl VerifyApply {
         Self { pool, backend, mutex: Default::default() }
     }
 
-    /// This function wraps the [`verify_apply_inner`] step, which runs on the rayon pool, in a tokio-friendly future.
+    /// This function wraps the [`verify_apply_signed_inner`] step, which runs on the rayon pool, in a tokio-friendly
+    /// future. The block is signed with `signer` when set.
     pub async fn verify_apply(
         &self,
         block: PreValidatedBlock,
         validation: BlockValidationContext,
+        signer: Option<Arc<dyn StarknetSigner>>,
     ) -> Result<BlockImportResult, BlockImportError> {
-        let _exclusive = self.mutex.lock().await;
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
 
         let backend = Arc::clone(&self.backend);
-        self.pool.spawn_rayon_task(move || verify_apply_inner(&backend, block, validation)).await
+        let runtime = tokio::runtime::Handle::current();
+        self.pool
+            .spawn_rayon_task(move || {
+                let _exclusive = exclusive;
+                verify_apply_signed_inner(&backend, block, validation, |block_hash| {
+                    let Some(signer) = signer else { return Ok(None) };
+                    // Signers may be remote. This runs on the rayon pool, outside of the tokio workers.
+                    let signature =
+                        runtime.block_on(signer.sign(block_hash)).map_err(BlockImportError::BlockSigning)?;
+                    Ok(Some(vec![signature.r, signature.s]))
+                })
+            })
+            .await
     }
 
     /// See [`Self::verify_apply`].
@@ -48,10 +67,60 @@ impl VerifyApply {
         block: PreValidatedPendingBlock,
         validation: BlockValidationContext,
     ) -> Result<PendingBlockImportResult, BlockImportError> {
-        let _exclusive = self.mutex.lock().await;
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
 
         let backend = Arc::clone(&self.backend);
-        self.pool.spawn_rayon_task(move || verify_apply_pending_inner(&backend, block, validation)).await
+        self.pool
+            .spawn_rayon_task(move || {
+                let _exclusive = exclusive;
+                verify_apply_pending_inner(&backend, block, validation)
+            })
+            .await
+    }
+
+    /// This function wraps the [`revert_to_inner`] step, which runs on the rayon pool, in a tokio-friendly future.
+    pub async fn revert_to(&self, block_n: u64) -> Result<BlockRevert, BlockImportError> {
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
+
+        let backend = Arc::clone(&self.backend);
+        self.pool
+            .spawn_rayon_task(move || {
+                let _exclusive = exclusive;
+                revert_to_inner(&backend, block_n)
+            })
+            .await
+    }
+
+    /// This function wraps the [`rebuild_contract_tries_inner`] step, which runs on the rayon pool, in a tokio-friendly
+    /// future.
+    pub async fn rebuild_contract_tries(&self, contracts: Vec<Felt>) -> Result<usize, BlockImportError> {
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
+
+        let backend = Arc::clone(&self.backend);
+        self.pool
+            .spawn_rayon_task(move || {
+                let _exclusive = exclusive;
+                rebuild_contract_tries_inner(&backend, &contracts)
+            })
+            .await
+    }
+
+    /// See [`Self::rebuild_contract_tries`].
+    pub async fn finish_trie_rebuild(&self, rebuild_classes: bool) -> Result<(u64, Felt), BlockImportError> {
+        let exclusive = Arc::clone(&self.mutex).lock_owned().await;
+
+        let backend = Arc::clone(&self.backend);
+        self.pool
+            .spawn_rayon_task(move || {
+                let _exclusive = exclusive;
+                finish_trie_rebuild_inner(&backend, rebuild_classes)
+            })
+            .await
+    }
+
+    /// Waits for the block being written to the database, if any.
+    pub async fn wait_for_writes(&self) {
+        let _exclusive = self.mutex.lock().await;
     }
 }
 
@@ -62,6 +131,17 @@ pub fn verify_apply_inner(
     backend: &MadaraBackend,
     block: PreValidatedBlock,
     validation: BlockValidationContext,
+) -> Result<BlockImportResult, BlockImportError> {
+    verify_apply_signed_inner(backend, block, validation, |_| Ok(None))
+}
+
+/// Same as [`verify_apply_inner`], with the signature of the block hash returned by `sign` written in the same batch
+/// as the block.
+pub fn verify_apply_signed_inner(
+    backend: &MadaraBackend,
+    block: PreValidatedBlock,
+    validation: BlockValidationContext,
+    sign: impl FnOnce(&Felt) -> Result<Option<Vec<Felt>>, BlockImportError>,
 ) -> Result<BlockImportResult, BlockImportError> {
     // Check block number and block hash against db
     let (block_number, parent_block_hash) =
@@ -73,11 +153,13 @@ pub fn verify_apply_inner(
     // Block hash
     let (block_hash, header) = block_hash(&block, &validation, block_number, parent_block_hash, global_state_root)?;
 
+    let signature = sign(&block_hash)?;
+
     log::debug!("verify_apply_inner store block {}", header.block_number);
 
     // store block, also uses rayon heavily internally
     backend
-        .store_block(
+        .store_signed_block(
             MadaraMaybePendingBlock {
                 info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
                     header: header.clone(),
@@ -89,12 +171,124 @@ pub fn verify_apply_inner(
             },
             block.state_diff,
             block.converted_classes,
+            signature,
         )
         .map_err(make_db_error("storing block in db"))?;
 
     Ok(BlockImportResult { header, block_hash })
 }
 
+/// Removes the blocks after `block_n` and reverts the global tries to their state at `block_n`, which becomes the
+/// latest block. The pending block is cleared.
+///
+/// The tries are reverted from their logs, and checked against the global state root of `block_n` before any block is
+/// removed: the blocks are kept when the tries cannot be reverted, so that they can still be rebuilt from the flat
+/// state. Only the last [`MAX_SAVED_TRIE_LOGS`] blocks can be reverted.
+pub fn revert_to_inner(backend: &MadaraBackend, block_n: u64) -> Result<BlockRevert, BlockImportError> {
+    let latest_block_n = backend
+        .get_latest_block_n()
+        .map_err(make_db_error("getting latest block number"))?
+        .ok_or(BlockImportError::Internal("Cannot revert an empty chain".into()))?;
+    if block_n > latest_block_n {
+        return Err(BlockImportError::Internal(
+            format!("Cannot revert to block #{block_n}, the latest block is #{latest_block_n}").into(),
+        ));
+    }
+    let expected_state_root = backend
+        .get_block_info(&BlockId::Number(block_n))
+        .map_err(make_db_error("getting block info"))?
+        .as_ref()
+        .and_then(|info| info.as_nonpending())
+        .ok_or(BlockImportError::Internal(format!("Block #{block_n} not found in db").into()))?
+        .header
+        .global_state_root;
+
+    if latest_block_n > block_n {
+        if latest_block_n - block_n > MAX_SAVED_TRIE_LOGS as u64 {
+            return Err(BlockImportError::Internal(
+                format!("Cannot revert more than {MAX_SAVED_TRIE_LOGS} blocks, the latest block is #{latest_block_n}")
+                    .into(),
+            ));
+        }
+
+        let (contract_trie_root, class_trie_root) = rayon::join(
+            || contracts::revert_contract_trie(backend, block_n, latest_block_n),
+            || classes::revert_class_trie(backend, block_n, latest_block_n),
+        );
+        let state_root = calculate_state_root(
+            contract_trie_root.map_err(make_db_error("reverting contract trie"))?,
+            class_trie_root.map_err(make_db_error("reverting class trie"))?,
+        );
+        if state_root != expected_state_root {
+            return Err(BlockImportError::GlobalStateRoot { got: state_root, expected: expected_state_root });
+        }
+    }
+
+    backend.revert_to(block_n).map_err(make_db_error("reverting blocks in db"))?;
+
+    let revert = BlockRevert { from: latest_block_n, to: block_n };
+    if latest_block_n > block_n {
+        backend.notify_block_revert(revert);
+    }
+    Ok(revert)
+}
+
+/// Rebuilds the storage tries of `contracts` from the flat state at the latest block, and replaces the stored ones that
+/// differ. Returns the number of replaced tries.
+pub fn rebuild_contract_tries_inner(backend: &MadaraBackend, contracts: &[Felt]) -> Result<usize, BlockImportError> {
+    contracts::rebuild_contract_storage_tries(backend, contracts)
+        .map_err(make_db_error("rebuilding contract storage tries"))
+}
+
+/// Ends a trie rebuild started with [`rebuild_contract_tries_inner`]: rebuilds the contract trie, and the class trie if
+/// `rebuild_classes` is set, from the flat state. They only replace the stored tries when the global state root they
+/// give matches the one of the latest block, whose number is returned with that root.
+pub fn finish_trie_rebuild_inner(
+    backend: &MadaraBackend,
+    rebuild_classes: bool,
+) -> Result<(u64, Felt), BlockImportError> {
+    let Some(MadaraMaybePendingBlockInfo::NotPending(block_info)) =
+        backend.get_block_info(&BlockId::Tag(BlockTag::Latest)).map_err(make_db_error("getting latest block info"))?
+    else {
+        return Err(BlockImportError::Internal("Cannot rebuild the tries of an empty chain".into()));
+    };
+    let expected_state_root = block_info.header.global_state_root;
+
+    let stored_contract_trie_root = backend
+        .contract_trie()
+        .root_hash(bonsai_identifier::CONTRACT)
+        .map_err(|error| make_db_error("getting contract trie root")(error.into()))?;
+    let stored_class_trie_root = backend
+        .class_trie()
+        .root_hash(bonsai_identifier::CLASS)
+        .map_err(|error| make_db_error("getting class trie root")(error.into()))?;
+
+    let (contract_trie, class_trie) = rayon::join(
+        || contracts::rebuild_contract_trie(backend),
+        || rebuild_classes.then(|| classes::rebuild_class_trie(backend)).transpose(),
+    );
+    let mut contract_trie = contract_trie.map_err(make_db_error("rebuilding contract trie"))?;
+    let mut class_trie = class_trie.map_err(make_db_error("rebuilding class trie"))?;
+
+    let contract_trie_root = contract_trie.commit();
+    let class_trie_root = class_trie.as_mut().map_or(stored_class_trie_root, |class_trie| class_trie.commit());
+    let state_root = calculate_state_root(contract_trie_root, class_trie_root);
+    if state_root != expected_state_root {
+        return Err(BlockImportError::GlobalStateRoot { got: state_root, expected: expected_state_root });
+    }
+
+    if contract_trie_root != stored_contract_trie_root {
+        log::debug!("contract_trie replacing, root {stored_contract_trie_root:#x}, rebuilt {contract_trie_root:#x}");
+        backend.replace_contract_trie(&contract_trie).map_err(make_db_error("replacing contract trie"))?;
+    }
+    if let Some(class_trie) = class_trie.filter(|_| class_trie_root != stored_class_trie_root) {
+        log::debug!("class_trie replacing, root {stored_class_trie_root:#x}, rebuilt {class_trie_root:#x}");
+        backend.replace_class_trie(&class_trie).map_err(make_db_error("replacing class trie"))?;
+    }
+
+    Ok((block_info.header.block_number, state_root))
+}
+
 /// See [`verify_apply_inner`].
 pub fn verify_apply_pending_inner(
     backend: &MadaraBackend,
@@ -292,12 +486,15 @@ fn block_hash(
         l1_gas_price,
         l1_da_mode,
     };
-    let block_hash = header.compute_hash(validation.chain_id.to_felt());
+    let quirks = BlockHashQuirks::for_chain(&validation.chain_id);
+    let block_hash = quirks.compute_hash(&header, &validation.chain_id);
 
     if let Some(expected) = block.unverified_block_hash {
-        // mismatched block hash is allowed for blocks 1466..=2242 on mainnet
-        let is_special_trusted_case = validation.chain_id == ChainId::Mainnet && (1466..=2242).contains(&block_number);
-        if is_special_trusted_case {
+        if quirks.is_unverifiable(block_number) {
+            log::debug!("Trusting the hash {expected:#x} of block {block_number}, which cannot be verified");
+            return Ok((expected, header));
+        }
+        if quirks.matches_with_fallback_sequencer(&header, &validation.chain_id, expected) {
             return Ok((expected, header));
         }
 
@@ -692,6 +889,125 @@ mod verify_apply_tests {
         }
     }
 
+    mod revert_to_inner_tests {
+        use super::*;
+
+        /// Test reverting the last block.
+        ///
+        /// Verifies that:
+        /// 1. The reverted block is removed and the previous block becomes the latest block.
+        /// 2. The tries get back to the global state root of the previous block.
+        /// 3. The state written by the reverted block is removed.
+        #[rstest]
+        #[tokio::test]
+        async fn test_revert_to_inner_restores_state(setup_test_backend: Arc<MadaraBackend>) {
+            let backend = setup_test_backend;
+            let validation = create_validation_context(true);
+            let (address, key) = (felt!("0x10"), felt!("0x20"));
+            let make_block = |block_n, state_diff| PreValidatedBlock {
+                header: UnverifiedHeader { parent_block_hash: None, ..create_dummy_unverified_header() },
+                unverified_block_number: Some(block_n),
+                unverified_global_state_root: None,
+                state_diff,
+                ..create_dummy_block()
+            };
+
+            let state_diff_zero = StateDiff {
+                storage_diffs: vec![ContractStorageDiffItem {
+                    address,
+                    storage_entries: vec![StorageEntry { key, value: felt!("0x1") }],
+                }],
+                ..Default::default()
+            };
+            let state_diff_one = StateDiff {
+                storage_diffs: vec![ContractStorageDiffItem {
+                    address,
+                    storage_entries: vec![StorageEntry { key, value: felt!("0x2") }],
+                }],
+                deployed_contracts: vec![DeployedContractItem { address: felt!("0x30"), class_hash: felt!("0x40") }],
+                ..Default::default()
+            };
+            let block_zero = verify_apply_inner(&backend, make_block(0, state_diff_zero), validation.clone()).unwrap();
+            verify_apply_inner(&backend, make_block(1, state_diff_one), validation.clone()).unwrap();
+
+            let revert = revert_to_inner(&backend, 0).unwrap();
+
+            assert_eq!(revert, BlockRevert { from: 1, to: 0 });
+            assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+            assert_eq!(backend.get_block_hash(&BlockId::Tag(BlockTag::Latest)).unwrap(), Some(block_zero.block_hash));
+            let latest = BlockId::Tag(BlockTag::Latest);
+            assert_eq!(backend.get_contract_storage_at(&latest, &address, &key).unwrap(), Some(felt!("0x1")));
+            assert_eq!(backend.get_contract_class_hash_at(&latest, &felt!("0x30")).unwrap(), None);
+        }
+    }
+
+    mod trie_rebuild_tests {
+        use super::*;
+        use bitvec::{order::Msb0, vec::BitVec, view::AsBits};
+        use bonsai_trie::id::BasicId;
+
+        fn trie_key(key: Felt) -> BitVec<u8, Msb0> {
+            key.to_bytes_be().as_bits()[5..].to_owned()
+        }
+
+        /// Test rebuilding corrupted tries.
+        ///
+        /// Verifies that:
+        /// 1. A storage trie holding a value missing from the flat state is replaced, and only once.
+        /// 2. A contract trie holding the leaf of an unknown contract is replaced.
+        /// 3. The rebuilt tries give back the global state root of the latest block.
+        #[rstest]
+        #[tokio::test]
+        async fn test_rebuild_corrupted_tries(setup_test_backend: Arc<MadaraBackend>) {
+            let backend = setup_test_backend;
+            let (address, key) = (felt!("0x10"), felt!("0x20"));
+            let block = PreValidatedBlock {
+                header: UnverifiedHeader { parent_block_hash: None, ..create_dummy_unverified_header() },
+                unverified_block_number: Some(0),
+                unverified_global_state_root: None,
+                state_diff: StateDiff {
+                    storage_diffs: vec![ContractStorageDiffItem {
+                        address,
+                        storage_entries: vec![StorageEntry { key, value: felt!("0x1") }],
+                    }],
+                    deployed_contracts: vec![DeployedContractItem { address, class_hash: felt!("0x40") }],
+                    ..Default::default()
+                },
+                ..create_dummy_block()
+            };
+            let state_root =
+                verify_apply_inner(&backend, block, create_validation_context(true)).unwrap().header.global_state_root;
+
+            {
+                let mut contract_storage_trie = backend.contract_storage_trie();
+                contract_storage_trie.insert(&address.to_bytes_be(), &trie_key(felt!("0x21")), &felt!("0x2")).unwrap();
+                contract_storage_trie.commit(BasicId::new(1)).unwrap();
+                let mut contract_trie = backend.contract_trie();
+                contract_trie.insert(bonsai_identifier::CONTRACT, &trie_key(felt!("0x11")), &felt!("0x3")).unwrap();
+                contract_trie.commit(BasicId::new(1)).unwrap();
+                assert_ne!(contract_trie.root_hash(bonsai_identifier::CONTRACT).unwrap(), state_root);
+            }
+
+            assert_eq!(rebuild_contract_tries_inner(&backend, &[address]).unwrap(), 1);
+            assert_eq!(rebuild_contract_tries_inner(&backend, &[address]).unwrap(), 0);
+            assert_eq!(finish_trie_rebuild_inner(&backend, true).unwrap(), (0, state_root));
+
+            assert_eq!(backend.contract_trie().root_hash(bonsai_identifier::CONTRACT).unwrap(), state_root);
+            assert_eq!(
+                backend.contract_trie().get(bonsai_identifier::CONTRACT, &trie_key(felt!("0x11"))).unwrap(),
+                None
+            );
+            assert_eq!(
+                backend.contract_storage_trie().get(&address.to_bytes_be(), &trie_key(felt!("0x21"))).unwrap(),
+                None
+            );
+            assert_eq!(
+                backend.contract_storage_trie().get(&address.to_bytes_be(), &trie_key(key)).unwrap(),
+                Some(felt!("0x1"))
+            );
+        }
+    }
+
     mod verify_apply_pending_tests {
         use mc_db::db_block_id::DbBlockId;
 
@@ -0,0 +1,116 @@
+//! Block hashes of the early blocks of a chain. Before protocol version 0.9.1 the blocks do not carry their version,
+//! so the formula of their hash follows the height of the block instead.
+
+use std::ops::RangeInclusive;
+
+use mp_block::Header;
+use mp_convert::ToFelt;
+use starknet_api::core::ChainId;
+use starknet_types_core::felt::Felt;
+
+/// How the block hashes of a chain deviate from the formula of their protocol version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHashQuirks {
+    /// First block hashed with the formula of protocol version 0.7.0. The blocks below it use the original formula,
+    /// which commits to the chain id. When `None`, the formula follows the protocol version of the block.
+    pub first_v0_7_block: Option<u64>,
+    /// Blocks hashed with a formula that was never published. Their hash cannot be verified and is trusted instead.
+    pub unverifiable_range: Option<RangeInclusive<u64>>,
+    /// Some blocks without a sequencer address were hashed with this one instead of zero.
+    pub fallback_sequencer_address: Option<Felt>,
+}
+
+impl BlockHashQuirks {
+    pub fn for_chain(chain_id: &ChainId) -> Self {
+        match chain_id {
+            ChainId::Mainnet => Self {
+                first_v0_7_block: Some(833),
+                unverifiable_range: Some(1466..=2242),
+                fallback_sequencer_address: Some(Felt::from_hex_unchecked(
+                    "0x21f4b90b0377c82bf330b7b5295820769e72d79d8acd0effa0ebde6e9988bc5",
+                )),
+            },
+            // These networks started after protocol version 0.7.0.
+            ChainId::Sepolia | ChainId::IntegrationSepolia => {
+                Self { first_v0_7_block: Some(0), unverifiable_range: None, fallback_sequencer_address: None }
+            }
+            _ => Self { first_v0_7_block: None, unverifiable_range: None, fallback_sequencer_address: None },
+        }
+    }
+
+    /// Hash of the header, with the formula of its height and protocol version.
+    pub fn compute_hash(&self, header: &Header, chain_id: &ChainId) -> Felt {
+        match self.first_v0_7_block {
+            Some(first_v0_7_block) if header.block_number < first_v0_7_block => {
+                header.compute_hash_pre_v0_7(chain_id.to_felt())
+            }
+            _ => header.compute_hash(chain_id.to_felt()),
+        }
+    }
+
+    pub fn is_unverifiable(&self, block_number: u64) -> bool {
+        self.unverifiable_range.as_ref().is_some_and(|range| range.contains(&block_number))
+    }
+
+    /// Whether `expected` is the hash of the header with the fallback sequencer address, for the blocks that do not
+    /// have one.
+    pub fn matches_with_fallback_sequencer(&self, header: &Header, chain_id: &ChainId, expected: Felt) -> bool {
+        let Some(fallback) = self.fallback_sequencer_address else { return false };
+        if header.sequencer_address != Felt::ZERO {
+            return false;
+        }
+        let header = Header { sequencer_address: fallback, ..header.clone() };
+        self.compute_hash(&header, chain_id) == expected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::block_import_utils::create_dummy_header;
+    use mp_chain_config::StarknetVersion;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_formula_by_height() {
+        let quirks = BlockHashQuirks::for_chain(&ChainId::Mainnet);
+        let header = Header { block_number: 832, protocol_version: StarknetVersion::V0_7_0, ..create_dummy_header() };
+        assert_eq!(
+            quirks.compute_hash(&header, &ChainId::Mainnet),
+            header.compute_hash_pre_v0_7(ChainId::Mainnet.to_felt())
+        );
+
+        let header = Header { block_number: 833, ..header };
+        assert_eq!(quirks.compute_hash(&header, &ChainId::Mainnet), header.compute_hash(ChainId::Mainnet.to_felt()));
+
+        // Other chains follow the protocol version.
+        let chain_id = ChainId::Other("MADARA".into());
+        let header = Header { block_number: 0, protocol_version: StarknetVersion::V0_13_2, ..header };
+        let quirks = BlockHashQuirks::for_chain(&chain_id);
+        assert_eq!(quirks.compute_hash(&header, &chain_id), header.compute_hash(chain_id.to_felt()));
+    }
+
+    #[rstest]
+    fn test_fallback_sequencer_address() {
+        let quirks = BlockHashQuirks::for_chain(&ChainId::Mainnet);
+        let fallback = quirks.fallback_sequencer_address.unwrap();
+        let header = Header {
+            block_number: 2300,
+            sequencer_address: Felt::ZERO,
+            protocol_version: StarknetVersion::POST_LEGACY,
+            ..create_dummy_header()
+        };
+        let expected =
+            Header { sequencer_address: fallback, ..header.clone() }.compute_hash(ChainId::Mainnet.to_felt());
+        assert!(quirks.matches_with_fallback_sequencer(&header, &ChainId::Mainnet, expected));
+
+        // Only the blocks without a sequencer address are concerned.
+        let header = Header { sequencer_address: Felt::ONE, ..header };
+        assert!(!quirks.matches_with_fallback_sequencer(&header, &ChainId::Mainnet, expected));
+        assert!(!BlockHashQuirks::for_chain(&ChainId::Sepolia).matches_with_fallback_sequencer(
+            &header,
+            &ChainId::Sepolia,
+            expected
+        ));
+    }
+}
@@ -2,6 +2,7 @@ use bitvec::order::Msb0;
 use bitvec::vec::BitVec;
 use bitvec::view::AsBits;
 use bonsai_trie::id::BasicId;
+use mc_db::memory_trie::MemoryTrie;
 use mc_db::MadaraBackend;
 use mc_db::{bonsai_identifier, MadaraStorageError};
 use mp_state_update::DeclaredClassItem;
@@ -19,13 +20,14 @@ pub fn class_trie_root(
 ) -> Result<Felt, MadaraStorageError> {
     let mut class_trie = backend.class_trie();
 
-    let updates: Vec<_> = declared_classes
+    let mut updates: Vec<_> = declared_classes
         .into_par_iter()
         .map(|DeclaredClassItem { class_hash, compiled_class_hash }| {
             let hash = Poseidon::hash(&CONTRACT_CLASS_HASH_VERSION, compiled_class_hash);
             (*class_hash, hash)
         })
         .collect();
+    updates.par_sort_unstable_by_key(|(class_hash, _)| *class_hash);
 
     log::debug!("class_trie inserting");
     for (key, value) in updates {
@@ -44,6 +46,29 @@ pub fn class_trie_root(
     Ok(root_hash)
 }
 
+/// Reverts the class trie from `latest_block_number` to its state at `block_number`, using the trie logs.
+pub fn revert_class_trie(
+    backend: &MadaraBackend,
+    block_number: u64,
+    latest_block_number: u64,
+) -> Result<Felt, MadaraStorageError> {
+    let mut class_trie = backend.class_trie();
+    class_trie.revert_to(BasicId::new(block_number), BasicId::new(latest_block_number))?;
+    Ok(class_trie.root_hash(bonsai_identifier::CLASS)?)
+}
+
+/// Builds the class trie in memory from all the declared Sierra classes. The returned trie is not committed yet.
+pub fn rebuild_class_trie(backend: &MadaraBackend) -> Result<MemoryTrie<Poseidon>, MadaraStorageError> {
+    let declared_classes = backend.get_declared_sierra_classes()?;
+    log::debug!("class_trie rebuilding {} classes", declared_classes.len());
+
+    let mut class_trie = MemoryTrie::new(bonsai_identifier::CLASS);
+    for DeclaredClassItem { class_hash, compiled_class_hash } in declared_classes {
+        class_trie.insert(&class_hash, &Poseidon::hash(&CONTRACT_CLASS_HASH_VERSION, &compiled_class_hash));
+    }
+    Ok(class_trie)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,6 +2,7 @@ use bitvec::order::Msb0;
 use bitvec::vec::BitVec;
 use bitvec::view::AsBits;
 use bonsai_trie::id::BasicId;
+use mc_db::memory_trie::MemoryTrie;
 use mc_db::MadaraBackend;
 use mc_db::{bonsai_identifier, MadaraStorageError};
 use mp_block::{BlockId, BlockTag};
@@ -9,7 +10,7 @@ use mp_state_update::{ContractStorageDiffItem, DeployedContractItem, NonceUpdate
 use rayon::prelude::*;
 use starknet_types_core::felt::Felt;
 use starknet_types_core::hash::{Pedersen, StarkHash};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Default)]
 struct ContractLeaf {
@@ -20,6 +21,10 @@ struct ContractLeaf {
 
 /// Calculates the contract trie root
 ///
+/// The storage writes are inserted contract by contract, in key order, and the storage trie is committed once for all
+/// the contracts. The nonces and class hashes of the updated
+/// contracts are read from the database while the storage trie is committing.
+///
 /// # Arguments
 ///
 /// * `csd`             - Commitment state diff for the current block.
@@ -38,26 +43,11 @@ pub fn contract_trie_root(
 ) -> Result<Felt, MadaraStorageError> {
     let mut contract_leafs: HashMap<Felt, ContractLeaf> = HashMap::new();
 
-    let mut contract_storage_trie = backend.contract_storage_trie();
-
-    log::debug!("contract_storage_trie inserting");
-
-    // First we insert the contract storage changes
-    for ContractStorageDiffItem { address, storage_entries } in storage_diffs {
-        for StorageEntry { key, value } in storage_entries {
-            let bytes = key.to_bytes_be();
-            let bv: BitVec<u8, Msb0> = bytes.as_bits()[5..].to_owned();
-            contract_storage_trie.insert(&address.to_bytes_be(), &bv, value)?;
-        }
-        // insert the contract address in the contract_leafs to put the storage root later
+    // insert the contract addresses in the contract_leafs to put the storage roots later
+    for ContractStorageDiffItem { address, .. } in storage_diffs {
         contract_leafs.insert(*address, Default::default());
     }
 
-    log::debug!("contract_storage_trie commit");
-
-    // Then we commit them
-    contract_storage_trie.commit(BasicId::new(block_number))?;
-
     for NonceUpdate { contract_address, nonce } in nonces {
         contract_leafs.entry(*contract_address).or_default().nonce = Some(*nonce);
     }
@@ -70,21 +60,50 @@ pub fn contract_trie_root(
         contract_leafs.entry(*contract_address).or_default().class_hash = Some(*class_hash);
     }
 
-    let mut contract_trie = backend.contract_trie();
+    let mut contract_storage_trie = backend.contract_storage_trie();
 
-    let leaf_hashes: Vec<_> = contract_leafs
+    let (committed, contract_leafs) = rayon::join(
+        || {
+            log::debug!("contract_storage_trie inserting");
+            for (address, storage_entries) in sorted_storage_writes(storage_diffs) {
+                let identifier = address.to_bytes_be();
+                for (key, value) in storage_entries {
+                    let bytes = key.to_bytes_be();
+                    let bv: BitVec<u8, Msb0> = bytes.as_bits()[5..].to_owned();
+                    contract_storage_trie.insert(&identifier, &bv, &value)?;
+                }
+            }
+
+            log::debug!("contract_storage_trie commit");
+            contract_storage_trie.commit(BasicId::new(block_number))
+        },
+        || {
+            contract_leafs
+                .into_par_iter()
+                .map(|(contract_address, leaf)| {
+                    Ok((contract_address, read_leaf_from_db(backend, &contract_address, leaf)?))
+                })
+                .collect::<Result<Vec<_>, MadaraStorageError>>()
+        },
+    );
+    committed?;
+
+    let mut leaf_hashes: Vec<_> = contract_leafs?
         .into_par_iter()
         .map(|(contract_address, mut leaf)| {
             let storage_root = contract_storage_trie.root_hash(&contract_address.to_bytes_be())?;
             leaf.storage_root = Some(storage_root);
             let leaf_hash = contract_state_leaf_hash(backend, &contract_address, &leaf)?;
-            let bytes = contract_address.to_bytes_be();
-            let bv: BitVec<u8, Msb0> = bytes.as_bits()[5..].to_owned();
-            Ok((bv, leaf_hash))
+            Ok((contract_address, leaf_hash))
         })
         .collect::<Result<_, MadaraStorageError>>()?;
+    leaf_hashes.par_sort_unstable_by_key(|(contract_address, _)| *contract_address);
 
-    for (k, v) in leaf_hashes {
+    let mut contract_trie = backend.contract_trie();
+
+    for (contract_address, v) in leaf_hashes {
+        let bytes = contract_address.to_bytes_be();
+        let k: BitVec<u8, Msb0> = bytes.as_bits()[5..].to_owned();
         contract_trie.insert(bonsai_identifier::CONTRACT, &k, &v)?;
     }
 
@@ -98,6 +117,110 @@ pub fn contract_trie_root(
     Ok(root_hash)
 }
 
+/// Reverts the contract and storage tries from `latest_block_number` to their state at `block_number`, using the
+/// trie logs.
+pub fn revert_contract_trie(
+    backend: &MadaraBackend,
+    block_number: u64,
+    latest_block_number: u64,
+) -> Result<Felt, MadaraStorageError> {
+    let (block_id, latest_block_id) = (BasicId::new(block_number), BasicId::new(latest_block_number));
+
+    log::debug!("contract_storage_trie reverting");
+    backend.contract_storage_trie().revert_to(block_id, latest_block_id)?;
+
+    log::debug!("contract_trie reverting");
+    let mut contract_trie = backend.contract_trie();
+    contract_trie.revert_to(block_id, latest_block_id)?;
+    Ok(contract_trie.root_hash(bonsai_identifier::CONTRACT)?)
+}
+
+/// Rebuilds the storage tries of the given contracts in memory from their latest flat state, and replaces the stored
+/// tries whose root differs. Returns the number of replaced tries.
+pub fn rebuild_contract_storage_tries(
+    backend: &MadaraBackend,
+    contracts: &[Felt],
+) -> Result<usize, MadaraStorageError> {
+    let contract_storage_trie = backend.contract_storage_trie();
+    let mut n_replaced = 0;
+    for contract_address in contracts {
+        let identifier = contract_address.to_bytes_be();
+        let mut trie = MemoryTrie::<Pedersen>::new(&identifier);
+        for (key, value) in backend.get_latest_contract_storage(contract_address)? {
+            // Zero values are not in the trie.
+            if value != Felt::ZERO {
+                trie.insert(&key, &value);
+            }
+        }
+        let storage_root = trie.commit();
+
+        let stored_root = contract_storage_trie.root_hash(&identifier)?;
+        if stored_root != storage_root {
+            log::debug!(
+                "Replacing the storage trie of {contract_address:#x}: root {stored_root:#x}, rebuilt {storage_root:#x}"
+            );
+            backend.replace_contract_storage_trie(contract_address, &trie)?;
+            // Read the replaced trie back without the cached nodes of the previous one.
+            if backend.contract_storage_trie().root_hash(&identifier)? != storage_root {
+                return Err(MadaraStorageError::InconsistentStorage(
+                    format!("Replaced the storage trie of contract {contract_address:#x} with a different root").into(),
+                ));
+            }
+            n_replaced += 1;
+        }
+    }
+    Ok(n_replaced)
+}
+
+/// Builds the contract trie in memory, from the latest class hash and nonce of every contract and its stored storage
+/// root. The returned trie is not committed yet.
+pub fn rebuild_contract_trie(backend: &MadaraBackend) -> Result<MemoryTrie<Pedersen>, MadaraStorageError> {
+    let latest = BlockId::Tag(BlockTag::Latest);
+    let contract_storage_trie = backend.contract_storage_trie();
+    let contracts = backend.get_contract_addresses()?;
+    log::debug!("contract_trie rebuilding {} contracts", contracts.len());
+
+    let mut contract_trie = MemoryTrie::new(bonsai_identifier::CONTRACT);
+    for contract_address in contracts {
+        let leaf = ContractLeaf {
+            class_hash: Some(backend.get_contract_class_hash_at(&latest, &contract_address)?.unwrap_or(Felt::ZERO)),
+            storage_root: Some(contract_storage_trie.root_hash(&contract_address.to_bytes_be())?),
+            nonce: Some(backend.get_contract_nonce_at(&latest, &contract_address)?.unwrap_or(Felt::ZERO)),
+        };
+        contract_trie.insert(&contract_address, &contract_state_leaf_hash(backend, &contract_address, &leaf)?);
+    }
+    Ok(contract_trie)
+}
+
+/// The storage writes of each contract, sorted by contract address and then by key. A state diff should not write a
+/// key twice, the last write wins otherwise.
+fn sorted_storage_writes(storage_diffs: &[ContractStorageDiffItem]) -> BTreeMap<Felt, BTreeMap<Felt, Felt>> {
+    let mut writes: BTreeMap<Felt, BTreeMap<Felt, Felt>> = BTreeMap::new();
+    for ContractStorageDiffItem { address, storage_entries } in storage_diffs {
+        writes
+            .entry(*address)
+            .or_default()
+            .extend(storage_entries.iter().map(|StorageEntry { key, value }| (*key, *value)));
+    }
+    writes
+}
+
+/// Fills the nonce and class hash of the contract from the latest state when the block does not update them.
+fn read_leaf_from_db(
+    backend: &MadaraBackend,
+    contract_address: &Felt,
+    mut leaf: ContractLeaf,
+) -> Result<ContractLeaf, MadaraStorageError> {
+    let latest = BlockId::Tag(BlockTag::Latest);
+    if leaf.nonce.is_none() {
+        leaf.nonce = Some(backend.get_contract_nonce_at(&latest, contract_address)?.unwrap_or(Felt::ZERO));
+    }
+    if leaf.class_hash.is_none() {
+        leaf.class_hash = Some(backend.get_contract_class_hash_at(&latest, contract_address)?.unwrap_or(Felt::ZERO));
+    }
+    Ok(leaf)
+}
+
 /// Computes the contract state leaf hash
 ///
 /// # Arguments
@@ -114,13 +237,17 @@ fn contract_state_leaf_hash(
     contract_address: &Felt,
     contract_leaf: &ContractLeaf,
 ) -> Result<Felt, MadaraStorageError> {
-    let nonce = contract_leaf.nonce.unwrap_or(
-        backend.get_contract_nonce_at(&BlockId::Tag(BlockTag::Latest), contract_address)?.unwrap_or(Felt::ZERO),
-    );
-
-    let class_hash = contract_leaf.class_hash.unwrap_or(
-        backend.get_contract_class_hash_at(&BlockId::Tag(BlockTag::Latest), contract_address)?.unwrap_or(Felt::ZERO), // .ok_or(MadaraStorageError::InconsistentStorage("Class hash not found".into()))?
-    );
+    let nonce = match contract_leaf.nonce {
+        Some(nonce) => nonce,
+        None => backend.get_contract_nonce_at(&BlockId::Tag(BlockTag::Latest), contract_address)?.unwrap_or(Felt::ZERO),
+    };
+
+    let class_hash = match contract_leaf.class_hash {
+        Some(class_hash) => class_hash,
+        None => {
+            backend.get_contract_class_hash_at(&BlockId::Tag(BlockTag::Latest), contract_address)?.unwrap_or(Felt::ZERO)
+        }
+    };
 
     let storage_root = contract_leaf
         .storage_root
@@ -206,4 +333,26 @@ mod contract_trie_root_tests {
             Felt::from_hex_unchecked("0x6bbd8d4b5692148f83c38e19091f64381b5239e2a73f53b59be3ec3efb41143")
         );
     }
+
+    #[test]
+    fn test_sorted_storage_writes() {
+        let entry = |key: u64, value: u64| StorageEntry { key: Felt::from(key), value: Felt::from(value) };
+        let storage_diffs = vec![
+            ContractStorageDiffItem { address: Felt::from(2), storage_entries: vec![entry(3, 1), entry(1, 2)] },
+            ContractStorageDiffItem { address: Felt::from(1), storage_entries: vec![entry(2, 3)] },
+            ContractStorageDiffItem { address: Felt::from(2), storage_entries: vec![entry(3, 4)] },
+        ];
+
+        let writes: Vec<(Felt, Vec<(Felt, Felt)>)> = sorted_storage_writes(&storage_diffs)
+            .into_iter()
+            .map(|(address, entries)| (address, entries.into_iter().collect()))
+            .collect();
+        assert_eq!(
+            writes,
+            vec![
+                (Felt::from(1), vec![(Felt::from(2), Felt::from(3))]),
+                (Felt::from(2), vec![(Felt::from(1), Felt::from(2)), (Felt::from(3), Felt::from(4))]),
+            ]
+        );
+    }
 }
@@ -0,0 +1,183 @@
+//! Avail adapter.
+//!
+//! Data is submitted through the HTTP API of an avail-light client running in app client mode, which signs the
+//! data submission with its own key. Inclusion proofs are not served by the light client, and are queried from
+//! the JSON-RPC of an Avail node instead.
+
+use crate::{felts_to_bytes, DaClient, DaInclusion};
+use anyhow::{bail, ensure, Context};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::json;
+use starknet_types_core::felt::Felt;
+use url::Url;
+
+#[derive(Deserialize)]
+struct Status {
+    app_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct SubmitResponse {
+    block_number: u64,
+    block_hash: String,
+    index: u32,
+}
+
+pub struct AvailClient {
+    client: reqwest::Client,
+    url: Url,
+    rpc_url: Url,
+    app_id: u32,
+}
+
+impl AvailClient {
+    pub fn new(url: Url, rpc_url: Url, app_id: u32) -> Self {
+        Self { client: reqwest::Client::new(), url, rpc_url, app_id }
+    }
+
+    /// The light client submits data under the app id it was started with, which has to be the one of the chain.
+    async fn check_app_id(&self) -> anyhow::Result<()> {
+        let url = self.url.join("v2/status")?;
+        let status: Status = self.client.get(url).send().await?.error_for_status()?.json().await?;
+        ensure!(
+            status.app_id == Some(self.app_id),
+            "The Avail light client is running with app id {:?}, but the chain config expects app id {}",
+            status.app_id,
+            self.app_id
+        );
+        Ok(())
+    }
+}
+
+fn parse_block_hash(hash: &str) -> anyhow::Result<[u8; 32]> {
+    let hash = hash.strip_prefix("0x").unwrap_or(hash);
+    if !hash.is_ascii() || hash.len() != 64 {
+        bail!("Invalid Avail block hash {hash:?}");
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte =
+            u8::from_str_radix(&hash[i * 2..i * 2 + 2], 16).with_context(|| format!("Invalid block hash {hash:?}"))?;
+    }
+    Ok(bytes)
+}
+
+fn block_hash_to_hex(hash: &[u8; 32]) -> String {
+    format!("0x{}", hash.iter().map(|b| format!("{b:02x}")).collect::<String>())
+}
+
+#[async_trait::async_trait]
+impl DaClient for AvailClient {
+    fn name(&self) -> &'static str {
+        "Avail"
+    }
+
+    async fn submit(&self, data: &[Felt]) -> anyhow::Result<DaInclusion> {
+        self.check_app_id().await.context("Checking the Avail light client status")?;
+
+        let url = self.url.join("v2/submit")?;
+        let response: SubmitResponse = self
+            .client
+            .post(url)
+            .json(&json!({ "data": BASE64.encode(felts_to_bytes(data)) }))
+            .send()
+            .await
+            .context("Submitting data to Avail")?
+            .error_for_status()
+            .context("Submitting data to Avail")?
+            .json()
+            .await
+            .context("Parsing the Avail submission response")?;
+
+        Ok(DaInclusion::Avail {
+            block_number: response.block_number,
+            block_hash: parse_block_hash(&response.block_hash)?,
+            tx_index: response.index,
+        })
+    }
+
+    async fn get_inclusion_proof(&self, inclusion: &DaInclusion) -> anyhow::Result<serde_json::Value> {
+        let DaInclusion::Avail { block_hash, tx_index, .. } = inclusion else {
+            bail!("Not an Avail inclusion: {inclusion:?}");
+        };
+        let response: serde_json::Value = self
+            .client
+            .post(self.rpc_url.clone())
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "kate_queryDataProof",
+                "params": [tx_index, block_hash_to_hex(block_hash)],
+            }))
+            .send()
+            .await
+            .context("Calling kate_queryDataProof")?
+            .error_for_status()
+            .context("Calling kate_queryDataProof")?
+            .json()
+            .await
+            .context("Parsing the kate_queryDataProof response")?;
+
+        if let Some(error) = response.get("error") {
+            bail!("kate_queryDataProof failed: {error}");
+        }
+        response.get("result").cloned().context("kate_queryDataProof returned no result")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+
+    fn client(server: &MockServer, app_id: u32) -> AvailClient {
+        let url: Url = server.base_url().parse().unwrap();
+        AvailClient::new(url.clone(), url, app_id)
+    }
+
+    #[tokio::test]
+    async fn submit_works() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method("GET").path("/v2/status");
+            then.status(200).json_body(json!({ "modes": ["light", "app"], "app_id": 42 }));
+        });
+        server.mock(|when, then| {
+            when.method("POST").path("/v2/submit");
+            then.status(200).json_body(json!({
+                "block_number": 100,
+                "block_hash": format!("0x{}", "ab".repeat(32)),
+                "hash": format!("0x{}", "cd".repeat(32)),
+                "index": 3
+            }));
+        });
+
+        let inclusion = client(&server, 42).submit(&[Felt::ONE]).await.unwrap();
+
+        assert_eq!(inclusion, DaInclusion::Avail { block_number: 100, block_hash: [0xab; 32], tx_index: 3 });
+    }
+
+    #[tokio::test]
+    async fn submit_with_wrong_app_id_fails() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method("GET").path("/v2/status");
+            then.status(200).json_body(json!({ "modes": ["light", "app"], "app_id": 1 }));
+        });
+        let submit = server.mock(|when, then| {
+            when.method("POST").path("/v2/submit");
+            then.status(200);
+        });
+
+        assert!(client(&server, 42).submit(&[Felt::ONE]).await.is_err());
+        submit.assert_hits(0);
+    }
+
+    #[test]
+    fn block_hash_roundtrip() {
+        let hash = [0x12; 32];
+        assert_eq!(parse_block_hash(&block_hash_to_hex(&hash)).unwrap(), hash);
+    }
+}
@@ -0,0 +1,201 @@
+//! Celestia adapter, talking to the JSON-RPC of a celestia-node (v0.15 or later).
+//!
+//! Data is submitted as a single blob in the chain namespace. `blob.Submit` returns once the blob is included, and
+//! the share commitment identifying it is then read back with `blob.GetAll`.
+
+use crate::{felts_to_bytes, DaClient, DaInclusion};
+use anyhow::{bail, Context};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use starknet_types_core::felt::Felt;
+use url::Url;
+
+/// Size of a Celestia namespace: one version byte followed by a 28 bytes id.
+pub const NAMESPACE_SIZE: usize = 29;
+/// Version 0 namespace ids are 18 zero bytes followed by up to 10 user-specified bytes.
+pub const NAMESPACE_USER_ID_SIZE: usize = 10;
+
+/// Builds a version 0 namespace from a hex-encoded id of at most 10 bytes.
+pub fn namespace_from_hex(id: &str) -> anyhow::Result<[u8; NAMESPACE_SIZE]> {
+    let id = id.strip_prefix("0x").unwrap_or(id);
+    if !id.is_ascii() || id.is_empty() || id.len() % 2 != 0 || id.len() > NAMESPACE_USER_ID_SIZE * 2 {
+        bail!("Invalid Celestia namespace {id:?}: expected an hex string of 1 to {NAMESPACE_USER_ID_SIZE} bytes");
+    }
+    let mut namespace = [0u8; NAMESPACE_SIZE];
+    let offset = NAMESPACE_SIZE - id.len() / 2;
+    for (i, byte) in (0..id.len()).step_by(2).enumerate() {
+        namespace[offset + i] = u8::from_str_radix(&id[byte..byte + 2], 16)
+            .with_context(|| format!("Invalid Celestia namespace {id:?}"))?;
+    }
+    Ok(namespace)
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize)]
+struct Blob {
+    data: String,
+    commitment: String,
+}
+
+pub struct CelestiaClient {
+    client: reqwest::Client,
+    url: Url,
+    auth_token: Option<String>,
+    namespace: [u8; NAMESPACE_SIZE],
+}
+
+impl CelestiaClient {
+    pub fn new(url: Url, auth_token: Option<String>, namespace: &str) -> anyhow::Result<Self> {
+        Ok(Self { client: reqwest::Client::new(), url, auth_token, namespace: namespace_from_hex(namespace)? })
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: serde_json::Value) -> anyhow::Result<T> {
+        let mut request = self.client.post(self.url.clone()).json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        }));
+        if let Some(token) = &self.auth_token {
+            request = request.bearer_auth(token);
+        }
+        let response: JsonRpcResponse<T> = request
+            .send()
+            .await
+            .with_context(|| format!("Calling {method}"))?
+            .error_for_status()
+            .with_context(|| format!("Calling {method}"))?
+            .json()
+            .await
+            .with_context(|| format!("Parsing the {method} response"))?;
+
+        match response {
+            JsonRpcResponse { error: Some(JsonRpcError { code, message }), .. } => {
+                bail!("{method} failed with error {code}: {message}")
+            }
+            JsonRpcResponse { result: Some(result), .. } => Ok(result),
+            _ => bail!("{method} returned neither a result nor an error"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DaClient for CelestiaClient {
+    fn name(&self) -> &'static str {
+        "Celestia"
+    }
+
+    async fn submit(&self, data: &[Felt]) -> anyhow::Result<DaInclusion> {
+        let namespace = BASE64.encode(self.namespace);
+        let data = BASE64.encode(felts_to_bytes(data));
+
+        // The node computes the commitment of submitted blobs itself.
+        let blob = json!({ "namespace": namespace, "data": data, "share_version": 0 });
+        let height: u64 = self.call("blob.Submit", json!([[blob], {}])).await?;
+
+        let blobs: Vec<Blob> = self.call("blob.GetAll", json!([height, [namespace]])).await?;
+        let blob = blobs
+            .into_iter()
+            .find(|blob| blob.data == data)
+            .with_context(|| format!("Submitted blob not found in Celestia block {height}"))?;
+        let commitment = BASE64.decode(&blob.commitment).context("Decoding the blob commitment")?;
+
+        Ok(DaInclusion::Celestia { height, commitment })
+    }
+
+    async fn get_inclusion_proof(&self, inclusion: &DaInclusion) -> anyhow::Result<serde_json::Value> {
+        let DaInclusion::Celestia { height, commitment } = inclusion else {
+            bail!("Not a Celestia inclusion: {inclusion:?}");
+        };
+        self.call("blob.GetProof", json!([height, BASE64.encode(self.namespace), BASE64.encode(commitment)])).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("6d6164617261", b"madara".as_slice())]
+    #[case("0x01", &[1])]
+    #[case("00112233445566778899", &[0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99])]
+    fn namespace_is_left_padded(#[case] id: &str, #[case] expected_suffix: &[u8]) {
+        let namespace = namespace_from_hex(id).unwrap();
+        let (prefix, suffix) = namespace.split_at(NAMESPACE_SIZE - expected_suffix.len());
+        assert!(prefix.iter().all(|b| *b == 0), "version and id prefix should be zero");
+        assert_eq!(suffix, expected_suffix);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("abc")]
+    #[case("0011223344556677889900")]
+    #[case("zz")]
+    fn invalid_namespace_fails(#[case] id: &str) {
+        assert!(namespace_from_hex(id).is_err());
+    }
+
+    #[tokio::test]
+    async fn submit_returns_the_blob_commitment() {
+        let server = MockServer::start();
+        let data = [Felt::from(42)];
+        let encoded = BASE64.encode(felts_to_bytes(&data));
+
+        let submit = server.mock(|when, then| {
+            when.method("POST").path("/").header("authorization", "Bearer token").body_contains("blob.Submit");
+            then.status(200).json_body(json!({ "jsonrpc": "2.0", "id": 1, "result": 1234 }));
+        });
+        server.mock(|when, then| {
+            when.method("POST").path("/").body_contains("blob.GetAll");
+            then.status(200).json_body(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": [
+                    { "namespace": "", "data": "AAAA", "share_version": 0, "commitment": BASE64.encode([1u8; 32]) },
+                    { "namespace": "", "data": encoded, "share_version": 0, "commitment": BASE64.encode([2u8; 32]) },
+                ]
+            }));
+        });
+
+        let url = server.base_url().parse().unwrap();
+        let client = CelestiaClient::new(url, Some("token".into()), "6d6164617261").unwrap();
+        let inclusion = client.submit(&data).await.unwrap();
+
+        submit.assert();
+        assert_eq!(inclusion, DaInclusion::Celestia { height: 1234, commitment: vec![2u8; 32] });
+    }
+
+    #[tokio::test]
+    async fn rpc_errors_are_reported() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method("POST").path("/");
+            then.status(200).json_body(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "error": { "code": 1, "message": "not enough funds" }
+            }));
+        });
+
+        let url = server.base_url().parse().unwrap();
+        let client = CelestiaClient::new(url, None, "6d6164617261").unwrap();
+        let err = client.submit(&[Felt::ONE]).await.unwrap_err();
+        assert!(format!("{err:#}").contains("not enough funds"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Data availability layer adapters.
+//!
+//! By default, the state diffs of an appchain are published on its settlement layer, along with the state
+//! updates. Appchains can instead publish them on a dedicated DA layer, chosen with the `da_layer` chain config
+//! field independently of the settlement layer.
+
+use anyhow::Context;
+use mp_chain_config::DaLayer;
+use starknet_types_core::felt::Felt;
+use std::sync::Arc;
+use url::Url;
+
+pub mod avail;
+pub mod celestia;
+
+/// Where a submission was included on the DA layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DaInclusion {
+    /// A Celestia blob, identified by its share commitment.
+    Celestia { height: u64, commitment: Vec<u8> },
+    /// An Avail data submission, identified by its position in the block.
+    Avail { block_number: u64, block_hash: [u8; 32], tx_index: u32 },
+}
+
+impl DaInclusion {
+    /// Height of the DA layer block the submission was included in.
+    pub fn height(&self) -> u64 {
+        match self {
+            Self::Celestia { height, .. } => *height,
+            Self::Avail { block_number, .. } => *block_number,
+        }
+    }
+}
+
+/// A client to a data availability layer.
+#[async_trait::async_trait]
+pub trait DaClient: Send + Sync {
+    /// Human readable name of the DA layer, for displaying to the console.
+    fn name(&self) -> &'static str;
+
+    /// Publishes the data, and waits for it to be included in a block of the DA layer.
+    async fn submit(&self, data: &[Felt]) -> anyhow::Result<DaInclusion>;
+
+    /// Retrieves the proof that a submission was included, as returned by the DA layer.
+    async fn get_inclusion_proof(&self, inclusion: &DaInclusion) -> anyhow::Result<serde_json::Value>;
+}
+
+/// Endpoints of the DA layers, which are node configuration rather than chain configuration.
+#[derive(Debug, Clone, Default)]
+pub struct DaClientConfig {
+    pub celestia_url: Option<Url>,
+    pub celestia_auth_token: Option<String>,
+    pub avail_url: Option<Url>,
+    pub avail_rpc_url: Option<Url>,
+}
+
+/// Creates the client to the DA layer selected in the chain config. Returns `None` when the data is published on
+/// the settlement layer.
+pub fn create_da_client(da_layer: &DaLayer, config: &DaClientConfig) -> anyhow::Result<Option<Arc<dyn DaClient>>> {
+    let client: Arc<dyn DaClient> = match da_layer {
+        DaLayer::Settlement => return Ok(None),
+        DaLayer::Celestia { namespace } => {
+            let url = config.celestia_url.clone().context("Publishing to Celestia requires `--da-celestia-url`")?;
+            Arc::new(celestia::CelestiaClient::new(url, config.celestia_auth_token.clone(), namespace)?)
+        }
+        DaLayer::Avail { app_id } => {
+            let url = config.avail_url.clone().context("Publishing to Avail requires `--da-avail-url`")?;
+            let rpc_url = config.avail_rpc_url.clone().context("Publishing to Avail requires `--da-avail-rpc-url`")?;
+            Arc::new(avail::AvailClient::new(url, rpc_url, *app_id))
+        }
+    };
+    Ok(Some(client))
+}
+
+/// DA layers take raw bytes: felts are serialized as 32 bytes big-endian each.
+pub(crate) fn felts_to_bytes(data: &[Felt]) -> Vec<u8> {
+    data.iter().flat_map(|felt| felt.to_bytes_be()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settlement_layer_has_no_da_client() {
+        assert!(create_da_client(&DaLayer::Settlement, &DaClientConfig::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn missing_endpoint_fails() {
+        let layer = DaLayer::Celestia { namespace: "6d6164617261".into() };
+        assert!(create_da_client(&layer, &DaClientConfig::default()).is_err());
+    }
+
+    #[test]
+    fn felts_to_bytes_is_big_endian() {
+        let bytes = felts_to_bytes(&[Felt::ONE, Felt::TWO]);
+        assert_eq!(bytes.len(), 64);
+        assert_eq!(bytes[31], 1);
+        assert_eq!(bytes[63], 2);
+    }
+}
@@ -15,17 +15,19 @@ use starknet_types_core::felt::Felt;
 type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-struct ChainInfo {
-    chain_id: ChainId,
-    chain_name: String,
+pub(crate) struct ChainInfo {
+    pub chain_id: ChainId,
+    pub chain_name: String,
 }
 
-const ROW_CHAIN_INFO: &[u8] = b"chain_info";
+pub(crate) const ROW_CHAIN_INFO: &[u8] = b"chain_info";
 const ROW_PENDING_INFO: &[u8] = b"pending_info";
 const ROW_PENDING_STATE_UPDATE: &[u8] = b"pending_state_update";
 const ROW_PENDING_INNER: &[u8] = b"pending";
-const ROW_SYNC_TIP: &[u8] = b"sync_tip";
-const ROW_L1_LAST_CONFIRMED_BLOCK: &[u8] = b"l1_last";
+pub(crate) const ROW_SYNC_TIP: &[u8] = b"sync_tip";
+pub(crate) const ROW_L1_LAST_CONFIRMED_BLOCK: &[u8] = b"l1_last";
+const ROW_SEQUENCER_PUBLIC_KEY: &[u8] = b"sequencer_public_key";
+pub(crate) const ROW_NO_GLOBAL_TRIES_SINCE: &[u8] = b"no_global_tries_since";
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct TxIndex(pub u64);
@@ -63,7 +65,8 @@ impl MadaraBackend {
 
     // DB read operations
 
-    fn tx_hash_to_block_n(&self, tx_hash: &Felt) -> Result<Option<u64>> {
+    /// Returns the number of the closed block containing the transaction.
+    pub fn tx_hash_to_block_n(&self, tx_hash: &Felt) -> Result<Option<u64>> {
         let col = self.db.get_column(Column::TxHashToBlockN);
         let res = self.db.get_cf(&col, bincode::serialize(tx_hash)?)?;
         let Some(res) = res else { return Ok(None) };
@@ -128,7 +131,7 @@ impl MadaraBackend {
                         // will error somewhere else anyway.
                         sequencer_address: **self.chain_config().sequencer_address,
                         block_timestamp: 0, // Junk timestamp: unix epoch
-                        protocol_version: self.chain_config.latest_protocol_version,
+                        protocol_version: self.chain_config.protocol_version_at(0),
                         l1_gas_price: GasPrices {
                             eth_l1_gas_price: 1,
                             strk_l1_gas_price: 1,
@@ -225,8 +228,64 @@ impl MadaraBackend {
         self.write_last_confirmed_block(0)
     }
 
+    /// Signature of the block hash by the sequencer, `None` when the block was not signed.
+    pub fn get_block_signature(&self, block_n: u64) -> Result<Option<Vec<Felt>>> {
+        let col = self.db.get_column(Column::BlockNToSignature);
+        let Some(res) = self.db.get_pinned_cf(&col, bincode::serialize(&block_n)?)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    /// Public key the sequencer signs the blocks with, `None` when block signing is disabled.
+    pub fn get_sequencer_public_key(&self) -> Result<Option<Felt>> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_pinned_cf(&col, ROW_SEQUENCER_PUBLIC_KEY)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    /// Sets the public key the sequencer signs the blocks with, or clears it when block signing is disabled.
+    pub fn write_sequencer_public_key(&self, public_key: Option<&Felt>) -> Result<()> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        match public_key {
+            Some(public_key) => self.db.put_cf(&col, ROW_SEQUENCER_PUBLIC_KEY, bincode::serialize(public_key)?)?,
+            None => self.db.delete_cf(&col, ROW_SEQUENCER_PUBLIC_KEY)?,
+        }
+        Ok(())
+    }
+
+    /// Stops or keeps maintaining the global tries, as the node is configured. Once stopped, the global tries fall
+    /// behind the flat state for good: this fails when they were stopped and `disabled` is not set.
+    pub fn set_global_tries_disabled(&self, disabled: bool) -> anyhow::Result<()> {
+        match (disabled, self.get_global_tries_disabled_since()?) {
+            (true, Some(_)) | (false, None) => {}
+            (true, None) => {
+                let next_block_n = self.get_latest_block_n()?.map_or(0, |block_n| block_n + 1);
+                let col = self.db.get_column(Column::BlockStorageMeta);
+                self.db
+                    .put_cf(&col, ROW_NO_GLOBAL_TRIES_SINCE, bincode::serialize(&next_block_n)?)
+                    .context("Writing the global tries mode to db")?;
+            }
+            (false, Some(block_n)) => anyhow::bail!(
+                "The global tries of the database were not updated since block #{block_n}, as the node was started \
+                 with `--no-global-tries`. Keep using this option, or resync the node from an empty database."
+            ),
+        }
+        Ok(())
+    }
+
+    /// First block not applied to the global tries, `None` when they are maintained.
+    pub fn get_global_tries_disabled_since(&self) -> Result<Option<u64>> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_pinned_cf(&col, ROW_NO_GLOBAL_TRIES_SINCE)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
     /// Also clears pending block
-    pub(crate) fn block_db_store_block(&self, block: &MadaraBlock, state_diff: &StateDiff) -> Result<()> {
+    pub(crate) fn block_db_store_block(
+        &self,
+        block: &MadaraBlock,
+        state_diff: &StateDiff,
+        signature: Option<&[Felt]>,
+    ) -> Result<()> {
         let mut tx = WriteBatchWithTransaction::default();
 
         let tx_hash_to_block_n = self.db.get_column(Column::TxHashToBlockN);
@@ -247,6 +306,10 @@ impl MadaraBackend {
         tx.put_cf(&block_n_to_block, &block_n_encoded, bincode::serialize(&block.info)?);
         tx.put_cf(&block_n_to_block_inner, &block_n_encoded, bincode::serialize(&block.inner)?);
         tx.put_cf(&block_n_to_state_diff, &block_n_encoded, bincode::serialize(state_diff)?);
+        if let Some(signature) = signature {
+            let block_n_to_signature = self.db.get_column(Column::BlockNToSignature);
+            tx.put_cf(&block_n_to_signature, &block_n_encoded, bincode::serialize(signature)?);
+        }
         tx.put_cf(&meta, ROW_SYNC_TIP, block_n_encoded);
 
         // clear pending
@@ -260,6 +323,46 @@ impl MadaraBackend {
         Ok(())
     }
 
+    /// Removes blocks `block_n + 1..=latest_block_n`, `block_n` becomes the latest block. Also clears pending block.
+    pub(crate) fn block_db_revert(&self, block_n: u64, latest_block_n: u64) -> Result<()> {
+        let mut tx = WriteBatchWithTransaction::default();
+
+        let tx_hash_to_block_n = self.db.get_column(Column::TxHashToBlockN);
+        let block_hash_to_block_n = self.db.get_column(Column::BlockHashToBlockN);
+        let block_n_to_block = self.db.get_column(Column::BlockNToBlockInfo);
+        let block_n_to_block_inner = self.db.get_column(Column::BlockNToBlockInner);
+        let block_n_to_state_diff = self.db.get_column(Column::BlockNToStateDiff);
+        let block_n_to_signature = self.db.get_column(Column::BlockNToSignature);
+        let meta = self.db.get_column(Column::BlockStorageMeta);
+
+        for reverted_block_n in block_n + 1..=latest_block_n {
+            let info = self.get_block_info_from_block_n(reverted_block_n)?.ok_or_else(|| {
+                MadaraStorageError::InconsistentStorage(format!("Block #{reverted_block_n} not found").into())
+            })?;
+            let block_n_encoded = bincode::serialize(&reverted_block_n)?;
+
+            for hash in &info.tx_hashes {
+                tx.delete_cf(&tx_hash_to_block_n, bincode::serialize(hash)?);
+            }
+            tx.delete_cf(&block_hash_to_block_n, bincode::serialize(&info.block_hash)?);
+            tx.delete_cf(&block_n_to_block, &block_n_encoded);
+            tx.delete_cf(&block_n_to_block_inner, &block_n_encoded);
+            tx.delete_cf(&block_n_to_state_diff, &block_n_encoded);
+            tx.delete_cf(&block_n_to_signature, &block_n_encoded);
+        }
+        tx.put_cf(&meta, ROW_SYNC_TIP, bincode::serialize(&block_n)?);
+
+        // clear pending
+        tx.delete_cf(&meta, ROW_PENDING_INFO);
+        tx.delete_cf(&meta, ROW_PENDING_INNER);
+        tx.delete_cf(&meta, ROW_PENDING_STATE_UPDATE);
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(tx, &writeopts)?;
+        Ok(())
+    }
+
     // Convenience functions
 
     pub(crate) fn id_to_storage_type(&self, id: &BlockId) -> Result<Option<DbBlockId>> {
@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
 use bonsai_trie::id::BasicId;
 use bonsai_trie::{BonsaiDatabase, BonsaiPersistentDatabase, ByteVec, DatabaseKey};
 use rocksdb::{Direction, IteratorMode, WriteOptions};
@@ -13,6 +16,16 @@ pub(crate) struct DatabaseKeyMapping {
 }
 
 impl DatabaseKeyMapping {
+    pub(crate) const CONTRACTS: Self =
+        Self { flat: Column::BonsaiContractsFlat, trie: Column::BonsaiContractsTrie, log: Column::BonsaiContractsLog };
+    pub(crate) const CONTRACTS_STORAGE: Self = Self {
+        flat: Column::BonsaiContractsStorageFlat,
+        trie: Column::BonsaiContractsStorageTrie,
+        log: Column::BonsaiContractsStorageLog,
+    };
+    pub(crate) const CLASSES: Self =
+        Self { flat: Column::BonsaiClassesFlat, trie: Column::BonsaiClassesTrie, log: Column::BonsaiClassesLog };
+
     pub(crate) fn map(&self, key: &DatabaseKey) -> Column {
         match key {
             DatabaseKey::Trie(_) => self.trie,
@@ -282,3 +295,97 @@ where
         Ok(())
     }
 }
+
+/// Bonsai database held in memory. Its clones share the same content, which can be read once a trie is built, see
+/// [`crate::memory_trie::MemoryTrie`].
+#[derive(Clone, Default)]
+pub(crate) struct MemoryBonsaiDb(Arc<Mutex<MemoryColumns>>);
+
+#[derive(Default)]
+pub(crate) struct MemoryColumns {
+    pub(crate) trie: BTreeMap<Vec<u8>, Vec<u8>>,
+    pub(crate) flat: BTreeMap<Vec<u8>, Vec<u8>>,
+    log: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MemoryColumns {
+    fn column(&mut self, key: &DatabaseKey) -> &mut BTreeMap<Vec<u8>, Vec<u8>> {
+        match key {
+            DatabaseKey::Trie(_) => &mut self.trie,
+            DatabaseKey::Flat(_) => &mut self.flat,
+            DatabaseKey::TrieLog(_) => &mut self.log,
+        }
+    }
+}
+
+impl MemoryBonsaiDb {
+    pub(crate) fn inner(&self) -> MutexGuard<'_, MemoryColumns> {
+        self.0.lock().expect("Poisoned lock")
+    }
+}
+
+impl BonsaiDatabase for MemoryBonsaiDb {
+    type Batch = ();
+    type DatabaseError = DbError;
+
+    fn create_batch(&self) -> Self::Batch {}
+
+    fn get(&self, key: &DatabaseKey) -> Result<Option<ByteVec>, Self::DatabaseError> {
+        Ok(self.inner().column(key).get(key.as_slice()).map(|value| value.clone().into()))
+    }
+
+    fn get_by_prefix(&self, prefix: &DatabaseKey) -> Result<Vec<(ByteVec, ByteVec)>, Self::DatabaseError> {
+        Ok(self
+            .inner()
+            .column(prefix)
+            .range(prefix.as_slice().to_vec()..)
+            .take_while(|(key, _)| key.starts_with(prefix.as_slice()))
+            .map(|(key, value)| (key.clone().into(), value.clone().into()))
+            .collect())
+    }
+
+    fn contains(&self, key: &DatabaseKey) -> Result<bool, Self::DatabaseError> {
+        Ok(self.inner().column(key).contains_key(key.as_slice()))
+    }
+
+    fn insert(
+        &mut self,
+        key: &DatabaseKey,
+        value: &[u8],
+        _batch: Option<&mut Self::Batch>,
+    ) -> Result<Option<ByteVec>, Self::DatabaseError> {
+        Ok(self.inner().column(key).insert(key.as_slice().to_vec(), value.to_vec()).map(Into::into))
+    }
+
+    fn remove(
+        &mut self,
+        key: &DatabaseKey,
+        _batch: Option<&mut Self::Batch>,
+    ) -> Result<Option<ByteVec>, Self::DatabaseError> {
+        Ok(self.inner().column(key).remove(key.as_slice()).map(Into::into))
+    }
+
+    fn remove_by_prefix(&mut self, prefix: &DatabaseKey) -> Result<(), Self::DatabaseError> {
+        self.inner().column(prefix).retain(|key, _| !key.starts_with(prefix.as_slice()));
+        Ok(())
+    }
+
+    fn write_batch(&mut self, _batch: Self::Batch) -> Result<(), Self::DatabaseError> {
+        Ok(())
+    }
+}
+
+impl BonsaiPersistentDatabase<BasicId> for MemoryBonsaiDb {
+    type Transaction = Self;
+    type DatabaseError = DbError;
+
+    fn snapshot(&mut self, _id: BasicId) {}
+
+    fn transaction(&self, _id: BasicId) -> Option<Self::Transaction> {
+        None
+    }
+
+    fn merge(&mut self, _transaction: Self::Transaction) -> Result<(), Self::DatabaseError> {
+        Ok(())
+    }
+}
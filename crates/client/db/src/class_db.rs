@@ -1,6 +1,7 @@
 use mp_class::{ClassInfo, CompiledSierra, ConvertedClass};
+use mp_state_update::{DeclaredClassItem, StateDiff};
 use rayon::{iter::ParallelIterator, slice::ParallelSlice};
-use rocksdb::WriteOptions;
+use rocksdb::{IteratorMode, WriteOptions};
 use starknet_types_core::felt::Felt;
 
 use crate::{
@@ -11,9 +12,9 @@ use crate::{
 const LAST_KEY: &[u8] = &[0xFF; 64];
 
 #[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
-struct ClassInfoWithBlockNumber {
-    class_info: ClassInfo,
-    block_id: DbBlockId,
+pub(crate) struct ClassInfoWithBlockNumber {
+    pub(crate) class_info: ClassInfo,
+    pub(crate) block_id: DbBlockId,
 }
 
 impl MadaraBackend {
@@ -60,7 +61,7 @@ impl MadaraBackend {
             Column::ClassInfo,
         )?
         else {
-            return Ok(None);
+            return self.forked_class_info(&requested_id, class_hash);
         };
 
         log::debug!("class info got {:?}", info.block_id);
@@ -84,6 +85,23 @@ impl MadaraBackend {
         Ok(self.db.get_pinned_cf(&col, &key_encoded)?.is_some())
     }
 
+    /// Class hash and compiled class hash of every Sierra class declared in a confirmed block. Legacy classes are not
+    /// returned, as they are not committed to the class trie.
+    pub fn get_declared_sierra_classes(&self) -> Result<Vec<DeclaredClassItem>, MadaraStorageError> {
+        let mut classes = Vec::new();
+        for res in self.db.iterator_cf(&self.db.get_column(Column::ClassInfo), IteratorMode::Start) {
+            let (k, v) = res?;
+            let ClassInfoWithBlockNumber { class_info, block_id } = bincode::deserialize(&v)?;
+            if let (ClassInfo::Sierra(class_info), DbBlockId::Number(_)) = (class_info, block_id) {
+                classes.push(DeclaredClassItem {
+                    class_hash: bincode::deserialize(&k)?,
+                    compiled_class_hash: class_info.compiled_class_hash,
+                });
+            }
+        }
+        Ok(classes)
+    }
+
     pub fn get_sierra_compiled(
         &self,
         id: &impl DbBlockIdResolvable,
@@ -100,7 +118,7 @@ impl MadaraBackend {
             Column::ClassCompiled,
         )?
         else {
-            return Ok(None);
+            return self.forked_sierra_compiled(&requested_id, class_hash);
         };
 
         Ok(Some(compiled))
@@ -190,6 +208,45 @@ impl MadaraBackend {
         )
     }
 
+    /// Removes the classes declared after `block_n` by the reverted blocks, given with their state diffs.
+    pub(crate) fn class_db_revert(
+        &self,
+        block_n: u64,
+        reverted_blocks: &[(u64, StateDiff)],
+    ) -> Result<(), MadaraStorageError> {
+        let mut batch = WriteBatchWithTransaction::default();
+
+        let col_info = self.db.get_column(Column::ClassInfo);
+        let col_compiled = self.db.get_column(Column::ClassCompiled);
+
+        for (_, state_diff) in reverted_blocks {
+            let sierra_classes = state_diff
+                .declared_classes
+                .iter()
+                .map(|DeclaredClassItem { class_hash, compiled_class_hash }| (class_hash, Some(compiled_class_hash)));
+            let legacy_classes = state_diff.deprecated_declared_classes.iter().map(|class_hash| (class_hash, None));
+
+            for (class_hash, compiled_class_hash) in sierra_classes.chain(legacy_classes) {
+                let key_bin = bincode::serialize(class_hash)?;
+                let Some(info) = self.db.get_pinned_cf(&col_info, &key_bin)? else { continue };
+                let info: ClassInfoWithBlockNumber = bincode::deserialize(&info)?;
+                // Legacy classes declared multiple times are stored with the block of their first declaration.
+                if matches!(info.block_id, DbBlockId::Number(declared_at) if declared_at <= block_n) {
+                    continue;
+                }
+                batch.delete_cf(&col_info, &key_bin);
+                if let Some(compiled_class_hash) = compiled_class_hash {
+                    batch.delete_cf(&col_compiled, bincode::serialize(compiled_class_hash)?);
+                }
+            }
+        }
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(batch, &writeopts)?;
+        Ok(())
+    }
+
     pub(crate) fn class_db_clear_pending(&self) -> Result<(), MadaraStorageError> {
         let mut writeopts = WriteOptions::new();
         writeopts.disable_wal(true);
@@ -1,7 +1,11 @@
 #![doc = include_str!("../docs/flat_storage.md")]
 
+use std::collections::BTreeSet;
 use std::sync::Arc;
 
+use mp_state_update::{
+    ContractStorageDiffItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StateDiff, StorageEntry,
+};
 use rayon::{iter::ParallelIterator, slice::ParallelSlice};
 use rocksdb::{BoundColumnFamily, IteratorMode, ReadOptions, WriteOptions};
 use serde::Serialize;
@@ -96,13 +100,16 @@ impl MadaraBackend {
         id: &impl DbBlockIdResolvable,
         contract_addr: &Felt,
     ) -> Result<Option<Felt>, MadaraStorageError> {
-        self.resolve_history_kv(
+        if let Some(class_hash) = self.resolve_history_kv(
             id,
             Column::PendingContractToClassHashes,
             Column::ContractToClassHashes,
             contract_addr,
             |k| k.to_bytes_be(),
-        )
+        )? {
+            return Ok(Some(class_hash));
+        }
+        self.forked_class_hash_at(id, contract_addr)
     }
 
     pub fn get_contract_nonce_at(
@@ -110,9 +117,32 @@ impl MadaraBackend {
         id: &impl DbBlockIdResolvable,
         contract_addr: &Felt,
     ) -> Result<Option<Felt>, MadaraStorageError> {
-        self.resolve_history_kv(id, Column::PendingContractToNonces, Column::ContractToNonces, contract_addr, |k| {
-            k.to_bytes_be()
-        })
+        if let Some(nonce) = self.resolve_history_kv(
+            id,
+            Column::PendingContractToNonces,
+            Column::ContractToNonces,
+            contract_addr,
+            |k| k.to_bytes_be(),
+        )? {
+            return Ok(Some(nonce));
+        }
+        self.forked_nonce_at(id, contract_addr)
+    }
+
+    /// Adds the nonce at `id` of the contracts touched by the state diff without a nonce update. The DA encoding of a
+    /// state diff includes the nonce of every touched contract, see [`StateDiff::encode_da`].
+    pub fn add_touched_contract_nonces(
+        &self,
+        id: &impl DbBlockIdResolvable,
+        state_diff: &mut StateDiff,
+    ) -> Result<(), MadaraStorageError> {
+        for contract_address in state_diff.touched_contracts() {
+            if state_diff.nonces.iter().all(|nonce| nonce.contract_address != contract_address) {
+                let nonce = self.get_contract_nonce_at(id, &contract_address)?.unwrap_or_default();
+                state_diff.nonces.push(NonceUpdate { contract_address, nonce });
+            }
+        }
+        Ok(())
     }
 
     pub fn get_contract_storage_at(
@@ -121,13 +151,60 @@ impl MadaraBackend {
         contract_addr: &Felt,
         key: &Felt,
     ) -> Result<Option<Felt>, MadaraStorageError> {
-        self.resolve_history_kv(
+        if let Some(value) = self.resolve_history_kv(
             id,
             Column::PendingContractStorage,
             Column::ContractStorage,
             &(*contract_addr, *key),
             |(k1, k2)| make_storage_key_prefix(*k1, *k2),
-        )
+        )? {
+            return Ok(Some(value));
+        }
+        self.forked_storage_at(id, contract_addr, key)
+    }
+
+    /// Addresses of all the contracts with a class hash, a nonce or a storage value in the flat state, in order.
+    pub fn get_contract_addresses(&self) -> Result<Vec<Felt>, MadaraStorageError> {
+        let mut addresses = BTreeSet::new();
+        for column in [Column::ContractToClassHashes, Column::ContractToNonces, Column::ContractStorage] {
+            let mut options = ReadOptions::default();
+            options.set_total_order_seek(true);
+            let mut iter = self.db.raw_iterator_cf_opt(&self.db.get_column(column), options);
+            iter.seek_to_first();
+            while let Some(key) = iter.key() {
+                let address = Felt::from_bytes_be_slice(&key[..32]);
+                addresses.insert(address);
+                // Skip the remaining keys of the contract. Contract addresses are below 2**251, this cannot overflow.
+                iter.seek((address + Felt::ONE).to_bytes_be());
+            }
+            iter.status()?;
+        }
+        Ok(addresses.into_iter().collect())
+    }
+
+    /// Latest value of every storage key written for the contract, in key order. This does not include the pending
+    /// block.
+    pub fn get_latest_contract_storage(&self, contract_addr: &Felt) -> Result<Vec<(Felt, Felt)>, MadaraStorageError> {
+        let prefix = contract_addr.to_bytes_be();
+        let mut options = ReadOptions::default();
+        options.set_total_order_seek(true);
+        let mode = IteratorMode::From(&prefix, rocksdb::Direction::Forward);
+
+        let mut entries: Vec<(Felt, Felt)> = Vec::new();
+        for res in self.db.iterator_cf_opt(&self.db.get_column(Column::ContractStorage), options, mode) {
+            let (k, v) = res?;
+            if !k.starts_with(&prefix) {
+                break;
+            }
+            let key = Felt::from_bytes_be_slice(&k[32..CONTRACT_STORAGE_PREFIX_EXTRACTOR]);
+            let value = bincode::deserialize(&v)?;
+            // The history of a key is sorted by block number: the last entry is the latest value.
+            match entries.last_mut() {
+                Some((last_key, last_value)) if *last_key == key => *last_value = value,
+                _ => entries.push((key, value)),
+            }
+        }
+        Ok(entries)
     }
 
     /// NB: This functions needs to run on the rayon thread pool
@@ -261,4 +338,41 @@ impl MadaraBackend {
 
         Ok(())
     }
+
+    /// Removes the history entries written by the reverted blocks, given with their state diffs.
+    pub(crate) fn contract_db_revert(&self, reverted_blocks: &[(u64, StateDiff)]) -> Result<(), MadaraStorageError> {
+        let mut batch = WriteBatchWithTransaction::default();
+
+        let class_hashes = self.db.get_column(Column::ContractToClassHashes);
+        let nonces = self.db.get_column(Column::ContractToNonces);
+        let storage = self.db.get_column(Column::ContractStorage);
+
+        for (block_n, state_diff) in reverted_blocks {
+            let block_n = u32::try_from(*block_n).map_err(|_| MadaraStorageError::InvalidBlockNumber)?;
+            let suffix = block_n.to_be_bytes();
+
+            for DeployedContractItem { address, .. } in &state_diff.deployed_contracts {
+                batch.delete_cf(&class_hashes, [address.to_bytes_be().as_ref(), &suffix as &[u8]].concat());
+            }
+            for ReplacedClassItem { contract_address, .. } in &state_diff.replaced_classes {
+                batch.delete_cf(&class_hashes, [contract_address.to_bytes_be().as_ref(), &suffix as &[u8]].concat());
+            }
+            for NonceUpdate { contract_address, .. } in &state_diff.nonces {
+                batch.delete_cf(&nonces, [contract_address.to_bytes_be().as_ref(), &suffix as &[u8]].concat());
+            }
+            for ContractStorageDiffItem { address, storage_entries } in &state_diff.storage_diffs {
+                for StorageEntry { key, .. } in storage_entries {
+                    batch.delete_cf(
+                        &storage,
+                        [make_storage_key_prefix(*address, *key).as_ref(), &suffix as &[u8]].concat(),
+                    );
+                }
+            }
+        }
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(batch, &writeopts)?;
+        Ok(())
+    }
 }
@@ -21,6 +21,8 @@ pub enum MadaraStorageError {
     InconsistentStorage(Cow<'static, str>),
     #[error("Cannot create a pending block of the genesis block of a chain")]
     PendingCreationNoGenesis,
+    #[error("Fetching state from the forked network: {0:#}")]
+    Fork(anyhow::Error),
 }
 
 impl From<bonsai_trie::BonsaiStorageError<DbError>> for MadaraStorageError {
@@ -0,0 +1,40 @@
+use rocksdb::WriteOptions;
+
+use crate::error::DbError;
+use crate::{Column, DatabaseExt, MadaraBackend, MadaraStorageError};
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+/// Progress of the execution extensions, so that the blocks they missed can be replayed across restarts.
+impl MadaraBackend {
+    /// Last block fully processed by the ExEx, `None` when it never reported any.
+    pub fn get_exex_finished_height(&self, exex_id: &str) -> Result<Option<u64>> {
+        let col = self.db.get_column(Column::ExExFinishedHeights);
+        let Some(res) = self.db.get_pinned_cf(&col, exex_id.as_bytes())? else {
+            return Ok(None);
+        };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    pub fn write_exex_finished_height(&self, exex_id: &str, block_n: u64) -> Result<(), DbError> {
+        let col = self.db.get_column(Column::ExExFinishedHeights);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&col, exex_id.as_bytes(), bincode::serialize(&block_n)?, &writeopts)?;
+        Ok(())
+    }
+
+    /// State an ExEx persisted under this key, in the format of its choice.
+    pub fn get_exex_state(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let col = self.db.get_column(Column::ExExState);
+        Ok(self.db.get_cf(&col, key.as_bytes())?)
+    }
+
+    pub fn write_exex_state(&self, key: &str, state: &[u8]) -> Result<(), DbError> {
+        let col = self.db.get_column(Column::ExExState);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&col, key.as_bytes(), state, &writeopts)?;
+        Ok(())
+    }
+}
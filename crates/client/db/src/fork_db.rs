@@ -0,0 +1,174 @@
+//! State of the network a devnet was forked from.
+//!
+//! The local database only holds the blocks produced after the fork. When a contract, storage value or class was
+//! never written locally, it is fetched from the forked network at the fork block and cached in the
+//! [`Column::ForkCache`] column, so that every value is only fetched once.
+
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::Context;
+use mp_class::{ClassInfo, CompiledSierra, ConvertedClass};
+use rocksdb::WriteOptions;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+
+use crate::db_block_id::DbBlockIdResolvable;
+use crate::{Column, DatabaseExt, MadaraBackend, MadaraStorageError};
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+const ROW_FORK_BLOCK: &[u8] = b"fork_block";
+
+/// Read access to the state of the forked network at the fork block. Values are `None` when the contract or the
+/// class does not exist on the forked network.
+///
+/// These are blocking calls, made from the execution and database reads.
+pub trait ForkedState: fmt::Debug + Send + Sync {
+    /// Block of the forked network the local chain starts from.
+    fn fork_block_n(&self) -> u64;
+    fn get_storage_at(&self, contract_address: &Felt, key: &Felt) -> anyhow::Result<Option<Felt>>;
+    fn get_nonce_at(&self, contract_address: &Felt) -> anyhow::Result<Option<Felt>>;
+    fn get_class_hash_at(&self, contract_address: &Felt) -> anyhow::Result<Option<Felt>>;
+    fn get_class(&self, class_hash: &Felt) -> anyhow::Result<Option<ConvertedClass>>;
+}
+
+#[derive(Serialize, Deserialize)]
+enum ForkCacheKey {
+    Storage(Felt, Felt),
+    Nonce(Felt),
+    ClassHash(Felt),
+    ClassInfo(Felt),
+    /// Keyed by compiled class hash, filled in when fetching the class info.
+    Compiled(Felt),
+}
+
+impl MadaraBackend {
+    /// Fall back to the state of the forked network for the values that were never written locally. Must be called
+    /// before any read.
+    pub fn set_forked_state(&self, forked_state: Arc<dyn ForkedState>) -> anyhow::Result<()> {
+        let fork_block_n = forked_state.fork_block_n();
+        match self.get_fork_block_n()? {
+            Some(db_fork_block_n) if db_fork_block_n != fork_block_n => anyhow::bail!(
+                "The database was forked at block {db_fork_block_n}, but the node is configured to fork at block \
+                 {fork_block_n}."
+            ),
+            Some(_) => {}
+            None if self.get_latest_block_n()?.is_some() => {
+                anyhow::bail!("The database was not created from a fork, it cannot be used to fork a network.")
+            }
+            None => {
+                let col = self.db.get_column(Column::BlockStorageMeta);
+                self.db
+                    .put_cf(&col, ROW_FORK_BLOCK, bincode::serialize(&fork_block_n)?)
+                    .context("Writing fork block to db")?;
+            }
+        }
+        self.forked_state.set(forked_state).map_err(|_| anyhow::anyhow!("The forked state is already set"))?;
+        Ok(())
+    }
+
+    /// Block of the forked network the database was created from, `None` when it is not a fork.
+    pub fn get_fork_block_n(&self) -> Result<Option<u64>> {
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_pinned_cf(&col, ROW_FORK_BLOCK)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    pub fn is_forked(&self) -> bool {
+        self.forked_state.get().is_some()
+    }
+
+    pub(crate) fn forked_storage_at(
+        &self,
+        id: &impl DbBlockIdResolvable,
+        contract_address: &Felt,
+        key: &Felt,
+    ) -> Result<Option<Felt>> {
+        self.forked_value(id, &ForkCacheKey::Storage(*contract_address, *key), |forked| {
+            forked.get_storage_at(contract_address, key)
+        })
+    }
+
+    pub(crate) fn forked_nonce_at(
+        &self,
+        id: &impl DbBlockIdResolvable,
+        contract_address: &Felt,
+    ) -> Result<Option<Felt>> {
+        self.forked_value(id, &ForkCacheKey::Nonce(*contract_address), |forked| forked.get_nonce_at(contract_address))
+    }
+
+    pub(crate) fn forked_class_hash_at(
+        &self,
+        id: &impl DbBlockIdResolvable,
+        contract_address: &Felt,
+    ) -> Result<Option<Felt>> {
+        self.forked_value(id, &ForkCacheKey::ClassHash(*contract_address), |forked| {
+            forked.get_class_hash_at(contract_address)
+        })
+    }
+
+    pub(crate) fn forked_class_info(
+        &self,
+        id: &impl DbBlockIdResolvable,
+        class_hash: &Felt,
+    ) -> Result<Option<ClassInfo>> {
+        self.forked_value(id, &ForkCacheKey::ClassInfo(*class_hash), |forked| {
+            let Some(class) = forked.get_class(class_hash)? else { return Ok(None) };
+            if let ConvertedClass::Sierra(sierra) = &class {
+                self.fork_cache_put(
+                    &ForkCacheKey::Compiled(sierra.info.compiled_class_hash),
+                    &Some(sierra.compiled.as_ref()),
+                )?;
+            }
+            Ok(Some(class.info()))
+        })
+    }
+
+    /// Compiled classes are not fetched: they are cached alongside the class info.
+    pub(crate) fn forked_sierra_compiled(
+        &self,
+        id: &impl DbBlockIdResolvable,
+        compiled_class_hash: &Felt,
+    ) -> Result<Option<CompiledSierra>> {
+        if self.forked_state.get().is_none() || id.resolve_db_block_id(self)?.is_none() {
+            return Ok(None);
+        }
+        Ok(self.fork_cache_get(&ForkCacheKey::Compiled(*compiled_class_hash))?.flatten())
+    }
+
+    fn forked_value<V: Serialize + DeserializeOwned>(
+        &self,
+        id: &impl DbBlockIdResolvable,
+        key: &ForkCacheKey,
+        fetch: impl FnOnce(&dyn ForkedState) -> anyhow::Result<Option<V>>,
+    ) -> Result<Option<V>> {
+        let Some(forked) = self.forked_state.get() else { return Ok(None) };
+        // Blocks that do not exist locally do not exist on the fork either.
+        if id.resolve_db_block_id(self)?.is_none() {
+            return Ok(None);
+        }
+
+        if let Some(cached) = self.fork_cache_get(key)? {
+            return Ok(cached);
+        }
+        let value = fetch(forked.as_ref()).map_err(MadaraStorageError::Fork)?;
+        self.fork_cache_put(key, &value)?;
+        Ok(value)
+    }
+
+    /// `Some(None)` when the value was fetched, and does not exist on the forked network.
+    fn fork_cache_get<V: DeserializeOwned>(&self, key: &ForkCacheKey) -> Result<Option<Option<V>>> {
+        let col = self.db.get_column(Column::ForkCache);
+        let Some(res) = self.db.get_pinned_cf(&col, bincode::serialize(key)?)? else { return Ok(None) };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    fn fork_cache_put<V: Serialize>(&self, key: &ForkCacheKey, value: &Option<V>) -> Result<()> {
+        let col = self.db.get_column(Column::ForkCache);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&col, bincode::serialize(key)?, bincode::serialize(value)?, &writeopts)?;
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ use crate::{Column, DatabaseExt, MadaraBackend, MadaraStorageError};
 type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
 
 pub const LAST_SYNCED_L1_EVENT_BLOCK: &[u8] = b"LAST_SYNCED_L1_EVENT_BLOCK";
+pub const LAST_SYNCED_L1_CANCELLATION_BLOCK: &[u8] = b"LAST_SYNCED_L1_CANCELLATION_BLOCK";
 
 /// Struct to store block number and event_index where L1->L2 Message occured
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -23,6 +24,18 @@ impl LastSyncedEventBlock {
     }
 }
 
+/// Cancellation status of an L1->L2 message, tracked from the core contract events.
+///
+/// The sender of a message can start its cancellation on L1, and finalize it once the cancellation delay of the
+/// core contract has elapsed. Messages are not consumed on L2 from the moment their cancellation has started.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum L1ToL2MessageCancellation {
+    /// A `MessageToL2CancellationStarted` event was emitted in this L1 block.
+    Started { l1_block_number: u64 },
+    /// A `MessageToL2Canceled` event was emitted in this L1 block.
+    Cancelled { l1_block_number: u64 },
+}
+
 /// We add method in MadaraBackend to be able to handle L1->L2 messaging related data
 impl MadaraBackend {
     /// Retrieves the last stored L1 block data that contains a message from the database.
@@ -124,4 +137,47 @@ impl MadaraBackend {
         self.db.put_cf_opt(&nonce_column, bincode::serialize(&nonce)?, /* empty value */ [], &writeopts)?;
         Ok(())
     }
+
+    /// L1 block of the last recorded message cancellation event, `None` when the cancellations were never tracked.
+    pub fn messaging_last_synced_l1_cancellation_block(&self) -> Result<Option<u64>> {
+        let messaging_column = self.db.get_column(Column::L1Messaging);
+        let Some(res) = self.db.get_pinned_cf(&messaging_column, LAST_SYNCED_L1_CANCELLATION_BLOCK)? else {
+            return Ok(None);
+        };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    pub fn messaging_update_last_synced_l1_cancellation_block(&self, l1_block_number: u64) -> Result<(), DbError> {
+        let messaging_column = self.db.get_column(Column::L1Messaging);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(
+            &messaging_column,
+            LAST_SYNCED_L1_CANCELLATION_BLOCK,
+            bincode::serialize(&l1_block_number)?,
+            &writeopts,
+        )?;
+        Ok(())
+    }
+
+    /// Cancellation status of the L1->L2 message with the given hash, `None` when no cancellation was requested.
+    pub fn get_l1_to_l2_message_cancellation(&self, msg_hash: &[u8; 32]) -> Result<Option<L1ToL2MessageCancellation>> {
+        let cancellations_column = self.db.get_column(Column::L1MessagingCancellations);
+        let Some(res) = self.db.get_pinned_cf(&cancellations_column, msg_hash)? else {
+            return Ok(None);
+        };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    pub fn set_l1_to_l2_message_cancellation(
+        &self,
+        msg_hash: &[u8; 32],
+        cancellation: L1ToL2MessageCancellation,
+    ) -> Result<(), DbError> {
+        let cancellations_column = self.db.get_column(Column::L1MessagingCancellations);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&cancellations_column, msg_hash, bincode::serialize(&cancellation)?, &writeopts)?;
+        Ok(())
+    }
 }
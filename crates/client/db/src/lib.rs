@@ -1,7 +1,7 @@
 //! Madara database
 
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use std::{fmt, fs};
 
@@ -27,13 +27,19 @@ pub mod contract_db;
 pub mod db_block_id;
 pub mod db_metrics;
 pub mod devnet_db;
+pub mod exex_db;
+pub mod fork_db;
 pub mod l1_db;
+pub mod maintenance;
+pub mod memory_trie;
+pub mod migration;
+pub mod proving_db;
 pub mod storage_updates;
 pub mod tests;
 
 pub use error::{MadaraStorageError, TrieType};
 use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
 
 pub type DB = DBWithThreadMode<MultiThreaded>;
 
@@ -125,6 +131,8 @@ pub enum Column {
     BlockNToStateDiff,
     /// Meta column for block storage (sync tip, pending block)
     BlockStorageMeta,
+    /// block_n => Signature of the block hash by the sequencer
+    BlockNToSignature,
 
     /// Contract class hash to class data
     ClassInfo,
@@ -169,9 +177,21 @@ pub enum Column {
 
     L1Messaging,
     L1MessagingNonce,
+    /// L1 -> L2 message hash => cancellation status
+    L1MessagingCancellations,
+
+    /// ExEx id => last block fully processed by the ExEx
+    ExExFinishedHeights,
+    /// Key => state an ExEx persists across restarts
+    ExExState,
+
+    /// block_n => Proving job of the block
+    BlockNToProvingJob,
 
     /// Devnet: stores the private keys for the devnet predeployed contracts
     Devnet,
+    /// Devnet: state fetched from the forked network
+    ForkCache,
 }
 
 impl fmt::Debug for Column {
@@ -196,6 +216,7 @@ impl Column {
             BlockHashToBlockN,
             BlockStorageMeta,
             BlockNToStateDiff,
+            BlockNToSignature,
             ClassInfo,
             ClassCompiled,
             PendingClassInfo,
@@ -216,10 +237,15 @@ impl Column {
             BonsaiClassesLog,
             L1Messaging,
             L1MessagingNonce,
+            L1MessagingCancellations,
+            ExExFinishedHeights,
+            ExExState,
+            BlockNToProvingJob,
             PendingContractToClassHashes,
             PendingContractToNonces,
             PendingContractStorage,
             Devnet,
+            ForkCache,
         ]
     };
     pub const NUM_COLUMNS: usize = Self::ALL.len();
@@ -233,6 +259,7 @@ impl Column {
             BlockHashToBlockN => "block_hash_to_block_n",
             BlockStorageMeta => "block_storage_meta",
             BlockNToStateDiff => "block_n_to_state_diff",
+            BlockNToSignature => "block_n_to_signature",
             BonsaiContractsTrie => "bonsai_contracts_trie",
             BonsaiContractsFlat => "bonsai_contracts_flat",
             BonsaiContractsLog => "bonsai_contracts_log",
@@ -253,13 +280,22 @@ impl Column {
             ContractStorage => "contract_storage",
             L1Messaging => "l1_messaging",
             L1MessagingNonce => "l1_messaging_nonce",
+            L1MessagingCancellations => "l1_messaging_cancellations",
+            ExExFinishedHeights => "exex_finished_heights",
+            ExExState => "exex_state",
+            BlockNToProvingJob => "block_n_to_proving_job",
             PendingContractToClassHashes => "pending_contract_to_class_hashes",
             PendingContractToNonces => "pending_contract_to_nonces",
             PendingContractStorage => "pending_contract_storage",
             Devnet => "devnet",
+            ForkCache => "fork_cache",
         }
     }
 
+    pub fn from_rocksdb_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|column| column.rocksdb_name() == name)
+    }
+
     /// Per column rocksdb options, like memory budget, compaction profiles, block sizes for hdd/sdd
     /// etc. TODO: add basic sensible defaults
     pub(crate) fn rocksdb_options(&self) -> Options {
@@ -300,6 +336,16 @@ impl DatabaseExt for DB {
     }
 }
 
+/// Blocks `to + 1..=from` were rolled back, and `to` is the new chain tip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockRevert {
+    pub from: u64,
+    pub to: u64,
+}
+
+/// Reverts are rare: a small buffer is enough for slow subscribers to catch up.
+const BLOCK_REVERTS_CHANNEL_CAPACITY: usize = 16;
+
 /// Madara client database backend singleton.
 #[derive(Debug)]
 pub struct MadaraBackend {
@@ -308,6 +354,13 @@ pub struct MadaraBackend {
     last_flush_time: Mutex<Option<Instant>>,
     chain_config: Arc<ChainConfig>,
     db_metrics: DbMetrics,
+    block_reverts: broadcast::Sender<BlockRevert>,
+    /// Number of the last closed block stored, `None` until a block is stored.
+    closed_blocks: watch::Sender<Option<u64>>,
+    /// Signals the updates of the pending block.
+    pending_block_updates: watch::Sender<()>,
+    /// Devnet: state of the forked network, for the values that were never written locally.
+    forked_state: OnceLock<Arc<dyn fork_db::ForkedState>>,
     #[cfg(feature = "testing")]
     _temp_dir: Option<tempfile::TempDir>,
 }
@@ -361,7 +414,11 @@ impl DatabaseService {
     }
 }
 
-impl Service for DatabaseService {}
+impl Service for DatabaseService {
+    fn name(&self) -> &'static str {
+        "database"
+    }
+}
 
 struct BackupRequest {
     callback: oneshot::Sender<()>,
@@ -389,6 +446,10 @@ impl MadaraBackend {
             last_flush_time: Default::default(),
             chain_config,
             db_metrics: DbMetrics::register(&MetricsRegistry::dummy()).unwrap(),
+            block_reverts: broadcast::channel(BLOCK_REVERTS_CHANNEL_CAPACITY).0,
+            closed_blocks: watch::channel(None).0,
+            pending_block_updates: watch::channel(()).0,
+            forked_state: OnceLock::new(),
             _temp_dir: Some(temp_dir),
         })
     }
@@ -432,13 +493,44 @@ impl MadaraBackend {
             db,
             last_flush_time: Default::default(),
             chain_config: Arc::clone(&chain_config),
+            block_reverts: broadcast::channel(BLOCK_REVERTS_CHANNEL_CAPACITY).0,
+            closed_blocks: watch::channel(None).0,
+            pending_block_updates: watch::channel(()).0,
+            forked_state: OnceLock::new(),
             #[cfg(feature = "testing")]
             _temp_dir: None,
         });
+        backend.migrate()?;
         backend.check_configuration()?;
+        backend.check_chain_config_hash()?;
         Ok(backend)
     }
 
+    /// Subscribes to block reverts, for components maintaining state derived from the chain.
+    pub fn subscribe_block_reverts(&self) -> broadcast::Receiver<BlockRevert> {
+        self.block_reverts.subscribe()
+    }
+
+    /// Signals the subscribers that blocks were rolled back. Must be called by whatever rolls back the chain, once
+    /// the storage reflects the new tip.
+    pub fn notify_block_revert(&self, revert: BlockRevert) {
+        log::info!("⏪ Reverted blocks #{} to #{}", revert.to + 1, revert.from);
+        // No subscribers is not an error.
+        let _ = self.block_reverts.send(revert);
+    }
+
+    /// Subscribes to the closed blocks stored in the database, for components following the chain tip. Only the
+    /// last block is kept: subscribers falling behind should read the blocks they missed from the database.
+    pub fn subscribe_closed_blocks(&self) -> watch::Receiver<Option<u64>> {
+        self.closed_blocks.subscribe()
+    }
+
+    /// Subscribes to the updates of the pending block stored in the database. The pending block should be read from
+    /// the database when notified.
+    pub fn subscribe_pending_block_updates(&self) -> watch::Receiver<()> {
+        self.pending_block_updates.subscribe()
+    }
+
     pub fn maybe_flush(&self, force: bool) -> Result<bool> {
         let mut inst = self.last_flush_time.lock().expect("poisoned mutex");
         let will_flush = force
@@ -474,46 +566,16 @@ impl MadaraBackend {
 
     // tries
 
-    pub(crate) fn get_bonsai<H: StarkHash + Send + Sync>(
-        &self,
-        map: DatabaseKeyMapping,
-    ) -> BonsaiStorage<BasicId, BonsaiDb<'_>, H> {
-        let bonsai = BonsaiStorage::new(
-            BonsaiDb::new(&self.db, map),
-            BonsaiStorageConfig {
-                max_saved_trie_logs: Some(0),
-                max_saved_snapshots: Some(0),
-                snapshot_interval: u64::MAX,
-            },
-        )
-        // TODO(bonsai-trie): change upstream to reflect that.
-        .expect("New bonsai storage can never error");
-
-        bonsai
-    }
-
     pub fn contract_trie(&self) -> BonsaiStorage<BasicId, BonsaiDb<'_>, Pedersen> {
-        self.get_bonsai(DatabaseKeyMapping {
-            flat: Column::BonsaiContractsFlat,
-            trie: Column::BonsaiContractsTrie,
-            log: Column::BonsaiContractsLog,
-        })
+        open_bonsai(&self.db, DatabaseKeyMapping::CONTRACTS)
     }
 
     pub fn contract_storage_trie(&self) -> BonsaiStorage<BasicId, BonsaiDb<'_>, Pedersen> {
-        self.get_bonsai(DatabaseKeyMapping {
-            flat: Column::BonsaiContractsStorageFlat,
-            trie: Column::BonsaiContractsStorageTrie,
-            log: Column::BonsaiContractsStorageLog,
-        })
+        open_bonsai(&self.db, DatabaseKeyMapping::CONTRACTS_STORAGE)
     }
 
     pub fn class_trie(&self) -> BonsaiStorage<BasicId, BonsaiDb<'_>, Poseidon> {
-        self.get_bonsai(DatabaseKeyMapping {
-            flat: Column::BonsaiClassesFlat,
-            trie: Column::BonsaiClassesTrie,
-            log: Column::BonsaiClassesLog,
-        })
+        open_bonsai(&self.db, DatabaseKeyMapping::CLASSES)
     }
 
     /// Returns the total storage size
@@ -522,6 +584,25 @@ impl MadaraBackend {
     }
 }
 
+/// Number of blocks the trie logs are kept for, which is how far back the global tries can be reverted.
+pub const MAX_SAVED_TRIE_LOGS: usize = 64;
+
+pub(crate) fn open_bonsai<H: StarkHash + Send + Sync>(
+    db: &DB,
+    map: DatabaseKeyMapping,
+) -> BonsaiStorage<BasicId, BonsaiDb<'_>, H> {
+    BonsaiStorage::new(
+        BonsaiDb::new(db, map),
+        BonsaiStorageConfig {
+            max_saved_trie_logs: Some(MAX_SAVED_TRIE_LOGS),
+            max_saved_snapshots: Some(0),
+            snapshot_interval: u64::MAX,
+        },
+    )
+    // TODO(bonsai-trie): change upstream to reflect that.
+    .expect("New bonsai storage can never error")
+}
+
 pub mod bonsai_identifier {
     pub const CONTRACT: &[u8] = b"0xcontract";
     pub const CLASS: &[u8] = b"0xclass";
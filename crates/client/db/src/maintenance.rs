@@ -0,0 +1,161 @@
+//! Offline database maintenance, used by the `madara db` command. These operate on the rocksdb columns directly, and
+//! must not be used while a node has the database open.
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use starknet_api::core::ChainId;
+
+use crate::block_db::{ChainInfo, ROW_CHAIN_INFO, ROW_L1_LAST_CONFIRMED_BLOCK, ROW_SYNC_TIP};
+use crate::migration::get_db_version;
+use crate::{open_rocksdb, Column, DatabaseExt, WriteBatchWithTransaction, DB, DB_UPDATES_BATCH_SIZE};
+
+mod verify_tries;
+
+pub use verify_tries::{DivergentContract, TrieVerification};
+
+/// Columns that only hold data that is not needed to run the node, and are cleared by [`DbMaintenance::prune`].
+const PRUNABLE_COLUMNS: &[Column] =
+    &[Column::BonsaiContractsLog, Column::BonsaiContractsStorageLog, Column::BonsaiClassesLog, Column::ForkCache];
+
+#[derive(Debug, Clone)]
+pub struct ColumnInfo {
+    pub column: Column,
+    /// Size of the column on disk, in bytes.
+    pub size: u64,
+    /// Estimated by rocksdb.
+    pub num_keys: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DbInfo {
+    /// `None` until a node was started on the database.
+    pub chain_id: Option<ChainId>,
+    pub chain_name: Option<String>,
+    /// `None` for databases created before versioning, see [`crate::migration::DB_VERSION`].
+    pub db_version: Option<u32>,
+    pub latest_block_n: Option<u64>,
+    pub l1_last_confirmed_block: Option<u64>,
+    pub columns: Vec<ColumnInfo>,
+}
+
+/// Entry of an export, see [`DbMaintenance::export`].
+#[derive(Serialize, Deserialize)]
+struct ExportEntry {
+    column: String,
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+pub struct DbMaintenance {
+    db: Arc<DB>,
+}
+
+impl DbMaintenance {
+    /// Opens the database of a node, `base_path` being its `--base-path`.
+    pub fn open(base_path: &Path) -> anyhow::Result<Self> {
+        let db_path = base_path.join("db");
+        anyhow::ensure!(db_path.exists(), "No database found at {}", db_path.display());
+        let db = open_rocksdb(&db_path, false)
+            .with_context(|| format!("Opening the database at {}. Is a node running on it?", db_path.display()))?;
+        Ok(Self { db })
+    }
+
+    pub fn info(&self) -> anyhow::Result<DbInfo> {
+        let meta = self.db.get_column(Column::BlockStorageMeta);
+        let chain_info = self
+            .db
+            .get_cf(&meta, ROW_CHAIN_INFO)?
+            .map(|res| bincode::deserialize::<ChainInfo>(&res))
+            .transpose()
+            .context("Reading the chain info")?;
+        let db_version = get_db_version(&self.db)?;
+        let latest_block_n = self
+            .db
+            .get_cf(&meta, ROW_SYNC_TIP)?
+            .map(|res| bincode::deserialize(&res))
+            .transpose()
+            .context("Reading the latest block number")?;
+        let l1_last_confirmed_block = self
+            .db
+            .get_cf(&meta, ROW_L1_LAST_CONFIRMED_BLOCK)?
+            .map(|res| bincode::deserialize(&res))
+            .transpose()
+            .context("Reading the latest l1 confirmed block number")?;
+
+        let columns = Column::ALL
+            .iter()
+            .map(|&column| {
+                let col = self.db.get_column(column);
+                let num_keys = self.db.property_int_value_cf(&col, "rocksdb.estimate-num-keys")?.unwrap_or(0);
+                Ok(ColumnInfo { column, size: self.db.get_column_family_metadata_cf(&col).size, num_keys })
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let (chain_id, chain_name) = chain_info.map(|info| (info.chain_id, info.chain_name)).unzip();
+        Ok(DbInfo { chain_id, chain_name, db_version, latest_block_n, l1_last_confirmed_block, columns })
+    }
+
+    /// Writes every entry of the `columns` to `writer`, in a format read by [`Self::import`]. Returns the number of
+    /// entries exported.
+    pub fn export(&self, columns: &[Column], mut writer: impl Write) -> anyhow::Result<u64> {
+        let mut count = 0;
+        for &column in columns {
+            let col = self.db.get_column(column);
+            for entry in self.db.iterator_cf(&col, rocksdb::IteratorMode::Start) {
+                let (key, value) = entry.with_context(|| format!("Reading column {column}"))?;
+                let entry = ExportEntry { column: column.to_string(), key: key.into(), value: value.into() };
+                bincode::serialize_into(&mut writer, &Some(entry)).context("Writing the export")?;
+                count += 1;
+            }
+        }
+        bincode::serialize_into(&mut writer, &None::<ExportEntry>).context("Writing the export")?;
+        writer.flush().context("Writing the export")?;
+        Ok(count)
+    }
+
+    /// Writes the entries of an export to the database, replacing the existing values of the same keys. Returns the
+    /// number of entries imported.
+    pub fn import(&self, mut reader: impl Read) -> anyhow::Result<u64> {
+        let mut count = 0;
+        let mut batch = WriteBatchWithTransaction::default();
+        while let Some(entry) =
+            bincode::deserialize_from::<_, Option<ExportEntry>>(&mut reader).context("Reading the export")?
+        {
+            let column = Column::from_rocksdb_name(&entry.column)
+                .with_context(|| format!("Unknown column {} in the export", entry.column))?;
+            batch.put_cf(&self.db.get_column(column), entry.key, entry.value);
+            count += 1;
+            if batch.len() >= DB_UPDATES_BATCH_SIZE {
+                self.db.write(std::mem::take(&mut batch)).context("Writing to the database")?;
+            }
+        }
+        self.db.write(batch).context("Writing to the database")?;
+        self.db.flush().context("Flushing the database")?;
+        Ok(count)
+    }
+
+    /// Clears the trie logs and the fork cache, which are not needed to run the node, and compacts every column to
+    /// reclaim the disk space.
+    pub fn prune(&self) -> anyhow::Result<()> {
+        for &column in PRUNABLE_COLUMNS {
+            self.drop_column(column)?;
+        }
+        for &column in Column::ALL {
+            log::debug!("Compacting column {column}");
+            self.db.compact_range_cf(&self.db.get_column(column), None::<&[u8]>, None::<&[u8]>);
+        }
+        Ok(())
+    }
+
+    /// Removes every entry of a column.
+    pub fn drop_column(&self, column: Column) -> anyhow::Result<()> {
+        let name = column.rocksdb_name();
+        self.db.drop_cf(name).with_context(|| format!("Dropping column {name}"))?;
+        self.db.create_cf(name, &column.rocksdb_options()).with_context(|| format!("Recreating column {name}"))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+//! Recomputation of the global tries from the flat state, used by `madara db verify-tries`.
+//!
+//! The flat state of a forked chain only holds the values written since the fork, so its tries cannot be recomputed.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use mp_block::MadaraBlockInfo;
+use mp_class::ClassInfo;
+use rocksdb::{IteratorMode, ReadOptions};
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
+
+use super::DbMaintenance;
+use crate::block_db::{ROW_NO_GLOBAL_TRIES_SINCE, ROW_SYNC_TIP};
+use crate::bonsai_db::DatabaseKeyMapping;
+use crate::class_db::ClassInfoWithBlockNumber;
+use crate::db_block_id::DbBlockId;
+use crate::memory_trie::{trie_key, MemoryTrie};
+use crate::{bonsai_identifier, open_bonsai, Column, DatabaseExt, MadaraStorageError};
+
+/// "STARKNET_STATE_V0"
+const STARKNET_STATE_PREFIX: Felt = Felt::from_hex_unchecked("0x535441524b4e45545f53544154455f5630");
+/// "CONTRACT_CLASS_LEAF_V0"
+const CONTRACT_CLASS_LEAF_PREFIX: Felt = Felt::from_hex_unchecked("0x434f4e54524143545f434c4153535f4c4541465f5630");
+
+/// Outcome of [`DbMaintenance::verify_tries`].
+#[derive(Debug, Clone)]
+pub struct TrieVerification {
+    pub block_n: u64,
+    /// Global state root in the header of the block.
+    pub expected_state_root: Felt,
+    pub contract_trie_root: Felt,
+    pub class_trie_root: Felt,
+    /// Global state root recomputed from the contract and class trie roots.
+    pub state_root: Felt,
+    pub n_contracts: usize,
+    pub n_classes: usize,
+    /// First contract, by address, whose leaf differs from the one in the stored contract trie. Only checked at the
+    /// latest block, as the stored tries only hold the latest state, and when the node maintains the global tries.
+    pub divergent_contract: Option<DivergentContract>,
+}
+
+impl TrieVerification {
+    pub fn is_consistent(&self) -> bool {
+        self.state_root == self.expected_state_root && self.divergent_contract.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DivergentContract {
+    pub contract_address: Felt,
+    pub class_hash: Felt,
+    pub nonce: Felt,
+    /// Storage root recomputed from the flat storage.
+    pub storage_root: Felt,
+    pub stored_storage_root: Felt,
+    pub leaf_hash: Felt,
+    /// `None` when the contract is missing from the stored trie.
+    pub stored_leaf_hash: Option<Felt>,
+}
+
+#[derive(Debug, Default)]
+struct ContractLeaf {
+    class_hash: Felt,
+    nonce: Felt,
+    storage_root: Felt,
+}
+
+impl ContractLeaf {
+    fn hash(&self) -> Felt {
+        Pedersen::hash(&Pedersen::hash(&Pedersen::hash(&self.class_hash, &self.storage_root), &self.nonce), &Felt::ZERO)
+    }
+}
+
+impl DbMaintenance {
+    /// Recomputes the contract and class tries from the flat state at `block_n`, the latest block by default, and
+    /// compares the resulting global state root to the one in the header of the block.
+    ///
+    /// This reads the whole state of the chain, and keeps a leaf per contract in memory.
+    pub fn verify_tries(&self, block_n: Option<u64>) -> anyhow::Result<TrieVerification> {
+        let meta = self.db.get_column(Column::BlockStorageMeta);
+        let latest_block_n: u64 =
+            bincode::deserialize(&self.db.get_cf(&meta, ROW_SYNC_TIP)?.context("The database has no block")?)
+                .context("Reading the latest block number")?;
+        let block_n = block_n.unwrap_or(latest_block_n);
+        anyhow::ensure!(block_n <= latest_block_n, "Block #{block_n} is after the latest block #{latest_block_n}");
+
+        let block_info: MadaraBlockInfo = bincode::deserialize(
+            &self
+                .db
+                .get_cf(&self.db.get_column(Column::BlockNToBlockInfo), bincode::serialize(&block_n)?)?
+                .with_context(|| format!("Block #{block_n} not found"))?,
+        )
+        .with_context(|| format!("Reading block #{block_n}"))?;
+        let history_block_n = u32::try_from(block_n).context("Block number too large")?;
+
+        let mut contracts: BTreeMap<Felt, ContractLeaf> = BTreeMap::new();
+        log::info!("🔍 Reading the class hashes and nonces of the contracts at block #{block_n}");
+        self.for_each_value_at(Column::ContractToClassHashes, 32, history_block_n, |key, class_hash| {
+            contracts.entry(Felt::from_bytes_be_slice(key)).or_default().class_hash = class_hash;
+        })?;
+        self.for_each_value_at(Column::ContractToNonces, 32, history_block_n, |key, nonce| {
+            contracts.entry(Felt::from_bytes_be_slice(key)).or_default().nonce = nonce;
+        })?;
+
+        log::info!("🔍 Recomputing the contract storage tries");
+        // The storage keys are prefixed by the contract address: the storage of a contract is read in one go.
+        let mut storage: Option<(Felt, MemoryTrie<Pedersen>)> = None;
+        self.for_each_value_at(Column::ContractStorage, 64, history_block_n, |key, value| {
+            let contract_address = Felt::from_bytes_be_slice(&key[..32]);
+            if storage.as_ref().is_some_and(|(current, _)| *current != contract_address) {
+                let (address, mut trie) = storage.take().expect("Checked above");
+                contracts.entry(address).or_default().storage_root = trie.commit();
+            }
+            let (_, trie) =
+                storage.get_or_insert_with(|| (contract_address, MemoryTrie::new(&contract_address.to_bytes_be())));
+            // Zero values are not in the trie.
+            if value != Felt::ZERO {
+                trie.insert(&Felt::from_bytes_be_slice(&key[32..]), &value);
+            }
+        })?;
+        if let Some((address, mut trie)) = storage {
+            contracts.entry(address).or_default().storage_root = trie.commit();
+        }
+
+        log::info!("🔍 Recomputing the contract trie from {} contracts", contracts.len());
+        let mut contract_trie = MemoryTrie::<Pedersen>::new(bonsai_identifier::CONTRACT);
+        for (contract_address, leaf) in &contracts {
+            contract_trie.insert(contract_address, &leaf.hash());
+        }
+        let contract_trie_root = contract_trie.commit();
+
+        log::info!("🔍 Recomputing the class trie");
+        let mut class_trie = MemoryTrie::<Poseidon>::new(bonsai_identifier::CLASS);
+        let mut n_classes = 0;
+        let mut options = ReadOptions::default();
+        options.set_total_order_seek(true);
+        for entry in self.db.iterator_cf_opt(&self.db.get_column(Column::ClassInfo), options, IteratorMode::Start) {
+            let (key, value) = entry.context("Reading the classes")?;
+            let ClassInfoWithBlockNumber { class_info, block_id } =
+                bincode::deserialize(&value).context("Reading a class")?;
+            // Legacy classes are not in the class trie.
+            let ClassInfo::Sierra(class_info) = class_info else { continue };
+            if matches!(block_id, DbBlockId::Number(declared_at) if declared_at <= block_n) {
+                let class_hash: Felt = bincode::deserialize(&key).context("Reading a class hash")?;
+                class_trie
+                    .insert(&class_hash, &Poseidon::hash(&CONTRACT_CLASS_LEAF_PREFIX, &class_info.compiled_class_hash));
+                n_classes += 1;
+            }
+        }
+        let class_trie_root = class_trie.commit();
+
+        let state_root = if class_trie_root == Felt::ZERO {
+            contract_trie_root
+        } else {
+            Poseidon::hash_array(&[STARKNET_STATE_PREFIX, contract_trie_root, class_trie_root])
+        };
+
+        // The stored tries are only compared when they are maintained.
+        let divergent_contract =
+            if block_n == latest_block_n && self.db.get_cf(&meta, ROW_NO_GLOBAL_TRIES_SINCE)?.is_none() {
+                self.first_divergent_contract(&contracts)?
+            } else {
+                None
+            };
+
+        Ok(TrieVerification {
+            block_n,
+            expected_state_root: block_info.header.global_state_root,
+            contract_trie_root,
+            class_trie_root,
+            state_root,
+            n_contracts: contracts.len(),
+            n_classes,
+            divergent_contract,
+        })
+    }
+
+    /// Compares the recomputed contract leaves to the ones in the stored contract trie.
+    fn first_divergent_contract(
+        &self,
+        contracts: &BTreeMap<Felt, ContractLeaf>,
+    ) -> anyhow::Result<Option<DivergentContract>> {
+        log::info!("🔍 Comparing the contracts with the stored contract trie");
+        let contract_trie = open_bonsai::<Pedersen>(&self.db, DatabaseKeyMapping::CONTRACTS);
+        let contract_storage_trie = open_bonsai::<Pedersen>(&self.db, DatabaseKeyMapping::CONTRACTS_STORAGE);
+
+        for (contract_address, leaf) in contracts {
+            let leaf_hash = leaf.hash();
+            let stored_leaf_hash = contract_trie
+                .get(bonsai_identifier::CONTRACT, &trie_key(contract_address))
+                .map_err(MadaraStorageError::from)?;
+            if stored_leaf_hash != Some(leaf_hash) {
+                let stored_storage_root = contract_storage_trie
+                    .root_hash(&contract_address.to_bytes_be())
+                    .map_err(MadaraStorageError::from)?;
+                return Ok(Some(DivergentContract {
+                    contract_address: *contract_address,
+                    class_hash: leaf.class_hash,
+                    nonce: leaf.nonce,
+                    storage_root: leaf.storage_root,
+                    stored_storage_root,
+                    leaf_hash,
+                    stored_leaf_hash,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Calls `f` with every key of a history column, in order, and its value at `block_n`. The keys of these columns
+    /// are suffixed by the number of the block which wrote the value, see the flat storage documentation.
+    fn for_each_value_at(
+        &self,
+        column: Column,
+        key_len: usize,
+        block_n: u32,
+        mut f: impl FnMut(&[u8], Felt),
+    ) -> anyhow::Result<()> {
+        let mut options = ReadOptions::default();
+        options.set_total_order_seek(true);
+        let mut current: Option<(Box<[u8]>, Felt)> = None;
+        for entry in self.db.iterator_cf_opt(&self.db.get_column(column), options, IteratorMode::Start) {
+            let (key, value) = entry.with_context(|| format!("Reading column {column}"))?;
+            anyhow::ensure!(key.len() == key_len + 4, "Invalid key length in column {column}");
+            let (key, written_at) = key.split_at(key_len);
+            let written_at = u32::from_be_bytes(written_at.try_into().expect("Checked the key length"));
+
+            if current.as_ref().is_some_and(|(current, _)| current.as_ref() != key) {
+                let (key, value) = current.take().expect("Checked above");
+                f(&key, value);
+            }
+            if written_at <= block_n {
+                current = Some((
+                    key.into(),
+                    bincode::deserialize(&value).with_context(|| format!("Reading column {column}"))?,
+                ));
+            }
+        }
+        if let Some((key, value)) = current {
+            f(&key, value);
+        }
+        Ok(())
+    }
+}
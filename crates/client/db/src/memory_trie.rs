@@ -0,0 +1,129 @@
+//! Tries built from scratch in memory, to compute their root without touching the stored tries, and to replace a
+//! stored trie that got corrupted.
+
+use bitvec::order::Msb0;
+use bitvec::vec::BitVec;
+use bitvec::view::AsBits;
+use bonsai_trie::id::{BasicId, BasicIdBuilder};
+use bonsai_trie::{BonsaiStorage, BonsaiStorageConfig};
+use rocksdb::{IteratorMode, WriteOptions};
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, Poseidon, StarkHash};
+
+use crate::bonsai_db::{DatabaseKeyMapping, MemoryBonsaiDb};
+use crate::{bonsai_identifier, Column, DatabaseExt, MadaraBackend, MadaraStorageError, WriteBatchWithTransaction};
+
+/// A trie held in memory, which holds the single trie `identifier`.
+///
+/// The memory database can't fail, the results of the trie operations are unwrapped.
+pub struct MemoryTrie<H: StarkHash + Send + Sync> {
+    identifier: Vec<u8>,
+    db: MemoryBonsaiDb,
+    storage: BonsaiStorage<BasicId, MemoryBonsaiDb, H>,
+}
+
+impl<H: StarkHash + Send + Sync> MemoryTrie<H> {
+    pub fn new(identifier: &[u8]) -> Self {
+        let db = MemoryBonsaiDb::default();
+        let storage = BonsaiStorage::new(
+            db.clone(),
+            BonsaiStorageConfig {
+                max_saved_trie_logs: Some(0),
+                max_saved_snapshots: Some(0),
+                snapshot_interval: u64::MAX,
+            },
+        )
+        .expect("Failed to create bonsai storage");
+        Self { identifier: identifier.to_vec(), db, storage }
+    }
+
+    pub fn insert(&mut self, key: &Felt, value: &Felt) {
+        self.storage.insert(&self.identifier, &trie_key(key), value).expect("Failed to insert into bonsai storage");
+    }
+
+    /// Commits the inserted values and returns the root of the trie.
+    pub fn commit(&mut self) -> Felt {
+        self.storage.commit(BasicIdBuilder::new().new_id()).expect("Failed to commit to bonsai storage");
+        self.storage.root_hash(&self.identifier).expect("Failed to get root hash")
+    }
+}
+
+/// Path of a key in the global tries, its 251 low bits.
+pub(crate) fn trie_key(key: &Felt) -> BitVec<u8, Msb0> {
+    key.to_bytes_be().as_bits()[5..].to_owned()
+}
+
+impl MadaraBackend {
+    /// Replaces the contract trie with `trie`, built with the [`bonsai_identifier::CONTRACT`] identifier.
+    pub fn replace_contract_trie(&self, trie: &MemoryTrie<Pedersen>) -> Result<(), MadaraStorageError> {
+        self.replace_trie(DatabaseKeyMapping::CONTRACTS, bonsai_identifier::CONTRACT, trie)
+    }
+
+    /// Replaces the storage trie of a contract with `trie`, built with the contract address as identifier.
+    pub fn replace_contract_storage_trie(
+        &self,
+        contract_address: &Felt,
+        trie: &MemoryTrie<Pedersen>,
+    ) -> Result<(), MadaraStorageError> {
+        self.replace_trie(DatabaseKeyMapping::CONTRACTS_STORAGE, &contract_address.to_bytes_be(), trie)
+    }
+
+    /// Replaces the class trie with `trie`, built with the [`bonsai_identifier::CLASS`] identifier.
+    pub fn replace_class_trie(&self, trie: &MemoryTrie<Poseidon>) -> Result<(), MadaraStorageError> {
+        self.replace_trie(DatabaseKeyMapping::CLASSES, bonsai_identifier::CLASS, trie)
+    }
+
+    /// Removes the nodes and values stored under `identifier` and writes the ones of the committed `trie` instead, in a
+    /// single batch. The trie logs of the columns are cleared, as they do not apply to the new trie: the tries can no
+    /// longer be reverted to a block before the replacement.
+    fn replace_trie<H: StarkHash + Send + Sync>(
+        &self,
+        map: DatabaseKeyMapping,
+        identifier: &[u8],
+        trie: &MemoryTrie<H>,
+    ) -> Result<(), MadaraStorageError> {
+        if trie.identifier != identifier {
+            return Err(MadaraStorageError::InconsistentStorage(
+                "The trie was not built with the identifier it replaces".into(),
+            ));
+        }
+
+        let mut batch = WriteBatchWithTransaction::default();
+        for column in [map.trie, map.flat] {
+            self.delete_by_prefix(&mut batch, column, identifier)?;
+        }
+        self.delete_by_prefix(&mut batch, map.log, &[])?;
+
+        let db = trie.db.inner();
+        let trie_col = self.db.get_column(map.trie);
+        for (key, value) in &db.trie {
+            batch.put_cf(&trie_col, key, value);
+        }
+        let flat_col = self.db.get_column(map.flat);
+        for (key, value) in &db.flat {
+            batch.put_cf(&flat_col, key, value);
+        }
+
+        let mut writeopts = WriteOptions::new();
+        writeopts.disable_wal(true);
+        self.db.write_opt(batch, &writeopts)?;
+        Ok(())
+    }
+
+    fn delete_by_prefix(
+        &self,
+        batch: &mut WriteBatchWithTransaction,
+        column: Column,
+        prefix: &[u8],
+    ) -> Result<(), MadaraStorageError> {
+        let col = self.db.get_column(column);
+        for kv in self.db.iterator_cf(&col, IteratorMode::From(prefix, rocksdb::Direction::Forward)) {
+            let (key, _) = kv?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            batch.delete_cf(&col, &key);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Versioning of the database schema. The version is stored in the database, and the registered migrations bring
+//! databases created by older versions of madara up to date when the node starts.
+//!
+//! The hash of the chain config the database was created with is stored along with it, see [`chain_config_hash`].
+
+use anyhow::Context;
+use mp_chain_config::ChainConfig;
+use rocksdb::WriteOptions;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Poseidon, StarkHash};
+
+use crate::block_db::ROW_CHAIN_INFO;
+use crate::{Column, DatabaseExt, MadaraBackend, DB};
+
+/// Version of the schema written by this version of madara. Every change of the schema increments it, and registers
+/// a migration from the previous version in [`MIGRATIONS`].
+pub const DB_VERSION: u32 = 2;
+
+pub(crate) const ROW_DB_VERSION: &[u8] = b"db_version";
+pub(crate) const ROW_CHAIN_CONFIG_HASH: &[u8] = b"chain_config_hash";
+
+struct Migration {
+    /// The migration brings the database from this version to the next one.
+    from: u32,
+    description: &'static str,
+    run: fn(&DB) -> anyhow::Result<()>,
+}
+
+/// Migrations, ordered by version.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "record the database version, which databases created before versioning do not have",
+        run: |_| Ok(()),
+    },
+    // The column is created when opening the database.
+    Migration { from: 1, description: "add the proving jobs column", run: |_| Ok(()) },
+];
+
+pub(crate) fn get_db_version(db: &DB) -> anyhow::Result<Option<u32>> {
+    let col = db.get_column(Column::BlockStorageMeta);
+    let Some(res) = db.get_cf(&col, ROW_DB_VERSION)? else { return Ok(None) };
+    Ok(Some(bincode::deserialize(&res).context("Deserializing the database version")?))
+}
+
+pub(crate) fn set_db_version(db: &DB, version: u32) -> anyhow::Result<()> {
+    let col = db.get_column(Column::BlockStorageMeta);
+    let mut writeopts = WriteOptions::new();
+    writeopts.set_sync(true);
+    db.put_cf_opt(&col, ROW_DB_VERSION, bincode::serialize(&version)?, &writeopts)
+        .context("Writing the database version")?;
+    Ok(())
+}
+
+/// Hash of the chain config parameters the stored chain depends on: the chain id and the fee token addresses. The
+/// other parameters, such as the block time or the protocol upgrades, may change between restarts.
+pub fn chain_config_hash(chain_config: &ChainConfig) -> Felt {
+    Poseidon::hash_array(&[
+        Felt::from_bytes_be_slice(chain_config.chain_id.to_string().as_bytes()),
+        *chain_config.native_fee_token_address.0.key(),
+        *chain_config.parent_fee_token_address.0.key(),
+    ])
+}
+
+impl MadaraBackend {
+    /// Refuses to open a database created with a different chain config, see [`chain_config_hash`]. The hash is
+    /// recorded when the database does not have one yet.
+    pub(crate) fn check_chain_config_hash(&self) -> anyhow::Result<()> {
+        let expected = chain_config_hash(&self.chain_config);
+        let col = self.db.get_column(Column::BlockStorageMeta);
+        let Some(res) = self.db.get_cf(&col, ROW_CHAIN_CONFIG_HASH)? else {
+            self.db
+                .put_cf(&col, ROW_CHAIN_CONFIG_HASH, bincode::serialize(&expected)?)
+                .context("Writing the chain config hash")?;
+            return Ok(());
+        };
+
+        let stored: Felt = bincode::deserialize(&res).context("Deserializing the chain config hash")?;
+        anyhow::ensure!(
+            stored == expected,
+            "The database was created with a different chain config (hash {stored:#x}, the node is configured with              {expected:#x}): the chain id or the fee token addresses changed. Start the node with the chain config              the database was created with, or remove the database and sync again."
+        );
+        Ok(())
+    }
+
+    /// Version of the database schema. Databases created before versioning have no version.
+    pub fn get_db_version(&self) -> anyhow::Result<Option<u32>> {
+        get_db_version(&self.db)
+    }
+
+    /// Runs the migrations needed to bring the database to [`DB_VERSION`], or refuses to open a database that cannot
+    /// be migrated.
+    pub(crate) fn migrate(&self) -> anyhow::Result<()> {
+        let version = match self.get_db_version()? {
+            Some(version) => version,
+            None => {
+                let col = self.db.get_column(Column::BlockStorageMeta);
+                if self.db.get_cf(&col, ROW_CHAIN_INFO)?.is_none() {
+                    // New database.
+                    return set_db_version(&self.db, DB_VERSION);
+                }
+                0
+            }
+        };
+
+        anyhow::ensure!(
+            version <= DB_VERSION,
+            "The database has version {version}, which was created by a newer version of madara. This version of \
+             madara supports databases up to version {DB_VERSION}: upgrade madara, or restore a backup of the \
+             database."
+        );
+
+        for from in version..DB_VERSION {
+            let migration = MIGRATIONS.iter().find(|migration| migration.from == from).with_context(|| {
+                format!(
+                    "The database has version {version}, and there is no migration from version {from} to the \
+                     current version {DB_VERSION}. Remove the database and sync again, or use a version of madara \
+                     that supports it."
+                )
+            })?;
+            log::info!("⏫ Migrating the database from version {from} to {}: {}", from + 1, migration.description);
+            (migration.run)(&self.db).with_context(|| format!("Migrating the database from version {from}"))?;
+            set_db_version(&self.db, from + 1)?;
+        }
+        Ok(())
+    }
+}
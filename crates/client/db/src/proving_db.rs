@@ -0,0 +1,74 @@
+use rocksdb::{Direction, IteratorMode, WriteOptions};
+use serde::{Deserialize, Serialize};
+
+use crate::error::DbError;
+use crate::{Column, DatabaseExt, MadaraBackend, MadaraStorageError};
+
+type Result<T, E = MadaraStorageError> = std::result::Result<T, E>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProvingJobStatus {
+    /// The prover is proving the block.
+    InProgress,
+    /// The block is proven. The fact is only known for provers returning it.
+    Proven { fact: Option<[u8; 32]> },
+    /// The prover rejected the block, or failed to prove it.
+    Failed { error: String },
+}
+
+/// Proving of the Cairo PIE of a block by an external prover.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvingJob {
+    /// Id of the job on the prover.
+    pub job_id: String,
+    pub status: ProvingJobStatus,
+    /// Unix timestamp of the last submission, in seconds.
+    pub submitted_at: u64,
+    /// Number of times the block was submitted.
+    pub attempts: u32,
+}
+
+/// Proving jobs of the blocks, submitted in order.
+impl MadaraBackend {
+    pub fn get_proving_job(&self, block_n: u64) -> Result<Option<ProvingJob>> {
+        let col = self.db.get_column(Column::BlockNToProvingJob);
+        let Some(res) = self.db.get_pinned_cf(&col, block_n.to_be_bytes())? else {
+            return Ok(None);
+        };
+        Ok(Some(bincode::deserialize(&res)?))
+    }
+
+    pub fn write_proving_job(&self, block_n: u64, job: &ProvingJob) -> Result<(), DbError> {
+        let col = self.db.get_column(Column::BlockNToProvingJob);
+        let mut writeopts = WriteOptions::default();
+        writeopts.disable_wal(true);
+        self.db.put_cf_opt(&col, block_n.to_be_bytes(), bincode::serialize(job)?, &writeopts)?;
+        Ok(())
+    }
+
+    /// Proving jobs of the blocks from `from_block`, in order, at most `limit` of them.
+    pub fn get_proving_jobs(&self, from_block: u64, limit: usize) -> Result<Vec<(u64, ProvingJob)>> {
+        let col = self.db.get_column(Column::BlockNToProvingJob);
+        let start = from_block.to_be_bytes();
+        self.db
+            .iterator_cf(&col, IteratorMode::From(&start, Direction::Forward))
+            .take(limit)
+            .map(|entry| decode_entry(entry?))
+            .collect()
+    }
+
+    /// Proving job of the highest block submitted to the prover.
+    pub fn get_last_proving_job(&self) -> Result<Option<(u64, ProvingJob)>> {
+        let col = self.db.get_column(Column::BlockNToProvingJob);
+        let Some(entry) = self.db.iterator_cf(&col, IteratorMode::End).next() else {
+            return Ok(None);
+        };
+        Ok(Some(decode_entry(entry?)?))
+    }
+}
+
+fn decode_entry((key, value): (Box<[u8]>, Box<[u8]>)) -> Result<(u64, ProvingJob)> {
+    let key =
+        (*key).try_into().map_err(|_| MadaraStorageError::InconsistentStorage("Invalid proving job key".into()))?;
+    Ok((u64::from_be_bytes(key), bincode::deserialize(&value)?))
+}
@@ -1,3 +1,4 @@
+use crate::db_block_id::DbBlockId;
 use crate::MadaraBackend;
 use crate::MadaraStorageError;
 use mp_block::{MadaraBlock, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo, MadaraPendingBlock};
@@ -15,6 +16,18 @@ impl MadaraBackend {
         block: MadaraMaybePendingBlock,
         state_diff: StateDiff,
         converted_classes: Vec<ConvertedClass>,
+    ) -> Result<(), MadaraStorageError> {
+        self.store_signed_block(block, state_diff, converted_classes, None)
+    }
+
+    /// Same as [`Self::store_block`], also storing the signature of the block hash by the sequencer in the same write
+    /// batch as the block. Pending blocks are not signed.
+    pub fn store_signed_block(
+        &self,
+        block: MadaraMaybePendingBlock,
+        state_diff: StateDiff,
+        converted_classes: Vec<ConvertedClass>,
+        signature: Option<Vec<Felt>>,
     ) -> Result<(), MadaraStorageError> {
         let block_n = block.info.block_n();
         let state_diff_cpy = state_diff.clone();
@@ -23,9 +36,11 @@ impl MadaraBackend {
             MadaraMaybePendingBlockInfo::Pending(info) => {
                 self.block_db_store_pending(&MadaraPendingBlock { info, inner: block.inner }, &state_diff_cpy)
             }
-            MadaraMaybePendingBlockInfo::NotPending(info) => {
-                self.block_db_store_block(&MadaraBlock { info, inner: block.inner }, &state_diff_cpy)
-            }
+            MadaraMaybePendingBlockInfo::NotPending(info) => self.block_db_store_block(
+                &MadaraBlock { info, inner: block.inner },
+                &state_diff_cpy,
+                signature.as_deref(),
+            ),
         };
 
         let task_contract_db = || {
@@ -75,7 +90,12 @@ impl MadaraBackend {
 
         let ((r1, r2), r3) = rayon::join(|| rayon::join(task_block_db, task_contract_db), task_class_db);
 
-        r1.and(r2).and(r3)
+        r1.and(r2).and(r3)?;
+        match block_n {
+            Some(block_n) => self.closed_blocks.send_replace(Some(block_n)),
+            None => self.pending_block_updates.send_replace(()),
+        };
+        Ok(())
     }
 
     pub fn clear_pending_block(&self) -> Result<(), MadaraStorageError> {
@@ -84,4 +104,34 @@ impl MadaraBackend {
         self.class_db_clear_pending()?;
         Ok(())
     }
+
+    /// Removes the blocks after `block_n` from the database, `block_n` becomes the latest block and the pending block
+    /// is cleared. Returns the state diffs of the removed blocks, in block order.
+    ///
+    /// The global tries are not reverted here: this is done beforehand by the block importer, which then notifies the
+    /// subscribers of [`MadaraBackend::subscribe_block_reverts`].
+    pub fn revert_to(&self, block_n: u64) -> Result<Vec<StateDiff>, MadaraStorageError> {
+        let latest_block_n = self.get_latest_block_n()?.unwrap_or(0);
+        if block_n > latest_block_n {
+            return Err(MadaraStorageError::InvalidBlockNumber);
+        }
+
+        let reverted_blocks = (block_n + 1..=latest_block_n)
+            .map(|reverted_block_n| {
+                let state_diff = self.get_block_state_diff(&DbBlockId::Number(reverted_block_n))?.ok_or_else(|| {
+                    MadaraStorageError::InconsistentStorage(
+                        format!("State diff of block #{reverted_block_n} not found").into(),
+                    )
+                })?;
+                Ok((reverted_block_n, state_diff))
+            })
+            .collect::<Result<Vec<_>, MadaraStorageError>>()?;
+
+        self.contract_db_revert(&reverted_blocks)?;
+        self.class_db_revert(block_n, &reverted_blocks)?;
+        self.block_db_revert(block_n, latest_block_n)?;
+        self.clear_pending_block()?;
+
+        Ok(reverted_blocks.into_iter().map(|(_, state_diff)| state_diff).collect())
+    }
 }
@@ -1,4 +1,10 @@
 pub mod common;
 pub mod test_block;
 #[cfg(test)]
+pub mod test_fork;
+#[cfg(test)]
+pub mod test_maintenance;
+#[cfg(test)]
 pub mod test_open;
+#[cfg(test)]
+pub mod test_proving;
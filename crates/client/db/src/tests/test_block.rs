@@ -4,10 +4,12 @@ mod block_tests {
     use super::super::common::*;
     use crate::db_block_id::DbBlockIdResolvable;
     use crate::{block_db::TxIndex, db_block_id::DbBlockId};
-    use mp_block::BlockId;
     use mp_block::Header;
+    use mp_block::{BlockId, BlockTag};
     use mp_chain_config::ChainConfig;
+    use mp_state_update::{ContractStorageDiffItem, NonceUpdate, StateDiff, StorageEntry};
     use starknet_api::felt;
+    use starknet_types_core::felt::Felt;
 
     #[tokio::test]
     async fn test_chain_info() {
@@ -141,6 +143,125 @@ mod block_tests {
         assert_eq!(backend.get_l1_last_confirmed_block().unwrap().unwrap(), 0);
     }
 
+    #[tokio::test]
+    async fn test_block_signature() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        assert!(backend.get_block_signature(0).unwrap().is_none());
+        assert!(backend.get_sequencer_public_key().unwrap().is_none());
+
+        backend
+            .store_signed_block(
+                finalized_block_zero(Header::default()),
+                finalized_state_diff_zero(),
+                vec![],
+                Some(vec![felt!("0x1"), felt!("0x2")]),
+            )
+            .unwrap();
+        backend.write_sequencer_public_key(Some(&felt!("0x3"))).unwrap();
+
+        assert_eq!(backend.get_block_signature(0).unwrap().unwrap(), vec![felt!("0x1"), felt!("0x2")]);
+        assert!(backend.get_block_signature(1).unwrap().is_none());
+        assert_eq!(backend.get_sequencer_public_key().unwrap().unwrap(), felt!("0x3"));
+
+        // Signing disabled.
+        backend.write_sequencer_public_key(None).unwrap();
+        assert!(backend.get_sequencer_public_key().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_revert_to() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        let (address, key) = (felt!("0x10"), felt!("0x20"));
+        let storage_diff = |value| StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address,
+                storage_entries: vec![StorageEntry { key, value }],
+            }],
+            ..Default::default()
+        };
+
+        backend.store_block(finalized_block_zero(Header::default()), storage_diff(felt!("0x1")), vec![]).unwrap();
+        let block_one = finalized_block_one();
+        backend.store_block(block_one.clone(), storage_diff(felt!("0x2")), vec![]).unwrap();
+        backend.store_block(pending_block_two(), pending_state_diff_two(), vec![]).unwrap();
+        assert_eq!(
+            backend.get_contract_storage_at(&BlockId::Tag(BlockTag::Latest), &address, &key).unwrap(),
+            Some(felt!("0x2"))
+        );
+
+        let reverted = backend.revert_to(0).unwrap();
+
+        assert_eq!(reverted, vec![storage_diff(felt!("0x2"))]);
+        assert_eq!(backend.get_latest_block_n().unwrap(), Some(0));
+        assert!(backend.get_block(&DbBlockId::Number(1)).unwrap().is_none());
+        assert!(backend.get_block_n(&BlockId::Hash(block_one.info.block_hash().unwrap())).unwrap().is_none());
+        assert!(backend.find_tx_hash_block_info(&block_one.info.tx_hashes()[0]).unwrap().is_none());
+        assert!(backend.get_block_info(&DbBlockId::Pending).unwrap().unwrap().tx_hashes().is_empty());
+        assert_eq!(
+            backend.get_contract_storage_at(&BlockId::Tag(BlockTag::Latest), &address, &key).unwrap(),
+            Some(felt!("0x1"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_latest_contract_storage() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        let address = felt!("0x10");
+        let state_diff = |entries: Vec<(Felt, Felt)>, nonces| StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address,
+                storage_entries: entries.into_iter().map(|(key, value)| StorageEntry { key, value }).collect(),
+            }],
+            nonces,
+            ..Default::default()
+        };
+
+        backend
+            .store_block(
+                finalized_block_zero(Header::default()),
+                state_diff(
+                    vec![(felt!("0x20"), felt!("0x1")), (felt!("0x21"), felt!("0x3"))],
+                    vec![NonceUpdate { contract_address: felt!("0x30"), nonce: felt!("0x1") }],
+                ),
+                vec![],
+            )
+            .unwrap();
+        backend
+            .store_block(finalized_block_one(), state_diff(vec![(felt!("0x20"), felt!("0x2"))], vec![]), vec![])
+            .unwrap();
+
+        assert_eq!(
+            backend.get_latest_contract_storage(&address).unwrap(),
+            vec![(felt!("0x20"), felt!("0x2")), (felt!("0x21"), felt!("0x3"))]
+        );
+        assert!(backend.get_latest_contract_storage(&felt!("0x30")).unwrap().is_empty());
+        assert_eq!(backend.get_contract_addresses().unwrap(), vec![address, felt!("0x30")]);
+    }
+
+    #[tokio::test]
+    async fn test_global_tries_disabled() {
+        let db = temp_db().await;
+        let backend = db.backend();
+
+        backend.set_global_tries_disabled(false).unwrap();
+        assert_eq!(backend.get_global_tries_disabled_since().unwrap(), None);
+
+        backend.store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![]).unwrap();
+        backend.set_global_tries_disabled(true).unwrap();
+        assert_eq!(backend.get_global_tries_disabled_since().unwrap(), Some(1));
+
+        backend.store_block(finalized_block_one(), StateDiff::default(), vec![]).unwrap();
+        backend.set_global_tries_disabled(true).unwrap();
+        assert_eq!(backend.get_global_tries_disabled_since().unwrap(), Some(1));
+        assert!(backend.set_global_tries_disabled(false).is_err());
+    }
+
     #[tokio::test]
     async fn test_store_block_transactions() {
         let db = temp_db().await;
@@ -0,0 +1,75 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use super::common::*;
+use crate::fork_db::ForkedState;
+use mp_block::{BlockId, BlockTag, Header};
+use mp_class::ConvertedClass;
+use starknet_api::felt;
+use starknet_types_core::felt::Felt;
+
+/// Contract `0x1` exists on the forked network, with `0x2` at every storage key.
+#[derive(Debug, Default)]
+struct MockForkedState {
+    n_requests: AtomicUsize,
+}
+
+impl MockForkedState {
+    fn contract(&self, contract_address: &Felt, value: Felt) -> anyhow::Result<Option<Felt>> {
+        self.n_requests.fetch_add(1, Ordering::SeqCst);
+        Ok((*contract_address == Felt::ONE).then_some(value))
+    }
+}
+
+impl ForkedState for MockForkedState {
+    fn fork_block_n(&self) -> u64 {
+        100
+    }
+    fn get_storage_at(&self, contract_address: &Felt, _key: &Felt) -> anyhow::Result<Option<Felt>> {
+        self.contract(contract_address, Felt::TWO)
+    }
+    fn get_nonce_at(&self, contract_address: &Felt) -> anyhow::Result<Option<Felt>> {
+        self.contract(contract_address, Felt::ZERO)
+    }
+    fn get_class_hash_at(&self, contract_address: &Felt) -> anyhow::Result<Option<Felt>> {
+        self.contract(contract_address, felt!("0x1234"))
+    }
+    fn get_class(&self, _class_hash: &Felt) -> anyhow::Result<Option<ConvertedClass>> {
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn test_fork_fallback() {
+    let db = temp_db::temp_db().await;
+    let backend = db.backend();
+    let forked = Arc::new(MockForkedState::default());
+    backend.set_forked_state(forked.clone()).unwrap();
+    assert_eq!(backend.get_fork_block_n().unwrap(), Some(100));
+
+    let latest = BlockId::Tag(BlockTag::Latest);
+    // No local block yet.
+    assert_eq!(backend.get_contract_storage_at(&latest, &Felt::ONE, &Felt::ONE).unwrap(), None);
+    assert_eq!(forked.n_requests.load(Ordering::SeqCst), 0);
+
+    backend.store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![]).unwrap();
+
+    assert_eq!(backend.get_contract_storage_at(&latest, &Felt::ONE, &Felt::ONE).unwrap(), Some(Felt::TWO));
+    assert_eq!(backend.get_contract_class_hash_at(&latest, &Felt::ONE).unwrap(), Some(felt!("0x1234")));
+    assert_eq!(backend.get_contract_nonce_at(&latest, &Felt::TWO).unwrap(), None);
+    assert_eq!(forked.n_requests.load(Ordering::SeqCst), 3);
+
+    // Cached, including the contracts that do not exist on the forked network.
+    assert_eq!(backend.get_contract_storage_at(&latest, &Felt::ONE, &Felt::ONE).unwrap(), Some(Felt::TWO));
+    assert_eq!(backend.get_contract_nonce_at(&latest, &Felt::TWO).unwrap(), None);
+    assert_eq!(forked.n_requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_fork_existing_chain() {
+    let db = temp_db::temp_db().await;
+    let backend = db.backend();
+    backend.store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![]).unwrap();
+
+    assert!(backend.set_forked_state(Arc::new(MockForkedState::default())).is_err());
+}
@@ -0,0 +1,81 @@
+use super::common::finalized_block_zero;
+use crate::maintenance::DbMaintenance;
+use crate::{Column, DatabaseService};
+use mc_metrics::MetricsRegistry;
+use mp_block::Header;
+use mp_chain_config::ChainConfig;
+use mp_state_update::{ContractStorageDiffItem, StateDiff, StorageEntry};
+use starknet_types_core::felt::Felt;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_export_import() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let chain_config = Arc::new(ChainConfig::madara_test());
+    drop(
+        DatabaseService::new(temp_dir.path(), None, false, Arc::clone(&chain_config), &MetricsRegistry::dummy())
+            .await
+            .unwrap(),
+    );
+
+    let db = DbMaintenance::open(temp_dir.path()).unwrap();
+    assert_eq!(db.info().unwrap().chain_id, Some(chain_config.chain_id.clone()));
+
+    let mut export = vec![];
+    assert_eq!(db.export(&[Column::BlockStorageMeta], &mut export).unwrap(), 1);
+
+    db.drop_column(Column::BlockStorageMeta).unwrap();
+    assert_eq!(db.info().unwrap().chain_id, None);
+
+    assert_eq!(db.import(export.as_slice()).unwrap(), 1);
+    assert_eq!(db.info().unwrap().chain_id, Some(chain_config.chain_id.clone()));
+}
+
+#[test]
+fn test_open_missing_db() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    assert!(DbMaintenance::open(temp_dir.path()).is_err());
+}
+
+#[tokio::test]
+async fn test_verify_tries() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let service = DatabaseService::new(
+        temp_dir.path(),
+        None,
+        false,
+        Arc::new(ChainConfig::madara_test()),
+        &MetricsRegistry::dummy(),
+    )
+    .await
+    .unwrap();
+    service.backend().store_block(finalized_block_zero(Header::default()), StateDiff::default(), vec![]).unwrap();
+    // The state root of block 1 is wrong, and the block does not update the stored tries.
+    let state_diff = StateDiff {
+        storage_diffs: vec![ContractStorageDiffItem {
+            address: Felt::from(0x100),
+            storage_entries: vec![StorageEntry { key: Felt::ONE, value: Felt::TWO }],
+        }],
+        ..Default::default()
+    };
+    let header = Header { block_number: 1, global_state_root: Felt::ONE, ..Default::default() };
+    service.backend().store_block(finalized_block_zero(header), state_diff, vec![]).unwrap();
+    drop(service);
+
+    let db = DbMaintenance::open(temp_dir.path()).unwrap();
+
+    let verification = db.verify_tries(Some(0)).unwrap();
+    assert_eq!((verification.n_contracts, verification.state_root), (0, Felt::ZERO));
+    assert!(verification.is_consistent());
+
+    let verification = db.verify_tries(None).unwrap();
+    assert_eq!(verification.block_n, 1);
+    assert_eq!(verification.n_contracts, 1);
+    assert_ne!(verification.state_root, verification.expected_state_root);
+    let divergent_contract = verification.divergent_contract.unwrap();
+    assert_eq!(divergent_contract.contract_address, Felt::from(0x100));
+    assert_eq!(divergent_contract.stored_leaf_hash, None);
+    assert!(!verification.is_consistent());
+
+    assert!(db.verify_tries(Some(2)).is_err());
+}
@@ -1,6 +1,8 @@
 use super::common::*;
-use crate::DatabaseService;
+use crate::migration::{set_db_version, DB_VERSION, ROW_DB_VERSION};
+use crate::{BlockRevert, Column, DatabaseExt, DatabaseService};
 use mc_metrics::MetricsRegistry;
+use mp_block::Header;
 use mp_chain_config::ChainConfig;
 
 #[tokio::test]
@@ -19,3 +21,79 @@ async fn test_open_different_chain_id() {
     let chain_config = std::sync::Arc::new(ChainConfig::madara_test());
     assert!(DatabaseService::new(temp_dir.path(), None, false, chain_config, &MetricsRegistry::dummy()).await.is_err());
 }
+
+#[tokio::test]
+async fn test_open_different_chain_config() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    {
+        let chain_config = std::sync::Arc::new(ChainConfig::madara_test());
+        let _db =
+            DatabaseService::new(temp_dir.path(), None, false, chain_config, &MetricsRegistry::dummy()).await.unwrap();
+    }
+    {
+        // Parameters the stored chain does not depend on may change.
+        let mut chain_config = ChainConfig::madara_test();
+        chain_config.block_time *= 2;
+        let _db = DatabaseService::new(temp_dir.path(), None, false, chain_config.into(), &MetricsRegistry::dummy())
+            .await
+            .unwrap();
+    }
+    let mut chain_config = ChainConfig::madara_test();
+    chain_config.parent_fee_token_address = chain_config.native_fee_token_address;
+    let err = DatabaseService::new(temp_dir.path(), None, false, chain_config.into(), &MetricsRegistry::dummy())
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("different chain config"), "{err:#}");
+}
+
+#[tokio::test]
+async fn test_open_db_version() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let chain_config = std::sync::Arc::new(ChainConfig::madara_test());
+    {
+        let db = DatabaseService::new(temp_dir.path(), None, false, chain_config.clone(), &MetricsRegistry::dummy())
+            .await
+            .unwrap();
+        assert_eq!(db.backend().get_db_version().unwrap(), Some(DB_VERSION));
+        // Database created before versioning.
+        let col = db.backend().db.get_column(Column::BlockStorageMeta);
+        db.backend().db.delete_cf(&col, ROW_DB_VERSION).unwrap();
+    }
+    {
+        let db = DatabaseService::new(temp_dir.path(), None, false, chain_config.clone(), &MetricsRegistry::dummy())
+            .await
+            .unwrap();
+        assert_eq!(db.backend().get_db_version().unwrap(), Some(DB_VERSION));
+        set_db_version(&db.backend().db, DB_VERSION + 1).unwrap();
+    }
+    assert!(DatabaseService::new(temp_dir.path(), None, false, chain_config, &MetricsRegistry::dummy()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_block_revert_subscription() {
+    let db = temp_db::temp_db().await;
+    let mut reverts = db.backend().subscribe_block_reverts();
+
+    db.backend().notify_block_revert(BlockRevert { from: 10, to: 7 });
+
+    assert_eq!(reverts.recv().await.unwrap(), BlockRevert { from: 10, to: 7 });
+}
+
+#[tokio::test]
+async fn test_new_block_subscriptions() {
+    let db = temp_db::temp_db().await;
+    let backend = db.backend();
+    let mut closed_blocks = backend.subscribe_closed_blocks();
+    let mut pending_block_updates = backend.subscribe_pending_block_updates();
+
+    backend.store_block(pending_block_one(), pending_state_diff_one(), vec![]).unwrap();
+    assert!(!closed_blocks.has_changed().unwrap());
+    assert!(pending_block_updates.has_changed().unwrap());
+    pending_block_updates.borrow_and_update();
+
+    backend.store_block(finalized_block_zero(Header::default()), finalized_state_diff_zero(), vec![]).unwrap();
+    assert!(closed_blocks.has_changed().unwrap());
+    assert_eq!(*closed_blocks.borrow_and_update(), Some(0));
+    assert!(!pending_block_updates.has_changed().unwrap());
+}
@@ -0,0 +1,27 @@
+use super::common::*;
+use crate::proving_db::{ProvingJob, ProvingJobStatus};
+
+fn job(job_id: &str, status: ProvingJobStatus) -> ProvingJob {
+    ProvingJob { job_id: job_id.into(), status, submitted_at: 0, attempts: 1 }
+}
+
+#[tokio::test]
+async fn test_proving_jobs() {
+    let db = temp_db::temp_db().await;
+    let backend = db.backend();
+    assert_eq!(backend.get_last_proving_job().unwrap(), None);
+
+    // Keys are big-endian, so that block 256 comes after block 2.
+    for block_n in [2, 256, 0] {
+        backend.write_proving_job(block_n, &job(&format!("job-{block_n}"), ProvingJobStatus::InProgress)).unwrap();
+    }
+    let proven = job("job-2", ProvingJobStatus::Proven { fact: Some([1; 32]) });
+    backend.write_proving_job(2, &proven).unwrap();
+
+    assert_eq!(backend.get_proving_job(2).unwrap(), Some(proven.clone()));
+    assert_eq!(backend.get_proving_job(1).unwrap(), None);
+    let blocks: Vec<_> = backend.get_proving_jobs(1, 10).unwrap().into_iter().map(|(block_n, _)| block_n).collect();
+    assert_eq!(blocks, [2, 256]);
+    assert_eq!(backend.get_proving_jobs(0, 2).unwrap()[1], (2, proven));
+    assert_eq!(backend.get_last_proving_job().unwrap().map(|(block_n, _)| block_n), Some(256));
+}
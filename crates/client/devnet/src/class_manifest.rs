@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::InitiallyDeclaredClass;
+
+/// Classes declared in the devnet genesis block, in addition to the ones of the predeployed contracts. This saves
+/// the tests from declaring the classes they deploy.
+///
+/// Read from a JSON file:
+/// ```json
+/// {
+///     "common_classes": true,
+///     "classes": ["target/dev/my_contracts_Token.contract_class.json"]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassManifest {
+    /// Declare the common classes shipped with Madara: the OpenZeppelin account, ERC20 and ERC721 presets and the
+    /// Cairo 1 UDC.
+    #[serde(default = "default_common_classes")]
+    pub common_classes: bool,
+    /// Class definitions, Sierra (`.contract_class.json`) or legacy (compiled Cairo 0). Relative paths are resolved
+    /// from the directory of the manifest.
+    #[serde(default)]
+    pub classes: Vec<PathBuf>,
+}
+
+fn default_common_classes() -> bool {
+    true
+}
+
+impl Default for ClassManifest {
+    fn default() -> Self {
+        Self { common_classes: default_common_classes(), classes: vec![] }
+    }
+}
+
+impl ClassManifest {
+    pub fn read_file(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read(path).with_context(|| format!("Reading class manifest {}", path.display()))?;
+        let mut manifest: Self =
+            serde_json::from_slice(&content).with_context(|| format!("Parsing class manifest {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for class in &mut manifest.classes {
+            *class = dir.join(&*class);
+        }
+        Ok(manifest)
+    }
+
+    /// Load the class definitions listed in the manifest, without the common classes.
+    pub fn load_classes(&self) -> anyhow::Result<Vec<InitiallyDeclaredClass>> {
+        self.classes
+            .iter()
+            .map(|path| {
+                let definition = fs::read(path).with_context(|| format!("Reading class {}", path.display()))?;
+                InitiallyDeclaredClass::new(definition).with_context(|| format!("Loading class {}", path.display()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_class_manifest_fields() {
+        let manifest: ClassManifest = serde_json::from_str(r#"{ "classes": ["token.contract_class.json"] }"#).unwrap();
+        assert_eq!(
+            manifest,
+            ClassManifest { common_classes: true, classes: vec![PathBuf::from("token.contract_class.json")] }
+        );
+        assert!(serde_json::from_str::<ClassManifest>(r#"{ "class": [] }"#).is_err());
+    }
+}
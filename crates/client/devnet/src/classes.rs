@@ -26,6 +26,17 @@ pub enum InitiallyDeclaredClass {
 }
 
 impl InitiallyDeclaredClass {
+    /// Sierra or legacy class, depending on the definition.
+    pub fn new(definition: impl Into<Vec<u8>>) -> anyhow::Result<Self> {
+        let definition = definition.into();
+        let value: serde_json::Value = serde_json::from_slice(&definition).context("Deserializing class")?;
+        if value.get("sierra_program").is_some() {
+            Self::new_sierra(definition)
+        } else {
+            Self::new_legacy(definition)
+        }
+    }
+
     pub fn new_sierra(definition: impl Into<Vec<u8>>) -> anyhow::Result<Self> {
         let class = serde_json::from_slice::<SierraClass>(&definition.into())
             .with_context(|| "Deserializing sierra class".to_string())?;
@@ -72,6 +83,10 @@ impl InitiallyDeclaredClasses {
         self.0.insert(class.class_hash(), class);
     }
 
+    pub fn contains(&self, class_hash: &Felt) -> bool {
+        self.0.contains_key(class_hash)
+    }
+
     pub fn as_state_diff(&self) -> Vec<DeclaredClassItem> {
         self.0
             .iter()
@@ -0,0 +1,156 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use mc_block_import::{
+    BlockImporter, BlockValidationContext, DeclaredClass, LegacyDeclaredClass, SierraDeclaredClass,
+    UnverifiedCommitments, UnverifiedFullBlock, UnverifiedHeader,
+};
+use mc_db::db_block_id::DbBlockId;
+use mc_db::devnet_db::DevnetPredeployedKeys;
+use mc_db::MadaraBackend;
+use mp_block::MadaraBlock;
+use mp_class::ClassInfo;
+use serde::{Deserialize, Serialize};
+
+/// The closed blocks of a devnet and its predeployed accounts, which can be loaded in another devnet to reproduce the
+/// same chain. The pending block is not part of the dump.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DevnetDump {
+    pub keys: DevnetPredeployedKeys,
+    pub blocks: Vec<UnverifiedFullBlock>,
+}
+
+impl DevnetDump {
+    pub fn from_db(backend: &MadaraBackend) -> anyhow::Result<Self> {
+        let keys = backend
+            .get_devnet_predeployed_keys()
+            .context("Getting the devnet predeployed keys from db")?
+            .context("The current database was not initialized in devnet mode")?;
+        let latest_block_n = backend.get_latest_block_n().context("Getting the latest block number in db")?;
+
+        let blocks = (0..latest_block_n.map_or(0, |n| n + 1))
+            .map(|block_n| block_from_db(backend, block_n))
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self { keys, blocks })
+    }
+
+    pub fn read_file(path: &Path) -> anyhow::Result<Self> {
+        let file = File::open(path).with_context(|| format!("Opening devnet dump {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file)).with_context(|| format!("Reading devnet dump {}", path.display()))
+    }
+
+    pub fn write_file(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::create(path).with_context(|| format!("Creating devnet dump {}", path.display()))?;
+        serde_json::to_writer(BufWriter::new(file), self)
+            .with_context(|| format!("Writing devnet dump {}", path.display()))
+    }
+
+    /// Blocks of the dump that are not in the database. The chain in the database must be the beginning of the
+    /// dumped chain: the dump can be loaded in an empty database, or in the devnet it was taken from.
+    pub fn new_blocks(&self, backend: &MadaraBackend) -> anyhow::Result<Vec<UnverifiedFullBlock>> {
+        let n_local_blocks =
+            backend.get_latest_block_n().context("Getting the latest block number in db")?.map_or(0, |n| n + 1);
+        anyhow::ensure!(
+            n_local_blocks as usize <= self.blocks.len(),
+            "The database has {n_local_blocks} blocks, more than the {} blocks of the dump",
+            self.blocks.len()
+        );
+
+        for (block_n, block) in self.blocks.iter().enumerate().take(n_local_blocks as usize) {
+            let local_hash = backend
+                .get_block_hash(&DbBlockId::Number(block_n as u64))
+                .context("Getting block hash from db")?
+                .with_context(|| format!("Block #{block_n} not found in db"))?;
+            anyhow::ensure!(
+                block.commitments.block_hash == Some(local_hash),
+                "Block #{block_n} of the dump is not the one in the database: the dump was taken from another chain"
+            );
+        }
+
+        Ok(self.blocks[n_local_blocks as usize..].to_vec())
+    }
+
+    /// Import the blocks of the dump that are not in the database yet. Must not be called while the block production
+    /// is running.
+    pub async fn load(&self, backend: &MadaraBackend, importer: &BlockImporter) -> anyhow::Result<()> {
+        let blocks = self.new_blocks(backend)?;
+        let validation = BlockValidationContext::new(backend.chain_config().chain_id.clone())
+            .trust_transaction_hashes(true)
+            .trust_class_hashes(true);
+        for block in blocks {
+            let block_n = block.unverified_block_number;
+            importer
+                .add_block(block, validation.clone())
+                .await
+                .with_context(|| format!("Importing block #{block_n:?} of the devnet dump"))?;
+        }
+
+        if backend.get_devnet_predeployed_keys().context("Getting the devnet predeployed keys from db")?.is_none() {
+            backend
+                .set_devnet_predeployed_keys(self.keys.clone())
+                .context("Saving devnet predeployed contracts keys to database")?;
+        }
+        Ok(())
+    }
+}
+
+fn block_from_db(backend: &MadaraBackend, block_n: u64) -> anyhow::Result<UnverifiedFullBlock> {
+    let id = DbBlockId::Number(block_n);
+    let block = backend
+        .get_block(&id)
+        .context("Getting block from db")?
+        .with_context(|| format!("Block #{block_n} not found in db"))?;
+    let MadaraBlock { info, inner } = MadaraBlock::try_from(block).context("Dumping a pending block")?;
+    let state_diff = backend
+        .get_block_state_diff(&id)
+        .context("Getting block state diff from db")?
+        .with_context(|| format!("State diff of block #{block_n} not found in db"))?;
+
+    let declared_classes = state_diff
+        .declared_classes
+        .iter()
+        .map(|item| item.class_hash)
+        .chain(state_diff.deprecated_declared_classes.iter().copied())
+        .map(|class_hash| {
+            let class_info = backend
+                .get_class_info(&id, &class_hash)
+                .context("Getting class info from db")?
+                .with_context(|| format!("Class {class_hash:#x} of block #{block_n} not found in db"))?;
+            Ok(match class_info {
+                ClassInfo::Sierra(info) => DeclaredClass::Sierra(SierraDeclaredClass {
+                    class_hash,
+                    contract_class: Arc::unwrap_or_clone(info.contract_class),
+                    compiled_class_hash: info.compiled_class_hash,
+                }),
+                ClassInfo::Legacy(info) => DeclaredClass::Legacy(LegacyDeclaredClass {
+                    class_hash,
+                    contract_class: Arc::unwrap_or_clone(info.contract_class),
+                }),
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let header = info.header;
+    Ok(UnverifiedFullBlock {
+        unverified_block_number: Some(block_n),
+        header: UnverifiedHeader {
+            parent_block_hash: Some(header.parent_block_hash),
+            sequencer_address: header.sequencer_address,
+            block_timestamp: header.block_timestamp,
+            protocol_version: header.protocol_version,
+            l1_gas_price: header.l1_gas_price,
+            l1_da_mode: header.l1_da_mode,
+        },
+        state_diff,
+        transactions: inner.transactions,
+        receipts: inner.receipts,
+        declared_classes,
+        // The imported blocks must have the same hashes as the dumped ones.
+        commitments: UnverifiedCommitments { block_hash: Some(info.block_hash), ..Default::default() },
+        ..Default::default()
+    })
+}
@@ -0,0 +1,147 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{mpsc as std_mpsc, Arc};
+
+use anyhow::Context;
+use mc_db::fork_db::ForkedState;
+use mp_class::{
+    ContractClass, ConvertedClass, LegacyClassInfo, LegacyConvertedClass, SierraClassInfo, SierraConvertedClass,
+};
+use starknet_core::types::{BlockId, StarknetError};
+use starknet_providers::jsonrpc::HttpTransport;
+use starknet_providers::{JsonRpcClient, Provider, ProviderError};
+use starknet_types_core::felt::Felt;
+use tokio::sync::mpsc;
+use url::Url;
+
+type Client = Arc<JsonRpcClient<HttpTransport>>;
+type Job = Box<dyn FnOnce(Client) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// State of a live network fetched through its RPC, used as the initial state of a forked devnet.
+///
+/// The state is read from blocking contexts (blockifier state reads), so the requests are made by a dedicated thread
+/// with its own runtime.
+#[derive(Debug)]
+pub struct ForkedNetwork {
+    url: Url,
+    fork_block_n: u64,
+    jobs: mpsc::UnboundedSender<Job>,
+}
+
+impl ForkedNetwork {
+    /// Forks the network at `fork_block_n`, or at its latest block.
+    pub async fn connect(url: Url, fork_block_n: Option<u64>) -> anyhow::Result<Self> {
+        let client = Arc::new(JsonRpcClient::new(HttpTransport::new(url.clone())));
+        let latest_block_n = client
+            .block_number()
+            .await
+            .with_context(|| format!("Getting the latest block of the fork network {url}"))?;
+        let fork_block_n = fork_block_n.unwrap_or(latest_block_n);
+        anyhow::ensure!(
+            fork_block_n <= latest_block_n,
+            "Cannot fork at block {fork_block_n}: the latest block of the fork network is {latest_block_n}"
+        );
+
+        let (jobs, mut receiver) = mpsc::unbounded_channel::<Job>();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Building the fork network runtime")?;
+        std::thread::Builder::new()
+            .name("fork-rpc".into())
+            .spawn(move || {
+                runtime.block_on(async move {
+                    while let Some(job) = receiver.recv().await {
+                        tokio::spawn(job(Arc::clone(&client)));
+                    }
+                })
+            })
+            .context("Spawning the fork network thread")?;
+
+        Ok(Self { url, fork_block_n, jobs })
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Runs the request on the fork thread and blocks until it returns. On a tokio worker thread, the worker is handed
+    /// over to the other tasks while blocking.
+    fn call<T: Send + 'static, Fut: Future<Output = Result<T, ProviderError>> + Send + 'static>(
+        &self,
+        request: impl FnOnce(Client, BlockId) -> Fut + Send + 'static,
+    ) -> anyhow::Result<Option<T>> {
+        let block_id = BlockId::Number(self.fork_block_n);
+        let (sender, receiver) = std_mpsc::sync_channel(1);
+        self.jobs
+            .send(Box::new(move |client| {
+                Box::pin(async move {
+                    let _ = sender.send(request(client, block_id).await);
+                })
+            }))
+            .map_err(|_| anyhow::anyhow!("The fork network thread stopped"))?;
+
+        let response = match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| receiver.recv())
+            }
+            _ => receiver.recv(),
+        };
+
+        match response.context("The fork network thread stopped")? {
+            Ok(res) => Ok(Some(res)),
+            Err(ProviderError::StarknetError(StarknetError::ContractNotFound | StarknetError::ClassHashNotFound)) => {
+                Ok(None)
+            }
+            Err(err) => Err(err).with_context(|| format!("Querying the fork network {}", self.url)),
+        }
+    }
+}
+
+impl ForkedState for ForkedNetwork {
+    fn fork_block_n(&self) -> u64 {
+        self.fork_block_n
+    }
+
+    fn get_storage_at(&self, contract_address: &Felt, key: &Felt) -> anyhow::Result<Option<Felt>> {
+        let (contract_address, key) = (*contract_address, *key);
+        self.call(move |client, block_id| async move { client.get_storage_at(contract_address, key, block_id).await })
+    }
+
+    fn get_nonce_at(&self, contract_address: &Felt) -> anyhow::Result<Option<Felt>> {
+        let contract_address = *contract_address;
+        self.call(move |client, block_id| async move { client.get_nonce(block_id, contract_address).await })
+    }
+
+    fn get_class_hash_at(&self, contract_address: &Felt) -> anyhow::Result<Option<Felt>> {
+        let contract_address = *contract_address;
+        self.call(move |client, block_id| async move { client.get_class_hash_at(block_id, contract_address).await })
+    }
+
+    fn get_class(&self, class_hash: &Felt) -> anyhow::Result<Option<ConvertedClass>> {
+        let class_hash = *class_hash;
+        let Some(class) =
+            self.call(move |client, block_id| async move { client.get_class(block_id, class_hash).await })?
+        else {
+            return Ok(None);
+        };
+
+        let class = match ContractClass::from(class) {
+            ContractClass::Sierra(sierra) => {
+                log::debug!("Compiling the forked class {class_hash:#x}");
+                let (compiled_class_hash, compiled) =
+                    sierra.compile_to_casm().with_context(|| format!("Compiling the forked class {class_hash:#x}"))?;
+                ConvertedClass::Sierra(SierraConvertedClass {
+                    class_hash,
+                    info: SierraClassInfo { contract_class: sierra, compiled_class_hash },
+                    compiled: Arc::new(compiled),
+                })
+            }
+            ContractClass::Legacy(legacy) => ConvertedClass::Legacy(LegacyConvertedClass {
+                class_hash,
+                info: LegacyClassInfo { contract_class: legacy },
+            }),
+        };
+        Ok(Some(class))
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use mc_block_import::UnverifiedFullBlock;
+use mp_chain_config::ChainConfig;
+use serde::Deserialize;
+use starknet_api::core::ContractAddress;
+use starknet_api::state::StorageKey;
+use starknet_types_core::felt::Felt;
+
+use crate::{ChainGenesisDescription, ClassManifest, ContractFeeTokensBalance};
+
+/// Genesis block of a chain, built by the sequencer at first startup. The same specification always gives the same
+/// block.
+///
+/// Read from a JSON file:
+/// ```json
+/// {
+///     "timestamp": 1700000000,
+///     "classes": ["target/dev/my_contracts_Account.contract_class.json"],
+///     "contracts": [{
+///         "address": "0x1234",
+///         "class_hash": "0x5678",
+///         "storage": { "0x1": "0x2" }
+///     }],
+///     "storage": { "0x4321": { "0x1": "0x2" } },
+///     "balances": { "0x1234": { "strk": "0xde0b6b3a7640000", "eth": "0x0" } }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisSpec {
+    /// Timestamp of the genesis block, in seconds since the UNIX epoch. Defaults to the `genesis_timestamp` of the
+    /// chain config, or to 0.
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    /// Declare and deploy the UDC at its well known address, and the two fee token contracts at the addresses of the
+    /// chain config.
+    #[serde(default = "default_true")]
+    pub base_contracts: bool,
+    /// Declare the common classes shipped with Madara, see [`ClassManifest::common_classes`].
+    #[serde(default)]
+    pub common_classes: bool,
+    /// Class definitions to declare. Relative paths are resolved from the directory of the specification.
+    #[serde(default)]
+    pub classes: Vec<PathBuf>,
+    /// Contracts to deploy. Their class must be declared in the genesis block.
+    #[serde(default)]
+    pub contracts: Vec<GenesisContract>,
+    /// Storage writes, by contract address and storage key.
+    #[serde(default)]
+    pub storage: BTreeMap<Felt, BTreeMap<Felt, Felt>>,
+    /// Fee token balances, by contract address.
+    #[serde(default)]
+    pub balances: BTreeMap<Felt, GenesisBalance>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisContract {
+    pub address: Felt,
+    pub class_hash: Felt,
+    #[serde(default)]
+    pub storage: BTreeMap<Felt, Felt>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisBalance {
+    /// Balance in the native fee token (STRK by default), in base units.
+    #[serde(default)]
+    pub strk: Felt,
+    /// Balance in the parent fee token (ETH by default), in base units.
+    #[serde(default)]
+    pub eth: Felt,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl GenesisSpec {
+    pub fn read_file(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read(path).with_context(|| format!("Reading genesis specification {}", path.display()))?;
+        let mut spec: Self = serde_json::from_slice(&content)
+            .with_context(|| format!("Parsing genesis specification {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for class in &mut spec.classes {
+            *class = dir.join(&*class);
+        }
+        Ok(spec)
+    }
+
+    pub fn description(&self, chain_config: &ChainConfig) -> anyhow::Result<ChainGenesisDescription> {
+        let mut genesis = if self.base_contracts {
+            ChainGenesisDescription::base_config(chain_config).context("Failed to create base genesis config")?
+        } else {
+            ChainGenesisDescription::default()
+        };
+        genesis
+            .add_classes(&ClassManifest { common_classes: self.common_classes, classes: self.classes.clone() })
+            .context("Failed to add genesis classes")?;
+
+        for contract in &self.contracts {
+            anyhow::ensure!(
+                genesis.declared_classes.contains(&contract.class_hash),
+                "Class {:#x} of contract {:#x} is not declared in the genesis block",
+                contract.class_hash,
+                contract.address
+            );
+            genesis.deployed_contracts.insert(contract.address, contract.class_hash);
+            write_storage(&mut genesis, contract.address, &contract.storage)?;
+        }
+        for (&address, storage) in &self.storage {
+            write_storage(&mut genesis, address, storage)?;
+        }
+        for (&address, balance) in &self.balances {
+            genesis
+                .initial_balances
+                .insert(contract_address(address)?, ContractFeeTokensBalance { fri: balance.strk, wei: balance.eth });
+        }
+        Ok(genesis)
+    }
+
+    pub fn build(&self, chain_config: &ChainConfig) -> anyhow::Result<UnverifiedFullBlock> {
+        let mut block = self.description(chain_config)?.build(chain_config)?;
+        block.header.block_timestamp = self.timestamp.or(chain_config.genesis_timestamp).unwrap_or(0);
+        block.state_diff.sort();
+        Ok(block)
+    }
+}
+
+fn contract_address(address: Felt) -> anyhow::Result<ContractAddress> {
+    ContractAddress::try_from(address).with_context(|| format!("Invalid contract address {address:#x}"))
+}
+
+fn write_storage(
+    genesis: &mut ChainGenesisDescription,
+    address: Felt,
+    storage: &BTreeMap<Felt, Felt>,
+) -> anyhow::Result<()> {
+    let contract_storage = genesis.initial_storage.contract_mut(contract_address(address)?);
+    for (&key, &value) in storage {
+        let key = key.try_into().with_context(|| format!("Invalid storage key {key:#x}"))?;
+        contract_storage.insert(StorageKey(key), value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_spec_deterministic() {
+        let spec: GenesisSpec = serde_json::from_str(
+            r#"{
+                "storage": { "0x4321": { "0x1": "0x2", "0x3": "0x4" } },
+                "balances": { "0x1234": { "strk": "0x10" } }
+            }"#,
+        )
+        .unwrap();
+        let chain_config = ChainConfig::madara_devnet();
+
+        let block = spec.build(&chain_config).unwrap();
+        assert_eq!(block.header.block_timestamp, 0);
+        assert_eq!(block.state_diff, spec.build(&chain_config).unwrap().state_diff);
+        assert_eq!(block.state_diff.deployed_contracts.len(), 3);
+    }
+
+    #[test]
+    fn test_genesis_spec_undeclared_class() {
+        let spec: GenesisSpec =
+            serde_json::from_str(r#"{ "contracts": [{ "address": "0x1234", "class_hash": "0x5678" }] }"#).unwrap();
+        assert!(spec.description(&ChainConfig::madara_devnet()).is_err());
+        assert!(serde_json::from_str::<GenesisSpec>(r#"{ "contract": [] }"#).is_err());
+    }
+}
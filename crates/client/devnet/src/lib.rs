@@ -2,7 +2,7 @@ use anyhow::Context;
 use blockifier::abi::abi_utils::get_storage_var_address;
 use mc_block_import::{UnverifiedFullBlock, UnverifiedHeader};
 use mp_block::header::GasPrices;
-use mp_chain_config::ChainConfig;
+use mp_chain_config::{ChainConfig, FeeToken};
 use mp_convert::ToFelt;
 use mp_state_update::{ContractStorageDiffItem, StateDiff, StorageEntry};
 use rand::rngs::StdRng;
@@ -13,22 +13,28 @@ use starknet_types_core::felt::Felt;
 use std::{collections::HashMap, time::SystemTime};
 
 mod balances;
+mod class_manifest;
 mod classes;
 mod contracts;
+mod dump;
 mod entrypoint;
+mod fork;
+mod genesis_spec;
 mod predeployed_contracts;
 
 pub use balances::*;
+pub use class_manifest::*;
 pub use classes::*;
 pub use contracts::*;
+pub use dump::*;
 pub use entrypoint::*;
+pub use fork::*;
+pub use genesis_spec::*;
 use mp_transactions::compute_hash::calculate_contract_address;
 pub use predeployed_contracts::*;
 
-// 1 ETH = 1e18 WEI
-const ETH_WEI_DECIMALS: u128 = 1_000_000_000_000_000_000;
-// 1 STRK = 1e18 FRI
-const STRK_FRI_DECIMALS: u128 = 1_000_000_000_000_000_000;
+/// Balance of the devnet accounts in each fee token, in whole tokens.
+const DEVNET_ACCOUNT_BALANCE: u128 = 10_000;
 
 #[derive(Debug, Clone, Default)]
 pub struct StorageDiffs(HashMap<ContractAddress, HashMap<StorageKey, Felt>>);
@@ -48,7 +54,8 @@ impl StorageDiffs {
     }
 }
 
-// We allow ourselves to lie about the contract_address. This is because we want the UDC and the two ERC20 contracts to have well known addresses on every chain.
+// We allow ourselves to lie about the contract_address. This is because we want the UDC to have a well known address on
+// every chain. The two ERC20 contracts are deployed at the fee token addresses of the chain config.
 
 /// Universal Deployer Contract.
 const UDC_CLASS_DEFINITION: &[u8] = include_bytes!("../../../../cairo_0/madara_contracts_UDC.json");
@@ -57,14 +64,16 @@ const UDC_CONTRACT_ADDRESS: Felt =
 
 const ERC20_CLASS_DEFINITION: &[u8] =
     include_bytes!("../../../../cairo/target/dev/madara_contracts_ERC20.contract_class.json");
-const ERC20_STRK_CONTRACT_ADDRESS: Felt =
-    Felt::from_hex_unchecked("0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d");
-const ERC20_ETH_CONTRACT_ADDRESS: Felt =
-    Felt::from_hex_unchecked("0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7");
 
 const ACCOUNT_CLASS_DEFINITION: &[u8] =
     include_bytes!("../../../../cairo/target/dev/madara_contracts_AccountUpgradeable.contract_class.json");
 
+const ERC721_CLASS_DEFINITION: &[u8] =
+    include_bytes!("../../../../cairo/target/dev/madara_contracts_ERC721.contract_class.json");
+/// The Cairo 1 UDC. The deployed UDC is the Cairo 0 one, which has the well known address.
+const UDC_SIERRA_CLASS_DEFINITION: &[u8] =
+    include_bytes!("../../../../cairo/target/dev/madara_contracts_UniversalDeployer.contract_class.json");
+
 /// High level description of the genesis block.
 #[derive(Clone, Debug, Default)]
 pub struct ChainGenesisDescription {
@@ -76,7 +85,7 @@ pub struct ChainGenesisDescription {
 }
 
 impl ChainGenesisDescription {
-    pub fn base_config() -> anyhow::Result<Self> {
+    pub fn base_config(chain_config: &ChainConfig) -> anyhow::Result<Self> {
         let udc_class = InitiallyDeclaredClass::new_legacy(UDC_CLASS_DEFINITION).context("Failed to add UDC class")?;
         let erc20_class =
             InitiallyDeclaredClass::new_sierra(ERC20_CLASS_DEFINITION).context("Failed to add ERC20 class")?;
@@ -84,14 +93,40 @@ impl ChainGenesisDescription {
             initial_balances: InitialBalances::default(),
             deployed_contracts: InitiallyDeployedContracts::default()
                 .with(UDC_CONTRACT_ADDRESS, udc_class.class_hash())
-                .with(ERC20_ETH_CONTRACT_ADDRESS, erc20_class.class_hash())
-                .with(ERC20_STRK_CONTRACT_ADDRESS, erc20_class.class_hash()),
+                .with(chain_config.parent_fee_token_address.to_felt(), erc20_class.class_hash())
+                .with(chain_config.native_fee_token_address.to_felt(), erc20_class.class_hash()),
             declared_classes: InitiallyDeclaredClasses::default().with(udc_class).with(erc20_class),
             initial_storage: StorageDiffs::default(),
         })
     }
 
-    pub fn add_devnet_contracts(&mut self, n_addr: u64) -> anyhow::Result<DevnetKeys> {
+    /// Genesis of a devnet forked from a live network, which already has the UDC and the fee token contracts.
+    pub fn fork_config() -> Self {
+        Self::default()
+    }
+
+    /// Declare the classes of the manifest in the genesis block.
+    pub fn add_classes(&mut self, manifest: &ClassManifest) -> anyhow::Result<()> {
+        if manifest.common_classes {
+            let common_classes = [
+                ("account", ACCOUNT_CLASS_DEFINITION),
+                ("ERC20", ERC20_CLASS_DEFINITION),
+                ("ERC721", ERC721_CLASS_DEFINITION),
+                ("UDC", UDC_SIERRA_CLASS_DEFINITION),
+            ];
+            for (name, definition) in common_classes {
+                let class = InitiallyDeclaredClass::new_sierra(definition)
+                    .with_context(|| format!("Failed to add {name} class"))?;
+                self.declared_classes.insert(class);
+            }
+        }
+        for class in manifest.load_classes()? {
+            self.declared_classes.insert(class);
+        }
+        Ok(())
+    }
+
+    pub fn add_devnet_contracts(&mut self, n_addr: u64, chain_config: &ChainConfig) -> anyhow::Result<DevnetKeys> {
         let account_class =
             InitiallyDeclaredClass::new_sierra(ACCOUNT_CLASS_DEFINITION).context("Failed to add account class")?;
         let account_class_hash = account_class.class_hash();
@@ -110,6 +145,16 @@ impl ChainGenesisDescription {
             Felt::from_bytes_be_slice(&buffer)
         }
 
+        let balance_of = |token: &FeeToken| {
+            token.base_units(DEVNET_ACCOUNT_BALANCE).with_context(|| {
+                format!("The devnet balance of {DEVNET_ACCOUNT_BALANCE} {} overflows a u128", token.symbol)
+            })
+        };
+        let balance = ContractFeeTokensBalance {
+            fri: balance_of(&chain_config.native_fee_token)?.into(),
+            wei: balance_of(&chain_config.parent_fee_token)?.into(),
+        };
+
         Ok(DevnetKeys(
             (0..n_addr)
                 .map(|addr_idx| {
@@ -121,11 +166,6 @@ impl ChainGenesisDescription {
                     let calculated_address =
                         calculate_contract_address(Felt::ZERO, account_class_hash, &[pubkey.scalar()], Felt::ZERO);
 
-                    let balance = ContractFeeTokensBalance {
-                        fri: (10_000 * ETH_WEI_DECIMALS).into(),
-                        wei: (10_000 * STRK_FRI_DECIMALS).into(),
-                    };
-
                     self.deployed_contracts.insert(calculated_address, account_class_hash);
                     self.initial_balances
                         .insert(ContractAddress::try_from(calculated_address).unwrap(), balance.clone());
@@ -136,7 +176,7 @@ impl ChainGenesisDescription {
                     DevnetPredeployedContract {
                         secret: key,
                         pubkey: pubkey.scalar(),
-                        balance,
+                        balance: balance.clone(),
                         address: calculated_address,
                         class_hash: account_class_hash,
                     }
@@ -152,11 +192,13 @@ impl ChainGenesisDescription {
             header: UnverifiedHeader {
                 parent_block_hash: Some(Felt::ZERO),
                 sequencer_address: chain_config.sequencer_address.to_felt(),
-                block_timestamp: SystemTime::now()
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .expect("Current time is before unix epoch!")
-                    .as_secs(),
-                protocol_version: chain_config.latest_protocol_version,
+                block_timestamp: chain_config.genesis_timestamp.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .expect("Current time is before unix epoch!")
+                        .as_secs()
+                }),
+                protocol_version: chain_config.protocol_version_at(0),
                 l1_gas_price: GasPrices {
                     eth_l1_gas_price: 5,
                     strk_l1_gas_price: 5,
@@ -188,7 +230,10 @@ mod tests {
     use mc_db::MadaraBackend;
     use mc_mempool::block_production::BlockProductionTask;
     use mc_mempool::MempoolProvider;
-    use mc_mempool::{transaction_hash, L1DataProvider, Mempool, MockL1DataProvider};
+    use mc_mempool::{
+        transaction_hash, BuilderBlockOutcome, BuilderHandle, BundleRejection, L1DataProvider, Mempool,
+        MockL1DataProvider, MAX_BUNDLE_SIZE, MAX_PENDING_BUNDLES,
+    };
     use mc_metrics::MetricsRegistry;
     use mp_block::header::L1DataAvailabilityMode;
     use mp_block::{BlockId, BlockTag};
@@ -198,6 +243,8 @@ mod tests {
     use mp_transactions::broadcasted_to_blockifier;
     use mp_transactions::compute_hash::calculate_contract_address;
     use rstest::{fixture, rstest};
+    use starknet_api::core::{ChainId, ClassHash, Nonce};
+    use starknet_api::state::StorageKey;
     use starknet_core::types::contract::SierraClass;
     use starknet_core::types::{
         BroadcastedDeclareTransaction, BroadcastedDeclareTransactionV3, BroadcastedDeployAccountTransaction,
@@ -207,6 +254,13 @@ mod tests {
     };
     use std::sync::Arc;
 
+    // 1 ETH = 1e18 WEI
+    const ETH_WEI_DECIMALS: u128 = 1_000_000_000_000_000_000;
+    // 1 STRK = 1e18 FRI
+    const STRK_FRI_DECIMALS: u128 = 1_000_000_000_000_000_000;
+    const ERC20_STRK_CONTRACT_ADDRESS: Felt =
+        Felt::from_hex_unchecked("0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d");
+
     struct DevnetForTesting {
         backend: Arc<MadaraBackend>,
         contracts: DevnetKeys,
@@ -217,26 +271,52 @@ mod tests {
     impl DevnetForTesting {
         pub fn sign_and_add_invoke_tx(
             &self,
-            mut tx: BroadcastedInvokeTransaction,
+            tx: BroadcastedInvokeTransaction,
             contract: &DevnetPredeployedContract,
         ) -> InvokeTransactionResult {
+            let (tx, _tx_hash) = self.sign_invoke_tx(tx, contract);
+
+            log::debug!("tx: {:?}", tx);
+
+            self.mempool.accept_invoke_tx(tx).unwrap()
+        }
+
+        /// Returns the signed transaction and its hash.
+        pub fn sign_invoke_tx(
+            &self,
+            mut tx: BroadcastedInvokeTransaction,
+            contract: &DevnetPredeployedContract,
+        ) -> (BroadcastedInvokeTransaction, Felt) {
             let (blockifier_tx, _classes) = broadcasted_to_blockifier(
                 BroadcastedTransaction::Invoke(tx.clone()),
                 self.backend.chain_config().chain_id.to_felt(),
                 self.backend.chain_config().latest_protocol_version,
             )
             .unwrap();
-            let signature = contract.secret.sign(&transaction_hash(&blockifier_tx)).unwrap();
+            let tx_hash = transaction_hash(&blockifier_tx);
+            let signature = contract.secret.sign(&tx_hash).unwrap();
 
             let tx_signature = match &mut tx {
                 BroadcastedInvokeTransaction::V1(tx) => &mut tx.signature,
                 BroadcastedInvokeTransaction::V3(tx) => &mut tx.signature,
             };
             *tx_signature = vec![signature.r, signature.s];
+            (tx, tx_hash)
+        }
 
-            log::debug!("tx: {:?}", tx);
-
-            self.mempool.accept_invoke_tx(tx).unwrap()
+        /// Executes the transactions as a block of the external block builder.
+        pub fn submit_builder_block(
+            &mut self,
+            transactions: Vec<BroadcastedInvokeTransaction>,
+        ) -> Result<BuilderBlockOutcome, mc_mempool::block_production::Error> {
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let (handle, mut builder_blocks) = BuilderHandle::new(Arc::clone(&self.backend));
+            let transactions = transactions.into_iter().map(BroadcastedTransaction::Invoke).collect();
+            let outcome = runtime.spawn(async move { handle.submit_block(transactions).await });
+            if let Some(builder_block) = builder_blocks.blocking_recv() {
+                self.block_production.on_builder_block(builder_block);
+            }
+            runtime.block_on(outcome).unwrap()
         }
 
         pub fn sign_and_add_declare_tx(
@@ -294,10 +374,10 @@ mod tests {
     fn chain() -> DevnetForTesting {
         let _ = env_logger::builder().is_test(true).try_init();
 
-        let mut g = ChainGenesisDescription::base_config().unwrap();
-        let contracts = g.add_devnet_contracts(10).unwrap();
-
         let chain_config = Arc::new(ChainConfig::madara_devnet());
+        let mut g = ChainGenesisDescription::base_config(&chain_config).unwrap();
+        let contracts = g.add_devnet_contracts(10, &chain_config).unwrap();
+
         let block = g.build(&chain_config).unwrap();
         let backend = MadaraBackend::open_for_testing(Arc::clone(&chain_config));
         let importer =
@@ -325,7 +405,9 @@ mod tests {
             strk_l1_data_gas_price: 128,
         });
         let l1_data_provider = Arc::new(l1_data_provider) as Arc<dyn L1DataProvider>;
-        let mempool = Arc::new(Mempool::new(Arc::clone(&backend), Arc::clone(&l1_data_provider)));
+        let mempool = Arc::new(
+            Mempool::new(Arc::clone(&backend), Arc::clone(&l1_data_provider)).with_chain_id_mismatch_detection(true),
+        );
         let block_production = BlockProductionTask::new(
             Arc::clone(&backend),
             Arc::clone(&importer),
@@ -399,6 +481,87 @@ mod tests {
         assert_eq!(receipt.execution_result, ExecutionResult::Succeeded);
     }
 
+    #[rstest]
+    fn test_mint(mut chain: DevnetForTesting) {
+        let address = Felt::from_hex_unchecked("0x1234");
+        let strk = chain.backend.chain_config().native_fee_token_address;
+        let eth = chain.backend.chain_config().parent_fee_token_address;
+        let contract_address = address.try_into().unwrap();
+
+        assert_eq!(chain.block_production.mint(strk, contract_address, 1_000u64.into()).unwrap(), 1_000u64.into());
+        assert_eq!(chain.block_production.mint(strk, contract_address, 500u64.into()).unwrap(), 1_500u64.into());
+        assert_eq!(chain.block_production.mint(eth, contract_address, 42u64.into()).unwrap(), 42u64.into());
+        assert_eq!(chain.get_bal_strk_eth(address), (1_500, 42));
+    }
+
+    #[test]
+    fn test_common_classes() {
+        let mut genesis = ChainGenesisDescription::base_config(&ChainConfig::madara_devnet()).unwrap();
+        genesis.add_classes(&ClassManifest { common_classes: false, classes: vec![] }).unwrap();
+        let n_classes = genesis.declared_classes.as_state_diff().len();
+
+        genesis.add_classes(&ClassManifest::default()).unwrap();
+        // The ERC20 class is already declared by the base config.
+        assert_eq!(genesis.declared_classes.as_state_diff().len(), n_classes + 3);
+    }
+
+    #[test]
+    fn test_genesis_timestamp() {
+        let chain_config = ChainConfig { genesis_timestamp: Some(1_700_000_000), ..ChainConfig::madara_devnet() };
+        let block = ChainGenesisDescription::base_config(&chain_config).unwrap().build(&chain_config).unwrap();
+        assert_eq!(block.header.block_timestamp, 1_700_000_000);
+    }
+
+    #[rstest]
+    fn test_cheat_codes(mut chain: DevnetForTesting) {
+        let pending = BlockId::Tag(BlockTag::Pending);
+        let address = Felt::from_hex_unchecked("0x1234");
+        let contract_address = address.try_into().unwrap();
+        let key = Felt::from_hex_unchecked("0x10");
+        let class_hash = chain.contracts.0[0].class_hash;
+
+        chain
+            .block_production
+            .set_storage_at(contract_address, StorageKey(key.try_into().unwrap()), Felt::from_hex_unchecked("0x42"))
+            .unwrap();
+        chain.block_production.set_nonce(contract_address, Nonce(Felt::from(3))).unwrap();
+        chain.block_production.set_class_hash_at(contract_address, ClassHash(class_hash)).unwrap();
+
+        assert_eq!(
+            chain.backend.get_contract_storage_at(&pending, &address, &key).unwrap(),
+            Some(Felt::from_hex_unchecked("0x42"))
+        );
+        assert_eq!(chain.backend.get_contract_nonce_at(&pending, &address).unwrap(), Some(Felt::from(3)));
+        assert_eq!(chain.backend.get_contract_class_hash_at(&pending, &address).unwrap(), Some(class_hash));
+
+        assert_matches!(chain.block_production.set_nonce(contract_address, Nonce(Felt::ONE)), Err(_));
+        assert_matches!(
+            chain.block_production.set_class_hash_at(contract_address, ClassHash(Felt::from_hex_unchecked("0xdead"))),
+            Err(_)
+        );
+    }
+
+    #[rstest]
+    fn test_dump_and_load(chain: DevnetForTesting) {
+        chain.contracts.save_to_db(&chain.backend).unwrap();
+        let dump = DevnetDump::from_db(&chain.backend).unwrap();
+        let dump: DevnetDump = serde_json::from_slice(&serde_json::to_vec(&dump).unwrap()).unwrap();
+        assert!(dump.new_blocks(&chain.backend).unwrap().is_empty());
+
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_devnet()));
+        let importer =
+            Arc::new(BlockImporter::new(Arc::clone(&backend), &MetricsRegistry::dummy(), None, true).unwrap());
+        assert_eq!(dump.new_blocks(&backend).unwrap().len(), 1);
+        tokio::runtime::Runtime::new().unwrap().block_on(dump.load(&backend, &importer)).unwrap();
+
+        assert_eq!(
+            backend.get_block_hash(&BlockId::Tag(BlockTag::Latest)).unwrap(),
+            chain.backend.get_block_hash(&BlockId::Tag(BlockTag::Latest)).unwrap()
+        );
+        let keys = DevnetKeys::from_db(&backend).unwrap();
+        assert_eq!(keys.0[0].address, chain.contracts.0[0].address);
+    }
+
     #[rstest]
     fn test_account_deploy(mut chain: DevnetForTesting) {
         let key = SigningKey::from_random();
@@ -493,6 +656,179 @@ mod tests {
         assert_eq!(receipt.execution_result, ExecutionResult::Succeeded);
     }
 
+    fn transfer_tx(
+        sender: &DevnetPredeployedContract,
+        recipient: Felt,
+        amount: u128,
+        nonce: u64,
+    ) -> BroadcastedInvokeTransaction {
+        BroadcastedInvokeTransaction::V3(BroadcastedInvokeTransactionV3 {
+            sender_address: sender.address,
+            calldata: Multicall::default()
+                .with(Call {
+                    to: ERC20_STRK_CONTRACT_ADDRESS,
+                    selector: Selector::from("transfer"),
+                    calldata: vec![recipient, amount.into(), Felt::ZERO],
+                })
+                .flatten()
+                .collect(),
+            signature: vec![],
+            nonce: nonce.into(),
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds { max_amount: 60000, max_price_per_unit: 10000 },
+                l2_gas: ResourceBounds { max_amount: 60000, max_price_per_unit: 10000 },
+            },
+            tip: 0,
+            paymaster_data: vec![],
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: starknet_core::types::DataAvailabilityMode::L1,
+            fee_data_availability_mode: starknet_core::types::DataAvailabilityMode::L1,
+            is_query: false,
+        })
+    }
+
+    #[rstest]
+    fn test_builder_block(mut chain: DevnetForTesting) {
+        let contract_0 = &chain.contracts.0[0];
+        let contract_1 = &chain.contracts.0[1];
+        let address_1 = contract_1.address;
+        let (tx_0, tx_hash_0) = chain.sign_invoke_tx(transfer_tx(contract_0, contract_1.address, 1_000, 0), contract_0);
+        let (tx_1, tx_hash_1) = chain.sign_invoke_tx(transfer_tx(contract_1, contract_0.address, 10, 0), contract_1);
+
+        let outcome = chain.submit_builder_block(vec![tx_1, tx_0]).unwrap();
+        assert_eq!(
+            outcome,
+            BuilderBlockOutcome { block_number: 1, included: vec![tx_hash_1, tx_hash_0], rejected: vec![] }
+        );
+
+        // The transactions are executed in the order of the builder.
+        let block = chain.backend.get_block(&BlockId::Tag(BlockTag::Pending)).unwrap().unwrap();
+        let tx_hashes: Vec<_> = block.inner.receipts.iter().map(|receipt| receipt.transaction_hash()).collect();
+        assert_eq!(tx_hashes, [tx_hash_1, tx_hash_0]);
+        assert!(block.inner.receipts.iter().all(|receipt| receipt.execution_result() == ExecutionResult::Succeeded));
+        let fees_fri = felt_to_u128(&block.inner.receipts[0].actual_fee().amount).unwrap();
+        assert_eq!(chain.get_bal_strk_eth(address_1).0, 10_000 * STRK_FRI_DECIMALS + 1_000 - 10 - fees_fri);
+    }
+
+    #[rstest]
+    fn test_builder_block_rejected(mut chain: DevnetForTesting) {
+        let contract_0 = &chain.contracts.0[0];
+        let contract_1 = &chain.contracts.0[1];
+        let (valid_tx, valid_tx_hash) =
+            chain.sign_invoke_tx(transfer_tx(contract_0, contract_1.address, 1_000, 0), contract_0);
+        // Signed with the key of another account.
+        let (invalid_tx, invalid_tx_hash) =
+            chain.sign_invoke_tx(transfer_tx(contract_1, contract_0.address, 1_000, 0), contract_0);
+
+        let outcome = chain.submit_builder_block(vec![invalid_tx, valid_tx]).unwrap();
+        assert_eq!(
+            outcome,
+            BuilderBlockOutcome { block_number: 1, included: vec![valid_tx_hash], rejected: vec![invalid_tx_hash] }
+        );
+        let block = chain.backend.get_block(&BlockId::Tag(BlockTag::Pending)).unwrap().unwrap();
+        assert_eq!(block.inner.transactions.len(), 1);
+        assert_eq!(block.inner.receipts[0].transaction_hash(), valid_tx_hash);
+
+        assert_matches!(
+            chain.submit_builder_block(vec![]),
+            Err(mc_mempool::block_production::Error::InvalidBuilderBlock(_))
+        );
+    }
+
+    #[rstest]
+    fn test_bundle(mut chain: DevnetForTesting) {
+        let contract_0 = &chain.contracts.0[0];
+        let contract_1 = &chain.contracts.0[1];
+        // Received before the bundle, but executed after it.
+        let mempool_tx = chain.sign_and_add_invoke_tx(transfer_tx(contract_1, contract_0.address, 10, 0), contract_1);
+        let (tx_0, tx_hash_0) = chain.sign_invoke_tx(transfer_tx(contract_0, contract_1.address, 1_000, 0), contract_0);
+        let (tx_1, tx_hash_1) = chain.sign_invoke_tx(transfer_tx(contract_0, contract_1.address, 2_000, 1), contract_0);
+
+        let (tx_hashes, mut outcome) = chain
+            .mempool
+            .accept_bundle(vec![BroadcastedTransaction::Invoke(tx_0), BroadcastedTransaction::Invoke(tx_1)])
+            .unwrap();
+        assert_eq!(tx_hashes, [tx_hash_0, tx_hash_1]);
+
+        chain.block_production.set_current_pending_tick(1);
+        chain.block_production.on_pending_time_tick().unwrap();
+
+        assert_eq!(outcome.try_recv().unwrap(), Ok(1));
+        let block = chain.backend.get_block(&BlockId::Tag(BlockTag::Pending)).unwrap().unwrap();
+        let tx_hashes: Vec<_> = block.inner.receipts.iter().map(|receipt| receipt.transaction_hash()).collect();
+        assert_eq!(tx_hashes, [tx_hash_0, tx_hash_1, mempool_tx.transaction_hash]);
+        assert!(block.inner.receipts.iter().all(|receipt| receipt.execution_result() == ExecutionResult::Succeeded));
+    }
+
+    #[rstest]
+    fn test_bundle_rejected(mut chain: DevnetForTesting) {
+        let contract_0 = &chain.contracts.0[0];
+        let contract_1 = &chain.contracts.0[1];
+        let (valid_tx, valid_tx_hash) =
+            chain.sign_invoke_tx(transfer_tx(contract_0, contract_1.address, 1_000, 0), contract_0);
+        // Reverts, as the balance is insufficient.
+        let (reverted_tx, reverted_tx_hash) = chain
+            .sign_invoke_tx(transfer_tx(contract_0, contract_1.address, 20_000 * STRK_FRI_DECIMALS, 1), contract_0);
+        // Signed with the key of another account.
+        let (invalid_tx, invalid_tx_hash) =
+            chain.sign_invoke_tx(transfer_tx(contract_1, contract_0.address, 1_000, 0), contract_0);
+
+        let (_, mut reverted) = chain
+            .mempool
+            .accept_bundle(vec![
+                BroadcastedTransaction::Invoke(valid_tx.clone()),
+                BroadcastedTransaction::Invoke(reverted_tx),
+            ])
+            .unwrap();
+        let (_, mut failed) = chain.mempool.accept_bundle(vec![BroadcastedTransaction::Invoke(invalid_tx)]).unwrap();
+        // Only valid when the first transaction of the rejected bundle was rolled back.
+        let (_, mut included) = chain.mempool.accept_bundle(vec![BroadcastedTransaction::Invoke(valid_tx)]).unwrap();
+
+        chain.block_production.set_current_pending_tick(1);
+        chain.block_production.on_pending_time_tick().unwrap();
+
+        assert_eq!(reverted.try_recv().unwrap(), Err(BundleRejection::Reverted { tx_hash: reverted_tx_hash }));
+        assert_matches!(
+            failed.try_recv().unwrap(),
+            Err(BundleRejection::Failed { tx_hash, .. }) if tx_hash == invalid_tx_hash
+        );
+        assert_eq!(included.try_recv().unwrap(), Ok(1));
+        let block = chain.backend.get_block(&BlockId::Tag(BlockTag::Pending)).unwrap().unwrap();
+        assert_eq!(block.inner.transactions.len(), 1);
+        assert_eq!(block.inner.receipts[0].transaction_hash(), valid_tx_hash);
+    }
+
+    #[rstest]
+    fn test_bundle_capacity(mut chain: DevnetForTesting) {
+        let contract_0 = &chain.contracts.0[0];
+        let (tx, tx_hash) =
+            chain.sign_invoke_tx(transfer_tx(contract_0, chain.contracts.0[1].address, 1_000, 0), contract_0);
+        let tx = BroadcastedTransaction::Invoke(tx);
+
+        assert_matches!(chain.mempool.accept_bundle(vec![]), Err(mc_mempool::Error::InvalidBundle(_)));
+        assert_matches!(
+            chain.mempool.accept_bundle(vec![tx.clone(); MAX_BUNDLE_SIZE + 1]),
+            Err(mc_mempool::Error::InvalidBundle(_))
+        );
+        let mut outcomes: Vec<_> =
+            (0..MAX_PENDING_BUNDLES).map(|_| chain.mempool.accept_bundle(vec![tx.clone()]).unwrap().1).collect();
+        assert_matches!(chain.mempool.accept_bundle(vec![tx.clone()]), Err(mc_mempool::Error::BundleQueueFull));
+
+        chain.block_production.set_current_pending_tick(1);
+        chain.block_production.on_pending_time_tick().unwrap();
+
+        // The same transaction is only included once.
+        assert_eq!(outcomes[0].try_recv().unwrap(), Ok(1));
+        for outcome in &mut outcomes[1..] {
+            assert_matches!(
+                outcome.try_recv().unwrap(),
+                Err(BundleRejection::Failed { tx_hash: failed_tx_hash, .. }) if failed_tx_hash == tx_hash
+            );
+        }
+        // The block production emptied the queue.
+        assert_matches!(chain.mempool.accept_bundle(vec![tx]), Ok(_));
+    }
+
     // TODO: add eth transfer
     #[rstest]
     #[case(24235u128, false)]
@@ -601,4 +937,48 @@ mod tests {
             }
         }
     }
+
+    #[rstest]
+    fn test_transaction_signed_for_another_chain_is_rejected(chain: DevnetForTesting) {
+        let contract_0 = &chain.contracts.0[0];
+        let mut tx = BroadcastedInvokeTransaction::V3(BroadcastedInvokeTransactionV3 {
+            sender_address: contract_0.address,
+            calldata: Multicall::default()
+                .with(Call {
+                    to: ERC20_STRK_CONTRACT_ADDRESS,
+                    selector: Selector::from("transfer"),
+                    calldata: vec![chain.contracts.0[1].address, 1u128.into(), Felt::ZERO],
+                })
+                .flatten()
+                .collect(),
+            signature: vec![],
+            nonce: Felt::ZERO,
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds { max_amount: 60000, max_price_per_unit: 10000 },
+                l2_gas: ResourceBounds { max_amount: 60000, max_price_per_unit: 10000 },
+            },
+            tip: 0,
+            paymaster_data: vec![],
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: starknet_core::types::DataAvailabilityMode::L1,
+            fee_data_availability_mode: starknet_core::types::DataAvailabilityMode::L1,
+            is_query: false,
+        });
+
+        // Signed with the hash the transaction has on Sepolia.
+        let (blockifier_tx, _classes) = broadcasted_to_blockifier(
+            BroadcastedTransaction::Invoke(tx.clone()),
+            ChainId::Sepolia.to_felt(),
+            chain.backend.chain_config().latest_protocol_version,
+        )
+        .unwrap();
+        let signature = contract_0.secret.sign(&transaction_hash(&blockifier_tx)).unwrap();
+        let BroadcastedInvokeTransaction::V3(tx_v3) = &mut tx else { unreachable!() };
+        tx_v3.signature = vec![signature.r, signature.s];
+
+        assert_matches!(
+            chain.mempool.accept_invoke_tx(tx),
+            Err(mc_mempool::Error::ChainIdMismatch { signed: ChainId::Sepolia, .. })
+        );
+    }
 }
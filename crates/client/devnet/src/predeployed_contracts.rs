@@ -3,13 +3,12 @@ use blockifier::abi::{abi_utils::get_fee_token_var_address, sierra_types::next_s
 use core::fmt;
 use mc_db::MadaraBackend;
 use mp_block::{BlockId, BlockTag};
+use mp_chain_config::{ChainConfig, FeeToken};
+use mp_convert::ToFelt;
 use starknet_core::types::Felt;
 use starknet_signers::SigningKey;
 
-use crate::{
-    ContractFeeTokensBalance, ERC20_ETH_CONTRACT_ADDRESS, ERC20_STRK_CONTRACT_ADDRESS, ETH_WEI_DECIMALS,
-    STRK_FRI_DECIMALS,
-};
+use crate::ContractFeeTokensBalance;
 
 pub struct DevnetPredeployedContract {
     pub address: Felt,
@@ -21,18 +20,28 @@ pub struct DevnetPredeployedContract {
 
 pub struct DevnetKeys(pub Vec<DevnetPredeployedContract>);
 
-impl fmt::Display for DevnetKeys {
+/// Displays the devnet keys, with the balances in whole fee tokens.
+pub struct DisplayDevnetKeys<'a> {
+    keys: &'a DevnetKeys,
+    chain_config: &'a ChainConfig,
+}
+
+impl fmt::Display for DisplayDevnetKeys<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let whole_tokens =
+            |amount: u128, token: &FeeToken| amount / 10u128.checked_pow(token.decimals.into()).unwrap_or(u128::MAX);
+        let (native, parent) = (&self.chain_config.native_fee_token, &self.chain_config.parent_fee_token);
+
         writeln!(f)?;
         writeln!(f, "==== DEVNET PREDEPLOYED CONTRACTS ====")?;
         writeln!(f)?;
-        for (i, contract) in self.0.iter().enumerate() {
+        for (i, contract) in self.keys.0.iter().enumerate() {
             writeln!(f, "(#{}) Address: {:#x}", i + 1, contract.address,)?;
             writeln!(f, "  Private key: {:#x}", contract.secret.secret_scalar())?;
             match contract.balance.as_u128_fri_wei() {
                 Ok((fri, wei)) => {
-                    let (strk, eth) = (fri / STRK_FRI_DECIMALS, wei / ETH_WEI_DECIMALS);
-                    writeln!(f, "  Balance: {strk} STRK, {eth} ETH")?;
+                    let (native_balance, parent_balance) = (whole_tokens(fri, native), whole_tokens(wei, parent));
+                    writeln!(f, "  Balance: {native_balance} {}, {parent_balance} {}", native.symbol, parent.symbol)?;
                     writeln!(f)?;
                 }
                 Err(err) => writeln!(f, "Error getting balance: {err:#}\n")?,
@@ -70,18 +79,23 @@ pub fn get_bal_contract(
     Ok(low)
 }
 
-/// (STRK in FRI, ETH in WEI)
+/// (STRK in FRI, ETH in WEI), or the native and parent fee tokens of the chain config in their base units.
 pub fn get_fee_tokens_balance(
     backend: &MadaraBackend,
     contract_address: Felt,
 ) -> anyhow::Result<ContractFeeTokensBalance> {
+    let chain_config = backend.chain_config();
     Ok(ContractFeeTokensBalance {
-        fri: get_bal_contract(backend, contract_address, ERC20_STRK_CONTRACT_ADDRESS)?,
-        wei: get_bal_contract(backend, contract_address, ERC20_ETH_CONTRACT_ADDRESS)?,
+        fri: get_bal_contract(backend, contract_address, chain_config.native_fee_token_address.to_felt())?,
+        wei: get_bal_contract(backend, contract_address, chain_config.parent_fee_token_address.to_felt())?,
     })
 }
 
 impl DevnetKeys {
+    pub fn display<'a>(&'a self, chain_config: &'a ChainConfig) -> DisplayDevnetKeys<'a> {
+        DisplayDevnetKeys { keys: self, chain_config }
+    }
+
     pub fn from_db(backend: &MadaraBackend) -> anyhow::Result<Self> {
         let keys = backend
             .get_devnet_predeployed_keys()
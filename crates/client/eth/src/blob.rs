@@ -0,0 +1,212 @@
+//! EIP-4844 blob data availability.
+//!
+//! The onchain data of a state update is published in a blob instead of calldata. The Starknet OS treats the felts
+//! as the coefficients of a polynomial over the BLS12-381 scalar field, while a blob holds the evaluations of its
+//! polynomial over the 4096th roots of unity, in bit-reversed order. The blob is therefore the FFT of the data, with
+//! its evaluations permuted by bit-reversal of their index.
+//! The core contract checks the blob against the program output using a KZG point evaluation proof at a point
+//! `z` derived from the data and the blob commitment.
+
+use alloy::consensus::BlobTransactionSidecar;
+use alloy::eips::eip4844::env_settings::EnvKzgSettings;
+use alloy::eips::eip4844::{Blob, BYTES_PER_BLOB, FIELD_ELEMENTS_PER_BLOB};
+use alloy::primitives::{Bytes, FixedBytes};
+use anyhow::{bail, Context};
+use c_kzg::{Bytes32, KzgCommitment, KzgProof};
+use num_bigint::BigUint;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Poseidon, StarkHash};
+
+/// Size in bytes of a field element of a blob.
+const BYTES_PER_FIELD_ELEMENT: usize = 32;
+
+lazy_static::lazy_static! {
+    /// Modulus of the BLS12-381 scalar field.
+    static ref BLS_MODULUS: BigUint = BigUint::parse_bytes(
+        b"52435875175126190479447740508185965837690552500527637822603658699938581184513",
+        10,
+    )
+    .unwrap();
+    /// Primitive 4096th root of unity of the BLS12-381 scalar field, `7^((BLS_MODULUS - 1) / 4096)`, generating the
+    /// evaluation domain of the blobs.
+    static ref ROOT_OF_UNITY: BigUint = BigUint::parse_bytes(
+        b"39033254847818212395286706435128746857159659164139250548781411570340225835782",
+        10,
+    )
+    .unwrap();
+}
+
+/// A blob carrying the onchain data of a state update, along with what the core contract needs to verify it.
+#[derive(Clone, Debug)]
+pub struct BlobDa {
+    pub sidecar: BlobTransactionSidecar,
+    /// KZG segment of the program output: `[z, commitment_low, commitment_high, y_low, y_high]`.
+    pub kzg_segment: Vec<Felt>,
+    /// Proof of the evaluation of the blob polynomial at `z`.
+    pub kzg_proof: Bytes,
+}
+
+/// Evaluates the polynomial with the coefficients `coefficients` at the powers of `root`, a primitive root of unity of
+/// order `coefficients.len()`, which must be a power of two.
+fn fft(coefficients: &[BigUint], root: &BigUint) -> Vec<BigUint> {
+    let n = coefficients.len();
+    if n == 1 {
+        return coefficients.to_vec();
+    }
+    let modulus = &*BLS_MODULUS;
+    let root_squared = root * root % modulus;
+    let even: Vec<_> = coefficients.iter().step_by(2).cloned().collect();
+    let odd: Vec<_> = coefficients.iter().skip(1).step_by(2).cloned().collect();
+    let even = fft(&even, &root_squared);
+    let odd = fft(&odd, &root_squared);
+
+    let mut evaluations = vec![BigUint::default(); n];
+    let mut power = BigUint::from(1u8);
+    for (i, (even, odd)) in even.iter().zip(&odd).enumerate() {
+        let term = &power * odd % modulus;
+        evaluations[i] = (even + &term) % modulus;
+        evaluations[i + n / 2] = (even + modulus - &term) % modulus;
+        power = power * root % modulus;
+    }
+    evaluations
+}
+
+/// Encodes the data as a single blob: the data is the coefficients of the blob polynomial, and the blob holds its
+/// evaluations over the roots of unity in bit-reversed order.
+pub fn data_to_blob(data: &[Felt]) -> anyhow::Result<Blob> {
+    if data.len() > FIELD_ELEMENTS_PER_BLOB as usize {
+        bail!(
+            "The onchain data does not fit in a blob: {} felts, the maximum is {FIELD_ELEMENTS_PER_BLOB}. Try to \
+             lower the number of blocks per state update.",
+            data.len()
+        );
+    }
+    let mut coefficients = vec![BigUint::default(); FIELD_ELEMENTS_PER_BLOB as usize];
+    for (coefficient, felt) in coefficients.iter_mut().zip(data) {
+        *coefficient = BigUint::from_bytes_be(&felt.to_bytes_be());
+    }
+    let evaluations = fft(&coefficients, &ROOT_OF_UNITY);
+
+    let log_size = FIELD_ELEMENTS_PER_BLOB.trailing_zeros();
+    let mut blob = [0u8; BYTES_PER_BLOB];
+    for (i, chunk) in blob.chunks_exact_mut(BYTES_PER_FIELD_ELEMENT).enumerate() {
+        let evaluation = evaluations[(i as u32).reverse_bits() as usize >> (u32::BITS - log_size)].to_bytes_be();
+        chunk[BYTES_PER_FIELD_ELEMENT - evaluation.len()..].copy_from_slice(&evaluation);
+    }
+    Ok(Blob::from(blob))
+}
+
+/// Splits a big-endian integer of up to 384 bits into its low and high 192 bits halves.
+fn split_u384(bytes: &[u8]) -> [Felt; 2] {
+    let mut padded = [0u8; 48];
+    padded[48 - bytes.len()..].copy_from_slice(bytes);
+    [Felt::from_bytes_be_slice(&padded[24..]), Felt::from_bytes_be_slice(&padded[..24])]
+}
+
+/// Builds the blob carrying the onchain data, its sidecar and the KZG proof checked by the core contract.
+pub fn prepare_blob_da(data: &[Felt]) -> anyhow::Result<BlobDa> {
+    let settings = EnvKzgSettings::Default;
+    let settings = settings.get();
+
+    let blob = data_to_blob(data)?;
+    let kzg_blob = c_kzg::Blob::from_bytes(blob.as_slice()).context("Invalid blob")?;
+    let commitment = KzgCommitment::blob_to_kzg_commitment(&kzg_blob, settings).context("Computing KZG commitment")?;
+    let commitment_bytes = commitment.to_bytes();
+    let blob_proof =
+        KzgProof::compute_blob_kzg_proof(&kzg_blob, &commitment_bytes, settings).context("Computing blob KZG proof")?;
+
+    let [commitment_low, commitment_high] = split_u384(commitment_bytes.as_slice());
+    let z = Poseidon::hash_array(&[Poseidon::hash_array(data), commitment_low, commitment_high]);
+    let z_bytes = Bytes32::from_bytes(&z.to_bytes_be()).context("Invalid evaluation point")?;
+    let (kzg_proof, y) =
+        KzgProof::compute_kzg_proof(&kzg_blob, &z_bytes, settings).context("Computing KZG evaluation proof")?;
+    let [y_low, y_high] = split_u384(y.as_slice());
+
+    let sidecar = BlobTransactionSidecar::new(
+        vec![blob],
+        vec![FixedBytes::from(commitment_bytes.into_inner())],
+        vec![FixedBytes::from(blob_proof.to_bytes().into_inner())],
+    );
+
+    Ok(BlobDa {
+        sidecar,
+        kzg_segment: vec![z, commitment_low, commitment_high, y_low, y_high],
+        kzg_proof: Bytes::copy_from_slice(kzg_proof.to_bytes().as_slice()),
+    })
+}
+
+#[cfg(test)]
+mod blob_tests {
+    use super::*;
+
+    fn to_biguint(felt: &Felt) -> BigUint {
+        BigUint::from_bytes_be(&felt.to_bytes_be())
+    }
+
+    #[test]
+    fn root_of_unity_has_order_4096() {
+        let one = BigUint::from(1u8);
+        assert_eq!(ROOT_OF_UNITY.modpow(&BigUint::from(FIELD_ELEMENTS_PER_BLOB), &BLS_MODULUS), one);
+        assert_ne!(ROOT_OF_UNITY.modpow(&BigUint::from(FIELD_ELEMENTS_PER_BLOB / 2), &BLS_MODULUS), one);
+        assert_eq!(ROOT_OF_UNITY.clone(), BigUint::from(7u8).modpow(&((&*BLS_MODULUS - 1u8) / 4096u32), &BLS_MODULUS));
+    }
+
+    #[test]
+    fn data_to_blob_works() {
+        let data = [Felt::ONE, Felt::MAX, Felt::TWO];
+        let blob = data_to_blob(&data).unwrap();
+        let element = |i: usize| BigUint::from_bytes_be(&blob[i * 32..(i + 1) * 32]);
+        let modulus = &*BLS_MODULUS;
+
+        // The first element is the evaluation at 1, the second at -1 once bit-reversed.
+        let sum = data.iter().map(to_biguint).sum::<BigUint>() % modulus;
+        assert_eq!(element(0), sum);
+        let alternating = (to_biguint(&data[0]) + to_biguint(&data[2]) + modulus - to_biguint(&data[1])) % modulus;
+        assert_eq!(element(1), alternating);
+    }
+
+    #[test]
+    fn data_to_blob_empty_is_zero() {
+        let blob = data_to_blob(&[]).unwrap();
+        assert!(blob.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn data_to_blob_too_big_fails() {
+        assert!(data_to_blob(&vec![Felt::ONE; FIELD_ELEMENTS_PER_BLOB as usize + 1]).is_err());
+    }
+
+    #[test]
+    fn split_u384_works() {
+        let mut bytes = [0u8; 48];
+        bytes[23] = 2;
+        bytes[47] = 1;
+        assert_eq!(split_u384(&bytes), [Felt::ONE, Felt::TWO]);
+    }
+
+    #[test]
+    fn prepare_blob_da_works() {
+        let blob_da = prepare_blob_da(&[Felt::ONE, Felt::TWO]).unwrap();
+        assert_eq!(blob_da.sidecar.blobs.len(), 1);
+        assert_eq!(blob_da.kzg_segment.len(), 5);
+        assert_eq!(blob_da.kzg_proof.len(), 48);
+    }
+
+    /// The core contract checks that the blob polynomial evaluates at `z` to the evaluation of the polynomial whose
+    /// coefficients are the data, as computed by the Starknet OS.
+    #[test]
+    fn prepare_blob_da_evaluates_the_data_polynomial() {
+        let data: Vec<Felt> = (0..100u64).map(|i| Felt::from(i * i + 7) * Felt::MAX).collect();
+        let blob_da = prepare_blob_da(&data).unwrap();
+        let [z, _, _, y_low, y_high] = blob_da.kzg_segment[..] else { unreachable!() };
+
+        let modulus = &*BLS_MODULUS;
+        let z = to_biguint(&z);
+        let expected = data
+            .iter()
+            .rev()
+            .fold(BigUint::default(), |acc, coefficient| (acc * &z + to_biguint(coefficient)) % modulus);
+        let y = (to_biguint(&y_high) << 192) + to_biguint(&y_low);
+        assert_eq!(y, expected);
+    }
+}
@@ -1,15 +1,19 @@
 use crate::client::StarknetCoreContract::StarknetCoreContractInstance;
 use crate::utils::u256_to_felt;
+use alloy::eips::BlockNumberOrTag;
 use alloy::sol_types::SolEvent;
 use alloy::{
     primitives::Address,
-    providers::{Provider, ProviderBuilder, ReqwestProvider, RootProvider},
-    rpc::types::Filter,
+    providers::{Provider, ProviderBuilder, ReqwestProvider, RootProvider, WsConnect},
+    pubsub::PubSubFrontend,
+    rpc::types::{Filter, Log},
     sol,
     transports::http::{Client, Http},
 };
 use anyhow::{bail, Context};
 use bitvec::macros::internal::funty::Fundamental;
+use futures::stream::{self, BoxStream};
+use futures::{future, StreamExt, TryStreamExt};
 use mc_metrics::{Gauge, MetricsRegistry, PrometheusError, F64};
 use starknet_types_core::felt::Felt;
 use std::sync::Arc;
@@ -22,6 +26,8 @@ pub struct L1BlockMetrics {
     // gas price is also define in sync/metrics/block_metrics.rs but this would be the price from l1
     pub l1_gas_price_wei: Gauge<F64>,
     pub l1_gas_price_strk: Gauge<F64>,
+    // 1 when the local state root does not match the one verified by the core contract
+    pub state_root_mismatch: Gauge<F64>,
 }
 
 impl L1BlockMetrics {
@@ -33,6 +39,10 @@ impl L1BlockMetrics {
             l1_gas_price_wei: registry.register(Gauge::new("madara_l1_gas_price", "Gauge for madara L1 gas price")?)?,
             l1_gas_price_strk: registry
                 .register(Gauge::new("madara_l1_gas_price_strk", "Gauge for madara L1 gas price in strk")?)?,
+            state_root_mismatch: registry.register(Gauge::new(
+                "madara_l1_state_root_mismatch",
+                "Gauge set to 1 when the local state root does not match the one verified by the core contract",
+            )?)?,
         })
     }
 }
@@ -46,8 +56,14 @@ sol!(
     "src/abis/starknet_core.json"
 );
 
+/// A stream of decoded core contract events, along with the log they were decoded from.
+pub type L1EventStream<E> = BoxStream<'static, anyhow::Result<(E, Log)>>;
+
 pub struct EthereumClient {
     pub provider: Arc<ReqwestProvider>,
+    /// Websocket provider used for `eth_subscribe`. This is `None` when no websocket endpoint was
+    /// provided or when the connection could not be established, in which case we poll the http provider.
+    pub ws_provider: Option<Arc<RootProvider<PubSubFrontend>>>,
     pub l1_core_contract: StarknetCoreContractInstance<Http<Client>, RootProvider<Http<Client>>>,
     pub l1_block_metrics: L1BlockMetrics,
 }
@@ -56,6 +72,7 @@ impl Clone for EthereumClient {
     fn clone(&self) -> Self {
         EthereumClient {
             provider: Arc::clone(&self.provider),
+            ws_provider: self.ws_provider.clone(),
             l1_core_contract: self.l1_core_contract.clone(),
             l1_block_metrics: self.l1_block_metrics.clone(),
         }
@@ -63,15 +80,191 @@ impl Clone for EthereumClient {
 }
 
 impl EthereumClient {
-    /// Create a new EthereumClient instance with the given RPC URL
-    pub async fn new(url: Url, l1_core_address: Address, l1_block_metrics: L1BlockMetrics) -> anyhow::Result<Self> {
+    /// Create a new EthereumClient instance with the given RPC URL.
+    ///
+    /// When `ws_url` is provided, the client will try to connect to it in order to subscribe to new heads
+    /// and core contract logs. Failing to connect is not an error: the client falls back to polling.
+    pub async fn new(
+        url: Url,
+        ws_url: Option<Url>,
+        l1_core_address: Address,
+        l1_block_metrics: L1BlockMetrics,
+    ) -> anyhow::Result<Self> {
         let provider = ProviderBuilder::new().on_http(url);
 
         EthereumClient::assert_core_contract_exists(&provider, l1_core_address).await?;
 
         let core_contract = StarknetCoreContract::new(l1_core_address, provider.clone());
 
-        Ok(Self { provider: Arc::new(provider), l1_core_contract: core_contract, l1_block_metrics })
+        let ws_provider = match ws_url {
+            Some(ws_url) => EthereumClient::connect_ws(ws_url).await.map(Arc::new),
+            None => None,
+        };
+
+        Ok(Self { provider: Arc::new(provider), ws_provider, l1_core_contract: core_contract, l1_block_metrics })
+    }
+
+    /// Connect to the websocket endpoint, returning `None` if the endpoint does not support it.
+    async fn connect_ws(ws_url: Url) -> Option<RootProvider<PubSubFrontend>> {
+        match ProviderBuilder::new().on_ws(WsConnect::new(ws_url.as_str())).await {
+            Ok(provider) => {
+                log::info!("🔌 Connected to the L1 websocket endpoint");
+                Some(provider)
+            }
+            Err(err) => {
+                log::warn!("Could not connect to the L1 websocket endpoint, falling back to polling: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Subscribe to new L1 block heads, yielding their block number.
+    ///
+    /// Returns `None` when the websocket provider is unavailable or does not support subscriptions.
+    pub async fn subscribe_new_heads(&self) -> Option<BoxStream<'static, u64>> {
+        let ws_provider = self.ws_provider.as_ref()?;
+        match ws_provider.subscribe_blocks().await {
+            Ok(subscription) => {
+                Some(subscription.into_stream().filter_map(|block| async move { block.header.number }).boxed())
+            }
+            Err(err) => {
+                log::warn!("Could not subscribe to new L1 heads, falling back to polling: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Subscribe to the logs of the given event emitted by the core contract.
+    ///
+    /// Returns `None` when the websocket provider is unavailable or does not support subscriptions.
+    pub async fn subscribe_core_contract_event<E: SolEvent + Send + 'static>(&self) -> Option<L1EventStream<E>> {
+        let ws_provider = self.ws_provider.as_ref()?;
+        let filter = Filter::new().address(*self.l1_core_contract.address()).event_signature(E::SIGNATURE_HASH);
+        match ws_provider.subscribe_logs(&filter).await {
+            Ok(subscription) => Some(
+                subscription
+                    .into_stream()
+                    // Logs of blocks dropped by an L1 reorg are sent again with `removed` set.
+                    .filter(|log| future::ready(!log.removed))
+                    .map(|log| {
+                        let event = log.log_decode::<E>().context("Decoding core contract log")?.inner.data;
+                        Ok((event, log))
+                    })
+                    .boxed(),
+            ),
+            Err(err) => {
+                log::warn!("Could not subscribe to the core contract logs, falling back to polling: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Poll the http provider for the logs of the given event emitted by the core contract.
+    pub async fn watch_core_contract_event<E: SolEvent + Send + 'static>(&self) -> anyhow::Result<L1EventStream<E>> {
+        let event_filter = self.l1_core_contract.event_filter::<E>();
+        let event_stream = event_filter.watch().await.context("Failed to watch event filter")?.into_stream();
+        Ok(event_stream.map(|res| res.context("Decoding core contract log")).boxed())
+    }
+
+    /// Stream of the logs of the given event emitted by the core contract. The logs are received through
+    /// `eth_subscribe` when a websocket endpoint is available, and we fall back to polling the http endpoint if the
+    /// subscription cannot be created or gets closed.
+    ///
+    /// When `from_block` is set, the logs emitted since this L1 block are yielded first.
+    pub async fn core_contract_event_stream<E: SolEvent + Send + 'static>(
+        &self,
+        from_block: Option<u64>,
+    ) -> anyhow::Result<L1EventStream<E>> {
+        let events = match self.subscribe_core_contract_event::<E>().await {
+            Some(ws_events) => {
+                let client = self.clone();
+                let polling_fallback = stream::once(async move {
+                    log::warn!("L1 websocket subscription closed, falling back to polling the core contract logs");
+                    client.watch_core_contract_event::<E>().await
+                })
+                .try_flatten();
+                ws_events.chain(polling_fallback).boxed()
+            }
+            None => self.watch_core_contract_event::<E>().await?,
+        };
+
+        let Some(from_block) = from_block else { return Ok(events) };
+        // The subscription is created before fetching the past logs so that no log is missed in between, the logs
+        // it receives for the blocks we already fetched are skipped.
+        let to_block = self.get_latest_block_number().await?;
+        let past_events = self.get_core_contract_events::<E>(from_block, to_block).await?;
+        let events =
+            events.try_filter(move |(_, log)| future::ready(log.block_number.map_or(true, |block| block > to_block)));
+        Ok(stream::iter(past_events.into_iter().map(Ok)).chain(events).boxed())
+    }
+
+    /// Stream of the logs of the given event emitted by the core contract in finalized L1 blocks, from `from_block`
+    /// included. These logs cannot be reverted by an L1 reorg. The finalized block is polled at the poll interval of the
+    /// http provider, like the event filters, and errors are logged before polling again.
+    pub fn finalized_core_contract_event_stream<E: SolEvent + Send + 'static>(
+        &self,
+        from_block: u64,
+    ) -> L1EventStream<E> {
+        stream::unfold((self.clone(), from_block), |(client, from_block)| async move {
+            loop {
+                match client.get_finalized_core_contract_events::<E>(from_block).await {
+                    Ok(Some((events, finalized_block))) => {
+                        let events = stream::iter(events.into_iter().map(Ok));
+                        return Some((events, (client, finalized_block + 1)));
+                    }
+                    Ok(None) => {}
+                    Err(err) => log::warn!("Could not get the finalized core contract logs: {err:#}"),
+                }
+                tokio::time::sleep(client.provider.client().poll_interval()).await;
+            }
+        })
+        .flatten()
+        .boxed()
+    }
+
+    /// Get the logs of the given event emitted by the core contract from `from_block` up to the finalized block, with
+    /// the number of the finalized block. Returns `None` when `from_block` is not finalized yet.
+    async fn get_finalized_core_contract_events<E: SolEvent>(
+        &self,
+        from_block: u64,
+    ) -> anyhow::Result<Option<(Vec<(E, Log)>, u64)>> {
+        let finalized_block = self.get_finalized_block_number().await?;
+        if finalized_block < from_block {
+            return Ok(None);
+        }
+        Ok(Some((self.get_core_contract_events::<E>(from_block, finalized_block).await?, finalized_block)))
+    }
+
+    /// Retrieves the number of the latest finalized Ethereum block.
+    pub async fn get_finalized_block_number(&self) -> anyhow::Result<u64> {
+        let block = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Finalized, false)
+            .await
+            .context("Getting the finalized L1 block")?
+            .context("No finalized L1 block")?;
+        block.header.number.context("Missing number in the finalized L1 block")
+    }
+
+    /// Get the logs of the given event emitted by the core contract between `from_block` and `to_block` included.
+    pub async fn get_core_contract_events<E: SolEvent>(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> anyhow::Result<Vec<(E, Log)>> {
+        let filter = Filter::new()
+            .address(*self.l1_core_contract.address())
+            .event_signature(E::SIGNATURE_HASH)
+            .from_block(from_block)
+            .to_block(to_block);
+        let logs = self.provider.get_logs(&filter).await.context("Getting the core contract logs")?;
+        logs.into_iter()
+            .filter(|log| !log.removed)
+            .map(|log| {
+                let event = log.log_decode::<E>().context("Decoding core contract log")?.inner.data;
+                Ok((event, log))
+            })
+            .collect()
     }
 
     /// Assert that L1 Core contract exists by checking its bytecode.
@@ -207,7 +400,12 @@ pub mod eth_client_getter_test {
         let prometheus_service = MetricsService::new(true, false, 9615).unwrap();
         let l1_block_metrics = L1BlockMetrics::register(prometheus_service.registry()).unwrap();
 
-        EthereumClient { provider: Arc::new(provider), l1_core_contract: contract.clone(), l1_block_metrics }
+        EthereumClient {
+            provider: Arc::new(provider),
+            ws_provider: None,
+            l1_core_contract: contract.clone(),
+            l1_block_metrics,
+        }
     }
 
     #[serial]
@@ -223,7 +421,7 @@ pub mod eth_client_getter_test {
         let prometheus_service = MetricsService::new(true, false, 9615).unwrap();
         let l1_block_metrics = L1BlockMetrics::register(prometheus_service.registry()).unwrap();
 
-        let new_client_result = EthereumClient::new(rpc_url, core_contract_address, l1_block_metrics).await;
+        let new_client_result = EthereumClient::new(rpc_url, None, core_contract_address, l1_block_metrics).await;
         assert!(new_client_result.is_err(), "EthereumClient::new should fail with an invalid core contract address");
     }
 
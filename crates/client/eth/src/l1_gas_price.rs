@@ -1,19 +1,97 @@
 use crate::client::EthereumClient;
+use crate::strk_price::StrkPriceSource;
 use alloy::eips::BlockNumberOrTag;
+use alloy::primitives::Address;
 use alloy::providers::Provider;
+use alloy::sol;
 use anyhow::Context;
+use futures::StreamExt;
 use mc_mempool::{GasPriceProvider, L1DataProvider};
+use mp_block::header::GasPrices;
 use std::time::{Duration, UNIX_EPOCH};
 
 use mp_utils::wait_or_graceful_shutdown;
 use std::time::SystemTime;
 
+sol!(
+    #[sol(rpc)]
+    interface GasPriceOracle {
+        function getGasPrices() external view returns (uint128 ethL1GasPrice, uint128 ethL1DataGasPrice, uint128 strkL1GasPrice, uint128 strkL1DataGasPrice);
+    }
+);
+
+/// Where the gas prices published to the [`GasPriceProvider`] come from.
+#[derive(Clone)]
+pub enum GasPriceSource {
+    /// Constant gas prices, set once at startup.
+    Fixed(GasPrices),
+    /// Derived from the L1 base fee and the average blob base fee over the last hour. The STRK gas prices
+    /// are converted from the ETH ones using `strk_price_source`, and are left untouched when it is `None`.
+    L1BaseFee { eth_client: EthereumClient, strk_price_source: Option<StrkPriceSource> },
+    /// Read from a gas price oracle contract deployed on L1.
+    Oracle { eth_client: EthereumClient, oracle_address: Address },
+}
+
+/// Inclusive minimum and maximum of a gas price. Unset bounds are not enforced.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PriceBounds {
+    pub min: Option<u128>,
+    pub max: Option<u128>,
+}
+
+impl PriceBounds {
+    pub fn new(min: Option<u128>, max: Option<u128>) -> anyhow::Result<Self> {
+        if let (Some(min), Some(max)) = (min, max) {
+            anyhow::ensure!(min <= max, "The minimum gas price ({min}) is greater than the maximum gas price ({max})");
+        }
+        Ok(Self { min, max })
+    }
+
+    pub fn apply(&self, price: u128) -> u128 {
+        let price = self.min.map_or(price, |min| price.max(min));
+        self.max.map_or(price, |max| price.min(max))
+    }
+}
+
+/// Caps and floors applied to the gas prices before they are published, protecting users from L1 fee spikes
+/// and from zero prices.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasPriceBounds {
+    pub eth_l1_gas_price: PriceBounds,
+    pub eth_l1_data_gas_price: PriceBounds,
+    pub strk_l1_gas_price: PriceBounds,
+    pub strk_l1_data_gas_price: PriceBounds,
+}
+
+impl GasPriceBounds {
+    pub fn apply(&self, gas_prices: GasPrices) -> GasPrices {
+        GasPrices {
+            eth_l1_gas_price: self.eth_l1_gas_price.apply(gas_prices.eth_l1_gas_price),
+            strk_l1_gas_price: self.strk_l1_gas_price.apply(gas_prices.strk_l1_gas_price),
+            eth_l1_data_gas_price: self.eth_l1_data_gas_price.apply(gas_prices.eth_l1_data_gas_price),
+            strk_l1_data_gas_price: self.strk_l1_data_gas_price.apply(gas_prices.strk_l1_data_gas_price),
+        }
+    }
+}
+
+impl GasPriceSource {
+    fn eth_client(&self) -> Option<&EthereumClient> {
+        match self {
+            GasPriceSource::Fixed(_) => None,
+            GasPriceSource::L1BaseFee { eth_client, .. } | GasPriceSource::Oracle { eth_client, .. } => {
+                Some(eth_client)
+            }
+        }
+    }
+}
+
 pub async fn gas_price_worker_once(
-    eth_client: &EthereumClient,
+    gas_price_source: &GasPriceSource,
+    gas_price_bounds: &GasPriceBounds,
     l1_gas_provider: GasPriceProvider,
     gas_price_poll_ms: Duration,
 ) -> anyhow::Result<()> {
-    match update_gas_price(eth_client, l1_gas_provider.clone()).await {
+    match update_gas_price(gas_price_source, gas_price_bounds, l1_gas_provider.clone()).await {
         Ok(_) => log::trace!("Updated gas prices"),
         Err(e) => log::error!("Failed to update gas prices: {:?}", e),
     }
@@ -32,21 +110,86 @@ pub async fn gas_price_worker_once(
 
     Ok(())
 }
+/// Keeps the gas prices up to date.
+///
+/// When a websocket endpoint is available, gas prices are refreshed on every new L1 head. Otherwise, or if
+/// the subscription gets closed, they are polled every `gas_price_poll_ms`. Fixed gas prices are set once
+/// and never refreshed.
 pub async fn gas_price_worker(
-    eth_client: &EthereumClient,
+    gas_price_source: &GasPriceSource,
+    gas_price_bounds: &GasPriceBounds,
     l1_gas_provider: GasPriceProvider,
     gas_price_poll_ms: Duration,
 ) -> anyhow::Result<()> {
     l1_gas_provider.update_last_update_timestamp();
+
+    let Some(eth_client) = gas_price_source.eth_client() else {
+        return gas_price_worker_once(gas_price_source, gas_price_bounds, l1_gas_provider, gas_price_poll_ms).await;
+    };
+
+    if let Some(mut new_heads) = eth_client.subscribe_new_heads().await {
+        log::debug!("gas_price_worker: using new heads subscription");
+        loop {
+            match wait_or_graceful_shutdown(new_heads.next()).await {
+                // Graceful shutdown
+                None => return Ok(()),
+                Some(Some(_block_number)) => {
+                    gas_price_worker_once(
+                        gas_price_source,
+                        gas_price_bounds,
+                        l1_gas_provider.clone(),
+                        gas_price_poll_ms,
+                    )
+                    .await?
+                }
+                Some(None) => {
+                    log::warn!("L1 new heads subscription closed, falling back to polling for gas prices");
+                    break;
+                }
+            }
+        }
+    }
+
     let mut interval = tokio::time::interval(gas_price_poll_ms);
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
-        gas_price_worker_once(eth_client, l1_gas_provider.clone(), gas_price_poll_ms).await?;
+        gas_price_worker_once(gas_price_source, gas_price_bounds, l1_gas_provider.clone(), gas_price_poll_ms).await?;
     }
     Ok(())
 }
 
-async fn update_gas_price(eth_client: &EthereumClient, l1_gas_provider: GasPriceProvider) -> anyhow::Result<()> {
+async fn update_gas_price(
+    gas_price_source: &GasPriceSource,
+    gas_price_bounds: &GasPriceBounds,
+    l1_gas_provider: GasPriceProvider,
+) -> anyhow::Result<()> {
+    // Prices that are not derived from the source keep their current value.
+    let mut gas_prices = l1_gas_provider.get_gas_prices();
+    match gas_price_source {
+        GasPriceSource::Fixed(fixed_gas_prices) => gas_prices = fixed_gas_prices.clone(),
+        GasPriceSource::L1BaseFee { eth_client, strk_price_source } => {
+            update_l1_base_fee_gas_price(eth_client, &mut gas_prices).await?;
+            if let Some(strk_price_source) = strk_price_source {
+                update_strk_gas_price(strk_price_source, &mut gas_prices).await?;
+            }
+        }
+        GasPriceSource::Oracle { eth_client, oracle_address } => {
+            gas_prices = get_oracle_gas_prices(eth_client, *oracle_address).await?
+        }
+    }
+    l1_gas_provider.set_gas_prices(gas_price_bounds.apply(gas_prices));
+
+    l1_gas_provider.update_last_update_timestamp();
+
+    // Update block number separately to avoid holding the lock for too long
+    if let Some(eth_client) = gas_price_source.eth_client() {
+        update_l1_block_metrics(eth_client, l1_gas_provider).await?;
+    }
+
+    Ok(())
+}
+
+async fn update_l1_base_fee_gas_price(eth_client: &EthereumClient, gas_prices: &mut GasPrices) -> anyhow::Result<()> {
     let block_number = eth_client.get_latest_block_number().await?;
     let fee_history = eth_client.provider.get_fee_history(300, BlockNumberOrTag::Number(block_number), &[]).await?;
 
@@ -60,17 +203,34 @@ async fn update_gas_price(eth_client: &EthereumClient, l1_gas_provider: GasPrice
 
     let eth_gas_price = fee_history.base_fee_per_gas.last().context("Getting eth gas price")?;
 
-    l1_gas_provider.update_eth_l1_gas_price(*eth_gas_price);
-    l1_gas_provider.update_eth_l1_data_gas_price(avg_blob_base_fee);
+    gas_prices.eth_l1_gas_price = *eth_gas_price;
+    gas_prices.eth_l1_data_gas_price = avg_blob_base_fee;
 
-    l1_gas_provider.update_last_update_timestamp();
+    Ok(())
+}
 
-    // Update block number separately to avoid holding the lock for too long
-    update_l1_block_metrics(eth_client, l1_gas_provider).await?;
+/// Converts the ETH gas prices into STRK gas prices. The conversion uses the ETH prices before any bound is applied.
+async fn update_strk_gas_price(strk_price_source: &StrkPriceSource, gas_prices: &mut GasPrices) -> anyhow::Result<()> {
+    let strk_price = strk_price_source.get_strk_price().await.context("Getting the STRK price")?;
+
+    gas_prices.strk_l1_gas_price = strk_price.wei_to_fri(gas_prices.eth_l1_gas_price)?;
+    gas_prices.strk_l1_data_gas_price = strk_price.wei_to_fri(gas_prices.eth_l1_data_gas_price)?;
 
     Ok(())
 }
 
+async fn get_oracle_gas_prices(eth_client: &EthereumClient, oracle_address: Address) -> anyhow::Result<GasPrices> {
+    let oracle = GasPriceOracle::new(oracle_address, eth_client.provider.as_ref());
+    let prices = oracle.getGasPrices().call().await.context("Reading gas prices from the oracle contract")?;
+
+    Ok(GasPrices {
+        eth_l1_gas_price: prices.ethL1GasPrice,
+        strk_l1_gas_price: prices.strkL1GasPrice,
+        eth_l1_data_gas_price: prices.ethL1DataGasPrice,
+        strk_l1_data_gas_price: prices.strkL1DataGasPrice,
+    })
+}
+
 async fn update_l1_block_metrics(eth_client: &EthereumClient, l1_gas_provider: GasPriceProvider) -> anyhow::Result<()> {
     // Get the latest block number
     let latest_block_number = eth_client.get_latest_block_number().await?;
@@ -82,8 +242,7 @@ async fn update_l1_block_metrics(eth_client: &EthereumClient, l1_gas_provider: G
     // Update the metrics
     eth_client.l1_block_metrics.l1_block_number.set(latest_block_number as f64);
     eth_client.l1_block_metrics.l1_gas_price_wei.set(eth_gas_price as f64);
-
-    // We're ignoring l1_gas_price_strk
+    eth_client.l1_block_metrics.l1_gas_price_strk.set(current_gas_price.strk_l1_gas_price as f64);
 
     Ok(())
 }
@@ -122,7 +281,15 @@ mod eth_client_gas_price_worker_test {
         let worker_handle: JoinHandle<anyhow::Result<()>> = tokio::spawn({
             let eth_client = eth_client.clone();
             let l1_gas_provider = l1_gas_provider.clone();
-            async move { gas_price_worker(&eth_client, l1_gas_provider, Duration::from_millis(200)).await }
+            async move {
+                gas_price_worker(
+                    &GasPriceSource::L1BaseFee { eth_client, strk_price_source: None },
+                    &GasPriceBounds::default(),
+                    l1_gas_provider,
+                    Duration::from_millis(200),
+                )
+                .await
+            }
         });
 
         // Wait for a short duration to allow the worker to run
@@ -160,7 +327,12 @@ mod eth_client_gas_price_worker_test {
         let l1_gas_provider = GasPriceProvider::new();
 
         // Run the worker for a short time
-        let worker_handle = gas_price_worker_once(&eth_client, l1_gas_provider.clone(), Duration::from_millis(200));
+        let worker_handle = gas_price_worker_once(
+            &GasPriceSource::L1BaseFee { eth_client, strk_price_source: None },
+            &GasPriceBounds::default(),
+            l1_gas_provider.clone(),
+            Duration::from_millis(200),
+        );
 
         // Wait for the worker to complete
         worker_handle.await.expect("issue with the gas worker");
@@ -208,7 +380,12 @@ mod eth_client_gas_price_worker_test {
 
         let result = timeout(
             timeout_duration,
-            gas_price_worker(&eth_client, l1_gas_provider.clone(), Duration::from_millis(200)),
+            gas_price_worker(
+                &GasPriceSource::L1BaseFee { eth_client, strk_price_source: None },
+                &GasPriceBounds::default(),
+                l1_gas_provider.clone(),
+                Duration::from_millis(200),
+            ),
         )
         .await;
 
@@ -245,7 +422,13 @@ mod eth_client_gas_price_worker_test {
         l1_gas_provider.update_last_update_timestamp();
 
         // Update gas prices
-        update_gas_price(&eth_client, l1_gas_provider.clone()).await.expect("Failed to update gas prices");
+        update_gas_price(
+            &GasPriceSource::L1BaseFee { eth_client, strk_price_source: None },
+            &GasPriceBounds::default(),
+            l1_gas_provider.clone(),
+        )
+        .await
+        .expect("Failed to update gas prices");
 
         // Access the updated gas prices
         let updated_prices = l1_gas_provider.get_gas_prices();
@@ -266,4 +449,71 @@ mod eth_client_gas_price_worker_test {
 
         assert!(time_since_last_update.as_secs() < 60, "Last update timestamp should be within the last minute");
     }
+
+    #[tokio::test]
+    async fn gas_price_worker_with_fixed_prices_works() {
+        let gas_prices = GasPrices {
+            eth_l1_gas_price: 100,
+            strk_l1_gas_price: 200,
+            eth_l1_data_gas_price: 1,
+            strk_l1_data_gas_price: 2,
+        };
+        let l1_gas_provider = GasPriceProvider::new();
+
+        let result = timeout(
+            Duration::from_secs(2),
+            gas_price_worker(
+                &GasPriceSource::Fixed(gas_prices.clone()),
+                &GasPriceBounds::default(),
+                l1_gas_provider.clone(),
+                Duration::from_millis(200),
+            ),
+        )
+        .await;
+
+        assert!(matches!(result, Ok(Ok(()))), "Fixed gas prices should be set once without polling");
+        assert_eq!(l1_gas_provider.get_gas_prices(), gas_prices);
+    }
+
+    #[tokio::test]
+    async fn gas_price_bounds_are_applied() {
+        let gas_prices = GasPrices {
+            eth_l1_gas_price: 1_000,
+            strk_l1_gas_price: 0,
+            eth_l1_data_gas_price: 5,
+            strk_l1_data_gas_price: 7,
+        };
+        let gas_price_bounds = GasPriceBounds {
+            eth_l1_gas_price: PriceBounds::new(None, Some(500)).unwrap(),
+            strk_l1_gas_price: PriceBounds::new(Some(10), None).unwrap(),
+            eth_l1_data_gas_price: PriceBounds::new(Some(1), Some(10)).unwrap(),
+            strk_l1_data_gas_price: PriceBounds::default(),
+        };
+        let l1_gas_provider = GasPriceProvider::new();
+
+        gas_price_worker_once(
+            &GasPriceSource::Fixed(gas_prices),
+            &gas_price_bounds,
+            l1_gas_provider.clone(),
+            Duration::from_millis(200),
+        )
+        .await
+        .expect("Failed to update gas prices");
+
+        assert_eq!(
+            l1_gas_provider.get_gas_prices(),
+            GasPrices {
+                eth_l1_gas_price: 500,
+                strk_l1_gas_price: 10,
+                eth_l1_data_gas_price: 5,
+                strk_l1_data_gas_price: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn price_bounds_with_min_above_max_fails() {
+        assert!(PriceBounds::new(Some(2), Some(1)).is_err());
+        assert!(PriceBounds::new(Some(1), Some(1)).is_ok());
+    }
 }
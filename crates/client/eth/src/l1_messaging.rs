@@ -1,15 +1,16 @@
-use alloy::eips::BlockNumberOrTag;
 use anyhow::Context;
 use futures::StreamExt;
 use std::sync::Arc;
 
-use crate::client::StarknetCoreContract::LogMessageToL2;
-use crate::client::{EthereumClient, StarknetCoreContract};
+use crate::client::EthereumClient;
+use crate::client::StarknetCoreContract::{LogMessageToL2, MessageToL2Canceled, MessageToL2CancellationStarted};
 use crate::utils::u256_to_felt;
-use alloy::primitives::{keccak256, FixedBytes, U256};
+use alloy::primitives::{keccak256, Address, FixedBytes, U256};
 use alloy::sol_types::SolValue;
 use blockifier::transaction::transactions::L1HandlerTransaction as BlockifierL1HandlerTransaction;
-use mc_db::{l1_db::LastSyncedEventBlock, MadaraBackend};
+use futures::stream;
+use mc_db::l1_db::{L1ToL2MessageCancellation, LastSyncedEventBlock};
+use mc_db::MadaraBackend;
 use mp_utils::channel_wait_or_graceful_shutdown;
 use starknet_api::core::{ChainId, ContractAddress, EntryPointSelector, Nonce};
 use starknet_api::transaction::{
@@ -52,14 +53,10 @@ pub async fn sync(backend: &MadaraBackend, client: &EthereumClient, chain_id: &C
             return Err(e.into());
         }
     };
-    let event_filter = client.l1_core_contract.event_filter::<StarknetCoreContract::LogMessageToL2>();
-    let mut event_stream = event_filter
-        .from_block(last_synced_event_block.block_number)
-        .select(BlockNumberOrTag::Finalized)
-        .watch()
-        .await
-        .context("Failed to watch event filter")?
-        .into_stream();
+    // Only the messages of finalized L1 blocks are turned into L1 handler transactions, an L1 reorg could otherwise
+    // remove a message which was already executed on L2.
+    let mut event_stream =
+        client.finalized_core_contract_event_stream::<LogMessageToL2>(last_synced_event_block.block_number);
 
     while let Some(event_result) = channel_wait_or_graceful_shutdown(event_stream.next()).await {
         if let Ok((event, meta)) = event_result {
@@ -74,9 +71,16 @@ pub async fn sync(backend: &MadaraBackend, client: &EthereumClient, chain_id: &C
             // Check if cancellation was initiated
             let event_hash = get_l1_to_l2_msg_hash(&event)?;
             tracing::info!("⟠ Checking for cancelation, event hash : {:?}", event_hash);
+            let cancellation = backend.get_l1_to_l2_message_cancellation(&event_hash.0)?;
             let cancellation_timestamp = client.get_l1_to_l2_message_cancellations(event_hash).await?;
-            if cancellation_timestamp != Felt::ZERO {
-                tracing::info!("⟠ L1 Message was cancelled in block at timestamp : {:?}", cancellation_timestamp);
+            if cancellation.is_some() || cancellation_timestamp != Felt::ZERO {
+                match cancellation {
+                    Some(cancellation) => tracing::info!("⟠ L1 Message was cancelled: {:?}", cancellation),
+                    None => tracing::info!(
+                        "⟠ L1 Message was cancelled in block at timestamp : {:?}",
+                        cancellation_timestamp
+                    ),
+                }
                 let tx_nonce = Nonce(u256_to_felt(event.nonce)?);
                 // cancelled message nonce should be inserted to avoid reprocessing
                 match backend.has_l1_messaging_nonce(tx_nonce) {
@@ -121,6 +125,76 @@ pub async fn sync(backend: &MadaraBackend, client: &EthereumClient, chain_id: &C
     Ok(())
 }
 
+/// Tracks the cancellations of L1->L2 messages from the core contract events.
+///
+/// Messages whose cancellation has started are recorded in the database, and are skipped by the messaging sync: they
+/// are never turned into L1 handler transactions, so they never reach the mempool or the block production.
+///
+/// The tracking resumes from the L1 block of the last recorded cancellation, so that the cancellations emitted while
+/// the node was stopped are not missed.
+pub async fn cancellation_sync(backend: &MadaraBackend, client: &EthereumClient) -> anyhow::Result<()> {
+    tracing::info!("⟠ Starting L1 Message Cancellations Tracking...");
+
+    let from_block = match backend.messaging_last_synced_l1_cancellation_block()? {
+        Some(block_number) => block_number,
+        // First start: the cancellations are tracked from now on.
+        None => {
+            let block_number = client.get_latest_block_number().await?;
+            backend.messaging_update_last_synced_l1_cancellation_block(block_number)?;
+            block_number
+        }
+    };
+    let from_block = Some(from_block);
+    let started = client.core_contract_event_stream::<MessageToL2CancellationStarted>(from_block).await?.map(|res| {
+        let (event, log) = res?;
+        let msg_hash =
+            l1_to_l2_msg_hash(event.fromAddress, event.toAddress, event.nonce, event.selector, &event.payload);
+        let l1_block_number = log.block_number.context("Missing block number in log")?;
+        anyhow::Ok((msg_hash, L1ToL2MessageCancellation::Started { l1_block_number }))
+    });
+    let cancelled = client.core_contract_event_stream::<MessageToL2Canceled>(from_block).await?.map(|res| {
+        let (event, log) = res?;
+        let msg_hash =
+            l1_to_l2_msg_hash(event.fromAddress, event.toAddress, event.nonce, event.selector, &event.payload);
+        let l1_block_number = log.block_number.context("Missing block number in log")?;
+        anyhow::Ok((msg_hash, L1ToL2MessageCancellation::Cancelled { l1_block_number }))
+    });
+    let mut events = stream::select(started, cancelled);
+
+    while let Some(event_result) = channel_wait_or_graceful_shutdown(events.next()).await {
+        match event_result {
+            Ok((msg_hash, cancellation)) => {
+                if record_cancellation(backend, &msg_hash, cancellation)? {
+                    tracing::info!("⟠ L1 Message {:?} cancellation: {:?}", msg_hash, cancellation);
+                }
+                let (L1ToL2MessageCancellation::Started { l1_block_number }
+                | L1ToL2MessageCancellation::Cancelled { l1_block_number }) = cancellation;
+                if backend.messaging_last_synced_l1_cancellation_block()?.map_or(true, |last| last < l1_block_number) {
+                    backend.messaging_update_last_synced_l1_cancellation_block(l1_block_number)?;
+                }
+            }
+            Err(e) => tracing::error!("⟠ Unexpected error while tracking L1 Message cancellations: {:?}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Records the cancellation of a message. A finalized cancellation is never overwritten by a cancellation start,
+/// whatever the order the events are received in. Returns whether the status changed.
+fn record_cancellation(
+    backend: &MadaraBackend,
+    msg_hash: &FixedBytes<32>,
+    cancellation: L1ToL2MessageCancellation,
+) -> anyhow::Result<bool> {
+    let current = backend.get_l1_to_l2_message_cancellation(&msg_hash.0)?;
+    if matches!(current, Some(L1ToL2MessageCancellation::Cancelled { .. })) || current == Some(cancellation) {
+        return Ok(false);
+    }
+    backend.set_l1_to_l2_message_cancellation(&msg_hash.0, cancellation)?;
+    Ok(true)
+}
+
 async fn process_l1_message(
     backend: &MadaraBackend,
     event: &LogMessageToL2,
@@ -194,16 +268,19 @@ pub fn parse_handle_l1_message_transaction(event: &LogMessageToL2) -> anyhow::Re
 
 /// Computes the message hashed with the given event data
 fn get_l1_to_l2_msg_hash(event: &LogMessageToL2) -> anyhow::Result<FixedBytes<32>> {
-    let data = (
-        [0u8; 12],
-        event.fromAddress.0 .0,
-        event.toAddress,
-        event.nonce,
-        event.selector,
-        U256::from(event.payload.len()),
-        event.payload.clone(),
-    );
-    Ok(keccak256(data.abi_encode_packed()))
+    Ok(l1_to_l2_msg_hash(event.fromAddress, event.toAddress, event.nonce, event.selector, &event.payload))
+}
+
+/// Computes the hash of an L1->L2 message, as the core contract does.
+fn l1_to_l2_msg_hash(
+    from_address: Address,
+    to_address: U256,
+    nonce: U256,
+    selector: U256,
+    payload: &[U256],
+) -> FixedBytes<32> {
+    let data = ([0u8; 12], from_address.0 .0, to_address, nonce, selector, U256::from(payload.len()), payload.to_vec());
+    keccak256(data.abi_encode_packed())
 }
 
 #[cfg(test)]
@@ -217,17 +294,18 @@ mod l1_messaging_tests {
             EthereumClient, L1BlockMetrics,
             StarknetCoreContract::{self, LogMessageToL2},
         },
-        l1_messaging::get_l1_to_l2_msg_hash,
+        l1_messaging::{get_l1_to_l2_msg_hash, record_cancellation},
         utils::felt_to_u256,
     };
     use alloy::{
         hex::FromHex,
         node_bindings::{Anvil, AnvilInstance},
-        primitives::{Address, U256},
+        primitives::{Address, FixedBytes, U256},
         providers::{ProviderBuilder, RootProvider},
         sol,
         transports::http::{Client, Http},
     };
+    use mc_db::l1_db::L1ToL2MessageCancellation;
     use mc_db::DatabaseService;
     use mc_metrics::{MetricsRegistry, MetricsService};
     use mp_chain_config::ChainConfig;
@@ -363,6 +441,7 @@ mod l1_messaging_tests {
 
         let eth_client = EthereumClient {
             provider: Arc::new(provider.clone()),
+            ws_provider: None,
             l1_core_contract: core_contract.clone(),
             l1_block_metrics: l1_block_metrics.clone(),
         };
@@ -509,6 +588,27 @@ mod l1_messaging_tests {
         worker_handle.abort();
     }
 
+    #[tokio::test]
+    async fn finalized_cancellation_is_not_overwritten() {
+        let chain_config = Arc::new(ChainConfig::madara_test());
+        let temp_dir = TempDir::new().expect("issue while creating temporary directory");
+        let db =
+            DatabaseService::new(&temp_dir.path().join("data"), None, false, chain_config, &MetricsRegistry::dummy())
+                .await
+                .expect("Failed to create database service");
+        let msg_hash = FixedBytes::<32>::from([1u8; 32]);
+
+        let started = L1ToL2MessageCancellation::Started { l1_block_number: 10 };
+        let cancelled = L1ToL2MessageCancellation::Cancelled { l1_block_number: 20 };
+        assert!(record_cancellation(db.backend(), &msg_hash, started).unwrap());
+        assert!(!record_cancellation(db.backend(), &msg_hash, started).unwrap());
+        assert!(record_cancellation(db.backend(), &msg_hash, cancelled).unwrap());
+        // A late cancellation start event does not revert the finalized cancellation
+        assert!(!record_cancellation(db.backend(), &msg_hash, started).unwrap());
+
+        assert_eq!(db.backend().get_l1_to_l2_message_cancellation(&msg_hash.0).unwrap(), Some(cancelled));
+    }
+
     /// Test taken from starknet.rs to ensure consistency
     /// https://github.com/xJonathanLEI/starknet-rs/blob/2ddc69479d326ed154df438d22f2d720fbba746e/starknet-core/src/types/msg.rs#L96
     #[test]
@@ -1,7 +1,13 @@
+pub mod blob;
 pub mod client;
 pub mod error;
 pub mod l1_gas_price;
 pub mod l1_messaging;
+pub mod settlement;
+pub mod starknet_client;
+pub mod state_root_verification;
 pub mod state_update;
+pub mod state_update_submission;
+pub mod strk_price;
 pub mod sync;
 pub mod utils;
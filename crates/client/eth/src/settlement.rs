@@ -0,0 +1,72 @@
+//! Abstraction over the layer the chain settles on.
+//!
+//! The state of the chain is verified by a core contract deployed on the settlement layer. This is
+//! Ethereum for Starknet and L2 appchains, and Starknet for L3 appchains.
+
+use crate::client::{EthereumClient, L1BlockMetrics, StarknetCoreContract};
+use crate::state_update::L1StateUpdate;
+use crate::utils::convert_log_state_update;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use starknet_types_core::felt::Felt;
+
+/// A stream of the state updates verified by the core contract.
+pub type StateUpdateStream = BoxStream<'static, anyhow::Result<L1StateUpdate>>;
+
+/// A client to the settlement layer, used to follow the state verified by the core contract.
+#[async_trait::async_trait]
+pub trait SettlementClient: Send + Sync {
+    /// Human readable name of the settlement layer, for displaying to the console.
+    fn name(&self) -> &'static str;
+
+    fn l1_block_metrics(&self) -> &L1BlockMetrics;
+
+    /// Retrieves the latest block number of the settlement layer.
+    async fn get_latest_block_number(&self) -> anyhow::Result<u64>;
+
+    /// Get the last Starknet block number verified by the core contract.
+    async fn get_last_verified_block_number(&self) -> anyhow::Result<u64>;
+
+    /// Get the last Starknet state root verified by the core contract.
+    async fn get_last_state_root(&self) -> anyhow::Result<Felt>;
+
+    /// Get the last Starknet block hash verified by the core contract.
+    async fn get_last_verified_block_hash(&self) -> anyhow::Result<Felt>;
+
+    /// Returns a stream of the state updates verified by the core contract from now on.
+    async fn state_update_stream(&self) -> anyhow::Result<StateUpdateStream>;
+}
+
+#[async_trait::async_trait]
+impl SettlementClient for EthereumClient {
+    fn name(&self) -> &'static str {
+        "Ethereum"
+    }
+
+    fn l1_block_metrics(&self) -> &L1BlockMetrics {
+        &self.l1_block_metrics
+    }
+
+    async fn get_latest_block_number(&self) -> anyhow::Result<u64> {
+        EthereumClient::get_latest_block_number(self).await
+    }
+
+    async fn get_last_verified_block_number(&self) -> anyhow::Result<u64> {
+        EthereumClient::get_last_verified_block_number(self).await
+    }
+
+    async fn get_last_state_root(&self) -> anyhow::Result<Felt> {
+        EthereumClient::get_last_state_root(self).await
+    }
+
+    async fn get_last_verified_block_hash(&self) -> anyhow::Result<Felt> {
+        EthereumClient::get_last_verified_block_hash(self).await
+    }
+
+    /// LogStateUpdate events are received through `eth_subscribe` when a websocket endpoint is available. We
+    /// fall back to polling the http endpoint if the subscription cannot be created or gets closed.
+    async fn state_update_stream(&self) -> anyhow::Result<StateUpdateStream> {
+        let events = self.core_contract_event_stream::<StarknetCoreContract::LogStateUpdate>(None).await?;
+        Ok(events.map(|event| convert_log_state_update(event?.0)).boxed())
+    }
+}
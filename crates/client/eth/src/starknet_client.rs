@@ -0,0 +1,154 @@
+//! Settlement on Starknet (L3 mode).
+//!
+//! The core contract is deployed on Starknet and read through its JSON-RPC. It is expected to expose a
+//! `get_state` view returning `(state_root, block_number, block_hash)`, as the Piltover appchain contract does.
+
+use crate::client::L1BlockMetrics;
+use crate::settlement::{SettlementClient, StateUpdateStream};
+use crate::state_update::L1StateUpdate;
+use anyhow::{bail, Context};
+use futures::stream::{self, StreamExt};
+use mp_convert::felt_to_u64;
+use starknet_core::types::{BlockId, BlockTag, FunctionCall};
+use starknet_core::utils::get_selector_from_name;
+use starknet_providers::jsonrpc::HttpTransport;
+use starknet_providers::{JsonRpcClient, Provider};
+use starknet_types_core::felt::Felt;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Interval at which the core contract state is polled for new state updates.
+const STATE_POLL_INTERVAL: Duration = Duration::from_secs(6);
+
+#[derive(Clone)]
+pub struct StarknetClient {
+    pub provider: Arc<JsonRpcClient<HttpTransport>>,
+    pub core_contract_address: Felt,
+    pub l1_block_metrics: L1BlockMetrics,
+}
+
+impl StarknetClient {
+    /// Create a new StarknetClient instance with the given RPC URL
+    pub async fn new(url: Url, core_contract_address: Felt, l1_block_metrics: L1BlockMetrics) -> anyhow::Result<Self> {
+        let provider = JsonRpcClient::new(HttpTransport::new(url));
+
+        provider.get_class_hash_at(BlockId::Tag(BlockTag::Latest), core_contract_address).await.context(
+            "The Starknet Core Contract could not be found. Check that the L3 chain matches the Starknet RPC endpoint.",
+        )?;
+
+        Ok(Self { provider: Arc::new(provider), core_contract_address, l1_block_metrics })
+    }
+
+    /// Read the state verified by the core contract.
+    pub async fn get_state(&self) -> anyhow::Result<L1StateUpdate> {
+        let call = FunctionCall {
+            contract_address: self.core_contract_address,
+            entry_point_selector: get_selector_from_name("get_state")?,
+            calldata: vec![],
+        };
+        let res = self.provider.call(call, BlockId::Tag(BlockTag::Latest)).await.context("Calling get_state")?;
+
+        let [global_root, block_number, block_hash] = res[..] else {
+            bail!("Unexpected get_state return value: expected 3 felts, got {}", res.len());
+        };
+        let block_number = felt_to_u64(&block_number).context("Converting block number to u64")?;
+
+        Ok(L1StateUpdate { block_number, global_root, block_hash })
+    }
+}
+
+#[async_trait::async_trait]
+impl SettlementClient for StarknetClient {
+    fn name(&self) -> &'static str {
+        "Starknet"
+    }
+
+    fn l1_block_metrics(&self) -> &L1BlockMetrics {
+        &self.l1_block_metrics
+    }
+
+    async fn get_latest_block_number(&self) -> anyhow::Result<u64> {
+        Ok(self.provider.block_number().await?)
+    }
+
+    async fn get_last_verified_block_number(&self) -> anyhow::Result<u64> {
+        Ok(self.get_state().await?.block_number)
+    }
+
+    async fn get_last_state_root(&self) -> anyhow::Result<Felt> {
+        Ok(self.get_state().await?.global_root)
+    }
+
+    async fn get_last_verified_block_hash(&self) -> anyhow::Result<Felt> {
+        Ok(self.get_state().await?.block_hash)
+    }
+
+    /// Starknet has no log subscription over JSON-RPC: the core contract state is polled, and a state update
+    /// is yielded every time it changes.
+    async fn state_update_stream(&self) -> anyhow::Result<StateUpdateStream> {
+        let last_state = self.get_state().await?;
+        let mut interval = tokio::time::interval(STATE_POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        let stream =
+            stream::unfold((self.clone(), interval, last_state), |(client, mut interval, last_state)| async move {
+                loop {
+                    interval.tick().await;
+                    match client.get_state().await {
+                        Ok(state) if state == last_state => continue,
+                        Ok(state) => return Some((Ok(state.clone()), (client, interval, state))),
+                        Err(err) => log::warn!("Failed to poll the Starknet core contract state: {err:#}"),
+                    }
+                }
+            });
+
+        Ok(stream.boxed())
+    }
+}
+
+#[cfg(test)]
+mod starknet_client_tests {
+    use super::*;
+    use httpmock::MockServer;
+    use mc_metrics::MetricsRegistry;
+
+    fn create_starknet_client(url: &str) -> StarknetClient {
+        let provider = JsonRpcClient::new(HttpTransport::new(url.parse::<Url>().expect("issue while parsing URL")));
+        let l1_block_metrics = L1BlockMetrics::register(&MetricsRegistry::dummy()).unwrap();
+        StarknetClient { provider: Arc::new(provider), core_contract_address: Felt::from(0x1234), l1_block_metrics }
+    }
+
+    #[tokio::test]
+    async fn get_state_works() {
+        let mock_server = MockServer::start();
+        mock_server.mock(|when, then| {
+            when.method("POST").path("/").body_contains("starknet_call");
+            then.status(200).json_body_obj(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": ["0x456", "0x2a", "0x789"]
+            }));
+        });
+        let client = create_starknet_client(&format!("http://{}", mock_server.address()));
+
+        let state = client.get_state().await.expect("issue while getting the core contract state");
+
+        assert_eq!(
+            state,
+            L1StateUpdate { block_number: 42, global_root: Felt::from(0x456), block_hash: Felt::from(0x789) }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_state_with_unexpected_return_value_fails() {
+        let mock_server = MockServer::start();
+        mock_server.mock(|when, then| {
+            when.method("POST").path("/").body_contains("starknet_call");
+            then.status(200).json_body_obj(&serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": ["0x456"] }));
+        });
+        let client = create_starknet_client(&format!("http://{}", mock_server.address()));
+
+        assert!(client.get_state().await.is_err(), "get_state should fail when the contract returns 1 felt");
+    }
+}
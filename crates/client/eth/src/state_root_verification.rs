@@ -0,0 +1,203 @@
+//! Periodic verification of the local global state root against the one verified by the core contract.
+//!
+//! A mismatch means the local state diverged from the state settled on the settlement layer. This is
+//! reported through the logs and the `madara_l1_state_root_mismatch` metric, and can optionally halt the
+//! transaction submission endpoints until the states match again.
+
+use crate::settlement::SettlementClient;
+use crate::utils::trim_hash;
+use anyhow::Context;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mp_utils::wait_or_graceful_shutdown;
+use starknet_types_core::felt::Felt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateRootCheck {
+    /// The local state root matches the one verified by the core contract.
+    Match { block_number: u64 },
+    /// The local state root differs from the one verified by the core contract.
+    Mismatch { block_number: u64, local: Felt, settled: Felt },
+    /// The verified block has not been synced yet, or the settlement layer state changed while it was being read.
+    Skipped,
+}
+
+/// Compares the local global state root at the last verified block with the one verified by the core contract.
+pub async fn check_state_root(
+    backend: &MadaraBackend,
+    settlement_client: &dyn SettlementClient,
+) -> anyhow::Result<StateRootCheck> {
+    let block_number = settlement_client.get_last_verified_block_number().await?;
+    let settled = settlement_client.get_last_state_root().await?;
+    // The block number and the state root are not read atomically: a state update could land in between.
+    if settlement_client.get_last_verified_block_number().await? != block_number {
+        return Ok(StateRootCheck::Skipped);
+    }
+
+    let Some(block_info) = backend.get_block_info(&DbBlockId::Number(block_number)).context("Getting block info")?
+    else {
+        return Ok(StateRootCheck::Skipped);
+    };
+    let local = block_info.as_nonpending().context("Block is not a closed block")?.header.global_state_root;
+
+    if local == settled {
+        Ok(StateRootCheck::Match { block_number })
+    } else {
+        Ok(StateRootCheck::Mismatch { block_number, local, settled })
+    }
+}
+
+/// Checks the local state root against the core contract every `interval`.
+///
+/// When `writes_halted` is provided, it is set while the state roots mismatch.
+pub async fn state_root_verification_worker(
+    backend: &MadaraBackend,
+    settlement_client: &dyn SettlementClient,
+    interval: Duration,
+    writes_halted: Option<Arc<AtomicBool>>,
+) -> anyhow::Result<()> {
+    let metrics = settlement_client.l1_block_metrics();
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+        let mismatch = match check_state_root(backend, settlement_client).await {
+            Ok(StateRootCheck::Match { block_number }) => {
+                log::debug!("state_root_verification_worker: state root matches at block #{block_number}");
+                false
+            }
+            Ok(StateRootCheck::Mismatch { block_number, local, settled }) => {
+                log::error!(
+                    "❗ Local state root ({}) at block #{} does not match the state root verified on {} ({})",
+                    trim_hash(&local),
+                    block_number,
+                    settlement_client.name(),
+                    trim_hash(&settled)
+                );
+                true
+            }
+            Ok(StateRootCheck::Skipped) => continue,
+            Err(err) => {
+                log::warn!("Failed to verify the local state root: {err:#}");
+                continue;
+            }
+        };
+
+        metrics.state_root_mismatch.set(if mismatch { 1.0 } else { 0.0 });
+        if let Some(writes_halted) = &writes_halted {
+            if writes_halted.swap(mismatch, Ordering::Relaxed) != mismatch {
+                if mismatch {
+                    log::error!("⛔ Transaction submission is halted until the state roots match again");
+                } else {
+                    log::info!("✅ State roots match again, transaction submission is resumed");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod state_root_verification_test {
+    use super::*;
+    use crate::client::L1BlockMetrics;
+    use crate::settlement::StateUpdateStream;
+    use futures::{stream, StreamExt};
+    use mc_db::DatabaseService;
+    use mc_metrics::MetricsRegistry;
+    use mp_block::header::Header;
+    use mp_block::{MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
+    use mp_chain_config::ChainConfig;
+    use mp_state_update::StateDiff;
+    use rstest::*;
+    use tempfile::TempDir;
+
+    const LOCAL_STATE_ROOT: Felt = Felt::from_hex_unchecked("0x88912");
+
+    struct DummySettlementClient {
+        block_number: u64,
+        state_root: Felt,
+        l1_block_metrics: L1BlockMetrics,
+    }
+
+    #[async_trait::async_trait]
+    impl SettlementClient for DummySettlementClient {
+        fn name(&self) -> &'static str {
+            "Dummy"
+        }
+        fn l1_block_metrics(&self) -> &L1BlockMetrics {
+            &self.l1_block_metrics
+        }
+        async fn get_latest_block_number(&self) -> anyhow::Result<u64> {
+            Ok(0)
+        }
+        async fn get_last_verified_block_number(&self) -> anyhow::Result<u64> {
+            Ok(self.block_number)
+        }
+        async fn get_last_state_root(&self) -> anyhow::Result<Felt> {
+            Ok(self.state_root)
+        }
+        async fn get_last_verified_block_hash(&self) -> anyhow::Result<Felt> {
+            Ok(Felt::ZERO)
+        }
+        async fn state_update_stream(&self) -> anyhow::Result<StateUpdateStream> {
+            Ok(stream::empty().boxed())
+        }
+    }
+
+    fn settlement_client(block_number: u64, state_root: Felt) -> DummySettlementClient {
+        let l1_block_metrics = L1BlockMetrics::register(&MetricsRegistry::dummy()).unwrap();
+        DummySettlementClient { block_number, state_root, l1_block_metrics }
+    }
+
+    #[fixture]
+    async fn db() -> (DatabaseService, TempDir) {
+        let temp_dir = TempDir::new().expect("issue while creating temporary directory");
+        let db = DatabaseService::new(
+            temp_dir.path(),
+            None,
+            false,
+            Arc::new(ChainConfig::madara_test()),
+            &MetricsRegistry::dummy(),
+        )
+        .await
+        .expect("Failed to create database service");
+
+        let header = Header { block_number: 0, global_state_root: LOCAL_STATE_ROOT, ..Default::default() };
+        db.backend()
+            .store_block(
+                MadaraMaybePendingBlock {
+                    info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo { header, ..Default::default() }),
+                    inner: MadaraBlockInner::default(),
+                },
+                StateDiff::default(),
+                vec![],
+            )
+            .expect("Storing block");
+
+        (db, temp_dir)
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn check_state_root_works(#[future] db: (DatabaseService, TempDir)) {
+        let (db, _temp_dir) = db.await;
+
+        assert_eq!(
+            check_state_root(db.backend(), &settlement_client(0, LOCAL_STATE_ROOT)).await.unwrap(),
+            StateRootCheck::Match { block_number: 0 }
+        );
+        assert_eq!(
+            check_state_root(db.backend(), &settlement_client(0, Felt::ONE)).await.unwrap(),
+            StateRootCheck::Mismatch { block_number: 0, local: LOCAL_STATE_ROOT, settled: Felt::ONE }
+        );
+        assert_eq!(
+            check_state_root(db.backend(), &settlement_client(1, Felt::ONE)).await.unwrap(),
+            StateRootCheck::Skipped
+        );
+    }
+}
@@ -1,15 +1,14 @@
-use crate::client::{L1BlockMetrics, StarknetCoreContract};
-use crate::{
-    client::EthereumClient,
-    utils::{convert_log_state_update, trim_hash},
-};
+use crate::client::L1BlockMetrics;
+use crate::settlement::SettlementClient;
+use crate::utils::trim_hash;
 use anyhow::Context;
 use futures::StreamExt;
 use mc_db::MadaraBackend;
 use mp_transactions::MAIN_CHAIN_ID;
-use mp_utils::channel_wait_or_graceful_shutdown;
+use mp_utils::{channel_wait_or_graceful_shutdown, wait_or_graceful_shutdown};
 use serde::Deserialize;
 use starknet_types_core::felt::Felt;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct L1StateUpdate {
@@ -19,7 +18,7 @@ pub struct L1StateUpdate {
 }
 
 /// Get the last Starknet state update verified on the L1
-pub async fn get_initial_state(client: &EthereumClient) -> anyhow::Result<L1StateUpdate> {
+pub async fn get_initial_state(client: &dyn SettlementClient) -> anyhow::Result<L1StateUpdate> {
     let block_number = client.get_last_verified_block_number().await?;
     let block_hash = client.get_last_verified_block_hash().await?;
     let global_root = client.get_last_state_root().await?;
@@ -27,22 +26,18 @@ pub async fn get_initial_state(client: &EthereumClient) -> anyhow::Result<L1Stat
     Ok(L1StateUpdate { global_root, block_number, block_hash })
 }
 
-/// Subscribes to the LogStateUpdate event from the Starknet core contract and store latest
+/// Subscribes to the state updates verified by the core contract on the settlement layer and store latest
 /// verified state
 pub async fn listen_and_update_state(
-    eth_client: &EthereumClient,
+    settlement_client: &dyn SettlementClient,
     backend: &MadaraBackend,
     block_metrics: &L1BlockMetrics,
     chain_id: Felt,
 ) -> anyhow::Result<()> {
-    let event_filter = eth_client.l1_core_contract.event_filter::<StarknetCoreContract::LogStateUpdate>();
-
-    let mut event_stream = event_filter.watch().await.context("Failed to watch event filter")?.into_stream();
+    let mut event_stream = settlement_client.state_update_stream().await.context("Failed to watch state updates")?;
 
     while let Some(event_result) = channel_wait_or_graceful_shutdown(event_stream.next()).await {
-        let log = event_result.context("listening for events")?;
-        let format_event: L1StateUpdate =
-            convert_log_state_update(log.0.clone()).context("formatting event into an L1StateUpdate")?;
+        let format_event: L1StateUpdate = event_result.context("listening for events")?;
         update_l1(backend, format_event, block_metrics, chain_id)?;
     }
 
@@ -79,38 +74,70 @@ pub fn update_l1(
 
 pub async fn state_update_worker(
     backend: &MadaraBackend,
-    eth_client: &EthereumClient,
+    settlement_client: &dyn SettlementClient,
     chain_id: Felt,
 ) -> anyhow::Result<()> {
     // Clear L1 confirmed block at startup
     backend.clear_last_confirmed_block().context("Clearing l1 last confirmed block number")?;
     log::debug!("update_l1: cleared confirmed block number");
 
-    log::info!("🚀 Subscribed to L1 state verification");
+    log::info!("🚀 Subscribed to {} state verification", settlement_client.name());
     // ideally here there would be one service which will update the l1 gas prices and another one for messages and one that's already present is state update
     // Get and store the latest verified state
-    let initial_state = get_initial_state(eth_client).await.context("Getting initial ethereum state")?;
-    update_l1(backend, initial_state, &eth_client.l1_block_metrics, chain_id)?;
+    let initial_state = get_initial_state(settlement_client).await.context("Getting initial settlement layer state")?;
+    update_l1(backend, initial_state, settlement_client.l1_block_metrics(), chain_id)?;
 
     // Listen to LogStateUpdate (0x77552641) update and send changes continusly
-    listen_and_update_state(eth_client, backend, &eth_client.l1_block_metrics, chain_id)
+    listen_and_update_state(settlement_client, backend, settlement_client.l1_block_metrics(), chain_id)
         .await
         .context("Subscribing to the LogStateUpdate event")?;
 
     Ok(())
 }
 
+/// Finality of sovereign chains, which have no settlement layer: blocks are considered confirmed as soon as they
+/// are stored locally. The last confirmed block is moved to the latest block every `interval`.
+pub async fn local_finality_worker(backend: &MadaraBackend, interval: Duration) -> anyhow::Result<()> {
+    log::info!("👑 Running as a sovereign chain: blocks are final once stored locally");
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+        update_local_finality(backend)?;
+    }
+
+    Ok(())
+}
+
+fn update_local_finality(backend: &MadaraBackend) -> anyhow::Result<()> {
+    let Some(latest_block_n) = backend.get_latest_block_n().context("Getting the latest block number")? else {
+        return Ok(());
+    };
+    if backend.get_l1_last_confirmed_block().context("Getting the last confirmed block number")? != Some(latest_block_n)
+    {
+        backend.write_last_confirmed_block(latest_block_n).context("Setting the last confirmed block number")?;
+        log::debug!("update_local_finality: confirmed block #{latest_block_n}");
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod eth_client_event_subscription_test {
     use super::*;
+    use crate::client::{EthereumClient, StarknetCoreContract};
     use std::{sync::Arc, time::Duration};
 
-    use alloy::{node_bindings::Anvil, providers::ProviderBuilder, sol};
+    use alloy::{
+        node_bindings::Anvil,
+        providers::{ProviderBuilder, WsConnect},
+        sol,
+    };
     use mc_db::DatabaseService;
     use mc_metrics::{MetricsRegistry, MetricsService};
     use mp_chain_config::ChainConfig;
     use mp_convert::ToFelt;
     use rstest::*;
+    use serial_test::serial;
     use tempfile::TempDir;
     use url::Url;
 
@@ -143,8 +170,11 @@ mod eth_client_event_subscription_test {
     /// 5. Fires an event from the dummy contract
     /// 6. Waits for event processing and verifies the block number
     #[rstest]
+    #[serial]
+    #[case::polling(false)]
+    #[case::websocket(true)]
     #[tokio::test]
-    async fn listen_and_update_state_when_event_fired_works() {
+    async fn listen_and_update_state_when_event_fired_works(#[case] use_ws: bool) {
         // Start Anvil instance
         let anvil = Anvil::new()
             .block_time(1)
@@ -179,8 +209,18 @@ mod eth_client_event_subscription_test {
         let contract = DummyContract::deploy(provider.clone()).await.unwrap();
         let core_contract = StarknetCoreContract::new(*contract.address(), provider.clone());
 
-        let eth_client =
-            EthereumClient { provider: Arc::new(provider), l1_core_contract: core_contract.clone(), l1_block_metrics };
+        let ws_provider = if use_ws {
+            Some(Arc::new(ProviderBuilder::new().on_ws(WsConnect::new(anvil.ws_endpoint())).await.unwrap()))
+        } else {
+            None
+        };
+
+        let eth_client = EthereumClient {
+            provider: Arc::new(provider),
+            ws_provider,
+            l1_core_contract: core_contract.clone(),
+            l1_block_metrics,
+        };
 
         // Start listening for state updates
         let listen_handle = {
@@ -0,0 +1,571 @@
+//! Sequencer settlement: submission of the state updates of the produced blocks to the core contract.
+//!
+//! The state diffs of the blocks produced since the last settled block are aggregated and encoded for data
+//! availability, and an `updateState` transaction carrying the corresponding Starknet OS output is sent to the
+//! core contract. The core contract only accepts the update when the fact of the program output has been
+//! registered in its verifier.
+//!
+//! The program output is built by the node from its own blocks, without running the Starknet OS nor proving it: no
+//! fact is registered for it. Submission is therefore only usable against a core contract whose verifier accepts any
+//! fact (devnets with a mock verifier), and is refused on the public Starknet chains, see
+//! [`check_settlement_chain`].
+//!
+//! The onchain data is either published as calldata, or in an EIP-4844 blob carried by the `updateStateKzgDA`
+//! transaction.
+
+use crate::blob::prepare_blob_da;
+use crate::client::{EthereumClient, StarknetCoreContract};
+use crate::utils::{felt_to_u256, trim_hash};
+use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+use alloy::primitives::{keccak256, TxHash, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::transports::Transport;
+use anyhow::{bail, Context};
+use mc_da::{DaClient, DaInclusion};
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mp_block::header::L1DataAvailabilityMode;
+use mp_convert::ToFelt;
+use mp_state_update::StateDiff;
+use mp_transactions::Transaction;
+use mp_utils::wait_or_graceful_shutdown;
+use starknet_api::core::ChainId;
+use starknet_types_core::felt::Felt;
+use starknet_types_core::hash::{Pedersen, StarkHash};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Version of the Starknet OS config, used to compute the config hash.
+const STARKNET_OS_CONFIG_VERSION: &[u8] = b"StarknetOsConfig1";
+/// Offset of the `use_kzg_da` flag in the program output.
+const USE_KZG_DA_OFFSET: usize = 5;
+/// Size of the program output header.
+const HEADER_SIZE: usize = 6;
+/// The max fee per blob gas is set to this multiple of the current blob base fee, leaving room for it to rise
+/// until the transaction is included.
+const BLOB_BASE_FEE_MULTIPLIER: u128 = 2;
+/// A state update that was not confirmed in time is sent again with its fees raised by this percentage, L1 nodes only
+/// accept a transaction replacing a pending one with the same nonce when all its fees are raised by at least 10%.
+const FEE_BUMP_PERCENT: u128 = 20;
+
+#[derive(Clone)]
+pub struct SubmissionConfig {
+    /// Interval at which new blocks are checked for submission.
+    pub interval: Duration,
+    /// Maximum number of blocks aggregated in a single state update.
+    pub max_blocks_per_update: u64,
+    /// Number of L1 confirmations to wait for before considering a state update settled.
+    pub confirmations: u64,
+    /// Time after which an unconfirmed state update is considered lost, and submitted again.
+    pub confirmation_timeout: Duration,
+    /// Whether the onchain data is published as calldata or in a blob.
+    pub da_mode: L1DataAvailabilityMode,
+    /// Blob state updates are delayed while the blob base fee is above this price, in wei.
+    pub max_blob_gas_price: Option<u128>,
+    /// When set, the onchain data is published on this DA layer instead, and only its hash is sent to the core
+    /// contract.
+    pub da_client: Option<Arc<dyn DaClient>>,
+}
+
+/// Refuses to settle the blocks of the public Starknet chains: their core contracts check the fact of the program
+/// output, which is never registered for the output built by [`build_state_update`], and every `updateState`
+/// transaction would revert.
+pub fn check_settlement_chain(chain_id: &ChainId) -> anyhow::Result<()> {
+    match chain_id {
+        ChainId::Mainnet | ChainId::Sepolia | ChainId::IntegrationSepolia => bail!(
+            "State update submission is not supported on {chain_id}: the program output is not proven, it is only accepted by a core contract with a mock verifier"
+        ),
+        ChainId::Other(_) => Ok(()),
+    }
+}
+
+/// The aggregated state update of a range of blocks, ready to be submitted to the core contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateUpdateBatch {
+    pub first_block: u64,
+    pub last_block: u64,
+    /// Output of the Starknet OS for this range of blocks.
+    pub program_output: Vec<Felt>,
+    /// The aggregated state diff, encoded for data availability.
+    pub onchain_data: Vec<Felt>,
+}
+
+impl StateUpdateBatch {
+    /// Marks the onchain data as published in a blob, and adds the KZG segment to the program output.
+    pub fn set_kzg_da(&mut self, kzg_segment: &[Felt]) {
+        self.program_output[USE_KZG_DA_OFFSET] = Felt::ONE;
+        self.program_output.splice(HEADER_SIZE..HEADER_SIZE, kzg_segment.iter().copied());
+    }
+
+    /// Keccak hash of the onchain data, as 32 bytes big-endian words.
+    pub fn onchain_data_hash(&self) -> U256 {
+        let bytes: Vec<u8> = self.onchain_data.iter().flat_map(|felt| felt.to_bytes_be()).collect();
+        U256::from_be_bytes(keccak256(bytes).0)
+    }
+}
+
+/// Fees of a sent state update transaction, kept to replace it when it is not confirmed in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SubmissionFees {
+    max_fee_per_gas: u128,
+    max_priority_fee_per_gas: u128,
+    max_fee_per_blob_gas: Option<u128>,
+}
+
+impl SubmissionFees {
+    /// Fees of a transaction replacing one sent with these fees: the current `estimate`, or the bumped fees when
+    /// they are higher.
+    fn bump(self, estimate: SubmissionFees) -> SubmissionFees {
+        let bump = |fee: u128| fee.saturating_mul(100 + FEE_BUMP_PERCENT) / 100;
+        SubmissionFees {
+            max_fee_per_gas: estimate.max_fee_per_gas.max(bump(self.max_fee_per_gas)),
+            max_priority_fee_per_gas: estimate.max_priority_fee_per_gas.max(bump(self.max_priority_fee_per_gas)),
+            max_fee_per_blob_gas: match (estimate.max_fee_per_blob_gas, self.max_fee_per_blob_gas) {
+                (Some(estimate), Some(previous)) => Some(estimate.max(bump(previous))),
+                (estimate, _) => estimate,
+            },
+        }
+    }
+}
+
+/// Hash of the Starknet OS config, which is part of the program output and checked by the core contract.
+pub fn os_config_hash(backend: &MadaraBackend) -> Felt {
+    let chain_config = backend.chain_config();
+    Pedersen::hash_array(&[
+        Felt::from_bytes_be_slice(STARKNET_OS_CONFIG_VERSION),
+        (&chain_config.chain_id).to_felt(),
+        chain_config.parent_fee_token_address.to_felt(),
+    ])
+}
+
+/// Aggregates the blocks after `settled_block` up to `last_block` into a single state update.
+///
+/// `settled_root` is the state root currently verified by the core contract.
+pub fn build_state_update(
+    backend: &MadaraBackend,
+    settled_block: Option<u64>,
+    settled_root: Felt,
+    last_block: u64,
+) -> anyhow::Result<StateUpdateBatch> {
+    let first_block = settled_block.map(|n| n + 1).unwrap_or(0);
+    if first_block > last_block {
+        bail!("No block to settle after block #{settled_block:?}");
+    }
+
+    let mut state_diff = StateDiff::default();
+    let mut messages_to_l1 = vec![];
+    let mut messages_to_l2 = vec![];
+    for block_n in first_block..=last_block {
+        let block_id = DbBlockId::Number(block_n);
+        let diff = backend
+            .get_block_state_diff(&block_id)
+            .context("Getting block state diff")?
+            .with_context(|| format!("State diff of block #{block_n} not found"))?;
+        state_diff.merge(diff);
+
+        let inner = backend
+            .get_block_inner(&block_id)
+            .context("Getting block")?
+            .with_context(|| format!("Block #{block_n} not found"))?;
+        for receipt in &inner.receipts {
+            for message in receipt.messages_sent() {
+                messages_to_l1.extend([message.from_address, message.to_address]);
+                messages_to_l1.push(Felt::from(message.payload.len() as u64));
+                messages_to_l1.extend(&message.payload);
+            }
+        }
+        for tx in &inner.transactions {
+            if let Transaction::L1Handler(tx) = tx {
+                // The first calldata element of an L1 handler is the L1 sender of the message.
+                let (from_address, payload) =
+                    tx.calldata.split_first().context("L1 handler transaction without calldata")?;
+                messages_to_l2.extend([*from_address, tx.contract_address, Felt::from(tx.nonce)]);
+                messages_to_l2.push(tx.entry_point_selector);
+                messages_to_l2.push(Felt::from(payload.len() as u64));
+                messages_to_l2.extend(payload);
+            }
+        }
+    }
+
+    // Every touched contract is encoded along with its nonce, changed or not.
+    let last_block_id = DbBlockId::Number(last_block);
+    backend.add_touched_contract_nonces(&last_block_id, &mut state_diff).context("Getting contract nonces")?;
+    let onchain_data = state_diff.encode_da();
+
+    let header = backend
+        .get_block_info(&last_block_id)
+        .context("Getting block info")?
+        .with_context(|| format!("Block #{last_block} not found"))?;
+    let header = header.as_nonpending().context("Block is not a closed block")?;
+
+    let mut program_output = vec![
+        settled_root,
+        header.header.global_state_root,
+        Felt::from(last_block),
+        header.block_hash,
+        os_config_hash(backend),
+        // use_kzg_da
+        Felt::ZERO,
+    ];
+    program_output.push(Felt::from(messages_to_l1.len() as u64));
+    program_output.extend(messages_to_l1);
+    program_output.push(Felt::from(messages_to_l2.len() as u64));
+    program_output.extend(messages_to_l2);
+
+    Ok(StateUpdateBatch { first_block, last_block, program_output, onchain_data })
+}
+
+/// Sends the `updateState` (or `updateStateKzgDA`) transaction and waits for it to be confirmed.
+///
+/// `last_sent` holds the nonce and fees of the last sent transaction. When it has the same nonce, that transaction was
+/// not confirmed in time and is replaced with bumped fees.
+///
+/// `da_inclusion` holds the range of blocks whose onchain data was last published on the DA layer, with its
+/// inclusion. The data is only published again when the batch covers another range of blocks, so that retries and fee
+/// bumps do not pay for it again.
+async fn submit_state_update<T: Transport + Clone, P: Provider<T, Ethereum>>(
+    core_contract: &StarknetCoreContract::StarknetCoreContractInstance<T, P>,
+    mut batch: StateUpdateBatch,
+    nonce: u64,
+    last_sent: &mut Option<(u64, SubmissionFees)>,
+    da_inclusion: &mut Option<((u64, u64), DaInclusion)>,
+    config: &SubmissionConfig,
+) -> anyhow::Result<TxHash> {
+    if let Some(da_client) = &config.da_client {
+        let range = (batch.first_block, batch.last_block);
+        match da_inclusion {
+            Some((published_range, inclusion)) if *published_range == range => {
+                log::debug!(
+                    "State diff of blocks #{}..=#{} already published on {} at height {}",
+                    batch.first_block,
+                    batch.last_block,
+                    da_client.name(),
+                    inclusion.height()
+                );
+            }
+            _ => {
+                let inclusion = da_client
+                    .submit(&batch.onchain_data)
+                    .await
+                    .with_context(|| format!("Publishing the onchain data on {}", da_client.name()))?;
+                log::info!(
+                    "📦 Published the state diff of blocks #{}..=#{} on {} at height {}",
+                    batch.first_block,
+                    batch.last_block,
+                    da_client.name(),
+                    inclusion.height()
+                );
+                *da_inclusion = Some((range, inclusion));
+            }
+        }
+    }
+
+    let estimate = core_contract.provider().estimate_eip1559_fees(None).await.context("Estimating the L1 fees")?;
+    let max_fee_per_blob_gas = match config.da_mode {
+        L1DataAvailabilityMode::Calldata => None,
+        L1DataAvailabilityMode::Blob => {
+            let blob_base_fee =
+                core_contract.provider().get_blob_base_fee().await.context("Getting the blob base fee")?;
+            if let Some(max_blob_gas_price) = config.max_blob_gas_price {
+                if blob_base_fee > max_blob_gas_price {
+                    bail!("Blob base fee ({blob_base_fee} wei) is above the maximum blob gas price ({max_blob_gas_price} wei)");
+                }
+            }
+            Some(blob_base_fee * BLOB_BASE_FEE_MULTIPLIER)
+        }
+    };
+    let estimate = SubmissionFees {
+        max_fee_per_gas: estimate.max_fee_per_gas,
+        max_priority_fee_per_gas: estimate.max_priority_fee_per_gas,
+        max_fee_per_blob_gas,
+    };
+    let fees = match *last_sent {
+        Some((last_nonce, last_fees)) if last_nonce == nonce => {
+            log::info!("Replacing the unconfirmed state update transaction with nonce {nonce}");
+            last_fees.bump(estimate)
+        }
+        _ => estimate,
+    };
+    // Capping the bumped fee would make the replacement underpriced, and it would be rejected by the L1 nodes.
+    if let (Some(max_fee_per_blob_gas), Some(max_blob_gas_price)) =
+        (fees.max_fee_per_blob_gas, config.max_blob_gas_price)
+    {
+        if max_fee_per_blob_gas > max_blob_gas_price {
+            bail!("Max fee per blob gas ({max_fee_per_blob_gas} wei) is above the maximum blob gas price ({max_blob_gas_price} wei), not sending the state update");
+        }
+    }
+
+    let pending_tx = match fees.max_fee_per_blob_gas {
+        None => {
+            let program_output = batch.program_output.iter().copied().map(felt_to_u256).collect();
+            core_contract
+                .updateState(program_output, batch.onchain_data_hash(), U256::from(batch.onchain_data.len()))
+                .max_fee_per_gas(fees.max_fee_per_gas)
+                .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+                .nonce(nonce)
+                .send()
+                .await
+                .context("Sending updateState transaction")?
+        }
+        Some(max_fee_per_blob_gas) => {
+            let blob_da = prepare_blob_da(&batch.onchain_data).context("Preparing the blob")?;
+            batch.set_kzg_da(&blob_da.kzg_segment);
+            let program_output = batch.program_output.iter().copied().map(felt_to_u256).collect();
+            core_contract
+                .updateStateKzgDA(program_output, blob_da.kzg_proof)
+                .sidecar(blob_da.sidecar)
+                .max_fee_per_gas(fees.max_fee_per_gas)
+                .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+                .max_fee_per_blob_gas(max_fee_per_blob_gas)
+                .nonce(nonce)
+                .send()
+                .await
+                .context("Sending updateStateKzgDA transaction")?
+        }
+    };
+    *last_sent = Some((nonce, fees));
+    let tx_hash = *pending_tx.tx_hash();
+    log::info!("📤 Submitted state update for blocks #{}..=#{} (L1 tx {tx_hash})", batch.first_block, batch.last_block);
+
+    let receipt = pending_tx
+        .with_required_confirmations(config.confirmations)
+        .with_timeout(Some(config.confirmation_timeout))
+        .get_receipt()
+        .await
+        .context("Waiting for the updateState transaction confirmation")?;
+    if !receipt.status() {
+        bail!("updateState transaction {tx_hash} reverted");
+    }
+
+    Ok(tx_hash)
+}
+
+/// Submits the state updates of the produced blocks to the core contract, as they are produced. The transactions are
+/// sent from the default signer of `wallet`.
+pub async fn state_update_submission_worker(
+    backend: &MadaraBackend,
+    eth_client: &EthereumClient,
+    wallet: EthereumWallet,
+    config: SubmissionConfig,
+) -> anyhow::Result<()> {
+    check_settlement_chain(&backend.chain_config().chain_id)?;
+
+    let signer_address = NetworkWallet::<Ethereum>::default_signer_address(&wallet);
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(wallet)
+        .on_provider(eth_client.provider.as_ref().clone());
+    let core_contract = StarknetCoreContract::new(*eth_client.l1_core_contract.address(), &provider);
+
+    log::info!("🚀 Submitting state updates to the core contract from {signer_address}");
+
+    // Nonce of the next transaction. Fetched again from L1 after a failed submission: when the transaction was sent but
+    // not confirmed in time, this is still its nonce and it gets replaced.
+    let mut next_nonce: Option<u64> = None;
+    let mut last_sent: Option<(u64, SubmissionFees)> = None;
+    let mut da_inclusion: Option<((u64, u64), DaInclusion)> = None;
+    let mut interval = tokio::time::interval(config.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+        let Some(latest_block) = backend.get_latest_block_n().context("Getting latest block number")? else {
+            continue;
+        };
+        let (settled_block, settled_root) =
+            match tokio::try_join!(eth_client.get_last_verified_block_number(), eth_client.get_last_state_root()) {
+                Ok(state) => state,
+                Err(err) => {
+                    log::warn!("Failed to get the core contract state: {err:#}");
+                    continue;
+                }
+            };
+        // A freshly deployed core contract does not have any settled block.
+        let settled_block = (settled_root != Felt::ZERO).then_some(settled_block);
+        let last_block = match settled_block {
+            Some(settled_block) if settled_block >= latest_block => continue,
+            Some(settled_block) => latest_block.min(settled_block + config.max_blocks_per_update),
+            None => latest_block.min(config.max_blocks_per_update.saturating_sub(1)),
+        };
+
+        let batch = match build_state_update(backend, settled_block, settled_root, last_block) {
+            Ok(batch) => batch,
+            Err(err) => {
+                log::warn!("Failed to build the state update of blocks up to #{last_block}: {err:#}");
+                continue;
+            }
+        };
+
+        let nonce = match next_nonce {
+            Some(nonce) => nonce,
+            None => match provider.get_transaction_count(signer_address).await {
+                Ok(nonce) => nonce,
+                Err(err) => {
+                    log::warn!("Failed to get the settlement account nonce: {err:#}");
+                    continue;
+                }
+            },
+        };
+
+        let (first_block, last_block, new_root) = (batch.first_block, batch.last_block, batch.program_output[1]);
+        match submit_state_update(&core_contract, batch, nonce, &mut last_sent, &mut da_inclusion, &config).await {
+            Ok(tx_hash) => {
+                next_nonce = Some(nonce + 1);
+                log::info!(
+                    "✅ State update for blocks #{first_block}..=#{last_block} confirmed on L1 with state root {} (L1 tx {tx_hash})",
+                    trim_hash(&new_root)
+                );
+            }
+            Err(err) => {
+                next_nonce = None;
+                log::warn!("Failed to settle blocks #{first_block}..=#{last_block}: {err:#}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod state_update_submission_test {
+    use super::*;
+    use mc_db::DatabaseService;
+    use mc_metrics::MetricsRegistry;
+    use mp_block::header::Header;
+    use mp_block::{MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
+    use mp_chain_config::ChainConfig;
+    use mp_receipt::{L1HandlerTransactionReceipt, MsgToL1, TransactionReceipt};
+    use mp_state_update::{ContractStorageDiffItem, StorageEntry};
+    use mp_transactions::L1HandlerTransaction;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn build_state_update_works() {
+        let temp_dir = TempDir::new().expect("issue while creating temporary directory");
+        let db = DatabaseService::new(
+            temp_dir.path(),
+            None,
+            false,
+            Arc::new(ChainConfig::madara_test()),
+            &MetricsRegistry::dummy(),
+        )
+        .await
+        .expect("Failed to create database service");
+
+        let header = Header { block_number: 0, global_state_root: Felt::from(0x1234), ..Default::default() };
+        let block = MadaraMaybePendingBlock {
+            info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                header,
+                block_hash: Felt::from(0x5678),
+                tx_hashes: vec![Felt::ONE],
+            }),
+            inner: MadaraBlockInner {
+                transactions: vec![Transaction::L1Handler(L1HandlerTransaction {
+                    version: Felt::ZERO,
+                    nonce: 3,
+                    contract_address: Felt::from(10),
+                    entry_point_selector: Felt::from(11),
+                    calldata: vec![Felt::from(12), Felt::from(13)],
+                })],
+                receipts: vec![TransactionReceipt::L1Handler(L1HandlerTransactionReceipt {
+                    transaction_hash: Felt::ONE,
+                    messages_sent: vec![MsgToL1 {
+                        from_address: Felt::from(10),
+                        to_address: Felt::from(14),
+                        payload: vec![Felt::from(15)],
+                    }],
+                    ..Default::default()
+                })],
+            },
+        };
+        let state_diff = StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: Felt::from(10),
+                storage_entries: vec![StorageEntry { key: Felt::from(16), value: Felt::from(17) }],
+            }],
+            ..Default::default()
+        };
+        db.backend().store_block(block, state_diff, vec![]).expect("Storing block");
+
+        let batch = build_state_update(db.backend(), None, Felt::ZERO, 0).expect("Building state update");
+
+        assert_eq!(batch.first_block, 0);
+        assert_eq!(batch.last_block, 0);
+        assert_eq!(
+            batch.program_output,
+            vec![
+                Felt::ZERO,
+                Felt::from(0x1234),
+                Felt::ZERO,
+                Felt::from(0x5678),
+                os_config_hash(db.backend()),
+                Felt::ZERO,
+                // messages to L1
+                Felt::from(4),
+                Felt::from(10),
+                Felt::from(14),
+                Felt::ONE,
+                Felt::from(15),
+                // messages to L2
+                Felt::from(6),
+                Felt::from(12),
+                Felt::from(10),
+                Felt::from(3),
+                Felt::from(11),
+                Felt::ONE,
+                Felt::from(13),
+            ]
+        );
+        assert_eq!(
+            batch.onchain_data,
+            vec![Felt::ONE, Felt::from(10), Felt::ONE, Felt::from(16), Felt::from(17), Felt::ZERO]
+        );
+        assert!(build_state_update(db.backend(), Some(0), Felt::from(0x1234), 0).is_err());
+    }
+
+    #[test]
+    fn set_kzg_da_works() {
+        let mut batch = StateUpdateBatch {
+            first_block: 0,
+            last_block: 0,
+            program_output: vec![Felt::ZERO; HEADER_SIZE + 2],
+            onchain_data: vec![],
+        };
+
+        batch.set_kzg_da(&[Felt::from(1), Felt::from(2), Felt::from(3), Felt::from(4), Felt::from(5)]);
+
+        assert_eq!(batch.program_output[USE_KZG_DA_OFFSET], Felt::ONE);
+        assert_eq!(batch.program_output[HEADER_SIZE..HEADER_SIZE + 5], [1, 2, 3, 4, 5].map(Felt::from));
+        assert_eq!(batch.program_output.len(), HEADER_SIZE + 7);
+    }
+
+    #[test]
+    fn submission_fees_bump() {
+        let sent =
+            SubmissionFees { max_fee_per_gas: 100, max_priority_fee_per_gas: 10, max_fee_per_blob_gas: Some(50) };
+
+        // The fees went down since the transaction was sent: the bumped fees are used.
+        let estimate =
+            SubmissionFees { max_fee_per_gas: 80, max_priority_fee_per_gas: 5, max_fee_per_blob_gas: Some(40) };
+        assert_eq!(
+            sent.bump(estimate),
+            SubmissionFees { max_fee_per_gas: 120, max_priority_fee_per_gas: 12, max_fee_per_blob_gas: Some(60) }
+        );
+
+        // The fees went up above the bumped fees: the current estimate is used.
+        let estimate =
+            SubmissionFees { max_fee_per_gas: 200, max_priority_fee_per_gas: 11, max_fee_per_blob_gas: Some(70) };
+        assert_eq!(
+            sent.bump(estimate),
+            SubmissionFees { max_fee_per_gas: 200, max_priority_fee_per_gas: 12, max_fee_per_blob_gas: Some(70) }
+        );
+    }
+
+    #[test]
+    fn check_settlement_chain_refuses_public_chains() {
+        assert!(check_settlement_chain(&ChainId::Mainnet).is_err());
+        assert!(check_settlement_chain(&ChainId::Sepolia).is_err());
+        assert!(check_settlement_chain(&ChainId::IntegrationSepolia).is_err());
+        assert!(check_settlement_chain(&ChainId::Other("MADARA_DEVNET".into())).is_ok());
+    }
+}
@@ -0,0 +1,127 @@
+//! ETH/STRK conversion rate, used to derive the STRK-denominated gas prices from the ETH ones.
+
+use crate::client::EthereumClient;
+use alloy::primitives::{Address, U256};
+use alloy::sol;
+use anyhow::{bail, Context};
+use url::Url;
+
+// Chainlink-style aggregator interface.
+sol!(
+    #[sol(rpc)]
+    interface PriceFeed {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound);
+    }
+);
+
+/// Decimals used for prices read from an http API.
+const HTTP_PRICE_DECIMALS: u8 = 18;
+
+/// The price of one STRK, in ETH: `price / 10^decimals`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StrkPrice {
+    pub price: U256,
+    pub decimals: u8,
+}
+
+impl StrkPrice {
+    /// Converts an amount of wei into fri.
+    pub fn wei_to_fri(&self, wei: u128) -> anyhow::Result<u128> {
+        if self.price.is_zero() {
+            bail!("STRK price is zero");
+        }
+        let fri = U256::from(wei)
+            .checked_mul(U256::from(10u8).pow(U256::from(self.decimals)))
+            .context("Overflow while converting wei to fri")?
+            / self.price;
+        u128::try_from(fri).context("Converted fri amount does not fit in a u128")
+    }
+}
+
+/// Where the ETH/STRK conversion rate is read from.
+#[derive(Clone)]
+pub enum StrkPriceSource {
+    /// An aggregator contract deployed on L1, giving the price of STRK in ETH.
+    Oracle { eth_client: EthereumClient, feed_address: Address },
+    /// An http API returning a json document, with the price of STRK in ETH at `json_pointer`.
+    Http { client: reqwest::Client, url: Url, json_pointer: String },
+}
+
+impl StrkPriceSource {
+    pub fn http(url: Url, json_pointer: String) -> Self {
+        Self::Http { client: reqwest::Client::new(), url, json_pointer }
+    }
+
+    pub async fn get_strk_price(&self) -> anyhow::Result<StrkPrice> {
+        match self {
+            StrkPriceSource::Oracle { eth_client, feed_address } => {
+                let feed = PriceFeed::new(*feed_address, eth_client.provider.as_ref());
+                let decimals = feed.decimals().call().await.context("Reading the price feed decimals")?._0;
+                let answer = feed.latestRoundData().call().await.context("Reading the price feed latest round")?.answer;
+                if !answer.is_positive() {
+                    bail!("The price feed returned a non-positive price: {answer}");
+                }
+                Ok(StrkPrice { price: answer.into_raw(), decimals })
+            }
+            StrkPriceSource::Http { client, url, json_pointer } => {
+                let body: serde_json::Value = client
+                    .get(url.clone())
+                    .send()
+                    .await
+                    .context("Fetching the STRK price")?
+                    .error_for_status()?
+                    .json()
+                    .await
+                    .context("Parsing the STRK price response")?;
+                let value = body.pointer(json_pointer).with_context(|| format!("No STRK price at `{json_pointer}`"))?;
+                let price = match value {
+                    serde_json::Value::Number(number) => number.as_f64(),
+                    serde_json::Value::String(string) => string.parse().ok(),
+                    _ => None,
+                }
+                .with_context(|| format!("Invalid STRK price: {value}"))?;
+                if !(price.is_finite() && price > 0.0) {
+                    bail!("Invalid STRK price: {price}");
+                }
+                let price = (price * 10f64.powi(HTTP_PRICE_DECIMALS.into())) as u128;
+                Ok(StrkPrice { price: U256::from(price), decimals: HTTP_PRICE_DECIMALS })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod strk_price_tests {
+    use super::*;
+    use httpmock::MockServer;
+
+    #[test]
+    fn wei_to_fri_works() {
+        // 1 STRK = 0.0002 ETH
+        let strk_price = StrkPrice { price: U256::from(2u8), decimals: 4 };
+        assert_eq!(strk_price.wei_to_fri(1_000_000_000).unwrap(), 5_000_000_000_000);
+        assert_eq!(strk_price.wei_to_fri(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn wei_to_fri_with_zero_price_fails() {
+        let strk_price = StrkPrice { price: U256::ZERO, decimals: 4 };
+        assert!(strk_price.wei_to_fri(1).is_err());
+    }
+
+    #[tokio::test]
+    async fn get_strk_price_from_http_works() {
+        let mock_server = MockServer::start();
+        mock_server.mock(|when, then| {
+            when.method("GET").path("/price");
+            then.status(200).json_body_obj(&serde_json::json!({ "data": { "price": "0.0002" } }));
+        });
+        let url = format!("http://{}/price", mock_server.address()).parse().unwrap();
+        let source = StrkPriceSource::http(url, "/data/price".into());
+
+        let strk_price = source.get_strk_price().await.expect("issue while getting the STRK price");
+
+        assert_eq!(strk_price.wei_to_fri(1_000_000_000).unwrap(), 5_000_000_000_000);
+    }
+}
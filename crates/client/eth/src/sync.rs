@@ -1,5 +1,5 @@
-use crate::client::EthereumClient;
-use crate::l1_gas_price::gas_price_worker;
+use crate::l1_gas_price::{gas_price_worker, GasPriceBounds, GasPriceSource};
+use crate::settlement::SettlementClient;
 use crate::state_update::state_update_worker;
 use mc_mempool::GasPriceProvider;
 use starknet_types_core::felt::Felt;
@@ -7,17 +7,21 @@ use std::time::Duration;
 
 use mc_db::MadaraBackend;
 
+/// Follows the state verified on the settlement layer, and the L1 gas prices.
+///
+/// `gas_price_source` is `None` when gas price sync is disabled.
 pub async fn l1_sync_worker(
     backend: &MadaraBackend,
-    eth_client: &EthereumClient,
+    settlement_client: &dyn SettlementClient,
     chain_id: Felt,
     l1_gas_provider: GasPriceProvider,
-    gas_price_sync_disabled: bool,
+    gas_price_source: Option<&GasPriceSource>,
+    gas_price_bounds: &GasPriceBounds,
     gas_price_poll_ms: Duration,
 ) -> anyhow::Result<()> {
-    tokio::try_join!(state_update_worker(backend, eth_client, chain_id), async {
-        if !gas_price_sync_disabled {
-            gas_price_worker(eth_client, l1_gas_provider, gas_price_poll_ms).await?;
+    tokio::try_join!(state_update_worker(backend, settlement_client, chain_id), async {
+        if let Some(gas_price_source) = gas_price_source {
+            gas_price_worker(gas_price_source, gas_price_bounds, l1_gas_provider, gas_price_poll_ms).await?;
         }
         Ok(())
     })?;
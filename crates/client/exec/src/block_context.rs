@@ -4,7 +4,7 @@ use blockifier::{
         config::TransactionExecutorConfig, stateful_validator::StatefulValidator,
         transaction_executor::TransactionExecutor,
     },
-    context::{BlockContext, ChainInfo, FeeTokenAddresses},
+    context::{BlockContext, ChainInfo},
     state::cached_state::CachedState,
 };
 use mc_db::{db_block_id::DbBlockId, MadaraBackend};
@@ -16,6 +16,8 @@ pub struct ExecutionContext {
     pub(crate) backend: Arc<MadaraBackend>,
     pub(crate) block_context: BlockContext,
     pub(crate) db_id: DbBlockId,
+    /// State the transactions are executed on top of. `None` when executing the genesis block.
+    pub(crate) on_top_of: Option<DbBlockId>,
 }
 
 impl ExecutionContext {
@@ -33,60 +35,81 @@ impl ExecutionContext {
     }
 
     pub fn init_cached_state(&self) -> CachedState<BlockifierStateAdapter> {
-        let on_top_of = match self.db_id {
-            DbBlockId::Pending => Some(DbBlockId::Pending),
-            DbBlockId::Number(block_n) => {
-                // We exec on top of the previous block. None means we are executing genesis.
-                block_n.checked_sub(1).map(DbBlockId::Number)
-            }
-        };
-
         log::debug!(
             "Init cached state on top of {:?}, block number {:?}",
-            on_top_of,
+            self.on_top_of,
             self.block_context.block_info().block_number.0
         );
 
         CachedState::new(BlockifierStateAdapter::new(
             Arc::clone(&self.backend),
             self.block_context.block_info().block_number.0,
-            on_top_of,
+            self.on_top_of,
         ))
     }
 
     /// Create an execution context for executing transactions **within** that block.
     pub fn new_in_block(backend: Arc<MadaraBackend>, block_info: &MadaraMaybePendingBlockInfo) -> Result<Self, Error> {
-        let (db_id, protocol_version, block_number, block_timestamp, sequencer_address, l1_gas_price, l1_da_mode) =
-            match block_info {
-                MadaraMaybePendingBlockInfo::Pending(block) => (
-                    DbBlockId::Pending,
-                    block.header.protocol_version,
-                    // when the block is pending, we use the latest block n + 1
-                    // if there is no latest block, the pending block is actually the genesis block
-                    backend.get_latest_block_n()?.map(|el| el + 1).unwrap_or(0),
-                    block.header.block_timestamp,
-                    block.header.sequencer_address,
-                    block.header.l1_gas_price.clone(),
-                    block.header.l1_da_mode,
-                ),
-                MadaraMaybePendingBlockInfo::NotPending(block) => (
-                    DbBlockId::Number(block.header.block_number),
-                    block.header.protocol_version,
-                    block.header.block_number,
-                    block.header.block_timestamp,
-                    block.header.sequencer_address,
-                    block.header.l1_gas_price.clone(),
-                    block.header.l1_da_mode,
-                ),
-            };
+        Self::new(backend, block_info, false)
+    }
+
+    /// Create an execution context for executing transactions **after** that block, on top of its state, as the
+    /// sequencer would in the next block. Transactions are executed on top of the pending block within it.
+    pub fn new_at_block_end(
+        backend: Arc<MadaraBackend>,
+        block_info: &MadaraMaybePendingBlockInfo,
+    ) -> Result<Self, Error> {
+        Self::new(backend, block_info, true)
+    }
+
+    fn new(
+        backend: Arc<MadaraBackend>,
+        block_info: &MadaraMaybePendingBlockInfo,
+        at_block_end: bool,
+    ) -> Result<Self, Error> {
+        let (
+            db_id,
+            on_top_of,
+            protocol_version,
+            block_number,
+            block_timestamp,
+            sequencer_address,
+            l1_gas_price,
+            l1_da_mode,
+        ) = match block_info {
+            MadaraMaybePendingBlockInfo::Pending(block) => (
+                DbBlockId::Pending,
+                Some(DbBlockId::Pending),
+                block.header.protocol_version,
+                // when the block is pending, we use the latest block n + 1
+                // if there is no latest block, the pending block is actually the genesis block
+                backend.get_latest_block_n()?.map(|el| el + 1).unwrap_or(0),
+                block.header.block_timestamp,
+                block.header.sequencer_address,
+                block.header.l1_gas_price.clone(),
+                block.header.l1_da_mode,
+            ),
+            MadaraMaybePendingBlockInfo::NotPending(block) => (
+                DbBlockId::Number(block.header.block_number),
+                if at_block_end {
+                    Some(DbBlockId::Number(block.header.block_number))
+                } else {
+                    // We exec on top of the previous block. None means we are executing genesis.
+                    block.header.block_number.checked_sub(1).map(DbBlockId::Number)
+                },
+                block.header.protocol_version,
+                block.header.block_number + u64::from(at_block_end),
+                block.header.block_timestamp,
+                block.header.sequencer_address,
+                block.header.l1_gas_price.clone(),
+                block.header.l1_da_mode,
+            ),
+        };
 
         let versioned_constants = backend.chain_config().exec_constants_by_protocol_version(protocol_version)?;
         let chain_info = ChainInfo {
             chain_id: backend.chain_config().chain_id.clone(),
-            fee_token_addresses: FeeTokenAddresses {
-                strk_fee_token_address: backend.chain_config().native_fee_token_address,
-                eth_fee_token_address: backend.chain_config().parent_fee_token_address,
-            },
+            fee_token_addresses: backend.chain_config().fee_token_addresses(),
         };
         let block_info = blockifier::blockifier::block::BlockInfo {
             block_number: BlockNumber(block_number),
@@ -106,7 +129,33 @@ impl ExecutionContext {
                 backend.chain_config().bouncer_config.clone(),
             ),
             db_id,
+            on_top_of,
             backend,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_block::header::Header;
+    use mp_block::MadaraBlockInfo;
+    use mp_chain_config::{ChainConfig, StarknetVersion};
+    use starknet_types_core::felt::Felt;
+
+    #[test]
+    fn test_new_at_block_end() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let header = Header { block_number: 5, protocol_version: StarknetVersion::LATEST, ..Default::default() };
+        let block_info = MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo::new(header, vec![], Felt::ONE));
+
+        let in_block = ExecutionContext::new_in_block(Arc::clone(&backend), &block_info).unwrap();
+        assert_eq!(in_block.block_context.block_info().block_number, BlockNumber(5));
+        assert_eq!(in_block.on_top_of, Some(DbBlockId::Number(4)));
+
+        // Executed as the next block, on top of the state of the block.
+        let at_block_end = ExecutionContext::new_at_block_end(backend, &block_info).unwrap();
+        assert_eq!(at_block_end.block_context.block_info().block_number, BlockNumber(6));
+        assert_eq!(at_block_end.on_top_of, Some(DbBlockId::Number(5)));
+    }
+}
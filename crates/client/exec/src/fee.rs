@@ -1,7 +1,26 @@
-use crate::{ExecutionContext, ExecutionResult};
+use crate::{Error, ExecutionContext, ExecutionResult, MessageFeeEstimationError, TxReexecError};
 use blockifier::transaction::objects::FeeType;
+use blockifier::transaction::transaction_execution::Transaction;
+use blockifier::transaction::transactions::L1HandlerTransaction;
 
 impl ExecutionContext {
+    /// Estimate the fee of the L1 handler transaction of a message sent from L1, as charged by the sequencer.
+    /// The fee is not charged to the contract: it is paid on L1 when sending the message.
+    pub fn estimate_message_fee(&self, tx: L1HandlerTransaction) -> Result<starknet_core::types::FeeEstimate, Error> {
+        let execution_result = self
+            .re_execute_transactions([], [Transaction::L1HandlerTransaction(tx)], false, true)
+            .map_err(|err| match err {
+                Error::Reexecution(TxReexecError { err, .. }) => {
+                    MessageFeeEstimationError { block_n: self.db_id, err }.into()
+                }
+                err => err,
+            })?
+            .pop()
+            .expect("One transaction was executed");
+
+        Ok(self.execution_result_to_fee_estimate(&execution_result))
+    }
+
     pub fn execution_result_to_fee_estimate(
         &self,
         executions_result: &ExecutionResult,
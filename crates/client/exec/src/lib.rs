@@ -16,11 +16,15 @@ mod blockifier_state_adapter;
 mod call;
 mod execution;
 mod fee;
+pub mod metrics;
 mod trace;
 
 pub use block_context::ExecutionContext;
 pub use blockifier_state_adapter::BlockifierStateAdapter;
-pub use trace::execution_result_to_tx_trace;
+pub use trace::{
+    execution_result_to_call_resources, execution_result_to_tx_trace, CallResources, SyscallCounts,
+    TransactionCallResources,
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -51,7 +55,7 @@ impl From<Error> for StarknetRpcApiError {
                 error: "Transaction reexecution error".to_string(),
             },
             Error::FeeEstimation(_) => StarknetRpcApiError::InsufficientMaxFee,
-            Error::MessageFeeEstimation(_) => StarknetRpcApiError::InsufficientMaxFee,
+            Error::MessageFeeEstimation(_) => StarknetRpcApiError::ContractError,
             Error::CallContract(_) => StarknetRpcApiError::ContractError,
             Error::Storage(_) => StarknetRpcApiError::ErrUnexpectedError { data: "Storage error".to_string() },
             Error::InvalidSequencerAddress(_) => {
@@ -0,0 +1,147 @@
+//! Resources consumed by the execution of whole blocks, so that the bouncer capacity and the hardware of a node can be
+//! sized from the actual load of its chain.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use mc_metrics::{exponential_buckets, HistogramOpts, HistogramVec, MetricsRegistry, PrometheusError};
+use mp_receipt::TransactionReceipt;
+
+/// What executed the block, as reported in the `source` label of the metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionSource {
+    /// The block was produced by this node.
+    BlockProduction,
+    /// A closed block was executed again, to trace it.
+    ReExecution,
+}
+
+impl ExecutionSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::BlockProduction => "block_production",
+            Self::ReExecution => "re_execution",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlockExecutionMetrics {
+    cairo_steps: HistogramVec,
+    l1_gas: HistogramVec,
+    l1_data_gas: HistogramVec,
+    state_diff_entries: HistogramVec,
+    execution_time: HistogramVec,
+}
+
+impl BlockExecutionMetrics {
+    /// Registers the metrics the first time, and returns the same ones to the other services executing blocks.
+    pub fn get_or_register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        if let Some(metrics) = METRICS.get() {
+            return Ok(metrics.clone());
+        }
+        let metrics = Self::register(registry)?;
+        Ok(METRICS.get_or_init(|| metrics).clone())
+    }
+
+    /// The metrics, once registered by [`Self::get_or_register`].
+    pub fn get() -> Option<Self> {
+        METRICS.get().cloned()
+    }
+
+    fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        let histogram = |name: &str, help: &str, buckets: Vec<f64>| {
+            registry.register(HistogramVec::new(HistogramOpts::new(name, help).buckets(buckets), &["source"])?)
+        };
+        Ok(Self {
+            cairo_steps: histogram(
+                "madara_block_cairo_steps",
+                "Number of Cairo steps executed in a block",
+                exponential_buckets(10_000.0, 4.0, 10)?,
+            )?,
+            l1_gas: histogram(
+                "madara_block_l1_gas",
+                "L1 gas consumed by the transactions of a block",
+                exponential_buckets(1_000.0, 4.0, 12)?,
+            )?,
+            l1_data_gas: histogram(
+                "madara_block_l1_data_gas",
+                "L1 data gas consumed by the transactions of a block",
+                exponential_buckets(100.0, 4.0, 12)?,
+            )?,
+            state_diff_entries: histogram(
+                "madara_block_state_diff_entries",
+                "Number of entries in the state diff of a block",
+                exponential_buckets(1.0, 4.0, 10)?,
+            )?,
+            execution_time: histogram(
+                "madara_block_execution_time_seconds",
+                "Time [s] spent executing the transactions of a block",
+                exponential_buckets(0.001, 4.0, 10)?,
+            )?,
+        })
+    }
+
+    /// `state_diff_entries` is unknown for the blocks imported before the state diff length was committed to.
+    pub fn record(
+        &self,
+        source: ExecutionSource,
+        receipts: &[TransactionReceipt],
+        state_diff_entries: Option<usize>,
+        execution_time: Duration,
+    ) {
+        let label = [source.as_str()];
+        let (mut cairo_steps, mut l1_gas, mut l1_data_gas) = (0u64, 0u128, 0u128);
+        for receipt in receipts {
+            let resources = receipt.execution_resources();
+            cairo_steps += resources.steps;
+            l1_gas += resources.total_gas_consumed.l1_gas;
+            l1_data_gas += resources.total_gas_consumed.l1_data_gas;
+        }
+        self.cairo_steps.with_label_values(&label).observe(cairo_steps as f64);
+        self.l1_gas.with_label_values(&label).observe(l1_gas as f64);
+        self.l1_data_gas.with_label_values(&label).observe(l1_data_gas as f64);
+        if let Some(state_diff_entries) = state_diff_entries {
+            self.state_diff_entries.with_label_values(&label).observe(state_diff_entries as f64);
+        }
+        self.execution_time.with_label_values(&label).observe(execution_time.as_secs_f64());
+    }
+}
+
+static METRICS: OnceLock<BlockExecutionMetrics> = OnceLock::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_receipt::{ExecutionResources, InvokeTransactionReceipt, L1Gas};
+
+    fn receipt(steps: u64, l1_gas: u128, l1_data_gas: u128) -> TransactionReceipt {
+        TransactionReceipt::Invoke(InvokeTransactionReceipt {
+            execution_resources: ExecutionResources {
+                steps,
+                total_gas_consumed: L1Gas { l1_gas, l1_data_gas },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn record_sums_the_receipts_of_the_block() {
+        let metrics = BlockExecutionMetrics::register(&MetricsRegistry::dummy()).unwrap();
+        metrics.record(
+            ExecutionSource::BlockProduction,
+            &[receipt(100, 10, 1), receipt(200, 20, 2)],
+            Some(7),
+            Duration::from_millis(500),
+        );
+
+        let label = ["block_production"];
+        assert_eq!(metrics.cairo_steps.with_label_values(&label).get_sample_sum(), 300.0);
+        assert_eq!(metrics.l1_gas.with_label_values(&label).get_sample_sum(), 30.0);
+        assert_eq!(metrics.l1_data_gas.with_label_values(&label).get_sample_sum(), 3.0);
+        assert_eq!(metrics.state_diff_entries.with_label_values(&label).get_sample_sum(), 7.0);
+        assert_eq!(metrics.execution_time.with_label_values(&label).get_sample_sum(), 0.5);
+        assert_eq!(metrics.cairo_steps.with_label_values(&["re_execution"]).get_sample_count(), 0);
+    }
+}
@@ -3,7 +3,10 @@ use std::collections::HashMap;
 use blockifier::state::cached_state::CommitmentStateDiff;
 use blockifier::{execution::call_info::CallInfo, transaction::transaction_types::TransactionType};
 use cairo_vm::types::builtin_name::BuiltinName;
+use cairo_vm::vm::runners::cairo_runner::ExecutionResources;
 use mp_convert::ToFelt;
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
 
 use crate::{ExecutionResult, TransactionExecutionError};
 
@@ -108,6 +111,90 @@ pub fn execution_result_to_tx_trace(
     Ok(tx_trace)
 }
 
+/// Resources used by the calls of a transaction, for profiling contracts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionCallResources {
+    pub validate_invocation: Option<CallResources>,
+    /// The `__execute__` call of invoke transactions, the constructor of deploy account transactions, or the handler
+    /// of L1 handler transactions. `None` for reverted transactions.
+    pub execute_invocation: Option<CallResources>,
+    pub fee_transfer_invocation: Option<CallResources>,
+}
+
+/// Resources used by a call and its inner calls.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallResources {
+    pub contract_address: Felt,
+    pub class_hash: Felt,
+    pub entry_point_selector: Felt,
+    /// Resources of the call, including its inner calls and the OS overhead of its syscalls.
+    pub execution_resources: starknet_core::types::ComputationResources,
+    /// Resources of the call itself, excluding its inner calls.
+    pub own_execution_resources: starknet_core::types::ComputationResources,
+    pub syscalls: SyscallCounts,
+    pub calls: Vec<CallResources>,
+}
+
+/// Syscalls made by a call, excluding its inner calls. The execution engine does not record the storage writes of
+/// the calls, which are not counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallCounts {
+    pub call_contract: u64,
+    pub library_call: u64,
+    pub deploy: u64,
+    pub storage_read: u64,
+    pub emit_event: u64,
+    pub send_message_to_l1: u64,
+}
+
+pub fn execution_result_to_call_resources(executions_result: &ExecutionResult) -> TransactionCallResources {
+    let execution_info = &executions_result.execution_info;
+    TransactionCallResources {
+        validate_invocation: execution_info.validate_call_info.as_ref().map(call_resources),
+        execute_invocation: execution_info.execute_call_info.as_ref().map(call_resources),
+        fee_transfer_invocation: execution_info.fee_transfer_call_info.as_ref().map(call_resources),
+    }
+}
+
+fn call_resources(call_info: &CallInfo) -> CallResources {
+    let mut syscalls = SyscallCounts {
+        storage_read: call_info.storage_read_values.len() as u64,
+        emit_event: call_info.execution.events.len() as u64,
+        send_message_to_l1: call_info.execution.l2_to_l1_messages.len() as u64,
+        ..Default::default()
+    };
+    for inner_call in &call_info.inner_calls {
+        match (inner_call.call.entry_point_type, inner_call.call.call_type) {
+            (starknet_api::deprecated_contract_class::EntryPointType::Constructor, _) => syscalls.deploy += 1,
+            (_, blockifier::execution::entry_point::CallType::Call) => syscalls.call_contract += 1,
+            (_, blockifier::execution::entry_point::CallType::Delegate) => syscalls.library_call += 1,
+        }
+    }
+
+    // The resources of a call include the resources of its inner calls.
+    let mut own_resources = call_info.resources.clone();
+    for inner_call in &call_info.inner_calls {
+        own_resources.n_steps = own_resources.n_steps.saturating_sub(inner_call.resources.n_steps);
+        own_resources.n_memory_holes = own_resources.n_memory_holes.saturating_sub(inner_call.resources.n_memory_holes);
+        for (builtin, count) in &inner_call.resources.builtin_instance_counter {
+            if let Some(own_count) = own_resources.builtin_instance_counter.get_mut(builtin) {
+                *own_count = own_count.saturating_sub(*count);
+            }
+        }
+    }
+    own_resources.builtin_instance_counter.retain(|_, count| *count > 0);
+
+    CallResources {
+        contract_address: call_info.call.storage_address.0.to_felt(),
+        class_hash: call_info.call.class_hash.map(ToFelt::to_felt).unwrap_or_default(),
+        entry_point_selector: call_info.call.entry_point_selector.0,
+        execution_resources: computation_resources(&call_info.resources),
+        own_execution_resources: computation_resources(&own_resources),
+        syscalls,
+        calls: call_info.inner_calls.iter().map(call_resources).collect(),
+    }
+}
+
 fn try_get_funtion_invocation_from_call_info(
     call_info: &CallInfo,
 ) -> Result<starknet_core::types::FunctionInvocation, TryFuntionInvocationFromCallInfoError> {
@@ -182,9 +269,7 @@ fn collect_call_info_ordered_events(
         .collect()
 }
 
-fn computation_resources(
-    vm_resources: &cairo_vm::vm::runners::cairo_runner::ExecutionResources,
-) -> starknet_core::types::ComputationResources {
+fn computation_resources(vm_resources: &ExecutionResources) -> starknet_core::types::ComputationResources {
     let steps = vm_resources.n_steps as u64;
     let memory_holes = vm_resources.n_memory_holes as u64;
     resources_mapping(&vm_resources.builtin_instance_counter, steps, memory_holes)
@@ -348,3 +433,41 @@ fn agregate_execution_ressources(
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blockifier::execution::entry_point::{CallEntryPoint, CallType};
+    use rstest::rstest;
+
+    fn call_info(call_type: CallType, n_steps: usize, pedersen: usize, inner_calls: Vec<CallInfo>) -> CallInfo {
+        CallInfo {
+            call: CallEntryPoint { call_type, ..Default::default() },
+            resources: ExecutionResources {
+                n_steps,
+                n_memory_holes: 0,
+                builtin_instance_counter: [(BuiltinName::pedersen, pedersen)].into(),
+            },
+            inner_calls,
+            ..Default::default()
+        }
+    }
+
+    #[rstest]
+    fn call_resources_exclude_inner_calls() {
+        let call = call_info(
+            CallType::Call,
+            100,
+            3,
+            vec![call_info(CallType::Call, 30, 1, vec![]), call_info(CallType::Delegate, 20, 2, vec![])],
+        );
+
+        let resources = call_resources(&call);
+        assert_eq!(resources.execution_resources.steps, 100);
+        assert_eq!(resources.execution_resources.pedersen_builtin_applications, Some(3));
+        assert_eq!(resources.own_execution_resources.steps, 50);
+        assert_eq!(resources.own_execution_resources.pedersen_builtin_applications, None);
+        assert_eq!(resources.syscalls, SyscallCounts { call_contract: 1, library_call: 1, ..Default::default() });
+        assert_eq!(resources.calls[1].own_execution_resources.steps, 20);
+    }
+}
@@ -53,6 +53,13 @@ impl StarknetError {
         }
     }
 
+    pub fn no_signature_for_pending_block() -> Self {
+        Self {
+            code: StarknetErrorCode::NoSignatureForPendingBlock,
+            message: "Pending blocks are not signed".to_string(),
+        }
+    }
+
     pub fn malformed_request(e: serde_json::Error) -> Self {
         Self { code: StarknetErrorCode::MalformedRequest, message: format!("Failed to parse transaction: {}", e) }
     }
@@ -135,4 +142,6 @@ pub enum StarknetErrorCode {
     InvalidContractClassVersion,
     #[serde(rename = "StarknetErrorCode.RATE_LIMITED")]
     RateLimited,
+    #[serde(rename = "StarknetErrorCode.NO_SIGNATURE_FOR_PENDING_BLOCK")]
+    NoSignatureForPendingBlock,
 }
@@ -1,9 +1,10 @@
 use std::fmt::{self, Display};
 
 use hyper::{Body, Response};
+use jsonrpsee::types::ErrorObjectOwned;
 use mc_db::MadaraStorageError;
 
-use crate::error::StarknetError;
+use crate::error::{StarknetError, StarknetErrorCode};
 
 use super::helpers::internal_error_response;
 
@@ -22,6 +23,38 @@ impl From<MadaraStorageError> for GatewayError {
     }
 }
 
+/// Maps the RPC errors of the add transaction provider to the error codes of the gateway.
+impl From<ErrorObjectOwned> for GatewayError {
+    fn from(e: ErrorObjectOwned) -> Self {
+        let code = match e.code() {
+            20 => StarknetErrorCode::UninitializedContract,
+            28 => StarknetErrorCode::UndeclaredClass,
+            40 | 41 => StarknetErrorCode::TransactionFailed,
+            50 => StarknetErrorCode::InvalidContractClass,
+            51 => StarknetErrorCode::ClassAlreadyDeclared,
+            52 => StarknetErrorCode::InvalidTransactionNonce,
+            53 => StarknetErrorCode::InsufficientMaxFee,
+            54 => StarknetErrorCode::InsufficientAccountBalance,
+            55 | 58 => StarknetErrorCode::ValidateFailure,
+            56 => StarknetErrorCode::CompilationFailed,
+            57 => StarknetErrorCode::ContractClassObjectSizeTooLarge,
+            59 => StarknetErrorCode::DuplicatedTransaction,
+            60 => StarknetErrorCode::InvalidCompiledClassHash,
+            61 => StarknetErrorCode::InvalidTransactionVersion,
+            62 => StarknetErrorCode::InvalidContractClassVersion,
+            _ => {
+                log::error!(target: "gateway_errors", "Adding transaction: {e}");
+                return Self::InternalServerError(e.message().to_string());
+            }
+        };
+        let message = match e.data() {
+            Some(data) => format!("{}: {}", e.message(), data.get()),
+            None => e.message().to_string(),
+        };
+        Self::StarknetError(StarknetError::new(code, message))
+    }
+}
+
 impl From<GatewayError> for Response<Body> {
     fn from(e: GatewayError) -> Response<Body> {
         match e {
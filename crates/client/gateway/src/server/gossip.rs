@@ -0,0 +1,269 @@
+//! Gossip of the mempool between the replicas of a sequencer. The transactions accepted by a replica are pushed to the
+//! gateway of the other replicas, authenticated with a secret shared by all of them, so that users can submit to any
+//! replica while a single one produces blocks, and any replica can take over with the same mempool.
+//!
+//! The transactions received from a peer are added to the local mempool without being pushed again, so every replica
+//! must list all the others as peers.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::{Body, Request};
+use jsonrpsee::core::{async_trait, RpcResult};
+use mp_rpc::{AddTransactionProvider, TransactionBundleResult};
+use starknet_core::types::{
+    BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction,
+    BroadcastedTransaction, DeclareTransactionResult, DeployAccountTransactionResult, InvokeTransactionResult,
+};
+use url::Url;
+
+/// The secret shared by the replicas, authenticating the pushed transactions.
+pub const GOSSIP_SECRET_HEADER: &str = "x-gossip-secret";
+/// Path of the gateway endpoint receiving the transactions pushed by the peers.
+pub const GOSSIP_PATH: &str = "/gossip/add_transaction";
+
+const PUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct GossipConfig {
+    /// Gateway URLs of the other replicas.
+    pub peers: Vec<Url>,
+    pub secret: String,
+}
+
+/// This [`AddTransactionProvider`] adds the received transactions with the inner provider, and pushes the accepted
+/// ones to the peers in the background.
+pub struct GossipAddTxProvider {
+    inner: Arc<dyn AddTransactionProvider>,
+    client: reqwest::Client,
+    config: GossipConfig,
+}
+
+impl GossipAddTxProvider {
+    pub fn new(inner: Arc<dyn AddTransactionProvider>, config: GossipConfig) -> Self {
+        Self { inner, client: reqwest::Client::new(), config }
+    }
+
+    fn push(&self, transaction: BroadcastedTransaction) {
+        let transaction = Arc::new(transaction);
+        for peer in &self.config.peers {
+            let url = match peer.join(GOSSIP_PATH) {
+                Ok(url) => url,
+                Err(err) => {
+                    log::warn!("Invalid mempool gossip peer {peer}: {err:#}");
+                    continue;
+                }
+            };
+            let request = self
+                .client
+                .post(url)
+                .header(GOSSIP_SECRET_HEADER, &self.config.secret)
+                .json(transaction.as_ref())
+                .timeout(PUSH_TIMEOUT);
+            let peer = peer.clone();
+            tokio::spawn(async move {
+                if let Err(err) = request.send().await.and_then(|response| response.error_for_status()) {
+                    log::warn!("Pushing a transaction to mempool gossip peer {peer}: {err:#}");
+                }
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl AddTransactionProvider for GossipAddTxProvider {
+    async fn add_declare_transaction(
+        &self,
+        declare_transaction: BroadcastedDeclareTransaction,
+    ) -> RpcResult<DeclareTransactionResult> {
+        let result = self.inner.add_declare_transaction(declare_transaction.clone()).await?;
+        self.push(BroadcastedTransaction::Declare(declare_transaction));
+        Ok(result)
+    }
+    async fn add_deploy_account_transaction(
+        &self,
+        deploy_account_transaction: BroadcastedDeployAccountTransaction,
+    ) -> RpcResult<DeployAccountTransactionResult> {
+        let result = self.inner.add_deploy_account_transaction(deploy_account_transaction.clone()).await?;
+        self.push(BroadcastedTransaction::DeployAccount(deploy_account_transaction));
+        Ok(result)
+    }
+    async fn add_invoke_transaction(
+        &self,
+        invoke_transaction: BroadcastedInvokeTransaction,
+    ) -> RpcResult<InvokeTransactionResult> {
+        let result = self.inner.add_invoke_transaction(invoke_transaction.clone()).await?;
+        self.push(BroadcastedTransaction::Invoke(invoke_transaction));
+        Ok(result)
+    }
+    /// Bundles are not pushed to the peers: the caller waits for the outcome from the replica which received it.
+    async fn add_transaction_bundle(
+        &self,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> RpcResult<TransactionBundleResult> {
+        self.inner.add_transaction_bundle(transactions).await
+    }
+}
+
+/// Adds the transactions pushed by the peers to the local mempool.
+#[derive(Clone)]
+pub struct GossipReceiver {
+    secret: String,
+    pub(crate) add_transaction_provider: Arc<dyn AddTransactionProvider>,
+}
+
+impl GossipReceiver {
+    /// `add_transaction_provider` must not push the transactions to the peers again.
+    pub fn new(secret: String, add_transaction_provider: Arc<dyn AddTransactionProvider>) -> Self {
+        Self { secret, add_transaction_provider }
+    }
+
+    pub(crate) fn is_authorized(&self, req: &Request<Body>) -> bool {
+        has_secret(req, &self.secret)
+    }
+}
+
+fn has_secret(req: &Request<Body>, secret: &str) -> bool {
+    req.headers().get(GOSSIP_SECRET_HEADER).is_some_and(|value| constant_time_eq(value.as_bytes(), secret.as_bytes()))
+}
+
+/// Compares without returning early at the first difference, so that the time taken does not tell how much of the
+/// secret was guessed right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::router::gossip_router;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Server, StatusCode};
+    use mp_rpc::errors::StarknetRpcApiError;
+    use starknet_core::types::{BroadcastedInvokeTransactionV1, Felt};
+    use std::convert::Infallible;
+    use std::sync::Mutex;
+
+    /// Records the invoke transactions it receives.
+    #[derive(Default)]
+    struct RecordingProvider {
+        invoke_transactions: Mutex<Vec<BroadcastedInvokeTransaction>>,
+    }
+
+    impl RecordingProvider {
+        fn invoke_transactions(&self) -> Vec<BroadcastedInvokeTransaction> {
+            self.invoke_transactions.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl AddTransactionProvider for RecordingProvider {
+        async fn add_declare_transaction(
+            &self,
+            _declare_transaction: BroadcastedDeclareTransaction,
+        ) -> RpcResult<DeclareTransactionResult> {
+            Err(StarknetRpcApiError::UnimplementedMethod.into())
+        }
+        async fn add_deploy_account_transaction(
+            &self,
+            _deploy_account_transaction: BroadcastedDeployAccountTransaction,
+        ) -> RpcResult<DeployAccountTransactionResult> {
+            Err(StarknetRpcApiError::UnimplementedMethod.into())
+        }
+        async fn add_invoke_transaction(
+            &self,
+            invoke_transaction: BroadcastedInvokeTransaction,
+        ) -> RpcResult<InvokeTransactionResult> {
+            self.invoke_transactions.lock().unwrap().push(invoke_transaction);
+            Ok(InvokeTransactionResult { transaction_hash: Felt::ONE })
+        }
+    }
+
+    fn invoke_transaction() -> BroadcastedInvokeTransaction {
+        BroadcastedInvokeTransaction::V1(BroadcastedInvokeTransactionV1 {
+            sender_address: Felt::from(0x1234),
+            calldata: vec![Felt::ONE, Felt::TWO],
+            max_fee: Felt::from(1_000_000),
+            signature: vec![Felt::THREE],
+            nonce: Felt::ZERO,
+            is_query: false,
+        })
+    }
+
+    /// Serves the gossip endpoint of a replica, returning its gateway URL.
+    fn serve_gossip(receiver: GossipReceiver) -> Url {
+        let receiver = Arc::new(receiver);
+        let make_service = make_service_fn(move |_| {
+            let receiver = Arc::clone(&receiver);
+            async move { Ok::<_, Infallible>(service_fn(move |req| gossip_router(req, Some(Arc::clone(&receiver))))) }
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let url = Url::parse(&format!("http://{}/", server.local_addr())).unwrap();
+        tokio::spawn(server);
+        url
+    }
+
+    #[test]
+    fn gossip_secret() {
+        let request =
+            |secret: &str| Request::builder().header(GOSSIP_SECRET_HEADER, secret).body(Body::empty()).unwrap();
+
+        assert!(has_secret(&request("secret"), "secret"));
+        assert!(!has_secret(&request("wrong"), "secret"));
+        assert!(!has_secret(&Request::new(Body::empty()), "secret"));
+        assert!(!has_secret(&request("secret2"), "secret"));
+        assert!(!has_secret(&request("secre"), "secret"));
+    }
+
+    #[test]
+    fn peer_endpoint() {
+        let peer = Url::parse("http://replica-2:8080/").unwrap();
+        assert_eq!(peer.join(GOSSIP_PATH).unwrap().as_str(), "http://replica-2:8080/gossip/add_transaction");
+    }
+
+    #[tokio::test]
+    async fn push_to_peers() {
+        let peer_mempool = Arc::new(RecordingProvider::default());
+        let peer = serve_gossip(GossipReceiver::new("secret".into(), peer_mempool.clone()));
+
+        let local_mempool = Arc::new(RecordingProvider::default());
+        let provider = GossipAddTxProvider::new(
+            local_mempool.clone(),
+            GossipConfig { peers: vec![peer], secret: "secret".into() },
+        );
+        let result = provider.add_invoke_transaction(invoke_transaction()).await.unwrap();
+        assert_eq!(result.transaction_hash, Felt::ONE);
+        assert_eq!(local_mempool.invoke_transactions(), vec![invoke_transaction()]);
+
+        // The transaction is pushed in the background.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while peer_mempool.invoke_transactions().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("The transaction was not pushed to the peer");
+        assert_eq!(peer_mempool.invoke_transactions(), vec![invoke_transaction()]);
+    }
+
+    #[tokio::test]
+    async fn receive_requires_secret() {
+        let peer_mempool = Arc::new(RecordingProvider::default());
+        let url = serve_gossip(GossipReceiver::new("secret".into(), peer_mempool.clone())).join(GOSSIP_PATH).unwrap();
+        let transaction = BroadcastedTransaction::Invoke(invoke_transaction());
+        let client = reqwest::Client::new();
+
+        let response = client.post(url.clone()).json(&transaction).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response =
+            client.post(url.clone()).header(GOSSIP_SECRET_HEADER, "wrong").json(&transaction).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = client.get(url.clone()).header(GOSSIP_SECRET_HEADER, "secret").send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(peer_mempool.invoke_transactions().is_empty());
+
+        let response = client.post(url).header(GOSSIP_SECRET_HEADER, "secret").json(&transaction).send().await.unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(peer_mempool.invoke_transactions(), vec![invoke_transaction()]);
+    }
+}
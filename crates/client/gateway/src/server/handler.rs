@@ -2,21 +2,21 @@ use std::sync::Arc;
 
 use hyper::{body, Body, Request, Response};
 use mc_db::MadaraBackend;
+use mc_rpc::versions::v0_7_1::StarknetTraceRpcApiV0_7_1Server;
 use mp_block::{BlockId, BlockTag, MadaraBlock, MadaraPendingBlock};
-use mp_class::ContractClass;
+use mp_class::{ClassInfo, ContractClass};
 use mp_gateway::{
-    block::{BlockStatus, ProviderBlock, ProviderBlockPending},
+    block::{BlockStatus, ProviderBlock, ProviderBlockPending, ProviderBlockSignature},
     state_update::{ProviderStateUpdate, ProviderStateUpdatePending},
+    trace::{BlockTraces, TransactionTrace},
+    user_transaction::{AddTransactionResult, UserTransaction},
 };
-use mp_rpc::AddTransactionProvider;
+use mp_rpc::{AddTransactionProvider, Starknet};
 use serde_json::json;
-use starknet_core::types::{
-    BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction,
-    BroadcastedTransaction,
-};
+use starknet_core::types::BroadcastedTransaction;
 use starknet_types_core::felt::Felt;
 
-use crate::error::StarknetError;
+use crate::error::{StarknetError, StarknetErrorCode};
 
 use super::{
     error::{GatewayError, OptionExt, ResultExt},
@@ -24,6 +24,8 @@ use super::{
         block_id_from_params, create_json_response, create_response_with_json_body, get_params_from_request,
         include_block_params,
     },
+    trace_cache::TraceCache,
+    validation::validate_transaction,
 };
 
 pub async fn handle_get_block(req: Request<Body>, backend: Arc<MadaraBackend>) -> Result<Response<Body>, GatewayError> {
@@ -191,50 +193,135 @@ pub async fn handle_get_class_by_hash(
     Ok(json_response)
 }
 
-pub async fn handle_add_transaction(
+pub async fn handle_get_compiled_class_by_class_hash(
     req: Request<Body>,
-    add_transaction_provider: Arc<dyn AddTransactionProvider>,
+    backend: Arc<MadaraBackend>,
 ) -> Result<Response<Body>, GatewayError> {
-    let whole_body = body::to_bytes(req.into_body()).await.or_internal_server_error("Failed to read request body")?;
+    let params = get_params_from_request(&req);
+    let block_id = block_id_from_params(&params).unwrap_or(BlockId::Tag(BlockTag::Latest));
 
-    let transaction = serde_json::from_slice::<BroadcastedTransaction>(whole_body.as_ref())
-        .map_err(|e| GatewayError::StarknetError(StarknetError::malformed_request(e)))?;
+    let class_hash = params.get("classHash").ok_or(StarknetError::missing_class_hash())?;
+    let class_hash = Felt::from_hex(class_hash).map_err(StarknetError::invalid_class_hash)?;
+
+    let class_info = backend
+        .get_class_info(&block_id, &class_hash)
+        .or_internal_server_error(format!("Retrieving class info from class hash {class_hash:x}"))?
+        .ok_or(StarknetError::class_not_found(class_hash))?;
 
-    let response = match transaction {
-        BroadcastedTransaction::Declare(tx) => declare_transaction(tx, add_transaction_provider).await,
-        BroadcastedTransaction::DeployAccount(tx) => deploy_account_transaction(tx, add_transaction_provider).await,
-        BroadcastedTransaction::Invoke(tx) => invoke_transaction(tx, add_transaction_provider).await,
+    let ClassInfo::Sierra(class_info) = class_info else {
+        // Legacy classes are not compiled, their definition is the one returned by `get_class_by_hash`.
+        return Err(StarknetError::class_not_found(class_hash).into());
     };
 
-    Ok(response)
+    let compiled = backend
+        .get_sierra_compiled(&block_id, &class_info.compiled_class_hash)
+        .or_internal_server_error(format!("Retrieving compiled class from class hash {class_hash:x}"))?
+        .ok_or_internal_server_error(format!("Inconsistent state: compiled class {class_hash:x} not found"))?;
+
+    Ok(create_response_with_json_body(hyper::StatusCode::OK, compiled.as_ref()))
 }
 
-async fn declare_transaction(
-    tx: BroadcastedDeclareTransaction,
-    add_transaction_provider: Arc<dyn AddTransactionProvider>,
-) -> Response<Body> {
-    match add_transaction_provider.add_declare_transaction(tx).await {
-        Ok(result) => create_json_response(hyper::StatusCode::OK, &result),
-        Err(e) => create_json_response(hyper::StatusCode::OK, &e),
-    }
+pub async fn handle_get_signature(
+    req: Request<Body>,
+    backend: Arc<MadaraBackend>,
+) -> Result<Response<Body>, GatewayError> {
+    let params = get_params_from_request(&req);
+    let block_id = block_id_from_params(&params).or_internal_server_error("Retrieving block id")?;
+
+    let block_info = backend
+        .get_block_info(&block_id)
+        .or_internal_server_error(format!("Retrieving block info at block {block_id}"))?
+        .ok_or(StarknetError::block_not_found())?;
+    let block_info = block_info.as_nonpending().ok_or(StarknetError::no_signature_for_pending_block())?;
+
+    // Blocks produced without a signing key, or synced from another sequencer, have no signature.
+    let signature = backend
+        .get_block_signature(block_info.header.block_number)
+        .or_internal_server_error(format!("Retrieving signature of block {block_id}"))?
+        .unwrap_or_default();
+    let signature = ProviderBlockSignature { block_hash: block_info.block_hash, signature };
+
+    Ok(create_json_response(hyper::StatusCode::OK, &signature))
 }
 
-async fn deploy_account_transaction(
-    tx: BroadcastedDeployAccountTransaction,
+pub async fn handle_get_public_key(
+    _req: Request<Body>,
+    backend: Arc<MadaraBackend>,
+) -> Result<Response<Body>, GatewayError> {
+    // Zero when the blocks are not signed.
+    let public_key = backend
+        .get_sequencer_public_key()
+        .or_internal_server_error("Retrieving the sequencer public key")?
+        .unwrap_or(Felt::ZERO);
+    Ok(create_json_response(hyper::StatusCode::OK, &public_key))
+}
+
+pub async fn handle_get_block_traces(
+    req: Request<Body>,
+    backend: Arc<MadaraBackend>,
     add_transaction_provider: Arc<dyn AddTransactionProvider>,
-) -> Response<Body> {
-    match add_transaction_provider.add_deploy_account_transaction(tx).await {
-        Ok(result) => create_json_response(hyper::StatusCode::OK, &result),
-        Err(e) => create_json_response(hyper::StatusCode::OK, &e),
+    trace_cache: Arc<TraceCache>,
+) -> Result<Response<Body>, GatewayError> {
+    let params = get_params_from_request(&req);
+    let block_id = block_id_from_params(&params).or_internal_server_error("Retrieving block id")?;
+
+    let block = backend
+        .get_block(&block_id)
+        .or_internal_server_error(format!("Retrieving block {block_id}"))?
+        .ok_or(StarknetError::block_not_found())?;
+
+    // The pending block changes, its traces are not cached.
+    let block_hash = block.info.block_hash();
+    if let Some(traces) = block_hash.and_then(|block_hash| trace_cache.get(&block_hash)) {
+        return Ok(create_json_response(hyper::StatusCode::OK, &*traces));
     }
+
+    let starknet = Starknet::new(Arc::clone(&backend), Arc::clone(backend.chain_config()), add_transaction_provider);
+    let traces = starknet
+        .trace_block_transactions(block_id.into())
+        .await
+        .or_internal_server_error(format!("Tracing transactions of block {block_id}"))?;
+
+    let traces = traces
+        .into_iter()
+        .zip(block.inner.transactions)
+        .map(|(trace, tx)| TransactionTrace::new(trace, tx.signature().to_vec()))
+        .collect();
+    let traces = Arc::new(BlockTraces { traces });
+    if let Some(block_hash) = block_hash {
+        trace_cache.insert(block_hash, Arc::clone(&traces));
+    }
+
+    Ok(create_json_response(hyper::StatusCode::OK, &*traces))
 }
 
-async fn invoke_transaction(
-    tx: BroadcastedInvokeTransaction,
+pub async fn handle_add_transaction(
+    req: Request<Body>,
     add_transaction_provider: Arc<dyn AddTransactionProvider>,
-) -> Response<Body> {
-    match add_transaction_provider.add_invoke_transaction(tx).await {
-        Ok(result) => create_json_response(hyper::StatusCode::OK, &result),
-        Err(e) => create_json_response(hyper::StatusCode::OK, &e),
+) -> Result<Response<Body>, GatewayError> {
+    let whole_body = body::to_bytes(req.into_body()).await.or_internal_server_error("Failed to read request body")?;
+
+    let transaction = parse_transaction(whole_body.as_ref())?;
+    validate_transaction(&transaction)?;
+
+    let result: AddTransactionResult = match transaction {
+        BroadcastedTransaction::Declare(tx) => add_transaction_provider.add_declare_transaction(tx).await?.into(),
+        BroadcastedTransaction::DeployAccount(tx) => {
+            add_transaction_provider.add_deploy_account_transaction(tx).await?.into()
+        }
+        BroadcastedTransaction::Invoke(tx) => add_transaction_provider.add_invoke_transaction(tx).await?.into(),
+    };
+
+    Ok(create_json_response(hyper::StatusCode::OK, &result))
+}
+
+/// Transactions are expected in the gateway format, and are also accepted in the RPC format for compatibility.
+fn parse_transaction(body: &[u8]) -> Result<BroadcastedTransaction, StarknetError> {
+    let value: serde_json::Value = serde_json::from_slice(body).map_err(StarknetError::malformed_request)?;
+    match serde_json::from_value::<UserTransaction>(value.clone()) {
+        Ok(transaction) => Ok(transaction.into()),
+        Err(e) => serde_json::from_value::<BroadcastedTransaction>(value).map_err(|_| {
+            StarknetError::new(StarknetErrorCode::SchemaValidationError, format!("Invalid transaction: {e}"))
+        }),
     }
 }
@@ -21,6 +21,17 @@ pub(crate) fn not_found_response() -> Response<Body> {
         .expect("Failed to build NOT_FOUND response with a valid status and body")
 }
 
+pub(crate) fn unauthorized_response() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .body(Body::from("Unauthorized"))
+        .expect("Failed to build UNAUTHORIZED response with a valid status and body")
+}
+
+pub(crate) fn rate_limited_response() -> Response<Body> {
+    create_json_response(StatusCode::TOO_MANY_REQUESTS, &StarknetError::rate_limited())
+}
+
 pub(crate) fn internal_error_response(msg: &str) -> Response<Body> {
     Response::builder()
         .status(StatusCode::INTERNAL_SERVER_ERROR)
@@ -0,0 +1,78 @@
+use std::time::Instant;
+
+use hyper::{body::HttpBody, Body, Response};
+use mc_metrics::{CounterVec, HistogramOpts, HistogramVec, MetricsRegistry, Opts, PrometheusError, U64};
+
+/// Histogram time buckets in microseconds.
+const TIME_BUCKETS: [f64; 11] =
+    [5.0, 25.0, 100.0, 500.0, 1_000.0, 2_500.0, 10_000.0, 25_000.0, 100_000.0, 1_000_000.0, 10_000_000.0];
+/// Histogram size buckets in bytes.
+const SIZE_BUCKETS: [f64; 8] =
+    [100.0, 1_000.0, 10_000.0, 100_000.0, 1_000_000.0, 10_000_000.0, 50_000_000.0, 100_000_000.0];
+
+/// Endpoints served by the gateway server. Requests to other paths are labeled as `unknown`, so that clients cannot
+/// create new time series.
+const ENDPOINTS: &[&str] = &[
+    "/health",
+    "/feeder_gateway/get_block",
+    "/feeder_gateway/get_state_update",
+    "/feeder_gateway/get_class_by_hash",
+    "/feeder_gateway/get_compiled_class_by_class_hash",
+    "/feeder_gateway/get_signature",
+    "/feeder_gateway/get_public_key",
+    "/feeder_gateway/get_block_traces",
+    "/gateway/add_transaction",
+    "/feeder/add_transaction",
+];
+
+/// Metrics of the gateway server, labeled by endpoint.
+#[derive(Debug, Clone)]
+pub struct GatewayMetrics {
+    /// Number of processed requests.
+    requests: CounterVec<U64>,
+    /// Histogram over request processing times.
+    requests_time: HistogramVec,
+    /// Histogram over response body sizes.
+    response_size: HistogramVec,
+}
+
+impl GatewayMetrics {
+    pub fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            requests: registry.register(CounterVec::new(
+                Opts::new("gateway_requests", "Number of processed gateway requests"),
+                &["endpoint", "status", "is_error"],
+            )?)?,
+            requests_time: registry.register(HistogramVec::new(
+                HistogramOpts::new("gateway_requests_time", "Total time [μs] of processed gateway requests")
+                    .buckets(TIME_BUCKETS.to_vec()),
+                &["endpoint"],
+            )?)?,
+            response_size: registry.register(HistogramVec::new(
+                HistogramOpts::new("gateway_response_size", "Size [bytes] of the gateway response bodies")
+                    .buckets(SIZE_BUCKETS.to_vec()),
+                &["endpoint"],
+            )?)?,
+        })
+    }
+
+    pub(crate) fn on_response(&self, path: &str, response: &Response<Body>, started_at: Instant) {
+        let endpoint = ENDPOINTS.iter().find(|endpoint| **endpoint == path).copied().unwrap_or("unknown");
+        let micros = started_at.elapsed().as_micros();
+        let status = response.status();
+        log::debug!(target: "gateway_metrics", "{endpoint} request took {micros} μs, status {status}");
+
+        self.requests
+            .with_label_values(&[
+                endpoint,
+                status.as_str(),
+                if status.is_client_error() || status.is_server_error() { "true" } else { "false" },
+            ])
+            .inc();
+        self.requests_time.with_label_values(&[endpoint]).observe(micros as _);
+        // Responses are built from complete bodies, which have an exact size.
+        if let Some(size) = response.body().size_hint().exact() {
+            self.response_size.with_label_values(&[endpoint]).observe(size as _);
+        }
+    }
+}
@@ -1,5 +1,11 @@
 mod error;
+pub mod gossip;
 mod handler;
 mod helpers;
+pub mod metrics;
+pub mod rate_limit;
 mod router;
 pub mod service;
+pub mod tls;
+pub mod trace_cache;
+pub mod validation;
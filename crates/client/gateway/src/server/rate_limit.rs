@@ -0,0 +1,132 @@
+//! Rate limiting of the gateway server.
+//!
+//! Like on the public Starknet gateway, requests are limited per client IP and globally, and operators can bypass
+//! the limits by setting a secret key in the `X-Throttling-Bypass` header.
+
+use std::{net::IpAddr, num::NonZeroU32};
+
+use governor::{DefaultDirectRateLimiter, DefaultKeyedRateLimiter, Quota};
+use hyper::{Body, Request};
+use mp_utils::http::get_proxy_ip;
+
+pub const BYPASS_HEADER: &str = "x-throttling-bypass";
+
+/// Past this number of tracked clients, the ones whose quota is replenished are forgotten.
+const MAX_TRACKED_IPS: usize = 10_000;
+
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+    /// Requests per minute allowed for each client IP.
+    pub per_ip: Option<NonZeroU32>,
+    /// Requests per minute allowed across all clients.
+    pub global: Option<NonZeroU32>,
+    /// Requests with this value in the `X-Throttling-Bypass` header are not rate limited.
+    pub bypass_key: Option<String>,
+    /// Identify the clients using the IP found in the reverse proxy headers.
+    pub trust_proxy_headers: bool,
+}
+
+pub(crate) struct RateLimiter {
+    per_ip: Option<DefaultKeyedRateLimiter<IpAddr>>,
+    global: Option<DefaultDirectRateLimiter>,
+    bypass_key: Option<String>,
+    trust_proxy_headers: bool,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            per_ip: config.per_ip.map(|n| governor::RateLimiter::keyed(Quota::per_minute(n))),
+            global: config.global.map(|n| governor::RateLimiter::direct(Quota::per_minute(n))),
+            bypass_key: config.bypass_key,
+            trust_proxy_headers: config.trust_proxy_headers,
+        }
+    }
+
+    /// Returns whether the request is within the limits, and consumes its quota.
+    pub fn check(&self, req: &Request<Body>, remote_ip: IpAddr) -> bool {
+        if let Some(bypass_key) = &self.bypass_key {
+            if req.headers().get(BYPASS_HEADER).is_some_and(|value| value.as_bytes() == bypass_key.as_bytes()) {
+                return true;
+            }
+        }
+
+        if let Some(per_ip) = &self.per_ip {
+            let ip = if self.trust_proxy_headers { get_proxy_ip(req).unwrap_or(remote_ip) } else { remote_ip };
+            if per_ip.check_key(&ip).is_err() {
+                log::debug!(target: "gateway", "ip={ip} is rate limited");
+                return false;
+            }
+            if per_ip.len() > MAX_TRACKED_IPS {
+                per_ip.retain_recent();
+            }
+        }
+
+        // Checked last, so that requests rejected by the per-IP limit do not use up the global quota.
+        self.global.as_ref().map_or(true, |global| global.check().is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    const IP_1: IpAddr = IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1));
+    const IP_2: IpAddr = IpAddr::V4(Ipv4Addr::new(2, 2, 2, 2));
+
+    fn request() -> Request<Body> {
+        Request::new(Body::empty())
+    }
+
+    #[test]
+    fn per_ip_limit() {
+        let limiter = RateLimiter::new(RateLimitConfig { per_ip: NonZeroU32::new(2), ..Default::default() });
+
+        assert!(limiter.check(&request(), IP_1));
+        assert!(limiter.check(&request(), IP_1));
+        assert!(!limiter.check(&request(), IP_1));
+        assert!(limiter.check(&request(), IP_2));
+    }
+
+    #[test]
+    fn global_limit() {
+        let limiter = RateLimiter::new(RateLimitConfig { global: NonZeroU32::new(2), ..Default::default() });
+
+        assert!(limiter.check(&request(), IP_1));
+        assert!(limiter.check(&request(), IP_2));
+        assert!(!limiter.check(&request(), IP_1));
+        assert!(!limiter.check(&request(), IP_2));
+    }
+
+    #[test]
+    fn bypass_header() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_ip: NonZeroU32::new(1),
+            bypass_key: Some("secret".into()),
+            ..Default::default()
+        });
+        let bypass = || Request::builder().header(BYPASS_HEADER, "secret").body(Body::empty()).unwrap();
+        let wrong_key = || Request::builder().header(BYPASS_HEADER, "wrong").body(Body::empty()).unwrap();
+
+        assert!(limiter.check(&request(), IP_1));
+        assert!(!limiter.check(&request(), IP_1));
+        assert!(!limiter.check(&wrong_key(), IP_1));
+        assert!(limiter.check(&bypass(), IP_1));
+        assert!(limiter.check(&bypass(), IP_1));
+    }
+
+    #[test]
+    fn proxy_headers() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            per_ip: NonZeroU32::new(1),
+            trust_proxy_headers: true,
+            ..Default::default()
+        });
+        let forwarded = |ip: &str| Request::builder().header("x-forwarded-for", ip).body(Body::empty()).unwrap();
+
+        assert!(limiter.check(&forwarded("3.3.3.3"), IP_1));
+        assert!(!limiter.check(&forwarded("3.3.3.3"), IP_1));
+        assert!(limiter.check(&forwarded("4.4.4.4"), IP_1));
+    }
+}
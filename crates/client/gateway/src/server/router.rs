@@ -1,11 +1,17 @@
-use std::{convert::Infallible, sync::Arc};
+use std::{convert::Infallible, net::IpAddr, sync::Arc};
 
 use hyper::{Body, Method, Request, Response};
 use mc_db::MadaraBackend;
 use mp_rpc::AddTransactionProvider;
 
-use super::handler::{handle_add_transaction, handle_get_block, handle_get_class_by_hash, handle_get_state_update};
-use super::helpers::{not_found_response, service_unavailable_response};
+use super::gossip::{GossipReceiver, GOSSIP_PATH};
+use super::handler::{
+    handle_add_transaction, handle_get_block, handle_get_block_traces, handle_get_class_by_hash,
+    handle_get_compiled_class_by_class_hash, handle_get_public_key, handle_get_signature, handle_get_state_update,
+};
+use super::helpers::{not_found_response, rate_limited_response, service_unavailable_response, unauthorized_response};
+use super::rate_limit::RateLimiter;
+use super::trace_cache::TraceCache;
 
 // Main router to redirect to the appropriate sub-router
 pub(crate) async fn main_router(
@@ -14,19 +20,42 @@ pub(crate) async fn main_router(
     add_transaction_provider: Arc<dyn AddTransactionProvider>,
     feeder_gateway_enable: bool,
     gateway_enable: bool,
+    rate_limiter: Arc<RateLimiter>,
+    trace_cache: Arc<TraceCache>,
+    gossip: Option<Arc<GossipReceiver>>,
+    remote_ip: IpAddr,
 ) -> Result<Response<Body>, Infallible> {
+    // The peers are authenticated, and are not rate limited.
+    if req.uri().path() == GOSSIP_PATH {
+        return gossip_router(req, gossip).await;
+    }
+    if req.uri().path() != "/health" && !rate_limiter.check(&req, remote_ip) {
+        return Ok(rate_limited_response());
+    }
+
     match (req.uri().path(), feeder_gateway_enable, gateway_enable) {
         ("/health", _, _) => Ok(Response::new(Body::from("OK"))),
-        (path, true, _) if path.starts_with("/feeder_gateway/") => feeder_gateway_router(req, backend).await,
-        (path, _, true) if path.starts_with("/feeder/") => gateway_router(req, add_transaction_provider).await,
+        (path, true, _) if path.starts_with("/feeder_gateway/") => {
+            feeder_gateway_router(req, backend, add_transaction_provider, trace_cache).await
+        }
+        (path, _, true) if path.starts_with("/gateway/") || path.starts_with("/feeder/") => {
+            gateway_router(req, add_transaction_provider).await
+        }
         (path, false, _) if path.starts_with("/feeder_gateway/") => Ok(service_unavailable_response("Feeder Gateway")),
-        (path, _, false) if path.starts_with("/feeder/") => Ok(service_unavailable_response("Feeder")),
+        (path, _, false) if path.starts_with("/gateway/") || path.starts_with("/feeder/") => {
+            Ok(service_unavailable_response("Gateway"))
+        }
         _ => Ok(not_found_response()),
     }
 }
 
 // Router for requests related to feeder_gateway
-async fn feeder_gateway_router(req: Request<Body>, backend: Arc<MadaraBackend>) -> Result<Response<Body>, Infallible> {
+async fn feeder_gateway_router(
+    req: Request<Body>,
+    backend: Arc<MadaraBackend>,
+    add_transaction_provider: Arc<dyn AddTransactionProvider>,
+    trace_cache: Arc<TraceCache>,
+) -> Result<Response<Body>, Infallible> {
     match (req.method(), req.uri().path()) {
         (&Method::GET, "/feeder_gateway/get_block") => {
             Ok(handle_get_block(req, backend).await.unwrap_or_else(Into::into))
@@ -37,17 +66,45 @@ async fn feeder_gateway_router(req: Request<Body>, backend: Arc<MadaraBackend>)
         (&Method::GET, "/feeder_gateway/get_class_by_hash") => {
             Ok(handle_get_class_by_hash(req, backend).await.unwrap_or_else(Into::into))
         }
+        (&Method::GET, "/feeder_gateway/get_compiled_class_by_class_hash") => {
+            Ok(handle_get_compiled_class_by_class_hash(req, backend).await.unwrap_or_else(Into::into))
+        }
+        (&Method::GET, "/feeder_gateway/get_signature") => {
+            Ok(handle_get_signature(req, backend).await.unwrap_or_else(Into::into))
+        }
+        (&Method::GET, "/feeder_gateway/get_public_key") => {
+            Ok(handle_get_public_key(req, backend).await.unwrap_or_else(Into::into))
+        }
+        (&Method::GET, "/feeder_gateway/get_block_traces") => {
+            Ok(handle_get_block_traces(req, backend, add_transaction_provider, trace_cache)
+                .await
+                .unwrap_or_else(Into::into))
+        }
         _ => Ok(not_found_response()),
     }
 }
 
-// Router for requests related to feeder
+// Router for the transactions pushed by the other replicas of the sequencer
+pub(crate) async fn gossip_router(
+    req: Request<Body>,
+    gossip: Option<Arc<GossipReceiver>>,
+) -> Result<Response<Body>, Infallible> {
+    let Some(gossip) = gossip.filter(|_| req.method() == Method::POST) else {
+        return Ok(not_found_response());
+    };
+    if !gossip.is_authorized(&req) {
+        return Ok(unauthorized_response());
+    }
+    Ok(handle_add_transaction(req, Arc::clone(&gossip.add_transaction_provider)).await.unwrap_or_else(Into::into))
+}
+
+// Router for requests related to the gateway
 async fn gateway_router(
     req: Request<Body>,
     add_transaction_provider: Arc<dyn AddTransactionProvider>,
 ) -> Result<Response<Body>, Infallible> {
     match (req.method(), req.uri().path()) {
-        (&Method::POST, "/feeder/add_transaction") => {
+        (&Method::POST, "/gateway/add_transaction" | "/feeder/add_transaction") => {
             Ok(handle_add_transaction(req, add_transaction_provider).await.unwrap_or_else(Into::into))
         }
         _ => Ok(not_found_response()),
@@ -1,66 +1,136 @@
 use std::{
     convert::Infallible,
-    net::{Ipv4Addr, SocketAddr},
+    net::{IpAddr, SocketAddr},
     sync::Arc,
+    time::Instant,
 };
 
 use anyhow::Context;
 use hyper::{
+    server::{accept, conn::AddrStream},
     service::{make_service_fn, service_fn},
-    Server,
+    Body, Request, Response, Server,
 };
 use mc_db::MadaraBackend;
 use mp_rpc::AddTransactionProvider;
 use mp_utils::graceful_shutdown;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::server::TlsStream;
+use tokio_stream::wrappers::ReceiverStream;
 
-use super::router::main_router;
+use super::{
+    gossip::GossipReceiver,
+    metrics::GatewayMetrics,
+    rate_limit::{RateLimitConfig, RateLimiter},
+    router::main_router,
+    tls::{self, TlsConfig},
+    trace_cache::TraceCache,
+};
 
-pub async fn start_server(
+#[derive(Debug, Clone)]
+pub struct GatewayServerConfig {
+    pub feeder_gateway_enable: bool,
+    pub gateway_enable: bool,
+    pub listen_addr: SocketAddr,
+    /// Serve over HTTPS. `None` to serve over plain HTTP.
+    pub tls: Option<TlsConfig>,
+    pub rate_limit: RateLimitConfig,
+    /// Number of blocks whose `get_block_traces` responses are kept in memory.
+    pub trace_cache_size: usize,
+}
+
+/// Shared by all the connections.
+#[derive(Clone)]
+struct GatewayState {
     db_backend: Arc<MadaraBackend>,
     add_transaction_provider: Arc<dyn AddTransactionProvider>,
     feeder_gateway_enable: bool,
     gateway_enable: bool,
-    gateway_external: bool,
-    gateway_port: u16,
+    rate_limiter: Arc<RateLimiter>,
+    trace_cache: Arc<TraceCache>,
+    gossip: Option<Arc<GossipReceiver>>,
+    metrics: GatewayMetrics,
+}
+
+impl GatewayState {
+    async fn handle(self, req: Request<Body>, remote_ip: IpAddr) -> Result<Response<Body>, Infallible> {
+        let started_at = Instant::now();
+        let path = req.uri().path().to_owned();
+        let response = main_router(
+            req,
+            self.db_backend,
+            self.add_transaction_provider,
+            self.feeder_gateway_enable,
+            self.gateway_enable,
+            self.rate_limiter,
+            self.trace_cache,
+            self.gossip,
+            remote_ip,
+        )
+        .await?;
+        self.metrics.on_response(&path, &response, started_at);
+        Ok(response)
+    }
+}
+
+pub async fn start_server(
+    db_backend: Arc<MadaraBackend>,
+    add_transaction_provider: Arc<dyn AddTransactionProvider>,
+    gossip: Option<GossipReceiver>,
+    config: GatewayServerConfig,
+    metrics: GatewayMetrics,
 ) -> anyhow::Result<()> {
-    if !feeder_gateway_enable && !gateway_enable {
+    if !config.feeder_gateway_enable && !config.gateway_enable {
         return Ok(());
     }
 
-    let listen_addr = if gateway_external {
-        Ipv4Addr::UNSPECIFIED // listen on 0.0.0.0
-    } else {
-        Ipv4Addr::LOCALHOST
+    let addr = config.listen_addr;
+    let socket = TcpListener::bind(addr).await.with_context(|| format!("Opening socket server at {addr}"))?;
+
+    let state = GatewayState {
+        db_backend,
+        add_transaction_provider,
+        feeder_gateway_enable: config.feeder_gateway_enable,
+        gateway_enable: config.gateway_enable,
+        rate_limiter: Arc::new(RateLimiter::new(config.rate_limit)),
+        trace_cache: Arc::new(TraceCache::new(config.trace_cache_size)),
+        gossip: gossip.map(Arc::new),
+        metrics,
     };
-    let addr = SocketAddr::new(listen_addr.into(), gateway_port);
 
-    let socket = TcpListener::bind(addr).await.with_context(|| format!("Opening socket server at {addr}"))?;
+    match config.tls {
+        None => {
+            let listener = hyper::server::conn::AddrIncoming::from_listener(socket)
+                .with_context(|| format!("Opening socket server at {addr}"))?;
 
-    let listener = hyper::server::conn::AddrIncoming::from_listener(socket)
-        .with_context(|| format!("Opening socket server at {addr}"))?;
-
-    let make_service = make_service_fn(move |_| {
-        let db_backend = Arc::clone(&db_backend);
-        let add_transaction_provider = Arc::clone(&add_transaction_provider);
-        async move {
-            Ok::<_, Infallible>(service_fn(move |req| {
-                main_router(
-                    req,
-                    Arc::clone(&db_backend),
-                    Arc::clone(&add_transaction_provider),
-                    feeder_gateway_enable,
-                    gateway_enable,
-                )
-            }))
+            let make_service = make_service_fn(move |conn: &AddrStream| {
+                let state = state.clone();
+                let remote_ip = conn.remote_addr().ip();
+                async move { Ok::<_, Infallible>(service_fn(move |req| state.clone().handle(req, remote_ip))) }
+            });
+
+            log::info!("🌐 Gateway endpoint started at {}", listener.local_addr());
+
+            let server = Server::builder(listener).serve(make_service).with_graceful_shutdown(graceful_shutdown());
+            server.await.context("gateway server")?;
         }
-    });
+        Some(tls_config) => {
+            let acceptor = tls_config.acceptor().context("Loading the gateway TLS certificate")?;
+            let local_addr = socket.local_addr().with_context(|| format!("Opening socket server at {addr}"))?;
+            let incoming = accept::from_stream(ReceiverStream::new(tls::accept_tls(socket, acceptor)));
 
-    log::info!("🌐 Gateway endpoint started at {}", listener.local_addr());
+            let make_service = make_service_fn(move |conn: &TlsStream<TcpStream>| {
+                let state = state.clone();
+                let remote_ip = tls::remote_ip(conn);
+                async move { Ok::<_, Infallible>(service_fn(move |req| state.clone().handle(req, remote_ip))) }
+            });
 
-    let server = Server::builder(listener).serve(make_service).with_graceful_shutdown(graceful_shutdown());
+            log::info!("🌐 Gateway endpoint started at https://{local_addr}");
 
-    server.await.context("gateway server")?;
+            let server = Server::builder(incoming).serve(make_service).with_graceful_shutdown(graceful_shutdown());
+            server.await.context("gateway server")?;
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,213 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_rustls::{
+    rustls::{
+        self,
+        crypto::CryptoProvider,
+        pki_types::{CertificateDer, PrivateKeyDer},
+        ServerConfig,
+    },
+    server::TlsStream,
+    TlsAcceptor,
+};
+
+/// Clients that do not complete the TLS handshake in this time are disconnected.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections that completed the handshake and are waiting to be served.
+const PENDING_CONNECTIONS: usize = 128;
+
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// PEM file containing the certificate chain, starting with the end-entity certificate.
+    pub cert_path: PathBuf,
+    /// PEM file containing the private key of the certificate.
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    pub(crate) fn acceptor(&self) -> anyhow::Result<TlsAcceptor> {
+        let certs = rustls_pemfile::certs(&mut BufReader::new(
+            File::open(&self.cert_path).with_context(|| format!("Opening {}", self.cert_path.display()))?,
+        ))
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Parsing the certificates of {}", self.cert_path.display()))?;
+        anyhow::ensure!(!certs.is_empty(), "No certificate found in {}", self.cert_path.display());
+
+        let key = rustls_pemfile::private_key(&mut BufReader::new(
+            File::open(&self.key_path).with_context(|| format!("Opening {}", self.key_path.display()))?,
+        ))
+        .with_context(|| format!("Parsing the private key of {}", self.key_path.display()))?
+        .with_context(|| format!("No private key found in {}", self.key_path.display()))?;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        ensure_key_matches(&provider, &certs[0], &key)?;
+
+        let mut config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .context("Configuring the TLS protocol versions")?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("Invalid TLS certificate or private key")?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+/// rustls only uses the private key when a client connects, so a key that does not belong to the certificate would
+/// make every handshake fail. It is detected at startup by verifying a signature made with the key against the
+/// certificate.
+fn ensure_key_matches(provider: &CryptoProvider, cert: &CertificateDer, key: &PrivateKeyDer) -> anyhow::Result<()> {
+    const MESSAGE: &[u8] = b"madara gateway";
+
+    let algorithms = provider.signature_verification_algorithms.mapping;
+    let schemes: Vec<_> = algorithms.iter().map(|(scheme, _)| *scheme).collect();
+    let signer = provider
+        .key_provider
+        .load_private_key(key.clone_key())
+        .context("Invalid TLS private key")?
+        .choose_scheme(&schemes)
+        .context("Unsupported TLS private key")?;
+    let signature = signer.sign(MESSAGE).context("Signing with the TLS private key")?;
+
+    let cert = webpki::EndEntityCert::try_from(cert).context("Invalid TLS certificate")?;
+    let matches = algorithms
+        .iter()
+        .filter(|(scheme, _)| *scheme == signer.scheme())
+        .flat_map(|(_, algorithms)| algorithms.iter())
+        .any(|algorithm| cert.verify_signature(*algorithm, MESSAGE, &signature).is_ok());
+    anyhow::ensure!(matches, "The TLS private key does not belong to the certificate");
+    Ok(())
+}
+
+/// Accepts the TCP connections and performs the TLS handshakes in the background, so that a slow client does not
+/// delay the others. The established connections are returned through the channel.
+pub(crate) fn accept_tls(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+) -> mpsc::Receiver<io::Result<TlsStream<TcpStream>>> {
+    let (sender, receiver) = mpsc::channel(PENDING_CONNECTIONS);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, remote_addr) = tokio::select! {
+                res = listener.accept() => match res {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        // Usually running out of file descriptors, which lasts for a little while.
+                        log::debug!(target: "gateway", "Accepting connection: {err:#}");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        continue;
+                    }
+                },
+                // The server was stopped.
+                _ = sender.closed() => break,
+            };
+
+            let acceptor = acceptor.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = sender.send(Ok(stream)).await;
+                    }
+                    Ok(Err(err)) => log::debug!(target: "gateway", "TLS handshake with {remote_addr} failed: {err:#}"),
+                    Err(_) => log::debug!(target: "gateway", "TLS handshake with {remote_addr} timed out"),
+                }
+            });
+        }
+    });
+
+    receiver
+}
+
+pub(crate) fn remote_ip(stream: &TlsStream<TcpStream>) -> IpAddr {
+    stream.get_ref().0.peer_addr().map(|addr| addr.ip()).unwrap_or(Ipv4Addr::UNSPECIFIED.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::CertifiedKey;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::{
+        rustls::{pki_types::ServerName, ClientConfig, RootCertStore},
+        TlsConnector,
+    };
+
+    fn write_tls_config(dir: &tempfile::TempDir, cert: &CertifiedKey, key: &CertifiedKey) -> TlsConfig {
+        let config = TlsConfig { cert_path: dir.path().join("cert.pem"), key_path: dir.path().join("key.pem") };
+        std::fs::write(&config.cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&config.key_path, key.key_pair.serialize_pem()).unwrap();
+        config
+    }
+
+    #[tokio::test]
+    async fn serves_over_tls() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let acceptor = write_tls_config(&dir, &cert, &cert).acceptor().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut connections = accept_tls(listener, acceptor);
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert.cert.der().clone()).unwrap();
+        let mut client_config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        let client = tokio::spawn(async move {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let mut stream = TlsConnector::from(Arc::new(client_config))
+                .connect(ServerName::try_from("localhost").unwrap(), stream)
+                .await
+                .unwrap();
+            stream.write_all(b"ping").await.unwrap();
+            stream.flush().await.unwrap();
+            stream
+        });
+
+        let mut stream = connections.recv().await.unwrap().unwrap();
+        assert_eq!(stream.get_ref().1.alpn_protocol(), Some(b"http/1.1".as_slice()));
+        assert!(remote_ip(&stream).is_loopback());
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        client.await.unwrap();
+    }
+
+    #[test]
+    fn mismatched_key_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let other = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let err = write_tls_config(&dir, &cert, &other).acceptor().unwrap_err();
+        assert_eq!(err.to_string(), "The TLS private key does not belong to the certificate");
+    }
+
+    #[test]
+    fn missing_certificate_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let config = write_tls_config(&dir, &cert, &cert);
+        std::fs::write(&config.cert_path, cert.key_pair.serialize_pem()).unwrap();
+        let err = config.acceptor().unwrap_err();
+        assert!(err.to_string().starts_with("No certificate found in"), "{err:#}");
+    }
+}
@@ -0,0 +1,70 @@
+//! Cache of the responses of `get_block_traces`. Tracing a block re-executes all of its transactions, and the tools
+//! consuming the traces, such as provers and debuggers, often request the same recent blocks.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use mp_gateway::trace::BlockTraces;
+use starknet_types_core::felt::Felt;
+
+/// Number of blocks whose traces are cached by default.
+pub const DEFAULT_TRACE_CACHE_SIZE: usize = 16;
+
+/// Traces of the last traced closed blocks. They are keyed by block hash, so that the traces of a reverted block are
+/// never served for the block replacing it.
+pub struct TraceCache {
+    capacity: usize,
+    traces: Mutex<VecDeque<(Felt, Arc<BlockTraces>)>>,
+}
+
+impl TraceCache {
+    /// A `capacity` of 0 disables the cache.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, traces: Mutex::new(VecDeque::with_capacity(capacity)) }
+    }
+
+    pub fn get(&self, block_hash: &Felt) -> Option<Arc<BlockTraces>> {
+        let traces = self.traces.lock().expect("Poisoned lock");
+        traces.iter().find(|(hash, _)| hash == block_hash).map(|(_, traces)| Arc::clone(traces))
+    }
+
+    /// Evicts the oldest traces when full.
+    pub fn insert(&self, block_hash: Felt, block_traces: Arc<BlockTraces>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut traces = self.traces.lock().expect("Poisoned lock");
+        if traces.iter().any(|(hash, _)| hash == &block_hash) {
+            return;
+        }
+        if traces.len() == self.capacity {
+            traces.pop_front();
+        }
+        traces.push_back((block_hash, block_traces));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oldest_traces_are_evicted() {
+        let cache = TraceCache::new(2);
+        for block_hash in 1u64..=3 {
+            cache.insert(Felt::from(block_hash), Arc::default());
+        }
+
+        assert!(cache.get(&Felt::from(1u64)).is_none());
+        assert!(cache.get(&Felt::from(2u64)).is_some());
+        assert!(cache.get(&Felt::from(3u64)).is_some());
+    }
+
+    #[test]
+    fn disabled_cache() {
+        let cache = TraceCache::new(0);
+        cache.insert(Felt::ONE, Arc::default());
+
+        assert!(cache.get(&Felt::ONE).is_none());
+    }
+}
@@ -0,0 +1,214 @@
+//! Stateless validation of the transactions submitted to the gateway, with the limits and error codes of the Starknet
+//! gateway. The stateful validation (nonce, balance, `__validate__` entrypoint) is done when the transaction is added
+//! to the mempool.
+
+use starknet_core::types::{
+    BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction,
+    BroadcastedTransaction, FlattenedSierraClass, ResourceBoundsMapping,
+};
+use starknet_types_core::felt::Felt;
+
+use crate::error::{StarknetError, StarknetErrorCode};
+
+pub const MAX_CALLDATA_LENGTH: usize = 4_000;
+pub const MAX_SIGNATURE_LENGTH: usize = 4_000;
+/// Maximum length of the sierra program of a declared class, in felts.
+pub const MAX_BYTECODE_SIZE: usize = 81_920;
+/// Maximum size of a declared class, in bytes of serialized JSON.
+pub const MAX_CONTRACT_CLASS_OBJECT_SIZE: usize = 4_089_446;
+pub const SUPPORTED_CONTRACT_CLASS_VERSION: &str = "0.1.0";
+
+pub(crate) fn validate_transaction(tx: &BroadcastedTransaction) -> Result<(), StarknetError> {
+    match tx {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(tx)) => {
+            validate_not_query(tx.is_query)?;
+            validate_signature(&tx.signature)?;
+            validate_calldata(&tx.calldata)?;
+            validate_max_fee(&tx.max_fee)
+        }
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(tx)) => {
+            validate_not_query(tx.is_query)?;
+            validate_signature(&tx.signature)?;
+            validate_calldata(&tx.calldata)?;
+            validate_resource_bounds(&tx.resource_bounds)
+        }
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V1(tx)) => {
+            validate_not_query(tx.is_query)?;
+            validate_signature(&tx.signature)?;
+            validate_max_fee(&tx.max_fee)?;
+            validate_class_object_size(&*tx.contract_class)
+        }
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => {
+            validate_not_query(tx.is_query)?;
+            validate_signature(&tx.signature)?;
+            validate_max_fee(&tx.max_fee)?;
+            validate_sierra_class(&tx.contract_class)
+        }
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => {
+            validate_not_query(tx.is_query)?;
+            validate_signature(&tx.signature)?;
+            validate_resource_bounds(&tx.resource_bounds)?;
+            validate_sierra_class(&tx.contract_class)
+        }
+        BroadcastedTransaction::DeployAccount(BroadcastedDeployAccountTransaction::V1(tx)) => {
+            validate_not_query(tx.is_query)?;
+            validate_signature(&tx.signature)?;
+            validate_calldata(&tx.constructor_calldata)?;
+            validate_max_fee(&tx.max_fee)
+        }
+        BroadcastedTransaction::DeployAccount(BroadcastedDeployAccountTransaction::V3(tx)) => {
+            validate_not_query(tx.is_query)?;
+            validate_signature(&tx.signature)?;
+            validate_calldata(&tx.constructor_calldata)?;
+            validate_resource_bounds(&tx.resource_bounds)
+        }
+    }
+}
+
+fn validate_not_query(is_query: bool) -> Result<(), StarknetError> {
+    if is_query {
+        return Err(StarknetError::new(
+            StarknetErrorCode::InvalidTransactionVersion,
+            "Query-only transactions cannot be submitted".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_signature(signature: &[Felt]) -> Result<(), StarknetError> {
+    if signature.len() > MAX_SIGNATURE_LENGTH {
+        return Err(StarknetError::new(
+            StarknetErrorCode::SchemaValidationError,
+            format!("Signature length {} exceeds the maximum of {MAX_SIGNATURE_LENGTH}", signature.len()),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_calldata(calldata: &[Felt]) -> Result<(), StarknetError> {
+    if calldata.len() > MAX_CALLDATA_LENGTH {
+        return Err(StarknetError::new(
+            StarknetErrorCode::SchemaValidationError,
+            format!("Calldata length {} exceeds the maximum of {MAX_CALLDATA_LENGTH}", calldata.len()),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_max_fee(max_fee: &Felt) -> Result<(), StarknetError> {
+    if *max_fee == Felt::ZERO {
+        return Err(StarknetError::new(
+            StarknetErrorCode::InsufficientMaxFee,
+            "max_fee must be bigger than 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_resource_bounds(resource_bounds: &ResourceBoundsMapping) -> Result<(), StarknetError> {
+    if resource_bounds.l1_gas.max_amount == 0 || resource_bounds.l1_gas.max_price_per_unit == 0 {
+        return Err(StarknetError::new(
+            StarknetErrorCode::InsufficientMaxFee,
+            "The l1_gas resource bounds must be bigger than 0".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_sierra_class(class: &FlattenedSierraClass) -> Result<(), StarknetError> {
+    if class.contract_class_version != SUPPORTED_CONTRACT_CLASS_VERSION {
+        return Err(StarknetError::new(
+            StarknetErrorCode::InvalidContractClassVersion,
+            format!(
+                "Contract class version {} is not supported, expected {SUPPORTED_CONTRACT_CLASS_VERSION}",
+                class.contract_class_version
+            ),
+        ));
+    }
+    if class.sierra_program.len() > MAX_BYTECODE_SIZE {
+        return Err(StarknetError::new(
+            StarknetErrorCode::ContractBytecodeSizeTooLarge,
+            format!("Sierra program length {} exceeds the maximum of {MAX_BYTECODE_SIZE}", class.sierra_program.len()),
+        ));
+    }
+    validate_class_object_size(class)
+}
+
+fn validate_class_object_size(class: &impl serde::Serialize) -> Result<(), StarknetError> {
+    let size = serde_json::to_vec(class)
+        .map_err(|e| StarknetError::new(StarknetErrorCode::InvalidContractClass, e.to_string()))?
+        .len();
+    if size > MAX_CONTRACT_CLASS_OBJECT_SIZE {
+        return Err(StarknetError::new(
+            StarknetErrorCode::ContractClassObjectSizeTooLarge,
+            format!("Contract class size {size} exceeds the maximum of {MAX_CONTRACT_CLASS_OBJECT_SIZE} bytes"),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starknet_core::types::{BroadcastedInvokeTransactionV1, BroadcastedInvokeTransactionV3, ResourceBounds};
+
+    fn invoke_v1() -> BroadcastedInvokeTransactionV1 {
+        BroadcastedInvokeTransactionV1 {
+            sender_address: Felt::ONE,
+            calldata: vec![Felt::ONE; 10],
+            max_fee: Felt::from(1_000),
+            signature: vec![Felt::TWO; 2],
+            nonce: Felt::ZERO,
+            is_query: false,
+        }
+    }
+
+    fn validate_invoke_v1(tx: BroadcastedInvokeTransactionV1) -> Result<(), StarknetErrorCode> {
+        validate_transaction(&BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(tx))).map_err(|e| e.code)
+    }
+
+    #[test]
+    fn valid_invoke() {
+        assert_eq!(validate_invoke_v1(invoke_v1()), Ok(()));
+    }
+
+    #[test]
+    fn limits_are_enforced() {
+        let tx = BroadcastedInvokeTransactionV1 { calldata: vec![Felt::ONE; MAX_CALLDATA_LENGTH + 1], ..invoke_v1() };
+        assert_eq!(validate_invoke_v1(tx), Err(StarknetErrorCode::SchemaValidationError));
+
+        let tx = BroadcastedInvokeTransactionV1 { signature: vec![Felt::ONE; MAX_SIGNATURE_LENGTH + 1], ..invoke_v1() };
+        assert_eq!(validate_invoke_v1(tx), Err(StarknetErrorCode::SchemaValidationError));
+    }
+
+    #[test]
+    fn fees_must_be_positive() {
+        let tx = BroadcastedInvokeTransactionV1 { max_fee: Felt::ZERO, ..invoke_v1() };
+        assert_eq!(validate_invoke_v1(tx), Err(StarknetErrorCode::InsufficientMaxFee));
+
+        let tx = BroadcastedInvokeTransactionV3 {
+            sender_address: Felt::ONE,
+            calldata: vec![],
+            signature: vec![],
+            nonce: Felt::ZERO,
+            resource_bounds: ResourceBoundsMapping {
+                l1_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 1 },
+                l2_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+            },
+            tip: 0,
+            paymaster_data: vec![],
+            account_deployment_data: vec![],
+            nonce_data_availability_mode: starknet_core::types::DataAvailabilityMode::L1,
+            fee_data_availability_mode: starknet_core::types::DataAvailabilityMode::L1,
+            is_query: false,
+        };
+        let res = validate_transaction(&BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(tx)));
+        assert_eq!(res.map_err(|e| e.code), Err(StarknetErrorCode::InsufficientMaxFee));
+    }
+
+    #[test]
+    fn query_transactions_are_rejected() {
+        let tx = BroadcastedInvokeTransactionV1 { is_query: true, ..invoke_v1() };
+        assert_eq!(validate_invoke_v1(tx), Err(StarknetErrorCode::InvalidTransactionVersion));
+    }
+}
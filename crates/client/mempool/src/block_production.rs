@@ -1,23 +1,35 @@
 // TODO: Move this into its own crate.
 
+use crate::builder::{BuilderBlock, BuilderBlockOutcome};
+use crate::bundle::{BundleRejection, MempoolBundle};
+use crate::clock::BlockClock;
 use crate::close_block::close_block;
+use crate::devnet::{DevnetCommand, MiningMode};
 use crate::header::make_pending_header;
 use crate::{clone_account_tx, L1DataProvider, MempoolProvider, MempoolTransaction};
 use anyhow::Context;
-use blockifier::blockifier::transaction_executor::{TransactionExecutor, VisitedSegmentsMapping};
-use blockifier::bouncer::{Bouncer, BouncerWeights, BuiltinCount};
-use blockifier::state::cached_state::CommitmentStateDiff;
-use blockifier::state::state_api::StateReader;
+use blockifier::abi::abi_utils::{get_fee_token_var_address, get_storage_var_address};
+use blockifier::abi::sierra_types::next_storage_key;
+use blockifier::blockifier::config::TransactionExecutorConfig;
+use blockifier::blockifier::transaction_executor::{
+    TransactionExecutor, TransactionExecutorError, VisitedSegmentsMapping,
+};
+use blockifier::bouncer::{Bouncer, BouncerConfig, BouncerWeights, BuiltinCount};
+use blockifier::state::cached_state::{CommitmentStateDiff, TransactionalState};
+use blockifier::state::state_api::{State, StateReader};
 use blockifier::transaction::errors::TransactionExecutionError;
+use blockifier::transaction::objects::TransactionExecutionInfo;
 use blockifier::transaction::transaction_execution::Transaction;
-use mc_block_import::{BlockImportError, BlockImporter};
+use mc_block_import::{BlockImportError, BlockImporter, BlockValidationContext, UnverifiedFullBlock};
 use mc_db::db_block_id::DbBlockId;
 use mc_db::{MadaraBackend, MadaraStorageError};
+use mc_exec::metrics::{BlockExecutionMetrics, ExecutionSource};
 use mc_exec::{BlockifierStateAdapter, ExecutionContext};
 use mp_block::{BlockId, BlockTag, MadaraPendingBlock};
 use mp_class::ConvertedClass;
-use mp_convert::ToFelt;
+use mp_convert::{felt_to_u128, felt_to_u64, ToFelt};
 use mp_exex::{ExExManagerHandle, ExExNotification};
+use mp_keystore::StarknetSigner;
 use mp_receipt::from_blockifier_execution_info;
 use mp_state_update::{
     ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StateDiff,
@@ -25,13 +37,17 @@ use mp_state_update::{
 };
 use mp_transactions::TransactionWithHash;
 use mp_utils::graceful_shutdown;
+use primitive_types::U256;
 use starknet_api::block::BlockNumber;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::state::StorageKey;
 use starknet_types_core::felt::Felt;
 use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::mem;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 #[derive(Default, Clone)]
 struct ContinueBlockStats {
@@ -57,6 +73,8 @@ pub enum Error {
     Import(#[from] mc_block_import::BlockImportError),
     #[error("Unexpected error: {0:#}")]
     Unexpected(Cow<'static, str>),
+    #[error("Invalid block: {0}")]
+    InvalidBuilderBlock(Cow<'static, str>),
 }
 
 fn csd_to_state_diff(
@@ -166,6 +184,58 @@ fn finalize_execution_state<S: StateReader>(
     Ok((state_update, visited_segments, *tx_executor.bouncer.get_accumulated_weights()))
 }
 
+/// Adds `amount` to the u256 stored at `low_key` and the following key, and returns the new value.
+fn add_to_u256_storage(
+    state: &mut impl State,
+    contract_address: ContractAddress,
+    low_key: StorageKey,
+    amount: U256,
+) -> Result<U256, Error> {
+    fn read_limb(state: &impl State, contract_address: ContractAddress, key: StorageKey) -> Result<U256, Error> {
+        let felt = state.get_storage_at(contract_address, key).map_err(TransactionExecutionError::StateError)?;
+        let limb = felt_to_u128(&felt).map_err(|_| Error::Unexpected(format!("Invalid u256 limb {felt:#x}").into()))?;
+        Ok(U256::from(limb))
+    }
+
+    let high_key = next_storage_key(&low_key).map_err(|err| Error::Unexpected(format!("{err:#}").into()))?;
+    let value = (read_limb(&*state, contract_address, high_key)? << 128
+        | read_limb(&*state, contract_address, low_key)?)
+    .checked_add(amount)
+    .ok_or_else(|| Error::Unexpected("Balance overflow".into()))?;
+
+    state
+        .set_storage_at(contract_address, low_key, Felt::from(value.low_u128()))
+        .map_err(TransactionExecutionError::StateError)?;
+    state
+        .set_storage_at(contract_address, high_key, Felt::from((value >> 128).low_u128()))
+        .map_err(TransactionExecutionError::StateError)?;
+    Ok(value)
+}
+
+/// Upper bound of [`increase_nonce_to`], which takes one step per nonce increment.
+const MAX_NONCE_INCREASE: u64 = 1_000_000;
+
+/// Increments the nonce of `contract_address` until it is `nonce`: the blockifier state can only increment nonces.
+fn increase_nonce_to(state: &mut impl State, contract_address: ContractAddress, nonce: Nonce) -> Result<(), Error> {
+    let current = state.get_nonce_at(contract_address).map_err(TransactionExecutionError::StateError)?;
+    if nonce.to_felt() < current.to_felt() {
+        return Err(Error::Unexpected(
+            format!("Cannot decrease the nonce of {:#x} from {:#x}", contract_address.to_felt(), current.to_felt())
+                .into(),
+        ));
+    }
+    let steps = felt_to_u64(&(nonce.to_felt() - current.to_felt()))
+        .ok()
+        .filter(|steps| *steps <= MAX_NONCE_INCREASE)
+        .ok_or_else(|| {
+        Error::Unexpected(format!("Cannot increase a nonce by more than {MAX_NONCE_INCREASE} at once").into())
+    })?;
+    for _ in 0..steps {
+        state.increment_nonce(contract_address).map_err(TransactionExecutionError::StateError)?;
+    }
+    Ok(())
+}
+
 /// The block production task consumes transactions from the mempool in batches.
 /// This is to allow optimistic concurrency. However, the block may get full during batch execution,
 /// and we need to re-add the transactions back into the mempool.
@@ -181,7 +251,22 @@ pub struct BlockProductionTask<Mempool: MempoolProvider> {
     pub(crate) executor: TransactionExecutor<BlockifierStateAdapter>,
     l1_data_provider: Arc<dyn L1DataProvider>,
     current_pending_tick: usize,
+    /// Time spent executing the transactions of the pending block, over all of its ticks.
+    execution_time: Duration,
     exex_manager: Option<ExExManagerHandle>,
+    /// Signs the hashes of the closed blocks, so that full nodes can authenticate them.
+    signer: Option<Arc<dyn StarknetSigner>>,
+    clock: BlockClock,
+    devnet_commands: Option<mpsc::Receiver<DevnetCommand>>,
+    mining_mode: MiningMode,
+    metrics: Option<BlockExecutionMetrics>,
+    builder_blocks: Option<mpsc::Receiver<BuilderBlock>>,
+    /// Executed as the next block, once the pending block is closed.
+    queued_builder_block: Option<BuilderBlock>,
+    /// The pending block was built by the external block builder: no mempool transaction is added to it.
+    is_builder_block: bool,
+    /// Capacity of the pending block used by the bundles, which are executed with their own bouncer.
+    bundle_weights: BouncerWeights,
 }
 
 impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
@@ -200,7 +285,9 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         let parent_block_hash = backend
             .get_block_hash(&BlockId::Tag(BlockTag::Latest))?
             .unwrap_or(/* genesis block's parent hash */ Felt::ZERO);
+        let block_n = backend.get_latest_block_n()?.map(|n| n + 1).unwrap_or(0);
         let pending_block = MadaraPendingBlock::new_empty(make_pending_header(
+            block_n,
             parent_block_hash,
             backend.chain_config(),
             l1_data_provider.as_ref(),
@@ -222,17 +309,229 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
             mempool,
             executor,
             current_pending_tick: 0,
+            execution_time: Duration::ZERO,
             block: pending_block,
             declared_classes: vec![],
             l1_data_provider,
             exex_manager,
+            signer: None,
+            clock: BlockClock::default(),
+            devnet_commands: None,
+            mining_mode: MiningMode::default(),
+            metrics: None,
+            builder_blocks: None,
+            queued_builder_block: None,
+            is_builder_block: false,
+            bundle_weights: BouncerWeights::empty(),
         })
     }
 
+    /// Take the timestamps of the blocks from this clock instead of the system time.
+    pub fn with_clock(mut self, clock: BlockClock) -> Result<Self, Error> {
+        self.clock = clock;
+        self.restamp_pending_block()?;
+        Ok(self)
+    }
+
+    fn restamp_pending_block(&mut self) -> Result<(), Error> {
+        // The block has no transactions: the only state changes are the ones of the devnet commands, which are kept.
+        let csd: CommitmentStateDiff = self
+            .executor
+            .block_state
+            .as_mut()
+            .expect(BLOCK_STATE_ACCESS_ERR)
+            .to_state_diff()
+            .map_err(TransactionExecutionError::StateError)?
+            .into();
+
+        self.block.info.header.block_timestamp = self.clock.start_block();
+        self.executor =
+            ExecutionContext::new_in_block(Arc::clone(&self.backend), &self.block.info.clone().into())?.tx_executor();
+
+        let state = self.executor.block_state.as_mut().expect(BLOCK_STATE_ACCESS_ERR);
+        for (contract_address, storage) in csd.storage_updates {
+            for (key, value) in storage {
+                state.set_storage_at(contract_address, key, value).map_err(TransactionExecutionError::StateError)?;
+            }
+        }
+        for (contract_address, nonce) in csd.address_to_nonce {
+            increase_nonce_to(state, contract_address, nonce)?;
+        }
+        for (contract_address, class_hash) in csd.address_to_class_hash {
+            state.set_class_hash_at(contract_address, class_hash).map_err(TransactionExecutionError::StateError)?;
+        }
+        Ok(())
+    }
+
+    /// Apply the commands of the devnet RPC methods.
+    pub fn with_devnet_commands(mut self, devnet_commands: mpsc::Receiver<DevnetCommand>) -> Self {
+        self.devnet_commands = Some(devnet_commands);
+        self
+    }
+
+    /// Close the blocks on demand or as soon as transactions are received, instead of every block time.
+    pub fn with_mining_mode(mut self, mining_mode: MiningMode) -> Self {
+        self.mining_mode = mining_mode;
+        self
+    }
+
+    /// Build the blocks submitted through the [`crate::BuilderHandle`] from their transactions, in their order.
+    pub fn with_builder_blocks(mut self, builder_blocks: mpsc::Receiver<BuilderBlock>) -> Self {
+        self.builder_blocks = Some(builder_blocks);
+        self
+    }
+
+    /// Record the resources consumed by each closed block.
+    pub fn with_metrics(mut self, metrics: BlockExecutionMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    async fn on_devnet_command(&mut self, command: DevnetCommand) {
+        match command {
+            DevnetCommand::Mint { token_address, contract_address, amount, reply } => {
+                let _ = reply.send(self.mint(token_address, contract_address, amount));
+            }
+            DevnetCommand::SetStorageAt { contract_address, key, value, reply } => {
+                let _ = reply.send(self.set_storage_at(contract_address, key, value));
+            }
+            DevnetCommand::SetNonce { contract_address, nonce, reply } => {
+                let _ = reply.send(self.set_nonce(contract_address, nonce));
+            }
+            DevnetCommand::SetClassHashAt { contract_address, class_hash, reply } => {
+                let _ = reply.send(self.set_class_hash_at(contract_address, class_hash));
+            }
+            DevnetCommand::Mine { reply } => {
+                let block_n = self.block_n();
+                let _ = reply.send(self.on_block_time().await.map(|()| block_n));
+            }
+            DevnetCommand::ImportBlocks { blocks, reply } => {
+                let _ = reply.send(self.import_blocks(blocks).await);
+            }
+            DevnetCommand::RevertTo { block_n, reply } => {
+                let _ = reply.send(self.revert_to(block_n).await);
+            }
+        }
+    }
+
+    async fn import_blocks(&mut self, blocks: Vec<UnverifiedFullBlock>) -> Result<(), Error> {
+        if !self.block.inner.transactions.is_empty() {
+            return Err(Error::Unexpected("Cannot import blocks: the pending block has transactions".into()));
+        }
+
+        let validation = BlockValidationContext::new(self.backend.chain_config().chain_id.clone())
+            .trust_transaction_hashes(true)
+            .trust_class_hashes(true);
+        for block in blocks {
+            self.importer.add_block(block, validation.clone()).await?;
+        }
+        self.reset_pending_block()?;
+
+        log::info!("📥 Imported blocks up to #{}", self.block_n().saturating_sub(1));
+        Ok(())
+    }
+
+    /// Remove the blocks after `block_n`, and start a new pending block on top of it. The pending block is dropped.
+    async fn revert_to(&mut self, block_n: u64) -> Result<(), Error> {
+        self.importer.revert_to(block_n).await?;
+        self.reset_pending_block()
+    }
+
+    /// Start a new pending block on top of the latest block, after the chain was changed by a devnet command.
+    fn reset_pending_block(&mut self) -> Result<(), Error> {
+        let parent_block_hash = self
+            .backend
+            .get_block_hash(&BlockId::Tag(BlockTag::Latest))?
+            .ok_or_else(|| Error::Unexpected("No latest block".into()))?;
+        let block_n = self.backend.get_latest_block_n()?.map(|n| n + 1).unwrap_or(0);
+        self.block = MadaraPendingBlock::new_empty(make_pending_header(
+            block_n,
+            parent_block_hash,
+            self.backend.chain_config(),
+            self.l1_data_provider.as_ref(),
+        ));
+        self.block.info.header.block_timestamp = self.clock.start_block();
+        self.declared_classes.clear();
+        self.executor =
+            ExecutionContext::new_in_block(Arc::clone(&self.backend), &self.block.info.clone().into())?.tx_executor();
+        self.current_pending_tick = 0;
+        self.execution_time = Duration::ZERO;
+        self.bundle_weights = BouncerWeights::empty();
+        Ok(())
+    }
+
+    /// Credit `amount` of the fee token to the balance of `contract_address`, by writing its storage in the pending
+    /// block. Returns the new balance.
+    pub fn mint(
+        &mut self,
+        token_address: ContractAddress,
+        contract_address: ContractAddress,
+        amount: U256,
+    ) -> Result<U256, Error> {
+        let state = self.executor.block_state.as_mut().expect(BLOCK_STATE_ACCESS_ERR);
+        let balance = add_to_u256_storage(state, token_address, get_fee_token_var_address(contract_address), amount)?;
+        add_to_u256_storage(state, token_address, get_storage_var_address("ERC20_total_supply", &[]), amount)?;
+        log::info!("💰 Minted {amount} of token {:#x} to {:#x}", token_address.to_felt(), contract_address.to_felt());
+
+        let state_diff = self.pending_state_diff(&[])?;
+        self.store_pending_block(state_diff)?;
+        Ok(balance)
+    }
+
+    /// Write a storage value of a contract in the pending block.
+    pub fn set_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+        value: Felt,
+    ) -> Result<(), Error> {
+        let state = self.executor.block_state.as_mut().expect(BLOCK_STATE_ACCESS_ERR);
+        state.set_storage_at(contract_address, key, value).map_err(TransactionExecutionError::StateError)?;
+        log::info!("✏️ Set storage {:#x} of {:#x} to {value:#x}", key.0.to_felt(), contract_address.to_felt());
+
+        let state_diff = self.pending_state_diff(&[])?;
+        self.store_pending_block(state_diff)
+    }
+
+    /// Set the nonce of a contract in the pending block. Nonces can only be increased.
+    pub fn set_nonce(&mut self, contract_address: ContractAddress, nonce: Nonce) -> Result<(), Error> {
+        let state = self.executor.block_state.as_mut().expect(BLOCK_STATE_ACCESS_ERR);
+        increase_nonce_to(state, contract_address, nonce)?;
+        log::info!("✏️ Set nonce of {:#x} to {:#x}", contract_address.to_felt(), nonce.to_felt());
+
+        let state_diff = self.pending_state_diff(&[])?;
+        self.store_pending_block(state_diff)
+    }
+
+    /// Replace the class of a contract in the pending block, or deploy it when there is no contract at this address.
+    /// The class must be declared.
+    pub fn set_class_hash_at(&mut self, contract_address: ContractAddress, class_hash: ClassHash) -> Result<(), Error> {
+        if self.backend.get_class_info(&DbBlockId::Pending, &class_hash.to_felt())?.is_none() {
+            return Err(Error::Unexpected(format!("Class {:#x} is not declared", class_hash.to_felt()).into()));
+        }
+        let state = self.executor.block_state.as_mut().expect(BLOCK_STATE_ACCESS_ERR);
+        state.set_class_hash_at(contract_address, class_hash).map_err(TransactionExecutionError::StateError)?;
+        log::info!("✏️ Set class of {:#x} to {:#x}", contract_address.to_felt(), class_hash.to_felt());
+
+        let state_diff = self.pending_state_diff(&[])?;
+        self.store_pending_block(state_diff)
+    }
+
+    /// Sign the closed blocks with this key. Its public key is saved to the database, to be served to the full nodes.
+    pub fn with_signer(mut self, signer: Arc<dyn StarknetSigner>) -> Result<Self, Error> {
+        self.backend.write_sequencer_public_key(Some(&signer.public_key()))?;
+        self.signer = Some(signer);
+        Ok(self)
+    }
+
     fn continue_block(&mut self, bouncer_cap: BouncerWeights) -> Result<(StateDiff, ContinueBlockStats), Error> {
         let mut stats = ContinueBlockStats::default();
 
-        self.executor.bouncer.bouncer_config.block_max_capacity = bouncer_cap;
+        // Time changes apply to the pending block until it has executed transactions.
+        if self.clock.has_changed() && self.block.inner.transactions.is_empty() {
+            self.restamp_pending_block()?;
+        }
+
         let batch_size = self.backend.chain_config().execution_batch_size;
 
         let mut txs_to_process = VecDeque::with_capacity(batch_size);
@@ -240,11 +539,20 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         // This does not need to be outside the loop, but that saves an allocation
         let mut executed_txs = Vec::with_capacity(batch_size);
 
+        // Bundles go first in the tick, so that the transactions of the mempool cannot interleave with them.
+        if !self.is_builder_block {
+            self.execute_bundles(&mut executed_txs, &mut stats)?;
+        }
+
+        // The bundles were executed with their own bouncer.
+        self.executor.bouncer.bouncer_config.block_max_capacity =
+            combine_weights(&bouncer_cap, &self.bundle_weights, usize::saturating_sub);
+
         loop {
             // Take transactions from mempool.
             let to_take = batch_size.saturating_sub(txs_to_process.len());
             let cur_len = txs_to_process.len();
-            if to_take > 0 {
+            if to_take > 0 && !self.is_builder_block {
                 self.mempool.take_txs_chunk(/* extend */ &mut txs_to_process, batch_size);
 
                 txs_to_process_blockifier.extend(
@@ -282,17 +590,7 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
                             stats.n_reverted += 1;
                         }
 
-                        if let Some(class) = mem::take(&mut mempool_tx.converted_class) {
-                            self.declared_classes.push(class);
-                        }
-
-                        self.block.inner.receipts.push(from_blockifier_execution_info(
-                            &execution_info,
-                            &Transaction::AccountTransaction(clone_account_tx(&mempool_tx.tx)),
-                        ));
-                        let converted_tx = TransactionWithHash::from(clone_account_tx(&mempool_tx.tx)); // TODO: too many tx clones!
-                        self.block.info.tx_hashes.push(converted_tx.hash);
-                        self.block.inner.transactions.push(converted_tx.transaction);
+                        self.push_executed_tx(&mut mempool_tx, &execution_info);
                     }
                     Err(err) => {
                         // These are the transactions that have errored but we can't revert them. It can be because of an internal server error, but
@@ -319,6 +617,184 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         stats.n_re_added_to_mempool = txs_to_process.len();
         self.mempool.re_add_txs(txs_to_process);
 
+        let state_diff = self.pending_state_diff(&executed_txs)?;
+
+        log::debug!(
+            "Finished tick with {} new transactions, now at {} - re-adding {} txs to mempool",
+            stats.n_added_to_block,
+            self.block.inner.transactions.len(),
+            stats.n_re_added_to_mempool
+        );
+
+        Ok((state_diff, stats))
+    }
+
+    fn push_executed_tx(&mut self, mempool_tx: &mut MempoolTransaction, execution_info: &TransactionExecutionInfo) {
+        if let Some(class) = mem::take(&mut mempool_tx.converted_class) {
+            self.declared_classes.push(class);
+        }
+
+        self.block.inner.receipts.push(from_blockifier_execution_info(
+            execution_info,
+            &Transaction::AccountTransaction(clone_account_tx(&mempool_tx.tx)),
+        ));
+        let converted_tx = TransactionWithHash::from(clone_account_tx(&mempool_tx.tx)); // TODO: too many tx clones!
+        self.block.info.tx_hashes.push(converted_tx.hash);
+        self.block.inner.transactions.push(converted_tx.transaction);
+    }
+
+    /// Executes the pending bundles in the order they were received, on top of the pending block. A bundle is only
+    /// included when all of its transactions succeed and fit in the block, otherwise none of them is.
+    fn execute_bundles(
+        &mut self,
+        executed_txs: &mut Vec<MempoolTransaction>,
+        stats: &mut ContinueBlockStats,
+    ) -> Result<(), Error> {
+        let bundles = self.mempool.take_bundles();
+        if bundles.is_empty() {
+            return Ok(());
+        }
+
+        let block_n = self.block_n();
+        let mut n_included = 0;
+        for MempoolBundle { transactions, reply } in bundles {
+            let execution_infos = match self.execute_bundle(&transactions) {
+                Ok(execution_infos) => execution_infos,
+                Err(rejection) => {
+                    log::debug!("Rejected bundle of {} transaction(s): {rejection}", transactions.len());
+                    let _ = reply.send(Err(rejection));
+                    continue;
+                }
+            };
+            for (mut mempool_tx, execution_info) in transactions.into_iter().zip(execution_infos) {
+                self.push_executed_tx(&mut mempool_tx, &execution_info);
+                executed_txs.push(mempool_tx);
+                n_included += 1;
+            }
+            let _ = reply.send(Ok(block_n));
+        }
+        stats.n_added_to_block += n_included;
+        if n_included > 0 {
+            log::info!("📦 Included {n_included} bundled transaction(s) in the block at height {block_n}");
+        }
+        Ok(())
+    }
+
+    /// Executes a bundle on top of the pending block, with the capacity left in the block. The state changes of the
+    /// bundle are only applied to the pending block when all of its transactions succeed, and discarded otherwise.
+    fn execute_bundle(
+        &mut self,
+        transactions: &[MempoolTransaction],
+    ) -> Result<Vec<TransactionExecutionInfo>, BundleRejection> {
+        let used_capacity = combine_weights(
+            self.executor.bouncer.get_accumulated_weights(),
+            &self.bundle_weights,
+            usize::saturating_add,
+        );
+        let block_max_capacity = combine_weights(
+            &self.backend.chain_config().bouncer_config.block_max_capacity,
+            &used_capacity,
+            usize::saturating_sub,
+        );
+        let block_context = self.executor.block_context.clone();
+        let block_state = self.executor.block_state.as_mut().expect(BLOCK_STATE_ACCESS_ERR);
+        let mut executor = TransactionExecutor::new(
+            TransactionalState::create_transactional(block_state),
+            block_context,
+            TransactionExecutorConfig { concurrency_config: Default::default() },
+        );
+        executor.bouncer = Bouncer::new(BouncerConfig { block_max_capacity });
+
+        let mut execution_infos = Vec::with_capacity(transactions.len());
+        for tx in transactions {
+            let tx_hash = tx.tx_hash().to_felt();
+            match executor.execute(&Transaction::AccountTransaction(clone_account_tx(&tx.tx))) {
+                Ok(execution_info) if execution_info.is_reverted() => {
+                    return Err(BundleRejection::Reverted { tx_hash })
+                }
+                Ok(execution_info) => execution_infos.push(execution_info),
+                Err(TransactionExecutorError::BlockFull) => return Err(BundleRejection::BlockFull),
+                Err(err) => return Err(BundleRejection::Failed { tx_hash, error: format!("{err:#}") }),
+            }
+        }
+
+        let bundle_weights = *executor.bouncer.get_accumulated_weights();
+        executor.block_state.take().expect(BLOCK_STATE_ACCESS_ERR).commit();
+        self.bundle_weights = combine_weights(&self.bundle_weights, &bundle_weights, usize::saturating_add);
+        Ok(execution_infos)
+    }
+
+    /// Starts the block of the external block builder right away when the pending block is empty, otherwise queues
+    /// it until the pending block is closed.
+    pub fn on_builder_block(&mut self, builder_block: BuilderBlock) {
+        if self.queued_builder_block.is_some() {
+            let _ = builder_block
+                .reply
+                .send(Err(Error::InvalidBuilderBlock("Another block is waiting to be executed".into())));
+        } else if self.is_builder_block || !self.block.inner.transactions.is_empty() {
+            self.queued_builder_block = Some(builder_block);
+        } else {
+            self.start_builder_block(builder_block);
+        }
+    }
+
+    fn start_builder_block(&mut self, BuilderBlock { transactions, reply }: BuilderBlock) {
+        let res = self.execute_builder_block(transactions);
+        if let Err(err) = &res {
+            log::error!("Executing the block of the external block builder: {err:#}");
+        }
+        let _ = reply.send(res);
+    }
+
+    /// Executes the transactions in the empty pending block, in their order, with the full bouncer capacity.
+    fn execute_builder_block(&mut self, transactions: Vec<MempoolTransaction>) -> Result<BuilderBlockOutcome, Error> {
+        if self.clock.has_changed() {
+            self.restamp_pending_block()?;
+        }
+        self.is_builder_block = true;
+        self.executor.bouncer.bouncer_config.block_max_capacity =
+            self.backend.chain_config().bouncer_config.block_max_capacity;
+
+        let start_time = Instant::now();
+        let txs_blockifier: Vec<_> =
+            transactions.iter().map(|tx| Transaction::AccountTransaction(clone_account_tx(&tx.tx))).collect();
+        let all_results = self.executor.execute_txs(&txs_blockifier);
+
+        let mut outcome = BuilderBlockOutcome { block_number: self.block_n(), included: vec![], rejected: vec![] };
+        let mut executed_txs = Vec::with_capacity(all_results.len());
+        let mut transactions = transactions.into_iter();
+        for (mut mempool_tx, exec_result) in transactions.by_ref().zip(all_results) {
+            let tx_hash = mempool_tx.tx_hash().to_felt();
+            match exec_result {
+                Ok(execution_info) => {
+                    self.push_executed_tx(&mut mempool_tx, &execution_info);
+                    outcome.included.push(tx_hash);
+                }
+                Err(err) => {
+                    log::debug!("Rejected transaction {tx_hash:#x} of the external block builder: {err:#}");
+                    outcome.rejected.push(tx_hash);
+                }
+            }
+            executed_txs.push(mempool_tx);
+        }
+        // When the bouncer cap is reached, blockifier returns fewer results than the number of transactions.
+        outcome.rejected.extend(transactions.map(|tx| tx.tx_hash().to_felt()));
+
+        let state_diff = self.pending_state_diff(&executed_txs)?;
+        self.execution_time += start_time.elapsed();
+        log::info!(
+            "🏗️  Executed {} transaction(s) of the external block builder in the pending block at height {}, rejected {} - {:?}",
+            outcome.included.len(),
+            outcome.block_number,
+            outcome.rejected.len(),
+            start_time.elapsed(),
+        );
+
+        self.store_pending_block(state_diff)?;
+        Ok(outcome)
+    }
+
+    fn pending_state_diff(&mut self, executed_txs: &[MempoolTransaction]) -> Result<StateDiff, Error> {
         let on_top_of = self
             .executor
             .block_state
@@ -328,16 +804,18 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
             .on_top_of_block_id;
 
         let (state_diff, _visited_segments, _weights) =
-            finalize_execution_state(&executed_txs, &mut self.executor, &self.backend, &on_top_of)?;
-
-        log::debug!(
-            "Finished tick with {} new transactions, now at {} - re-adding {} txs to mempool",
-            stats.n_added_to_block,
-            self.block.inner.transactions.len(),
-            stats.n_re_added_to_mempool
-        );
+            finalize_execution_state(executed_txs, &mut self.executor, &self.backend, &on_top_of)?;
+        Ok(state_diff)
+    }
 
-        Ok((state_diff, stats))
+    fn store_pending_block(&mut self, state_diff: StateDiff) -> Result<(), Error> {
+        // todo, prefer using the block import pipeline?
+        self.backend.store_block(self.block.clone().into(), state_diff, self.declared_classes.clone())?;
+        // do not forget to flush :)
+        self.backend
+            .maybe_flush(true)
+            .map_err(|err| BlockImportError::Internal(format!("DB flushing error: {err:#}").into()))?;
+        Ok(())
     }
 
     /// Each "tick" of the block time updates the pending block but only with the appropriate fraction of the total bouncer capacity.
@@ -384,6 +862,7 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
 
         let start_time = Instant::now();
         let (state_diff, stats) = self.continue_block(bouncer_cap)?;
+        self.execution_time += start_time.elapsed();
         if stats.n_added_to_block > 0 {
             log::info!(
                 "🧮 Executed and added {} transaction(s) to the pending block at height {} - {:?}",
@@ -394,14 +873,7 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         }
 
         // Store pending block
-        // todo, prefer using the block import pipeline?
-        self.backend.store_block(self.block.clone().into(), state_diff, self.declared_classes.clone())?;
-        // do not forget to flush :)
-        self.backend
-            .maybe_flush(true)
-            .map_err(|err| BlockImportError::Internal(format!("DB flushing error: {err:#}").into()))?;
-
-        Ok(())
+        self.store_pending_block(state_diff)
     }
 
     /// This creates a block, continuing the current pending block state up to the full bouncer limit.
@@ -413,19 +885,30 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         let start_time = Instant::now();
         let (new_state_diff, _n_executed) =
             self.continue_block(self.backend.chain_config().bouncer_config.block_max_capacity)?;
+        let execution_time = mem::take(&mut self.execution_time) + start_time.elapsed();
 
         // Convert the pending block to a closed block and save to db.
         let parent_block_hash = Felt::ZERO; // temp parent block hash
-        let new_empty_block = MadaraPendingBlock::new_empty(make_pending_header(
+        let mut new_empty_block = MadaraPendingBlock::new_empty(make_pending_header(
+            block_n + 1,
             parent_block_hash,
             self.backend.chain_config(),
             self.l1_data_provider.as_ref(),
         ));
+        new_empty_block.info.header.block_timestamp = self.clock.start_block();
 
         let block_to_close = mem::replace(&mut self.block, new_empty_block);
         let declared_classes = mem::take(&mut self.declared_classes);
 
         let n_txs = block_to_close.inner.transactions.len();
+        if let Some(metrics) = &self.metrics {
+            metrics.record(
+                ExecutionSource::BlockProduction,
+                &block_to_close.inner.receipts,
+                Some(new_state_diff.len()),
+                execution_time,
+            );
+        }
 
         // This is compute heavy as it does the commitments and trie computations.
         let import_result = close_block(
@@ -435,6 +918,7 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
             self.backend.chain_config().chain_id.clone(),
             block_n,
             declared_classes,
+            self.signer.clone(),
         )
         .await?;
         self.block.info.header.parent_block_hash = import_result.block_hash; // fix temp parent block hash for new pending :)
@@ -443,9 +927,16 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
         self.executor =
             ExecutionContext::new_in_block(Arc::clone(&self.backend), &self.block.info.clone().into())?.tx_executor();
         self.current_pending_tick = 0;
+        self.is_builder_block = false;
+        self.bundle_weights = BouncerWeights::empty();
 
         log::info!("⛏️  Closed block #{} with {} transactions - {:?}", block_n, n_txs, start_time.elapsed());
-        let _ = self.notify_exexs(block_to_close, block_n).context("Sending notification to ExExs");
+        let _ =
+            self.notify_exexs(block_to_close, block_n, new_state_diff).await.context("Sending notification to ExExs");
+
+        if let Some(builder_block) = self.queued_builder_block.take() {
+            self.start_builder_block(builder_block);
+        }
 
         Ok(())
     }
@@ -464,9 +955,17 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
 
         log::info!("⛏️  Starting block production at block #{}", self.block_n());
 
+        let mut devnet_commands = self.devnet_commands.take();
+        let mut builder_blocks = self.builder_blocks.take();
+        let mining_mode = self.mining_mode;
+        let mempool = Arc::clone(&self.mempool);
+        if mining_mode != MiningMode::Interval {
+            log::info!("⛏️  Mining mode: {mining_mode:?}");
+        }
+
         loop {
             tokio::select! {
-                instant = interval_block_time.tick() => {
+                instant = interval_block_time.tick(), if mining_mode == MiningMode::Interval => {
                     if let Err(err) = self.on_block_time().await {
                         log::error!("Block production task has errored: {err:#}");
                     }
@@ -488,6 +987,13 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
                     }
                     self.current_pending_tick += 1;
                 },
+                _ = mempool.tx_received().notified(), if mining_mode == MiningMode::Instant => {
+                    if let Err(err) = self.on_block_time().await {
+                        log::error!("Block production task has errored: {err:#}");
+                    }
+                },
+                Some(command) = next_devnet_command(&mut devnet_commands) => self.on_devnet_command(command).await,
+                Some(builder_block) = next_builder_block(&mut builder_blocks) => self.on_builder_block(builder_block),
                 _ = graceful_shutdown() => break,
             }
         }
@@ -500,14 +1006,57 @@ impl<Mempool: MempoolProvider> BlockProductionTask<Mempool> {
     }
 
     /// Sends a notification to the ExExs that a block has been closed.
-    fn notify_exexs(&self, block_produced: MadaraPendingBlock, block_number: u64) -> anyhow::Result<()> {
-        let Some(manager) = self.exex_manager.as_ref() else {
+    async fn notify_exexs(
+        &mut self,
+        block_produced: MadaraPendingBlock,
+        block_number: u64,
+        state_diff: StateDiff,
+    ) -> anyhow::Result<()> {
+        let Some(manager) = self.exex_manager.as_mut() else {
             return Ok(());
         };
         let notification = ExExNotification::BlockProduced {
             block: Box::new(block_produced),
             block_number: BlockNumber(block_number),
+            state_diff: Some(Box::new(state_diff)),
         };
-        manager.send(notification).map_err(|e| anyhow::anyhow!("Could not send ExEx notification: {}", e))
+        manager.notify(notification).await.map_err(|e| anyhow::anyhow!("Could not send ExEx notification: {}", e))
+    }
+}
+
+async fn next_devnet_command(receiver: &mut Option<mpsc::Receiver<DevnetCommand>>) -> Option<DevnetCommand> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => None,
+    }
+}
+
+/// Applies `f` to each of the weights.
+fn combine_weights(a: &BouncerWeights, b: &BouncerWeights, f: impl Fn(usize, usize) -> usize) -> BouncerWeights {
+    BouncerWeights {
+        builtin_count: BuiltinCount {
+            add_mod: f(a.builtin_count.add_mod, b.builtin_count.add_mod),
+            bitwise: f(a.builtin_count.bitwise, b.builtin_count.bitwise),
+            ecdsa: f(a.builtin_count.ecdsa, b.builtin_count.ecdsa),
+            ec_op: f(a.builtin_count.ec_op, b.builtin_count.ec_op),
+            keccak: f(a.builtin_count.keccak, b.builtin_count.keccak),
+            mul_mod: f(a.builtin_count.mul_mod, b.builtin_count.mul_mod),
+            pedersen: f(a.builtin_count.pedersen, b.builtin_count.pedersen),
+            poseidon: f(a.builtin_count.poseidon, b.builtin_count.poseidon),
+            range_check: f(a.builtin_count.range_check, b.builtin_count.range_check),
+            range_check96: f(a.builtin_count.range_check96, b.builtin_count.range_check96),
+        },
+        gas: f(a.gas, b.gas),
+        message_segment_length: f(a.message_segment_length, b.message_segment_length),
+        n_events: f(a.n_events, b.n_events),
+        n_steps: f(a.n_steps, b.n_steps),
+        state_diff_size: f(a.state_diff_size, b.state_diff_size),
+    }
+}
+
+async fn next_builder_block(receiver: &mut Option<mpsc::Receiver<BuilderBlock>>) -> Option<BuilderBlock> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => None,
     }
 }
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use blockifier::transaction::transaction_execution::Transaction;
+use mc_db::MadaraBackend;
+use mp_transactions::broadcasted_to_blockifier;
+use starknet_core::types::BroadcastedTransaction;
+use starknet_types_core::felt::Felt;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::block_production::Error;
+use crate::{chain_id_to_felt, is_only_query, ArrivedAtTimestamp, MempoolTransaction};
+
+/// Transactions ordered by an external block builder. The block production task executes them in this order as the
+/// next block, instead of the transactions of the mempool.
+#[derive(Debug)]
+pub struct BuilderBlock {
+    pub transactions: Vec<MempoolTransaction>,
+    pub reply: oneshot::Sender<Result<BuilderBlockOutcome, Error>>,
+}
+
+/// Outcome of the execution of a [`BuilderBlock`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuilderBlockOutcome {
+    /// The pending block the transactions were executed in.
+    pub block_number: u64,
+    /// Hashes of the transactions added to the block, in order. This includes the reverted transactions.
+    pub included: Vec<Felt>,
+    /// Hashes of the transactions which failed validation, or did not fit in the block.
+    pub rejected: Vec<Felt>,
+}
+
+/// Sends the blocks of an external block builder to the block production task.
+#[derive(Clone)]
+pub struct BuilderHandle {
+    backend: Arc<MadaraBackend>,
+    sender: mpsc::Sender<BuilderBlock>,
+}
+
+impl BuilderHandle {
+    /// The receiver is given to [`crate::block_production::BlockProductionTask::with_builder_blocks`].
+    pub fn new(backend: Arc<MadaraBackend>) -> (Self, mpsc::Receiver<BuilderBlock>) {
+        let (sender, receiver) = mpsc::channel(1);
+        (Self { backend, sender }, receiver)
+    }
+
+    /// Executes the transactions as the pending block when it is still empty, otherwise as the block after it. Only
+    /// one block can be waiting for its turn. Returns once the transactions are executed.
+    ///
+    /// The transactions are not taken from the mempool: a transaction also submitted to the mempool is rejected
+    /// there once included in the block.
+    pub async fn submit_block(&self, transactions: Vec<BroadcastedTransaction>) -> Result<BuilderBlockOutcome, Error> {
+        if transactions.is_empty() {
+            return Err(Error::InvalidBuilderBlock("The block has no transactions".into()));
+        }
+        let chain_id = chain_id_to_felt(&self.backend.chain_config().chain_id);
+        let protocol_version = self.backend.chain_config().latest_protocol_version;
+        let arrived_at = ArrivedAtTimestamp::now();
+        let transactions = transactions
+            .into_iter()
+            .enumerate()
+            .map(|(index, tx)| {
+                let (tx, converted_class) = broadcasted_to_blockifier(tx, chain_id, protocol_version)
+                    .map_err(|err| Error::InvalidBuilderBlock(format!("Transaction {index}: {err:#}").into()))?;
+                match tx {
+                    Transaction::AccountTransaction(tx) if !is_only_query(&tx) => {
+                        Ok(MempoolTransaction { tx, arrived_at, converted_class })
+                    }
+                    _ => Err(Error::InvalidBuilderBlock(format!("Transaction {index} is a query").into())),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        let (reply, receiver) = oneshot::channel();
+        self.sender.try_send(BuilderBlock { transactions, reply }).map_err(|err| match err {
+            TrySendError::Full(_) => Error::InvalidBuilderBlock("Another block is waiting to be executed".into()),
+            TrySendError::Closed(_) => Error::Unexpected("Block production is not running".into()),
+        })?;
+        receiver.await.map_err(|_| Error::Unexpected("Block production stopped before replying".into()))?
+    }
+}
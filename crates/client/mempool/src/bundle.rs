@@ -0,0 +1,29 @@
+use starknet_types_core::felt::Felt;
+use tokio::sync::oneshot;
+
+use crate::MempoolTransaction;
+
+/// Maximum number of transactions in a bundle.
+pub const MAX_BUNDLE_SIZE: usize = 32;
+/// Maximum number of bundles waiting to be executed. The new bundles are rejected once it is reached.
+pub const MAX_PENDING_BUNDLES: usize = 64;
+
+/// Transactions which must be included consecutively in one block, or not at all. The bundles are executed at the
+/// next tick of the pending block, before the other transactions of the mempool.
+#[derive(Debug)]
+pub struct MempoolBundle {
+    pub transactions: Vec<MempoolTransaction>,
+    /// Receives the number of the block the bundle was included in.
+    pub reply: oneshot::Sender<Result<u64, BundleRejection>>,
+}
+
+/// Why a bundle was not included in the block. The bundle is dropped, and none of its transactions is executed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BundleRejection {
+    #[error("Transaction {tx_hash:#x} of the bundle failed: {error}")]
+    Failed { tx_hash: Felt, error: String },
+    #[error("Transaction {tx_hash:#x} of the bundle reverted")]
+    Reverted { tx_hash: Felt },
+    #[error("The bundle does not fit in the block")]
+    BlockFull,
+}
@@ -0,0 +1,98 @@
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Source of the timestamps of the produced blocks. It follows the system time, but devnets can move it around to
+/// test time-dependent contracts.
+///
+/// The timestamp of a block is chosen when the block is started: changes apply to the pending block while it has no
+/// transactions, or to the next block otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct BlockClock(Arc<Mutex<ClockState>>);
+
+#[derive(Debug, Default)]
+struct ClockState {
+    /// Seconds added to the system time.
+    offset: i64,
+    /// Timestamp of the next block, which only applies once.
+    next_block_timestamp: Option<u64>,
+    /// The time changed since the last block was started.
+    changed: bool,
+}
+
+fn system_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Current system time is before the UNIX epoch")
+        .as_secs() as i64
+}
+
+impl BlockClock {
+    /// Current time of the chain, in seconds since the UNIX epoch.
+    pub fn now(&self) -> u64 {
+        let state = self.0.lock().expect("Poisoned lock");
+        (system_now() + state.offset).max(0) as u64
+    }
+
+    /// The time of the chain continues from `timestamp`.
+    pub fn set_time(&self, timestamp: u64) {
+        let mut state = self.0.lock().expect("Poisoned lock");
+        state.offset = timestamp as i64 - system_now();
+        state.next_block_timestamp = None;
+        state.changed = true;
+    }
+
+    pub fn increase_time(&self, seconds: u64) {
+        let mut state = self.0.lock().expect("Poisoned lock");
+        state.offset += seconds as i64;
+        state.changed = true;
+    }
+
+    /// Only the next block gets this timestamp, the time of the chain then continues from it.
+    pub fn set_next_block_timestamp(&self, timestamp: u64) {
+        let mut state = self.0.lock().expect("Poisoned lock");
+        state.next_block_timestamp = Some(timestamp);
+        state.changed = true;
+    }
+
+    /// Timestamp of a block being started.
+    pub(crate) fn start_block(&self) -> u64 {
+        let mut state = self.0.lock().expect("Poisoned lock");
+        state.changed = false;
+        match state.next_block_timestamp.take() {
+            Some(timestamp) => {
+                state.offset = timestamp as i64 - system_now();
+                timestamp
+            }
+            None => (system_now() + state.offset).max(0) as u64,
+        }
+    }
+
+    /// Whether the time changed since the last block was started.
+    pub(crate) fn has_changed(&self) -> bool {
+        self.0.lock().expect("Poisoned lock").changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_block_timestamp_applies_once() {
+        let clock = BlockClock::default();
+        clock.set_next_block_timestamp(1_000);
+        assert!(clock.has_changed());
+        assert_eq!(clock.start_block(), 1_000);
+        assert!(!clock.has_changed());
+        // The time continues from the overridden timestamp.
+        assert!((1_000..1_010).contains(&clock.start_block()));
+    }
+
+    #[test]
+    fn increase_time() {
+        let clock = BlockClock::default();
+        clock.set_time(1_000);
+        clock.increase_time(3_600);
+        assert!((4_600..4_610).contains(&clock.start_block()));
+    }
+}
@@ -3,10 +3,13 @@ use mc_block_import::{
 };
 use mp_block::{header::PendingHeader, MadaraPendingBlock, MadaraPendingBlockInfo};
 use mp_class::ConvertedClass;
+use mp_keystore::StarknetSigner;
 use mp_state_update::StateDiff;
 use starknet_api::core::ChainId;
+use std::sync::Arc;
 
 /// Close the block (convert from pending to closed), and store to db. This is delegated to the block import module.
+/// When `signer` is set, the block hash signature is stored along with the block.
 pub async fn close_block(
     importer: &BlockImporter,
     block: MadaraPendingBlock,
@@ -14,6 +17,7 @@ pub async fn close_block(
     chain_id: ChainId,
     block_number: u64,
     declared_classes: Vec<ConvertedClass>,
+    signer: Option<Arc<dyn StarknetSigner>>,
 ) -> Result<BlockImportResult, BlockImportError> {
     let validation = BlockValidationContext::new(chain_id).trust_transaction_hashes(true);
 
@@ -53,5 +57,5 @@ pub async fn close_block(
         )
         .await?;
 
-    importer.verify_apply(block, validation.clone()).await
+    importer.verify_apply_signed(block, validation.clone(), signer).await
 }
@@ -0,0 +1,123 @@
+use mc_block_import::UnverifiedFullBlock;
+use primitive_types::U256;
+use starknet_api::core::{ClassHash, ContractAddress, Nonce};
+use starknet_api::state::StorageKey;
+use starknet_types_core::felt::Felt;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::block_production::Error;
+
+/// When the block production closes blocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MiningMode {
+    /// Every block time of the chain config.
+    #[default]
+    Interval,
+    /// As soon as transactions are received.
+    Instant,
+    /// Only when requested with [`DevnetHandle::mine`].
+    OnDemand,
+}
+
+/// Commands of the devnet RPC methods, applied by the block production task to the pending block.
+#[derive(Debug)]
+pub enum DevnetCommand {
+    /// Credit `amount` of a fee token to the balance of `contract_address`. Replies with the new balance.
+    Mint {
+        token_address: ContractAddress,
+        contract_address: ContractAddress,
+        amount: U256,
+        reply: oneshot::Sender<Result<U256, Error>>,
+    },
+    /// Write a storage value of a contract in the pending block.
+    SetStorageAt {
+        contract_address: ContractAddress,
+        key: StorageKey,
+        value: Felt,
+        reply: oneshot::Sender<Result<(), Error>>,
+    },
+    /// Increase the nonce of a contract in the pending block.
+    SetNonce { contract_address: ContractAddress, nonce: Nonce, reply: oneshot::Sender<Result<(), Error>> },
+    /// Replace the class of a contract in the pending block, or deploy it when there is no contract at this address.
+    SetClassHashAt {
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+        reply: oneshot::Sender<Result<(), Error>>,
+    },
+    /// Close the pending block. Replies with its block number.
+    Mine { reply: oneshot::Sender<Result<u64, Error>> },
+    /// Import blocks on top of the latest block, and start a new pending block after them. The pending block must
+    /// not have transactions.
+    ImportBlocks { blocks: Vec<UnverifiedFullBlock>, reply: oneshot::Sender<Result<(), Error>> },
+    /// Remove the blocks after `block_n` and drop the pending block, a new pending block is started on top of
+    /// `block_n`.
+    RevertTo { block_n: u64, reply: oneshot::Sender<Result<(), Error>> },
+}
+
+/// Sends the devnet commands to the block production task.
+#[derive(Debug, Clone)]
+pub struct DevnetHandle(mpsc::Sender<DevnetCommand>);
+
+impl DevnetHandle {
+    /// The receiver is given to [`crate::block_production::BlockProductionTask::with_devnet_commands`].
+    pub fn new() -> (Self, mpsc::Receiver<DevnetCommand>) {
+        let (sender, receiver) = mpsc::channel(16);
+        (Self(sender), receiver)
+    }
+
+    /// Write the new balance directly to the storage of the token in the pending block, without a transaction.
+    pub async fn mint(
+        &self,
+        token_address: ContractAddress,
+        contract_address: ContractAddress,
+        amount: U256,
+    ) -> Result<U256, Error> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(DevnetCommand::Mint { token_address, contract_address, amount, reply }, receiver).await
+    }
+
+    /// Like [`Self::mint`], these write to the pending block directly, without a transaction.
+    pub async fn set_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+        value: Felt,
+    ) -> Result<(), Error> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(DevnetCommand::SetStorageAt { contract_address, key, value, reply }, receiver).await
+    }
+
+    pub async fn set_nonce(&self, contract_address: ContractAddress, nonce: Nonce) -> Result<(), Error> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(DevnetCommand::SetNonce { contract_address, nonce, reply }, receiver).await
+    }
+
+    pub async fn set_class_hash_at(
+        &self,
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+    ) -> Result<(), Error> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(DevnetCommand::SetClassHashAt { contract_address, class_hash, reply }, receiver).await
+    }
+
+    pub async fn mine(&self) -> Result<u64, Error> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(DevnetCommand::Mine { reply }, receiver).await
+    }
+
+    pub async fn import_blocks(&self, blocks: Vec<UnverifiedFullBlock>) -> Result<(), Error> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(DevnetCommand::ImportBlocks { blocks, reply }, receiver).await
+    }
+
+    pub async fn revert_to(&self, block_n: u64) -> Result<(), Error> {
+        let (reply, receiver) = oneshot::channel();
+        self.send(DevnetCommand::RevertTo { block_n, reply }, receiver).await
+    }
+
+    async fn send<T>(&self, command: DevnetCommand, receiver: oneshot::Receiver<Result<T, Error>>) -> Result<T, Error> {
+        self.0.send(command).await.map_err(|_| Error::Unexpected("Block production is not running".into()))?;
+        receiver.await.map_err(|_| Error::Unexpected("Block production stopped before replying".into()))?
+    }
+}
@@ -4,7 +4,9 @@ use mp_chain_config::ChainConfig;
 use starknet_types_core::felt::Felt;
 use std::time::SystemTime;
 
+/// Header of the pending block `block_n`.
 pub fn make_pending_header(
+    block_n: u64,
     parent_block_hash: Felt,
     chain_config: &ChainConfig,
     l1_info: &dyn L1DataProvider,
@@ -16,7 +18,7 @@ pub fn make_pending_header(
             .duration_since(SystemTime::UNIX_EPOCH)
             .expect("Current system time is before the UNIX epoch")
             .as_secs(),
-        protocol_version: chain_config.latest_protocol_version,
+        protocol_version: chain_config.protocol_version_at(block_n),
         l1_gas_price: l1_info.get_gas_prices(),
         l1_da_mode: l1_info.get_da_mode(),
     }
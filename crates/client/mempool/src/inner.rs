@@ -2,6 +2,8 @@
 //! This is the chokepoint for all insertions and popping, as such, we want to make it as fast as possible.
 //! Insertion and popping should be O(log n).
 //! We also really don't want to poison the lock by panicking.
+//! An invoke transaction replaces the one with the same nonce when it pays a high enough fee, see
+//! [`REPLACEMENT_FEE_BUMP_PERCENT`].
 //!
 //! TODO: mempool size limits
 //! TODO(perf): should we box the MempoolTransaction?
@@ -11,7 +13,7 @@ use blockifier::transaction::account_transaction::AccountTransaction;
 use mp_class::ConvertedClass;
 use starknet_api::{
     core::{ContractAddress, Nonce},
-    transaction::TransactionHash,
+    transaction::{InvokeTransaction as ApiInvokeTransaction, TransactionHash},
 };
 use std::{
     cmp,
@@ -22,6 +24,9 @@ use std::{
 
 pub type ArrivedAtTimestamp = SystemTime;
 
+/// Minimum increase of the max fee, in percent, for a transaction to replace the one with the same nonce.
+pub const REPLACEMENT_FEE_BUMP_PERCENT: u128 = 10;
+
 #[derive(Debug)]
 pub struct MempoolTransaction {
     pub tx: AccountTransaction,
@@ -49,6 +54,40 @@ impl MempoolTransaction {
     pub fn tx_hash(&self) -> TransactionHash {
         tx_hash(&self.tx)
     }
+
+    /// Whether this transaction can replace `other`, which has the same sender and nonce. Only invoke transactions
+    /// can be replaced, by one with a max fee at least [`REPLACEMENT_FEE_BUMP_PERCENT`] higher in the same token.
+    fn can_replace(&self, other: &MempoolTransaction) -> bool {
+        match (max_fee(&self.tx), max_fee(&other.tx)) {
+            (Some(MaxFee::Wei(new)), Some(MaxFee::Wei(old))) | (Some(MaxFee::Fri(new)), Some(MaxFee::Fri(old))) => {
+                new > old && new >= old.saturating_add(old / 100 * REPLACEMENT_FEE_BUMP_PERCENT)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Most an invoke transaction can pay in fees.
+enum MaxFee {
+    /// V0 and V1 transactions.
+    Wei(u128),
+    /// V3 transactions, bounded by their resource bounds.
+    Fri(u128),
+}
+
+fn max_fee(tx: &AccountTransaction) -> Option<MaxFee> {
+    let AccountTransaction::Invoke(tx) = tx else { return None };
+    Some(match &tx.tx {
+        ApiInvokeTransaction::V0(tx) => MaxFee::Wei(tx.max_fee.0),
+        ApiInvokeTransaction::V1(tx) => MaxFee::Wei(tx.max_fee.0),
+        ApiInvokeTransaction::V3(tx) => MaxFee::Fri(
+            tx.resource_bounds
+                .0
+                .values()
+                .map(|bounds| u128::from(bounds.max_amount).saturating_mul(bounds.max_price_per_unit))
+                .fold(0, u128::saturating_add),
+        ),
+    })
 }
 
 struct OrderMempoolTransactionByNonce(MempoolTransaction);
@@ -82,7 +121,11 @@ pub struct NonceChain {
 
 #[derive(Eq, PartialEq, Debug)]
 pub enum InsertedPosition {
-    Front { former_head_arrived_at: ArrivedAtTimestamp },
+    Front {
+        former_head_arrived_at: ArrivedAtTimestamp,
+    },
+    /// Replaced the transaction with the same nonce, taking its place in the chain.
+    Replaced,
     Other,
 }
 
@@ -114,9 +157,31 @@ impl NonceChain {
     /// When `force` is `true`, this function should never return any error.
     pub fn insert(
         &mut self,
-        mempool_tx: MempoolTransaction,
+        mut mempool_tx: MempoolTransaction,
         force: bool,
     ) -> Result<InsertedPosition, TxInsersionError> {
+        if !force {
+            let mempool_tx_by_nonce = OrderMempoolTransactionByNonce(mempool_tx);
+            if let Some(existing) = self.transactions.get(&mempool_tx_by_nonce) {
+                if !mempool_tx_by_nonce.0.can_replace(&existing.0) {
+                    return Err(TxInsersionError::NonceConflict);
+                }
+                // The replacement keeps the arrival time of the replaced transaction, so that the tx queue does not
+                // change.
+                let mut replacement = mempool_tx_by_nonce.0;
+                replacement.arrived_at = existing.0.arrived_at;
+                #[cfg(debug_assertions)]
+                {
+                    if existing.0.tx_hash() == self.front_tx_hash {
+                        self.front_tx_hash = replacement.tx_hash();
+                    }
+                }
+                self.transactions.replace(OrderMempoolTransactionByNonce(replacement));
+                return Ok(InsertedPosition::Replaced);
+            }
+            mempool_tx = mempool_tx_by_nonce.0;
+        }
+
         let position = if self.front_arrived_at > mempool_tx.arrived_at {
             // We are inserting at the front here
             let former_head_arrived_at = self.front_arrived_at;
@@ -199,6 +264,7 @@ pub struct MempoolInner {
 
 #[derive(thiserror::Error, Debug)]
 pub enum TxInsersionError {
+    /// A transaction with the same nonce is in the mempool, and cannot be replaced by the new one.
     #[error("A transaction with this nonce already exists in the transaction pool")]
     NonceConflict,
     #[error("A transaction deploying the same account already exists in the transaction pool")]
@@ -272,7 +338,7 @@ impl MempoolInner {
                             self.tx_queue.insert(AccountOrderedByTimestamp { contract_addr, timestamp: arrived_at });
                         debug_assert!(inserted);
                     }
-                    InsertedPosition::Other => {
+                    InsertedPosition::Replaced | InsertedPosition::Other => {
                         // No need to update the tx queue.
                     }
                 }
@@ -351,12 +417,13 @@ mod tests {
     use proptest_derive::Arbitrary;
     use starknet_api::{
         data_availability::DataAvailabilityMode,
-        transaction::{DeclareTransactionV3, InvokeTransactionV3},
+        transaction::{DeclareTransactionV3, Fee, InvokeTransactionV1, InvokeTransactionV3},
     };
     use starknet_types_core::felt::Felt;
 
     use super::*;
     use std::fmt;
+    use std::time::Duration;
 
     #[derive(PartialEq, Eq, Hash)]
     struct AFelt(Felt);
@@ -525,6 +592,71 @@ mod tests {
         }
     }
 
+    fn invoke_v1(tx_hash: u64, nonce: u64, max_fee: u128, arrived_at: SystemTime) -> MempoolTransaction {
+        let tx = AccountTransaction::Invoke(InvokeTransaction::new(
+            starknet_api::transaction::InvokeTransaction::V1(InvokeTransactionV1 {
+                max_fee: Fee(max_fee),
+                signature: Default::default(),
+                nonce: Nonce(Felt::from(nonce)),
+                sender_address: ContractAddress::try_from(Felt::ONE).unwrap(),
+                calldata: Default::default(),
+            }),
+            TransactionHash(Felt::from(tx_hash)),
+        ));
+        MempoolTransaction { tx, arrived_at, converted_class: None }
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let arrived_at = SystemTime::UNIX_EPOCH;
+        let mut mempool = MempoolInner::default();
+        mempool.insert_tx(invoke_v1(1, 0, 1000, arrived_at), false).unwrap();
+        mempool.insert_tx(invoke_v1(2, 1, 1000, arrived_at + Duration::from_secs(1)), false).unwrap();
+
+        // Not enough of a fee bump.
+        assert!(matches!(
+            mempool.insert_tx(invoke_v1(3, 0, 1050, arrived_at + Duration::from_secs(2)), false),
+            Err(TxInsersionError::NonceConflict)
+        ));
+        mempool.insert_tx(invoke_v1(4, 0, 1100, arrived_at + Duration::from_secs(3)), false).unwrap();
+        mempool.check_invariants();
+
+        // The replacement takes the place of the replaced transaction.
+        let first = mempool.pop_next().unwrap();
+        assert_eq!(first.tx_hash(), TransactionHash(Felt::from(4)));
+        assert_eq!(first.arrived_at, arrived_at);
+        assert_eq!(mempool.pop_next().unwrap().tx_hash(), TransactionHash(Felt::from(2)));
+        assert!(mempool.pop_next().is_none());
+    }
+
+    #[test]
+    fn test_replace_by_fee_other_fee_token() {
+        let mut mempool = MempoolInner::default();
+        mempool.insert_tx(invoke_v1(1, 0, 1000, SystemTime::UNIX_EPOCH), false).unwrap();
+        let v3 = InvokeTransaction::new(
+            starknet_api::transaction::InvokeTransaction::V3(InvokeTransactionV3 {
+                resource_bounds: Default::default(),
+                tip: Default::default(),
+                signature: Default::default(),
+                nonce: Nonce(Felt::ZERO),
+                sender_address: ContractAddress::try_from(Felt::ONE).unwrap(),
+                calldata: Default::default(),
+                nonce_data_availability_mode: DataAvailabilityMode::L1,
+                fee_data_availability_mode: DataAvailabilityMode::L1,
+                paymaster_data: Default::default(),
+                account_deployment_data: Default::default(),
+            }),
+            TransactionHash(Felt::TWO),
+        );
+        let v3 = MempoolTransaction {
+            tx: AccountTransaction::Invoke(v3),
+            arrived_at: SystemTime::UNIX_EPOCH,
+            converted_class: None,
+        };
+        assert!(matches!(mempool.insert_tx(v3, false), Err(TxInsersionError::NonceConflict)));
+        mempool.check_invariants();
+    }
+
     proptest::proptest! {
         #![proptest_config(ProptestConfig::with_cases(5))] // comment this when developing, this is mostly for faster ci & whole workspace `cargo test`
         #[test]
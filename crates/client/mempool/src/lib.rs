@@ -17,8 +17,8 @@ use mp_class::ConvertedClass;
 use mp_rpc::errors::StarknetRpcApiError;
 use mp_transactions::broadcasted_to_blockifier;
 use mp_transactions::BroadcastedToBlockifierError;
-use starknet_api::core::{ContractAddress, Nonce};
-use starknet_api::transaction::TransactionHash;
+use starknet_api::core::{ChainId, ContractAddress, Nonce};
+use starknet_api::transaction::{Transaction as ApiTransaction, TransactionHash};
 use starknet_core::types::BroadcastedDeclareTransaction;
 use starknet_core::types::BroadcastedDeployAccountTransaction;
 use starknet_core::types::BroadcastedInvokeTransaction;
@@ -27,17 +27,28 @@ use starknet_core::types::DeclareTransactionResult;
 use starknet_core::types::DeployAccountTransactionResult;
 use starknet_core::types::InvokeTransactionResult;
 use starknet_types_core::felt::Felt;
+use std::borrow::Cow;
+use std::mem;
 use std::sync::Arc;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
+use tokio::sync::{oneshot, Notify};
 
+pub use builder::{BuilderBlock, BuilderBlockOutcome, BuilderHandle};
+pub use bundle::{BundleRejection, MempoolBundle, MAX_BUNDLE_SIZE, MAX_PENDING_BUNDLES};
+pub use clock::BlockClock;
+pub use devnet::{DevnetCommand, DevnetHandle, MiningMode};
 pub use inner::TxInsersionError;
-pub use inner::{ArrivedAtTimestamp, MempoolTransaction};
+pub use inner::{ArrivedAtTimestamp, MempoolTransaction, REPLACEMENT_FEE_BUMP_PERCENT};
 #[cfg(any(test, feature = "testing"))]
 pub use l1::MockL1DataProvider;
 pub use l1::{GasPriceProvider, L1DataProvider};
 
 pub mod block_production;
+mod builder;
+mod bundle;
+mod clock;
 mod close_block;
+mod devnet;
 pub mod header;
 mod inner;
 mod l1;
@@ -54,6 +65,12 @@ pub enum Error {
     Exec(#[from] mc_exec::Error),
     #[error("Preprocessing transaction: {0:#}")]
     BroadcastedToBlockifier(#[from] BroadcastedToBlockifierError),
+    #[error("Transaction signed for chain id {signed}, but this node is on chain id {expected}")]
+    ChainIdMismatch { expected: ChainId, signed: ChainId },
+    #[error("Invalid bundle: {0}")]
+    InvalidBundle(Cow<'static, str>),
+    #[error("{MAX_PENDING_BUNDLES} bundles are already waiting to be executed")]
+    BundleQueueFull,
 }
 impl Error {
     pub fn is_internal(&self) -> bool {
@@ -66,6 +83,9 @@ impl From<Error> for StarknetRpcApiError {
         match val {
             Error::InnerMempool(TxInsersionError::NonceConflict) => StarknetRpcApiError::DuplicateTxn,
             Error::Validation(err) => StarknetRpcApiError::ValidationFailure { error: format!("{err:#}") },
+            err @ (Error::ChainIdMismatch { .. } | Error::InvalidBundle(_) | Error::BundleQueueFull) => {
+                StarknetRpcApiError::ValidationFailure { error: format!("{err}") }
+            }
             Error::InnerMempool(err) => StarknetRpcApiError::ValidationFailure { error: format!("{err:#}") },
             Error::Exec(err) => StarknetRpcApiError::TxnExecutionError { tx_index: 0, error: format!("{err:#}") },
             Error::StorageError(err) => {
@@ -94,17 +114,80 @@ pub trait MempoolProvider: Send + Sync {
     where
         Self: Sized;
     fn chain_id(&self) -> Felt;
+    /// Notified when a transaction is added to the mempool.
+    fn tx_received(&self) -> &Notify;
+    /// Takes the bundles to execute at the next tick of the pending block, in the order they were received.
+    fn take_bundles(&self) -> Vec<MempoolBundle>;
 }
 
 pub struct Mempool {
     backend: Arc<MadaraBackend>,
     l1_data_provider: Arc<dyn L1DataProvider>,
     inner: RwLock<MempoolInner>,
+    bundles: Mutex<Vec<MempoolBundle>>,
+    tx_received: Notify,
+    detect_chain_id_mismatch: bool,
 }
 
 impl Mempool {
     pub fn new(backend: Arc<MadaraBackend>, l1_data_provider: Arc<dyn L1DataProvider>) -> Self {
-        Mempool { backend, l1_data_provider, inner: Default::default() }
+        Mempool {
+            backend,
+            l1_data_provider,
+            inner: Default::default(),
+            bundles: Default::default(),
+            tx_received: Notify::new(),
+            detect_chain_id_mismatch: false,
+        }
+    }
+
+    /// Rejects the transactions signed for another well known chain id with [`Error::ChainIdMismatch`]. Each
+    /// transaction failing validation is validated again for every well known chain id, which multiplies the cost of
+    /// invalid transactions: this is a debugging aid, not meant for public nodes.
+    pub fn with_chain_id_mismatch_detection(mut self, detect_chain_id_mismatch: bool) -> Self {
+        self.detect_chain_id_mismatch = detect_chain_id_mismatch;
+        self
+    }
+
+    /// Adds transactions which must be included consecutively in one block, or not at all. They are validated when
+    /// the bundle is executed, as the transactions of a bundle usually depend on each other. Returns the hashes of the
+    /// transactions, and the receiver of the outcome of the bundle.
+    pub fn accept_bundle(
+        &self,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> Result<(Vec<Felt>, oneshot::Receiver<Result<u64, BundleRejection>>), Error> {
+        if transactions.is_empty() || transactions.len() > MAX_BUNDLE_SIZE {
+            return Err(Error::InvalidBundle(
+                format!("A bundle has between 1 and {MAX_BUNDLE_SIZE} transactions, got {}", transactions.len()).into(),
+            ));
+        }
+
+        let arrived_at = ArrivedAtTimestamp::now();
+        let mut tx_hashes = Vec::with_capacity(transactions.len());
+        let mut bundle = Vec::with_capacity(transactions.len());
+        for tx in transactions {
+            let (tx, converted_class) =
+                broadcasted_to_blockifier(tx, self.chain_id(), self.backend.chain_config().latest_protocol_version)?;
+            let hash = transaction_hash(&tx);
+            let Transaction::AccountTransaction(tx) = tx else {
+                return Err(Error::InvalidBundle("L1 handler transactions cannot be bundled".into()));
+            };
+            if is_only_query(&tx) {
+                return Err(Error::InvalidBundle("Query transactions cannot be bundled".into()));
+            }
+            tx_hashes.push(hash);
+            bundle.push(MempoolTransaction { tx, arrived_at, converted_class });
+        }
+
+        let mut bundles = self.bundles.lock().expect("Poisoned lock");
+        if bundles.len() >= MAX_PENDING_BUNDLES {
+            return Err(Error::BundleQueueFull);
+        }
+        let (reply, receiver) = oneshot::channel();
+        bundles.push(MempoolBundle { transactions: bundle, reply });
+        drop(bundles);
+        self.tx_received.notify_one();
+        Ok((tx_hashes, receiver))
     }
 
     fn accept_tx(&self, tx: Transaction, converted_class: Option<ConvertedClass>) -> Result<(), Error> {
@@ -122,8 +205,14 @@ impl Mempool {
                 .backend
                 .get_block_hash(&BlockId::Tag(BlockTag::Latest))?
                 .unwrap_or(/* genesis block's parent hash */ Felt::ZERO);
+            let block_n = self.backend.get_latest_block_n()?.map(|n| n + 1).unwrap_or(0);
             MadaraPendingBlockInfo::new(
-                make_pending_header(parent_block_hash, self.backend.chain_config(), self.l1_data_provider.as_ref()),
+                make_pending_header(
+                    block_n,
+                    parent_block_hash,
+                    self.backend.chain_config(),
+                    self.l1_data_provider.as_ref(),
+                ),
                 vec![],
             )
             .into()
@@ -146,7 +235,15 @@ impl Mempool {
         // Perform validations
         let exec_context = ExecutionContext::new_in_block(Arc::clone(&self.backend), &pending_block_info)?;
         let mut validator = exec_context.tx_validator();
-        let _ = validator.perform_validations(clone_account_tx(&tx), deploy_account_tx_hash.is_some());
+        let skip_validate = deploy_account_tx_hash.is_some();
+        if validator.perform_validations(clone_account_tx(&tx), skip_validate).is_err()
+            && !skip_validate
+            && self.detect_chain_id_mismatch
+        {
+            if let Some(signed) = self.signed_chain_id(&exec_context, &tx) {
+                return Err(Error::ChainIdMismatch { expected: self.backend.chain_config().chain_id.clone(), signed });
+            }
+        }
 
         if !is_only_query(&tx) {
             // Finally, add it to the nonce chain for the account nonce
@@ -154,11 +251,40 @@ impl Mempool {
             self.inner
                 .write()
                 .expect("Poisoned lock")
-                .insert_tx(MempoolTransaction { tx, arrived_at, converted_class }, force)?
+                .insert_tx(MempoolTransaction { tx, arrived_at, converted_class }, force)?;
+            self.tx_received.notify_one();
         }
 
         Ok(())
     }
+
+    /// Finds the chain a transaction that failed validation was signed for, among the well known chains. Transactions
+    /// do not carry their chain id, which is only part of the transaction hash signed by the account, so the
+    /// transaction is validated again with the hash it has on each of these chains.
+    fn signed_chain_id(&self, exec_context: &ExecutionContext, tx: &AccountTransaction) -> Option<ChainId> {
+        let chain_config = self.backend.chain_config();
+        let (api_tx, only_query) = match tx {
+            AccountTransaction::Declare(tx) => (ApiTransaction::Declare(tx.tx.clone()), tx.only_query()),
+            AccountTransaction::DeployAccount(tx) => (ApiTransaction::DeployAccount(tx.tx.clone()), tx.only_query),
+            AccountTransaction::Invoke(tx) => (ApiTransaction::Invoke(tx.tx.clone()), tx.only_query),
+        };
+        let transaction = mp_transactions::Transaction::from(api_tx);
+
+        well_known_chain_ids().into_iter().filter(|chain_id| chain_id != &chain_config.chain_id).find(|chain_id| {
+            let hash =
+                transaction.compute_hash(chain_id_to_felt(chain_id), chain_config.latest_protocol_version, only_query);
+            exec_context.tx_validator().perform_validations(with_tx_hash(tx, TransactionHash(hash)), false).is_ok()
+        })
+    }
+}
+
+/// Chains the transactions submitted to the wrong node are usually signed for.
+fn well_known_chain_ids() -> [ChainId; 4] {
+    [ChainId::Mainnet, ChainId::Sepolia, ChainId::IntegrationSepolia, ChainId::Other("MADARA_DEVNET".into())]
+}
+
+fn chain_id_to_felt(chain_id: &ChainId) -> Felt {
+    Felt::from_bytes_be_slice(format!("{}", chain_id).as_bytes())
 }
 
 pub fn transaction_hash(tx: &Transaction) -> Felt {
@@ -250,7 +376,15 @@ impl MempoolProvider for Mempool {
     }
 
     fn chain_id(&self) -> Felt {
-        Felt::from_bytes_be_slice(format!("{}", self.backend.chain_config().chain_id).as_bytes())
+        chain_id_to_felt(&self.backend.chain_config().chain_id)
+    }
+
+    fn tx_received(&self) -> &Notify {
+        &self.tx_received
+    }
+
+    fn take_bundles(&self) -> Vec<MempoolBundle> {
+        mem::take(&mut *self.bundles.lock().expect("Poisoned lock"))
     }
 }
 
@@ -288,25 +422,28 @@ pub(crate) fn tx_hash(tx: &AccountTransaction) -> TransactionHash {
 
 // AccountTransaction does not implement Clone :(
 pub(crate) fn clone_account_tx(tx: &AccountTransaction) -> AccountTransaction {
+    with_tx_hash(tx, tx_hash(tx))
+}
+
+/// Copy of the transaction, with another hash.
+fn with_tx_hash(tx: &AccountTransaction, tx_hash: TransactionHash) -> AccountTransaction {
     match tx {
         // Declare has a private field :(
         AccountTransaction::Declare(tx) => AccountTransaction::Declare(match tx.only_query() {
             // These should never fail
-            true => DeclareTransaction::new_for_query(tx.tx.clone(), tx.tx_hash, tx.class_info.clone())
+            true => DeclareTransaction::new_for_query(tx.tx.clone(), tx_hash, tx.class_info.clone())
                 .expect("Making blockifier transaction for query"),
-            false => DeclareTransaction::new(tx.tx.clone(), tx.tx_hash, tx.class_info.clone())
+            false => DeclareTransaction::new(tx.tx.clone(), tx_hash, tx.class_info.clone())
                 .expect("Making blockifier transaction"),
         }),
         AccountTransaction::DeployAccount(tx) => AccountTransaction::DeployAccount(DeployAccountTransaction {
             tx: tx.tx.clone(),
-            tx_hash: tx.tx_hash,
+            tx_hash,
             contract_address: tx.contract_address,
             only_query: tx.only_query,
         }),
-        AccountTransaction::Invoke(tx) => AccountTransaction::Invoke(InvokeTransaction {
-            tx: tx.tx.clone(),
-            tx_hash: tx.tx_hash,
-            only_query: tx.only_query,
-        }),
+        AccountTransaction::Invoke(tx) => {
+            AccountTransaction::Invoke(InvokeTransaction { tx: tx.tx.clone(), tx_hash, only_query: tx.only_query })
+        }
     }
 }
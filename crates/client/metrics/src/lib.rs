@@ -1,3 +1,5 @@
+mod profiling;
+
 use std::net::{Ipv4Addr, SocketAddr};
 
 use anyhow::Context;
@@ -48,9 +50,15 @@ enum Error {
     Prometheus(#[from] prometheus::Error),
     Hyper(#[from] hyper::Error),
     HyperHttp(#[from] hyper::http::Error),
+    Profiling(#[from] anyhow::Error),
 }
 
-async fn endpoint(req: Request<Body>, registry: Registry) -> Result<Response<Body>, Error> {
+async fn endpoint(req: Request<Body>, registry: Registry, profiling: bool) -> Result<Response<Body>, Error> {
+    if profiling {
+        if let Some(res) = profiling::endpoint(&req).await {
+            return Ok(res?);
+        }
+    }
     if req.uri().path() == "/metrics" {
         let metric_families = registry.gather();
         let mut buffer = vec![];
@@ -73,6 +81,7 @@ pub struct MetricsService {
     no_prometheus: bool,
     prometheus_external: bool,
     prometheus_port: u16,
+    profiling: bool,
     registry: MetricsRegistry,
     stop_handle: StopHandle,
 }
@@ -83,11 +92,18 @@ impl MetricsService {
             no_prometheus,
             prometheus_external,
             prometheus_port,
+            profiling: false,
             registry: MetricsRegistry(if no_prometheus { None } else { Some(Default::default()) }),
             stop_handle: Default::default(),
         })
     }
 
+    /// Also serve CPU profiles, and heap statistics with the `jemalloc` feature, under `/debug/pprof`.
+    pub fn with_profiling(mut self, profiling: bool) -> Self {
+        self.profiling = profiling;
+        self
+    }
+
     pub fn registry(&self) -> &MetricsRegistry {
         &self.registry
     }
@@ -108,13 +124,14 @@ impl Service for MetricsService {
         let addr = SocketAddr::new(listen_addr.into(), self.prometheus_port);
 
         let registry = self.registry.clone();
+        let profiling = self.profiling;
         let service = make_service_fn(move |_| {
             let registry = registry.clone();
             async move {
                 Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
                     let registry = registry.clone();
                     async move {
-                        match endpoint(req, registry.0.expect("Registry should not be none").clone()).await {
+                        match endpoint(req, registry.0.expect("Registry should not be none").clone(), profiling).await {
                             Ok(res) => Ok::<_, Error>(res),
                             Err(err) => {
                                 log::error!("Error when handling prometheus request: {}", err);
@@ -145,4 +162,12 @@ impl Service for MetricsService {
 
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "prometheus"
+    }
+
+    fn restartable(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,153 @@
+//! On demand profiling, served next to the metrics when enabled:
+//! - `/debug/pprof/profile?seconds=30&frequency=99`: CPU profile in the pprof protobuf format, to be opened with
+//!   `go tool pprof`.
+//! - `/debug/pprof/flamegraph?seconds=30&frequency=99`: the same profile, rendered as an SVG flamegraph.
+//! - `/debug/pprof/heap`: heap statistics of the allocator, with the `jemalloc` feature.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::Context;
+use hyper::{Body, Request, Response, StatusCode};
+use pprof::protos::Message;
+
+const DEFAULT_SECONDS: u64 = 30;
+const MAX_SECONDS: u64 = 300;
+const DEFAULT_FREQUENCY: i32 = 99;
+const MAX_FREQUENCY: i32 = 1000;
+
+/// Only one CPU profile can be taken at a time.
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+enum CpuProfileFormat {
+    Pprof,
+    Flamegraph,
+}
+
+pub(crate) async fn endpoint(req: &Request<Body>) -> Option<anyhow::Result<Response<Body>>> {
+    let res = match req.uri().path() {
+        "/debug/pprof/profile" => cpu_profile(req, CpuProfileFormat::Pprof).await,
+        "/debug/pprof/flamegraph" => cpu_profile(req, CpuProfileFormat::Flamegraph).await,
+        #[cfg(feature = "jemalloc")]
+        "/debug/pprof/heap" => heap_stats(),
+        _ => return None,
+    };
+    Some(res)
+}
+
+fn query_param<T: std::str::FromStr>(req: &Request<Body>, name: &str) -> anyhow::Result<Option<T>> {
+    let Some(query) = req.uri().query() else { return Ok(None) };
+    query
+        .split('&')
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.parse().map_err(|_| anyhow::anyhow!("Invalid query parameter {name}: {value}")))
+        .transpose()
+}
+
+fn text_response(status: StatusCode, body: impl Into<Body>) -> anyhow::Result<Response<Body>> {
+    Ok(Response::builder().status(status).header("Content-Type", "text/plain").body(body.into())?)
+}
+
+async fn cpu_profile(req: &Request<Body>, format: CpuProfileFormat) -> anyhow::Result<Response<Body>> {
+    let (seconds, frequency) = match (query_param(req, "seconds"), query_param(req, "frequency")) {
+        (Ok(seconds), Ok(frequency)) => (
+            seconds.unwrap_or(DEFAULT_SECONDS).clamp(1, MAX_SECONDS),
+            frequency.unwrap_or(DEFAULT_FREQUENCY).clamp(1, MAX_FREQUENCY),
+        ),
+        (Err(err), _) | (_, Err(err)) => return text_response(StatusCode::BAD_REQUEST, format!("{err:#}")),
+    };
+
+    if PROFILING.swap(true, Ordering::SeqCst) {
+        return text_response(StatusCode::CONFLICT, "A CPU profile is already being taken");
+    }
+    log::info!("🔬 Taking a CPU profile for {seconds}s");
+    // The profiler samples every thread of the process while this one sleeps.
+    let res = tokio::task::spawn_blocking(move || {
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(frequency)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .context("Starting the profiler")?;
+        std::thread::sleep(Duration::from_secs(seconds));
+        let report = guard.report().build().context("Building the profile")?;
+
+        let mut body = Vec::new();
+        match format {
+            CpuProfileFormat::Pprof => {
+                report.pprof().context("Converting the profile")?.encode(&mut body).context("Encoding the profile")?
+            }
+            CpuProfileFormat::Flamegraph => report.flamegraph(&mut body).context("Rendering the flamegraph")?,
+        }
+        anyhow::Ok(body)
+    })
+    .await;
+    PROFILING.store(false, Ordering::SeqCst);
+    let body = res.context("Profiling task")??;
+
+    let content_type = match format {
+        CpuProfileFormat::Pprof => "application/octet-stream",
+        CpuProfileFormat::Flamegraph => "image/svg+xml",
+    };
+    Ok(Response::builder().status(StatusCode::OK).header("Content-Type", content_type).body(Body::from(body))?)
+}
+
+/// Allocator statistics, in bytes. Only available when the node uses jemalloc as its global allocator.
+#[cfg(feature = "jemalloc")]
+fn heap_stats() -> anyhow::Result<Response<Body>> {
+    use tikv_jemalloc_ctl::{epoch, stats};
+
+    // The statistics are cached, and only refreshed when the epoch is advanced.
+    epoch::advance().context("Refreshing the allocator statistics")?;
+    let stats = [
+        ("allocated", stats::allocated::read()),
+        ("active", stats::active::read()),
+        ("metadata", stats::metadata::read()),
+        ("resident", stats::resident::read()),
+        ("mapped", stats::mapped::read()),
+        ("retained", stats::retained::read()),
+    ];
+    let mut body = String::new();
+    for (name, value) in stats {
+        let value = value.with_context(|| format!("Reading the {name} allocator statistic"))?;
+        body.push_str(&format!("{name}: {value}\n"));
+    }
+    text_response(StatusCode::OK, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_other_routes_are_not_served() {
+        assert!(endpoint(&request("/metrics")).await.is_none());
+        assert!(endpoint(&request("/debug/pprof")).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_profiling_routes_are_gated() {
+        let registry = prometheus::Registry::new();
+        let res = crate::endpoint(request("/debug/pprof/profile?seconds=1"), registry.clone(), false).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = crate::endpoint(request("/debug/pprof/profile?seconds=x"), registry, true).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[cfg(feature = "jemalloc")]
+    #[tokio::test]
+    async fn test_heap_stats() {
+        let res = endpoint(&request("/debug/pprof/heap")).await.unwrap().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[cfg(not(feature = "jemalloc"))]
+    #[tokio::test]
+    async fn test_heap_stats_need_jemalloc() {
+        assert!(endpoint(&request("/debug/pprof/heap")).await.is_none());
+    }
+}
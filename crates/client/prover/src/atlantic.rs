@@ -0,0 +1,180 @@
+//! Atlantic adapter, talking to the proving API of Herodotus.
+//!
+//! PIEs are submitted as Atlantic queries, which are proven and then verified on L1. Atlantic returns the fact of the
+//! program output, and stores the proof separately: it is only downloaded when its URL is configured.
+
+use crate::{parse_fact, ProverClient, ProverJobStatus};
+use anyhow::Context;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use url::Url;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitResponse {
+    atlantic_query_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryResponse {
+    atlantic_query: AtlanticQuery,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AtlanticQuery {
+    status: String,
+    #[serde(default)]
+    program_fact_hash: Option<String>,
+    #[serde(default)]
+    error_reason: Option<String>,
+}
+
+pub struct AtlanticClient {
+    client: reqwest::Client,
+    url: Url,
+    api_key: String,
+    proof_url: Option<String>,
+}
+
+impl AtlanticClient {
+    pub fn new(url: Url, api_key: String, proof_url: Option<String>) -> Self {
+        Self { client: reqwest::Client::new(), url, api_key, proof_url }
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/{path}", self.url.as_str().trim_end_matches('/'))
+    }
+
+    async fn download_proof(&self, proof_url: &str, job_id: &str) -> anyhow::Result<Vec<u8>> {
+        let url = proof_url.replace("{job_id}", job_id);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Downloading the proof from {url}"))?;
+        Ok(response.bytes().await.with_context(|| format!("Downloading the proof from {url}"))?.to_vec())
+    }
+}
+
+#[async_trait::async_trait]
+impl ProverClient for AtlanticClient {
+    fn name(&self) -> &'static str {
+        "Atlantic"
+    }
+
+    async fn submit(&self, block_n: u64, pie: Vec<u8>) -> anyhow::Result<String> {
+        let form = Form::new()
+            .part("pieFile", Part::bytes(pie).file_name(format!("{block_n}.zip")).mime_str("application/zip")?)
+            .text("layout", "dynamic")
+            .text("cairoVm", "rust")
+            .text("cairoVersion", "cairo0")
+            .text("result", "PROOF_VERIFICATION")
+            .text("externalId", block_n.to_string());
+        let response: SubmitResponse = self
+            .client
+            .post(self.endpoint("atlantic-query"))
+            .query(&[("apiKey", &self.api_key)])
+            .multipart(form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Submitting the Atlantic query")?
+            .json()
+            .await
+            .context("Parsing the Atlantic query id")?;
+        Ok(response.atlantic_query_id)
+    }
+
+    async fn status(&self, job_id: &str) -> anyhow::Result<ProverJobStatus> {
+        let response: QueryResponse = self
+            .client
+            .get(self.endpoint(&format!("atlantic-query/{job_id}")))
+            .query(&[("apiKey", &self.api_key)])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Getting the Atlantic query {job_id}"))?
+            .json()
+            .await
+            .with_context(|| format!("Parsing the Atlantic query {job_id}"))?;
+
+        let query = response.atlantic_query;
+        Ok(match query.status.as_str() {
+            "DONE" => {
+                let fact = query.program_fact_hash.as_deref().map(parse_fact).transpose()?;
+                let proof = match &self.proof_url {
+                    Some(proof_url) => Some(self.download_proof(proof_url, job_id).await?),
+                    None => None,
+                };
+                ProverJobStatus::Done { fact, proof }
+            }
+            "FAILED" => ProverJobStatus::Failed(query.error_reason.unwrap_or_else(|| "Unknown error".into())),
+            _ => ProverJobStatus::InProgress,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn submit_returns_the_query_id() {
+        let server = MockServer::start();
+        let submit = server.mock(|when, then| {
+            when.method("POST")
+                .path("/atlantic-query")
+                .query_param("apiKey", "key")
+                .body_contains("PROOF_VERIFICATION");
+            then.status(201).json_body(json!({ "atlanticQueryId": "01JD" }));
+        });
+
+        let client = AtlanticClient::new(server.base_url().parse().unwrap(), "key".into(), None);
+        assert_eq!(client.submit(7, b"pie".to_vec()).await.unwrap(), "01JD");
+        submit.assert();
+    }
+
+    #[tokio::test]
+    async fn done_query_returns_the_fact_and_proof() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method("GET").path("/atlantic-query/01JD").query_param("apiKey", "key");
+            then.status(200).json_body(json!({
+                "atlanticQuery": { "id": "01JD", "status": "DONE", "programFactHash": "0x2a" }
+            }));
+        });
+        server.mock(|when, then| {
+            when.method("GET").path("/proofs/01JD/proof.json");
+            then.status(200).body("{}");
+        });
+
+        let proof_url = format!("{}/proofs/{{job_id}}/proof.json", server.base_url());
+        let client = AtlanticClient::new(server.base_url().parse().unwrap(), "key".into(), Some(proof_url));
+        let mut fact = [0u8; 32];
+        fact[31] = 0x2a;
+        assert_eq!(
+            client.status("01JD").await.unwrap(),
+            ProverJobStatus::Done { fact: Some(fact), proof: Some(b"{}".to_vec()) }
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_query_returns_the_error() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method("GET").path("/atlantic-query/01JD");
+            then.status(200).json_body(json!({
+                "atlanticQuery": { "status": "FAILED", "errorReason": "Trace too large" }
+            }));
+        });
+
+        let client = AtlanticClient::new(server.base_url().parse().unwrap(), "key".into(), None);
+        assert_eq!(client.status("01JD").await.unwrap(), ProverJobStatus::Failed("Trace too large".into()));
+    }
+}
@@ -0,0 +1,111 @@
+//! Proving of the blocks by an external prover.
+//!
+//! The Cairo PIE of each block, generated by the SNOS ExEx, is submitted to a proving service such as SHARP or
+//! Atlantic. The jobs are tracked in the database until the prover returns the proof, or the fact of the proof once
+//! it is verified on L1.
+
+use anyhow::Context;
+use std::sync::Arc;
+use url::Url;
+
+pub mod atlantic;
+pub mod sharp;
+pub mod worker;
+
+/// Status of a proving job on the prover.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProverJobStatus {
+    InProgress,
+    /// The block is proven. Provers only return the fact or the proof when they have them.
+    Done {
+        fact: Option<[u8; 32]>,
+        proof: Option<Vec<u8>>,
+    },
+    Failed(String),
+}
+
+/// A client to a proving service.
+#[async_trait::async_trait]
+pub trait ProverClient: Send + Sync {
+    /// Human readable name of the prover, for displaying to the console.
+    fn name(&self) -> &'static str;
+
+    /// Submits the Cairo PIE of a block, as a zip archive. Returns the id of the proving job.
+    async fn submit(&self, block_n: u64, pie: Vec<u8>) -> anyhow::Result<String>;
+
+    async fn status(&self, job_id: &str) -> anyhow::Result<ProverJobStatus>;
+}
+
+/// Supported proving services.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProverKind {
+    /// The SHARP gateway of StarkWare.
+    Sharp,
+    /// The Atlantic proving service of Herodotus.
+    Atlantic,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProverClientConfig {
+    pub kind: ProverKind,
+    pub url: Url,
+    pub api_key: Option<String>,
+    /// URL of the proofs, for the provers that store them separately. `{job_id}` is replaced with the id of the job.
+    pub proof_url: Option<String>,
+}
+
+pub fn create_prover_client(config: &ProverClientConfig) -> anyhow::Result<Arc<dyn ProverClient>> {
+    Ok(match config.kind {
+        ProverKind::Sharp => Arc::new(sharp::SharpClient::new(config.url.clone())),
+        ProverKind::Atlantic => {
+            let api_key = config.api_key.clone().context("Proving with Atlantic requires `--prover-api-key`")?;
+            Arc::new(atlantic::AtlanticClient::new(config.url.clone(), api_key, config.proof_url.clone()))
+        }
+    })
+}
+
+/// Parses a fact returned by a prover, an hex string of at most 32 bytes.
+pub(crate) fn parse_fact(fact: &str) -> anyhow::Result<[u8; 32]> {
+    let hex = fact.strip_prefix("0x").unwrap_or(fact);
+    anyhow::ensure!(hex.is_ascii() && !hex.is_empty() && hex.len() <= 64, "Invalid fact {fact:?}");
+    let hex = format!("{hex:0>64}");
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).with_context(|| format!("Invalid fact {fact:?}"))?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("0x01", 1)]
+    #[case("ff", 0xff)]
+    fn fact_is_left_padded(#[case] fact: &str, #[case] last_byte: u8) {
+        let bytes = parse_fact(fact).unwrap();
+        assert!(bytes[..31].iter().all(|b| *b == 0));
+        assert_eq!(bytes[31], last_byte);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("0xzz")]
+    #[case(&"1".repeat(65))]
+    fn invalid_fact_fails(#[case] fact: &str) {
+        assert!(parse_fact(fact).is_err());
+    }
+
+    #[rstest]
+    fn atlantic_requires_an_api_key() {
+        let config = ProverClientConfig {
+            kind: ProverKind::Atlantic,
+            url: "http://localhost".parse().unwrap(),
+            api_key: None,
+            proof_url: None,
+        };
+        assert!(create_prover_client(&config).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! SHARP adapter, talking to the gateway used by the `cairo-sharp` client of cairo-lang.
+//!
+//! SHARP registers the fact of the proofs in the L1 verifier, and returns neither the proof nor the fact: jobs are
+//! done once the fact is on-chain.
+
+use crate::{ProverClient, ProverJobStatus};
+use anyhow::Context;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+
+#[derive(Deserialize)]
+struct AddJobResponse {
+    cairo_job_key: String,
+}
+
+#[derive(Deserialize)]
+struct GetStatusResponse {
+    status: String,
+    #[serde(default)]
+    error_log: Option<String>,
+}
+
+pub struct SharpClient {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl SharpClient {
+    pub fn new(url: Url) -> Self {
+        Self { client: reqwest::Client::new(), url }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, action: &str, request: serde_json::Value) -> anyhow::Result<T> {
+        self.client
+            .post(self.url.clone())
+            .json(&json!({ "action": action, "request": request }))
+            .send()
+            .await
+            .with_context(|| format!("Calling {action}"))?
+            .error_for_status()
+            .with_context(|| format!("Calling {action}"))?
+            .json()
+            .await
+            .with_context(|| format!("Parsing the {action} response"))
+    }
+}
+
+#[async_trait::async_trait]
+impl ProverClient for SharpClient {
+    fn name(&self) -> &'static str {
+        "SHARP"
+    }
+
+    async fn submit(&self, _block_n: u64, pie: Vec<u8>) -> anyhow::Result<String> {
+        let response: AddJobResponse = self.call("add_job", json!({ "cairo_pie": BASE64.encode(pie) })).await?;
+        Ok(response.cairo_job_key)
+    }
+
+    async fn status(&self, job_id: &str) -> anyhow::Result<ProverJobStatus> {
+        let response: GetStatusResponse = self.call("get_status", json!({ "cairo_job_key": job_id })).await?;
+        Ok(match response.status.as_str() {
+            "ONCHAIN" => ProverJobStatus::Done { fact: None, proof: None },
+            // Proven jobs are only done once their fact is registered on L1.
+            "NOT_CREATED" | "IN_PROGRESS" | "PROCESSED" => ProverJobStatus::InProgress,
+            status => ProverJobStatus::Failed(match response.error_log {
+                Some(error_log) => format!("{status}: {error_log}"),
+                None => status.to_string(),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use httpmock::MockServer;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(json!({ "status": "IN_PROGRESS" }), ProverJobStatus::InProgress)]
+    #[case(json!({ "status": "PROCESSED" }), ProverJobStatus::InProgress)]
+    #[case(json!({ "status": "ONCHAIN" }), ProverJobStatus::Done { fact: None, proof: None })]
+    #[case(json!({ "status": "INVALID", "error_log": "bad pie" }), ProverJobStatus::Failed("INVALID: bad pie".into()))]
+    #[case(json!({ "status": "UNKNOWN" }), ProverJobStatus::Failed("UNKNOWN".into()))]
+    #[tokio::test]
+    async fn status_is_mapped(#[case] response: serde_json::Value, #[case] expected: ProverJobStatus) {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method("POST")
+                .path("/")
+                .json_body(json!({ "action": "get_status", "request": { "cairo_job_key": "key" } }));
+            then.status(200).json_body(response);
+        });
+
+        let client = SharpClient::new(server.base_url().parse().unwrap());
+        assert_eq!(client.status("key").await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn submit_sends_the_encoded_pie() {
+        let server = MockServer::start();
+        let add_job = server.mock(|when, then| {
+            when.method("POST")
+                .path("/")
+                .json_body(json!({ "action": "add_job", "request": { "cairo_pie": BASE64.encode(b"pie") } }));
+            then.status(200).json_body(json!({ "code": "JOB_RECEIVED_SUCCESSFULLY", "cairo_job_key": "key" }));
+        });
+
+        let client = SharpClient::new(server.base_url().parse().unwrap());
+        assert_eq!(client.submit(1, b"pie".to_vec()).await.unwrap(), "key");
+        add_job.assert();
+    }
+}
@@ -0,0 +1,353 @@
+//! Submission of the PIEs to the prover, in block order, and tracking of the proving jobs.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use mc_db::proving_db::{ProvingJob, ProvingJobStatus};
+use mc_db::MadaraBackend;
+use mc_metrics::{
+    exponential_buckets, Counter, Gauge, Histogram, HistogramOpts, MetricsRegistry, PrometheusError, F64, U64,
+};
+use mp_utils::wait_or_graceful_shutdown;
+
+use crate::{ProverClient, ProverJobStatus};
+
+#[derive(Debug, Clone)]
+pub struct ProvingConfig {
+    /// Directory of the Cairo PIEs, `{block_n}.zip` being the PIE of block `block_n`. The proofs returned by the
+    /// prover are stored next to them, as `{block_n}.proof.json`.
+    pub pie_dir: PathBuf,
+    /// Interval at which the jobs are polled, and new PIEs looked for.
+    pub poll_interval: Duration,
+    pub max_concurrent_jobs: usize,
+    /// Number of submissions of a block before giving up on it.
+    pub max_attempts: u32,
+    /// First block to prove, when no block was submitted yet.
+    pub first_block: u64,
+}
+
+impl ProvingConfig {
+    pub fn pie_path(&self, block_n: u64) -> PathBuf {
+        self.pie_dir.join(format!("{block_n}.zip"))
+    }
+
+    pub fn proof_path(&self, block_n: u64) -> PathBuf {
+        self.pie_dir.join(format!("{block_n}.proof.json"))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProverMetrics {
+    pub jobs_submitted: Counter<U64>,
+    pub jobs_failed: Counter<U64>,
+    pub jobs_in_progress: Gauge<F64>,
+    pub last_proven_block: Gauge<F64>,
+    pub proving_duration: Histogram,
+}
+
+impl ProverMetrics {
+    pub fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            jobs_submitted: registry
+                .register(Counter::new("madara_prover_jobs_submitted", "Number of PIEs submitted to the prover")?)?,
+            jobs_failed: registry
+                .register(Counter::new("madara_prover_jobs_failed", "Number of proving jobs that failed")?)?,
+            jobs_in_progress: registry
+                .register(Gauge::new("madara_prover_jobs_in_progress", "Number of blocks being proven")?)?,
+            last_proven_block: registry
+                .register(Gauge::new("madara_prover_last_proven_block", "Number of the last proven block")?)?,
+            proving_duration: registry.register(Histogram::with_opts(
+                HistogramOpts::new("madara_prover_duration_seconds", "Time [s] taken by the prover to prove a block")
+                    .buckets(exponential_buckets(60.0, 2.0, 10)?),
+            )?)?,
+        })
+    }
+}
+
+pub struct ProvingWorker {
+    backend: Arc<MadaraBackend>,
+    client: Arc<dyn ProverClient>,
+    config: ProvingConfig,
+    metrics: ProverMetrics,
+    in_progress: BTreeMap<u64, ProvingJob>,
+    next_block: u64,
+}
+
+impl ProvingWorker {
+    /// Resumes the jobs left in progress by the previous run.
+    pub fn new(
+        backend: Arc<MadaraBackend>,
+        client: Arc<dyn ProverClient>,
+        config: ProvingConfig,
+        metrics: ProverMetrics,
+    ) -> anyhow::Result<Self> {
+        let in_progress: BTreeMap<_, _> = backend
+            .get_proving_jobs(0, usize::MAX)
+            .context("Getting the proving jobs")?
+            .into_iter()
+            .filter(|(_, job)| job.status == ProvingJobStatus::InProgress)
+            .collect();
+        let next_block = backend
+            .get_last_proving_job()
+            .context("Getting the last proving job")?
+            .map_or(config.first_block, |(block_n, _)| block_n + 1);
+        Ok(Self { backend, client, config, metrics, in_progress, next_block })
+    }
+
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.config.pie_dir)
+            .await
+            .with_context(|| format!("Creating the PIE directory {}", self.config.pie_dir.display()))?;
+        log::info!(
+            "🔏 Proving the blocks from #{} with {}, using the PIEs of {}",
+            self.next_block,
+            self.client.name(),
+            self.config.pie_dir.display()
+        );
+
+        let mut interval = tokio::time::interval(self.config.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+            self.step().await?;
+        }
+        Ok(())
+    }
+
+    /// Polls the jobs in progress, then submits the next PIEs. The errors of a job are logged, and the job is handled
+    /// again at the next step.
+    pub async fn step(&mut self) -> anyhow::Result<()> {
+        for (block_n, job) in self.in_progress.clone() {
+            if let Err(err) = self.poll_job(block_n, job).await {
+                log::error!("Failed to handle the proving job of block #{block_n}: {err:#}");
+            }
+        }
+
+        while self.in_progress.len() < self.config.max_concurrent_jobs {
+            let block_n = self.next_block;
+            match tokio::fs::try_exists(self.config.pie_path(block_n)).await {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    log::error!("Failed to look for the PIE of block #{block_n}: {err:#}");
+                    break;
+                }
+            }
+            match self.submit(block_n, 1).await {
+                Ok(job) => {
+                    log::debug!("Block #{block_n} submitted for proving as job {}", job.job_id);
+                    self.in_progress.insert(block_n, job);
+                    self.next_block += 1;
+                }
+                Err(err) => {
+                    log::warn!("Failed to submit block #{block_n} for proving: {err:#}");
+                    break;
+                }
+            }
+        }
+
+        self.metrics.jobs_in_progress.set(self.in_progress.len() as f64);
+        Ok(())
+    }
+
+    async fn poll_job(&mut self, block_n: u64, job: ProvingJob) -> anyhow::Result<()> {
+        let status = match self.client.status(&job.job_id).await {
+            Ok(status) => status,
+            Err(err) => {
+                log::warn!("Failed to get the status of the proving job of block #{block_n}: {err:#}");
+                return Ok(());
+            }
+        };
+
+        match status {
+            ProverJobStatus::InProgress => {}
+            ProverJobStatus::Done { fact, proof } => {
+                if let Some(proof) = proof {
+                    let path = self.config.proof_path(block_n);
+                    tokio::fs::write(&path, proof)
+                        .await
+                        .with_context(|| format!("Writing the proof of block #{block_n} to {}", path.display()))?;
+                }
+                self.finish(block_n, ProvingJob { status: ProvingJobStatus::Proven { fact }, ..job.clone() })?;
+                self.metrics.proving_duration.observe(unix_now().saturating_sub(job.submitted_at) as f64);
+                if self.metrics.last_proven_block.get() < block_n as f64 {
+                    self.metrics.last_proven_block.set(block_n as f64);
+                }
+                log::info!("🔏 Block #{block_n} proven");
+            }
+            ProverJobStatus::Failed(error) => {
+                self.metrics.jobs_failed.inc();
+                if job.attempts < self.config.max_attempts {
+                    log::warn!("Proving of block #{block_n} failed, submitting it again: {error}");
+                    // The attempt is recorded before submitting, so that a block whose submission keeps failing is
+                    // eventually given up on.
+                    let job = ProvingJob { attempts: job.attempts + 1, ..job };
+                    self.backend.write_proving_job(block_n, &job).context("Writing the proving job")?;
+                    self.in_progress.insert(block_n, job.clone());
+                    match self.submit(block_n, job.attempts).await {
+                        Ok(job) => {
+                            self.in_progress.insert(block_n, job);
+                        }
+                        Err(err) => log::warn!("Failed to submit block #{block_n} for proving: {err:#}"),
+                    }
+                } else {
+                    log::error!("Proving of block #{block_n} failed {} times, giving up: {error}", job.attempts);
+                    self.finish(block_n, ProvingJob { status: ProvingJobStatus::Failed { error }, ..job })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn submit(&self, block_n: u64, attempts: u32) -> anyhow::Result<ProvingJob> {
+        let path = self.config.pie_path(block_n);
+        let pie = tokio::fs::read(&path).await.with_context(|| format!("Reading the PIE {}", path.display()))?;
+        let job_id = self.client.submit(block_n, pie).await?;
+        let job = ProvingJob { job_id, status: ProvingJobStatus::InProgress, submitted_at: unix_now(), attempts };
+        self.backend.write_proving_job(block_n, &job).context("Writing the proving job")?;
+        self.metrics.jobs_submitted.inc();
+        Ok(job)
+    }
+
+    fn finish(&mut self, block_n: u64, job: ProvingJob) -> anyhow::Result<()> {
+        self.backend.write_proving_job(block_n, &job).context("Writing the proving job")?;
+        self.in_progress.remove(&block_n);
+        Ok(())
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use std::sync::Mutex;
+
+    /// Fails the first job of every block, and proves the others on their first poll.
+    #[derive(Default)]
+    struct MockProver {
+        submitted: Mutex<Vec<u64>>,
+        /// Reject the submissions of the blocks that were already submitted.
+        reject_resubmissions: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl ProverClient for MockProver {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        async fn submit(&self, block_n: u64, _pie: Vec<u8>) -> anyhow::Result<String> {
+            let mut submitted = self.submitted.lock().unwrap();
+            let attempt = submitted.iter().filter(|n| **n == block_n).count();
+            if attempt > 0 && self.reject_resubmissions {
+                anyhow::bail!("Prover unavailable");
+            }
+            submitted.push(block_n);
+            Ok(format!("{block_n}-{attempt}"))
+        }
+
+        async fn status(&self, job_id: &str) -> anyhow::Result<ProverJobStatus> {
+            if job_id.ends_with("-0") {
+                Ok(ProverJobStatus::Failed("out of memory".into()))
+            } else {
+                Ok(ProverJobStatus::Done { fact: Some([1; 32]), proof: Some(b"proof".to_vec()) })
+            }
+        }
+    }
+
+    fn setup(
+        n_blocks: u64,
+        max_concurrent_jobs: usize,
+        client: Arc<MockProver>,
+    ) -> (tempfile::TempDir, Arc<MadaraBackend>, ProvingConfig, ProvingWorker) {
+        let pie_dir = tempfile::tempdir().unwrap();
+        for block_n in 0..n_blocks {
+            std::fs::write(pie_dir.path().join(format!("{block_n}.zip")), b"pie").unwrap();
+        }
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let config = ProvingConfig {
+            pie_dir: pie_dir.path().into(),
+            poll_interval: Duration::from_secs(1),
+            max_concurrent_jobs,
+            max_attempts: 2,
+            first_block: 0,
+        };
+        let metrics = ProverMetrics::register(&MetricsRegistry::dummy()).unwrap();
+        let worker = ProvingWorker::new(Arc::clone(&backend), client, config.clone(), metrics).unwrap();
+        (pie_dir, backend, config, worker)
+    }
+
+    #[tokio::test]
+    async fn blocks_are_proven_in_order() {
+        let client = Arc::new(MockProver::default());
+        let (_pie_dir, backend, config, mut worker) = setup(3, 2, client.clone());
+
+        worker.step().await.unwrap();
+        assert_eq!(*client.submitted.lock().unwrap(), [0, 1]);
+        assert_eq!(backend.get_proving_job(1).unwrap().unwrap().status, ProvingJobStatus::InProgress);
+
+        // The failed jobs are submitted again, which leaves no room for block 2.
+        worker.step().await.unwrap();
+        assert_eq!(*client.submitted.lock().unwrap(), [0, 1, 0, 1]);
+        assert_eq!(backend.get_proving_job(0).unwrap().unwrap().attempts, 2);
+
+        worker.step().await.unwrap();
+        let job = backend.get_proving_job(0).unwrap().unwrap();
+        assert_eq!(job.status, ProvingJobStatus::Proven { fact: Some([1; 32]) });
+        assert_eq!(std::fs::read(config.proof_path(0)).unwrap(), b"proof");
+        assert_eq!(*client.submitted.lock().unwrap(), [0, 1, 0, 1, 2]);
+
+        // A restarted worker resumes after the last submitted block.
+        let metrics = ProverMetrics::register(&MetricsRegistry::dummy()).unwrap();
+        let worker = ProvingWorker::new(Arc::clone(&backend), client, config, metrics).unwrap();
+        assert_eq!(worker.next_block, 3);
+        assert_eq!(worker.in_progress.keys().collect::<Vec<_>>(), [&2]);
+    }
+
+    #[tokio::test]
+    async fn failed_resubmissions_count_as_attempts() {
+        let client = Arc::new(MockProver { reject_resubmissions: true, ..Default::default() });
+        let (_pie_dir, backend, _config, mut worker) = setup(1, 1, client.clone());
+
+        worker.step().await.unwrap();
+        worker.step().await.unwrap();
+        let job = backend.get_proving_job(0).unwrap().unwrap();
+        assert_eq!(job.attempts, 2);
+        assert_eq!(job.status, ProvingJobStatus::InProgress);
+
+        // The job still failed, and the block has been submitted as many times as allowed.
+        worker.step().await.unwrap();
+        let job = backend.get_proving_job(0).unwrap().unwrap();
+        assert_eq!(job.attempts, 2);
+        assert!(matches!(job.status, ProvingJobStatus::Failed { .. }));
+        assert!(worker.in_progress.is_empty());
+        assert_eq!(*client.submitted.lock().unwrap(), [0]);
+    }
+
+    #[tokio::test]
+    async fn io_errors_do_not_stop_the_worker() {
+        let client = Arc::new(MockProver::default());
+        let (_pie_dir, backend, config, mut worker) = setup(1, 1, client.clone());
+        // The proof of the second job can't be written over a directory.
+        std::fs::create_dir(config.proof_path(0)).unwrap();
+
+        worker.step().await.unwrap();
+        worker.step().await.unwrap();
+        worker.step().await.unwrap();
+        assert_eq!(backend.get_proving_job(0).unwrap().unwrap().status, ProvingJobStatus::InProgress);
+        assert_eq!(worker.in_progress.keys().collect::<Vec<_>>(), [&0]);
+
+        std::fs::remove_dir(config.proof_path(0)).unwrap();
+        worker.step().await.unwrap();
+        assert_eq!(
+            backend.get_proving_job(0).unwrap().unwrap().status,
+            ProvingJobStatus::Proven { fact: Some([1; 32]) }
+        );
+    }
+}
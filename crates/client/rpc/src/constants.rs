@@ -2,3 +2,7 @@
 pub const MAX_EVENTS_KEYS: usize = 100;
 /// Maximum number of events that can be fetched in a single chunk for the `get_events` RPC.
 pub const MAX_EVENTS_CHUNK_SIZE: usize = 1000;
+/// Maximum number of storage keys that can be watched by a single storage subscription.
+pub const MAX_STORAGE_KEYS: usize = 100;
+/// Maximum number of classes that can be fetched in a single `madara_getClasses` call.
+pub const MAX_CLASSES_PER_REQUEST: usize = 100;
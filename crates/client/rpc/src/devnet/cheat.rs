@@ -0,0 +1,71 @@
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use starknet_api::core::{ClassHash, ContractAddress, Nonce, PatriciaKey};
+use starknet_api::state::StorageKey;
+use starknet_types_core::felt::Felt;
+
+use super::Devnet;
+
+fn contract_address(address: Felt) -> StarknetRpcResult<ContractAddress> {
+    address
+        .try_into()
+        .map_err(|_| StarknetRpcApiError::ErrUnexpectedError { data: format!("Invalid contract address {address:#x}") })
+}
+
+/// Write a storage value of a contract.
+///
+/// ### Arguments
+///
+/// * `address` - The contract. It does not need to be deployed.
+/// * `key` - The storage key.
+/// * `value` - The new value.
+///
+/// ### Returns
+///
+/// Nothing. The value is written directly to the pending block, no transaction is created.
+pub async fn set_storage_at(devnet: &Devnet, address: Felt, key: Felt, value: Felt) -> StarknetRpcResult<()> {
+    let contract_address = contract_address(address)?;
+    let key = PatriciaKey::try_from(key)
+        .map(StorageKey)
+        .map_err(|_| StarknetRpcApiError::ErrUnexpectedError { data: format!("Invalid storage key {key:#x}") })?;
+    devnet
+        .block_production
+        .set_storage_at(contract_address, key, value)
+        .await
+        .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Setting storage: {err:#}") })
+}
+
+/// Set the nonce of a contract, which can only be increased.
+///
+/// ### Arguments
+///
+/// * `address` - The contract.
+/// * `nonce` - The new nonce, at least the current one.
+///
+/// ### Returns
+///
+/// Nothing. The nonce is written directly to the pending block, no transaction is created.
+pub async fn set_nonce(devnet: &Devnet, address: Felt, nonce: Felt) -> StarknetRpcResult<()> {
+    devnet
+        .block_production
+        .set_nonce(contract_address(address)?, Nonce(nonce))
+        .await
+        .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Setting nonce: {err:#}") })
+}
+
+/// Replace the class of a contract, or deploy a contract of this class at the address. The constructor is not run.
+///
+/// ### Arguments
+///
+/// * `address` - The contract.
+/// * `class_hash` - A declared class.
+///
+/// ### Returns
+///
+/// Nothing. The class hash is written directly to the pending block, no transaction is created.
+pub async fn set_class_hash_at(devnet: &Devnet, address: Felt, class_hash: Felt) -> StarknetRpcResult<()> {
+    devnet
+        .block_production
+        .set_class_hash_at(contract_address(address)?, ClassHash(class_hash))
+        .await
+        .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Setting class hash: {err:#}") })
+}
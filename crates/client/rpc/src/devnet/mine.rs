@@ -0,0 +1,17 @@
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+
+use super::Devnet;
+
+/// Close the pending block, with the transactions of the mempool that fit in it.
+///
+/// ### Returns
+///
+/// The block number of the closed block. This is how blocks are produced with `--mining-mode on-demand`, but it
+/// works in every mining mode.
+pub async fn mine(devnet: &Devnet) -> StarknetRpcResult<u64> {
+    devnet
+        .block_production
+        .mine()
+        .await
+        .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Closing block: {err:#}") })
+}
@@ -0,0 +1,60 @@
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+use starknet_core::types::PriceUnit;
+use starknet_types_core::felt::Felt;
+
+use super::Devnet;
+
+/// Fee token credited by [`mint`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FeeToken {
+    #[default]
+    Eth,
+    Strk,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MintResult {
+    /// Balance after the mint, as a decimal string: it does not fit in a JSON number.
+    pub new_balance: String,
+    pub unit: PriceUnit,
+}
+
+/// Credit fee tokens to an address, without a faucet contract.
+///
+/// ### Arguments
+///
+/// * `address` - The address to fund. It does not need to be deployed.
+/// * `amount` - The amount to credit, in WEI for ETH and FRI for STRK.
+/// * `token` - The fee token, ETH when not specified.
+///
+/// ### Returns
+///
+/// The new balance of the address. The balance is written directly to the storage of the token in the pending block,
+/// no transaction is created.
+pub async fn mint(
+    devnet: &Devnet,
+    address: Felt,
+    amount: u128,
+    token: Option<FeeToken>,
+) -> StarknetRpcResult<MintResult> {
+    let chain_config = devnet.backend.chain_config();
+    let token = token.unwrap_or_default();
+    let (token_address, unit) = match token {
+        FeeToken::Eth => (chain_config.parent_fee_token_address, PriceUnit::Wei),
+        FeeToken::Strk => (chain_config.native_fee_token_address, PriceUnit::Fri),
+    };
+    let contract_address = address.try_into().map_err(|_| StarknetRpcApiError::ErrUnexpectedError {
+        data: format!("Invalid contract address {address:#x}"),
+    })?;
+
+    let new_balance = devnet
+        .block_production
+        .mint(token_address, contract_address, U256::from(amount))
+        .await
+        .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Minting {token:?}: {err:#}") })?;
+
+    Ok(MintResult { new_balance: new_balance.to_string(), unit })
+}
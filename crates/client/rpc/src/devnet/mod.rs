@@ -0,0 +1,141 @@
+//! Devnet specific RPC methods, used by tests to control the chain. They are only exposed by devnets.
+
+mod cheat;
+mod mine;
+mod mint;
+mod snapshot;
+mod state;
+mod time;
+
+use std::sync::{Arc, Mutex};
+
+use jsonrpsee::core::{async_trait, RpcResult};
+use jsonrpsee::proc_macros::rpc;
+use mc_db::MadaraBackend;
+use mc_mempool::{BlockClock, DevnetHandle};
+use starknet_types_core::felt::Felt;
+
+pub use cheat::*;
+pub use mine::*;
+pub use mint::*;
+pub use snapshot::*;
+pub use state::*;
+pub use time::*;
+
+#[rpc(server, namespace = "devnet")]
+pub trait DevnetRpcApi {
+    /// Set the time of the chain, in seconds since the UNIX epoch. Returns the new time.
+    #[method(name = "setTime")]
+    fn set_time(&self, time: u64) -> RpcResult<u64>;
+
+    /// Move the time of the chain forward by this number of seconds. Returns the new time.
+    #[method(name = "increaseTime")]
+    fn increase_time(&self, seconds: u64) -> RpcResult<u64>;
+
+    /// Set the timestamp of the next block only. The time of the chain then continues from it.
+    #[method(name = "setNextBlockTimestamp")]
+    fn set_next_block_timestamp(&self, timestamp: u64) -> RpcResult<u64>;
+
+    /// Credit fee tokens to an address. Returns the new balance.
+    #[method(name = "mint")]
+    async fn mint(&self, address: Felt, amount: u128, token: Option<FeeToken>) -> RpcResult<MintResult>;
+
+    /// Write a storage value of a contract in the pending block.
+    #[method(name = "setStorageAt")]
+    async fn set_storage_at(&self, address: Felt, key: Felt, value: Felt) -> RpcResult<()>;
+
+    /// Increase the nonce of a contract in the pending block.
+    #[method(name = "setNonce")]
+    async fn set_nonce(&self, address: Felt, nonce: Felt) -> RpcResult<()>;
+
+    /// Replace the class of a contract in the pending block, or deploy it without running a constructor.
+    #[method(name = "setClassHashAt")]
+    async fn set_class_hash_at(&self, address: Felt, class_hash: Felt) -> RpcResult<()>;
+
+    /// Close the pending block. Returns its block number.
+    #[method(name = "mine")]
+    async fn mine(&self) -> RpcResult<u64>;
+
+    /// Write the devnet chain to a file.
+    #[method(name = "dumpState")]
+    fn dump_state(&self, path: String) -> RpcResult<()>;
+
+    /// Import the blocks of a dump taken from this devnet.
+    #[method(name = "loadState")]
+    async fn load_state(&self, path: String) -> RpcResult<()>;
+
+    /// Save the current state of the chain. Returns the snapshot id.
+    #[method(name = "snapshot")]
+    async fn snapshot(&self) -> RpcResult<u64>;
+
+    /// Restore the state of the chain saved by `devnet_snapshot`. Returns the new latest block number.
+    #[method(name = "revert")]
+    async fn revert(&self, id: u64) -> RpcResult<u64>;
+}
+
+/// Handles shared by the devnet methods and the block production.
+#[derive(Clone)]
+pub struct Devnet {
+    pub(crate) backend: Arc<MadaraBackend>,
+    pub(crate) clock: BlockClock,
+    pub(crate) block_production: DevnetHandle,
+    /// Snapshots by id.
+    pub(crate) snapshots: Arc<Mutex<Vec<Snapshot>>>,
+}
+
+impl Devnet {
+    pub fn new(backend: Arc<MadaraBackend>, clock: BlockClock, block_production: DevnetHandle) -> Self {
+        Self { backend, clock, block_production, snapshots: Default::default() }
+    }
+}
+
+#[async_trait]
+impl DevnetRpcApiServer for Devnet {
+    fn set_time(&self, time: u64) -> RpcResult<u64> {
+        Ok(set_time(self, time)?)
+    }
+
+    fn increase_time(&self, seconds: u64) -> RpcResult<u64> {
+        Ok(increase_time(self, seconds))
+    }
+
+    fn set_next_block_timestamp(&self, timestamp: u64) -> RpcResult<u64> {
+        Ok(set_next_block_timestamp(self, timestamp)?)
+    }
+
+    async fn mint(&self, address: Felt, amount: u128, token: Option<FeeToken>) -> RpcResult<MintResult> {
+        Ok(mint(self, address, amount, token).await?)
+    }
+
+    async fn set_storage_at(&self, address: Felt, key: Felt, value: Felt) -> RpcResult<()> {
+        Ok(set_storage_at(self, address, key, value).await?)
+    }
+
+    async fn set_nonce(&self, address: Felt, nonce: Felt) -> RpcResult<()> {
+        Ok(set_nonce(self, address, nonce).await?)
+    }
+
+    async fn set_class_hash_at(&self, address: Felt, class_hash: Felt) -> RpcResult<()> {
+        Ok(set_class_hash_at(self, address, class_hash).await?)
+    }
+
+    async fn mine(&self) -> RpcResult<u64> {
+        Ok(mine(self).await?)
+    }
+
+    fn dump_state(&self, path: String) -> RpcResult<()> {
+        Ok(dump_state(self, &path)?)
+    }
+
+    async fn load_state(&self, path: String) -> RpcResult<()> {
+        Ok(load_state(self, &path).await?)
+    }
+
+    async fn snapshot(&self) -> RpcResult<u64> {
+        Ok(snapshot(self).await?)
+    }
+
+    async fn revert(&self, id: u64) -> RpcResult<u64> {
+        Ok(revert(self, id).await?)
+    }
+}
@@ -0,0 +1,68 @@
+use mp_block::{BlockId, BlockTag};
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use starknet_types_core::felt::Felt;
+
+use super::Devnet;
+
+/// Chain tip saved by [`snapshot`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Snapshot {
+    block_n: u64,
+    block_hash: Felt,
+}
+
+fn unexpected(data: impl Into<String>) -> StarknetRpcApiError {
+    StarknetRpcApiError::ErrUnexpectedError { data: data.into() }
+}
+
+/// Save the current state of the chain. The pending block is closed first when it has transactions, so that they are
+/// part of the snapshot.
+///
+/// ### Returns
+///
+/// The id of the snapshot, to be given to [`revert`].
+pub async fn snapshot(devnet: &Devnet) -> StarknetRpcResult<u64> {
+    let pending_info = devnet.backend.get_block_info(&BlockId::Tag(BlockTag::Pending))?;
+    if pending_info.is_some_and(|info| !info.tx_hashes().is_empty()) {
+        devnet.block_production.mine().await.map_err(|err| unexpected(format!("Closing block: {err:#}")))?;
+    }
+
+    let block_id = BlockId::Tag(BlockTag::Latest);
+    let block_n = devnet.backend.get_block_n(&block_id)?.ok_or_else(|| unexpected("No block to snapshot"))?;
+    let block_hash = devnet.backend.get_block_hash(&block_id)?.ok_or_else(|| unexpected("No block to snapshot"))?;
+
+    let mut snapshots = devnet.snapshots.lock().expect("Poisoned lock");
+    snapshots.push(Snapshot { block_n, block_hash });
+    Ok(snapshots.len() as u64 - 1)
+}
+
+/// Restore the state of the chain saved by [`snapshot`]: the blocks closed after it are removed, and the pending
+/// block is dropped.
+///
+/// ### Arguments
+///
+/// * `id` - The snapshot id. A snapshot can be reverted to several times, until the chain is reverted to an earlier
+///   snapshot.
+///
+/// ### Returns
+///
+/// The block number of the new latest block.
+pub async fn revert(devnet: &Devnet, id: u64) -> StarknetRpcResult<u64> {
+    let Snapshot { block_n, block_hash } = devnet
+        .snapshots
+        .lock()
+        .expect("Poisoned lock")
+        .get(id as usize)
+        .copied()
+        .ok_or_else(|| unexpected(format!("Unknown snapshot {id}")))?;
+    if devnet.backend.get_block_hash(&BlockId::Number(block_n))? != Some(block_hash) {
+        return Err(unexpected(format!("Snapshot {id} was removed by a revert to an earlier snapshot")));
+    }
+
+    devnet
+        .block_production
+        .revert_to(block_n)
+        .await
+        .map_err(|err| unexpected(format!("Reverting to snapshot {id}: {err:#}")))?;
+    Ok(block_n)
+}
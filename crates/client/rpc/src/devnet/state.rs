@@ -0,0 +1,35 @@
+use std::path::Path;
+
+use mc_devnet::DevnetDump;
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+
+use super::Devnet;
+
+/// Write the closed blocks of the devnet and its predeployed accounts to a file, which can then be loaded in another
+/// devnet with [`load_state`] or `--load-state`. The pending block is not dumped.
+pub fn dump_state(devnet: &Devnet, path: &str) -> StarknetRpcResult<()> {
+    DevnetDump::from_db(&devnet.backend)
+        .and_then(|dump| dump.write_file(Path::new(path)))
+        .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("{err:#}") })
+}
+
+/// Import the blocks of a dump that are not in the devnet yet.
+///
+/// ### Arguments
+///
+/// * `path` - The dump file, written by [`dump_state`] or `--dump-on-exit`.
+///
+/// ### Returns
+///
+/// Nothing. The dump must have been taken from this devnet: a dump of another chain can only be loaded in a new
+/// devnet with `--load-state`. The pending block is replaced, it must not have transactions.
+pub async fn load_state(devnet: &Devnet, path: &str) -> StarknetRpcResult<()> {
+    let blocks = DevnetDump::read_file(Path::new(path))
+        .and_then(|dump| dump.new_blocks(&devnet.backend))
+        .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("{err:#}") })?;
+    devnet
+        .block_production
+        .import_blocks(blocks)
+        .await
+        .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Loading devnet dump: {err:#}") })
+}
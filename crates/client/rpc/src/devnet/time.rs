@@ -0,0 +1,74 @@
+use mp_block::{BlockId, BlockTag};
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use mp_rpc::utils::ResultExt;
+
+use super::Devnet;
+
+/// Blocks cannot go back in time: the timestamps must not be before the one of the latest block.
+fn check_not_before_latest_block(devnet: &Devnet, timestamp: u64) -> StarknetRpcResult<()> {
+    let latest = devnet
+        .backend
+        .get_block_info(&BlockId::Tag(BlockTag::Latest))
+        .or_internal_server_error("Error getting the latest block")?;
+    if let Some(latest) = latest.as_ref().and_then(|info| info.as_nonpending()) {
+        if timestamp < latest.header.block_timestamp {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: format!(
+                    "Timestamp {timestamp} is before the timestamp {} of the latest block",
+                    latest.header.block_timestamp
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Set the time of the chain.
+///
+/// ### Arguments
+///
+/// * `time` - The new time, in seconds since the UNIX epoch.
+///
+/// ### Returns
+///
+/// The new time of the chain. It applies to the pending block if it has no transactions yet, to the next block
+/// otherwise.
+pub fn set_time(devnet: &Devnet, time: u64) -> StarknetRpcResult<u64> {
+    check_not_before_latest_block(devnet, time)?;
+    devnet.clock.set_time(time);
+    Ok(devnet.clock.now())
+}
+
+/// Move the time of the chain forward.
+pub fn increase_time(devnet: &Devnet, seconds: u64) -> u64 {
+    devnet.clock.increase_time(seconds);
+    devnet.clock.now()
+}
+
+/// Set the timestamp of the next block, after which the time of the chain continues from it.
+pub fn set_next_block_timestamp(devnet: &Devnet, timestamp: u64) -> StarknetRpcResult<u64> {
+    check_not_before_latest_block(devnet, timestamp)?;
+    devnet.clock.set_next_block_timestamp(timestamp);
+    Ok(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_test_setup;
+    use mc_db::MadaraBackend;
+    use mc_mempool::{BlockClock, DevnetHandle};
+    use mp_rpc::Starknet;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    #[rstest]
+    fn test_set_time(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, _) = rpc_test_setup;
+        let devnet = Devnet::new(backend, BlockClock::default(), DevnetHandle::new().0);
+
+        assert!((1_000_000..1_000_010).contains(&set_time(&devnet, 1_000_000).unwrap()));
+        assert!((1_003_600..1_003_610).contains(&increase_time(&devnet, 3_600)));
+        assert_eq!(set_next_block_timestamp(&devnet, 2_000_000).unwrap(), 2_000_000);
+    }
+}
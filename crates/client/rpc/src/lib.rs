@@ -3,7 +3,9 @@
 //! It uses the madara client and backend in order to answer queries.
 
 mod constants;
+pub mod devnet;
 mod macros;
+pub mod madara;
 pub mod providers;
 #[cfg(test)]
 pub mod test_utils;
@@ -13,6 +15,7 @@ pub mod versions;
 
 use jsonrpsee::RpcModule;
 
+use madara::{MadaraReadRpcApiServer, MadaraWriteRpcApiServer};
 use mp_rpc::Starknet;
 
 /// Returns the RpcModule merged with all the supported RPC versions.
@@ -25,5 +28,12 @@ pub fn versioned_rpc_api(starknet: &Starknet, read: bool, write: bool, trace: bo
                 // , v0_8_0 (for example)
     );
 
+    if read {
+        rpc_api.merge(MadaraReadRpcApiServer::into_rpc(starknet.clone()))?;
+    }
+    if write {
+        rpc_api.merge(MadaraWriteRpcApiServer::into_rpc(starknet.clone()))?;
+    }
+
     Ok(rpc_api)
 }
@@ -0,0 +1,51 @@
+use jsonrpsee::core::RpcResult;
+use mp_rpc::utils::OptionExt;
+use mp_utils::spawn_execution_task;
+use serde::{Deserialize, Serialize};
+use starknet_core::types::{
+    BlockId, BlockTag, BroadcastedInvokeTransaction, BroadcastedTransaction, Felt, SimulatedTransaction,
+};
+
+use crate::versions::v0_7_1::methods::trace::simulate_transactions::simulate_transactions;
+use crate::Starknet;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvokeTransactionWithSimulationResult {
+    pub transaction_hash: Felt,
+    /// Trace and fee of the transaction, executed on top of the pending block when it was submitted.
+    pub simulation: SimulatedTransaction,
+}
+
+/// Simulate an invoke transaction on top of the pending block, then submit it.
+///
+/// The transaction is only submitted when it can be included in a block: transactions failing validation or unable to
+/// pay their fee are rejected with the execution error. Reverted transactions are submitted, and the reverted
+/// execution is part of the simulation.
+///
+/// ### Arguments
+///
+/// * `invoke_transaction` - The transaction to submit.
+///
+/// ### Returns
+///
+/// The hash of the transaction, and the outcome of its simulation. The simulation does not account for the
+/// transactions received in-between by the node: the transaction may still end up executing differently.
+pub async fn add_invoke_transaction_with_simulation(
+    starknet: &Starknet,
+    invoke_transaction: BroadcastedInvokeTransaction,
+) -> RpcResult<InvokeTransactionWithSimulationResult> {
+    let transaction = BroadcastedTransaction::Invoke(invoke_transaction.clone());
+    let simulation = {
+        let starknet = starknet.clone();
+        spawn_execution_task(move || {
+            simulate_transactions(&starknet, BlockId::Tag(BlockTag::Pending), vec![transaction], vec![])
+        })
+        .await?
+        .pop()
+        .ok_or_internal_server_error("No simulation returned for the transaction")?
+    };
+
+    let result = starknet.add_transaction_provider.add_invoke_transaction(invoke_transaction).await?;
+
+    Ok(InvokeTransactionWithSimulationResult { transaction_hash: result.transaction_hash, simulation })
+}
@@ -0,0 +1,26 @@
+use jsonrpsee::core::RpcResult;
+use mp_rpc::TransactionBundleResult;
+use starknet_core::types::BroadcastedTransaction;
+
+use crate::Starknet;
+
+/// Submit an ordered bundle of transactions, which are included consecutively in one block, or not at all.
+///
+/// The bundles are executed at the next tick of the pending block, before the other transactions, in the order they
+/// were received. A bundle is rejected as a whole when one of its transactions fails or reverts, or when it does not
+/// fit in the block: none of its transactions is included then, and it is not retried. The bundle is also rejected
+/// right away when too many bundles are already waiting.
+///
+/// ### Arguments
+///
+/// * `transactions` - The transactions of the bundle, in the order they are executed.
+///
+/// ### Returns
+///
+/// The block the bundle was included in, and the hashes of its transactions. Returns once the bundle is executed.
+pub async fn add_transaction_bundle(
+    starknet: &Starknet,
+    transactions: Vec<BroadcastedTransaction>,
+) -> RpcResult<TransactionBundleResult> {
+    starknet.add_transaction_provider.add_transaction_bundle(transactions).await
+}
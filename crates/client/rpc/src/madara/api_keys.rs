@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// An API key of the RPC server, and its usage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyInfo {
+    pub key: String,
+    /// Tier of the key, which sets its rate limit and its daily quota.
+    pub tier: String,
+    /// Number of calls made with the key since midnight UTC.
+    pub calls_today: u64,
+}
+
+/// API keys of the RPC server, see [`super::MadaraAdminRpcApiServer::set_api_key`].
+pub trait ApiKeyManager: Send + Sync {
+    fn api_keys(&self) -> Vec<ApiKeyInfo>;
+
+    /// Adds the key, or moves it to another tier. Fails when the tier does not exist.
+    fn set_api_key(&self, key: String, tier: &str) -> anyhow::Result<()>;
+
+    /// Returns whether the key existed.
+    fn remove_api_key(&self, key: &str) -> bool;
+}
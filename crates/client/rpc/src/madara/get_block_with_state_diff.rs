@@ -0,0 +1,70 @@
+use mc_db::db_block_id::DbBlockId;
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use mp_rpc::utils::ResultExt;
+use serde::{Deserialize, Serialize};
+use starknet_core::types::{BlockId, BlockTag, MaybePendingBlockWithReceipts, StateDiff};
+
+use crate::versions::v0_7_1::methods::read::get_block_with_receipts::get_block_with_receipts;
+use crate::Starknet;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockWithStateDiff {
+    /// The block with its transactions and receipts, as returned by `starknet_getBlockWithReceipts`.
+    pub block: MaybePendingBlockWithReceipts,
+    pub state_diff: StateDiff,
+}
+
+/// Get a block with its transactions, receipts and state diff.
+///
+/// ### Arguments
+///
+/// * `block_id` - The hash, number or tag of the block.
+///
+/// ### Returns
+///
+/// The block as returned by `starknet_getBlockWithReceipts`, and its state diff as returned by
+/// `starknet_getStateUpdate`. Both are read from the same block, even when a new block is closed in-between.
+pub fn get_block_with_state_diff(starknet: &Starknet, block_id: BlockId) -> StarknetRpcResult<BlockWithStateDiff> {
+    let resolved_block_id = starknet
+        .backend
+        .resolve_block_id(&block_id)
+        .or_internal_server_error("Error resolving block id")?
+        .ok_or(StarknetRpcApiError::BlockNotFound)?;
+    let state_diff = starknet
+        .backend
+        .get_block_state_diff(&resolved_block_id)
+        .or_internal_server_error("Error getting block state diff")?
+        .ok_or(StarknetRpcApiError::BlockNotFound)?;
+
+    let block_id = match resolved_block_id {
+        DbBlockId::Pending => BlockId::Tag(BlockTag::Pending),
+        DbBlockId::Number(block_n) => BlockId::Number(block_n),
+    };
+    let block = get_block_with_receipts(starknet, block_id)?;
+
+    Ok(BlockWithStateDiff { block, state_diff: state_diff.into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{sample_chain_for_state_updates, SampleChainForStateUpdates};
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_get_block_with_state_diff(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
+        let (SampleChainForStateUpdates { state_diffs, .. }, rpc) = sample_chain_for_state_updates;
+
+        for block_n in 0..3 {
+            let res = get_block_with_state_diff(&rpc, BlockId::Number(block_n)).unwrap();
+            assert_eq!(res.block, get_block_with_receipts(&rpc, BlockId::Number(block_n)).unwrap());
+            assert_eq!(res.state_diff, state_diffs[block_n as usize].clone().into());
+        }
+
+        let res = get_block_with_state_diff(&rpc, BlockId::Tag(BlockTag::Pending)).unwrap();
+        assert!(matches!(res.block, MaybePendingBlockWithReceipts::PendingBlock(_)));
+        assert_eq!(res.state_diff, state_diffs[3].clone().into());
+
+        assert_eq!(get_block_with_state_diff(&rpc, BlockId::Number(3)), Err(StarknetRpcApiError::BlockNotFound));
+    }
+}
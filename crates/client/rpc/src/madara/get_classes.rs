@@ -0,0 +1,70 @@
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use mp_rpc::utils::ResultExt;
+use starknet_core::types::{BlockId, ContractClass, Felt};
+
+use crate::constants::MAX_CLASSES_PER_REQUEST;
+use crate::Starknet;
+
+/// Get several contract classes at once, as declared at the given block.
+///
+/// ### Arguments
+///
+/// * `block_id` - The hash, number or tag of the block.
+/// * `class_hashes` - The hashes of the classes, at most [`MAX_CLASSES_PER_REQUEST`] of them.
+///
+/// ### Returns
+///
+/// The classes in the order of `class_hashes`, `null` for the classes not declared at that block.
+///
+/// ### Errors
+///
+/// BlockNotFound : If the specified block does not exist.
+/// PageSizeTooBig : If more than [`MAX_CLASSES_PER_REQUEST`] classes are requested.
+pub fn get_classes(
+    starknet: &Starknet,
+    block_id: BlockId,
+    class_hashes: Vec<Felt>,
+) -> StarknetRpcResult<Vec<Option<ContractClass>>> {
+    if class_hashes.len() > MAX_CLASSES_PER_REQUEST {
+        return Err(StarknetRpcApiError::PageSizeTooBig);
+    }
+    let block_exists =
+        starknet.backend.contains_block(&block_id).or_internal_server_error("Checking if block is in database")?;
+    if !block_exists {
+        return Err(StarknetRpcApiError::BlockNotFound);
+    }
+
+    class_hashes
+        .iter()
+        .map(|class_hash| {
+            let class_info = starknet
+                .backend
+                .get_class_info(&block_id, class_hash)
+                .or_internal_server_error("Error getting contract class info")?;
+            Ok(class_info.map(|class_info| class_info.contract_class().into()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{sample_chain_for_state_updates, SampleChainForStateUpdates};
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_get_classes_errors(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
+        let (SampleChainForStateUpdates { class_hashes, .. }, rpc) = sample_chain_for_state_updates;
+
+        // The sample chain does not store the class definitions.
+        assert_eq!(get_classes(&rpc, BlockId::Number(2), class_hashes[..2].to_vec()), Ok(vec![None, None]));
+        assert_eq!(
+            get_classes(&rpc, BlockId::Number(3), vec![class_hashes[0]]),
+            Err(StarknetRpcApiError::BlockNotFound)
+        );
+        assert_eq!(
+            get_classes(&rpc, BlockId::Number(0), vec![class_hashes[0]; MAX_CLASSES_PER_REQUEST + 1]),
+            Err(StarknetRpcApiError::PageSizeTooBig)
+        );
+    }
+}
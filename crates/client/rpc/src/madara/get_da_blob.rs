@@ -0,0 +1,56 @@
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use mp_rpc::utils::ResultExt;
+use starknet_core::types::{BlockId, Felt};
+
+use crate::Starknet;
+
+/// Get the state diff of a block, encoded for data availability.
+///
+/// ### Arguments
+///
+/// * `block_id` - The hash, number or tag of the block.
+///
+/// ### Returns
+///
+/// The state diff of the block in the format published by the Starknet OS, which is the data settled in the calldata
+/// or blobs of the state updates on L1. The nonce of every touched contract is included, as of this block. State
+/// updates covering several blocks publish their merged state diff instead.
+pub fn get_da_blob(starknet: &Starknet, block_id: BlockId) -> StarknetRpcResult<Vec<Felt>> {
+    let resolved_block_id = starknet
+        .backend
+        .resolve_block_id(&block_id)
+        .or_internal_server_error("Error resolving block id")?
+        .ok_or(StarknetRpcApiError::BlockNotFound)?;
+    let mut state_diff = starknet
+        .backend
+        .get_block_state_diff(&resolved_block_id)
+        .or_internal_server_error("Error getting block state diff")?
+        .ok_or(StarknetRpcApiError::BlockNotFound)?;
+    starknet
+        .backend
+        .add_touched_contract_nonces(&resolved_block_id, &mut state_diff)
+        .or_internal_server_error("Error getting contract nonces")?;
+    Ok(state_diff.encode_da())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{sample_chain_for_state_updates, SampleChainForStateUpdates};
+    use mp_state_update::{NonceUpdate, StateDiff};
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_get_da_blob(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
+        let (SampleChainForStateUpdates { state_diffs, contracts, .. }, rpc) = sample_chain_for_state_updates;
+
+        // Block 2 does not update the nonce of contract 2, which was set to 2 by block 1.
+        let expected = StateDiff {
+            nonces: vec![NonceUpdate { contract_address: contracts[2], nonce: 2.into() }],
+            ..state_diffs[2].clone()
+        };
+        assert_eq!(get_da_blob(&rpc, BlockId::Number(2)).unwrap(), expected.encode_da());
+        assert_eq!(get_da_blob(&rpc, BlockId::Number(1)).unwrap(), state_diffs[1].encode_da());
+        assert_eq!(get_da_blob(&rpc, BlockId::Number(3)), Err(StarknetRpcApiError::BlockNotFound));
+    }
+}
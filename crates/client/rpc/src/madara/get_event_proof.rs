@@ -0,0 +1,172 @@
+use mp_block::MadaraMaybePendingBlockInfo;
+use mp_chain_config::StarknetVersion;
+use mp_rpc::bail_internal_server_error;
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Felt;
+use starknet_types_core::hash::{Pedersen, Poseidon};
+
+use super::merkle_trie::{merkle_proof, MerkleNode};
+use crate::Starknet;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventProof {
+    pub block_hash: Felt,
+    pub block_number: u64,
+    /// Root of the event trie, committed to in the block hash.
+    pub event_commitment: Felt,
+    /// Index of the event among all the events of the block, which is the key of its leaf in the trie.
+    pub event_index_in_block: u64,
+    /// Leaf of the trie.
+    pub event_hash: Felt,
+    /// Nodes on the path from the root to the leaf. The trie is hashed with Pedersen before Starknet v0.13.2, and
+    /// with Poseidon since.
+    pub proof: Vec<MerkleNode>,
+}
+
+/// Get the Merkle proof of an event against the event commitment of its block.
+///
+/// ### Arguments
+///
+/// * `transaction_hash` - The hash of the transaction which emitted the event.
+/// * `event_index` - The index of the event among the events emitted by the transaction.
+///
+/// ### Returns
+///
+/// The hash of the event, and the nodes of the event trie from the event commitment to the event. Once the block
+/// hash is settled on L1, this proves the event was emitted without trusting the node. Only closed blocks have an
+/// event commitment.
+pub fn get_event_proof(starknet: &Starknet, transaction_hash: Felt, event_index: u64) -> StarknetRpcResult<EventProof> {
+    let (block, tx_index) =
+        starknet.find_tx_hash_block(&transaction_hash)?.ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+    let MadaraMaybePendingBlockInfo::NotPending(info) = block.info else {
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: "The pending block has no event commitment".into(),
+        });
+    };
+
+    let receipts = &block.inner.receipts;
+    let Some(receipt) = receipts.get(tx_index.0 as usize) else {
+        bail_internal_server_error!("Storage block receipt mismatch for block #{}", info.header.block_number);
+    };
+    if event_index >= receipt.events().len() as u64 {
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: format!(
+                "Event index {event_index} out of range, the transaction emitted {} events",
+                receipt.events().len()
+            ),
+        });
+    }
+    let event_index_in_block =
+        receipts[..tx_index.0 as usize].iter().map(|receipt| receipt.events().len() as u64).sum::<u64>() + event_index;
+
+    let legacy = info.header.protocol_version < StarknetVersion::V0_13_2;
+    let event_hashes = receipts
+        .iter()
+        .flat_map(|receipt| {
+            receipt.events().iter().map(|event| {
+                if legacy {
+                    event.compute_hash_pedersen()
+                } else {
+                    event.compute_hash_poseidon(&receipt.transaction_hash())
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    let (root, proof) = if legacy {
+        merkle_proof::<Pedersen>(&event_hashes, event_index_in_block)
+    } else {
+        merkle_proof::<Poseidon>(&event_hashes, event_index_in_block)
+    };
+    if root != info.header.event_commitment {
+        bail_internal_server_error!(
+            "Event commitment mismatch for block #{}: computed {root:#x}, stored {:#x}",
+            info.header.block_number,
+            info.header.event_commitment
+        );
+    }
+
+    Ok(EventProof {
+        block_hash: info.block_hash,
+        block_number: info.header.block_number,
+        event_commitment: root,
+        event_index_in_block,
+        event_hash: event_hashes[event_index_in_block as usize],
+        proof,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_test_setup;
+    use mc_db::MadaraBackend;
+    use mp_block::{Header, MadaraBlockInfo, MadaraBlockInner, MadaraMaybePendingBlock};
+    use mp_receipt::{Event, InvokeTransactionReceipt, TransactionReceipt};
+    use mp_state_update::StateDiff;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    fn event(n: u64) -> Event {
+        Event { from_address: Felt::from(n), keys: vec![Felt::from(n + 1)], data: vec![Felt::from(n + 2)] }
+    }
+
+    #[rstest]
+    fn test_get_event_proof(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let tx_hashes = [Felt::from(0x10u64), Felt::from(0x11u64), Felt::from(0x12u64)];
+        let events = [vec![event(1), event(2)], vec![], vec![event(3)]];
+        let event_hashes = tx_hashes
+            .iter()
+            .zip(&events)
+            .flat_map(|(tx_hash, events)| events.iter().map(|event| event.compute_hash_poseidon(tx_hash)))
+            .collect::<Vec<_>>();
+        let (event_commitment, _) = merkle_proof::<Poseidon>(&event_hashes, 0);
+        backend
+            .store_block(
+                MadaraMaybePendingBlock {
+                    info: MadaraMaybePendingBlockInfo::NotPending(MadaraBlockInfo {
+                        header: Header {
+                            block_number: 0,
+                            event_commitment,
+                            protocol_version: StarknetVersion::V0_13_2,
+                            ..Default::default()
+                        },
+                        block_hash: Felt::ONE,
+                        tx_hashes: tx_hashes.to_vec(),
+                    }),
+                    inner: MadaraBlockInner {
+                        transactions: vec![],
+                        receipts: tx_hashes
+                            .iter()
+                            .zip(events)
+                            .map(|(tx_hash, events)| {
+                                TransactionReceipt::Invoke(InvokeTransactionReceipt {
+                                    transaction_hash: *tx_hash,
+                                    events,
+                                    ..Default::default()
+                                })
+                            })
+                            .collect(),
+                    },
+                },
+                StateDiff::default(),
+                vec![],
+            )
+            .unwrap();
+
+        let proof = get_event_proof(&rpc, tx_hashes[2], 0).unwrap();
+        assert_eq!(proof.event_commitment, event_commitment);
+        assert_eq!(proof.event_index_in_block, 2);
+        assert_eq!(proof.event_hash, event_hashes[2]);
+        assert_eq!(proof.proof, merkle_proof::<Poseidon>(&event_hashes, 2).1);
+        assert_eq!(get_event_proof(&rpc, tx_hashes[0], 1).unwrap().event_index_in_block, 1);
+
+        assert!(matches!(
+            get_event_proof(&rpc, tx_hashes[1], 0),
+            Err(StarknetRpcApiError::ErrUnexpectedError { data })
+                if data == "Event index 0 out of range, the transaction emitted 0 events"
+        ));
+        assert_eq!(get_event_proof(&rpc, Felt::from(0x13u64), 0), Err(StarknetRpcApiError::TxnHashNotFound));
+    }
+}
@@ -0,0 +1,84 @@
+use mc_db::l1_db::L1ToL2MessageCancellation;
+use mp_rpc::errors::StarknetRpcResult;
+use mp_rpc::utils::ResultExt;
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Hash256;
+
+use crate::Starknet;
+
+/// Cancellation status of an L1->L2 message, as seen on the core contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum L1ToL2MessageStatus {
+    /// No cancellation was requested for this message.
+    NotCancelled,
+    /// The sender started the cancellation of the message: it will not be consumed on L2 anymore.
+    CancellationStarted { l1_block_number: u64 },
+    /// The cancellation was finalized, and the message removed from the core contract.
+    Cancelled { l1_block_number: u64 },
+}
+
+impl From<Option<L1ToL2MessageCancellation>> for L1ToL2MessageStatus {
+    fn from(cancellation: Option<L1ToL2MessageCancellation>) -> Self {
+        match cancellation {
+            None => Self::NotCancelled,
+            Some(L1ToL2MessageCancellation::Started { l1_block_number }) => {
+                Self::CancellationStarted { l1_block_number }
+            }
+            Some(L1ToL2MessageCancellation::Cancelled { l1_block_number }) => Self::Cancelled { l1_block_number },
+        }
+    }
+}
+
+/// Get the cancellation status of an L1->L2 message.
+///
+/// ### Arguments
+///
+/// * `message_hash` - The hash of the L1->L2 message, as computed by the core contract.
+///
+/// ### Returns
+///
+/// The cancellation status of the message. Cancellations are only tracked by nodes following Ethereum.
+pub fn get_l1_to_l2_message_status(
+    starknet: &Starknet,
+    message_hash: Hash256,
+) -> StarknetRpcResult<L1ToL2MessageStatus> {
+    let cancellation = starknet
+        .backend
+        .get_l1_to_l2_message_cancellation(message_hash.as_bytes())
+        .or_internal_server_error("Error getting L1 to L2 message cancellation")?;
+    Ok(cancellation.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_test_setup;
+    use mc_db::MadaraBackend;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    #[rstest]
+    fn test_get_l1_to_l2_message_status(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, rpc) = rpc_test_setup;
+        let message_hash = Hash256::from_bytes([1u8; 32]);
+
+        assert_eq!(get_l1_to_l2_message_status(&rpc, message_hash).unwrap(), L1ToL2MessageStatus::NotCancelled);
+
+        backend
+            .set_l1_to_l2_message_cancellation(&[1u8; 32], L1ToL2MessageCancellation::Started { l1_block_number: 12 })
+            .unwrap();
+        assert_eq!(
+            get_l1_to_l2_message_status(&rpc, message_hash).unwrap(),
+            L1ToL2MessageStatus::CancellationStarted { l1_block_number: 12 }
+        );
+    }
+
+    #[test]
+    fn test_status_serialization() {
+        assert_eq!(
+            serde_json::to_value(L1ToL2MessageStatus::Cancelled { l1_block_number: 3 }).unwrap(),
+            serde_json::json!({ "status": "CANCELLED", "l1_block_number": 3 })
+        );
+    }
+}
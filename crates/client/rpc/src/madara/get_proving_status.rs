@@ -0,0 +1,93 @@
+use mc_db::proving_db::{ProvingJob, ProvingJobStatus};
+use mc_db::MadaraBackend;
+use mp_rpc::errors::StarknetRpcResult;
+use mp_rpc::utils::ResultExt;
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Hash256;
+
+/// Maximum number of proving jobs returned at once.
+pub const MAX_PROVING_JOBS: usize = 100;
+
+/// Proving of a block by the external prover.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvingJobInfo {
+    pub block_number: u64,
+    /// Id of the job on the prover.
+    pub job_id: String,
+    #[serde(flatten)]
+    pub status: ProvingJobState,
+    /// Unix timestamp of the last submission of the block.
+    pub submitted_at: u64,
+    pub attempts: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProvingJobState {
+    InProgress,
+    /// The fact is only known for provers returning it.
+    Proven {
+        fact: Option<Hash256>,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+impl ProvingJobInfo {
+    fn new(block_number: u64, job: ProvingJob) -> Self {
+        let status = match job.status {
+            ProvingJobStatus::InProgress => ProvingJobState::InProgress,
+            ProvingJobStatus::Proven { fact } => ProvingJobState::Proven { fact: fact.map(Hash256::from_bytes) },
+            ProvingJobStatus::Failed { error } => ProvingJobState::Failed { error },
+        };
+        Self { block_number, job_id: job.job_id, status, submitted_at: job.submitted_at, attempts: job.attempts }
+    }
+}
+
+/// Get the proving jobs of the blocks, when the node submits them to an external prover.
+///
+/// ### Arguments
+///
+/// * `from_block` - The first block to return the proving job of.
+///
+/// ### Returns
+///
+/// The proving jobs of the blocks from `from_block`, in order, at most [`MAX_PROVING_JOBS`] of them. Blocks that
+/// were not submitted yet have no job.
+pub fn get_proving_status(backend: &MadaraBackend, from_block: u64) -> StarknetRpcResult<Vec<ProvingJobInfo>> {
+    let jobs = backend
+        .get_proving_jobs(from_block, MAX_PROVING_JOBS)
+        .or_internal_server_error("Error getting proving jobs")?;
+    Ok(jobs.into_iter().map(|(block_number, job)| ProvingJobInfo::new(block_number, job)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::rpc_test_setup;
+    use crate::Starknet;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    #[rstest]
+    fn test_get_proving_status(rpc_test_setup: (Arc<MadaraBackend>, Starknet)) {
+        let (backend, _) = rpc_test_setup;
+        let job = ProvingJob {
+            job_id: "01JD".into(),
+            status: ProvingJobStatus::Proven { fact: Some([1u8; 32]) },
+            submitted_at: 1000,
+            attempts: 1,
+        };
+        backend.write_proving_job(4, &job).unwrap();
+        backend.write_proving_job(5, &ProvingJob { status: ProvingJobStatus::InProgress, ..job }).unwrap();
+
+        let jobs = get_proving_status(&backend, 5).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].status, ProvingJobState::InProgress);
+
+        let json = serde_json::to_value(&get_proving_status(&backend, 0).unwrap()[0]).unwrap();
+        assert_eq!(json["status"], "PROVEN");
+        assert_eq!(json["fact"], format!("0x{}", "01".repeat(32)));
+    }
+}
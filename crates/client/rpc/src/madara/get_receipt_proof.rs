@@ -0,0 +1,98 @@
+use mp_block::MadaraMaybePendingBlockInfo;
+use mp_receipt::TransactionReceipt;
+use mp_rpc::bail_internal_server_error;
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Felt;
+use starknet_types_core::hash::Poseidon;
+
+use super::merkle_trie::{merkle_proof, MerkleNode};
+use crate::Starknet;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptProof {
+    pub block_hash: Felt,
+    pub block_number: u64,
+    /// Root of the receipt trie, committed to in the block hash.
+    pub receipt_commitment: Felt,
+    /// Index of the transaction in the block, which is the key of its receipt in the trie.
+    pub transaction_index: u64,
+    /// Leaf of the trie.
+    pub receipt_hash: Felt,
+    /// Nodes on the path from the root to the leaf.
+    pub proof: Vec<MerkleNode>,
+}
+
+/// Get the Merkle proof of the receipt of a transaction against the receipt commitment of its block.
+///
+/// ### Arguments
+///
+/// * `transaction_hash` - The hash of the transaction.
+///
+/// ### Returns
+///
+/// The hash of the receipt, and the nodes of the receipt trie from the receipt commitment to the receipt. Hashing the
+/// nodes down to the leaf at the index of the transaction proves the outcome of the transaction is part of the block,
+/// without trusting the node. Only closed blocks have a receipt commitment, starting from Starknet v0.13.2.
+pub fn get_receipt_proof(starknet: &Starknet, transaction_hash: Felt) -> StarknetRpcResult<ReceiptProof> {
+    let (block, tx_index) =
+        starknet.find_tx_hash_block(&transaction_hash)?.ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+    let MadaraMaybePendingBlockInfo::NotPending(info) = block.info else {
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: "The pending block has no receipt commitment".into(),
+        });
+    };
+    let Some(receipt_commitment) = info.header.receipt_commitment else {
+        return Err(StarknetRpcApiError::ErrUnexpectedError {
+            data: format!("Block #{} has no receipt commitment", info.header.block_number),
+        });
+    };
+
+    let receipt_hashes = block.inner.receipts.iter().map(TransactionReceipt::compute_hash).collect::<Vec<_>>();
+    let Some(&receipt_hash) = receipt_hashes.get(tx_index.0 as usize) else {
+        bail_internal_server_error!("Storage block receipt mismatch for block #{}", info.header.block_number);
+    };
+    let (root, proof) = merkle_proof::<Poseidon>(&receipt_hashes, tx_index.0);
+    if root != receipt_commitment {
+        bail_internal_server_error!(
+            "Receipt commitment mismatch for block #{}: computed {root:#x}, stored {receipt_commitment:#x}",
+            info.header.block_number
+        );
+    }
+
+    Ok(ReceiptProof {
+        block_hash: info.block_hash,
+        block_number: info.header.block_number,
+        receipt_commitment,
+        transaction_index: tx_index.0,
+        receipt_hash,
+        proof,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{sample_chain_for_block_getters, SampleChainForBlockGetters};
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_get_receipt_proof_errors(sample_chain_for_block_getters: (SampleChainForBlockGetters, Starknet)) {
+        let (SampleChainForBlockGetters { tx_hashes, .. }, rpc) = sample_chain_for_block_getters;
+
+        // The receipt commitment of block 0 is not the one of its receipts.
+        assert_eq!(get_receipt_proof(&rpc, tx_hashes[0]), Err(StarknetRpcApiError::InternalServerError));
+        assert!(matches!(
+            get_receipt_proof(&rpc, tx_hashes[1]),
+            Err(StarknetRpcApiError::ErrUnexpectedError { data }) if data == "Block #2 has no receipt commitment"
+        ));
+        assert!(matches!(
+            get_receipt_proof(&rpc, tx_hashes[3]),
+            Err(StarknetRpcApiError::ErrUnexpectedError { data }) if data == "The pending block has no receipt commitment"
+        ));
+        assert_eq!(
+            get_receipt_proof(&rpc, Felt::from_hex_unchecked("0xdead")),
+            Err(StarknetRpcApiError::TxnHashNotFound)
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Proofs of the commitment tries of a block, the Merkle-Patricia tries of height 64 keyed by the index of the
+//! transactions, receipts or events in the block.
+
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Felt;
+use starknet_types_core::hash::StarkHash;
+
+const COMMITMENT_TRIE_HEIGHT: u8 = 64;
+
+/// A node of a Merkle-Patricia trie, in the format of `starknet_getStorageProof`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MerkleNode {
+    /// Hashed as `hash(left, right)`.
+    Binary { left: Felt, right: Felt },
+    /// Hashed as `hash(child, path) + length`.
+    Edge { child: Felt, path: Felt, length: u8 },
+}
+
+/// Computes the root of the commitment trie of `leaves`, keyed by their index, and the nodes on the path
+/// from the root to the leaf at `index`.
+pub(super) fn merkle_proof<H: StarkHash>(leaves: &[Felt], index: u64) -> (Felt, Vec<MerkleNode>) {
+    let leaves = leaves.iter().enumerate().map(|(key, leaf)| (key as u64, *leaf)).collect::<Vec<_>>();
+    if leaves.is_empty() {
+        return (Felt::ZERO, vec![]);
+    }
+    let mut proof = vec![];
+    let root = subtree_hash::<H>(&leaves, COMMITMENT_TRIE_HEIGHT, index, &mut proof);
+    proof.reverse();
+    (root, proof)
+}
+
+/// Hash of the subtree of height `height` holding the sorted `leaves`, whose keys share the bits above it. The nodes
+/// on the path to `index` are pushed to `proof`, from the bottom.
+fn subtree_hash<H: StarkHash>(leaves: &[(u64, Felt)], height: u8, index: u64, proof: &mut Vec<MerkleNode>) -> Felt {
+    if height == 0 {
+        return leaves[0].1;
+    }
+    let (first, last) = (leaves[0].0, leaves[leaves.len() - 1].0);
+    let in_path = first.checked_shr(height.into()) == index.checked_shr(height.into());
+    // Number of bits shared by all the keys below this node, which are the path of an edge node.
+    let shared = (height as u32 + ((first ^ last) & mask(height)).leading_zeros() - 64) as u8;
+
+    if shared == 0 {
+        let split = leaves.partition_point(|(key, _)| (key >> (height - 1)) & 1 == 0);
+        let left = subtree_hash::<H>(&leaves[..split], height - 1, index, proof);
+        let right = subtree_hash::<H>(&leaves[split..], height - 1, index, proof);
+        if in_path {
+            proof.push(MerkleNode::Binary { left, right });
+        }
+        H::hash(&left, &right)
+    } else {
+        let child = subtree_hash::<H>(leaves, height - shared, index, proof);
+        let path = Felt::from((first >> (height - shared)) & mask(shared));
+        if in_path {
+            proof.push(MerkleNode::Edge { child, path, length: shared });
+        }
+        H::hash(&child, &path) + Felt::from(shared)
+    }
+}
+
+/// Mask of the `bits` lowest bits.
+fn mask(bits: u8) -> u64 {
+    u64::MAX.checked_shr(64 - bits as u32).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use starknet_types_core::hash::{Pedersen, Poseidon};
+
+    /// What a light client does with the proof.
+    fn verify<H: StarkHash>(root: Felt, index: u64, leaf: Felt, proof: &[MerkleNode]) -> bool {
+        let (mut expected, mut height) = (root, COMMITMENT_TRIE_HEIGHT);
+        for node in proof {
+            match *node {
+                MerkleNode::Binary { left, right } => {
+                    if H::hash(&left, &right) != expected || height == 0 {
+                        return false;
+                    }
+                    height -= 1;
+                    expected = if (index >> height) & 1 == 0 { left } else { right };
+                }
+                MerkleNode::Edge { child, path, length } => {
+                    if H::hash(&child, &path) + Felt::from(length) != expected || length > height {
+                        return false;
+                    }
+                    height -= length;
+                    if path != Felt::from((index >> height) & mask(length)) {
+                        return false;
+                    }
+                    expected = child;
+                }
+            }
+        }
+        height == 0 && expected == leaf
+    }
+
+    #[test]
+    fn root_matches_the_block_commitments() {
+        let (root, _) = merkle_proof::<Poseidon>(&[Felt::ONE, Felt::TWO, Felt::THREE], 0);
+        assert_eq!(root, Felt::from_hex_unchecked("0x3b5cc7f1292eb3847c3f902d048a7e5dc7702d1c191ccd17c2d33f797e6fc32"));
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(3)]
+    #[case(7)]
+    #[case(16)]
+    fn proofs_verify(#[case] len: u64) {
+        check_proofs::<Poseidon>(len);
+        check_proofs::<Pedersen>(len);
+    }
+
+    fn check_proofs<H: StarkHash>(len: u64) {
+        let leaves = (0..len).map(|i| Felt::from(i * 31 + 5)).collect::<Vec<_>>();
+        let (root, _) = merkle_proof::<H>(&leaves, 0);
+        for index in 0..len {
+            let (proof_root, proof) = merkle_proof::<H>(&leaves, index);
+            assert_eq!(proof_root, root);
+            assert!(verify::<H>(root, index, leaves[index as usize], &proof), "proof of leaf {index}");
+            assert!(!verify::<H>(root, index, Felt::from(1234u64), &proof));
+        }
+    }
+}
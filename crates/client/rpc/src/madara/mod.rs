@@ -0,0 +1,306 @@
+//! Madara specific RPC methods, which are not part of the Starknet specs and are not versioned.
+
+mod add_invoke_transaction_with_simulation;
+mod add_transaction_bundle;
+mod api_keys;
+mod get_block_with_state_diff;
+mod get_classes;
+mod get_da_blob;
+mod get_event_proof;
+mod get_l1_to_l2_message_status;
+mod get_proving_status;
+mod get_receipt_proof;
+mod merkle_trie;
+mod submit_block;
+mod subscribe_events;
+mod subscribe_storage;
+mod trace_transaction_resources;
+
+use std::sync::Arc;
+
+use jsonrpsee::core::{async_trait, RpcResult, SubscriptionResult};
+use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::PendingSubscriptionSink;
+use mc_block_import::{BlockImporter, TrieRebuildStatus};
+use mc_db::MadaraBackend;
+use mc_exec::TransactionCallResources;
+use mc_mempool::BuilderHandle;
+use mp_rpc::errors::StarknetRpcApiError;
+use mp_rpc::TransactionBundleResult;
+use mp_utils::service::{ServiceStatus, ServiceStatuses};
+use mp_utils::spawn_execution_task;
+use starknet_core::types::{
+    BlockId, BroadcastedInvokeTransaction, BroadcastedTransaction, ContractClass, EmittedEvent, Felt, Hash256,
+};
+
+pub use add_invoke_transaction_with_simulation::*;
+pub use add_transaction_bundle::*;
+pub use api_keys::*;
+pub use get_block_with_state_diff::*;
+pub use get_classes::*;
+pub use get_da_blob::*;
+pub use get_event_proof::*;
+pub use get_l1_to_l2_message_status::*;
+pub use get_proving_status::*;
+pub use get_receipt_proof::*;
+pub use merkle_trie::MerkleNode;
+pub use submit_block::*;
+pub use subscribe_events::*;
+pub use subscribe_storage::*;
+pub use trace_transaction_resources::*;
+
+use crate::Starknet;
+
+#[rpc(server, namespace = "madara")]
+pub trait MadaraReadRpcApi {
+    /// Get the cancellation status of an L1->L2 message, given its hash.
+    #[method(name = "getL1ToL2MessageStatus")]
+    fn get_l1_to_l2_message_status(&self, message_hash: Hash256) -> RpcResult<L1ToL2MessageStatus>;
+
+    /// Get the state diff of a block encoded for data availability, as published on L1.
+    #[method(name = "getDaBlob")]
+    fn get_da_blob(&self, block_id: BlockId) -> RpcResult<Vec<Felt>>;
+
+    /// Get a block with its transactions, receipts and state diff.
+    #[method(name = "getBlockWithStateDiff")]
+    fn get_block_with_state_diff(&self, block_id: BlockId) -> RpcResult<BlockWithStateDiff>;
+
+    /// Get several contract classes at once, `null` for the classes not declared at the given block.
+    #[method(name = "getClasses")]
+    fn get_classes(&self, block_id: BlockId, class_hashes: Vec<Felt>) -> RpcResult<Vec<Option<ContractClass>>>;
+
+    /// Get the resources used by each call of a transaction, including the syscalls it made, for profiling contracts.
+    #[method(name = "traceTransactionResources")]
+    async fn trace_transaction_resources(&self, transaction_hash: Felt) -> RpcResult<TransactionCallResources>;
+
+    /// Get the Merkle proof of the receipt of a transaction against the receipt commitment of its block.
+    #[method(name = "getReceiptProof")]
+    fn get_receipt_proof(&self, transaction_hash: Felt) -> RpcResult<ReceiptProof>;
+
+    /// Get the Merkle proof of an event, given by the index of the event among the events of its transaction,
+    /// against the event commitment of its block.
+    #[method(name = "getEventProof")]
+    fn get_event_proof(&self, transaction_hash: Felt, event_index: u64) -> RpcResult<EventProof>;
+
+    /// Stream the events matching the filter, from the blocks since `from_block` and then from the new blocks, over a
+    /// websocket connection.
+    #[subscription(name = "subscribeEvents", unsubscribe = "unsubscribeEvents", item = EmittedEvent)]
+    async fn subscribe_events(
+        &self,
+        from_block: Option<BlockId>,
+        from_address: Option<Felt>,
+        keys: Option<Vec<Vec<Felt>>>,
+    ) -> SubscriptionResult;
+
+    /// Stream the storage writes to a contract as the blocks are stored, from the pending and closed blocks, over a
+    /// websocket connection.
+    #[subscription(name = "subscribeStorage", unsubscribe = "unsubscribeStorage", item = StorageUpdate)]
+    async fn subscribe_storage(&self, contract_address: Felt, keys: Option<Vec<Felt>>) -> SubscriptionResult;
+}
+
+#[rpc(server, namespace = "madara")]
+pub trait MadaraWriteRpcApi {
+    /// Submit an invoke transaction after simulating it on top of the pending block. Returns the outcome of the
+    /// simulation with the transaction hash, and rejects the transactions that cannot be included in a block.
+    #[method(name = "addInvokeTransactionWithSimulation")]
+    async fn add_invoke_transaction_with_simulation(
+        &self,
+        invoke_transaction: BroadcastedInvokeTransaction,
+    ) -> RpcResult<InvokeTransactionWithSimulationResult>;
+
+    /// Submit an ordered bundle of transactions, included consecutively in one block or not at all. Returns once the
+    /// bundle is included, with the number of its block, or rejected.
+    #[method(name = "addTransactionBundle")]
+    async fn add_transaction_bundle(
+        &self,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> RpcResult<TransactionBundleResult>;
+}
+
+/// Node operator methods, only exposed with `--rpc-methods unsafe`.
+#[rpc(server, namespace = "madara")]
+pub trait MadaraAdminRpcApi {
+    /// Get the health of the execution extensions of the node.
+    #[method(name = "exexStatus")]
+    fn exex_status(&self) -> RpcResult<Vec<ServiceStatus>>;
+
+    /// Get the health of the services of the node.
+    #[method(name = "serviceStatus")]
+    fn service_status(&self) -> RpcResult<Vec<ServiceStatus>>;
+
+    /// Re-read the node configuration and apply the settings that can change while the node is running. Returns the
+    /// names of the settings that changed.
+    #[method(name = "reloadConfig")]
+    fn reload_config(&self) -> RpcResult<Vec<String>>;
+
+    /// Get the proving jobs of the blocks from `from_block`, when the node submits them to an external prover.
+    #[method(name = "provingStatus")]
+    fn proving_status(&self, from_block: u64) -> RpcResult<Vec<ProvingJobInfo>>;
+
+    /// Get the API keys of the RPC server, with the number of calls made with them today.
+    #[method(name = "apiKeys")]
+    fn api_keys(&self) -> RpcResult<Vec<ApiKeyInfo>>;
+
+    /// Add an API key to the RPC server, or move it to another tier. The tiers are defined in the `--rpc-api-keys`
+    /// file, and the keys added with this method are forgotten when the node restarts.
+    #[method(name = "setApiKey")]
+    fn set_api_key(&self, key: String, tier: String) -> RpcResult<()>;
+
+    /// Remove an API key from the RPC server. Returns whether the key existed.
+    #[method(name = "removeApiKey")]
+    fn remove_api_key(&self, key: String) -> RpcResult<bool>;
+
+    /// Execute a list of transactions ordered by an external block builder, in this order, as the next block instead
+    /// of the transactions of the mempool. The block is started right away when the pending block is still empty.
+    /// Requires `--external-block-builder`.
+    #[method(name = "submitBlock")]
+    async fn submit_block(&self, transactions: Vec<BroadcastedTransaction>) -> RpcResult<SubmittedBlock>;
+
+    /// Start rebuilding the global tries from the flat state in the background, for the given contracts or for all the
+    /// contracts and classes. The node keeps running meanwhile, see `madara_trieRebuildStatus` for the progress.
+    #[method(name = "rebuildTries")]
+    fn rebuild_tries(&self, contracts: Option<Vec<Felt>>) -> RpcResult<()>;
+
+    /// Get the status of the last trie rebuild, `null` if none was started since the node started.
+    #[method(name = "trieRebuildStatus")]
+    fn trie_rebuild_status(&self) -> RpcResult<Option<TrieRebuildStatus>>;
+}
+
+/// Applies the node configuration again, see [`MadaraAdminRpcApiServer::reload_config`].
+pub trait ConfigReloader: Send + Sync {
+    fn reload(&self) -> anyhow::Result<Vec<String>>;
+}
+
+/// State of the node operator methods.
+#[derive(Clone)]
+pub struct MadaraAdmin {
+    pub backend: Arc<MadaraBackend>,
+    pub exex_statuses: ServiceStatuses,
+    pub service_statuses: ServiceStatuses,
+    pub config_reloader: Arc<dyn ConfigReloader>,
+    pub api_keys: Arc<dyn ApiKeyManager>,
+    pub block_builder: Option<BuilderHandle>,
+    pub block_importer: Arc<BlockImporter>,
+}
+
+#[async_trait]
+impl MadaraReadRpcApiServer for Starknet {
+    fn get_l1_to_l2_message_status(&self, message_hash: Hash256) -> RpcResult<L1ToL2MessageStatus> {
+        Ok(get_l1_to_l2_message_status(self, message_hash)?)
+    }
+
+    fn get_da_blob(&self, block_id: BlockId) -> RpcResult<Vec<Felt>> {
+        Ok(get_da_blob(self, block_id)?)
+    }
+
+    fn get_block_with_state_diff(&self, block_id: BlockId) -> RpcResult<BlockWithStateDiff> {
+        Ok(get_block_with_state_diff(self, block_id)?)
+    }
+
+    fn get_classes(&self, block_id: BlockId, class_hashes: Vec<Felt>) -> RpcResult<Vec<Option<ContractClass>>> {
+        Ok(get_classes(self, block_id, class_hashes)?)
+    }
+
+    async fn trace_transaction_resources(&self, transaction_hash: Felt) -> RpcResult<TransactionCallResources> {
+        let starknet = self.clone();
+        Ok(spawn_execution_task(move || trace_transaction_resources(&starknet, transaction_hash)).await?)
+    }
+
+    fn get_receipt_proof(&self, transaction_hash: Felt) -> RpcResult<ReceiptProof> {
+        Ok(get_receipt_proof(self, transaction_hash)?)
+    }
+
+    fn get_event_proof(&self, transaction_hash: Felt, event_index: u64) -> RpcResult<EventProof> {
+        Ok(get_event_proof(self, transaction_hash, event_index)?)
+    }
+
+    async fn subscribe_events(
+        &self,
+        pending: PendingSubscriptionSink,
+        from_block: Option<BlockId>,
+        from_address: Option<Felt>,
+        keys: Option<Vec<Vec<Felt>>>,
+    ) -> SubscriptionResult {
+        subscribe_events(self, pending, from_block, from_address, keys).await;
+        Ok(())
+    }
+
+    async fn subscribe_storage(
+        &self,
+        pending: PendingSubscriptionSink,
+        contract_address: Felt,
+        keys: Option<Vec<Felt>>,
+    ) -> SubscriptionResult {
+        subscribe_storage(self, pending, contract_address, keys).await;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MadaraWriteRpcApiServer for Starknet {
+    async fn add_invoke_transaction_with_simulation(
+        &self,
+        invoke_transaction: BroadcastedInvokeTransaction,
+    ) -> RpcResult<InvokeTransactionWithSimulationResult> {
+        add_invoke_transaction_with_simulation(self, invoke_transaction).await
+    }
+
+    async fn add_transaction_bundle(
+        &self,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> RpcResult<TransactionBundleResult> {
+        add_transaction_bundle(self, transactions).await
+    }
+}
+
+#[async_trait]
+impl MadaraAdminRpcApiServer for MadaraAdmin {
+    fn exex_status(&self) -> RpcResult<Vec<ServiceStatus>> {
+        Ok(self.exex_statuses.get())
+    }
+
+    fn service_status(&self) -> RpcResult<Vec<ServiceStatus>> {
+        Ok(self.service_statuses.get())
+    }
+
+    fn reload_config(&self) -> RpcResult<Vec<String>> {
+        Ok(self
+            .config_reloader
+            .reload()
+            .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Reloading config: {err:#}") })?)
+    }
+
+    fn proving_status(&self, from_block: u64) -> RpcResult<Vec<ProvingJobInfo>> {
+        Ok(get_proving_status(&self.backend, from_block)?)
+    }
+
+    fn api_keys(&self) -> RpcResult<Vec<ApiKeyInfo>> {
+        Ok(self.api_keys.api_keys())
+    }
+
+    fn set_api_key(&self, key: String, tier: String) -> RpcResult<()> {
+        Ok(self
+            .api_keys
+            .set_api_key(key, &tier)
+            .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Setting API key: {err:#}") })?)
+    }
+
+    fn remove_api_key(&self, key: String) -> RpcResult<bool> {
+        Ok(self.api_keys.remove_api_key(&key))
+    }
+
+    async fn submit_block(&self, transactions: Vec<BroadcastedTransaction>) -> RpcResult<SubmittedBlock> {
+        Ok(submit_block(self.block_builder.as_ref(), transactions).await?)
+    }
+
+    fn rebuild_tries(&self, contracts: Option<Vec<Felt>>) -> RpcResult<()> {
+        Ok(self
+            .block_importer
+            .start_trie_rebuild(contracts)
+            .map_err(|err| StarknetRpcApiError::ErrUnexpectedError { data: format!("Rebuilding tries: {err:#}") })?)
+    }
+
+    fn trie_rebuild_status(&self) -> RpcResult<Option<TrieRebuildStatus>> {
+        Ok(self.block_importer.trie_rebuild_status())
+    }
+}
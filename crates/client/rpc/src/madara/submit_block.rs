@@ -0,0 +1,43 @@
+use mc_mempool::block_production::Error;
+use mc_mempool::{BuilderBlockOutcome, BuilderHandle};
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use serde::{Deserialize, Serialize};
+use starknet_core::types::{BroadcastedTransaction, Felt};
+
+/// Outcome of a block submitted by an external block builder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmittedBlock {
+    /// The pending block the transactions were executed in.
+    pub block_number: u64,
+    /// Hashes of the transactions added to the block, in order, including the reverted ones.
+    pub included: Vec<Felt>,
+    /// Hashes of the transactions which failed validation, or did not fit in the block.
+    pub rejected: Vec<Felt>,
+}
+
+impl From<BuilderBlockOutcome> for SubmittedBlock {
+    fn from(BuilderBlockOutcome { block_number, included, rejected }: BuilderBlockOutcome) -> Self {
+        Self { block_number, included, rejected }
+    }
+}
+
+/// Execute the transactions, in the given order, as the next block instead of the transactions of the mempool.
+///
+/// ### Returns
+///
+/// Once the transactions are executed in the pending block, which transactions were added to it.
+pub async fn submit_block(
+    block_builder: Option<&BuilderHandle>,
+    transactions: Vec<BroadcastedTransaction>,
+) -> StarknetRpcResult<SubmittedBlock> {
+    let block_builder = block_builder.ok_or_else(|| StarknetRpcApiError::ErrUnexpectedError {
+        data: "External block building is disabled, enable it with `--external-block-builder`".into(),
+    })?;
+    match block_builder.submit_block(transactions).await {
+        Ok(outcome) => Ok(outcome.into()),
+        Err(err @ Error::InvalidBuilderBlock(_)) => {
+            Err(StarknetRpcApiError::ValidationFailure { error: format!("{err:#}") })
+        }
+        Err(err) => Err(StarknetRpcApiError::ErrUnexpectedError { data: format!("Submitting block: {err:#}") }),
+    }
+}
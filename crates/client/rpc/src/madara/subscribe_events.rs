@@ -0,0 +1,179 @@
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage, SubscriptionSink};
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use mp_rpc::utils::ResultExt;
+use starknet_core::types::{BlockId, BlockTag, EmittedEvent, Felt};
+
+use crate::constants::{MAX_EVENTS_CHUNK_SIZE, MAX_EVENTS_KEYS};
+use crate::versions::v0_7_1::methods::read::get_events::{event_match_filter, get_block_events};
+use crate::Starknet;
+
+/// Maximum number of blocks read at once when catching up with the chain tip, for filters matching few events.
+const MAX_BLOCKS_PER_CHUNK: u64 = 1000;
+
+/// Streams the events matching the filter, from the closed blocks starting at `from_block`, then from the blocks
+/// closed while the subscription is open.
+///
+/// The historical events are read in chunks of about [`MAX_EVENTS_CHUNK_SIZE`] events, and the next chunk is only
+/// read once the previous one was sent to the client. Events are sent in block order, pending blocks excluded. When
+/// the chain is reverted, streaming resumes from the new chain tip: the events of the reverted blocks are not
+/// retracted.
+///
+/// ### Arguments
+///
+/// * `from_block` - First block of the historical events, the latest block when not set. `pending` only streams the
+///   events of the blocks closed from now on.
+/// * `from_address` - Only stream the events emitted by this contract.
+/// * `keys` - Only stream the events matching these keys, as in `starknet_getEvents`.
+///
+/// ### Errors
+///
+/// BlockNotFound : If the specified block does not exist.
+/// TooManyKeysInFilter : If more than [`MAX_EVENTS_KEYS`] keys are given.
+pub async fn subscribe_events(
+    starknet: &Starknet,
+    pending: PendingSubscriptionSink,
+    from_block: Option<BlockId>,
+    from_address: Option<Felt>,
+    keys: Option<Vec<Vec<Felt>>>,
+) {
+    let keys = keys.unwrap_or_default();
+    // Subscribe before reading the tip, to not miss the blocks closed in-between.
+    let mut closed_blocks = starknet.backend.subscribe_closed_blocks();
+    let mut reverts = starknet.backend.subscribe_block_reverts();
+
+    let next_block = match first_block(starknet, from_block, &keys) {
+        Ok(next_block) => next_block,
+        Err(err) => {
+            pending.reject(err).await;
+            return;
+        }
+    };
+    let Ok(sink) = pending.accept().await else {
+        return;
+    };
+
+    let mut next_block = next_block;
+    loop {
+        // Catch up with the chain tip.
+        loop {
+            let chunk_start = next_block;
+            let events = match events_chunk(starknet, &mut next_block, from_address, &keys) {
+                Ok(events) => events,
+                Err(err) => {
+                    log::error!("Events subscription: {err}");
+                    return;
+                }
+            };
+            if next_block == chunk_start {
+                break;
+            }
+            if sink.is_closed() {
+                return;
+            }
+            for event in events {
+                if !send_event(&sink, &event).await {
+                    return;
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = sink.closed() => return,
+            changed = closed_blocks.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+            }
+            revert = reverts.recv() => {
+                if let Ok(revert) = revert {
+                    next_block = next_block.min(revert.to + 1);
+                }
+            }
+        }
+    }
+}
+
+fn first_block(starknet: &Starknet, from_block: Option<BlockId>, keys: &[Vec<Felt>]) -> StarknetRpcResult<u64> {
+    if keys.len() > MAX_EVENTS_KEYS {
+        return Err(StarknetRpcApiError::TooManyKeysInFilter);
+    }
+    let latest_block_n =
+        starknet.backend.get_latest_block_n().or_internal_server_error("Error getting the latest block number")?;
+    match from_block {
+        Some(BlockId::Tag(BlockTag::Pending)) => Ok(latest_block_n.map_or(0, |n| n + 1)),
+        Some(block_id) => starknet.get_block_n(&block_id),
+        None => Ok(latest_block_n.unwrap_or(0)),
+    }
+}
+
+/// Reads the matching events of the closed blocks from `next_block`, until the chain tip is reached or the chunk is
+/// full. Blocks are read whole: a chunk may exceed [`MAX_EVENTS_CHUNK_SIZE`] by the events of its last block.
+/// `next_block` is left unchanged once the chain tip is reached.
+fn events_chunk(
+    starknet: &Starknet,
+    next_block: &mut u64,
+    from_address: Option<Felt>,
+    keys: &[Vec<Felt>],
+) -> StarknetRpcResult<Vec<EmittedEvent>> {
+    let Some(latest_block_n) =
+        starknet.backend.get_latest_block_n().or_internal_server_error("Error getting the latest block number")?
+    else {
+        return Ok(vec![]);
+    };
+
+    let mut events = vec![];
+    let end = latest_block_n.min(next_block.saturating_add(MAX_BLOCKS_PER_CHUNK - 1));
+    while *next_block <= end && events.len() < MAX_EVENTS_CHUNK_SIZE {
+        let block = starknet.get_block(&BlockId::Number(*next_block))?;
+        events.extend(
+            get_block_events(starknet, &block)
+                .into_iter()
+                .filter(|event| event_match_filter(event, from_address, keys)),
+        );
+        *next_block += 1;
+    }
+    Ok(events)
+}
+
+/// Returns `false` when the client is gone.
+async fn send_event(sink: &SubscriptionSink, event: &EmittedEvent) -> bool {
+    let message = match SubscriptionMessage::from_json(event) {
+        Ok(message) => message,
+        Err(err) => {
+            log::error!("Events subscription: serializing event: {err}");
+            return false;
+        }
+    };
+    sink.send(message).await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{sample_chain_for_state_updates, SampleChainForStateUpdates};
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_first_block(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
+        let (_, rpc) = sample_chain_for_state_updates;
+
+        assert_eq!(first_block(&rpc, None, &[]), Ok(2));
+        assert_eq!(first_block(&rpc, Some(BlockId::Number(1)), &[]), Ok(1));
+        assert_eq!(first_block(&rpc, Some(BlockId::Tag(BlockTag::Pending)), &[]), Ok(3));
+        assert_eq!(first_block(&rpc, Some(BlockId::Number(3)), &[]), Err(StarknetRpcApiError::BlockNotFound));
+        assert_eq!(
+            first_block(&rpc, None, &vec![vec![]; MAX_EVENTS_KEYS + 1]),
+            Err(StarknetRpcApiError::TooManyKeysInFilter)
+        );
+    }
+
+    #[rstest]
+    fn test_events_chunk_reaches_the_tip(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
+        let (_, rpc) = sample_chain_for_state_updates;
+
+        let mut next_block = 0;
+        events_chunk(&rpc, &mut next_block, None, &[]).unwrap();
+        assert_eq!(next_block, 3);
+        assert_eq!(events_chunk(&rpc, &mut next_block, None, &[]), Ok(vec![]));
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage, SubscriptionSink};
+use mc_db::db_block_id::DbBlockId;
+use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
+use mp_rpc::utils::ResultExt;
+use mp_state_update::{StateDiff, StorageEntry};
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Felt;
+
+use crate::constants::MAX_STORAGE_KEYS;
+use crate::Starknet;
+
+/// Storage writes of a block to the subscribed contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageUpdate {
+    /// `None` for the pending block.
+    pub block_hash: Option<Felt>,
+    /// `None` for the pending block.
+    pub block_number: Option<u64>,
+    pub storage_entries: Vec<StorageEntry>,
+}
+
+/// Streams the storage writes to a contract, from the state diffs of the blocks as they are stored.
+///
+/// The writes of the pending block are sent as it is updated, each value being sent once. When the block is closed,
+/// all of its writes are sent again with the block hash and number. Blocks without any matching write are skipped.
+///
+/// ### Arguments
+///
+/// * `contract_address` - The contract whose storage is watched.
+/// * `keys` - Only stream the writes to these storage keys, all the writes when not set.
+///
+/// ### Errors
+///
+/// TooManyKeysInFilter : If more than [`MAX_STORAGE_KEYS`] keys are given.
+pub async fn subscribe_storage(
+    starknet: &Starknet,
+    pending: PendingSubscriptionSink,
+    contract_address: Felt,
+    keys: Option<Vec<Felt>>,
+) {
+    let keys = keys.unwrap_or_default();
+    if keys.len() > MAX_STORAGE_KEYS {
+        pending.reject(StarknetRpcApiError::TooManyKeysInFilter).await;
+        return;
+    }
+
+    let mut closed_blocks = starknet.backend.subscribe_closed_blocks();
+    let mut pending_block_updates = starknet.backend.subscribe_pending_block_updates();
+    let mut reverts = starknet.backend.subscribe_block_reverts();
+    let next_block = match starknet.backend.get_latest_block_n() {
+        Ok(latest_block_n) => latest_block_n.map_or(0, |n| n + 1),
+        Err(err) => {
+            pending.reject(StarknetRpcApiError::from(err)).await;
+            return;
+        }
+    };
+    let Ok(sink) = pending.accept().await else {
+        return;
+    };
+
+    let mut next_block = next_block;
+    // Values of the pending block already sent.
+    let mut sent_pending: HashMap<Felt, Felt> = HashMap::new();
+    loop {
+        let res = tokio::select! {
+            _ = sink.closed() => return,
+            changed = closed_blocks.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                sent_pending.clear();
+                send_closed_blocks(starknet, &sink, &mut next_block, contract_address, &keys).await
+            }
+            changed = pending_block_updates.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                send_pending_block(starknet, &sink, &mut sent_pending, contract_address, &keys).await
+            }
+            revert = reverts.recv() => {
+                if let Ok(revert) = revert {
+                    next_block = next_block.min(revert.to + 1);
+                    sent_pending.clear();
+                }
+                Ok(true)
+            }
+        };
+        match res {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(err) => {
+                log::error!("Storage subscription: {err}");
+                return;
+            }
+        }
+    }
+}
+
+/// Sends the writes of the closed blocks from `next_block`. Returns `false` when the client is gone.
+async fn send_closed_blocks(
+    starknet: &Starknet,
+    sink: &SubscriptionSink,
+    next_block: &mut u64,
+    contract_address: Felt,
+    keys: &[Felt],
+) -> StarknetRpcResult<bool> {
+    let Some(latest_block_n) =
+        starknet.backend.get_latest_block_n().or_internal_server_error("Error getting the latest block number")?
+    else {
+        return Ok(true);
+    };
+
+    while *next_block <= latest_block_n {
+        let block_id = DbBlockId::Number(*next_block);
+        let state_diff = starknet
+            .backend
+            .get_block_state_diff(&block_id)
+            .or_internal_server_error("Error getting block state diff")?
+            .ok_or(StarknetRpcApiError::BlockNotFound)?;
+        let storage_entries = storage_entries(&state_diff, contract_address, keys);
+        if !storage_entries.is_empty() {
+            let block_hash = starknet
+                .backend
+                .get_block_hash(&block_id)
+                .or_internal_server_error("Error getting block hash")?
+                .ok_or(StarknetRpcApiError::BlockNotFound)?;
+            let update =
+                StorageUpdate { block_hash: Some(block_hash), block_number: Some(*next_block), storage_entries };
+            if !send_update(sink, &update).await {
+                return Ok(false);
+            }
+        }
+        *next_block += 1;
+    }
+    Ok(true)
+}
+
+/// Sends the writes of the pending block that were not sent yet. Returns `false` when the client is gone.
+async fn send_pending_block(
+    starknet: &Starknet,
+    sink: &SubscriptionSink,
+    sent_pending: &mut HashMap<Felt, Felt>,
+    contract_address: Felt,
+    keys: &[Felt],
+) -> StarknetRpcResult<bool> {
+    let Some(state_diff) = starknet
+        .backend
+        .get_block_state_diff(&DbBlockId::Pending)
+        .or_internal_server_error("Error getting pending block state diff")?
+    else {
+        return Ok(true);
+    };
+
+    let storage_entries: Vec<_> = storage_entries(&state_diff, contract_address, keys)
+        .into_iter()
+        .filter(|entry| sent_pending.get(&entry.key) != Some(&entry.value))
+        .collect();
+    if storage_entries.is_empty() {
+        return Ok(true);
+    }
+    sent_pending.extend(storage_entries.iter().map(|entry| (entry.key, entry.value)));
+    Ok(send_update(sink, &StorageUpdate { block_hash: None, block_number: None, storage_entries }).await)
+}
+
+/// Writes of a state diff to the contract, restricted to `keys` when not empty.
+fn storage_entries(state_diff: &StateDiff, contract_address: Felt, keys: &[Felt]) -> Vec<StorageEntry> {
+    state_diff
+        .storage_diffs
+        .iter()
+        .filter(|diff| diff.address == contract_address)
+        .flat_map(|diff| diff.storage_entries.iter())
+        .filter(|entry| keys.is_empty() || keys.contains(&entry.key))
+        .cloned()
+        .collect()
+}
+
+/// Returns `false` when the client is gone.
+async fn send_update(sink: &SubscriptionSink, update: &StorageUpdate) -> bool {
+    let message = match SubscriptionMessage::from_json(update) {
+        Ok(message) => message,
+        Err(err) => {
+            log::error!("Storage subscription: serializing update: {err}");
+            return false;
+        }
+    };
+    sink.send(message).await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{sample_chain_for_state_updates, SampleChainForStateUpdates};
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_storage_entries(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
+        let (SampleChainForStateUpdates { state_diffs, contracts, keys, values, .. }, _) =
+            sample_chain_for_state_updates;
+
+        // The pending block writes two keys of contract 0.
+        assert_eq!(
+            storage_entries(&state_diffs[3], contracts[0], &[]),
+            vec![StorageEntry { key: keys[1], value: values[0] }, StorageEntry { key: keys[0], value: values[2] }]
+        );
+        assert_eq!(
+            storage_entries(&state_diffs[3], contracts[0], &[keys[0]]),
+            vec![StorageEntry { key: keys[0], value: values[2] }]
+        );
+        assert_eq!(storage_entries(&state_diffs[3], contracts[1], &[]), vec![]);
+        assert_eq!(storage_entries(&state_diffs[2], contracts[1], &[keys[1]]), vec![]);
+    }
+}
@@ -0,0 +1,29 @@
+use mc_exec::{execution_result_to_call_resources, TransactionCallResources};
+use mp_rpc::errors::StarknetRpcResult;
+use starknet_core::types::Felt;
+
+use crate::versions::v0_7_1::methods::trace::trace_transaction::re_execute_transaction;
+use crate::Starknet;
+
+/// Get the resources used by each call of a transaction, by re-executing it.
+///
+/// ### Arguments
+///
+/// * `transaction_hash` - The hash of the transaction.
+///
+/// ### Returns
+///
+/// The call tree of the transaction, as in `starknet_traceTransaction`. The resources of each call are given with and
+/// without those of its inner calls, along with the syscalls it made.
+///
+/// ### Errors
+///
+/// TxnHashNotFound : If the transaction does not exist.
+/// UnsupportedTxnVersion : If the transaction is in a block older than Starknet 0.13.0, which cannot be re-executed.
+pub fn trace_transaction_resources(
+    starknet: &Starknet,
+    transaction_hash: Felt,
+) -> StarknetRpcResult<TransactionCallResources> {
+    let execution_result = re_execute_transaction(starknet, transaction_hash)?;
+    Ok(execution_result_to_call_resources(&execution_result))
+}
@@ -0,0 +1,63 @@
+use jsonrpsee::core::{async_trait, RpcResult};
+use mp_rpc::errors::StarknetRpcApiError;
+use mp_rpc::{AddTransactionProvider, TransactionBundleResult};
+use starknet_core::types::{
+    BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction,
+    BroadcastedTransaction, DeclareTransactionResult, DeployAccountTransactionResult, InvokeTransactionResult,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// This [`AddTransactionProvider`] rejects the received transactions while `halted` is set, and forwards them
+/// to the inner provider otherwise.
+pub struct HaltableAddTxProvider {
+    inner: Arc<dyn AddTransactionProvider>,
+    halted: Arc<AtomicBool>,
+}
+
+impl HaltableAddTxProvider {
+    pub fn new(inner: Arc<dyn AddTransactionProvider>, halted: Arc<AtomicBool>) -> Self {
+        Self { inner, halted }
+    }
+
+    fn check_not_halted(&self) -> Result<(), StarknetRpcApiError> {
+        if self.halted.load(Ordering::Relaxed) {
+            return Err(StarknetRpcApiError::ErrUnexpectedError {
+                data: "Transaction submission is halted: the local state root does not match the settled one".into(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AddTransactionProvider for HaltableAddTxProvider {
+    async fn add_declare_transaction(
+        &self,
+        declare_transaction: BroadcastedDeclareTransaction,
+    ) -> RpcResult<DeclareTransactionResult> {
+        self.check_not_halted()?;
+        self.inner.add_declare_transaction(declare_transaction).await
+    }
+    async fn add_deploy_account_transaction(
+        &self,
+        deploy_account_transaction: BroadcastedDeployAccountTransaction,
+    ) -> RpcResult<DeployAccountTransactionResult> {
+        self.check_not_halted()?;
+        self.inner.add_deploy_account_transaction(deploy_account_transaction).await
+    }
+    async fn add_invoke_transaction(
+        &self,
+        invoke_transaction: BroadcastedInvokeTransaction,
+    ) -> RpcResult<InvokeTransactionResult> {
+        self.check_not_halted()?;
+        self.inner.add_invoke_transaction(invoke_transaction).await
+    }
+    async fn add_transaction_bundle(
+        &self,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> RpcResult<TransactionBundleResult> {
+        self.check_not_halted()?;
+        self.inner.add_transaction_bundle(transactions).await
+    }
+}
@@ -2,10 +2,10 @@ use jsonrpsee::core::{async_trait, RpcResult};
 use mc_mempool::Mempool;
 use mc_mempool::MempoolProvider;
 use mp_rpc::errors::StarknetRpcApiError;
-use mp_rpc::AddTransactionProvider;
+use mp_rpc::{AddTransactionProvider, TransactionBundleResult};
 use starknet_core::types::{
     BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction,
-    DeclareTransactionResult, DeployAccountTransactionResult, InvokeTransactionResult,
+    BroadcastedTransaction, DeclareTransactionResult, DeployAccountTransactionResult, InvokeTransactionResult,
 };
 use std::sync::Arc;
 
@@ -40,4 +40,18 @@ impl AddTransactionProvider for MempoolAddTxProvider {
     ) -> RpcResult<InvokeTransactionResult> {
         Ok(self.mempool.accept_invoke_tx(invoke_transaction).map_err(StarknetRpcApiError::from)?)
     }
+    async fn add_transaction_bundle(
+        &self,
+        transactions: Vec<BroadcastedTransaction>,
+    ) -> RpcResult<TransactionBundleResult> {
+        let (transaction_hashes, receiver) =
+            self.mempool.accept_bundle(transactions).map_err(StarknetRpcApiError::from)?;
+        let block_number = receiver
+            .await
+            .map_err(|_| StarknetRpcApiError::ErrUnexpectedError {
+                data: "Block production stopped before executing the bundle".into(),
+            })?
+            .map_err(|rejection| StarknetRpcApiError::ValidationFailure { error: format!("{rejection}") })?;
+        Ok(TransactionBundleResult { block_number, transaction_hashes })
+    }
 }
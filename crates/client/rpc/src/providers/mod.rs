@@ -1,5 +1,7 @@
 pub mod forward_to_provider;
+pub mod haltable;
 pub mod mempool;
 
 pub use forward_to_provider::*;
+pub use haltable::*;
 pub use mempool::*;
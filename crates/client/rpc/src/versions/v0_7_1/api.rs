@@ -59,7 +59,7 @@ pub trait StarknetReadRpcApi {
 
     /// Call a contract function at a given block id
     #[method(name = "call")]
-    fn call(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<Vec<Felt>>;
+    async fn call(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<Vec<Felt>>;
 
     /// Get the chain id
     #[method(name = "chainId")]
@@ -21,7 +21,7 @@ use mp_rpc::utils::ResultExt;
 /// # Returns
 ///
 /// * `fee_estimate` - fee estimate in gwei
-pub async fn estimate_fee(
+pub fn estimate_fee(
     starknet: &Starknet,
     request: Vec<BroadcastedTransaction>,
     simulation_flags: Vec<SimulationFlagForEstimateFee>,
@@ -11,10 +11,13 @@ use crate::versions::v0_7_1::methods::trace::trace_transaction::FALLBACK_TO_SEQU
 use crate::Starknet;
 use mp_rpc::errors::StarknetRpcApiError;
 use mp_rpc::errors::StarknetRpcResult;
-use mp_rpc::utils::OptionExt;
+use mp_rpc::utils::ResultExt;
 
 /// Estimate the L2 fee of a message sent on L1
 ///
+/// The message is converted into the L1 handler transaction the sequencer would execute, and executed on top of the
+/// state of the requested block.
+///
 /// # Arguments
 ///
 /// * `message` - the message to estimate
@@ -29,7 +32,7 @@ use mp_rpc::utils::OptionExt;
 /// BlockNotFound : If the specified block does not exist.
 /// ContractNotFound : If the specified contract address does not exist.
 /// ContractError : If there is an error with the contract.
-pub async fn estimate_message_fee(
+pub fn estimate_message_fee(
     starknet: &Starknet,
     message: MsgFromL1,
     block_id: BlockId,
@@ -40,15 +43,19 @@ pub async fn estimate_message_fee(
         return Err(StarknetRpcApiError::UnsupportedTxnVersion);
     }
 
-    let exec_context = ExecutionContext::new_in_block(Arc::clone(&starknet.backend), &block_info)?;
+    if starknet
+        .backend
+        .get_contract_class_hash_at(&block_id, &message.to_address)
+        .or_internal_server_error("Error getting contract class hash at")?
+        .is_none()
+    {
+        return Err(StarknetRpcApiError::ContractNotFound);
+    }
 
-    let transaction = convert_message_into_transaction(message, starknet.chain_id());
-    let execution_result = exec_context
-        .re_execute_transactions([], [transaction], false, true)?
-        .pop()
-        .ok_or_internal_server_error("Failed to convert BroadcastedTransaction to AccountTransaction")?;
+    let exec_context = ExecutionContext::new_at_block_end(Arc::clone(&starknet.backend), &block_info)?;
 
-    let fee_estimate = exec_context.execution_result_to_fee_estimate(&execution_result);
+    let transaction = convert_message_into_transaction(message, starknet.chain_id())?;
+    let fee_estimate = exec_context.estimate_message_fee(transaction)?;
 
     Ok(fee_estimate)
 }
@@ -56,15 +63,90 @@ pub async fn estimate_message_fee(
 pub fn convert_message_into_transaction(
     message: MsgFromL1,
     chain_id: Felt,
-) -> blockifier::transaction::transaction_execution::Transaction {
+) -> StarknetRpcResult<blockifier::transaction::transactions::L1HandlerTransaction> {
     let l1_handler: L1HandlerTransaction = message.into();
     let tx_hash = l1_handler.compute_hash(chain_id, false, false);
-    let tx: starknet_api::transaction::L1HandlerTransaction = l1_handler.try_into().unwrap();
+    let tx: starknet_api::transaction::L1HandlerTransaction =
+        l1_handler.try_into().map_err(|err| StarknetRpcApiError::ErrUnexpectedError {
+            data: format!("Converting the message into an L1 handler transaction: {err}"),
+        })?;
 
-    let tx = blockifier::transaction::transactions::L1HandlerTransaction {
+    // The fee paid on L1 is not known here. It only needs to be non-zero for the transaction to be executed.
+    Ok(blockifier::transaction::transactions::L1HandlerTransaction {
         tx,
         tx_hash: TransactionHash(tx_hash),
         paid_fee_on_l1: Fee(1),
-    };
-    blockifier::transaction::transaction_execution::Transaction::L1HandlerTransaction(tx)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{rpc_test_setup, sample_chain_for_state_updates, SampleChainForStateUpdates};
+    use mc_block_import::{BlockImporter, BlockValidationContext};
+    use mc_devnet::{ChainGenesisDescription, InitiallyDeclaredClass};
+    use mc_metrics::MetricsRegistry;
+    use rstest::rstest;
+    use starknet_core::types::{BlockTag, EthAddress};
+    use starknet_core::utils::get_selector_from_name;
+
+    /// Its `deposit` L1 handler adds the amount to the balance.
+    const HELLO_CLASS_DEFINITION: &[u8] =
+        include_bytes!("../../../../../../../../cairo/target/dev/madara_contracts_HelloStarknet.contract_class.json");
+
+    fn message(to_address: Felt) -> MsgFromL1 {
+        MsgFromL1 {
+            from_address: EthAddress::from_hex("0x8453fc6cd1bcfe8d4dfc069c400b433054d47bdc").unwrap(),
+            to_address,
+            entry_point_selector: Felt::ONE,
+            payload: vec![Felt::ONE],
+        }
+    }
+
+    #[rstest]
+    fn test_estimate_message_fee_errors(sample_chain_for_state_updates: (SampleChainForStateUpdates, Starknet)) {
+        let (SampleChainForStateUpdates { contracts, .. }, rpc) = sample_chain_for_state_updates;
+
+        assert_eq!(
+            estimate_message_fee(&rpc, message(contracts[0]), BlockId::Number(3)),
+            Err(StarknetRpcApiError::BlockNotFound)
+        );
+        // The contract is only deployed in block 1.
+        assert_eq!(
+            estimate_message_fee(&rpc, message(contracts[1]), BlockId::Number(0)),
+            Err(StarknetRpcApiError::ContractNotFound)
+        );
+        assert_eq!(
+            estimate_message_fee(&rpc, message(Felt::from_hex_unchecked("0xdead")), BlockId::Tag(BlockTag::Pending)),
+            Err(StarknetRpcApiError::ContractNotFound)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimate_message_fee() {
+        let (backend, rpc) = rpc_test_setup();
+        let chain_config = backend.chain_config();
+        let hello_address = Felt::from_hex_unchecked("0x1234");
+        let hello_class = InitiallyDeclaredClass::new_sierra(HELLO_CLASS_DEFINITION).unwrap();
+        let mut genesis = ChainGenesisDescription::base_config(chain_config).unwrap();
+        genesis.deployed_contracts.insert(hello_address, hello_class.class_hash());
+        genesis.declared_classes.insert(hello_class);
+        let importer = BlockImporter::new(Arc::clone(&backend), &MetricsRegistry::dummy(), None, true).unwrap();
+        importer
+            .add_block(
+                genesis.build(chain_config).unwrap(),
+                BlockValidationContext::new(chain_config.chain_id.clone()).trust_class_hashes(true),
+            )
+            .await
+            .unwrap();
+
+        // The contract is deployed in block 0: the message is only executed when it is on top of the state of block 0,
+        // as the first transaction of block 1.
+        let message =
+            MsgFromL1 { entry_point_selector: get_selector_from_name("deposit").unwrap(), ..message(hello_address) };
+        let fee_estimate = estimate_message_fee(&rpc, message.clone(), BlockId::Number(0)).unwrap();
+        assert_ne!(fee_estimate.gas_consumed, Felt::ZERO);
+        assert_ne!(fee_estimate.overall_fee, Felt::ZERO);
+        assert_eq!(estimate_message_fee(&rpc, message, BlockId::Tag(BlockTag::Latest)), Ok(fee_estimate));
+    }
 }
@@ -94,7 +94,7 @@ pub async fn get_events(starknet: &Starknet, filter: EventFilterWithPage) -> Sta
 }
 
 #[inline]
-fn event_match_filter(event: &EmittedEvent, address: Option<Felt>, keys: &[Vec<Felt>]) -> bool {
+pub(crate) fn event_match_filter(event: &EmittedEvent, address: Option<Felt>, keys: &[Vec<Felt>]) -> bool {
     let match_from_address = address.map_or(true, |addr| addr == event.from_address);
     let match_keys = keys
         .iter()
@@ -122,7 +122,7 @@ fn block_range(
     Ok((from_block_n, to_block_n, latest_block_n))
 }
 
-fn get_block_events(_starknet: &Starknet, block: &MadaraMaybePendingBlock) -> Vec<EmittedEvent> {
+pub(crate) fn get_block_events(_starknet: &Starknet, block: &MadaraMaybePendingBlock) -> Vec<EmittedEvent> {
     let (block_hash, block_number) = match &block.info {
         MadaraMaybePendingBlockInfo::Pending(_) => (None, None),
         MadaraMaybePendingBlockInfo::NotPending(block) => (Some(block.block_hash), Some(block.header.block_number)),
@@ -2,7 +2,7 @@ use starknet_core::types::{Felt, Transaction};
 
 use crate::Starknet;
 use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
-use mp_rpc::utils::{OptionExt, ResultExt};
+use mp_rpc::utils::OptionExt;
 
 /// Get the details and status of a submitted transaction.
 ///
@@ -32,11 +32,8 @@ use mp_rpc::utils::{OptionExt, ResultExt};
 /// - `TOO_MANY_KEYS_IN_FILTER` if there are too many keys in the filter, which may exceed the
 ///   system's capacity.
 pub fn get_transaction_by_hash(starknet: &Starknet, transaction_hash: Felt) -> StarknetRpcResult<Transaction> {
-    let (block, tx_index) = starknet
-        .backend
-        .find_tx_hash_block(&transaction_hash)
-        .or_internal_server_error("Error getting block from tx hash")?
-        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+    let (block, tx_index) =
+        starknet.find_tx_hash_block(&transaction_hash)?.ok_or(StarknetRpcApiError::TxnHashNotFound)?;
     let transaction = block
         .inner
         .transactions
@@ -4,7 +4,6 @@ use starknet_core::types::{Felt, TransactionFinalityStatus, TransactionReceiptWi
 use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
 
 use crate::Starknet;
-use mp_rpc::utils::ResultExt;
 
 /// Get the transaction receipt by the transaction hash.
 ///
@@ -32,11 +31,8 @@ pub fn get_transaction_receipt(
     starknet: &Starknet,
     transaction_hash: Felt,
 ) -> StarknetRpcResult<TransactionReceiptWithBlockInfo> {
-    let (block, tx_index) = starknet
-        .backend
-        .find_tx_hash_block(&transaction_hash)
-        .or_internal_server_error("Error getting block from tx_hash")?
-        .ok_or(StarknetRpcApiError::TxnHashNotFound)?;
+    let (block, tx_index) =
+        starknet.find_tx_hash_block(&transaction_hash)?.ok_or(StarknetRpcApiError::TxnHashNotFound)?;
 
     let is_on_l1 = if let Some(block_n) = block.info.block_n() {
         block_n <= starknet.get_l1_last_confirmed_block()?
@@ -1,4 +1,5 @@
 use jsonrpsee::core::{async_trait, RpcResult};
+use mp_utils::spawn_execution_task;
 use starknet_core::types::{
     BlockHashAndNumber, BlockId, BroadcastedTransaction, ContractClass, EventFilterWithPage, EventsPage, FeeEstimate,
     FunctionCall, MaybePendingBlockWithReceipts, MaybePendingBlockWithTxHashes, MaybePendingBlockWithTxs,
@@ -45,8 +46,9 @@ impl StarknetReadRpcApiV0_7_1Server for Starknet {
         Ok(block_hash_and_number(self)?)
     }
 
-    fn call(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<Vec<Felt>> {
-        Ok(call(self, request, block_id)?)
+    async fn call(&self, request: FunctionCall, block_id: BlockId) -> RpcResult<Vec<Felt>> {
+        let starknet = self.clone();
+        Ok(spawn_execution_task(move || call(&starknet, request, block_id)).await?)
     }
 
     fn chain_id(&self) -> RpcResult<Felt> {
@@ -63,11 +65,13 @@ impl StarknetReadRpcApiV0_7_1Server for Starknet {
         simulation_flags: Vec<SimulationFlagForEstimateFee>,
         block_id: BlockId,
     ) -> RpcResult<Vec<FeeEstimate>> {
-        Ok(estimate_fee(self, request, simulation_flags, block_id).await?)
+        let starknet = self.clone();
+        Ok(spawn_execution_task(move || estimate_fee(&starknet, request, simulation_flags, block_id)).await?)
     }
 
     async fn estimate_message_fee(&self, message: MsgFromL1, block_id: BlockId) -> RpcResult<FeeEstimate> {
-        Ok(estimate_message_fee(self, message, block_id).await?)
+        let starknet = self.clone();
+        Ok(spawn_execution_task(move || estimate_message_fee(&starknet, message, block_id)).await?)
     }
 
     async fn get_block_with_receipts(&self, block_id: BlockId) -> RpcResult<MaybePendingBlockWithReceipts> {
@@ -3,6 +3,7 @@ pub(crate) mod trace_block_transactions;
 pub(crate) mod trace_transaction;
 
 use jsonrpsee::core::{async_trait, RpcResult};
+use mp_utils::spawn_execution_task;
 use starknet_core::types::{
     BlockId, BroadcastedTransaction, Felt, SimulatedTransaction, SimulationFlag, TransactionTraceWithHash,
 };
@@ -21,14 +22,18 @@ impl StarknetTraceRpcApiV0_7_1Server for Starknet {
         transactions: Vec<BroadcastedTransaction>,
         simulation_flags: Vec<SimulationFlag>,
     ) -> RpcResult<Vec<SimulatedTransaction>> {
-        Ok(simulate_transactions(self, block_id, transactions, simulation_flags).await?)
+        let starknet = self.clone();
+        Ok(spawn_execution_task(move || simulate_transactions(&starknet, block_id, transactions, simulation_flags))
+            .await?)
     }
 
     async fn trace_block_transactions(&self, block_id: BlockId) -> RpcResult<Vec<TransactionTraceWithHash>> {
-        Ok(trace_block_transactions(self, block_id).await?)
+        let starknet = self.clone();
+        Ok(spawn_execution_task(move || trace_block_transactions(&starknet, block_id)).await?)
     }
 
     async fn trace_transaction(&self, transaction_hash: Felt) -> RpcResult<TransactionTraceWithHash> {
-        Ok(trace_transaction(self, transaction_hash).await?)
+        let starknet = self.clone();
+        Ok(spawn_execution_task(move || trace_transaction(&starknet, transaction_hash)).await?)
     }
 }
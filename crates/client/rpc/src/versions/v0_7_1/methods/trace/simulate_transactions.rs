@@ -7,7 +7,7 @@ use mp_transactions::broadcasted_to_blockifier;
 use starknet_core::types::{BlockId, BroadcastedTransaction, SimulatedTransaction, SimulationFlag};
 use std::sync::Arc;
 
-pub async fn simulate_transactions(
+pub fn simulate_transactions(
     starknet: &Starknet,
     block_id: BlockId,
     transactions: Vec<BroadcastedTransaction>,
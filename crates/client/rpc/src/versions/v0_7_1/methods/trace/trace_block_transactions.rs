@@ -1,15 +1,18 @@
 use super::trace_transaction::FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW;
 use crate::utils::transaction::to_blockifier_transactions;
 use crate::Starknet;
+use mc_exec::metrics::{BlockExecutionMetrics, ExecutionSource};
 use mc_exec::{execution_result_to_tx_trace, ExecutionContext};
+use mp_block::MadaraMaybePendingBlockInfo;
 use mp_convert::ToFelt;
 use mp_rpc::errors::{StarknetRpcApiError, StarknetRpcResult};
 use mp_rpc::utils::ResultExt;
 use starknet_api::transaction::TransactionHash;
 use starknet_core::types::{BlockId, TransactionTraceWithHash};
 use std::sync::Arc;
+use std::time::Instant;
 
-pub async fn trace_block_transactions(
+pub fn trace_block_transactions(
     starknet: &Starknet,
     block_id: BlockId,
 ) -> StarknetRpcResult<Vec<TransactionTraceWithHash>> {
@@ -23,12 +26,24 @@ pub async fn trace_block_transactions(
     let transactions: Vec<_> = block
         .inner
         .transactions
-        .into_iter()
+        .iter()
+        .cloned()
         .zip(block.info.tx_hashes())
         .map(|(tx, hash)| to_blockifier_transactions(starknet, block_id.into(), tx, &TransactionHash(*hash)))
         .collect::<Result<_, _>>()?;
 
+    let start_time = Instant::now();
     let executions_results = exec_context.re_execute_transactions([], transactions, true, true)?;
+    // The pending block is still being executed, its resources are not the ones of a whole block.
+    if let (Some(metrics), MadaraMaybePendingBlockInfo::NotPending(info)) = (BlockExecutionMetrics::get(), &block.info)
+    {
+        metrics.record(
+            ExecutionSource::ReExecution,
+            &block.inner.receipts,
+            info.header.state_diff_length.map(|len| len as usize),
+            start_time.elapsed(),
+        );
+    }
 
     let traces = executions_results
         .into_iter()
@@ -1,7 +1,7 @@
 use crate::utils::transaction::to_blockifier_transactions;
 use crate::Starknet;
 use mc_exec::execution_result_to_tx_trace;
-use mc_exec::ExecutionContext;
+use mc_exec::{ExecutionContext, ExecutionResult};
 use mp_chain_config::StarknetVersion;
 use mp_rpc::errors::StarknetRpcApiError;
 use mp_rpc::errors::StarknetRpcResult;
@@ -14,10 +14,20 @@ use std::sync::Arc;
 // For now, we fallback to the sequencer - that is what pathfinder and juno do too, but this is temporary
 pub const FALLBACK_TO_SEQUENCER_WHEN_VERSION_BELOW: StarknetVersion = StarknetVersion::V0_13_0;
 
-pub async fn trace_transaction(
+pub fn trace_transaction(starknet: &Starknet, transaction_hash: Felt) -> StarknetRpcResult<TransactionTraceWithHash> {
+    let execution_result = re_execute_transaction(starknet, transaction_hash)?;
+
+    let trace = execution_result_to_tx_trace(&execution_result)
+        .or_internal_server_error("Converting execution infos to tx trace")?;
+
+    Ok(TransactionTraceWithHash { transaction_hash, trace_root: trace })
+}
+
+/// Re-executes a transaction on top of the transactions before it in its block.
+pub(crate) fn re_execute_transaction(
     starknet: &Starknet,
     transaction_hash: Felt,
-) -> StarknetRpcResult<TransactionTraceWithHash> {
+) -> StarknetRpcResult<ExecutionResult> {
     let (block, tx_index) = starknet
         .backend
         .find_tx_hash_block(&transaction_hash)
@@ -42,11 +52,5 @@ pub async fn trace_transaction(
     let mut executions_results =
         exec_context.re_execute_transactions(transactions_before, [transaction], true, true)?;
 
-    let execution_result =
-        executions_results.pop().ok_or_internal_server_error("No execution info returned for the last transaction")?;
-
-    let trace = execution_result_to_tx_trace(&execution_result)
-        .or_internal_server_error("Converting execution infos to tx trace")?;
-
-    Ok(TransactionTraceWithHash { transaction_hash, trace_root: trace })
+    executions_results.pop().ok_or_internal_server_error("No execution info returned for the last transaction")
 }
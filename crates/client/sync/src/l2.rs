@@ -14,15 +14,17 @@ use mc_gateway::error::SequencerError;
 use mc_telemetry::{TelemetryHandle, VerbosityLevel};
 use mp_block::BlockId;
 use mp_block::BlockTag;
+use mp_block::{MadaraBlock, MadaraBlockInfo, MadaraBlockInner};
 use mp_exex::ExExManagerHandle;
 use mp_exex::ExExNotification;
+use mp_state_update::StateDiff;
 use mp_utils::{channel_wait_or_graceful_shutdown, wait_or_graceful_shutdown, PerfStopwatch};
 use starknet_api::block::BlockNumber;
 use starknet_api::core::ChainId;
 use starknet_types_core::felt::Felt;
 use std::pin::pin;
 use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio::task::JoinSet;
 use tokio::time::Duration;
 
@@ -48,13 +50,21 @@ pub struct L2StateUpdate {
 }
 
 /// Sends a notification to the ExExs that a block has been imported.
-fn notify_exexs(exex_manager: &Option<ExExManagerHandle>, block_n: u64) -> anyhow::Result<()> {
-    let Some(manager) = exex_manager.as_ref() else {
+async fn notify_exexs(
+    exex_manager: &mut Option<ExExManagerHandle>,
+    block: MadaraBlock,
+    state_diff: StateDiff,
+) -> anyhow::Result<()> {
+    let Some(manager) = exex_manager.as_mut() else {
         return Ok(());
     };
 
-    let notification = ExExNotification::BlockSynced { block_number: BlockNumber(block_n) };
-    manager.send(notification).map_err(|e| anyhow::anyhow!("Could not send ExEx notification: {}", e))
+    let notification = ExExNotification::BlockSynced {
+        block_number: BlockNumber(block.info.header.block_number),
+        block: Some(Box::new(block)),
+        state_diff: Some(Box::new(state_diff)),
+    };
+    manager.notify(notification).await.map_err(|e| anyhow::anyhow!("Could not send ExEx notification: {}", e))
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -65,9 +75,13 @@ async fn l2_verify_and_apply_task(
     validation: BlockValidationContext,
     backup_every_n_blocks: Option<u64>,
     telemetry: TelemetryHandle,
-    exex_manager: Option<ExExManagerHandle>,
+    mut exex_manager: Option<ExExManagerHandle>,
 ) -> anyhow::Result<()> {
     while let Some(block) = channel_wait_or_graceful_shutdown(pin!(updates_receiver.recv())).await {
+        // The block data is only kept around when there are ExExs to hand it to.
+        let exex_data = exex_manager.as_ref().map(|_| {
+            (MadaraBlockInner::new(block.transactions.clone(), block.receipts.clone()), block.state_diff.clone())
+        });
         let BlockImportResult { header, block_hash } = block_import.verify_apply(block, validation.clone()).await?;
 
         log::info!(
@@ -83,7 +97,11 @@ async fn l2_verify_and_apply_task(
             header.global_state_root
         );
 
-        notify_exexs(&exex_manager, header.block_number)?;
+        if let Some((inner, state_diff)) = exex_data {
+            let tx_hashes = inner.receipts.iter().map(|receipt| receipt.transaction_hash()).collect();
+            let block = MadaraBlock::new(MadaraBlockInfo::new(header.clone(), tx_hashes, block_hash), inner);
+            notify_exexs(&mut exex_manager, block, state_diff).await?;
+        }
 
         telemetry.send(
             VerbosityLevel::Info,
@@ -144,7 +162,7 @@ async fn l2_pending_block_task(
     validation: BlockValidationContext,
     sync_finished_cb: oneshot::Receiver<()>,
     provider: Arc<FeederClient>,
-    pending_block_poll_interval: Duration,
+    mut pending_block_poll_interval: watch::Receiver<Duration>,
 ) -> anyhow::Result<()> {
     // clear pending status
     {
@@ -160,9 +178,16 @@ async fn l2_pending_block_task(
 
     log::debug!("Start pending block poll");
 
-    let mut interval = tokio::time::interval(pending_block_poll_interval);
+    let mut interval = tokio::time::interval(*pending_block_poll_interval.borrow_and_update());
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
     while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+        // A new poll interval applies from the next poll.
+        if pending_block_poll_interval.has_changed().unwrap_or(false) {
+            let period = *pending_block_poll_interval.borrow_and_update();
+            interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        }
+
         log::debug!("Getting pending block...");
 
         let current_block_hash = backend
@@ -199,7 +224,8 @@ pub struct L2SyncConfig {
     pub verify: bool,
     pub sync_polling_interval: Option<Duration>,
     pub backup_every_n_blocks: Option<u64>,
-    pub pending_block_poll_interval: Duration,
+    /// Can be changed while syncing.
+    pub pending_block_poll_interval: watch::Receiver<Duration>,
     pub ignore_block_order: bool,
 }
 
@@ -437,7 +463,7 @@ mod tests {
             validation.clone(),
             ctx.once_caught_up_receiver,
             ctx.provider.clone(),
-            std::time::Duration::from_secs(5),
+            watch::channel(std::time::Duration::from_secs(5)).1,
         ));
 
         // Simulate the "once_caught_up" signal
@@ -8,6 +8,7 @@ use mc_telemetry::TelemetryHandle;
 use mp_exex::ExExManagerHandle;
 use reqwest::header::{HeaderName, HeaderValue};
 use std::{sync::Arc, time::Duration};
+use tokio::sync::watch;
 
 pub mod fetch;
 pub mod l2;
@@ -24,7 +25,7 @@ pub async fn sync(
     starting_block: Option<u64>,
     backup_every_n_blocks: Option<u64>,
     telemetry: TelemetryHandle,
-    pending_block_poll_interval: Duration,
+    pending_block_poll_interval: watch::Receiver<Duration>,
     exex_manager: Option<ExExManagerHandle>,
 ) -> anyhow::Result<()> {
     let (starting_block, ignore_block_order) = if let Some(starting_block) = starting_block {
@@ -154,4 +154,8 @@ impl Service for TelemetryService {
 
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "telemetry"
+    }
 }
@@ -8,6 +8,15 @@ use std::process::Command;
 pub fn main() {
     generate_cargo_keys();
     rerun_if_git_head_changed();
+    compile_protos();
+}
+
+/// Generates the gRPC code of the remote ExEx. The protos are compiled with protox, so that building the node does
+/// not require protoc to be installed.
+fn compile_protos() {
+    let file_descriptors = protox::compile(["exex.proto"], ["proto"]).expect("Compiling the protos");
+    tonic_build::configure().build_client(false).compile_fds(file_descriptors).expect("Generating the gRPC code");
+    println!("cargo:rerun-if-changed=proto");
 }
 pub fn generate_cargo_keys() {
     let commit = if let Ok(hash) = std::env::var("GIT_COMMIT_HASH") {
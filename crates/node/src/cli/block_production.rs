@@ -1,3 +1,10 @@
+use std::path::PathBuf;
+
+use mc_devnet::{ClassManifest, GenesisSpec};
+use mp_keystore::{KeySource, Password, Secret};
+use mp_utils::parsers::parse_url;
+use url::Url;
+
 /// Parameters used to config block production.
 #[derive(Clone, Debug, clap::Parser)]
 pub struct BlockProductionParams {
@@ -11,7 +18,169 @@ pub struct BlockProductionParams {
     #[arg(env = "MADARA_OVERRIDE_DEVNET_CHAIN_ID", long, default_value_t = false)]
     pub override_devnet_chain_id: bool,
 
+    /// Chain id of the devnet, to simulate a specific appchain locally. Any chain id other than the default
+    /// `MADARA_DEVNET` also requires `--override-devnet-chain-id`.
+    #[arg(env = "MADARA_DEVNET_CHAIN_ID", long, value_name = "CHAIN ID", requires = "devnet")]
+    pub devnet_chain_id: Option<String>,
+
+    /// Timestamp of the devnet genesis block, in seconds since the UNIX epoch. Defaults to the `genesis_timestamp`
+    /// of the chain config, or to the time the devnet is created.
+    #[arg(env = "MADARA_DEVNET_GENESIS_TIMESTAMP", long, value_name = "TIMESTAMP", requires = "devnet")]
+    pub devnet_genesis_timestamp: Option<u64>,
+
     /// Create this number of contracts in the genesis block for the devnet configuration.
     #[arg(env = "MADARA_DEVNET_CONTRACTS", long, default_value_t = 10)]
     pub devnet_contracts: u64,
+
+    /// JSON manifest of the classes declared in the devnet genesis block, see `mc_devnet::ClassManifest`. By default,
+    /// the OpenZeppelin account, ERC20 and ERC721 presets and the Cairo 1 UDC are declared.
+    #[arg(env = "MADARA_DEVNET_CLASS_MANIFEST", long, value_name = "PATH", requires = "devnet")]
+    pub devnet_class_manifest: Option<PathBuf>,
+
+    /// Start the devnet from the state of a live network, fetched on first access through this RPC endpoint and
+    /// cached in the database. The fee token contracts and the UDC are those of the forked network.
+    #[arg(env = "MADARA_FORK_URL", long, value_name = "RPC URL", requires = "devnet")]
+    pub fork_url: Option<Url>,
+
+    /// Block of the forked network the devnet starts from. Defaults to its latest block, or to the block the
+    /// database was forked at.
+    #[arg(env = "MADARA_FORK_BLOCK", long, value_name = "BLOCK NUMBER", requires = "fork_url")]
+    pub fork_block: Option<u64>,
+
+    /// When the devnet closes blocks.
+    #[arg(env = "MADARA_MINING_MODE", long, value_enum, default_value_t = MiningMode::Interval, requires = "devnet")]
+    pub mining_mode: MiningMode,
+
+    /// Start the devnet from a dump written by `devnet_dumpState` or `--dump-on-exit`. The database must be empty,
+    /// or hold the beginning of the dumped chain.
+    #[arg(env = "MADARA_LOAD_STATE", long, value_name = "PATH", requires = "devnet")]
+    pub load_state: Option<PathBuf>,
+
+    /// Write the devnet chain to this file when the node shuts down, to be loaded later with `--load-state`.
+    #[arg(env = "MADARA_DUMP_ON_EXIT", long, value_name = "PATH", requires = "devnet")]
+    pub dump_on_exit: Option<PathBuf>,
+
+    /// JSON specification of the genesis block, see `mc_devnet::GenesisSpec`: declared classes, deployed contracts,
+    /// storage writes and fee token balances. The genesis block is built when the database is empty.
+    #[arg(env = "MADARA_GENESIS", long, value_name = "PATH", conflicts_with = "devnet")]
+    pub genesis: Option<PathBuf>,
+
+    /// Accept blocks built by an external block builder, with the `madara_submitBlock` node operator RPC method. The
+    /// submitted transactions are executed in their order as the next block, instead of the mempool transactions.
+    #[arg(env = "MADARA_EXTERNAL_BLOCK_BUILDER", long)]
+    pub external_block_builder: bool,
+
+    /// Reject the transactions signed for another well known chain id (mainnet, sepolia, devnet) with a specific
+    /// validation error. Every transaction failing validation is validated again once per chain id, only enable
+    /// this to debug misconfigured clients, never on a public node.
+    #[arg(env = "MADARA_DETECT_CHAIN_ID_MISMATCH", long)]
+    pub detect_chain_id_mismatch: bool,
+
+    /// Encrypted JSON keystore holding the private key used to sign the produced blocks. The signatures and the
+    /// public key are served by the feeder gateway, so that full nodes can authenticate the blocks.
+    #[arg(
+        env = "MADARA_BLOCK_SIGNING_KEYSTORE",
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["block_signing_private_key_env", "block_signing_remote_signer"]
+    )]
+    pub block_signing_keystore: Option<PathBuf>,
+
+    /// Password of the block signing keystore.
+    #[arg(
+        env = "MADARA_BLOCK_SIGNING_KEYSTORE_PASSWORD",
+        long,
+        hide_env_values = true,
+        requires = "block_signing_keystore",
+        conflicts_with = "block_signing_keystore_password_file"
+    )]
+    pub block_signing_keystore_password: Option<String>,
+
+    /// File holding the password of the block signing keystore.
+    #[arg(
+        env = "MADARA_BLOCK_SIGNING_KEYSTORE_PASSWORD_FILE",
+        long,
+        value_name = "PATH",
+        requires = "block_signing_keystore"
+    )]
+    pub block_signing_keystore_password_file: Option<PathBuf>,
+
+    /// Environment variable holding the hex private key used to sign the produced blocks, as an alternative to a
+    /// keystore when the key is injected by a secret manager.
+    #[arg(
+        env = "MADARA_BLOCK_SIGNING_PRIVATE_KEY_ENV",
+        long,
+        value_name = "VAR",
+        conflicts_with = "block_signing_remote_signer"
+    )]
+    pub block_signing_private_key_env: Option<String>,
+
+    /// Signing service holding the key used to sign the produced blocks, for keys kept in an HSM. See
+    /// `mp_keystore::RemoteSigner` for the protocol.
+    #[arg(env = "MADARA_BLOCK_SIGNING_REMOTE_SIGNER", long, value_parser = parse_url, value_name = "URL")]
+    pub block_signing_remote_signer: Option<Url>,
+
+    /// File holding the bearer token sent to the remote signer, when it requires authentication.
+    #[arg(
+        env = "MADARA_BLOCK_SIGNING_REMOTE_SIGNER_TOKEN_FILE",
+        long,
+        value_name = "PATH",
+        requires = "block_signing_remote_signer"
+    )]
+    pub block_signing_remote_signer_token_file: Option<PathBuf>,
+}
+
+impl BlockProductionParams {
+    /// Classes declared in the devnet genesis block.
+    pub fn class_manifest(&self) -> anyhow::Result<ClassManifest> {
+        match &self.devnet_class_manifest {
+            Some(path) => ClassManifest::read_file(path),
+            None => Ok(ClassManifest::default()),
+        }
+    }
+
+    pub fn genesis_spec(&self) -> anyhow::Result<Option<GenesisSpec>> {
+        self.genesis.as_deref().map(GenesisSpec::read_file).transpose()
+    }
+
+    /// Where the block signing key is loaded from, `None` when block signing is disabled.
+    pub fn signing_key_source(&self) -> anyhow::Result<Option<KeySource>> {
+        let password = match (&self.block_signing_keystore_password, &self.block_signing_keystore_password_file) {
+            (Some(password), _) => Some(Password::Value(Secret::new(password.clone()))),
+            (None, Some(path)) => Some(Password::File(path.clone())),
+            (None, None) if self.block_signing_keystore.is_some() => anyhow::bail!(
+                "Decrypting the block signing keystore requires either `--block-signing-keystore-password` or \
+                 `--block-signing-keystore-password-file`"
+            ),
+            (None, None) => None,
+        };
+        KeySource::from_options(
+            self.block_signing_keystore.clone(),
+            password,
+            self.block_signing_private_key_env.clone(),
+            self.block_signing_remote_signer.clone(),
+            self.block_signing_remote_signer_token_file.clone().map(Password::File),
+        )
+    }
+}
+
+/// When the devnet closes blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MiningMode {
+    /// Every block time of the chain config.
+    Interval,
+    /// As soon as transactions are received.
+    Instant,
+    /// Only when requested with `devnet_mine`.
+    OnDemand,
+}
+
+impl From<MiningMode> for mc_mempool::MiningMode {
+    fn from(value: MiningMode) -> Self {
+        match value {
+            MiningMode::Interval => Self::Interval,
+            MiningMode::Instant => Self::Instant,
+            MiningMode::OnDemand => Self::OnDemand,
+        }
+    }
 }
@@ -0,0 +1,404 @@
+//! `madara chain-config new`: generates the chain config file of a new appchain. The values that are not given as
+//! flags are asked for on the terminal, or take their default value when the command is not run interactively.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::Context;
+use mp_block::H160;
+use mp_chain_config::{ChainConfig, ChainVersionedConstants, StarknetVersion};
+use mp_utils::parsers::parse_duration;
+use starknet_api::core::ContractAddress;
+use starknet_core::types::Felt;
+
+/// The STRK token deployed in the devnet genesis.
+const DEFAULT_NATIVE_FEE_TOKEN_ADDRESS: &str = "0x04718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+/// The ETH token deployed in the devnet genesis.
+const DEFAULT_PARENT_FEE_TOKEN_ADDRESS: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct ChainConfigNewArgs {
+    /// Where to write the chain config.
+    #[arg(long, short, default_value = "chain_config.yaml", value_name = "PATH")]
+    pub output: PathBuf,
+
+    /// Overwrite the output file when it exists.
+    #[arg(long)]
+    pub force: bool,
+
+    /// Never ask for the missing values: use their default value, or fail when they have none.
+    #[arg(long)]
+    pub non_interactive: bool,
+
+    /// Human readable chain name.
+    #[arg(long, value_name = "NAME")]
+    pub chain_name: Option<String>,
+
+    /// Chain id, at most 31 ASCII characters (e.g. `MY_APPCHAIN`).
+    #[arg(long, value_name = "CHAIN ID")]
+    pub chain_id: Option<String>,
+
+    /// Address of the STRK fee token, or of the fee token of the appchain.
+    #[arg(long, value_name = "ADDRESS")]
+    pub native_fee_token_address: Option<String>,
+
+    /// Symbol of the native fee token, when the appchain has its own fee token instead of STRK.
+    #[arg(long, value_name = "SYMBOL")]
+    pub native_fee_token_symbol: Option<String>,
+
+    /// Decimals of the native fee token.
+    #[arg(long, value_name = "DECIMALS")]
+    pub native_fee_token_decimals: Option<String>,
+
+    /// Address of the ETH fee token.
+    #[arg(long, value_name = "ADDRESS")]
+    pub parent_fee_token_address: Option<String>,
+
+    /// Layer the chain settles on: `ethereum` or `starknet`.
+    #[arg(long, value_name = "LAYER")]
+    pub settlement_layer: Option<String>,
+
+    /// Address of the core contract on the settlement layer. Chains run with `--sovereign` can leave it to zero.
+    #[arg(long, value_name = "ADDRESS")]
+    pub core_contract_address: Option<String>,
+
+    /// Most recent Starknet version supported.
+    #[arg(long, value_name = "VERSION")]
+    pub latest_protocol_version: Option<String>,
+
+    /// Versioned constants of a Starknet version, in addition to the ones built in madara (0.13.0 to 0.13.2).
+    #[arg(long, value_name = "VERSION=PATH")]
+    pub versioned_constants: Vec<String>,
+
+    /// Address receiving the fees of the produced blocks.
+    #[arg(long, value_name = "ADDRESS")]
+    pub sequencer_address: Option<String>,
+
+    /// Target time between blocks (e.g. `30s`).
+    #[arg(long, value_name = "DURATION")]
+    pub block_time: Option<String>,
+
+    /// Interval at which the pending block is updated (e.g. `2s`).
+    #[arg(long, value_name = "DURATION")]
+    pub pending_block_update_time: Option<String>,
+}
+
+impl ChainConfigNewArgs {
+    pub fn run(self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.force || !self.output.exists(),
+            "{} already exists, use `--force` to overwrite it",
+            self.output.display()
+        );
+        let prompt = Prompt { interactive: !self.non_interactive && std::io::stdin().is_terminal() };
+
+        let chain_name = prompt.ask("Chain name", self.chain_name, Some("Madara Appchain"), |_| Ok(()))?;
+        let chain_id = prompt.ask("Chain id", self.chain_id, None, validate_chain_id)?;
+        let native_fee_token_address = prompt.ask(
+            "Native fee token address",
+            self.native_fee_token_address,
+            Some(DEFAULT_NATIVE_FEE_TOKEN_ADDRESS),
+            validate_address,
+        )?;
+        let native_fee_token_symbol =
+            prompt.ask("Native fee token symbol", self.native_fee_token_symbol, Some("STRK"), |symbol| {
+                anyhow::ensure!(!symbol.is_empty(), "The symbol cannot be empty");
+                Ok(())
+            })?;
+        let native_fee_token_decimals =
+            prompt.ask("Native fee token decimals", self.native_fee_token_decimals, Some("18"), |decimals| {
+                decimals.parse::<u8>().map(|_| ()).context("Expected a number of decimals, at most 255")
+            })?;
+        let parent_fee_token_address = prompt.ask(
+            "ETH fee token address",
+            self.parent_fee_token_address,
+            Some(DEFAULT_PARENT_FEE_TOKEN_ADDRESS),
+            validate_address,
+        )?;
+        let settlement_layer =
+            prompt.ask("Settlement layer (ethereum, starknet)", self.settlement_layer, Some("ethereum"), |layer| {
+                match layer {
+                    "ethereum" | "starknet" => Ok(()),
+                    _ => anyhow::bail!("Expected `ethereum` or `starknet`"),
+                }
+            })?;
+        let core_contract_address = if settlement_layer == "starknet" {
+            prompt.ask("Core contract address on Starknet", self.core_contract_address, None, validate_address)?
+        } else {
+            prompt.ask(
+                "Core contract address on Ethereum",
+                self.core_contract_address,
+                Some("0x0000000000000000000000000000000000000000"),
+                |address| H160::from_str(address).map(|_| ()).context("Invalid Ethereum address"),
+            )?
+        };
+        let latest_protocol_version =
+            prompt.ask("Latest protocol version", self.latest_protocol_version, Some("0.13.2"), |version| {
+                let version = StarknetVersion::from_str(version)?;
+                anyhow::ensure!(
+                    version >= StarknetVersion::V0_13_0,
+                    "Starknet versions before 0.13.0 are not supported"
+                );
+                Ok(())
+            })?;
+        let versioned_constants = if self.versioned_constants.is_empty() {
+            prompt.ask_list("Additional versioned constants (VERSION=PATH)", validate_versioned_constants)?
+        } else {
+            self.versioned_constants.iter().try_for_each(|entry| validate_versioned_constants(entry))?;
+            self.versioned_constants
+        };
+        let sequencer_address =
+            prompt.ask("Sequencer address", self.sequencer_address, None, validate_sequencer_address)?;
+        let block_time = prompt.ask("Block time", self.block_time, Some("30s"), validate_duration)?;
+        let pending_block_update_time =
+            prompt.ask("Pending block update time", self.pending_block_update_time, Some("2s"), validate_duration)?;
+        let versioned_constants_paths: BTreeMap<String, String> = versioned_constants
+            .iter()
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(version, path)| (version.trim().into(), path.trim().into()))
+            .collect();
+
+        let yaml = ChainConfigTemplate {
+            chain_name,
+            chain_id,
+            native_fee_token_address,
+            native_fee_token_symbol,
+            native_fee_token_decimals,
+            parent_fee_token_address,
+            settlement_layer,
+            core_contract_address,
+            latest_protocol_version,
+            versioned_constants,
+            sequencer_address,
+            block_time,
+            pending_block_update_time,
+        }
+        .render();
+
+        let mut chain_config: ChainConfig = serde_yaml::from_str(&yaml).context("Invalid chain config")?;
+        chain_config.versioned_constants.merge(ChainVersionedConstants::from_file(versioned_constants_paths)?);
+        chain_config.precheck_block_production().context("Invalid chain config")?;
+        chain_config.validate()?;
+
+        std::fs::write(&self.output, yaml).with_context(|| format!("Writing {}", self.output.display()))?;
+        println!("Chain config written to {}", self.output.display());
+        Ok(())
+    }
+}
+
+struct Prompt {
+    interactive: bool,
+}
+
+impl Prompt {
+    /// The value given as a flag, or asked for on the terminal.
+    fn ask(
+        &self,
+        question: &str,
+        value: Option<String>,
+        default: Option<&str>,
+        validate: impl Fn(&str) -> anyhow::Result<()>,
+    ) -> anyhow::Result<String> {
+        if let Some(value) = value {
+            validate(&value).with_context(|| format!("Invalid {}", question.to_lowercase()))?;
+            return Ok(value);
+        }
+        if !self.interactive {
+            let default = default.with_context(|| format!("Missing {}", question.to_lowercase()))?;
+            return Ok(default.into());
+        }
+
+        loop {
+            let answer = match default {
+                Some(default) => read_line(&format!("{question} [{default}]: "))?,
+                None => read_line(&format!("{question}: "))?,
+            };
+            let answer = match (answer.is_empty(), default) {
+                (true, Some(default)) => default.to_string(),
+                _ => answer,
+            };
+            match validate(&answer) {
+                Ok(()) => return Ok(answer),
+                Err(err) => eprintln!("{err:#}"),
+            }
+        }
+    }
+
+    /// Values asked for until an empty line.
+    fn ask_list(&self, question: &str, validate: impl Fn(&str) -> anyhow::Result<()>) -> anyhow::Result<Vec<String>> {
+        let mut values = vec![];
+        if !self.interactive {
+            return Ok(values);
+        }
+        loop {
+            let answer = read_line(&format!("{question}, empty to continue: "))?;
+            if answer.is_empty() {
+                return Ok(values);
+            }
+            match validate(&answer) {
+                Ok(()) => values.push(answer),
+                Err(err) => eprintln!("{err:#}"),
+            }
+        }
+    }
+}
+
+fn read_line(prompt: &str) -> anyhow::Result<String> {
+    eprint!("{prompt}");
+    std::io::stderr().flush().context("Writing to the terminal")?;
+    let mut line = String::new();
+    let read = std::io::stdin().lock().read_line(&mut line).context("Reading from the terminal")?;
+    anyhow::ensure!(read > 0, "Unexpected end of input");
+    Ok(line.trim().to_string())
+}
+
+fn validate_chain_id(chain_id: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(!chain_id.is_empty(), "The chain id cannot be empty");
+    anyhow::ensure!(
+        chain_id.is_ascii() && chain_id.len() <= 31,
+        "The chain id must be at most 31 ASCII characters, to fit in a felt"
+    );
+    Ok(())
+}
+
+fn validate_address(address: &str) -> anyhow::Result<()> {
+    let felt = Felt::from_hex(address).context("Expected a hex-encoded felt")?;
+    ContractAddress::try_from(felt).context("The address is out of range")?;
+    Ok(())
+}
+
+fn validate_sequencer_address(address: &str) -> anyhow::Result<()> {
+    validate_address(address)?;
+    anyhow::ensure!(Felt::from_hex(address)? != Felt::ZERO, "The sequencer address cannot be 0x0");
+    Ok(())
+}
+
+fn validate_duration(duration: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(!parse_duration(duration)?.is_zero(), "The duration cannot be zero");
+    Ok(())
+}
+
+fn validate_versioned_constants(entry: &str) -> anyhow::Result<()> {
+    let (version, path) = entry.split_once('=').context("Expected VERSION=PATH")?;
+    ChainVersionedConstants::from_file(BTreeMap::from([(version.trim().into(), path.trim().into())]))?;
+    Ok(())
+}
+
+struct ChainConfigTemplate {
+    chain_name: String,
+    chain_id: String,
+    native_fee_token_address: String,
+    native_fee_token_symbol: String,
+    native_fee_token_decimals: String,
+    parent_fee_token_address: String,
+    settlement_layer: String,
+    core_contract_address: String,
+    latest_protocol_version: String,
+    versioned_constants: Vec<String>,
+    sequencer_address: String,
+    block_time: String,
+    pending_block_update_time: String,
+}
+
+impl ChainConfigTemplate {
+    /// The chain config, with the comments of `configs/chain_config.example.yaml`.
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Human readable chain name, for displaying to the console.");
+        let _ = writeln!(out, "chain_name: {}\n", quote(&self.chain_name));
+        let _ = writeln!(out, "chain_id: {}\n", quote(&self.chain_id));
+        let _ = writeln!(out, "# For starknet, this is the STRK ERC-20 contract on starknet.");
+        let _ = writeln!(out, "native_fee_token_address: {}\n", quote(&self.native_fee_token_address));
+        let _ = writeln!(out, "# For starknet, this is the ETH ERC-20 contract on starknet.");
+        let _ = writeln!(out, "parent_fee_token_address: {}\n", quote(&self.parent_fee_token_address));
+        if self.native_fee_token_symbol != "STRK" || self.native_fee_token_decimals != "18" {
+            let _ = writeln!(out, "# The fee token of the appchain, paying the fees of V3 transactions.");
+            let _ = writeln!(out, "native_fee_token:");
+            let _ = writeln!(out, "  symbol: {}", quote(&self.native_fee_token_symbol));
+            let _ = writeln!(out, "  decimals: {}\n", self.native_fee_token_decimals);
+        }
+
+        if !self.versioned_constants.is_empty() {
+            let _ = writeln!(out, "# Paths to JSON files containing blockifier's constants for different versions");
+            let _ = writeln!(out, "versioned_constants_path:");
+            for entry in &self.versioned_constants {
+                let (version, path) = entry.split_once('=').expect("Validated");
+                let _ = writeln!(out, "  {}: {}", quote(version.trim()), quote(path.trim()));
+            }
+            out.push('\n');
+        }
+
+        let _ = writeln!(out, "# The layer this chain settles on: `ethereum` (default) or `starknet` (L3 mode).");
+        let _ = writeln!(out, "settlement_layer: {}\n", quote(&self.settlement_layer));
+        if self.settlement_layer == "starknet" {
+            let _ = writeln!(
+                out,
+                "# The core contract address on Starknet, read by the L1 watcher to follow the verified state."
+            );
+            let _ = writeln!(out, "starknet_core_contract_address: {}\n", quote(&self.core_contract_address));
+        } else {
+            let _ = writeln!(out, "# The Starknet core contract address for the L1 watcher.");
+            let _ = writeln!(out, "# Sovereign chains, run with `--sovereign`, do not settle and can omit it.");
+            let _ = writeln!(out, "eth_core_contract_address: {}\n", quote(&self.core_contract_address));
+        }
+
+        let _ = writeln!(out, "# Most recent Starknet version supported");
+        let _ = writeln!(out, "latest_protocol_version: {}\n", quote(&self.latest_protocol_version));
+        let _ = writeln!(out, "# /!\\ Only used for block production.");
+        let _ = writeln!(out, "# Target time interval between blocks");
+        let _ = writeln!(out, "block_time: {}\n", quote(&self.block_time));
+        let _ = writeln!(out, "# /!\\ Only used for block production.");
+        let _ = writeln!(
+            out,
+            "# Block time is divided into \"ticks\": everytime this duration elapses, the pending block is updated."
+        );
+        let _ = writeln!(out, "pending_block_update_time: {}\n", quote(&self.pending_block_update_time));
+        let _ = writeln!(out, "# /!\\ Only used for block production.");
+        let _ =
+            writeln!(out, "# Each batch of the block production pops this number of transactions from the mempool.");
+        let _ = writeln!(out, "execution_batch_size: 16\n");
+        let _ = writeln!(out, "# /!\\ Only used for block production.");
+        let _ =
+            writeln!(out, "# The bouncer is in charge of limiting block sizes, with the limits of Starknet mainnet.");
+        out.push_str(BOUNCER_CONFIG);
+        out.push('\n');
+        let _ = writeln!(out, "# /!\\ Only used for block production.");
+        let _ = writeln!(out, "# Address of the sequencer, receiving the fees.");
+        let _ = writeln!(out, "sequencer_address: {}\n", quote(&self.sequencer_address));
+        let _ = writeln!(out, "# /!\\ Only used for block production.");
+        let _ = writeln!(
+            out,
+            "# Maximum nonce of an invoke transaction sent with the account deployment, for its validation to be skipped."
+        );
+        let _ = writeln!(out, "max_nonce_for_validation_skip: 2");
+        out
+    }
+}
+
+const BOUNCER_CONFIG: &str = "bouncer_config:
+  block_max_capacity:
+    builtin_count:
+      add_mod: 18446744073709551615
+      bitwise: 18446744073709551615
+      ecdsa: 18446744073709551615
+      ec_op: 18446744073709551615
+      keccak: 18446744073709551615
+      mul_mod: 18446744073709551615
+      pedersen: 18446744073709551615
+      poseidon: 18446744073709551615
+      range_check: 18446744073709551615
+      range_check96: 18446744073709551615
+    gas: 5000000
+    n_steps: 40000000
+    message_segment_length: 18446744073709551615
+    n_events: 18446744073709551615
+    state_diff_size: 131072
+";
+
+/// A double-quoted YAML string. JSON strings are valid YAML.
+fn quote(value: &str) -> String {
+    serde_json::Value::String(value.into()).to_string()
+}
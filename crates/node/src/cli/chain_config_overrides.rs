@@ -6,11 +6,12 @@ use clap::Parser;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
 use starknet_api::core::{ChainId, ContractAddress};
+use starknet_core::types::Felt;
 
 use mp_block::H160;
 use mp_chain_config::{
     deserialize_bouncer_config, deserialize_starknet_version, serialize_bouncer_config, serialize_starknet_version,
-    ChainConfig, StarknetVersion,
+    ChainConfig, DaLayer, FeeToken, ProtocolUpgrades, SettlementLayer, StarknetVersion,
 };
 use mp_utils::parsers::parse_key_value_yaml;
 use mp_utils::serde::{deserialize_duration, serialize_duration};
@@ -19,6 +20,9 @@ use mp_utils::serde::{deserialize_duration, serialize_duration};
 /// Format: "--chain-config-override chain_id=SN_MADARA,chain_name=MADARA,block_time=1500ms,bouncer_config.block_max_capacity.n_steps=100000000"
 #[derive(Parser, Clone, Debug)]
 pub struct ChainConfigOverrideParams {
+    /// Overrides a field of the chain config, on top of the preset or chain config file. Can be repeated, or given a
+    /// comma-separated list of `key=value`. The values are YAML, and the keys of nested fields are separated by dots,
+    /// such as `bouncer_config.block_max_capacity.n_steps=100000000` or `protocol_upgrades.150000=0.13.2`.
     #[clap(env = "MADARA_CHAIN_CONFIG_OVERRIDE", long = "chain-config-override", value_parser = parse_key_value_yaml, use_value_delimiter = true, value_delimiter = ',')]
     pub overrides: Vec<(String, Value)>,
 }
@@ -29,8 +33,11 @@ pub struct ChainConfigOverridesInner {
     pub chain_id: ChainId,
     pub native_fee_token_address: ContractAddress,
     pub parent_fee_token_address: ContractAddress,
+    pub native_fee_token: FeeToken,
+    pub parent_fee_token: FeeToken,
     #[serde(deserialize_with = "deserialize_starknet_version", serialize_with = "serialize_starknet_version")]
     pub latest_protocol_version: StarknetVersion,
+    pub protocol_upgrades: ProtocolUpgrades,
     #[serde(deserialize_with = "deserialize_duration", serialize_with = "serialize_duration")]
     pub block_time: Duration,
     #[serde(deserialize_with = "deserialize_duration", serialize_with = "serialize_duration")]
@@ -41,6 +48,10 @@ pub struct ChainConfigOverridesInner {
     pub sequencer_address: ContractAddress,
     pub max_nonce_for_validation_skip: u64,
     pub eth_core_contract_address: H160,
+    pub settlement_layer: SettlementLayer,
+    pub starknet_core_contract_address: Option<Felt>,
+    pub da_layer: DaLayer,
+    pub genesis_timestamp: Option<u64>,
 }
 
 impl From<&ChainConfig> for ChainConfigOverridesInner {
@@ -50,7 +61,10 @@ impl From<&ChainConfig> for ChainConfigOverridesInner {
             chain_id: config.chain_id.clone(),
             native_fee_token_address: config.native_fee_token_address,
             parent_fee_token_address: config.parent_fee_token_address,
+            native_fee_token: config.native_fee_token.clone(),
+            parent_fee_token: config.parent_fee_token.clone(),
             latest_protocol_version: config.latest_protocol_version,
+            protocol_upgrades: config.protocol_upgrades.clone(),
             block_time: config.block_time,
             pending_block_update_time: config.pending_block_update_time,
             execution_batch_size: config.execution_batch_size,
@@ -58,6 +72,10 @@ impl From<&ChainConfig> for ChainConfigOverridesInner {
             sequencer_address: config.sequencer_address,
             max_nonce_for_validation_skip: config.max_nonce_for_validation_skip,
             eth_core_contract_address: config.eth_core_contract_address,
+            settlement_layer: config.settlement_layer,
+            starknet_core_contract_address: config.starknet_core_contract_address,
+            da_layer: config.da_layer.clone(),
+            genesis_timestamp: config.genesis_timestamp,
         }
     }
 }
@@ -68,44 +86,24 @@ impl ChainConfigOverrideParams {
             .context("Failed to convert ChainConfig to Value")?;
 
         for (key, value) in &self.overrides {
-            // Split the key by '.' to handle nested fields
-            let key_parts = key.split('.').collect::<Vec<_>>();
-
-            // Navigate to the last field in the path
-            let mut current_value = &mut chain_config_overrides;
-            for part in key_parts.iter().take(key_parts.len() - 1) {
-                current_value = match current_value.get_mut(part) {
-                    Some(v) => v,
-                    None => bail!("Invalid chain config override key path: {}", key),
-                };
-            }
-
-            // Set the value to the final field in the path
-            let last_key =
-                key_parts.last().with_context(|| format!("Invalid chain config override key path: {}", key))?;
-            match current_value.get_mut(*last_key) {
-                Some(field) => {
-                    *field = value.clone();
-                }
-                None => {
-                    bail!("Invalid chain config override key path: {}", key);
-                }
-            }
+            set_field(&mut chain_config_overrides, key, value.clone())?;
+            log::debug!("Chain config override: {key} = {value:?}");
         }
 
-        let chain_config_overrides: ChainConfigOverridesInner = serde_yaml::from_value(chain_config_overrides)
-            .context("Failed to convert Value to ChainConfigOverridesInner")?;
-
-        println!("chain_config_overrides: {:#?}", chain_config_overrides);
+        let chain_config_overrides: ChainConfigOverridesInner =
+            serde_yaml::from_value(chain_config_overrides).context("Invalid chain config override")?;
 
         let versioned_constants = chain_config.versioned_constants;
 
-        Ok(ChainConfig {
+        let chain_config = ChainConfig {
             chain_name: chain_config_overrides.chain_name,
             chain_id: chain_config_overrides.chain_id,
             native_fee_token_address: chain_config_overrides.native_fee_token_address,
             parent_fee_token_address: chain_config_overrides.parent_fee_token_address,
+            native_fee_token: chain_config_overrides.native_fee_token,
+            parent_fee_token: chain_config_overrides.parent_fee_token,
             latest_protocol_version: chain_config_overrides.latest_protocol_version,
+            protocol_upgrades: chain_config_overrides.protocol_upgrades,
             block_time: chain_config_overrides.block_time,
             pending_block_update_time: chain_config_overrides.pending_block_update_time,
             execution_batch_size: chain_config_overrides.execution_batch_size,
@@ -113,7 +111,39 @@ impl ChainConfigOverrideParams {
             sequencer_address: chain_config_overrides.sequencer_address,
             max_nonce_for_validation_skip: chain_config_overrides.max_nonce_for_validation_skip,
             eth_core_contract_address: chain_config_overrides.eth_core_contract_address,
+            settlement_layer: chain_config_overrides.settlement_layer,
+            starknet_core_contract_address: chain_config_overrides.starknet_core_contract_address,
+            da_layer: chain_config_overrides.da_layer,
+            genesis_timestamp: chain_config_overrides.genesis_timestamp,
             versioned_constants,
-        })
+        };
+        Ok(chain_config)
+    }
+}
+
+/// Sets the field at the dot-separated `path`. The fields of the chain config must already exist, to catch typos,
+/// while entries can be added to the maps keyed by block height and fields to the variants of the enums.
+fn set_field(config: &mut Value, path: &str, value: Value) -> anyhow::Result<()> {
+    let mut current = config;
+    for part in path.split('.') {
+        let Value::Mapping(mapping) = current else {
+            bail!("Invalid chain config override key path {path}: `{part}` is not a field")
+        };
+        let key = match part.parse::<u64>() {
+            Ok(height) => Value::Number(height.into()),
+            Err(_) => Value::String(part.into()),
+        };
+        if !mapping.contains_key(&key) {
+            if !key.is_number() && !mapping.contains_key("type") {
+                let fields = mapping.keys().filter_map(Value::as_str).collect::<Vec<_>>().join(", ");
+                bail!(
+                    "Invalid chain config override key path {path}: unknown field `{part}`, expected one of {fields}"
+                );
+            }
+            mapping.insert(key.clone(), Value::Null);
+        }
+        current = mapping.get_mut(&key).expect("The key was just checked");
     }
+    *current = value;
+    Ok(())
 }
@@ -0,0 +1,267 @@
+//! Node configuration file.
+//!
+//! Every parameter of [`super::RunCmd`] can be given in a TOML or YAML file, keyed by the name of its command line
+//! flag (`rpc-port = 9944`, or `rpc_port = 9944`). Lists are given as arrays. The file is turned into command line
+//! arguments, and the parameters given on the command line or in their environment variable take precedence over it.
+
+use std::ffi::OsString;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::{Arg, ArgAction, Command};
+use serde_json::Value;
+
+const CONFIG_FLAG: &str = "config";
+const CONFIG_ENV: &str = "MADARA_CONFIG";
+
+/// Path of the config file, from the `--config` flag or its environment variable.
+pub fn config_file_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1).filter_map(|arg| arg.to_str());
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(path.into());
+        }
+    }
+    std::env::var_os(CONFIG_ENV).map(PathBuf::from)
+}
+
+/// Command line arguments equivalent to the config file. The parameters set in the command line `args` or in their
+/// environment variable are left out.
+pub fn config_file_args(path: &Path, command: &Command, args: &[OsString]) -> anyhow::Result<Vec<OsString>> {
+    let params = read_file(path)?;
+
+    let mut res = vec![];
+    for (key, value) in params {
+        let long = key.replace('_', "-");
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(&long))
+            .with_context(|| format!("Unknown parameter `{key}` in config file {}", path.display()))?;
+        anyhow::ensure!(long != CONFIG_FLAG, "A config file cannot include another config file");
+        if is_set(arg, args) {
+            continue;
+        }
+
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Bool(_) | Value::Number(_) => value.to_string(),
+                _ => anyhow::bail!(
+                    "Parameter `{key}` in config file {} must be a string, a number, a boolean or a list of them",
+                    path.display()
+                ),
+            };
+            if matches!(arg.get_action(), ArgAction::SetTrue) {
+                match value.as_str() {
+                    "true" => res.push(format!("--{long}").into()),
+                    "false" => {}
+                    _ => anyhow::bail!("Parameter `{key}` in config file {} must be a boolean", path.display()),
+                }
+            } else {
+                res.push(format!("--{long}={value}").into());
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// A TOML config file with the default value of every parameter. Parameters without a default are commented out.
+pub fn print_defaults(command: &Command) -> String {
+    let mut out = String::from("# Madara node configuration, to use with `madara --config <FILE>`.\n");
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long() else { continue };
+        if long == CONFIG_FLAG || arg.is_hide_set() {
+            continue;
+        }
+
+        out.push('\n');
+        if let Some(help) = arg.get_help() {
+            for line in help.to_string().lines() {
+                let _ = writeln!(out, "# {line}");
+            }
+        }
+        let defaults =
+            arg.get_default_values().iter().map(|value| toml_value(&value.to_string_lossy())).collect::<Vec<_>>();
+        let _ = match (arg.get_action(), defaults.as_slice()) {
+            (ArgAction::SetTrue, _) => writeln!(out, "{long} = false"),
+            (_, []) => writeln!(out, "# {long} ="),
+            (ArgAction::Append, defaults) => writeln!(out, "{long} = [{}]", defaults.join(", ")),
+            (_, [default, ..]) => writeln!(out, "{long} = {default}"),
+        };
+    }
+    out
+}
+
+fn read_file(path: &Path) -> anyhow::Result<serde_json::Map<String, Value>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Reading config file {}", path.display()))?;
+    let value: Value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content).with_context(|| format!("Parsing config file {}", path.display()))?,
+        Some("yaml" | "yml") => {
+            serde_yaml::from_str(&content).with_context(|| format!("Parsing config file {}", path.display()))?
+        }
+        _ => anyhow::bail!("Config file {} must be a .toml, .yaml or .yml file", path.display()),
+    };
+    match value {
+        Value::Object(params) => Ok(params),
+        Value::Null => Ok(Default::default()),
+        _ => anyhow::bail!("Config file {} must be a map of parameters", path.display()),
+    }
+}
+
+/// Whether the parameter is set in the command line or in its environment variable.
+fn is_set(arg: &Arg, args: &[OsString]) -> bool {
+    if arg.get_env().is_some_and(|env| std::env::var_os(env).is_some()) {
+        return true;
+    }
+
+    let longs = arg
+        .get_long()
+        .into_iter()
+        .chain(arg.get_all_aliases().unwrap_or_default())
+        .map(|long| format!("--{long}"))
+        .collect::<Vec<_>>();
+    let short = arg.get_short().map(|short| format!("-{short}"));
+    args.iter().skip(1).filter_map(|arg| arg.to_str()).take_while(|arg| *arg != "--").any(|arg| {
+        longs
+            .iter()
+            .any(|long| arg == long || arg.strip_prefix(long.as_str()).is_some_and(|rest| rest.starts_with('=')))
+            || short.as_deref().is_some_and(|short| arg.starts_with(short) && !arg.starts_with("--"))
+    })
+}
+
+fn toml_value(value: &str) -> String {
+    if value.parse::<i64>().is_ok() || value == "true" || value == "false" {
+        value.to_string()
+    } else {
+        toml::Value::String(value.into()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use tempfile::TempDir;
+
+    fn command() -> Command {
+        Command::new("madara")
+            .arg(Arg::new("config").long("config"))
+            .arg(Arg::new("rpc-port").long("rpc-port").short('p').default_value("9944").help("The RPC port."))
+            .arg(Arg::new("rpc-cors").long("rpc-cors").alias("cors").action(ArgAction::Append).default_value("*"))
+            .arg(Arg::new("devnet").long("devnet").action(ArgAction::SetTrue))
+            .arg(Arg::new("base-path").long("base-path").env("MADARA_TEST_CONFIG_FILE_BASE_PATH"))
+            .arg(Arg::new("secret").long("secret").hide(true))
+    }
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        std::iter::once("madara").chain(args.iter().copied()).map(OsString::from).collect()
+    }
+
+    fn file_args(name: &str, content: &str, args: &[&str]) -> anyhow::Result<Vec<String>> {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        let mut res = config_file_args(&path, &command(), &os_args(args))?
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect::<Vec<_>>();
+        res.sort();
+        Ok(res)
+    }
+
+    #[rstest]
+    #[case::toml("config.toml", "rpc_port = 9945\nrpc-cors = [\"a\", \"b\"]\ndevnet = true\n")]
+    #[case::yaml("config.yaml", "rpc_port: 9945\nrpc-cors: [a, b]\ndevnet: true\n")]
+    fn test_config_file_args(#[case] name: &str, #[case] content: &str) {
+        assert_eq!(
+            file_args(name, content, &[]).unwrap(),
+            ["--devnet", "--rpc-cors=a", "--rpc-cors=b", "--rpc-port=9945"]
+        );
+    }
+
+    #[rstest]
+    #[case::long(&["--rpc-port", "1"])]
+    #[case::long_equals(&["--rpc-port=1"])]
+    #[case::short(&["-p1"])]
+    fn test_config_file_args_command_line_precedence(#[case] args: &[&str]) {
+        assert_eq!(file_args("config.toml", "rpc-port = 9945\ndevnet = true\n", args).unwrap(), ["--devnet"]);
+    }
+
+    #[test]
+    fn test_config_file_args_bool() {
+        assert_eq!(file_args("config.toml", "devnet = false\n", &[]).unwrap(), Vec::<String>::new());
+        let err = file_args("config.toml", "devnet = \"yes\"\n", &[]).unwrap_err();
+        assert!(err.to_string().contains("must be a boolean"), "{err:#}");
+    }
+
+    #[rstest]
+    #[case::unknown("unknown = 1\n", "Unknown parameter `unknown`")]
+    #[case::config("config = \"other.toml\"\n", "cannot include another config file")]
+    #[case::table("[rpc-port]\nport = 1\n", "must be a string, a number, a boolean or a list of them")]
+    fn test_config_file_args_invalid(#[case] content: &str, #[case] expected: &str) {
+        let err = file_args("config.toml", content, &[]).unwrap_err();
+        assert!(err.to_string().contains(expected), "{err:#}");
+    }
+
+    #[test]
+    fn test_config_file_args_extension() {
+        let err = file_args("config.json", "{}", &[]).unwrap_err();
+        assert!(err.to_string().contains("must be a .toml, .yaml or .yml file"), "{err:#}");
+    }
+
+    #[rstest]
+    #[case::long("rpc-port", &["--rpc-port", "1"], true)]
+    #[case::long_equals("rpc-port", &["--rpc-port=1"], true)]
+    #[case::short("rpc-port", &["-p", "1"], true)]
+    #[case::short_attached("rpc-port", &["-p1"], true)]
+    #[case::alias("rpc-cors", &["--cors=a"], true)]
+    #[case::prefix("rpc-port", &["--rpc-port-other=1"], false)]
+    #[case::other("rpc-port", &["--devnet"], false)]
+    #[case::after_separator("rpc-port", &["--", "--rpc-port=1"], false)]
+    fn test_is_set(#[case] id: &str, #[case] args: &[&str], #[case] expected: bool) {
+        let command = command();
+        let arg = command.get_arguments().find(|arg| arg.get_id() == id).unwrap();
+        assert_eq!(is_set(arg, &os_args(args)), expected);
+    }
+
+    #[test]
+    fn test_is_set_env() {
+        let command = command();
+        let arg = command.get_arguments().find(|arg| arg.get_id() == "base-path").unwrap();
+        assert!(!is_set(arg, &os_args(&[])));
+        std::env::set_var("MADARA_TEST_CONFIG_FILE_BASE_PATH", "/tmp/madara");
+        assert!(is_set(arg, &os_args(&[])));
+        std::env::remove_var("MADARA_TEST_CONFIG_FILE_BASE_PATH");
+    }
+
+    #[test]
+    fn test_print_defaults() {
+        let defaults = print_defaults(&command());
+        assert!(defaults.contains("\n# The RPC port.\nrpc-port = 9944\n"), "{defaults}");
+        assert!(defaults.contains("\nrpc-cors = [\"*\"]\n"), "{defaults}");
+        assert!(defaults.contains("\ndevnet = false\n"), "{defaults}");
+        assert!(defaults.contains("\n# base-path =\n"), "{defaults}");
+        assert!(!defaults.contains("secret"), "{defaults}");
+        assert!(!defaults.contains("\nconfig"), "{defaults}");
+        // The defaults are a valid config file.
+        let values: toml::Table = toml::from_str(&defaults).unwrap();
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn test_config_file_path() {
+        assert_eq!(config_file_path(&os_args(&["--config", "a.toml"])), Some("a.toml".into()));
+        assert_eq!(config_file_path(&os_args(&["--devnet", "--config=b.yaml"])), Some("b.yaml".into()));
+    }
+}
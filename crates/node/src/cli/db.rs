@@ -1,5 +1,12 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
 use std::path::PathBuf;
 
+use anyhow::Context;
+use mc_db::maintenance::DbMaintenance;
+use mc_db::migration::DB_VERSION;
+use mc_db::Column;
+
 #[derive(Clone, Debug, clap::Args)]
 pub struct DbParams {
     /// The path where madara will store the database. You should probably change it.
@@ -14,3 +21,138 @@ pub struct DbParams {
     #[clap(env = "MADARA_RESTORE_FROM_LATEST_BACKUP", long)]
     pub restore_from_latest_backup: bool,
 }
+
+/// Parameters of `madara db`.
+#[derive(Clone, Debug, clap::Args)]
+pub struct DbCmd {
+    /// The path of the database, see the `--base-path` parameter of the node.
+    #[clap(env = "MADARA_BASE_PATH", long, default_value = "/tmp/madara", value_name = "PATH", global = true)]
+    pub base_path: PathBuf,
+
+    #[command(subcommand)]
+    pub command: DbSubcommand,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+pub enum DbSubcommand {
+    /// Print the chain of the database, its latest block, and the size of every column.
+    Info,
+    /// Export columns of the database to a file.
+    Export {
+        /// The file to write the export to.
+        file: PathBuf,
+        /// Only export these columns. Every column is exported by default.
+        #[clap(long = "column", value_parser = parse_column, value_name = "COLUMN")]
+        columns: Vec<Column>,
+    },
+    /// Import a file created with `db export`. The imported entries replace the existing ones with the same keys.
+    Import {
+        /// The file to import.
+        file: PathBuf,
+    },
+    /// Remove the data that is not needed to run the node (trie logs, fork cache), and compact the database.
+    Prune,
+    /// Remove every entry of a column. This is irreversible, and the node may not be able to run without the column.
+    DropColumn {
+        #[clap(value_parser = parse_column)]
+        column: Column,
+    },
+    /// Recompute the contract and class tries from the flat state at a block, and compare them to the global state
+    /// root of the block. At the latest block, also report the first contract which differs from the stored tries.
+    /// This reads the whole state of the chain.
+    VerifyTries {
+        /// The block to verify, the latest block by default.
+        #[clap(long, value_name = "BLOCK NUMBER")]
+        block: Option<u64>,
+    },
+}
+
+impl DbCmd {
+    pub fn run(self) -> anyhow::Result<()> {
+        let db = DbMaintenance::open(&self.base_path)?;
+        match self.command {
+            DbSubcommand::Info => {
+                let info = db.info()?;
+                match (info.chain_name, info.chain_id) {
+                    (Some(chain_name), Some(chain_id)) => println!("Chain: {chain_name} (chain id `{chain_id}`)"),
+                    _ => println!("Chain: unknown"),
+                }
+                println!(
+                    "Database version: {} (supported: {DB_VERSION})",
+                    info.db_version.map_or("none".into(), |v| v.to_string())
+                );
+                println!("Latest block: {}", info.latest_block_n.map_or("none".into(), |n| format!("#{n}")));
+                println!(
+                    "Latest l1 confirmed block: {}",
+                    info.l1_last_confirmed_block.map_or("none".into(), |n| format!("#{n}"))
+                );
+                println!();
+                println!("{:<40} {:>14} {:>14}", "Column", "Size (bytes)", "Keys (est.)");
+                for column in &info.columns {
+                    println!("{:<40} {:>14} {:>14}", column.column.to_string(), column.size, column.num_keys);
+                }
+                println!("{:<40} {:>14}", "Total", info.columns.iter().map(|column| column.size).sum::<u64>());
+            }
+            DbSubcommand::Export { file, columns } => {
+                let columns = if columns.is_empty() { Column::ALL.to_vec() } else { columns };
+                let writer = BufWriter::new(
+                    File::create(&file).with_context(|| format!("Creating export file {}", file.display()))?,
+                );
+                let count = db.export(&columns, writer)?;
+                log::info!("💾 Exported {count} entries to {}", file.display());
+            }
+            DbSubcommand::Import { file } => {
+                let reader = BufReader::new(
+                    File::open(&file).with_context(|| format!("Opening export file {}", file.display()))?,
+                );
+                let count = db.import(reader)?;
+                log::info!("💾 Imported {count} entries from {}", file.display());
+            }
+            DbSubcommand::Prune => {
+                db.prune()?;
+                log::info!("💾 Pruned the database");
+            }
+            DbSubcommand::DropColumn { column } => {
+                db.drop_column(column)?;
+                log::info!("💾 Dropped column {column}");
+            }
+            DbSubcommand::VerifyTries { block } => {
+                let verification = db.verify_tries(block)?;
+                println!("Block: #{}", verification.block_n);
+                println!("Contracts: {}", verification.n_contracts);
+                println!("Declared Sierra classes: {}", verification.n_classes);
+                println!("Contract trie root: {:#x}", verification.contract_trie_root);
+                println!("Class trie root: {:#x}", verification.class_trie_root);
+                println!("Global state root: {:#x}", verification.state_root);
+                println!("Global state root in the block header: {:#x}", verification.expected_state_root);
+                if let Some(contract) = &verification.divergent_contract {
+                    println!();
+                    println!("First divergent contract: {:#x}", contract.contract_address);
+                    println!("  Class hash: {:#x}", contract.class_hash);
+                    println!("  Nonce: {:#x}", contract.nonce);
+                    println!("  Storage root: {:#x}", contract.storage_root);
+                    println!("  Stored storage root: {:#x}", contract.stored_storage_root);
+                    println!("  Leaf hash: {:#x}", contract.leaf_hash);
+                    println!(
+                        "  Stored leaf hash: {}",
+                        contract.stored_leaf_hash.map_or("missing".into(), |hash| format!("{hash:#x}"))
+                    );
+                }
+                anyhow::ensure!(
+                    verification.is_consistent(),
+                    "The tries of block #{} are inconsistent with the flat state",
+                    verification.block_n
+                );
+                log::info!("💾 The tries of block #{} are consistent", verification.block_n);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_column(name: &str) -> Result<Column, String> {
+    Column::from_rocksdb_name(name).ok_or_else(|| {
+        let names = Column::ALL.iter().map(|column| column.to_string()).collect::<Vec<_>>();
+        format!("unknown column, expected one of: {}", names.join(", "))
+    })
+}
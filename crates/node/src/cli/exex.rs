@@ -0,0 +1,61 @@
+use mp_exex::{ExExManagerConfig, SlowConsumerPolicy};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct ExExParams {
+    /// Execution extensions to enable, by id. Extensions can also be enabled and configured in the `--exex-config`
+    /// file.
+    #[clap(env = "MADARA_EXEX", long = "exex", value_name = "ID", value_delimiter = ',')]
+    pub exexs: Vec<String>,
+
+    /// Path to the TOML config file of the execution extensions. Each `[<id>]` table enables the extension with the
+    /// same id, unless it contains `enabled = false`, and the rest of the table is its configuration.
+    #[clap(env = "MADARA_EXEX_CONFIG", long, value_name = "PATH")]
+    pub exex_config: Option<PathBuf>,
+
+    /// Directory of the WASM execution extensions to run along the node. Every `.wasm` file of the directory is
+    /// loaded as an ExEx, with the file name as its id.
+    #[clap(env = "MADARA_EXEX_WASM_DIR", long, value_name = "PATH")]
+    pub exex_wasm_dir: Option<PathBuf>,
+
+    /// Fuel given to each call into a WASM execution extension, roughly the number of instructions it may execute.
+    /// An extension running out of fuel fails instead of stalling the node.
+    #[clap(env = "MADARA_EXEX_WASM_FUEL", long, default_value_t = 1_000_000_000, value_name = "FUEL")]
+    pub exex_wasm_fuel: u64,
+
+    /// Number of notifications buffered for the execution extensions before the slow consumer policy applies.
+    #[clap(env = "MADARA_EXEX_BUFFER_CAPACITY", long, default_value_t = 16, value_name = "NOTIFICATIONS")]
+    pub exex_buffer_capacity: usize,
+
+    /// What to do when an execution extension cannot keep up with the blocks.
+    #[clap(env = "MADARA_EXEX_SLOW_CONSUMER_POLICY", long, value_enum, default_value_t = ExExSlowConsumerPolicy::Pause)]
+    pub exex_slow_consumer_policy: ExExSlowConsumerPolicy,
+}
+
+impl ExExParams {
+    pub fn manager_config(&self) -> ExExManagerConfig {
+        ExExManagerConfig { capacity: self.exex_buffer_capacity, policy: self.exex_slow_consumer_policy.into() }
+    }
+}
+
+/// What to do when an execution extension cannot keep up with the blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExExSlowConsumerPolicy {
+    /// Keep buffering the notifications, without bound.
+    Buffer,
+    /// Drop the oldest notifications over the buffer capacity, except the reverted blocks. Extensions may miss
+    /// blocks, only use this for extensions that can tolerate it.
+    DropOldest,
+    /// Pause block production and sync until the extension catches up.
+    Pause,
+}
+
+impl From<ExExSlowConsumerPolicy> for SlowConsumerPolicy {
+    fn from(value: ExExSlowConsumerPolicy) -> Self {
+        match value {
+            ExExSlowConsumerPolicy::Buffer => Self::Buffer,
+            ExExSlowConsumerPolicy::DropOldest => Self::DropOldest,
+            ExExSlowConsumerPolicy::Pause => Self::Pause,
+        }
+    }
+}
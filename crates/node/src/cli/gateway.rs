@@ -1,4 +1,14 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
 use clap::Args;
+use mc_gateway::server::gossip::GossipConfig;
+use mc_gateway::server::rate_limit::RateLimitConfig;
+use mc_gateway::server::service::GatewayServerConfig;
+use mc_gateway::server::tls::TlsConfig;
+use mc_gateway::server::trace_cache::DEFAULT_TRACE_CACHE_SIZE;
+use url::Url;
 
 /// Parameters used to config gateway.
 #[derive(Debug, Clone, Args)]
@@ -12,10 +22,114 @@ pub struct GatewayParams {
     pub gateway_enable: bool,
 
     /// Listen on all network interfaces. This usually means the gateway server will be accessible externally.
-    #[arg(env = "MADARA_GATEWAY_EXTERNAL", long)]
+    #[arg(env = "MADARA_GATEWAY_EXTERNAL", long, conflicts_with = "gateway_listen_address")]
     pub gateway_external: bool,
 
+    /// The address of the network interface the gateway server listens on, IPv4 or IPv6.
+    ///
+    /// Defaults to localhost, or all network interfaces with `--gateway-external`.
+    #[arg(env = "MADARA_GATEWAY_LISTEN_ADDRESS", long, value_name = "IP ADDRESS")]
+    pub gateway_listen_address: Option<IpAddr>,
+
     /// The gateway port to listen at.
     #[arg(env = "MADARA_GATEWAY_PORT", long, value_name = "GATEWAY PORT", default_value = "8080")]
     pub gateway_port: u16,
+
+    /// Serve the gateway over HTTPS, with the certificate chain of this PEM file.
+    #[arg(env = "MADARA_GATEWAY_TLS_CERT", long, value_name = "PATH", requires = "gateway_tls_key")]
+    pub gateway_tls_cert: Option<PathBuf>,
+
+    /// The PEM file containing the private key of the `--gateway-tls-cert` certificate.
+    #[arg(env = "MADARA_GATEWAY_TLS_KEY", long, value_name = "PATH", requires = "gateway_tls_cert")]
+    pub gateway_tls_key: Option<PathBuf>,
+
+    /// Gateway rate limiting (requests/minute) for each client IP.
+    ///
+    /// This is disabled by default.
+    #[arg(env = "MADARA_GATEWAY_RATE_LIMIT", long)]
+    pub gateway_rate_limit: Option<NonZeroU32>,
+
+    /// Gateway rate limiting (requests/minute) across all clients.
+    ///
+    /// This is disabled by default.
+    #[arg(env = "MADARA_GATEWAY_GLOBAL_RATE_LIMIT", long)]
+    pub gateway_global_rate_limit: Option<NonZeroU32>,
+
+    /// Requests with this key in the `X-Throttling-Bypass` header are not rate limited.
+    #[arg(env = "MADARA_GATEWAY_RATE_LIMIT_BYPASS_KEY", long, value_name = "KEY")]
+    pub gateway_rate_limit_bypass_key: Option<String>,
+
+    /// Trust proxy headers to identify the clients for rate limiting.
+    ///
+    /// When using a reverse proxy setup, the real requester IP is usually added to the headers as `X-Real-IP` or `X-Forwarded-For`.
+    /// By default, the gateway server will not trust these headers.
+    #[arg(env = "MADARA_GATEWAY_RATE_LIMIT_TRUST_PROXY_HEADERS", long)]
+    pub gateway_rate_limit_trust_proxy_headers: bool,
+
+    /// Number of blocks whose `get_block_traces` responses are kept in memory, so that the tools reading the traces
+    /// of the same blocks do not re-execute them every time. `0` disables the cache.
+    #[arg(env = "MADARA_GATEWAY_TRACE_CACHE_SIZE", long, value_name = "BLOCKS", default_value_t = DEFAULT_TRACE_CACHE_SIZE)]
+    pub gateway_trace_cache_size: usize,
+
+    /// Gateway URLs of the other replicas of this sequencer. The transactions accepted by this node are pushed to
+    /// them, so that users can submit to any replica while a single one produces blocks.
+    ///
+    /// All the replicas must list each other, and share the same `--gateway-gossip-secret`.
+    #[arg(
+        env = "MADARA_GATEWAY_GOSSIP_PEERS",
+        long,
+        value_name = "URL",
+        value_delimiter = ',',
+        requires = "gateway_gossip_secret"
+    )]
+    pub gateway_gossip_peers: Vec<Url>,
+
+    /// Secret authenticating the transactions pushed by the replicas, in the `X-Gossip-Secret` header.
+    #[arg(env = "MADARA_GATEWAY_GOSSIP_SECRET", long, value_name = "SECRET", requires = "gateway_enable")]
+    pub gateway_gossip_secret: Option<String>,
+}
+
+impl GatewayParams {
+    pub fn listen_addr(&self) -> SocketAddr {
+        let ip = match self.gateway_listen_address {
+            Some(ip) => ip,
+            None if self.gateway_external => Ipv4Addr::UNSPECIFIED.into(), // listen on 0.0.0.0
+            None => Ipv4Addr::LOCALHOST.into(),
+        };
+        SocketAddr::new(ip, self.gateway_port)
+    }
+
+    pub fn tls(&self) -> Option<TlsConfig> {
+        match (&self.gateway_tls_cert, &self.gateway_tls_key) {
+            (Some(cert_path), Some(key_path)) => {
+                Some(TlsConfig { cert_path: cert_path.clone(), key_path: key_path.clone() })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn server_config(&self) -> GatewayServerConfig {
+        GatewayServerConfig {
+            feeder_gateway_enable: self.feeder_gateway_enable,
+            gateway_enable: self.gateway_enable,
+            listen_addr: self.listen_addr(),
+            tls: self.tls(),
+            rate_limit: self.rate_limit(),
+            trace_cache_size: self.gateway_trace_cache_size,
+        }
+    }
+
+    pub fn gossip_config(&self) -> Option<GossipConfig> {
+        let secret = self.gateway_gossip_secret.clone()?;
+        Some(GossipConfig { peers: self.gateway_gossip_peers.clone(), secret })
+    }
+
+    pub fn rate_limit(&self) -> RateLimitConfig {
+        RateLimitConfig {
+            per_ip: self.gateway_rate_limit,
+            global: self.gateway_global_rate_limit,
+            bypass_key: self.gateway_rate_limit_bypass_key.clone(),
+            trust_proxy_headers: self.gateway_rate_limit_trust_proxy_headers,
+        }
+    }
 }
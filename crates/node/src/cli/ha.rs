@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use mp_utils::parsers::{parse_duration, parse_url};
+use url::Url;
+
+use crate::service::HaConfig;
+
+/// Parameters of the high availability of the sequencer.
+#[derive(Clone, Debug, clap::Args)]
+pub struct HaParams {
+    /// Run the sequencer as one of several instances, of which a single one produces blocks. The leader is elected
+    /// through a lease held in the etcd cluster at this endpoint, and the standbys sync the blocks from its gateway.
+    #[arg(
+        env = "MADARA_HA_ETCD_ENDPOINT",
+        long,
+        value_parser = parse_url,
+        value_name = "URL",
+        requires_all = ["sequencer", "ha_advertised_gateway"],
+        conflicts_with = "devnet"
+    )]
+    pub ha_etcd_endpoint: Option<Url>,
+
+    /// Key of the leadership lease, shared by all the instances of the sequencer.
+    #[arg(env = "MADARA_HA_LEASE_KEY", long, value_name = "KEY", default_value = "/madara/sequencer/leader")]
+    pub ha_lease_key: String,
+
+    /// Time to live of the leadership lease. A standby takes over once the leader did not renew it for this long,
+    /// and the leader stops producing blocks after half of it. etcd only supports a whole number of seconds.
+    #[arg(env = "MADARA_HA_LEASE_TTL", long, value_parser = parse_lease_ttl, default_value = "10s")]
+    pub ha_lease_ttl: Duration,
+
+    /// Gateway URL of this instance, which the standbys sync from while it is the leader. Its gateway and feeder
+    /// gateway must be enabled.
+    #[arg(env = "MADARA_HA_ADVERTISED_GATEWAY", long, value_parser = parse_url, value_name = "URL")]
+    pub ha_advertised_gateway: Option<Url>,
+}
+
+fn parse_lease_ttl(s: &str) -> Result<Duration, String> {
+    let ttl = parse_duration(s).map_err(|err| format!("{err:#}"))?;
+    if ttl.is_zero() || ttl.subsec_nanos() != 0 {
+        return Err(format!("the lease TTL must be a whole number of seconds, got {ttl:?}"));
+    }
+    Ok(ttl)
+}
+
+impl HaParams {
+    pub fn ha_config(&self) -> Option<HaConfig> {
+        Some(HaConfig {
+            etcd_endpoint: self.ha_etcd_endpoint.clone()?,
+            lease_key: self.ha_lease_key.clone(),
+            lease_ttl: self.ha_lease_ttl,
+            advertised_gateway: self.ha_advertised_gateway.clone()?,
+        })
+    }
+}
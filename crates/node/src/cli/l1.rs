@@ -1,19 +1,58 @@
 use std::time::Duration;
 
+use alloy::primitives::Address;
+use anyhow::Context;
+use mc_eth::l1_gas_price::{GasPriceBounds, PriceBounds};
 use url::Url;
 
 use mp_utils::parsers::{parse_duration, parse_url};
 
 #[derive(Clone, Debug, clap::Args)]
 pub struct L1SyncParams {
+    /// Run a sovereign chain, without any settlement layer. The L1 sync service is disabled, no core contract is
+    /// needed, and blocks are considered final as soon as they are stored locally.
+    #[clap(
+        env = "MADARA_SOVEREIGN",
+        long,
+        conflicts_with_all = ["l1_endpoint", "l1_ws_endpoint", "state_root_verification_interval", "settlement_enable"]
+    )]
+    pub sovereign: bool,
+
     /// Disable L1 sync.
     #[clap(env = "MADARA_SYNC_L1_DISABLED", long, alias = "no-l1-sync", conflicts_with = "l1_endpoint")]
     pub sync_l1_disabled: bool,
 
-    /// The L1 rpc endpoint url for state verification.
+    /// The L1 rpc endpoint url for state verification. When settling on Starknet (L3 mode), this is the
+    /// Starknet rpc endpoint url.
     #[clap(env = "MADARA_L1_ENDPOINT", long, value_parser = parse_url, value_name = "ETHEREUM RPC URL")]
     pub l1_endpoint: Option<Url>,
 
+    /// The L1 websocket endpoint url. When provided, new L1 heads and core contract events are received
+    /// through `eth_subscribe` instead of being polled, which lowers latency. Madara falls back to polling
+    /// the http endpoint if the websocket endpoint is unavailable.
+    #[clap(env = "MADARA_L1_WS_ENDPOINT", long, value_parser = parse_url, value_name = "ETHEREUM WS URL")]
+    pub l1_ws_endpoint: Option<Url>,
+
+    /// Chain id of the L1 endpoint, checked at startup. Defaults to Ethereum mainnet for Starknet mainnet, and to
+    /// Sepolia for the Starknet test networks; not checked for the other chains unless given.
+    #[clap(env = "MADARA_L1_CHAIN_ID", long, value_name = "CHAIN ID")]
+    pub l1_chain_id: Option<u64>,
+
+    /// Interval at which the local global state root is compared with the one verified by the core contract.
+    /// A mismatch is logged and reported through the `madara_l1_state_root_mismatch` metric. Disabled by default.
+    #[clap(
+        env = "MADARA_STATE_ROOT_VERIFICATION_INTERVAL",
+        long,
+        value_parser = parse_duration,
+        value_name = "DURATION"
+    )]
+    pub state_root_verification_interval: Option<Duration>,
+
+    /// Reject the transactions submitted through the RPC and gateway write endpoints while the local state
+    /// root does not match the one verified by the core contract.
+    #[clap(env = "MADARA_HALT_WRITES_ON_STATE_ROOT_MISMATCH", long, requires = "state_root_verification_interval")]
+    pub halt_writes_on_state_root_mismatch: bool,
+
     /// Disable the gas price sync service. The sync service is responsible to fetch the fee history from the ethereum.
     #[clap(env = "MADARA_GAS_PRICE_SYNC_DISABLED", long, alias = "no-gas-price-sync")]
     pub gas_price_sync_disabled: bool,
@@ -26,4 +65,129 @@ pub struct L1SyncParams {
         value_parser = parse_duration,
     )]
     pub gas_price_poll: Duration,
+
+    /// Where the sequencer gets its gas prices from.
+    #[clap(env = "MADARA_GAS_PRICE_STRATEGY", long, value_enum, default_value_t = GasPriceStrategy::L1)]
+    pub gas_price_strategy: GasPriceStrategy,
+
+    /// L1 gas price in wei, used with the `fixed` gas price strategy.
+    #[clap(env = "MADARA_FIXED_GAS_PRICE", long, value_name = "WEI", required_if_eq("gas_price_strategy", "fixed"))]
+    pub fixed_gas_price: Option<u128>,
+
+    /// L1 data gas price in wei, used with the `fixed` gas price strategy.
+    #[clap(
+        env = "MADARA_FIXED_DATA_GAS_PRICE",
+        long,
+        value_name = "WEI",
+        required_if_eq("gas_price_strategy", "fixed")
+    )]
+    pub fixed_data_gas_price: Option<u128>,
+
+    /// L1 gas price in fri, used with the `fixed` gas price strategy.
+    #[clap(
+        env = "MADARA_FIXED_STRK_GAS_PRICE",
+        long,
+        value_name = "FRI",
+        required_if_eq("gas_price_strategy", "fixed")
+    )]
+    pub fixed_strk_gas_price: Option<u128>,
+
+    /// L1 data gas price in fri, used with the `fixed` gas price strategy.
+    #[clap(
+        env = "MADARA_FIXED_STRK_DATA_GAS_PRICE",
+        long,
+        value_name = "FRI",
+        required_if_eq("gas_price_strategy", "fixed")
+    )]
+    pub fixed_strk_data_gas_price: Option<u128>,
+
+    /// Address of the gas price oracle contract on L1, used with the `oracle` gas price strategy.
+    /// The contract must expose a `getGasPrices()` view returning the eth gas price, eth data gas price,
+    /// strk gas price and strk data gas price, as `uint128`s.
+    #[clap(
+        env = "MADARA_GAS_PRICE_ORACLE_ADDRESS",
+        long,
+        value_name = "ADDRESS",
+        required_if_eq("gas_price_strategy", "oracle")
+    )]
+    pub gas_price_oracle_address: Option<Address>,
+
+    /// Address of a price feed contract on L1 giving the price of STRK in ETH, using the chainlink
+    /// aggregator interface. Used to derive the STRK gas prices with the `l1` gas price strategy.
+    #[clap(
+        env = "MADARA_STRK_PRICE_FEED_ADDRESS",
+        long,
+        value_name = "ADDRESS",
+        conflicts_with = "strk_price_api_url"
+    )]
+    pub strk_price_feed_address: Option<Address>,
+
+    /// Url of an http API giving the price of STRK in ETH. Used to derive the STRK gas prices with the `l1` gas
+    /// price strategy.
+    #[clap(env = "MADARA_STRK_PRICE_API_URL", long, value_parser = parse_url, value_name = "URL")]
+    pub strk_price_api_url: Option<Url>,
+
+    /// JSON pointer to the price in the response of the STRK price API, for example `/data/price`. The price can
+    /// either be a json number or a string.
+    #[clap(env = "MADARA_STRK_PRICE_API_JSON_POINTER", long, default_value = "/price", value_name = "POINTER")]
+    pub strk_price_api_json_pointer: String,
+
+    /// Minimum L1 gas price in wei. Lower gas prices are raised to this value before being used.
+    #[clap(env = "MADARA_MIN_GAS_PRICE", long, value_name = "WEI")]
+    pub min_gas_price: Option<u128>,
+
+    /// Maximum L1 gas price in wei. Higher gas prices are capped to this value before being used.
+    #[clap(env = "MADARA_MAX_GAS_PRICE", long, value_name = "WEI")]
+    pub max_gas_price: Option<u128>,
+
+    /// Minimum L1 data gas price in wei.
+    #[clap(env = "MADARA_MIN_DATA_GAS_PRICE", long, value_name = "WEI")]
+    pub min_data_gas_price: Option<u128>,
+
+    /// Maximum L1 data gas price in wei.
+    #[clap(env = "MADARA_MAX_DATA_GAS_PRICE", long, value_name = "WEI")]
+    pub max_data_gas_price: Option<u128>,
+
+    /// Minimum L1 gas price in fri.
+    #[clap(env = "MADARA_MIN_STRK_GAS_PRICE", long, value_name = "FRI")]
+    pub min_strk_gas_price: Option<u128>,
+
+    /// Maximum L1 gas price in fri.
+    #[clap(env = "MADARA_MAX_STRK_GAS_PRICE", long, value_name = "FRI")]
+    pub max_strk_gas_price: Option<u128>,
+
+    /// Minimum L1 data gas price in fri.
+    #[clap(env = "MADARA_MIN_STRK_DATA_GAS_PRICE", long, value_name = "FRI")]
+    pub min_strk_data_gas_price: Option<u128>,
+
+    /// Maximum L1 data gas price in fri.
+    #[clap(env = "MADARA_MAX_STRK_DATA_GAS_PRICE", long, value_name = "FRI")]
+    pub max_strk_data_gas_price: Option<u128>,
+}
+
+impl L1SyncParams {
+    /// Bounds applied to the gas prices, whatever the gas price strategy.
+    pub fn gas_price_bounds(&self) -> anyhow::Result<GasPriceBounds> {
+        Ok(GasPriceBounds {
+            eth_l1_gas_price: PriceBounds::new(self.min_gas_price, self.max_gas_price)
+                .context("Invalid `--min-gas-price` and `--max-gas-price`")?,
+            eth_l1_data_gas_price: PriceBounds::new(self.min_data_gas_price, self.max_data_gas_price)
+                .context("Invalid `--min-data-gas-price` and `--max-data-gas-price`")?,
+            strk_l1_gas_price: PriceBounds::new(self.min_strk_gas_price, self.max_strk_gas_price)
+                .context("Invalid `--min-strk-gas-price` and `--max-strk-gas-price`")?,
+            strk_l1_data_gas_price: PriceBounds::new(self.min_strk_data_gas_price, self.max_strk_data_gas_price)
+                .context("Invalid `--min-strk-data-gas-price` and `--max-strk-data-gas-price`")?,
+        })
+    }
+}
+
+/// The source of the gas prices used by the sequencer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GasPriceStrategy {
+    /// Fixed gas prices, given using the `--fixed-*gas-price` arguments.
+    Fixed,
+    /// Derived from the L1 base fee and blob base fee.
+    L1,
+    /// Read from an on-chain gas price oracle contract, given using `--gas-price-oracle-address`.
+    Oracle,
 }
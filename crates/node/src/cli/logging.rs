@@ -0,0 +1,63 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use mp_utils::parsers::parse_duration;
+
+use crate::util::{LogOutput, RotatingFile};
+
+#[derive(Clone, Debug, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human readable lines.
+    #[default]
+    Pretty,
+    /// One json object per line, with the `timestamp`, `level`, `target` and `message` fields.
+    Json,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct LoggingParams {
+    /// Log filter, using the `RUST_LOG` syntax (e.g., 'info', 'info,mc_sync=debug'). This can be changed while the node
+    /// is running, see `--config`.
+    #[arg(env = "RUST_LOG", long, value_name = "FILTER", default_value = "info")]
+    pub log_filter: String,
+
+    /// Format of the logs.
+    #[arg(env = "MADARA_LOG_FORMAT", long, value_enum, default_value_t = LogFormat::Pretty)]
+    pub log_format: LogFormat,
+
+    /// Write the logs to this file instead of stderr.
+    #[arg(env = "MADARA_LOG_FILE", long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Rotate the log file once it reaches this size, in megabytes.
+    #[arg(env = "MADARA_LOG_FILE_MAX_SIZE", long, value_name = "MB", default_value_t = 100, requires = "log_file")]
+    pub log_file_max_size: u64,
+
+    /// Also rotate the log file after this duration (e.g. '24h'). By default, the log file is only rotated based on
+    /// its size.
+    #[arg(env = "MADARA_LOG_FILE_ROTATION", long, value_parser = parse_duration, value_name = "DURATION", requires = "log_file")]
+    pub log_file_rotation: Option<Duration>,
+
+    /// Number of rotated log files to keep, named `<log-file>.1` (the most recent) to `<log-file>.<N>`.
+    #[arg(env = "MADARA_LOG_FILE_KEEP", long, value_name = "N", default_value_t = 5, requires = "log_file")]
+    pub log_file_keep: usize,
+}
+
+impl LoggingParams {
+    pub fn log_output(&self) -> anyhow::Result<LogOutput> {
+        let file = self
+            .log_file
+            .clone()
+            .map(|path| {
+                RotatingFile::open(
+                    path,
+                    self.log_file_max_size * 1024 * 1024,
+                    self.log_file_rotation,
+                    self.log_file_keep,
+                )
+            })
+            .transpose()?;
+        Ok(LogOutput { json: self.log_format == LogFormat::Json, file: file.map(|file| Arc::new(Mutex::new(file))) })
+    }
+}
@@ -1,33 +1,133 @@
 pub mod block_production;
+pub mod chain_config_new;
 pub mod chain_config_overrides;
+pub mod config_file;
 pub mod db;
+pub mod exex;
 pub mod gateway;
+pub mod ha;
 pub mod l1;
+pub mod logging;
+pub mod preflight;
 pub mod prometheus;
+pub mod prover;
 pub mod rpc;
+pub mod service;
+pub mod settlement;
 pub mod sync;
 pub mod telemetry;
+pub mod thread_pool;
 
 use crate::cli::l1::L1SyncParams;
 pub use block_production::*;
+pub use chain_config_new::*;
 pub use chain_config_overrides::*;
 pub use db::*;
+pub use exex::*;
 pub use gateway::*;
+pub use ha::*;
+pub use logging::*;
+pub use preflight::*;
 pub use prometheus::*;
+pub use prover::*;
 pub use rpc::*;
+pub use service::*;
+pub use settlement::*;
 use starknet_api::core::ChainId;
 use std::str::FromStr;
 pub use sync::*;
 pub use telemetry::*;
-
-use clap::ArgGroup;
-use mp_chain_config::ChainConfig;
+pub use thread_pool::*;
+
+use anyhow::Context;
+use clap::{ArgGroup, CommandFactory, Parser};
+use mp_chain_config::{ChainConfig, ChainConfigErrors};
+use mp_utils::parsers::parse_duration;
+use std::ffi::OsString;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 
 /// Madara: High performance Starknet sequencer/full-node.
 #[derive(Clone, Debug, clap::Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<MadaraCommand>,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub run: RunCmd,
+}
+
+impl Cli {
+    /// Parses the command line, with the parameters of the `--config` file when there is one.
+    pub fn parse_with_config_file() -> anyhow::Result<Self> {
+        Ok(Self::parse_from(Self::args_with_config_file()?))
+    }
+
+    /// Like [`Self::parse_with_config_file`], but returns an error instead of exiting when the parameters are invalid.
+    pub fn try_parse_with_config_file() -> anyhow::Result<Self> {
+        Self::try_parse_from(Self::args_with_config_file()?).context("Parsing parameters")
+    }
+
+    fn args_with_config_file() -> anyhow::Result<Vec<OsString>> {
+        let args = std::env::args_os().collect::<Vec<_>>();
+        let command = Self::command();
+        // The config file only holds parameters of the node, not of the subcommands.
+        if args.get(1).and_then(|arg| arg.to_str()).is_some_and(|arg| command.find_subcommand(arg).is_some()) {
+            return Ok(args);
+        }
+        let Some(path) = config_file::config_file_path(&args) else { return Ok(args) };
+
+        let config_args = config_file::config_file_args(&path, &command, &args)?;
+        let mut args = args.into_iter();
+        let bin = args.next();
+        Ok(bin.into_iter().chain(config_args).chain(args).collect())
+    }
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+pub enum MadaraCommand {
+    /// Configuration file helpers.
+    #[command(subcommand)]
+    Config(ConfigCmd),
+    /// Chain config helpers.
+    #[command(subcommand)]
+    ChainConfig(ChainConfigCmd),
+    /// Database maintenance, without starting the node. The node must not be running on the database.
+    Db(DbCmd),
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+pub enum ConfigCmd {
+    /// Print a configuration file with the default value of every parameter.
+    PrintDefaults,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+pub enum ChainConfigCmd {
+    /// Generate the chain config file of a new chain. The values that are not given as flags are asked for.
+    New(ChainConfigNewArgs),
+}
+
+impl MadaraCommand {
+    pub fn run(self) -> anyhow::Result<()> {
+        match self {
+            MadaraCommand::Config(ConfigCmd::PrintDefaults) => {
+                print!("{}", config_file::print_defaults(&Cli::command()));
+                Ok(())
+            }
+            MadaraCommand::ChainConfig(ChainConfigCmd::New(args)) => args.run(),
+            MadaraCommand::Db(db_cmd) => db_cmd.run(),
+        }
+    }
+}
+
+/// Parameters of the node.
+#[derive(Clone, Debug, clap::Args)]
 #[clap(
     group(
         ArgGroup::new("mode")
@@ -53,6 +153,51 @@ pub struct RunCmd {
     #[arg(env = "MADARA_NAME", long, value_name = "NAME")]
     pub name: Option<String>,
 
+    /// Read the parameters from a TOML or YAML file, keyed by flag name. Flags and environment variables take
+    /// precedence over the file. Use `madara config print-defaults` to get a file with every parameter.
+    ///
+    /// The file is read again on SIGHUP or with the `madara_reloadConfig` admin RPC method, which applies the log
+    /// filter, the RPC rate limits, the fixed gas prices and the pending block poll interval without a restart.
+    #[arg(env = "MADARA_CONFIG", long, value_name = "CONFIG FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Maximum time to wait for the services to stop on SIGTERM or Ctrl-C (e.g., '30s', '1min'). The RPC and gateway
+    /// servers stop accepting requests and finish the in-flight ones, and the block import and production finish their
+    /// current step; the node exits once this delay is elapsed even if they are not done.
+    #[arg(env = "MADARA_SHUTDOWN_TIMEOUT", long, value_parser = parse_duration, default_value = "30s", value_name = "DURATION")]
+    pub shutdown_timeout: Duration,
+
+    /// Serve the task instrumentation of the tokio runtime to `tokio-console`, to debug stuck or starved tasks. Needs a
+    /// build with `RUSTFLAGS="--cfg tokio_unstable"`, which also adds the `madara_tokio_*` runtime metrics.
+    #[arg(env = "MADARA_TOKIO_CONSOLE", long)]
+    pub tokio_console: bool,
+
+    /// Address of the `tokio-console` server.
+    #[arg(
+        env = "MADARA_TOKIO_CONSOLE_ADDR",
+        long,
+        value_name = "ADDR",
+        default_value = "127.0.0.1:6669",
+        requires = "tokio_console"
+    )]
+    pub tokio_console_addr: SocketAddr,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub logging_params: LoggingParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub service_params: ServiceParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub thread_pool_params: ThreadPoolParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub preflight_params: PreflightParams,
+
     #[allow(missing_docs)]
     #[clap(flatten)]
     pub db_params: DbParams,
@@ -65,6 +210,10 @@ pub struct RunCmd {
     #[clap(flatten)]
     pub l1_sync_params: L1SyncParams,
 
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub settlement_params: SettlementParams,
+
     #[allow(missing_docs)]
     #[clap(flatten)]
     pub telemetry_params: TelemetryParams,
@@ -85,6 +234,18 @@ pub struct RunCmd {
     #[clap(flatten)]
     pub block_production_params: BlockProductionParams,
 
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub exex_params: ExExParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub ha_params: HaParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub prover_params: ProverParams,
+
     /// The node will run as a sequencer and produce its own state.
     #[arg(env = "MADARA_SEQUENCER", long, group = "mode")]
     pub sequencer: bool,
@@ -132,10 +293,8 @@ impl RunCmd {
             // Read from the preset if provided
             (Some(preset), _, _) => ChainConfig::from(preset),
             // Read the config path if provided
-            (_, Some(path), _) => ChainConfig::from_yaml(path).map_err(|err| {
-                log::error!("Failed to load config from YAML at path '{}': {}", path.display(), err);
-                anyhow::anyhow!("Failed to load chain config from file")
-            })?,
+            (_, Some(path), _) => ChainConfig::from_yaml(path)
+                .with_context(|| format!("Failed to load the chain config from {}", path.display()))?,
             // Devnet default preset is Devnet if not provided by CLI
             (_, _, true) => ChainConfig::from(&ChainPreset::Devnet),
             _ => {
@@ -153,6 +312,16 @@ impl RunCmd {
             chain_config = self.chain_config_override.override_chain_config(chain_config)?;
         };
 
+        if self.devnet {
+            if let Some(chain_id) = &self.block_production_params.devnet_chain_id {
+                chain_config.chain_id = ChainId::from(chain_id.clone());
+            }
+            if let Some(timestamp) = self.block_production_params.devnet_genesis_timestamp {
+                chain_config.genesis_timestamp = Some(timestamp);
+            }
+        }
+
+        self.validate_chain_config(&chain_config)?;
         Ok(Arc::new(chain_config))
     }
 
@@ -173,9 +342,18 @@ impl RunCmd {
             chain_config = self.chain_config_override.override_chain_config(chain_config)?;
         }
 
+        self.validate_chain_config(&chain_config)?;
         Ok(Arc::new(chain_config))
     }
 
+    /// Checks the chain config, and that its blocks fit in the limits of the node. Every issue is reported at once.
+    fn validate_chain_config(&self, chain_config: &ChainConfig) -> anyhow::Result<()> {
+        let mut issues = chain_config.issues();
+        issues.extend(self.rpc_params.chain_config_issues(chain_config));
+        ChainConfigErrors::check(issues)?;
+        Ok(())
+    }
+
     pub fn is_sequencer(&self) -> bool {
         self.sequencer || self.devnet
     }
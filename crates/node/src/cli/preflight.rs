@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+use mp_utils::parsers::parse_duration;
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct PreflightParams {
+    /// Skip the checks run at startup: file-descriptor limit, data directory, disk space, clock skew with the
+    /// gateway and chain id of the L1 endpoint.
+    #[arg(env = "MADARA_NO_PREFLIGHT_CHECKS", long)]
+    pub no_preflight_checks: bool,
+
+    /// Minimum free disk space in the data directory, in gigabytes. When syncing, the node also needs enough space
+    /// for the blocks it is behind, estimated from the current size of the database.
+    #[arg(env = "MADARA_PREFLIGHT_MIN_DISK_SPACE", long, value_name = "GB", default_value_t = 10)]
+    pub preflight_min_disk_space: u64,
+
+    /// Maximum difference between the system clock and the clock of the gateway.
+    #[arg(env = "MADARA_PREFLIGHT_MAX_CLOCK_SKEW", long, value_parser = parse_duration, default_value = "30s", value_name = "DURATION")]
+    pub preflight_max_clock_skew: Duration,
+}
@@ -12,4 +12,10 @@ pub struct PrometheusParams {
     /// Disable the prometheus service.
     #[arg(env = "MADARA_PROMETHEUS_DISABLED", long, alias = "no-prometheus")]
     pub prometheus_disabled: bool,
+    /// Serve CPU profiles and heap statistics on the prometheus port: `/debug/pprof/profile?seconds=30` (pprof
+    /// format), `/debug/pprof/flamegraph?seconds=30` (SVG) and `/debug/pprof/heap`, the latter only when the node is
+    /// built with the `jemalloc` feature. Taking a profile slows the node down while it lasts, the profiles can't be
+    /// served on all network interfaces.
+    #[arg(env = "MADARA_PROMETHEUS_PROFILING", long, conflicts_with = "prometheus_external")]
+    pub prometheus_profiling: bool,
 }
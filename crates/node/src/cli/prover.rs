@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use mc_prover::worker::ProvingConfig;
+use mc_prover::{ProverClientConfig, ProverKind};
+use mp_utils::parsers::{parse_duration, parse_url};
+use url::Url;
+
+/// Parameters of the proving of the blocks by an external prover.
+#[derive(Clone, Debug, clap::Args)]
+pub struct ProverParams {
+    /// Submit the Cairo PIEs of the blocks to this proving service. The PIEs are generated by the `snos` ExEx.
+    #[clap(env = "MADARA_PROVER", long, value_enum, value_name = "PROVER", requires_all = ["prover_url", "prover_pie_dir"])]
+    pub prover: Option<ProverType>,
+
+    /// Endpoint of the proving service.
+    #[clap(env = "MADARA_PROVER_URL", long, value_parser = parse_url, value_name = "URL")]
+    pub prover_url: Option<Url>,
+
+    /// API key of the proving service, for Atlantic.
+    #[clap(env = "MADARA_PROVER_API_KEY", long, value_name = "KEY", hide_env_values = true)]
+    pub prover_api_key: Option<String>,
+
+    /// URL the proofs are downloaded from, for the provers that store them separately. `{job_id}` is replaced with
+    /// the id of the proving job. Proofs are not downloaded when unset.
+    #[clap(env = "MADARA_PROVER_PROOF_URL", long, value_name = "URL")]
+    pub prover_proof_url: Option<String>,
+
+    /// Directory of the Cairo PIEs, the `output_dir` of the `snos` ExEx. The proofs are stored next to them.
+    #[clap(env = "MADARA_PROVER_PIE_DIR", long, value_name = "PATH")]
+    pub prover_pie_dir: Option<PathBuf>,
+
+    /// Interval at which the proving jobs are polled, and new PIEs looked for.
+    #[clap(env = "MADARA_PROVER_POLL_INTERVAL", long, default_value = "30s", value_parser = parse_duration)]
+    pub prover_poll_interval: Duration,
+
+    /// Maximum number of blocks being proven at the same time.
+    #[clap(env = "MADARA_PROVER_MAX_CONCURRENT_JOBS", long, default_value_t = 4, value_name = "JOBS")]
+    pub prover_max_concurrent_jobs: usize,
+
+    /// Number of submissions of a block before giving up on it.
+    #[clap(env = "MADARA_PROVER_MAX_ATTEMPTS", long, default_value_t = 3)]
+    pub prover_max_attempts: u32,
+
+    /// First block to prove, when no block was submitted yet.
+    #[clap(env = "MADARA_PROVER_FIRST_BLOCK", long, default_value_t = 0, value_name = "BLOCK")]
+    pub prover_first_block: u64,
+}
+
+impl ProverParams {
+    /// `None` when the blocks are not proven by this node.
+    pub fn client_config(&self) -> Option<ProverClientConfig> {
+        Some(ProverClientConfig {
+            kind: self.prover?.into(),
+            url: self.prover_url.clone()?,
+            api_key: self.prover_api_key.clone(),
+            proof_url: self.prover_proof_url.clone(),
+        })
+    }
+
+    pub fn proving_config(&self) -> Option<ProvingConfig> {
+        Some(ProvingConfig {
+            pie_dir: self.prover_pie_dir.clone()?,
+            poll_interval: self.prover_poll_interval,
+            max_concurrent_jobs: self.prover_max_concurrent_jobs,
+            max_attempts: self.prover_max_attempts,
+            first_block: self.prover_first_block,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProverType {
+    /// The SHARP gateway of StarkWare.
+    Sharp,
+    /// The Atlantic proving service of Herodotus.
+    Atlantic,
+}
+
+impl From<ProverType> for ProverKind {
+    fn from(prover: ProverType) -> Self {
+        match prover {
+            ProverType::Sharp => ProverKind::Sharp,
+            ProverType::Atlantic => ProverKind::Atlantic,
+        }
+    }
+}
@@ -1,11 +1,16 @@
 use std::convert::Infallible;
 use std::net::{Ipv4Addr, SocketAddr};
 use std::num::NonZeroU32;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 use clap::ValueEnum;
 use ip_network::IpNetwork;
 use jsonrpsee::server::BatchRequestConfig;
+use mp_chain_config::{ChainConfig, ChainConfigIssue, RpcVersion, SUPPORTED_RPC_VERSIONS};
+use mp_rpc::block_cache::DEFAULT_BLOCK_CACHE_SIZE;
+use mp_utils::parsers::parse_duration;
 
 /// Available RPC methods.
 #[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
@@ -34,6 +39,10 @@ pub const RPC_DEFAULT_MAX_CONNECTIONS: u32 = 100;
 /// is allowed to keep in memory per connection.
 pub const RPC_DEFAULT_MESSAGE_CAPACITY_PER_CONN: u32 = 64;
 
+/// Upper bound of the size of one word of a state diff in a JSON response: a felt and its key.
+const STATE_DIFF_WORD_JSON_SIZE: u64 = 100;
+const MEGABYTE: u64 = 1024 * 1024;
+
 #[derive(Clone, Debug)]
 pub enum Cors {
     /// All hosts allowed.
@@ -157,6 +166,52 @@ pub struct RpcParams {
     /// Learn more about CORS and web security at <https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS>.
     #[arg(env = "MADARA_RPC_CORS", long, value_name = "ORIGINS")]
     pub rpc_cors: Option<Cors>,
+
+    /// The RPC spec version served on the paths without a version, such as `/`. The other versions stay available at
+    /// `/rpc/v{version}`, for example `/rpc/v0_7_1`.
+    ///
+    /// Pinning it keeps the behavior of the node unchanged for the clients not passing a version when upgrading to a
+    /// node supporting a newer version. Defaults to the latest supported version.
+    #[arg(env = "MADARA_RPC_DEFAULT_VERSION", long, value_name = "VERSION", value_parser = parse_rpc_version, default_value_t = RpcVersion::RPC_VERSION_LATEST)]
+    pub rpc_default_version: RpcVersion,
+
+    /// Path to a TOML file of API keys, grouped in tiers with their own rate limit and daily quota.
+    ///
+    /// Calls made with a key, passed in the `X-Api-Key` header or the `api_key` query parameter, use the limits of
+    /// its tier instead of `--rpc-rate-limit`, shared by all the connections using the key. Keys can also be added
+    /// with the `madara_setApiKey` admin method, until the node restarts: add them to this file to keep them.
+    #[arg(env = "MADARA_RPC_API_KEYS", long, value_name = "PATH")]
+    pub rpc_api_keys: Option<PathBuf>,
+
+    /// Reject the calls made without an API key of `--rpc-api-keys`.
+    #[arg(env = "MADARA_RPC_API_KEY_REQUIRED", long, requires = "rpc_api_keys")]
+    pub rpc_api_key_required: bool,
+
+    /// Reject the low priority RPC calls with a retryable error while the calls executing transactions wait longer
+    /// than this on average for a thread, such as `200ms`. This keeps block production and sync responsive under
+    /// heavy RPC traffic. Submitting transactions and the cheap methods used to follow the chain are always served.
+    #[arg(env = "MADARA_RPC_OVERLOAD_MAX_EXECUTION_WAIT", long, value_name = "DURATION", value_parser = parse_duration)]
+    pub rpc_overload_max_execution_wait: Option<Duration>,
+
+    /// Reject the low priority RPC calls with a retryable error while reading the latest block from the database
+    /// takes longer than this, such as `50ms`. See `--rpc-overload-max-execution-wait`.
+    #[arg(env = "MADARA_RPC_OVERLOAD_MAX_DB_LATENCY", long, value_name = "DURATION", value_parser = parse_duration)]
+    pub rpc_overload_max_db_latency: Option<Duration>,
+
+    /// Number of recent blocks kept in memory with their transactions and receipts, so that the calls following the
+    /// chain tip do not read them from the database every time. `0` disables the cache.
+    #[arg(env = "MADARA_RPC_BLOCK_CACHE_SIZE", long, value_name = "BLOCKS", default_value_t = DEFAULT_BLOCK_CACHE_SIZE)]
+    pub rpc_block_cache_size: usize,
+}
+
+/// Parses a supported RPC version, written as `0.7.1`, `0_7_1` or `v0_7_1`.
+fn parse_rpc_version(s: &str) -> Result<RpcVersion, String> {
+    let version: RpcVersion = s.trim_start_matches('v').replace('.', "_").parse().map_err(|err| format!("{err}"))?;
+    if !SUPPORTED_RPC_VERSIONS.contains(&version) {
+        let supported: Vec<_> = SUPPORTED_RPC_VERSIONS.iter().map(ToString::to_string).collect();
+        return Err(format!("Unsupported RPC version {version}, supported versions are: {}", supported.join(", ")));
+    }
+    Ok(version)
 }
 
 impl RpcParams {
@@ -186,6 +241,27 @@ impl RpcParams {
         SocketAddr::new(listen_addr.into(), self.rpc_port)
     }
 
+    /// Issues of the chain config with the limits of the RPC server: a full block must fit in a response.
+    pub fn chain_config_issues(&self, chain_config: &ChainConfig) -> Vec<ChainConfigIssue> {
+        if self.rpc_disabled {
+            return vec![];
+        }
+        let state_diff_size = chain_config.bouncer_config.block_max_capacity.state_diff_size as u64;
+        let response_size = state_diff_size.saturating_mul(STATE_DIFF_WORD_JSON_SIZE);
+        if response_size <= u64::from(self.rpc_max_response_size) * MEGABYTE {
+            return vec![];
+        }
+        vec![ChainConfigIssue::new(
+            "bouncer_config.block_max_capacity.state_diff_size",
+            format!(
+                "The state update of a full block can take up to {} MB, more than the {} MB RPC responses are \
+                 limited to. Lower the state diff size, or raise `--rpc-max-response-size`.",
+                response_size.div_ceil(MEGABYTE),
+                self.rpc_max_response_size
+            ),
+        )]
+    }
+
     pub fn batch_config(&self) -> BatchRequestConfig {
         if self.rpc_disable_batch_requests {
             BatchRequestConfig::Disabled
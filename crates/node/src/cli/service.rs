@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use mp_utils::parsers::parse_duration;
+use mp_utils::service::RestartPolicy;
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct ServiceParams {
+    /// Services restarted when one of their tasks fails, instead of shutting the node down. Can be `rpc`, `gateway`,
+    /// `prometheus`, `node_metrics`, `prover` or `reload`; the other services cannot be restarted. The health of the services
+    /// is reported by the `madara_serviceStatus` admin RPC method.
+    #[arg(env = "MADARA_RESTART_ON_FAILURE", long, value_name = "SERVICE", value_delimiter = ',')]
+    pub restart_on_failure: Vec<String>,
+
+    /// Number of restarts after which a failing service shuts the node down. By default, services are restarted
+    /// indefinitely.
+    #[arg(env = "MADARA_RESTART_MAX_RETRIES", long, value_name = "N")]
+    pub restart_max_retries: Option<u32>,
+
+    /// Delay before the first restart of a service, doubled on every following one.
+    #[arg(env = "MADARA_RESTART_BACKOFF", long, value_parser = parse_duration, default_value = "1s", value_name = "DURATION")]
+    pub restart_backoff: Duration,
+
+    /// Upper bound of the delay between two restarts of a service.
+    #[arg(env = "MADARA_RESTART_MAX_BACKOFF", long, value_parser = parse_duration, default_value = "1min", value_name = "DURATION")]
+    pub restart_max_backoff: Duration,
+
+    /// A service running for at least this long before failing is considered healthy, and its restart count is reset.
+    #[arg(env = "MADARA_RESTART_RESET_AFTER", long, value_parser = parse_duration, default_value = "5min", value_name = "DURATION")]
+    pub restart_reset_after: Duration,
+}
+
+impl ServiceParams {
+    /// Restart policies of the `--restart-on-failure` services, by service name.
+    pub fn restart_policies(&self) -> impl Iterator<Item = (&str, RestartPolicy)> {
+        let policy = RestartPolicy {
+            max_restarts: self.restart_max_retries,
+            initial_backoff: self.restart_backoff,
+            max_backoff: self.restart_max_backoff,
+            reset_after: self.restart_reset_after,
+        };
+        self.restart_on_failure.iter().map(move |name| (name.as_str(), policy))
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use mc_da::DaClientConfig;
+use mp_block::header::L1DataAvailabilityMode;
+use mp_keystore::{KeySource, Password, Secret};
+use mp_utils::parsers::{parse_duration, parse_url};
+use url::Url;
+
+/// Parameters used to settle the produced blocks on L1.
+#[derive(Clone, Debug, clap::Args)]
+pub struct SettlementParams {
+    /// Submit the state updates of the produced blocks to the core contract. This is only available for
+    /// sequencers settling on Ethereum, on devnets whose core contract uses a mock verifier: the state updates are
+    /// not proven.
+    #[clap(env = "MADARA_SETTLEMENT_ENABLE", long, alias = "settle")]
+    pub settlement_enable: bool,
+
+    /// Private key of the L1 account submitting the state updates. This account must be registered as an
+    /// operator of the core contract.
+    #[clap(
+        env = "MADARA_SETTLEMENT_PRIVATE_KEY",
+        long,
+        value_name = "PRIVATE KEY",
+        hide_env_values = true,
+        conflicts_with = "settlement_keystore"
+    )]
+    pub settlement_private_key: Option<String>,
+
+    /// Encrypted JSON keystore holding the private key of the L1 account submitting the state updates, as an
+    /// alternative to `--settlement-private-key`.
+    #[clap(env = "MADARA_SETTLEMENT_KEYSTORE", long, value_name = "PATH")]
+    pub settlement_keystore: Option<PathBuf>,
+
+    /// Password of the settlement keystore.
+    #[clap(
+        env = "MADARA_SETTLEMENT_KEYSTORE_PASSWORD",
+        long,
+        hide_env_values = true,
+        requires = "settlement_keystore",
+        conflicts_with = "settlement_keystore_password_file"
+    )]
+    pub settlement_keystore_password: Option<String>,
+
+    /// File holding the password of the settlement keystore.
+    #[clap(
+        env = "MADARA_SETTLEMENT_KEYSTORE_PASSWORD_FILE",
+        long,
+        value_name = "PATH",
+        requires = "settlement_keystore"
+    )]
+    pub settlement_keystore_password_file: Option<PathBuf>,
+
+    /// Interval at which the produced blocks are checked for settlement.
+    #[clap(env = "MADARA_SETTLEMENT_INTERVAL", long, default_value = "1min", value_parser = parse_duration)]
+    pub settlement_interval: Duration,
+
+    /// Maximum number of blocks aggregated in a single state update.
+    #[clap(env = "MADARA_SETTLEMENT_MAX_BLOCKS", long, default_value_t = 10, value_name = "BLOCKS")]
+    pub settlement_max_blocks: u64,
+
+    /// Number of L1 confirmations to wait for before considering a state update settled.
+    #[clap(env = "MADARA_SETTLEMENT_CONFIRMATIONS", long, default_value_t = 3)]
+    pub settlement_confirmations: u64,
+
+    /// Time after which an unconfirmed state update is submitted again.
+    #[clap(
+        env = "MADARA_SETTLEMENT_CONFIRMATION_TIMEOUT",
+        long,
+        default_value = "10min",
+        value_parser = parse_duration
+    )]
+    pub settlement_confirmation_timeout: Duration,
+
+    /// How the state diffs are published on L1.
+    #[clap(env = "MADARA_SETTLEMENT_DA_MODE", long, value_enum, default_value_t = SettlementDaMode::Calldata)]
+    pub settlement_da_mode: SettlementDaMode,
+
+    /// Blob state updates are delayed while the blob base fee is above this price.
+    #[clap(env = "MADARA_SETTLEMENT_MAX_BLOB_GAS_PRICE", long, value_name = "WEI")]
+    pub settlement_max_blob_gas_price: Option<u128>,
+
+    /// JSON-RPC endpoint of the celestia-node publishing the state diffs, when the chain config `da_layer` is
+    /// Celestia.
+    #[clap(env = "MADARA_DA_CELESTIA_URL", long, value_parser = parse_url, value_name = "URL")]
+    pub da_celestia_url: Option<Url>,
+
+    /// Auth token of the celestia-node, with write permissions.
+    #[clap(env = "MADARA_DA_CELESTIA_AUTH_TOKEN", long, value_name = "TOKEN", hide_env_values = true)]
+    pub da_celestia_auth_token: Option<String>,
+
+    /// HTTP API of the avail-light client publishing the state diffs, when the chain config `da_layer` is Avail.
+    /// The light client must run in app client mode, with the app id of the chain.
+    #[clap(env = "MADARA_DA_AVAIL_URL", long, value_parser = parse_url, value_name = "URL")]
+    pub da_avail_url: Option<Url>,
+
+    /// JSON-RPC endpoint of an Avail node, used to retrieve the inclusion proofs.
+    #[clap(env = "MADARA_DA_AVAIL_RPC_URL", long, value_parser = parse_url, value_name = "URL")]
+    pub da_avail_rpc_url: Option<Url>,
+}
+
+impl SettlementParams {
+    pub fn da_client_config(&self) -> DaClientConfig {
+        DaClientConfig {
+            celestia_url: self.da_celestia_url.clone(),
+            celestia_auth_token: self.da_celestia_auth_token.clone(),
+            avail_url: self.da_avail_url.clone(),
+            avail_rpc_url: self.da_avail_rpc_url.clone(),
+        }
+    }
+
+    /// Where the key of the L1 account submitting the state updates is loaded from.
+    pub fn key_source(&self) -> anyhow::Result<KeySource> {
+        if let Some(private_key) = &self.settlement_private_key {
+            return Ok(KeySource::PrivateKey(Secret::new(private_key.clone())));
+        }
+        let Some(path) = self.settlement_keystore.clone() else {
+            anyhow::bail!("Settling the produced blocks requires `--settlement-private-key` or `--settlement-keystore`")
+        };
+        let password = match (&self.settlement_keystore_password, &self.settlement_keystore_password_file) {
+            (Some(password), _) => Password::Value(Secret::new(password.clone())),
+            (None, Some(path)) => Password::File(path.clone()),
+            (None, None) => anyhow::bail!(
+                "Decrypting the settlement keystore requires either `--settlement-keystore-password` or \
+                 `--settlement-keystore-password-file`"
+            ),
+        };
+        Ok(KeySource::Keystore { path, password })
+    }
+}
+
+/// Where the state diffs are published on L1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SettlementDaMode {
+    /// In the calldata of the state update transaction.
+    Calldata,
+    /// In an EIP-4844 blob carried by the state update transaction.
+    Blob,
+}
+
+impl From<SettlementDaMode> for L1DataAvailabilityMode {
+    fn from(mode: SettlementDaMode) -> Self {
+        match mode {
+            SettlementDaMode::Calldata => L1DataAvailabilityMode::Calldata,
+            SettlementDaMode::Blob => L1DataAvailabilityMode::Blob,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::Duration;
 
+use anyhow::Context;
 use starknet_api::core::ChainId;
 
 use mc_sync::fetch::fetchers::FetchConfig;
@@ -24,6 +25,12 @@ pub struct SyncParams {
     #[clap(env = "MADARA_DISABLE_ROOT", long)]
     pub disable_root: bool,
 
+    /// Do not maintain the global tries at all, and trust the state roots of the synced blocks. This saves most of the
+    /// CPU and disk usage of the sync for nodes which only serve RPC reads. The database keeps this mode for good, and
+    /// the methods reading the global tries are not supported. Only available to full nodes.
+    #[clap(env = "MADARA_NO_GLOBAL_TRIES", long)]
+    pub no_global_tries: bool,
+
     /// Gateway api key to avoid rate limiting (optional).
     #[clap(env = "MADARA_GATEWAY_KEY", long, value_name = "API KEY")]
     pub gateway_key: Option<String>,
@@ -69,6 +76,10 @@ pub struct SyncParams {
 }
 
 impl SyncParams {
+    fn verify_state_root(&self) -> bool {
+        !self.disable_root && !self.no_global_tries
+    }
+
     pub fn block_fetch_config(&self, chain_id: ChainId, network: NetworkType) -> FetchConfig {
         let (gateway, feeder_gateway) = match &self.gateway_url {
             Some(url) => (
@@ -84,10 +95,23 @@ impl SyncParams {
             gateway,
             feeder_gateway,
             chain_id,
-            verify: !self.disable_root,
+            verify: self.verify_state_root(),
             api_key: self.gateway_key.clone(),
             sync_polling_interval: polling,
             n_blocks_to_sync: self.n_blocks_to_sync,
         }
     }
+
+    /// Syncs from the gateway advertised by the leader of a highly available sequencer.
+    pub fn leader_fetch_config(&self, chain_id: ChainId, leader: &Url) -> anyhow::Result<FetchConfig> {
+        Ok(FetchConfig {
+            gateway: leader.join("gateway/").context("Invalid leader gateway URL")?,
+            feeder_gateway: leader.join("feeder_gateway/").context("Invalid leader gateway URL")?,
+            chain_id,
+            verify: self.verify_state_root(),
+            api_key: None,
+            sync_polling_interval: Some(self.sync_polling_interval),
+            n_blocks_to_sync: None,
+        })
+    }
 }
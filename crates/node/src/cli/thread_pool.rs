@@ -0,0 +1,14 @@
+use std::num::NonZeroUsize;
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct ThreadPoolParams {
+    /// Number of threads executing transactions for the RPC calls (`starknet_call`, `starknet_estimateFee`,
+    /// simulations and traces). Defaults to the number of cores.
+    #[arg(env = "MADARA_EXECUTION_THREADS", long, value_name = "N")]
+    pub execution_threads: Option<NonZeroUsize>,
+
+    /// Number of threads verifying the imported blocks and computing the trie commitments. Defaults to the number of
+    /// cores.
+    #[arg(env = "MADARA_COMMITMENT_THREADS", long, value_name = "N")]
+    pub commitment_threads: Option<NonZeroUsize>,
+}
@@ -0,0 +1,256 @@
+//! Decoding of the events emitted by Sierra contracts, with the ABI of their class.
+//!
+//! An event is matched by its selector, the first key, against the struct events of the ABI. It is decoded when all
+//! its members are single felts or `u256`s, and when the keys and data hold exactly these members. Other events, and
+//! the events of legacy classes, are left undecoded.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mp_class::ClassInfo;
+use mp_receipt::Event;
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Felt;
+use starknet_core::utils::starknet_keccak;
+
+/// Member types serialized as a single felt.
+const FELT_TYPES: [&str; 15] = [
+    "core::felt252",
+    "core::bool",
+    "core::integer::u8",
+    "core::integer::u16",
+    "core::integer::u32",
+    "core::integer::u64",
+    "core::integer::u128",
+    "core::integer::i8",
+    "core::integer::i16",
+    "core::integer::i32",
+    "core::integer::i64",
+    "core::integer::i128",
+    "core::bytes_31::bytes31",
+    "core::starknet::contract_address::ContractAddress",
+    "core::starknet::class_hash::ClassHash",
+];
+const U256_TYPE: &str = "core::integer::u256";
+
+/// A decoded event, named after its ABI definition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct DecodedEvent {
+    pub name: String,
+    pub fields: Vec<DecodedField>,
+}
+
+/// A member of a decoded event, with its value as a hex string.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct DecodedField {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AbiEntry {
+    Event(AbiEventEntry),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct AbiEventEntry {
+    name: String,
+    kind: String,
+    #[serde(default)]
+    members: Vec<AbiEventMember>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct AbiEventMember {
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+    /// `key` or `data`.
+    kind: String,
+}
+
+/// A struct event of an ABI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AbiEvent {
+    name: String,
+    members: Vec<AbiEventMember>,
+}
+
+/// Struct events of a Sierra class ABI, by selector. The selector of an event is the keccak of its name, without the
+/// module path.
+fn parse_abi_events(abi: &str) -> serde_json::Result<HashMap<Felt, AbiEvent>> {
+    let entries: Vec<AbiEntry> = serde_json::from_str(abi)?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            AbiEntry::Event(AbiEventEntry { name, kind, members }) if kind == "struct" => {
+                let short_name = name.rsplit("::").next().unwrap_or(&name);
+                Some((starknet_keccak(short_name.as_bytes()), AbiEvent { name, members }))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// Takes the next `n` felts.
+fn take<'a>(felts: &mut &'a [Felt], n: usize) -> Option<&'a [Felt]> {
+    if felts.len() < n {
+        return None;
+    }
+    let (taken, rest) = felts.split_at(n);
+    *felts = rest;
+    Some(taken)
+}
+
+impl AbiEvent {
+    fn decode(&self, keys: &[Felt], data: &[Felt]) -> Option<DecodedEvent> {
+        // The first key is the selector.
+        let (mut keys, mut data) = (keys.get(1..)?, data);
+        let mut fields = Vec::with_capacity(self.members.len());
+        for member in &self.members {
+            let felts = if member.kind == "key" { &mut keys } else { &mut data };
+            let value = if FELT_TYPES.contains(&member.ty.as_str()) {
+                format!("{:#x}", take(felts, 1)?[0])
+            } else if member.ty == U256_TYPE {
+                let (low, high) = match take(felts, 2)? {
+                    [low, high] => (*low, *high),
+                    _ => return None,
+                };
+                if high == Felt::ZERO {
+                    format!("{low:#x}")
+                } else {
+                    format!("{high:#x}{:032x}", u128::try_from(low).ok()?)
+                }
+            } else {
+                return None;
+            };
+            fields.push(DecodedField { name: member.name.clone(), value });
+        }
+        (keys.is_empty() && data.is_empty()).then(|| DecodedEvent { name: self.name.clone(), fields })
+    }
+}
+
+/// Decodes events with the ABI of the class of their emitter, caching the parsed ABIs by class hash.
+#[derive(Default)]
+pub(super) struct EventDecoder {
+    abis: HashMap<Felt, Arc<HashMap<Felt, AbiEvent>>>,
+}
+
+impl EventDecoder {
+    /// Decodes an event emitted in block `block_number`, `None` if it cannot be decoded.
+    pub fn decode(&mut self, backend: &MadaraBackend, block_number: u64, event: &Event) -> Option<DecodedEvent> {
+        let selector = event.keys.first()?;
+        let block_id = DbBlockId::Number(block_number);
+        let class_hash = match backend.get_contract_class_hash_at(&block_id, &event.from_address) {
+            Ok(class_hash) => class_hash?,
+            Err(err) => {
+                log::warn!("Getting the class of contract {:#x}: {err:#}", event.from_address);
+                return None;
+            }
+        };
+        let events = match self.abis.get(&class_hash) {
+            Some(events) => Arc::clone(events),
+            None => {
+                let events = Arc::new(load_abi_events(backend, &block_id, &class_hash));
+                self.abis.insert(class_hash, Arc::clone(&events));
+                events
+            }
+        };
+        events.get(selector)?.decode(&event.keys, &event.data)
+    }
+}
+
+/// Struct events of the ABI of a class, empty for legacy classes or when the ABI cannot be parsed.
+fn load_abi_events(backend: &MadaraBackend, block_id: &DbBlockId, class_hash: &Felt) -> HashMap<Felt, AbiEvent> {
+    match backend.get_class_info(block_id, class_hash) {
+        Ok(Some(ClassInfo::Sierra(info))) => parse_abi_events(&info.contract_class.abi).unwrap_or_else(|err| {
+            log::debug!("Parsing the ABI of class {class_hash:#x}: {err}");
+            HashMap::new()
+        }),
+        Ok(_) => HashMap::new(),
+        Err(err) => {
+            log::warn!("Getting class {class_hash:#x}: {err:#}");
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABI: &str = r#"[
+        {"type": "function", "name": "transfer", "inputs": [], "outputs": [], "state_mutability": "external"},
+        {
+            "type": "event",
+            "name": "openzeppelin::token::erc20::ERC20Component::Transfer",
+            "kind": "struct",
+            "members": [
+                {"name": "from", "type": "core::starknet::contract_address::ContractAddress", "kind": "key"},
+                {"name": "to", "type": "core::starknet::contract_address::ContractAddress", "kind": "key"},
+                {"name": "value", "type": "core::integer::u256", "kind": "data"}
+            ]
+        },
+        {
+            "type": "event",
+            "name": "openzeppelin::token::erc20::ERC20Component::Event",
+            "kind": "enum",
+            "variants": [
+                {"name": "Transfer", "type": "openzeppelin::token::erc20::ERC20Component::Transfer", "kind": "nested"}
+            ]
+        },
+        {
+            "type": "event",
+            "name": "my_contract::Named",
+            "kind": "struct",
+            "members": [{"name": "name", "type": "core::byte_array::ByteArray", "kind": "data"}]
+        }
+    ]"#;
+
+    fn transfer_selector() -> Felt {
+        starknet_keccak(b"Transfer")
+    }
+
+    #[test]
+    fn test_decode_event() {
+        let events = parse_abi_events(ABI).unwrap();
+        assert_eq!(events.len(), 2);
+        let transfer = &events[&transfer_selector()];
+
+        let keys = [transfer_selector(), Felt::from(1), Felt::from(2)];
+        assert_eq!(
+            transfer.decode(&keys, &[Felt::from(0x10), Felt::ZERO]),
+            Some(DecodedEvent {
+                name: "openzeppelin::token::erc20::ERC20Component::Transfer".into(),
+                fields: vec![
+                    DecodedField { name: "from".into(), value: "0x1".into() },
+                    DecodedField { name: "to".into(), value: "0x2".into() },
+                    DecodedField { name: "value".into(), value: "0x10".into() },
+                ]
+            })
+        );
+        let decoded = transfer.decode(&keys, &[Felt::from(0x10), Felt::ONE]).unwrap();
+        assert_eq!(decoded.fields[2].value, "0x100000000000000000000000000000010");
+    }
+
+    #[test]
+    fn test_decode_event_mismatch() {
+        let events = parse_abi_events(ABI).unwrap();
+        let transfer = &events[&transfer_selector()];
+
+        // Missing and extra felts.
+        assert_eq!(transfer.decode(&[transfer_selector(), Felt::ONE], &[Felt::ONE, Felt::ZERO]), None);
+        assert_eq!(
+            transfer.decode(&[transfer_selector(), Felt::ONE, Felt::TWO], &[Felt::ONE, Felt::ZERO, Felt::ONE]),
+            None
+        );
+        // Types that do not fit in a felt are not supported.
+        let named = &events[&starknet_keccak(b"Named")];
+        assert_eq!(named.decode(&[starknet_keccak(b"Named")], &[Felt::ZERO, Felt::ZERO, Felt::ZERO]), None);
+    }
+}
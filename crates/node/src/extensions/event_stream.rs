@@ -0,0 +1,312 @@
+//! ExEx publishing the imported blocks, receipts and events to Kafka or NATS, giving indexers a push-based feed.
+//!
+//! Each block is published to `{topic_prefix}.blocks`, its receipts to `{topic_prefix}.receipts` and its events,
+//! flattened with the transaction that emitted them, to `{topic_prefix}.events`. Reverts are published to
+//! `{topic_prefix}.reverts`. Messages are keyed by block number, and Kafka messages all go to partition 0 so that
+//! consumers see them in order.
+//!
+//! Events are published along with their decoding, when the ABI of the emitting contract describes them (see
+//! [`super::event_abi`]).
+//!
+//! The Kafka and NATS clients are built with the `exex-kafka` and `exex-nats` features.
+
+#[cfg(feature = "exex-kafka")]
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use futures::StreamExt;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mp_block::{MadaraBlock, MadaraBlockInfo};
+use mp_exex::{ExExContext, ExExEvent, ExExNotification};
+use mp_receipt::TransactionReceipt;
+#[cfg(feature = "exex-kafka")]
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+#[cfg(feature = "exex-kafka")]
+use rskafka::client::ClientBuilder;
+#[cfg(feature = "exex-kafka")]
+use rskafka::record::Record;
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Felt;
+
+use super::event_abi::{DecodedEvent, EventDecoder};
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Broker {
+    #[default]
+    Nats,
+    Kafka,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Format {
+    #[default]
+    Json,
+    Bincode,
+}
+
+impl Format {
+    pub(super) fn encode(self, value: &impl Serialize) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(value)?),
+            Self::Bincode => Ok(bincode::serialize(value)?),
+        }
+    }
+}
+
+fn default_topic_prefix() -> String {
+    "madara".into()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EventStreamConfig {
+    #[serde(default)]
+    broker: Broker,
+    /// Addresses of the Kafka brokers or NATS servers.
+    servers: Vec<String>,
+    #[serde(default = "default_topic_prefix")]
+    topic_prefix: String,
+    #[serde(default)]
+    format: Format,
+}
+
+#[derive(Serialize)]
+struct ReceiptMessage<'a> {
+    block_number: u64,
+    block_hash: Felt,
+    transaction_index: usize,
+    receipt: &'a TransactionReceipt,
+}
+
+#[derive(Serialize)]
+struct EventMessage<'a> {
+    block_number: u64,
+    block_hash: Felt,
+    transaction_hash: Felt,
+    transaction_index: usize,
+    event_index: usize,
+    from_address: Felt,
+    keys: &'a [Felt],
+    data: &'a [Felt],
+    /// `None` when the event is not described by the ABI of the contract.
+    decoded: Option<DecodedEvent>,
+}
+
+#[derive(Serialize)]
+struct RevertMessage {
+    from: u64,
+    to: u64,
+}
+
+#[cfg(feature = "exex-kafka")]
+const TOPICS: [&str; 4] = ["blocks", "receipts", "events", "reverts"];
+
+enum Publisher {
+    #[cfg(feature = "exex-nats")]
+    Nats(async_nats::Client),
+    /// One client per topic.
+    #[cfg(feature = "exex-kafka")]
+    Kafka(BTreeMap<String, PartitionClient>),
+}
+
+impl Publisher {
+    async fn connect(config: &EventStreamConfig) -> anyhow::Result<Self> {
+        match config.broker {
+            #[cfg(feature = "exex-nats")]
+            Broker::Nats => {
+                let client = async_nats::connect(config.servers.join(",")).await.context("Connecting to NATS")?;
+                Ok(Self::Nats(client))
+            }
+            #[cfg(not(feature = "exex-nats"))]
+            Broker::Nats => anyhow::bail!("The node was built without NATS support, enable the `exex-nats` feature"),
+            #[cfg(not(feature = "exex-kafka"))]
+            Broker::Kafka => anyhow::bail!("The node was built without Kafka support, enable the `exex-kafka` feature"),
+            #[cfg(feature = "exex-kafka")]
+            Broker::Kafka => {
+                let client = ClientBuilder::new(config.servers.clone()).build().await.context("Connecting to Kafka")?;
+                let mut partitions = BTreeMap::new();
+                for topic in TOPICS {
+                    let topic = format!("{}.{topic}", config.topic_prefix);
+                    let partition = client
+                        .partition_client(topic.clone(), 0, UnknownTopicHandling::Retry)
+                        .await
+                        .with_context(|| format!("Getting the partition of Kafka topic {topic}"))?;
+                    partitions.insert(topic, partition);
+                }
+                Ok(Self::Kafka(partitions))
+            }
+        }
+    }
+
+    /// Publishes the messages, and waits for the broker to acknowledge them.
+    async fn publish(&self, topic: &str, key: u64, messages: Vec<Vec<u8>>) -> anyhow::Result<()> {
+        if messages.is_empty() {
+            return Ok(());
+        }
+        match *self {
+            #[cfg(feature = "exex-nats")]
+            Self::Nats(ref client) => {
+                for message in messages {
+                    client.publish(topic.to_owned(), message.into()).await?;
+                }
+                client.flush().await?;
+            }
+            #[cfg(feature = "exex-kafka")]
+            Self::Kafka(ref partitions) => {
+                let partition = partitions.get(topic).context("Unknown topic")?;
+                let timestamp = chrono::Utc::now();
+                let records = messages
+                    .into_iter()
+                    .map(|message| Record {
+                        key: Some(key.to_be_bytes().to_vec()),
+                        value: Some(message),
+                        headers: Default::default(),
+                        timestamp,
+                    })
+                    .collect();
+                partition.produce(records, Compression::NoCompression).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct EventStream {
+    config: EventStreamConfig,
+    publisher: Publisher,
+    decoder: EventDecoder,
+}
+
+impl EventStream {
+    fn topic(&self, name: &str) -> String {
+        format!("{}.{name}", self.config.topic_prefix)
+    }
+
+    async fn publish_block(&mut self, backend: &MadaraBackend, block: &MadaraBlock) -> anyhow::Result<()> {
+        let MadaraBlockInfo { header, block_hash, .. } = &block.info;
+        let block_number = header.block_number;
+        let format = self.config.format;
+
+        self.publisher.publish(&self.topic("blocks"), block_number, vec![format.encode(&block.info)?]).await?;
+
+        let receipts = block
+            .inner
+            .receipts
+            .iter()
+            .enumerate()
+            .map(|(transaction_index, receipt)| {
+                format.encode(&ReceiptMessage { block_number, block_hash: *block_hash, transaction_index, receipt })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        self.publisher.publish(&self.topic("receipts"), block_number, receipts).await?;
+
+        let mut events = Vec::new();
+        for (transaction_index, receipt) in block.inner.receipts.iter().enumerate() {
+            for (event_index, event) in receipt.events().iter().enumerate() {
+                events.push(format.encode(&EventMessage {
+                    block_number,
+                    block_hash: *block_hash,
+                    transaction_hash: receipt.transaction_hash(),
+                    transaction_index,
+                    event_index,
+                    from_address: event.from_address,
+                    keys: &event.keys,
+                    data: &event.data,
+                    decoded: self.decoder.decode(backend, block_number, event),
+                })?);
+            }
+        }
+        self.publisher.publish(&self.topic("events"), block_number, events).await
+    }
+}
+
+/// Closed block of a notification. Produced blocks are loaded back from the backend, as the notification only
+/// carries the pending block, without its hash.
+pub(super) fn closed_block(backend: &MadaraBackend, notification: ExExNotification) -> anyhow::Result<MadaraBlock> {
+    if let ExExNotification::BlockSynced { block: Some(block), .. } = notification {
+        return Ok(*block);
+    }
+    let block_n = notification.block_number().0;
+    let block =
+        backend.get_block(&DbBlockId::Number(block_n))?.with_context(|| format!("Block #{block_n} not found"))?;
+    MadaraBlock::try_from(block).ok().with_context(|| format!("Block #{block_n} is pending"))
+}
+
+/// 🧩 Event stream ExEx, publishing the imported blocks to Kafka or NATS.
+pub async fn exex_event_stream(mut ctx: ExExContext) -> anyhow::Result<()> {
+    let config: EventStreamConfig = ctx.config()?;
+    let publisher = Publisher::connect(&config).await?;
+    let mut stream = EventStream { config, publisher, decoder: EventDecoder::default() };
+    log::info!("🧩 Event stream ExEx publishing to topics {}.*", stream.config.topic_prefix);
+
+    while let Some(notification) = ctx.notifications.next().await {
+        let block_number = notification.block_number();
+        if let ExExNotification::Reverted { from, to } = notification {
+            let message = stream.config.format.encode(&RevertMessage { from: from.0, to: to.0 })?;
+            stream.publisher.publish(&stream.topic("reverts"), to.0, vec![message]).await?;
+        } else {
+            let block = closed_block(&ctx.backend, notification)?;
+            stream
+                .publish_block(&ctx.backend, &block)
+                .await
+                .with_context(|| format!("Publishing block #{block_number}"))?;
+        }
+        ctx.events.send(ExExEvent::FinishedHeight(block_number))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extensions::event_abi::DecodedField;
+
+    #[test]
+    fn test_config_defaults() {
+        let config: EventStreamConfig = toml::from_str(r#"servers = ["localhost:4222"]"#).unwrap();
+        assert!(matches!(config.broker, Broker::Nats));
+        assert!(matches!(config.format, Format::Json));
+        assert_eq!(config.topic_prefix, "madara");
+
+        // Unknown keys are rejected.
+        let res = toml::from_str::<EventStreamConfig>("servers = []\ntopic = \"madara\"");
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_event_message() {
+        let decoded = DecodedEvent {
+            name: "my_contract::Transfer".into(),
+            fields: vec![DecodedField { name: "value".into(), value: "0x10".into() }],
+        };
+        let message = EventMessage {
+            block_number: 1,
+            block_hash: Felt::TWO,
+            transaction_hash: Felt::from(3),
+            transaction_index: 0,
+            event_index: 4,
+            from_address: Felt::ONE,
+            keys: &[Felt::ZERO],
+            data: &[Felt::from(0x10)],
+            decoded: Some(decoded.clone()),
+        };
+
+        let json: serde_json::Value = serde_json::from_slice(&Format::Json.encode(&message).unwrap()).unwrap();
+        assert_eq!(json["block_number"], 1);
+        assert_eq!(json["event_index"], 4);
+        assert_eq!(json["from_address"], "0x1");
+        assert_eq!(json["decoded"]["name"], "my_contract::Transfer");
+        assert_eq!(json["decoded"]["fields"][0]["value"], "0x10");
+
+        // Bincode messages are decoded in the order of the fields.
+        let bytes = Format::Bincode.encode(&message).unwrap();
+        type Decoded = (u64, Felt, Felt, usize, usize, Felt, Vec<Felt>, Vec<Felt>, Option<DecodedEvent>);
+        let (block_number, .., data, decoded_event): Decoded = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(block_number, 1);
+        assert_eq!(data, [Felt::from(0x10)]);
+        assert_eq!(decoded_event, Some(decoded));
+    }
+}
@@ -0,0 +1,225 @@
+//! ExEx writing the imported blocks as a stream of records to stdout or to a file, for the streaming indexer stacks
+//! reading the output of an instrumented node (the firehose pattern) rather than polling the RPC.
+//!
+//! Each record is the big-endian `u32` length of its payload, followed by the payload: the block with its
+//! transactions and receipts, and its state diff, or the range of reverted blocks. Payloads are encoded with bincode
+//! or JSON, and the state diffs are sorted, so that the same chain is always written as the same bytes.
+//!
+//! The node acknowledges a block once its record is flushed: after a restart, the stream resumes right after the last
+//! written block. A block whose record was written but not acknowledged before a crash is written again, consumers
+//! recognize it by its block number.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use futures::StreamExt;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mp_block::MadaraBlock;
+use mp_exex::{ExExContext, ExExEvent, ExExNotification, ExExNotifications};
+use mp_state_update::StateDiff;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::event_stream::{closed_block, Format};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FirehoseConfig {
+    /// File the records are appended to, or `-` for stdout.
+    output: PathBuf,
+    #[serde(default)]
+    format: Format,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FirehoseRecord<'a> {
+    Block {
+        block: &'a MadaraBlock,
+        state_diff: &'a StateDiff,
+    },
+    /// Blocks `to + 1..=from` are reverted, and will be followed by the blocks replacing them.
+    Revert {
+        from: u64,
+        to: u64,
+    },
+}
+
+struct Firehose {
+    format: Format,
+    output: Box<dyn AsyncWrite + Send + Unpin>,
+}
+
+impl Firehose {
+    async fn open(config: &FirehoseConfig) -> anyhow::Result<Self> {
+        let output: Box<dyn AsyncWrite + Send + Unpin> = if config.output.as_os_str() == "-" {
+            Box::new(tokio::io::stdout())
+        } else {
+            let file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&config.output)
+                .await
+                .with_context(|| format!("Opening the firehose output {}", config.output.display()))?;
+            Box::new(file)
+        };
+        Ok(Self { format: config.format, output })
+    }
+
+    /// Writes the record, flushed so that the consumers see it right away.
+    async fn write(&mut self, record: &FirehoseRecord<'_>) -> anyhow::Result<()> {
+        let payload = self.format.encode(record)?;
+        let len = u32::try_from(payload.len()).context("Firehose record too large")?;
+        let mut bytes = Vec::with_capacity(4 + payload.len());
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes.extend_from_slice(&payload);
+        self.output.write_all(&bytes).await?;
+        self.output.flush().await?;
+        Ok(())
+    }
+}
+
+/// 🧩 Firehose ExEx, writing the imported blocks as length-prefixed records.
+pub async fn exex_firehose(mut ctx: ExExContext) -> anyhow::Result<()> {
+    let config: FirehoseConfig = ctx.config()?;
+    let mut firehose = Firehose::open(&config).await?;
+    log::info!("🧩 Firehose ExEx writing the blocks to {}", config.output.display());
+    stream(&mut firehose, &ctx.backend, &mut ctx.notifications, &ctx.events).await
+}
+
+/// Writes the record of every notification, and acknowledges it once written.
+async fn stream(
+    firehose: &mut Firehose,
+    backend: &Arc<MadaraBackend>,
+    notifications: &mut ExExNotifications,
+    events: &UnboundedSender<ExExEvent>,
+) -> anyhow::Result<()> {
+    while let Some(notification) = notifications.next().await {
+        let block_number = notification.block_number();
+        if let ExExNotification::Reverted { from, to } = notification {
+            firehose.write(&FirehoseRecord::Revert { from: from.0, to: to.0 }).await?;
+        } else {
+            let mut state_diff = match notification.state_diff() {
+                Some(state_diff) => state_diff.clone(),
+                None => backend
+                    .get_block_state_diff(&DbBlockId::Number(block_number.0))?
+                    .with_context(|| format!("State diff of block #{block_number} not found"))?,
+            };
+            state_diff.sort();
+            let block = closed_block(backend, notification)?;
+            firehose
+                .write(&FirehoseRecord::Block { block: &block, state_diff: &state_diff })
+                .await
+                .with_context(|| format!("Writing block #{block_number}"))?;
+        }
+        events.send(ExExEvent::FinishedHeight(block_number))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_block::{Header, MadaraBlockInfo, MadaraBlockInner};
+    use mp_chain_config::ChainConfig;
+    use mp_state_update::NonceUpdate;
+    use starknet_api::block::BlockNumber;
+    use starknet_core::types::Felt;
+    use tokio::sync::mpsc;
+
+    fn synced(block_number: u64) -> ExExNotification {
+        // Unsorted, as the state diffs of the gateway.
+        synced_with_nonces(block_number, [2, 1])
+    }
+
+    fn synced_with_nonces(block_number: u64, contract_addresses: [u64; 2]) -> ExExNotification {
+        let header = Header { block_number, ..Default::default() };
+        let block = MadaraBlock::new(
+            MadaraBlockInfo::new(header, vec![], Felt::from(block_number + 100)),
+            MadaraBlockInner::default(),
+        );
+        let state_diff = StateDiff {
+            nonces: contract_addresses
+                .map(|address| NonceUpdate { contract_address: Felt::from(address), nonce: Felt::ONE })
+                .into(),
+            ..Default::default()
+        };
+        ExExNotification::BlockSynced {
+            block_number: BlockNumber(block_number),
+            block: Some(Box::new(block)),
+            state_diff: Some(Box::new(state_diff)),
+        }
+    }
+
+    /// Streams the notifications to `output`, and returns the acknowledged block numbers.
+    async fn run(output: &std::path::Path, format: Format, notifications: Vec<ExExNotification>) -> Vec<u64> {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let (notifications_tx, notifications_rx) = mpsc::channel(16);
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        for notification in notifications {
+            notifications_tx.send(notification).await.unwrap();
+        }
+        drop(notifications_tx);
+
+        let mut firehose = Firehose::open(&FirehoseConfig { output: output.into(), format }).await.unwrap();
+        stream(&mut firehose, &backend, &mut ExExNotifications::new(notifications_rx), &events_tx).await.unwrap();
+
+        std::iter::from_fn(|| events_rx.try_recv().ok()).map(|ExExEvent::FinishedHeight(n)| n.0).collect()
+    }
+
+    /// Splits the output into the payloads of its records.
+    fn split_records(bytes: &[u8]) -> Vec<&[u8]> {
+        let mut records = vec![];
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let (len, tail) = rest.split_at(4);
+            let (payload, tail) = tail.split_at(u32::from_be_bytes(len.try_into().unwrap()) as usize);
+            records.push(payload);
+            rest = tail;
+        }
+        records
+    }
+
+    #[tokio::test]
+    async fn test_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("firehose");
+        let reverted = ExExNotification::Reverted { from: BlockNumber(1), to: BlockNumber(0) };
+        let acked = run(&output, Format::Json, vec![synced(0), synced(1), reverted, synced(1)]).await;
+        assert_eq!(acked, [0, 1, 0, 1]);
+
+        let bytes = std::fs::read(&output).unwrap();
+        let records: Vec<serde_json::Value> =
+            split_records(&bytes).into_iter().map(|payload| serde_json::from_slice(payload).unwrap()).collect();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0]["block"]["block"]["info"]["header"]["block_number"], 0);
+        assert_eq!(records[1]["block"]["block"]["info"]["block_hash"], "0x65");
+        assert_eq!(records[1]["block"]["state_diff"]["nonces"][0]["contract_address"], "0x1");
+        assert_eq!(records[1]["block"]["state_diff"]["nonces"][1]["contract_address"], "0x2");
+        assert_eq!(records[2], serde_json::json!({ "revert": { "from": 1, "to": 0 } }));
+        assert_eq!(records[3], records[1]);
+
+        // A restarted stream resumes after the last acknowledged block, appending to the output.
+        assert_eq!(run(&output, Format::Json, vec![synced(2)]).await, [2]);
+        let bytes = std::fs::read(&output).unwrap();
+        let records = split_records(&bytes);
+        assert_eq!(records.len(), 5);
+        let record: serde_json::Value = serde_json::from_slice(records[4]).unwrap();
+        assert_eq!(record["block"]["block"]["info"]["header"]["block_number"], 2);
+    }
+
+    #[tokio::test]
+    async fn bincode_records_are_deterministic() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (dir.path().join("first"), dir.path().join("second"));
+        run(&first, Format::Bincode, vec![synced(0), synced_with_nonces(1, [2, 1])]).await;
+        run(&second, Format::Bincode, vec![synced(0), synced_with_nonces(1, [1, 2])]).await;
+
+        let bytes = std::fs::read(&first).unwrap();
+        assert_eq!(split_records(&bytes).len(), 2);
+        assert_eq!(bytes, std::fs::read(&second).unwrap());
+    }
+}
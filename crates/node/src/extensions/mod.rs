@@ -1,13 +1,32 @@
+mod event_abi;
+mod event_stream;
+mod firehose;
+mod postgres_indexer;
 mod pragma_dispatch;
+mod remote;
+mod snos;
+mod wasm;
 
+use std::collections::BTreeMap;
+
+use crate::cli::ExExParams;
+use anyhow::Context;
+use event_stream::exex_event_stream;
+use firehose::exex_firehose;
 use futures::future::BoxFuture;
-use mp_exex::{BoxExEx, BoxedLaunchExEx, ExExContext};
+use mp_exex::{BoxExEx, BoxedLaunchExEx, ExExContext, InstalledExEx};
+use mp_utils::service::RestartPolicy;
+use postgres_indexer::exex_postgres_indexer;
 use pragma_dispatch::exex_pragma_dispatch;
+use remote::exex_remote;
+use serde::Deserialize;
+use snos::exex_snos;
+use wasm::{exex_wasm, load_wasm_modules, wasm_engine};
 
 // Helper function to create a boxed ExEx
 fn box_exex<F, Fut>(f: F) -> Box<dyn BoxedLaunchExEx>
 where
-    F: FnOnce(ExExContext) -> Fut + Send + Sync + 'static,
+    F: FnOnce(ExExContext) -> Fut + Clone + Send + Sync + 'static,
     Fut: futures::Future<Output = anyhow::Result<()>> + Send + 'static,
 {
     Box::new(move |ctx| {
@@ -15,7 +34,100 @@ where
     })
 }
 
+/// Execution extensions built into the node, by id. They are disabled unless enabled with `--exex` or in the
+/// config file.
+fn builtin_exexs() -> Vec<(&'static str, Box<dyn BoxedLaunchExEx>)> {
+    vec![
+        ("event_stream", box_exex(exex_event_stream)),
+        ("firehose", box_exex(exex_firehose)),
+        ("postgres_indexer", box_exex(exex_postgres_indexer)),
+        ("pragma_dispatch", box_exex(exex_pragma_dispatch)),
+        ("remote", box_exex(exex_remote)),
+        ("snos", box_exex(exex_snos)),
+    ]
+}
+
+/// A table of the ExEx config file.
+#[derive(Debug, Default, Deserialize)]
+struct ExExSection {
+    enabled: Option<bool>,
+    /// Backfills the ExEx with the blocks from this one when it is first launched.
+    start_block: Option<u64>,
+    /// How the ExEx is restarted when it crashes.
+    #[serde(default)]
+    restart: RestartPolicy,
+    #[serde(flatten)]
+    config: toml::Table,
+}
+
+fn read_exex_config(params: &ExExParams) -> anyhow::Result<BTreeMap<String, ExExSection>> {
+    let Some(path) = &params.exex_config else {
+        return Ok(Default::default());
+    };
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Reading ExEx config file {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Parsing ExEx config file {}", path.display()))
+}
+
 /// List of all ExEx that will be ran along Madara.
-pub fn madara_exexs() -> Vec<(String, Box<dyn BoxedLaunchExEx>)> {
-    vec![("Pragma Dispatch ExEx".to_string(), box_exex(exex_pragma_dispatch))]
+pub fn madara_exexs(params: &ExExParams) -> anyhow::Result<Vec<InstalledExEx>> {
+    let mut sections = read_exex_config(params)?;
+    let mut exexs = Vec::new();
+
+    let builtins = builtin_exexs();
+    let builtin_ids: Vec<_> = builtins.iter().map(|(id, _)| *id).collect();
+    for id in &params.exexs {
+        if !builtin_ids.contains(&id.as_str()) {
+            anyhow::bail!("Unknown ExEx `{id}` in `--exex`");
+        }
+    }
+    for (id, exex) in builtins {
+        let section = sections.remove(id);
+        let enabled = params.exexs.iter().any(|enabled_id| enabled_id == id)
+            || section.as_ref().is_some_and(|section| section.enabled != Some(false));
+        if enabled {
+            let section = section.unwrap_or_default();
+            exexs.push(InstalledExEx {
+                id: id.into(),
+                config: section.config,
+                start_block: section.start_block,
+                restart_policy: section.restart,
+                exex,
+            });
+        }
+    }
+
+    // WASM extensions are opted into by being in the directory.
+    if let Some(dir) = &params.exex_wasm_dir {
+        let engine = wasm_engine()?;
+        let fuel = params.exex_wasm_fuel;
+        for (id, module) in load_wasm_modules(&engine, dir)? {
+            if builtin_ids.contains(&id.as_str()) {
+                anyhow::bail!("WASM ExEx `{id}` has the same id as a built-in ExEx");
+            }
+            let section = sections.remove(&id).unwrap_or_default();
+            if section.enabled == Some(false) {
+                continue;
+            }
+            let engine = engine.clone();
+            let exex_id = id.clone();
+            let exex = box_exex(move |ctx| exex_wasm(ctx, engine, module, exex_id, fuel));
+            exexs.push(InstalledExEx {
+                id,
+                config: section.config,
+                start_block: section.start_block,
+                restart_policy: section.restart,
+                exex,
+            });
+        }
+    }
+
+    if let Some(id) = sections.keys().next() {
+        anyhow::bail!("Unknown ExEx `{id}` in the ExEx config file");
+    }
+
+    for exex in &exexs {
+        log::info!("🧩 ExEx {} enabled", exex.id);
+    }
+    Ok(exexs)
 }
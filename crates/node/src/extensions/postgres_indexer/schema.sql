@@ -0,0 +1,76 @@
+-- Schema of the Postgres indexer ExEx. Felts are stored as 32 bytes big-endian.
+
+CREATE TABLE IF NOT EXISTS blocks (
+    block_number BIGINT PRIMARY KEY,
+    block_hash BYTEA NOT NULL UNIQUE,
+    parent_block_hash BYTEA NOT NULL,
+    global_state_root BYTEA NOT NULL,
+    sequencer_address BYTEA NOT NULL,
+    block_timestamp BIGINT NOT NULL,
+    transaction_count BIGINT NOT NULL,
+    event_count BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    transaction_hash BYTEA PRIMARY KEY,
+    block_number BIGINT NOT NULL REFERENCES blocks ON DELETE CASCADE,
+    transaction_index INT NOT NULL,
+    transaction_type TEXT NOT NULL,
+    execution_status TEXT NOT NULL,
+    revert_reason TEXT,
+    actual_fee BYTEA NOT NULL,
+    fee_unit TEXT NOT NULL,
+    transaction JSONB NOT NULL,
+    receipt JSONB NOT NULL,
+    UNIQUE (block_number, transaction_index)
+);
+
+CREATE TABLE IF NOT EXISTS events (
+    block_number BIGINT NOT NULL REFERENCES blocks ON DELETE CASCADE,
+    -- Index of the event in the block.
+    event_index INT NOT NULL,
+    transaction_hash BYTEA NOT NULL,
+    from_address BYTEA NOT NULL,
+    keys BYTEA[] NOT NULL,
+    data BYTEA[] NOT NULL,
+    PRIMARY KEY (block_number, event_index)
+);
+CREATE INDEX IF NOT EXISTS events_from_address ON events (from_address, block_number);
+CREATE INDEX IF NOT EXISTS events_selector ON events ((keys[1]), block_number);
+
+CREATE TABLE IF NOT EXISTS storage_updates (
+    block_number BIGINT NOT NULL REFERENCES blocks ON DELETE CASCADE,
+    contract_address BYTEA NOT NULL,
+    key BYTEA NOT NULL,
+    value BYTEA NOT NULL,
+    PRIMARY KEY (contract_address, key, block_number)
+);
+
+CREATE TABLE IF NOT EXISTS nonce_updates (
+    block_number BIGINT NOT NULL REFERENCES blocks ON DELETE CASCADE,
+    contract_address BYTEA NOT NULL,
+    nonce BYTEA NOT NULL,
+    PRIMARY KEY (contract_address, block_number)
+);
+
+-- Deployed contracts, and contracts whose class got replaced.
+CREATE TABLE IF NOT EXISTS contract_classes (
+    block_number BIGINT NOT NULL REFERENCES blocks ON DELETE CASCADE,
+    contract_address BYTEA NOT NULL,
+    class_hash BYTEA NOT NULL,
+    replaced BOOLEAN NOT NULL,
+    PRIMARY KEY (contract_address, block_number)
+);
+
+-- `compiled_class_hash` is null for legacy classes.
+CREATE TABLE IF NOT EXISTS declared_classes (
+    class_hash BYTEA PRIMARY KEY,
+    block_number BIGINT NOT NULL REFERENCES blocks ON DELETE CASCADE,
+    compiled_class_hash BYTEA
+);
+
+-- Latest block indexed, written along with each block.
+CREATE TABLE IF NOT EXISTS indexer_checkpoint (
+    id TEXT PRIMARY KEY,
+    block_number BIGINT NOT NULL
+);
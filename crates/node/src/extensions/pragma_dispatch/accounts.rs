@@ -0,0 +1,98 @@
+use super::config::DispatchAccount;
+use super::nonce::NonceManager;
+
+/// A dispatch account, along with its nonce.
+pub struct PooledAccount {
+    pub account: DispatchAccount,
+    pub nonces: NonceManager,
+}
+
+/// The dispatch accounts, used in turn so that the dispatches are not bound by the sequential nonces of a single
+/// account, and do not stop when one of them is stuck or runs out of funds.
+pub struct AccountPool {
+    accounts: Vec<PooledAccount>,
+    next: usize,
+}
+
+impl AccountPool {
+    pub fn new(accounts: Vec<DispatchAccount>) -> Self {
+        let accounts =
+            accounts.into_iter().map(|account| PooledAccount { account, nonces: NonceManager::default() }).collect();
+        Self { accounts, next: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// The account whose turn it is to dispatch.
+    pub fn next(&mut self) -> &mut PooledAccount {
+        let index = self.next_index();
+        &mut self.accounts[index]
+    }
+
+    /// Index of the account whose turn it is to dispatch.
+    pub fn next_index(&mut self) -> usize {
+        let index = self.next % self.accounts.len();
+        self.next = index + 1;
+        index
+    }
+
+    pub fn get(&mut self, index: usize) -> &mut PooledAccount {
+        &mut self.accounts[index]
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = String> + '_ {
+        self.accounts.iter().map(|pooled| format!("0x{:x}", pooled.account.address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_keystore::StarknetSigner;
+    use starknet_core::crypto::Signature;
+    use starknet_core::types::Felt;
+    use std::sync::Arc;
+
+    struct TestSigner;
+
+    #[async_trait::async_trait]
+    impl StarknetSigner for TestSigner {
+        fn public_key(&self) -> Felt {
+            Felt::ONE
+        }
+
+        async fn sign(&self, _hash: &Felt) -> anyhow::Result<Signature> {
+            anyhow::bail!("Not signing in tests")
+        }
+    }
+
+    fn pool(addresses: &[u64]) -> AccountPool {
+        AccountPool::new(
+            addresses
+                .iter()
+                .map(|address| DispatchAccount { address: Felt::from(*address), signer: Arc::new(TestSigner) })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_accounts_used_in_turn() {
+        let mut pool = pool(&[0x1, 0x2, 0x3]);
+        assert_eq!(pool.len(), 3);
+        let addresses: Vec<_> = (0..7).map(|_| pool.next().account.address).collect();
+        assert_eq!(addresses, [1u64, 2, 3, 1, 2, 3, 1].map(Felt::from));
+    }
+
+    #[test]
+    fn test_account_by_index() {
+        let mut pool = pool(&[0x1, 0x2]);
+        let index = pool.next_index();
+        assert_eq!(index, 0);
+        assert_eq!(pool.next_index(), 1);
+        assert_eq!(pool.next_index(), index);
+        assert_eq!(pool.get(1).account.address, Felt::TWO);
+        assert_eq!(pool.addresses().collect::<Vec<_>>(), ["0x1", "0x2"]);
+    }
+}
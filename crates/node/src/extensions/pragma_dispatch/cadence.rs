@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use starknet_core::types::Felt;
+
+/// Decides which produced blocks the feeds are dispatched at.
+#[derive(Debug)]
+pub struct Cadence {
+    every_blocks: u64,
+    interval: Option<Duration>,
+    last_dispatch: Option<(u64, Instant)>,
+}
+
+impl Cadence {
+    pub fn new(every_blocks: u64, interval: Option<Duration>) -> Self {
+        Self { every_blocks: every_blocks.max(1), interval, last_dispatch: None }
+    }
+
+    /// Whether the feeds should be dispatched at this block. The first block is always dispatched at.
+    pub fn is_due(&self, block_number: u64) -> bool {
+        let Some((last_block, last_time)) = self.last_dispatch else { return true };
+        match self.interval {
+            Some(interval) => last_time.elapsed() >= interval,
+            None => block_number >= last_block + self.every_blocks,
+        }
+    }
+
+    /// Records a successful dispatch. Failed dispatches are retried at the next block.
+    pub fn dispatched(&mut self, block_number: u64) {
+        self.last_dispatch = Some((block_number, Instant::now()));
+    }
+}
+
+/// Splits the feed ids list (its length followed by the ids) into lists of at most `max_feeds` feeds, in the same
+/// format.
+pub fn split_feed_ids(feed_ids: &[Felt], max_feeds: Option<usize>) -> Vec<Vec<Felt>> {
+    let ids = feed_ids.get(1..).unwrap_or_default();
+    let Some(max_feeds) = max_feeds.filter(|max_feeds| *max_feeds > 0 && ids.len() > *max_feeds) else {
+        return vec![feed_ids.to_vec()];
+    };
+    ids.chunks(max_feeds)
+        .map(|chunk| std::iter::once(Felt::from(chunk.len())).chain(chunk.iter().copied()).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cadence_every_blocks() {
+        let mut cadence = Cadence::new(3, None);
+        assert!(cadence.is_due(10));
+        cadence.dispatched(10);
+        assert!(!cadence.is_due(11));
+        assert!(!cadence.is_due(12));
+        assert!(cadence.is_due(13));
+        // A failed dispatch is retried at the next block.
+        assert!(cadence.is_due(14));
+    }
+
+    #[test]
+    fn test_cadence_zero_blocks() {
+        let mut cadence = Cadence::new(0, None);
+        cadence.dispatched(10);
+        assert!(!cadence.is_due(10));
+        assert!(cadence.is_due(11));
+    }
+
+    #[test]
+    fn test_cadence_interval() {
+        let mut cadence = Cadence::new(1, Some(Duration::from_millis(50)));
+        assert!(cadence.is_due(10));
+        cadence.dispatched(10);
+        // The interval takes precedence over the block count.
+        assert!(!cadence.is_due(100));
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cadence.is_due(11));
+    }
+
+    fn ids(ids: &[u64]) -> Vec<Felt> {
+        std::iter::once(Felt::from(ids.len())).chain(ids.iter().copied().map(Felt::from)).collect()
+    }
+
+    #[test]
+    fn test_split_feed_ids() {
+        assert_eq!(split_feed_ids(&ids(&[1, 2, 3, 4, 5]), Some(2)), vec![ids(&[1, 2]), ids(&[3, 4]), ids(&[5])]);
+        assert_eq!(split_feed_ids(&ids(&[1, 2, 3, 4]), Some(2)), vec![ids(&[1, 2]), ids(&[3, 4])]);
+    }
+
+    #[test]
+    fn test_split_feed_ids_single_transaction() {
+        assert_eq!(split_feed_ids(&ids(&[1, 2, 3]), None), vec![ids(&[1, 2, 3])]);
+        assert_eq!(split_feed_ids(&ids(&[1, 2, 3]), Some(0)), vec![ids(&[1, 2, 3])]);
+        assert_eq!(split_feed_ids(&ids(&[1, 2, 3]), Some(3)), vec![ids(&[1, 2, 3])]);
+        assert_eq!(split_feed_ids(&ids(&[]), Some(2)), vec![ids(&[])]);
+    }
+}
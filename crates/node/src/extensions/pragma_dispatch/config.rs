@@ -0,0 +1,339 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use mp_keystore::{KeySource, Password, StarknetSigner};
+use mp_utils::serde::deserialize_duration;
+use serde::Deserialize;
+use starknet_core::types::Felt;
+use url::Url;
+
+/// Pragma Feeds Registry of the default Pragma deployment.
+const DEFAULT_FEEDS_REGISTRY_ADDRESS: Felt =
+    Felt::from_hex_unchecked("0x13c3404ff9802442d0bf389afcf2fab9201b47c2268fcaa4bd36ba1978af76");
+/// Pragma Dispatcher of the default Pragma deployment.
+const DEFAULT_DISPATCHER_ADDRESS: Felt =
+    Felt::from_hex_unchecked("0x38d9b85bf3623681aaa37b1c591b07237dee8b17a11eaac53ddc07a306fefe2");
+/// `NewFeedId` event selector.
+const DEFAULT_NEW_FEED_ID_SELECTOR: Felt =
+    Felt::from_hex_unchecked("0x012eaeb62184f1ca53999ece2d2273b81f9c64bc057a93dad05e09f970b030f9");
+/// `RemovedFeedId` event selector.
+const DEFAULT_REMOVED_FEED_ID_SELECTOR: Felt =
+    Felt::from_hex_unchecked("0x02a45c5a3b53e7afa46712156f544cec1b9d4679804036a16ec9521389117be4");
+/// 0.01 ETH.
+const DEFAULT_MAX_FEE: Felt = Felt::from_hex_unchecked("0x2386F26FC10000");
+
+/// Version of the dispatch transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionVersion {
+    /// Fees are paid in ETH, up to `max_fee`.
+    #[default]
+    V1,
+    /// Fees are paid in STRK, with resource bounds derived from the estimated fee.
+    V3,
+}
+
+/// Config section of the Pragma dispatch ExEx. The contracts default to the ones the ExEx was built against.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PragmaDispatchConfig {
+    /// Contract listing the feeds to dispatch.
+    #[serde(default = "default_feeds_registry_address")]
+    pub feeds_registry_address: Felt,
+    /// Contract dispatching the feeds through Hyperlane.
+    #[serde(default = "default_dispatcher_address")]
+    pub dispatcher_address: Felt,
+    /// Selector of the event emitted by the registry when a feed is added.
+    #[serde(default = "default_new_feed_id_selector")]
+    pub new_feed_id_selector: Felt,
+    /// Selector of the event emitted by the registry when a feed is removed.
+    #[serde(default = "default_removed_feed_id_selector")]
+    pub removed_feed_id_selector: Felt,
+    #[serde(default)]
+    pub transaction_version: TransactionVersion,
+    /// Max fee of the V1 dispatch transactions, in wei.
+    #[serde(default = "default_max_fee")]
+    pub max_fee: Felt,
+    /// Margin applied to the estimated gas amount and price, to get the resource bounds of V3 dispatch transactions.
+    #[serde(default = "default_fee_estimate_multiplier")]
+    pub fee_estimate_multiplier: f64,
+    /// Cap of the fee escalation, as a multiple of the initial fee. Dispatch transactions with an insufficient fee,
+    /// or not included in time, are resubmitted with a higher fee up to this cap. `1` disables the escalation.
+    #[serde(default = "default_max_fee_escalation")]
+    pub max_fee_escalation: f64,
+    /// Multiplier applied to the fee on each escalation.
+    #[serde(default = "default_fee_escalation_multiplier")]
+    pub fee_escalation_multiplier: f64,
+    /// Number of blocks after which a dispatch transaction that is still not included gets its fee escalated, or is
+    /// given up on when its fee is at the cap. Each escalation needs to raise the fee by at least
+    /// [`mc_mempool::REPLACEMENT_FEE_BUMP_PERCENT`] for the mempool to replace the previous transaction.
+    #[serde(default = "default_fee_escalation_blocks")]
+    pub fee_escalation_blocks: u64,
+    /// Only dispatch these feeds, among the ones of the registry. All the feeds are dispatched when unset.
+    pub allowed_feeds: Option<Vec<Felt>>,
+    /// Never dispatch these feeds, even when they are in `allowed_feeds`.
+    #[serde(default)]
+    pub denied_feeds: Vec<Felt>,
+    /// Dispatch the feeds every this many produced blocks.
+    #[serde(default = "default_dispatch_every_blocks")]
+    pub dispatch_every_blocks: u64,
+    /// Dispatch the feeds at the first produced block after this interval elapsed since the previous dispatch,
+    /// instead of every `dispatch_every_blocks` blocks.
+    #[serde(default, deserialize_with = "deserialize_optional_duration")]
+    pub dispatch_interval: Option<Duration>,
+    /// Dispatch the feeds in several transactions of at most this many feeds, to stay within the calldata and gas
+    /// limits. All the feeds are dispatched in a single transaction when unset.
+    pub max_feeds_per_transaction: Option<usize>,
+    /// Simulate the dispatch transactions and log their expected outcome instead of submitting them. The metrics
+    /// then report the simulations.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Number of times a dispatch transaction is resubmitted when the mempool does not accept it.
+    #[serde(default = "default_max_submission_retries")]
+    pub max_submission_retries: u32,
+    /// Delay before resubmitting a dispatch transaction, doubled on each retry.
+    #[serde(default = "default_retry_interval", deserialize_with = "deserialize_duration")]
+    pub retry_interval: Duration,
+    /// Account sending the dispatch transactions.
+    pub account_address: Option<Felt>,
+    /// Encrypted JSON keystore holding the private key of the account.
+    pub keystore: Option<PathBuf>,
+    /// Environment variable holding the password of the keystore.
+    pub keystore_password_env: Option<String>,
+    /// File holding the password of the keystore.
+    pub keystore_password_file: Option<PathBuf>,
+    /// Environment variable holding the private key of the account, as an alternative to a keystore.
+    pub private_key_env: Option<String>,
+    /// Signing service holding the private key of the account, see `mp_keystore::RemoteSigner`.
+    pub remote_signer: Option<Url>,
+    /// Environment variable holding the bearer token of the remote signer, when it requires authentication.
+    pub remote_signer_token_env: Option<String>,
+    /// Additional accounts, used in turn with the main one to send the dispatch transactions.
+    #[serde(default)]
+    pub accounts: Vec<AccountConfig>,
+}
+
+/// An account sending dispatch transactions, with where to load its private key from.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+    pub address: Felt,
+    /// Encrypted JSON keystore holding the private key of the account.
+    pub keystore: Option<PathBuf>,
+    /// Environment variable holding the password of the keystore.
+    pub keystore_password_env: Option<String>,
+    /// File holding the password of the keystore.
+    pub keystore_password_file: Option<PathBuf>,
+    /// Environment variable holding the private key of the account, as an alternative to a keystore.
+    pub private_key_env: Option<String>,
+    /// Signing service holding the private key of the account, see `mp_keystore::RemoteSigner`.
+    pub remote_signer: Option<Url>,
+    /// Environment variable holding the bearer token of the remote signer, when it requires authentication.
+    pub remote_signer_token_env: Option<String>,
+}
+
+fn default_feeds_registry_address() -> Felt {
+    DEFAULT_FEEDS_REGISTRY_ADDRESS
+}
+
+fn default_dispatcher_address() -> Felt {
+    DEFAULT_DISPATCHER_ADDRESS
+}
+
+fn default_new_feed_id_selector() -> Felt {
+    DEFAULT_NEW_FEED_ID_SELECTOR
+}
+
+fn default_removed_feed_id_selector() -> Felt {
+    DEFAULT_REMOVED_FEED_ID_SELECTOR
+}
+
+fn default_max_fee() -> Felt {
+    DEFAULT_MAX_FEE
+}
+
+fn default_dispatch_every_blocks() -> u64 {
+    1
+}
+
+fn deserialize_optional_duration<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    deserialize_duration(deserializer).map(Some)
+}
+
+fn default_fee_estimate_multiplier() -> f64 {
+    1.5
+}
+
+fn default_max_fee_escalation() -> f64 {
+    1.0
+}
+
+fn default_fee_escalation_multiplier() -> f64 {
+    1.25
+}
+
+fn default_fee_escalation_blocks() -> u64 {
+    10
+}
+
+fn default_max_submission_retries() -> u32 {
+    5
+}
+
+fn default_retry_interval() -> Duration {
+    Duration::from_millis(500)
+}
+
+/// An account sending the dispatch transactions.
+pub struct DispatchAccount {
+    pub address: Felt,
+    pub signer: Arc<dyn StarknetSigner>,
+}
+
+impl PragmaDispatchConfig {
+    /// The feeds of the registry that are dispatched, in the same format: the length of the list followed by the ids.
+    pub fn filter_feed_ids(&self, feed_ids: &[Felt]) -> Vec<Felt> {
+        let ids: Vec<Felt> = feed_ids
+            .get(1..)
+            .unwrap_or_default()
+            .iter()
+            .filter(|id| self.allowed_feeds.as_ref().map_or(true, |allowed| allowed.contains(id)))
+            .filter(|id| !self.denied_feeds.contains(id))
+            .copied()
+            .collect();
+        std::iter::once(Felt::from(ids.len())).chain(ids).collect()
+    }
+
+    /// The next fee multiplier after `fee_factor`, or `None` when the fee is already at its cap.
+    pub fn escalated_fee_factor(&self, fee_factor: f64) -> Option<f64> {
+        (fee_factor < self.max_fee_escalation)
+            .then(|| (fee_factor * self.fee_escalation_multiplier).min(self.max_fee_escalation))
+    }
+
+    /// Loads the signing keys of the dispatch accounts, the main account first.
+    pub async fn load_accounts(&self) -> anyhow::Result<Vec<DispatchAccount>> {
+        let main_account = match self.account_address {
+            Some(address) => Some(AccountConfig {
+                address,
+                keystore: self.keystore.clone(),
+                keystore_password_env: self.keystore_password_env.clone(),
+                keystore_password_file: self.keystore_password_file.clone(),
+                private_key_env: self.private_key_env.clone(),
+                remote_signer: self.remote_signer.clone(),
+                remote_signer_token_env: self.remote_signer_token_env.clone(),
+            }),
+            None if self.keystore.is_some() || self.private_key_env.is_some() || self.remote_signer.is_some() => {
+                bail!("The key of the dispatch account requires an `account_address`")
+            }
+            None => None,
+        };
+        let accounts: Vec<_> = main_account.iter().chain(&self.accounts).collect();
+        if accounts.is_empty() {
+            bail!("The dispatch ExEx requires an `account_address`, or `accounts`");
+        }
+        let mut loaded = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            loaded.push(account.load().await.with_context(|| format!("Loading the account 0x{:x}", account.address))?);
+        }
+        Ok(loaded)
+    }
+}
+
+impl AccountConfig {
+    /// Loads the signing key of the account, from its keystore, the environment or a remote signer.
+    pub async fn load(&self) -> anyhow::Result<DispatchAccount> {
+        let password = match (&self.keystore_password_env, &self.keystore_password_file) {
+            (Some(var), None) => Some(Password::Env(var.clone())),
+            (None, Some(path)) => Some(Password::File(path.clone())),
+            (None, None) => None,
+            (Some(_), Some(_)) => bail!("Only one of `keystore_password_env` and `keystore_password_file` can be set"),
+        };
+        let source = KeySource::from_options(
+            self.keystore.clone(),
+            password,
+            self.private_key_env.clone(),
+            self.remote_signer.clone(),
+            self.remote_signer_token_env.clone().map(Password::Env),
+        )?
+        .context("The dispatch account requires a `keystore`, a `private_key_env` or a `remote_signer`")?;
+        Ok(DispatchAccount { address: self.address, signer: source.starknet_signer().await? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn config(toml: &str) -> PragmaDispatchConfig {
+        toml::from_str(toml).expect("Parsing the config")
+    }
+
+    fn ids(ids: &[u64]) -> Vec<Felt> {
+        std::iter::once(Felt::from(ids.len())).chain(ids.iter().copied().map(Felt::from)).collect()
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config = config("");
+        assert_eq!(config.feeds_registry_address, DEFAULT_FEEDS_REGISTRY_ADDRESS);
+        assert_eq!(config.transaction_version, TransactionVersion::V1);
+        assert_eq!(config.dispatch_every_blocks, 1);
+        assert_eq!(config.dispatch_interval, None);
+        assert_eq!(config.retry_interval, Duration::from_millis(500));
+        assert_eq!(config.escalated_fee_factor(1.0), None);
+    }
+
+    #[test]
+    fn test_config_unknown_field() {
+        assert!(toml::from_str::<PragmaDispatchConfig>("dispatch_every = 2").is_err());
+    }
+
+    #[rstest]
+    #[case::all("", &[1, 2, 3], &[1, 2, 3])]
+    #[case::allowed("allowed_feeds = [\"0x1\", \"0x3\", \"0x4\"]", &[1, 2, 3], &[1, 3])]
+    #[case::denied("denied_feeds = [\"0x2\"]", &[1, 2, 3], &[1, 3])]
+    #[case::allowed_and_denied(
+        "allowed_feeds = [\"0x1\", \"0x2\"]\ndenied_feeds = [\"0x2\"]",
+        &[1, 2, 3],
+        &[1]
+    )]
+    #[case::none_allowed("allowed_feeds = []", &[1, 2, 3], &[])]
+    #[case::empty_registry("", &[], &[])]
+    fn test_filter_feed_ids(#[case] toml: &str, #[case] registry: &[u64], #[case] expected: &[u64]) {
+        assert_eq!(config(toml).filter_feed_ids(&ids(registry)), ids(expected));
+    }
+
+    #[test]
+    fn test_escalated_fee_factor() {
+        let config = config("max_fee_escalation = 2.0\nfee_escalation_multiplier = 1.5");
+        assert_eq!(config.escalated_fee_factor(1.0), Some(1.5));
+        // Capped at `max_fee_escalation`.
+        assert_eq!(config.escalated_fee_factor(1.5), Some(2.0));
+        assert_eq!(config.escalated_fee_factor(2.0), None);
+    }
+
+    #[tokio::test]
+    async fn test_load_accounts_requires_an_account() {
+        assert!(config("").load_accounts().await.is_err());
+        assert!(config("private_key_env = \"DISPATCH_KEY\"").load_accounts().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_account_password_sources() {
+        let account = AccountConfig {
+            address: Felt::ONE,
+            keystore: Some("keystore.json".into()),
+            keystore_password_env: Some("DISPATCH_PASSWORD".into()),
+            keystore_password_file: Some("password.txt".into()),
+            private_key_env: None,
+            remote_signer: None,
+            remote_signer_token_env: None,
+        };
+        assert!(account.load().await.is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::Context;
+use mc_db::MadaraBackend;
+use serde::{Deserialize, Serialize};
+use starknet_core::types::Felt;
+
+/// Key of the feed ids cache in the database.
+const CACHE_KEY: &str = "pragma_dispatch/feed_ids";
+
+/// The feed ids to dispatch, persisted so that they are not requeried from the registry on restart.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FeedIdsCache {
+    /// The first element is the length of the vec & after are the elements.
+    pub feed_ids: Vec<Felt>,
+    /// Last block whose registry events are accounted for in the feed ids.
+    pub block_number: Option<u64>,
+}
+
+impl FeedIdsCache {
+    pub fn load(backend: &MadaraBackend) -> anyhow::Result<Option<Self>> {
+        let Some(bytes) = backend.get_exex_state(CACHE_KEY).context("Getting the feed ids cache")? else {
+            return Ok(None);
+        };
+        Ok(Some(bincode::deserialize(&bytes).context("Deserializing the feed ids cache")?))
+    }
+
+    pub fn save(&self, backend: &MadaraBackend) -> anyhow::Result<()> {
+        let bytes = bincode::serialize(self).context("Serializing the feed ids cache")?;
+        backend.write_exex_state(CACHE_KEY, &bytes).context("Writing the feed ids cache")?;
+        Ok(())
+    }
+
+    /// Whether the registry events of this block are already accounted for.
+    pub fn includes(&self, block_number: u64) -> bool {
+        self.block_number.is_some_and(|cached| block_number <= cached)
+    }
+}
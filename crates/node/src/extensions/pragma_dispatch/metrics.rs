@@ -0,0 +1,112 @@
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use mc_metrics::{
+    exponential_buckets, Counter, CounterVec, Gauge, Histogram, HistogramOpts, MetricsRegistry, Opts, PrometheusError,
+    F64, U64,
+};
+
+/// Why a dispatch failed, as reported in the `reason` label of the failures metric.
+///
+/// Attached as context to the dispatch errors, and recovered with [`FailureReason::of`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureReason {
+    /// The mempool definitely rejected the transaction.
+    Rejected,
+    /// The mempool still did not accept the transaction after all the retries.
+    RetriesExhausted,
+    /// The transaction was still not included once its fee was at the cap.
+    TransactionRejected,
+    /// The transaction execution reverted.
+    Reverted,
+    /// The transaction could not be built, or its status could not be retrieved.
+    Other,
+}
+
+impl FailureReason {
+    pub fn of(err: &anyhow::Error) -> Self {
+        err.downcast_ref::<Self>().copied().unwrap_or(Self::Other)
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Rejected => "rejected",
+            Self::RetriesExhausted => "retries_exhausted",
+            Self::TransactionRejected => "transaction_rejected",
+            Self::Reverted => "reverted",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Rejected => "Dispatch transaction rejected by the mempool",
+            Self::RetriesExhausted => "Dispatch transaction not accepted by the mempool",
+            Self::TransactionRejected => "Dispatch transaction not included",
+            Self::Reverted => "Dispatch transaction reverted",
+            Self::Other => "Dispatch failed",
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PragmaDispatchMetrics {
+    pub attempts: Counter<U64>,
+    pub successes: Counter<U64>,
+    failures: CounterVec<U64>,
+    /// Time from the submission of the dispatch transactions to their inclusion.
+    pub latency: Histogram,
+    pub feed_count: Gauge<F64>,
+    pub last_dispatched_block: Gauge<F64>,
+}
+
+impl PragmaDispatchMetrics {
+    /// Registers the metrics the first time, and returns the same ones when the ExEx is restarted.
+    pub fn get_or_register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        static METRICS: OnceLock<PragmaDispatchMetrics> = OnceLock::new();
+        if let Some(metrics) = METRICS.get() {
+            return Ok(metrics.clone());
+        }
+        let metrics = Self::register(registry)?;
+        Ok(METRICS.get_or_init(|| metrics).clone())
+    }
+
+    fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            attempts: registry
+                .register(Counter::new("pragma_dispatch_attempts", "Number of feed dispatches attempted")?)?,
+            successes: registry
+                .register(Counter::new("pragma_dispatch_successes", "Number of feed dispatches accepted on chain")?)?,
+            failures: registry.register(CounterVec::new(
+                Opts::new("pragma_dispatch_failures", "Number of failed feed dispatches, by reason"),
+                &["reason"],
+            )?)?,
+            latency: registry.register(Histogram::with_opts(
+                HistogramOpts::new(
+                    "pragma_dispatch_latency_seconds",
+                    "Time [s] from the submission of the dispatch transactions to their inclusion",
+                )
+                .buckets(exponential_buckets(0.1, 2.0, 10)?),
+            )?)?,
+            feed_count: registry.register(Gauge::new("pragma_dispatch_feed_count", "Number of feeds dispatched")?)?,
+            last_dispatched_block: registry.register(Gauge::new(
+                "pragma_dispatch_last_dispatched_block",
+                "Number of the last block the feeds were successfully dispatched at",
+            )?)?,
+        })
+    }
+
+    /// Records a dispatch whose transactions are all included.
+    pub fn succeeded(&self, block_number: u64, started_at: Instant) {
+        self.successes.inc();
+        self.latency.observe(started_at.elapsed().as_secs_f64());
+        self.last_dispatched_block.set(block_number as f64);
+    }
+
+    pub fn failed(&self, reason: FailureReason) {
+        self.failures.with_label_values(&[reason.as_str()]).inc();
+    }
+}
@@ -0,0 +1,681 @@
+//! ExEx of Pragma Dispatcher
+//! Adds a new TX at the end of each block, dispatching a message through
+//! Hyperlane.
+mod accounts;
+mod cadence;
+mod config;
+mod feeds;
+mod metrics;
+mod nonce;
+mod pending;
+
+use std::{mem, sync::Arc, time::Instant};
+
+use anyhow::{anyhow, Context};
+use futures::StreamExt;
+use mp_rpc::Starknet;
+use starknet_core::types::{
+    BlockId, BlockTag, BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1, BroadcastedInvokeTransactionV3,
+    BroadcastedTransaction, DataAvailabilityMode, ExecuteInvocation, Felt, FunctionCall, InvokeTransactionTrace,
+    ResourceBounds, ResourceBoundsMapping, RevertedInvocation, SimulatedTransaction, SimulationFlagForEstimateFee,
+    TransactionTrace,
+};
+
+use accounts::{AccountPool, PooledAccount};
+use cadence::{split_feed_ids, Cadence};
+use config::{DispatchAccount, PragmaDispatchConfig, TransactionVersion};
+use feeds::FeedIdsCache;
+use mc_devnet::{Call, Multicall, Selector};
+use mc_mempool::transaction_hash;
+use mc_rpc::versions::v0_7_1::{
+    StarknetReadRpcApiV0_7_1Server, StarknetTraceRpcApiV0_7_1Server, StarknetWriteRpcApiV0_7_1Server,
+};
+use metrics::{FailureReason, PragmaDispatchMetrics};
+use mp_convert::{felt_to_u128, ToFelt};
+use mp_exex::{ExExContext, ExExEvent, ExExNotification};
+use mp_transactions::broadcasted_to_blockifier;
+use nonce::{NonceManager, SubmissionFailure};
+use pending::{PendingDispatch, PendingTransaction};
+use tokio::time::sleep;
+
+const PENDING_BLOCK: BlockId = BlockId::Tag(BlockTag::Pending);
+
+lazy_static::lazy_static! {
+    // Empty feed list. Used instead of [`Vec::is_empty`].
+    // The first element is the length of the vec & after are the elements.
+    pub static ref EMPTY_FEEDS: Vec<Felt> = vec![Felt::ZERO];
+}
+
+/// 🧩 Pragma main ExEx.
+/// At the end of the produced blocks, following the configured cadence, adds new dispatch transactions
+/// using the Pragma Dispatcher contract.
+///
+/// The inclusion of the dispatch transactions is tracked in the following notifications, so that the ExEx never
+/// waits for blocks that are only produced once it handled its notifications.
+pub async fn exex_pragma_dispatch(mut ctx: ExExContext) -> anyhow::Result<()> {
+    let config: PragmaDispatchConfig = ctx.config()?;
+    let mut accounts = AccountPool::new(config.load_accounts().await?);
+    log::info!("🧩 Pragma's ExEx: Dispatching from account(s) {}", accounts.addresses().collect::<Vec<_>>().join(", "));
+    if config.dry_run {
+        log::info!("🧩 Pragma's ExEx: Dry run, the dispatch transactions will only be simulated");
+    }
+    if config.max_fee_escalation > 1.0
+        && config.fee_escalation_multiplier < 1.0 + mc_mempool::REPLACEMENT_FEE_BUMP_PERCENT as f64 / 100.0
+    {
+        log::warn!(
+            "🧩 Pragma's ExEx: A fee_escalation_multiplier under {:.2} is not enough for the mempool to replace the \
+             dispatch transactions that are not included",
+            1.0 + mc_mempool::REPLACEMENT_FEE_BUMP_PERCENT as f64 / 100.0
+        );
+    }
+    let mut cadence = Cadence::new(config.dispatch_every_blocks, config.dispatch_interval);
+    let metrics = PragmaDispatchMetrics::get_or_register(&ctx.metrics)?;
+
+    // Feed ids that will be dispatched, restored from the database when they were persisted by a previous run.
+    let mut feeds = match FeedIdsCache::load(&ctx.backend)? {
+        Some(feeds) => {
+            log::info!(
+                "🧩 Pragma's ExEx: Restored feed IDs up to block #{:?}. Total feeds: {}",
+                feeds.block_number,
+                feeds.feed_ids[0]
+            );
+            feeds
+        }
+        None => {
+            let feeds = FeedIdsCache {
+                feed_ids: get_feed_ids_from_registry(&ctx.starknet, &config).await.unwrap_or(vec![Felt::ZERO]),
+                block_number: ctx.backend.get_latest_block_n()?,
+            };
+            log::info!("🧩 Pragma's ExEx: Initialized feed IDs from Registry. Total feeds: {}", feeds.feed_ids[0]);
+            save_feed_ids(&ctx, &feeds);
+            feeds
+        }
+    };
+    metrics.feed_count.set(feed_count(&config.filter_feed_ids(&feeds.feed_ids)));
+    let mut pending_dispatches = Vec::new();
+
+    while let Some(notification) = ctx.notifications.next().await {
+        // Synced blocks, including the ones replayed on restart, are only scanned for registry events.
+        let (receipts, block_number, produced) = match notification {
+            ExExNotification::BlockProduced { block, block_number, .. } => (block.inner.receipts, block_number, true),
+            ExExNotification::BlockSynced { block, block_number, .. } => {
+                (block.map(|block| block.inner.receipts).unwrap_or_default(), block_number, false)
+            }
+            // Dispatches are transactions of the chain, and are rolled back along with it. The feed ids may have
+            // changed in the reverted blocks though.
+            ExExNotification::Reverted { to, .. } => {
+                if feeds.block_number.is_some_and(|block_number| block_number > to.0) {
+                    match get_feed_ids_from_registry(&ctx.starknet, &config).await {
+                        Ok(feed_ids) => {
+                            feeds = FeedIdsCache { feed_ids, block_number: Some(to.0) };
+                            save_feed_ids(&ctx, &feeds);
+                        }
+                        Err(e) => log::error!("🧩 [#{}] Pragma's ExEx: Error while refreshing feed IDs: {:?}", to, e),
+                    }
+                }
+                continue;
+            }
+        };
+
+        track_pending_dispatches(
+            &ctx,
+            &config,
+            &mut accounts,
+            &metrics,
+            &mut pending_dispatches,
+            &receipts,
+            block_number.0,
+        )
+        .await;
+
+        // Will update in-place the feed ids vec
+        match update_feed_ids_if_necessary(&ctx.starknet, &config, &receipts, block_number.0, &mut feeds).await {
+            Ok(true) => save_feed_ids(&ctx, &feeds),
+            Ok(false) => {}
+            Err(e) => {
+                log::error!("🧩 [#{}] Pragma's ExEx: Error while updating feed IDs: {:?}", block_number, e);
+                ctx.events.send(ExExEvent::FinishedHeight(block_number))?;
+                continue;
+            }
+        }
+        // The feeds excluded by the config are still tracked, so that they can be dispatched again once allowed.
+        let feed_ids = config.filter_feed_ids(&feeds.feed_ids);
+        metrics.feed_count.set(feed_count(&feed_ids));
+
+        if !produced {
+            ctx.events.send(ExExEvent::FinishedHeight(block_number))?;
+            continue;
+        }
+
+        if !cadence.is_due(block_number.0) {
+            ctx.events.send(ExExEvent::FinishedHeight(block_number))?;
+            continue;
+        }
+
+        if feed_ids == *EMPTY_FEEDS {
+            log::warn!("🧩 [#{}] Pragma's ExEx: No feed IDs available, skipping dispatch", block_number);
+            ctx.events.send(ExExEvent::FinishedHeight(block_number))?;
+            continue;
+        }
+
+        metrics.attempts.inc();
+        let started_at = Instant::now();
+        match process_dispatch_transaction(&ctx, &config, &mut accounts, block_number.0, &feed_ids).await {
+            Ok(transactions) => {
+                cadence.dispatched(block_number.0);
+                if transactions.is_empty() {
+                    // Dry run, there is nothing to wait for.
+                    metrics.succeeded(block_number.0, started_at);
+                } else {
+                    pending_dispatches.push(PendingDispatch {
+                        block_number: block_number.0,
+                        started_at,
+                        transactions,
+                        reverted: None,
+                    });
+                }
+            }
+            Err(e) => {
+                metrics.failed(FailureReason::of(&e));
+                log::error!(
+                    "🧩 [#{}] Pragma's ExEx: Error while processing dispatch transaction: {:?}",
+                    block_number,
+                    e
+                )
+            }
+        }
+
+        ctx.events.send(ExExEvent::FinishedHeight(block_number))?;
+    }
+    Ok(())
+}
+
+/// Number of feeds in a feed ids list.
+fn feed_count(feed_ids: &[Felt]) -> f64 {
+    feed_ids.len().saturating_sub(1) as f64
+}
+
+/// Persists the feed ids. Failing to do so only means they are requeried from the registry on restart.
+fn save_feed_ids(ctx: &ExExContext, feeds: &FeedIdsCache) {
+    if let Err(e) = feeds.save(&ctx.backend) {
+        log::error!("🧩 Pragma's ExEx: Error while persisting feed IDs: {:?}", e);
+    }
+}
+
+/// Update the feed ids list if necessary, and returns whether it changed.
+/// It means:
+///   * if the feed id list is empty,
+///   * if we find the event [NewFeedId] or [RemovedFeedId] in the block's events.
+async fn update_feed_ids_if_necessary(
+    starknet: &Arc<Starknet>,
+    config: &PragmaDispatchConfig,
+    receipts: &[mp_receipt::TransactionReceipt],
+    block_number: u64,
+    feeds: &mut FeedIdsCache,
+) -> anyhow::Result<bool> {
+    // If the list is empty, it may be because the contract wasn't deployed before.
+    // Requery.
+    if feeds.feed_ids == *EMPTY_FEEDS {
+        feeds.feed_ids = get_feed_ids_from_registry(starknet, config).await?;
+        feeds.block_number = Some(block_number);
+        log::info!("🧩 [#{}] Pragma's ExEx: Refreshed all feeds. Total feeds: {}", block_number, feeds.feed_ids[0]);
+        return Ok(feeds.feed_ids != *EMPTY_FEEDS);
+    }
+    // Events already accounted for, e.g. when replaying blocks after a restart.
+    if feeds.includes(block_number) {
+        return Ok(false);
+    }
+    feeds.block_number = Some(block_number);
+
+    let feed_ids = &mut feeds.feed_ids;
+    let mut changed = false;
+    for receipt in receipts {
+        if let mp_receipt::TransactionReceipt::Invoke(invoke_receipt) = receipt {
+            for event in &invoke_receipt.events {
+                if event.from_address != config.feeds_registry_address {
+                    continue;
+                }
+                if event.keys.is_empty() || event.data.len() != 2 {
+                    continue;
+                }
+                let selector = event.keys[0];
+                let feed_id = event.data[1];
+                if selector == config.new_feed_id_selector {
+                    if !feed_ids.contains(&feed_id) {
+                        feed_ids.push(feed_id);
+                        feed_ids[0] += Felt::ONE;
+                        changed = true;
+                        log::info!(
+                            "🧩 [#{}] Pragma's ExEx: Added new feed ID \"0x{:x}\". Total feeds: {}",
+                            block_number,
+                            feed_id,
+                            feed_ids[0]
+                        );
+                    }
+                } else if selector == config.removed_feed_id_selector {
+                    if let Some(pos) = feed_ids.iter().position(|x| *x == feed_id) {
+                        feed_ids.remove(pos);
+                        feed_ids[0] -= Felt::ONE;
+                        changed = true;
+                        log::info!(
+                            "🧩 [#{}] Pragma's ExEx: Removed feed ID \"0x{:x}\". Total feeds: {}",
+                            block_number,
+                            feed_id,
+                            feed_ids[0]
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Follows the pending dispatches in a new block: records the ones whose transactions are all included, and escalates
+/// the fee of the transactions still not included after `fee_escalation_blocks` blocks, giving up on them once their
+/// fee is at the cap.
+async fn track_pending_dispatches(
+    ctx: &ExExContext,
+    config: &PragmaDispatchConfig,
+    accounts: &mut AccountPool,
+    metrics: &PragmaDispatchMetrics,
+    pending_dispatches: &mut Vec<PendingDispatch>,
+    receipts: &[mp_receipt::TransactionReceipt],
+    block_number: u64,
+) {
+    for mut dispatch in mem::take(pending_dispatches) {
+        dispatch.remove_included(receipts);
+        if dispatch.is_included() {
+            match dispatch.reverted {
+                Some(reason) => {
+                    metrics.failed(FailureReason::Reverted);
+                    log::error!(
+                        "🧩 [#{}] Pragma's ExEx: Dispatch of block #{} failed: {}",
+                        block_number,
+                        dispatch.block_number,
+                        reason
+                    );
+                }
+                None => {
+                    log::info!(
+                        "🧩 [#{}] Pragma's ExEx: Dispatch of block #{} included.",
+                        block_number,
+                        dispatch.block_number
+                    );
+                    metrics.succeeded(dispatch.block_number, dispatch.started_at);
+                }
+            }
+            continue;
+        }
+
+        match escalate_overdue_transactions(&ctx.starknet, config, accounts, &mut dispatch, block_number).await {
+            Ok(()) => pending_dispatches.push(dispatch),
+            Err(e) => {
+                metrics.failed(FailureReason::of(&e));
+                log::error!(
+                    "🧩 [#{}] Pragma's ExEx: Dispatch of block #{} failed: {:?}",
+                    block_number,
+                    dispatch.block_number,
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Create the Dispatch txs and sends them, splitting the feeds across transactions as configured.
+/// Returns the transactions to wait for, none on a dry run.
+async fn process_dispatch_transaction(
+    ctx: &ExExContext,
+    config: &PragmaDispatchConfig,
+    accounts: &mut AccountPool,
+    block_number: u64,
+    feed_ids: &[Felt],
+) -> anyhow::Result<Vec<PendingTransaction>> {
+    if config.dry_run {
+        let PooledAccount { account, nonces } = accounts.next();
+        simulate_dispatch_txs(&ctx.starknet, config, account, nonces, block_number, feed_ids).await?;
+        return Ok(vec![]);
+    }
+
+    // All the transactions are submitted at once, so that they can land in the same block.
+    // The batches are spread over the accounts, and go to the next account when one cannot send them.
+    let mut pending_transactions = Vec::new();
+    for batch in split_feed_ids(feed_ids, config.max_feeds_per_transaction) {
+        let mut tried_accounts = 1;
+        let pending = loop {
+            let account_index = accounts.next_index();
+            let PooledAccount { account, nonces } = accounts.get(account_index);
+            let address = account.address;
+            let err = match submit_dispatch_tx(&ctx.starknet, config, account, nonces, &batch, block_number).await {
+                Ok((transaction_hash, nonce, fee_factor)) => {
+                    break PendingTransaction {
+                        transaction_hashes: vec![transaction_hash],
+                        account_index,
+                        nonce,
+                        fee_factor,
+                        feed_ids: batch,
+                        escalate_at: block_number + config.fee_escalation_blocks,
+                    }
+                }
+                Err(err) => err,
+            };
+            let unusable = matches!(FailureReason::of(&err), FailureReason::Rejected | FailureReason::RetriesExhausted);
+            if !unusable || tried_accounts >= accounts.len() {
+                return Err(err);
+            }
+            log::warn!(
+                "🧩 [#{}] Pragma's ExEx: Account 0x{:x} could not dispatch, trying the next account: {:#}",
+                block_number,
+                address,
+                err
+            );
+            tried_accounts += 1;
+        };
+        pending_transactions.push(pending);
+    }
+
+    Ok(pending_transactions)
+}
+
+/// Resubmits the transactions of a dispatch that are overdue with a higher fee, and fails when one of them is overdue
+/// with its fee already at the cap.
+async fn escalate_overdue_transactions(
+    starknet: &Arc<Starknet>,
+    config: &PragmaDispatchConfig,
+    accounts: &mut AccountPool,
+    dispatch: &mut PendingDispatch,
+    block_number: u64,
+) -> anyhow::Result<()> {
+    for pending in dispatch.transactions.iter_mut().filter(|pending| block_number >= pending.escalate_at) {
+        let PooledAccount { account, nonces } = accounts.get(pending.account_index);
+        if config.escalated_fee_factor(pending.fee_factor).is_none() {
+            // The transaction may have been dropped, leaving a gap in the nonces of the account.
+            nonces.reset();
+            return Err(anyhow!(
+                "Transaction with nonce {} still not included at block #{}",
+                pending.nonce,
+                block_number
+            )
+            .context(FailureReason::TransactionRejected));
+        }
+        escalate_dispatch_tx(starknet, config, account, pending, block_number).await?;
+        pending.escalate_at = block_number + config.fee_escalation_blocks;
+    }
+    Ok(())
+}
+
+/// Resubmits a dispatch transaction that was not included in time with the same nonce and a higher fee, replacing
+/// the previous one in the mempool.
+async fn escalate_dispatch_tx(
+    starknet: &Arc<Starknet>,
+    config: &PragmaDispatchConfig,
+    account: &DispatchAccount,
+    pending: &mut PendingTransaction,
+    block_number: u64,
+) -> anyhow::Result<()> {
+    let fee_factor = config.escalated_fee_factor(pending.fee_factor).context("The fee is already at its cap")?;
+    log::warn!(
+        "🧩 [#{}] Pragma's ExEx: Transaction with nonce {} not included after {} blocks, resubmitting it with {:.2}x the fee",
+        block_number,
+        pending.nonce,
+        config.fee_escalation_blocks,
+        fee_factor
+    );
+    // Recorded even when the replacement is not accepted, so that the escalation ends at the cap.
+    pending.fee_factor = fee_factor;
+
+    let (dispatch_tx, _) =
+        create_dispatch_tx(starknet, config, account, &pending.feed_ids, pending.nonce, fee_factor).await?;
+    match starknet.add_invoke_transaction(dispatch_tx).await {
+        Ok(invoke_result) => pending.transaction_hashes.push(invoke_result.transaction_hash),
+        Err(err) => log::warn!(
+            "🧩 [#{}] Pragma's ExEx: Could not replace the transaction with nonce {}, still waiting for it: {}",
+            block_number,
+            pending.nonce,
+            err
+        ),
+    }
+    Ok(())
+}
+
+/// Simulates the Dispatch TXs instead of sending them, and logs their expected outcome.
+async fn simulate_dispatch_txs(
+    starknet: &Arc<Starknet>,
+    config: &PragmaDispatchConfig,
+    account: &DispatchAccount,
+    nonces: &NonceManager,
+    block_number: u64,
+    feed_ids: &[Felt],
+) -> anyhow::Result<()> {
+    let mut nonce = nonces.next(starknet, account.address)?;
+    let mut transactions = Vec::new();
+    for batch in split_feed_ids(feed_ids, config.max_feeds_per_transaction) {
+        let (dispatch_tx, _) = create_dispatch_tx(starknet, config, account, &batch, nonce, 1.0).await?;
+        transactions.push(BroadcastedTransaction::Invoke(dispatch_tx));
+        nonce += Felt::ONE;
+    }
+
+    let simulated = starknet
+        .simulate_transactions(PENDING_BLOCK, transactions, vec![])
+        .await
+        .context("Simulating the dispatch transactions")?;
+    for SimulatedTransaction { transaction_trace, fee_estimation } in simulated {
+        if let TransactionTrace::Invoke(InvokeTransactionTrace {
+            execute_invocation: ExecuteInvocation::Reverted(RevertedInvocation { revert_reason }),
+            ..
+        }) = transaction_trace
+        {
+            return Err(anyhow!("Simulated transaction reverted: {revert_reason}").context(FailureReason::Reverted));
+        }
+        log::info!(
+            "🧩 [#{}] Pragma's ExEx: Dry run: dispatch transaction would succeed, paying {} ({:?})",
+            block_number,
+            fee_estimation.overall_fee,
+            fee_estimation.unit
+        );
+    }
+
+    Ok(())
+}
+
+/// Creates & Invoke the Dispatch TX, and returns its hash, along with the nonce and fee multiplier it was sent with.
+///
+/// Transactions the mempool does not accept are resubmitted with a backoff, re-signed with a fresh nonce when the
+/// nonce was the issue, or with an escalated fee when it was insufficient.
+async fn submit_dispatch_tx(
+    starknet: &Arc<Starknet>,
+    config: &PragmaDispatchConfig,
+    account: &DispatchAccount,
+    nonces: &mut NonceManager,
+    feed_ids: &[Felt],
+    block_number: u64,
+) -> anyhow::Result<(Felt, Felt, f64)> {
+    let mut retry_interval = config.retry_interval;
+    let mut retries = 0;
+    let mut fee_factor = 1.0;
+    loop {
+        let nonce = nonces.next(starknet, account.address)?;
+        let (dispatch_tx, transaction_hash) =
+            create_dispatch_tx(starknet, config, account, feed_ids, nonce, fee_factor).await?;
+        log::info!("🧩 [#{}] Pragma's ExEx: Adding dispatch transaction with nonce {}...", block_number, nonce);
+        let err = match starknet.add_invoke_transaction(dispatch_tx).await {
+            Ok(invoke_result) => {
+                nonces.submitted(nonce);
+                return Ok((invoke_result.transaction_hash, nonce, fee_factor));
+            }
+            Err(err) => err,
+        };
+
+        match SubmissionFailure::classify(&err) {
+            SubmissionFailure::Duplicate => {
+                log::debug!("🧩 [#{}] Pragma's ExEx: Dispatch transaction already in the mempool", block_number);
+                nonces.submitted(nonce);
+                return Ok((transaction_hash, nonce, fee_factor));
+            }
+            SubmissionFailure::Rejected => return Err(anyhow!("{err}").context(FailureReason::Rejected)),
+            SubmissionFailure::Fee if config.escalated_fee_factor(fee_factor).is_none() => {
+                return Err(anyhow!("{err}").context(FailureReason::Rejected))
+            }
+            _ if retries >= config.max_submission_retries => {
+                return Err(anyhow!("Still not accepted after {retries} retries: {err}")
+                    .context(FailureReason::RetriesExhausted));
+            }
+            SubmissionFailure::Nonce => {
+                log::warn!(
+                    "🧩 [#{}] Pragma's ExEx: Nonce {} not accepted, resyncing it and retrying in {:?}: {}",
+                    block_number,
+                    nonce,
+                    retry_interval,
+                    err
+                );
+                nonces.reset();
+            }
+            SubmissionFailure::Fee => {
+                fee_factor = config.escalated_fee_factor(fee_factor).unwrap_or(fee_factor);
+                log::warn!(
+                    "🧩 [#{}] Pragma's ExEx: Insufficient fee, retrying in {:?} with {:.2}x the fee: {}",
+                    block_number,
+                    retry_interval,
+                    fee_factor,
+                    err
+                );
+            }
+            SubmissionFailure::Transient => {
+                log::warn!(
+                    "🧩 [#{}] Pragma's ExEx: Failed to add the dispatch transaction, retrying in {:?}: {}",
+                    block_number,
+                    retry_interval,
+                    err
+                );
+            }
+        }
+        sleep(retry_interval).await;
+        retry_interval = retry_interval.saturating_mul(2);
+        retries += 1;
+    }
+}
+
+/// Creates a new Dispatch transaction, and returns it along with its hash.
+/// The transaction will be signed by the dispatch account.
+async fn create_dispatch_tx(
+    starknet: &Arc<Starknet>,
+    config: &PragmaDispatchConfig,
+    account: &DispatchAccount,
+    feed_ids: &[Felt],
+    nonce: Felt,
+    fee_factor: f64,
+) -> anyhow::Result<(BroadcastedInvokeTransaction, Felt)> {
+    let calldata = Multicall::default()
+        .with(Call { to: config.dispatcher_address, selector: Selector::from("dispatch"), calldata: feed_ids.to_vec() })
+        .flatten()
+        .collect();
+    let tx = match config.transaction_version {
+        TransactionVersion::V1 => BroadcastedInvokeTransaction::V1(BroadcastedInvokeTransactionV1 {
+            sender_address: account.address,
+            calldata,
+            max_fee: scale_fee(config.max_fee, fee_factor)?,
+            signature: vec![], // This will get filled below
+            nonce,
+            is_query: false,
+        }),
+        TransactionVersion::V3 => {
+            let mut tx = BroadcastedInvokeTransactionV3 {
+                sender_address: account.address,
+                calldata,
+                signature: vec![], // This will get filled below
+                nonce,
+                resource_bounds: ResourceBoundsMapping {
+                    l1_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+                    l2_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+                },
+                tip: 0,
+                paymaster_data: vec![],
+                account_deployment_data: vec![],
+                nonce_data_availability_mode: DataAvailabilityMode::L1,
+                fee_data_availability_mode: DataAvailabilityMode::L1,
+                is_query: false,
+            };
+            tx.resource_bounds =
+                estimate_resource_bounds(starknet, &tx, config.fee_estimate_multiplier * fee_factor).await?;
+            BroadcastedInvokeTransaction::V3(tx)
+        }
+    };
+    sign_tx(starknet, account, tx).await
+}
+
+/// Multiplies a max fee.
+fn scale_fee(max_fee: Felt, factor: f64) -> anyhow::Result<Felt> {
+    let max_fee = felt_to_u128(&max_fee).context("Converting the max fee")?;
+    Ok(Felt::from((max_fee as f64 * factor) as u128))
+}
+
+/// Resource bounds of a V3 transaction, from its estimated fee with a margin.
+///
+/// The data gas is paid as L1 gas: the L1 gas amount is bounded by the overall fee at the L1 gas price.
+async fn estimate_resource_bounds(
+    starknet: &Arc<Starknet>,
+    tx: &BroadcastedInvokeTransactionV3,
+    multiplier: f64,
+) -> anyhow::Result<ResourceBoundsMapping> {
+    let query = BroadcastedInvokeTransactionV3 { is_query: true, ..tx.clone() };
+    let estimate = starknet
+        .estimate_fee(
+            vec![BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(query))],
+            vec![SimulationFlagForEstimateFee::SkipValidate],
+            PENDING_BLOCK,
+        )
+        .await
+        .context("Estimating the fee of the dispatch transaction")?
+        .pop()
+        .context("No fee estimate for the dispatch transaction")?;
+
+    let gas_price = felt_to_u128(&estimate.gas_price).context("Converting the gas price")?;
+    let overall_fee = felt_to_u128(&estimate.overall_fee).context("Converting the overall fee")?;
+    let max_amount = overall_fee.div_ceil(gas_price.max(1));
+
+    Ok(ResourceBoundsMapping {
+        l1_gas: ResourceBounds {
+            max_amount: (max_amount as f64 * multiplier) as u64,
+            max_price_per_unit: (gas_price as f64 * multiplier) as u128,
+        },
+        l2_gas: ResourceBounds { max_amount: 0, max_price_per_unit: 0 },
+    })
+}
+
+/// Sign a transaction with the key of the dispatch account, and returns it along with its hash.
+async fn sign_tx(
+    starknet: &Arc<Starknet>,
+    account: &DispatchAccount,
+    mut tx: BroadcastedInvokeTransaction,
+) -> anyhow::Result<(BroadcastedInvokeTransaction, Felt)> {
+    let (blockifier_tx, _) = broadcasted_to_blockifier(
+        BroadcastedTransaction::Invoke(tx.clone()),
+        starknet.chain_config.chain_id.to_felt(),
+        starknet.chain_config.latest_protocol_version,
+    )?;
+
+    let transaction_hash = transaction_hash(&blockifier_tx);
+    let signature = account.signer.sign(&transaction_hash).await?;
+    let tx_signature = match &mut tx {
+        BroadcastedInvokeTransaction::V1(tx) => &mut tx.signature,
+        BroadcastedInvokeTransaction::V3(tx) => &mut tx.signature,
+    };
+    *tx_signature = vec![signature.r, signature.s];
+    Ok((tx, transaction_hash))
+}
+
+/// Retrieves the available feed ids from the Pragma Feeds Registry.
+async fn get_feed_ids_from_registry(
+    starknet: &Arc<Starknet>,
+    config: &PragmaDispatchConfig,
+) -> anyhow::Result<Vec<Felt>> {
+    let call = FunctionCall {
+        contract_address: config.feeds_registry_address,
+        entry_point_selector: Selector::from("get_all_feeds").into(),
+        calldata: vec![],
+    };
+    let feed_ids = starknet.call(call, PENDING_BLOCK).await?;
+    Ok(feed_ids)
+}
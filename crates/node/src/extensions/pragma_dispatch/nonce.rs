@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use jsonrpsee::types::ErrorObjectOwned;
+use mc_rpc::versions::v0_7_1::StarknetReadRpcApiV0_7_1Server;
+use mp_rpc::Starknet;
+use starknet_core::types::{BlockId, BlockTag, Felt};
+
+/// Tracks the nonce of the dispatch account.
+///
+/// The pending state lags behind the transactions still in the mempool, so the nonce of the last submitted
+/// transaction is remembered and the highest of the two is used.
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    next: Option<Felt>,
+}
+
+impl NonceManager {
+    /// Nonce of the next transaction of the account.
+    pub fn next(&self, starknet: &Arc<Starknet>, address: Felt) -> anyhow::Result<Felt> {
+        let chain_nonce = starknet.get_nonce(BlockId::Tag(BlockTag::Pending), address)?;
+        Ok(self.next.map_or(chain_nonce, |next| next.max(chain_nonce)))
+    }
+
+    /// Records that a transaction with this nonce got accepted by the mempool.
+    pub fn submitted(&mut self, nonce: Felt) {
+        self.next = Some(nonce + Felt::ONE);
+    }
+
+    /// Forgets the tracked nonce, to resync with the chain.
+    pub fn reset(&mut self) {
+        self.next = None;
+    }
+}
+
+/// Why a dispatch transaction was not accepted by the mempool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmissionFailure {
+    /// The nonce was already used, or is too far ahead: the transaction should be re-signed with a fresh nonce.
+    Nonce,
+    /// The same transaction is already in the mempool.
+    Duplicate,
+    /// The max fee or resource bounds do not cover the transaction fee.
+    Fee,
+    /// The transaction can never be accepted as is, e.g. the account cannot pay for it.
+    Rejected,
+    /// Any other error, which may go away when retrying.
+    Transient,
+}
+
+impl SubmissionFailure {
+    // Starknet RPC error codes.
+    const INVALID_TRANSACTION_NONCE: i32 = 52;
+    const INSUFFICIENT_MAX_FEE: i32 = 53;
+    const INSUFFICIENT_ACCOUNT_BALANCE: i32 = 54;
+    const VALIDATION_FAILURE: i32 = 55;
+    const NON_ACCOUNT: i32 = 58;
+    const DUPLICATE_TX: i32 = 59;
+    const UNSUPPORTED_TX_VERSION: i32 = 61;
+
+    pub fn classify(err: &ErrorObjectOwned) -> Self {
+        match err.code() {
+            Self::INVALID_TRANSACTION_NONCE => Self::Nonce,
+            // Nonce checks happen during the account validation.
+            Self::VALIDATION_FAILURE if Self::mentions_nonce(err) => Self::Nonce,
+            Self::DUPLICATE_TX => Self::Duplicate,
+            Self::INSUFFICIENT_MAX_FEE => Self::Fee,
+            Self::INSUFFICIENT_ACCOUNT_BALANCE
+            | Self::VALIDATION_FAILURE
+            | Self::NON_ACCOUNT
+            | Self::UNSUPPORTED_TX_VERSION => Self::Rejected,
+            _ => Self::Transient,
+        }
+    }
+
+    fn mentions_nonce(err: &ErrorObjectOwned) -> bool {
+        err.message().to_lowercase().contains("nonce")
+            || err.data().is_some_and(|data| data.get().to_lowercase().contains("nonce"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::ErrorObject;
+    use rstest::rstest;
+
+    fn error(code: i32, message: &str, data: Option<&str>) -> ErrorObjectOwned {
+        ErrorObject::owned(code, message, data)
+    }
+
+    #[rstest]
+    #[case::invalid_nonce(error(52, "Invalid transaction nonce", None), SubmissionFailure::Nonce)]
+    #[case::validation_nonce(
+        error(55, "Account validation failed", Some("Invalid transaction nonce of contract")),
+        SubmissionFailure::Nonce
+    )]
+    #[case::validation_nonce_message(error(55, "Nonce too old", None), SubmissionFailure::Nonce)]
+    #[case::duplicate(error(59, "A transaction with the same hash already exists", None), SubmissionFailure::Duplicate)]
+    #[case::fee(error(53, "Max fee is smaller than the minimal transaction cost", None), SubmissionFailure::Fee)]
+    #[case::balance(error(54, "Account balance is smaller than the max fee", None), SubmissionFailure::Rejected)]
+    #[case::validation(error(55, "Account validation failed", Some("Invalid signature")), SubmissionFailure::Rejected)]
+    #[case::non_account(error(58, "Sender address in not an account contract", None), SubmissionFailure::Rejected)]
+    #[case::version(error(61, "The transaction version is not supported", None), SubmissionFailure::Rejected)]
+    #[case::internal(error(-32603, "Internal error", None), SubmissionFailure::Transient)]
+    fn test_classify(#[case] err: ErrorObjectOwned, #[case] expected: SubmissionFailure) {
+        assert_eq!(SubmissionFailure::classify(&err), expected);
+    }
+}
@@ -0,0 +1,101 @@
+use std::time::Instant;
+
+use mp_receipt::{ExecutionResult, TransactionReceipt};
+use starknet_core::types::Felt;
+
+/// A dispatch transaction waiting to be included.
+pub struct PendingTransaction {
+    /// Hashes of the transaction and of its fee escalations. Any of them may be included, as a replacement is not
+    /// always accepted, and the replaced transaction may already be in the pending block.
+    pub transaction_hashes: Vec<Felt>,
+    /// Index of the sending account in the [`super::accounts::AccountPool`].
+    pub account_index: usize,
+    pub nonce: Felt,
+    /// Multiplier of the fee the transaction was last sent with.
+    pub fee_factor: f64,
+    pub feed_ids: Vec<Felt>,
+    /// Block from which the fee of the transaction is escalated, or the transaction given up on when its fee is at
+    /// the cap, if it is still not included.
+    pub escalate_at: u64,
+}
+
+/// The transactions of a dispatch, tracked in the following block notifications until they are all included.
+pub struct PendingDispatch {
+    /// Block the feeds were dispatched at.
+    pub block_number: u64,
+    pub started_at: Instant,
+    pub transactions: Vec<PendingTransaction>,
+    /// Revert reason of the first transaction that reverted.
+    pub reverted: Option<String>,
+}
+
+impl PendingDispatch {
+    /// Removes the transactions included in a block, recording whether they reverted.
+    pub fn remove_included(&mut self, receipts: &[TransactionReceipt]) {
+        for receipt in receipts {
+            let transaction_hash = receipt.transaction_hash();
+            let Some(index) =
+                self.transactions.iter().position(|pending| pending.transaction_hashes.contains(&transaction_hash))
+            else {
+                continue;
+            };
+            self.transactions.swap_remove(index);
+            if let ExecutionResult::Reverted { reason } = receipt.execution_result() {
+                self.reverted.get_or_insert(format!("Transaction 0x{transaction_hash:x} reverted: {reason}"));
+            }
+        }
+    }
+
+    /// Whether all the transactions are included.
+    pub fn is_included(&self) -> bool {
+        self.transactions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_receipt::InvokeTransactionReceipt;
+
+    fn pending(transaction_hashes: &[Felt]) -> PendingTransaction {
+        PendingTransaction {
+            transaction_hashes: transaction_hashes.to_vec(),
+            account_index: 0,
+            nonce: Felt::ZERO,
+            fee_factor: 1.0,
+            feed_ids: vec![Felt::ZERO],
+            escalate_at: 10,
+        }
+    }
+
+    fn receipt(transaction_hash: Felt, execution_result: ExecutionResult) -> TransactionReceipt {
+        TransactionReceipt::Invoke(InvokeTransactionReceipt {
+            transaction_hash,
+            execution_result,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_remove_included() {
+        let mut dispatch = PendingDispatch {
+            block_number: 1,
+            started_at: Instant::now(),
+            // The second transaction was escalated.
+            transactions: vec![pending(&[Felt::from(1)]), pending(&[Felt::from(2), Felt::from(3)])],
+            reverted: None,
+        };
+
+        dispatch.remove_included(&[receipt(Felt::from(7), ExecutionResult::Succeeded)]);
+        assert_eq!(dispatch.transactions.len(), 2);
+
+        dispatch.remove_included(&[receipt(Felt::from(1), ExecutionResult::Succeeded)]);
+        assert!(!dispatch.is_included());
+        assert_eq!(dispatch.reverted, None);
+
+        // The replaced transaction got included instead of its replacement.
+        dispatch.remove_included(&[receipt(Felt::from(2), ExecutionResult::Reverted { reason: "Oops".into() })]);
+        assert!(dispatch.is_included());
+        assert_eq!(dispatch.reverted.as_deref(), Some("Transaction 0x2 reverted: Oops"));
+    }
+}
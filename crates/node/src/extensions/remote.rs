@@ -0,0 +1,214 @@
+//! Remote ExEx: exposes the ExEx notifications over gRPC, so that extensions can be written in any language and run
+//! as separate processes, with their own lifecycle.
+//!
+//! The protocol is defined in `proto/exex.proto`. Notifications are buffered by the node while no client is
+//! subscribed, following the slow consumer policy of the ExEx manager.
+//!
+//! A notification is only acknowledged once the client reports a finished height covering it. Until then it is kept,
+//! and sent again to the next subscribed client, as it may have been lost with the connection.
+
+use std::collections::VecDeque;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::Context;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use mp_exex::{ExExContext, ExExEvent, ExExNotification, ExExNotifications};
+use serde::Deserialize;
+use starknet_api::block::BlockNumber;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Mutex;
+use tonic::{Request, Response, Status};
+
+mod proto {
+    tonic::include_proto!("madara.exex.v1");
+}
+
+use proto::remote_ex_ex_server::{RemoteExEx, RemoteExExServer};
+
+const DEFAULT_LISTEN_PORT: u16 = 9946;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RemoteExExConfig {
+    /// Address of the gRPC server.
+    #[serde(default = "default_listen_address")]
+    listen_address: SocketAddr,
+}
+
+fn default_listen_address() -> SocketAddr {
+    (Ipv4Addr::LOCALHOST, DEFAULT_LISTEN_PORT).into()
+}
+
+struct RemoteExExService {
+    /// Locked by the subscribed client, for as long as it stays connected.
+    notifications: Arc<Mutex<ExExNotifications>>,
+    /// Notifications sent to a client, and not acknowledged yet.
+    unacked: Arc<std::sync::Mutex<VecDeque<ExExNotification>>>,
+    events: UnboundedSender<ExExEvent>,
+}
+
+impl RemoteExExService {
+    fn new(notifications: ExExNotifications, events: UnboundedSender<ExExEvent>) -> Self {
+        Self { notifications: Arc::new(Mutex::new(notifications)), unacked: Default::default(), events }
+    }
+}
+
+/// Records a notification sent to the client. A revert discards the unacknowledged notifications of the reverted
+/// blocks, which the client does not need anymore.
+fn push_unacked(unacked: &mut VecDeque<ExExNotification>, notification: ExExNotification) {
+    if let ExExNotification::Reverted { to, .. } = notification {
+        unacked.retain(|unacked| unacked.block_number() <= to);
+    }
+    unacked.push_back(notification);
+}
+
+/// Discards the notifications acknowledged by a finished height.
+fn ack(unacked: &mut VecDeque<ExExNotification>, block_number: BlockNumber) {
+    while unacked.front().is_some_and(|notification| notification.block_number() <= block_number) {
+        unacked.pop_front();
+    }
+}
+
+#[tonic::async_trait]
+impl RemoteExEx for RemoteExExService {
+    type SubscribeStream = BoxStream<'static, Result<proto::Notification, Status>>;
+
+    async fn subscribe(
+        &self,
+        _request: Request<proto::SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let notifications = Arc::clone(&self.notifications)
+            .try_lock_owned()
+            .map_err(|_| Status::failed_precondition("Another client is already subscribed to the notifications"))?;
+        log::info!("🧩 Remote ExEx client subscribed");
+
+        let replay: Vec<_> = self.unacked.lock().expect("Poisoned lock").iter().cloned().collect();
+        let unacked = Arc::clone(&self.unacked);
+        let live = stream::unfold((notifications, unacked), |(mut notifications, unacked)| async move {
+            let notification = notifications.next().await?;
+            push_unacked(&mut unacked.lock().expect("Poisoned lock"), notification.clone());
+            Some((encode_notification(notification), (notifications, unacked)))
+        });
+        Ok(Response::new(stream::iter(replay).map(encode_notification).chain(live).boxed()))
+    }
+
+    async fn finished_height(
+        &self,
+        request: Request<proto::FinishedHeightRequest>,
+    ) -> Result<Response<proto::FinishedHeightResponse>, Status> {
+        let block_number = BlockNumber(request.into_inner().block_number);
+        ack(&mut self.unacked.lock().expect("Poisoned lock"), block_number);
+        self.events
+            .send(ExExEvent::FinishedHeight(block_number))
+            .map_err(|_| Status::unavailable("The node is shutting down"))?;
+        Ok(Response::new(proto::FinishedHeightResponse {}))
+    }
+}
+
+fn encode_json(value: &impl serde::Serialize) -> Result<Vec<u8>, Status> {
+    serde_json::to_vec(value).map_err(|err| Status::internal(format!("Encoding the notification: {err}")))
+}
+
+fn encode_notification(notification: ExExNotification) -> Result<proto::Notification, Status> {
+    use proto::notification::Kind;
+
+    let kind = match notification {
+        ExExNotification::BlockProduced { block, block_number, state_diff } => {
+            Kind::BlockProduced(proto::BlockProduced {
+                block_number: block_number.0,
+                block: encode_json(&block)?,
+                state_diff: state_diff.map(|state_diff| encode_json(&state_diff)).transpose()?,
+            })
+        }
+        ExExNotification::BlockSynced { block_number, block, state_diff } => Kind::BlockSynced(proto::BlockSynced {
+            block_number: block_number.0,
+            block: block.map(|block| encode_json(&block)).transpose()?,
+            state_diff: state_diff.map(|state_diff| encode_json(&state_diff)).transpose()?,
+        }),
+        ExExNotification::Reverted { from, to } => Kind::Reverted(proto::Reverted { from: from.0, to: to.0 }),
+    };
+    Ok(proto::Notification { kind: Some(kind) })
+}
+
+/// 🧩 Remote ExEx, serving the notifications to an external process over gRPC.
+pub async fn exex_remote(ctx: ExExContext) -> anyhow::Result<()> {
+    let config: RemoteExExConfig = ctx.config()?;
+    let ExExContext { notifications, events, .. } = ctx;
+
+    let service = RemoteExExService::new(notifications, events);
+
+    log::info!("🧩 Remote ExEx gRPC server listening on {}", config.listen_address);
+    tonic::transport::Server::builder()
+        .add_service(RemoteExExServer::new(service))
+        .serve(config.listen_address)
+        .await
+        .context("Running the remote ExEx gRPC server")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    fn reverted(from: u64, to: u64) -> ExExNotification {
+        ExExNotification::Reverted { from: BlockNumber(from), to: BlockNumber(to) }
+    }
+
+    fn synced(block_number: u64) -> ExExNotification {
+        ExExNotification::BlockSynced { block_number: BlockNumber(block_number), block: None, state_diff: None }
+    }
+
+    fn block_numbers(unacked: &VecDeque<ExExNotification>) -> Vec<u64> {
+        unacked.iter().map(|notification| notification.block_number().0).collect()
+    }
+
+    #[test]
+    fn test_ack() {
+        let mut unacked = VecDeque::new();
+        for block_number in 0..4 {
+            push_unacked(&mut unacked, synced(block_number));
+        }
+        ack(&mut unacked, BlockNumber(1));
+        assert_eq!(block_numbers(&unacked), [2, 3]);
+
+        // The notifications of the reverted blocks are not sent again.
+        push_unacked(&mut unacked, reverted(3, 2));
+        assert_eq!(block_numbers(&unacked), [2, 2]);
+        ack(&mut unacked, BlockNumber(2));
+        assert!(unacked.is_empty());
+    }
+
+    async fn next_block_number(stream: &mut <RemoteExExService as RemoteExEx>::SubscribeStream) -> u64 {
+        match stream.next().await.unwrap().unwrap().kind.unwrap() {
+            proto::notification::Kind::BlockSynced(block) => block.block_number,
+            kind => panic!("Unexpected notification {kind:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_replays_unacked_notifications() {
+        let (notifications_tx, notifications_rx) = mpsc::channel(16);
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let service = RemoteExExService::new(ExExNotifications::new(notifications_rx), events_tx);
+        for block_number in 0..3 {
+            notifications_tx.send(synced(block_number)).await.unwrap();
+        }
+
+        let mut stream = service.subscribe(Request::new(proto::SubscribeRequest {})).await.unwrap().into_inner();
+        assert_eq!(next_block_number(&mut stream).await, 0);
+        assert_eq!(next_block_number(&mut stream).await, 1);
+        // A single client can be subscribed at once.
+        assert!(service.subscribe(Request::new(proto::SubscribeRequest {})).await.is_err());
+
+        service.finished_height(Request::new(proto::FinishedHeightRequest { block_number: 0 })).await.unwrap();
+        assert!(matches!(events_rx.recv().await, Some(ExExEvent::FinishedHeight(BlockNumber(0)))));
+
+        // The client disconnects before acknowledging block #1.
+        drop(stream);
+        let mut stream = service.subscribe(Request::new(proto::SubscribeRequest {})).await.unwrap().into_inner();
+        assert_eq!(next_block_number(&mut stream).await, 1);
+        assert_eq!(next_block_number(&mut stream).await, 2);
+    }
+}
@@ -0,0 +1,172 @@
+//! ExEx running the Starknet OS (SNOS) over each block and storing the resulting Cairo PIE, the input of the proving
+//! of the block.
+//!
+//! SNOS pins its own versions of the Cairo VM and of the blockifier, so it runs as a separate program, such as
+//! `prove_block` from <https://github.com/keep-starknet-strange/snos>, which re-executes the block through the RPC of
+//! the node. The PIE of block `N` is stored as `{output_dir}/{N}.zip`. Blocks that already have a PIE are skipped, so
+//! that the ExEx can be restarted or backfilled without running SNOS twice.
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use futures::StreamExt;
+use mc_metrics::{
+    exponential_buckets, Counter, Gauge, Histogram, HistogramOpts, MetricsRegistry, PrometheusError, F64, U64,
+};
+use mp_exex::{ExExContext, ExExEvent, ExExNotification};
+use mp_utils::serde::deserialize_duration;
+use serde::Deserialize;
+use url::Url;
+
+use crate::cli::RPC_DEFAULT_PORT;
+
+/// Lines of the standard error of SNOS included in the errors.
+const STDERR_TAIL_LINES: usize = 20;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SnosConfig {
+    /// Program running SNOS over a block and writing its Cairo PIE, with its arguments. `{block_number}`, `{rpc_url}`
+    /// and `{output}` are replaced in the arguments.
+    command: Vec<String>,
+    /// RPC endpoint SNOS reads the blocks, the state and the storage proofs from.
+    #[serde(default = "default_rpc_url")]
+    rpc_url: Url,
+    /// Directory the PIEs are stored in.
+    output_dir: PathBuf,
+    /// SNOS is killed when it takes longer than this on a block, and the ExEx fails.
+    #[serde(default = "default_timeout", deserialize_with = "deserialize_duration")]
+    timeout: Duration,
+}
+
+fn default_rpc_url() -> Url {
+    format!("http://localhost:{RPC_DEFAULT_PORT}").parse().expect("Valid URL")
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(30 * 60)
+}
+
+impl SnosConfig {
+    fn pie_path(&self, block_number: u64) -> PathBuf {
+        self.output_dir.join(format!("{block_number}.zip"))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SnosMetrics {
+    runs: Counter<U64>,
+    failures: Counter<U64>,
+    duration: Histogram,
+    last_block: Gauge<F64>,
+}
+
+impl SnosMetrics {
+    /// Registers the metrics the first time, and returns the same ones when the ExEx is restarted.
+    fn get_or_register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        static METRICS: OnceLock<SnosMetrics> = OnceLock::new();
+        if let Some(metrics) = METRICS.get() {
+            return Ok(metrics.clone());
+        }
+        let metrics = Self::register(registry)?;
+        Ok(METRICS.get_or_init(|| metrics).clone())
+    }
+
+    fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            runs: registry.register(Counter::new("snos_runs", "Number of blocks SNOS was run over")?)?,
+            failures: registry.register(Counter::new("snos_failures", "Number of failed SNOS runs")?)?,
+            duration: registry.register(Histogram::with_opts(
+                HistogramOpts::new("snos_duration_seconds", "Time [s] taken by SNOS to generate the PIE of a block")
+                    .buckets(exponential_buckets(1.0, 2.0, 12)?),
+            )?)?,
+            last_block: registry
+                .register(Gauge::new("snos_last_block", "Number of the last block a Cairo PIE was generated for")?)?,
+        })
+    }
+}
+
+pub async fn exex_snos(mut ctx: ExExContext) -> anyhow::Result<()> {
+    let config: SnosConfig = ctx.config()?;
+    anyhow::ensure!(!config.command.is_empty(), "The SNOS command is empty");
+    let metrics = SnosMetrics::get_or_register(&ctx.metrics)?;
+    tokio::fs::create_dir_all(&config.output_dir)
+        .await
+        .with_context(|| format!("Creating the PIE directory {}", config.output_dir.display()))?;
+    log::info!("🧩 SNOS ExEx storing the Cairo PIEs in {}", config.output_dir.display());
+
+    while let Some(notification) = ctx.notifications.next().await {
+        let block_number = notification.block_number();
+        if let ExExNotification::Reverted { from, to } = notification {
+            for reverted in to.0 + 1..=from.0 {
+                remove_if_exists(&config.pie_path(reverted)).await?;
+            }
+        } else if !tokio::fs::try_exists(config.pie_path(block_number.0)).await? {
+            metrics.runs.inc();
+            let started = Instant::now();
+            if let Err(err) = run_snos(&config, block_number.0).await {
+                metrics.failures.inc();
+                return Err(err);
+            }
+            metrics.duration.observe(started.elapsed().as_secs_f64());
+            metrics.last_block.set(block_number.0 as f64);
+            log::debug!("Cairo PIE of block #{block_number} generated in {:?}", started.elapsed());
+        }
+        ctx.events.send(ExExEvent::FinishedHeight(block_number))?;
+    }
+    Ok(())
+}
+
+/// Runs SNOS over a block. The PIE is written to a temporary file first, so that a killed run leaves no partial PIE
+/// behind.
+async fn run_snos(config: &SnosConfig, block_number: u64) -> anyhow::Result<()> {
+    let pie_path = config.pie_path(block_number);
+    let tmp_path = pie_path.with_extension("zip.tmp");
+    let args: Vec<String> = config
+        .command
+        .iter()
+        .map(|arg| {
+            arg.replace("{block_number}", &block_number.to_string())
+                .replace("{rpc_url}", config.rpc_url.as_str())
+                .replace("{output}", &tmp_path.to_string_lossy())
+        })
+        .collect();
+    let (program, args) = args.split_first().expect("The command is not empty");
+
+    let child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Starting SNOS with `{program}`"))?;
+    let output = tokio::time::timeout(config.timeout, child.wait_with_output())
+        .await
+        .with_context(|| format!("SNOS did not finish block #{block_number} within {:?}", config.timeout))?
+        .context("Waiting for SNOS")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<_> = stderr.lines().collect();
+        let tail = lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n");
+        remove_if_exists(&tmp_path).await?;
+        anyhow::bail!("SNOS failed on block #{block_number} with {}:\n{tail}", output.status);
+    }
+    tokio::fs::rename(&tmp_path, &pie_path).await.with_context(|| {
+        format!("Moving the Cairo PIE of block #{block_number} from {}, where SNOS should write it", tmp_path.display())
+    })?;
+    Ok(())
+}
+
+async fn remove_if_exists(path: &Path) -> anyhow::Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("Removing {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,368 @@
+//! WASM execution extensions.
+//!
+//! Every `.wasm` file of the `--exex-wasm-dir` directory is loaded as an ExEx, identified by its file name, and
+//! runs sandboxed in wasmtime. Extensions can be shipped without recompiling the node, as long as they implement
+//! the ABI below.
+//!
+//! Each call into a module is given `--exex-wasm-fuel` units of fuel, roughly one per executed instruction. A module
+//! running out of fuel, e.g. stuck in a loop, traps and the call fails instead of stalling the node.
+//!
+//! # ABI (version 1)
+//!
+//! The module exports:
+//! - `memory`: its linear memory.
+//! - `madara_abi_version() -> i32`: the version of the ABI the module implements.
+//! - `madara_alloc(len: i32) -> i32`: allocates `len` bytes for the host to write into.
+//! - `madara_init(ptr: i32, len: i32) -> i32` (optional): receives the config section of the ExEx, JSON-encoded.
+//!   Returns 0 on success, any other value is an error.
+//! - `madara_on_notification(ptr: i32, len: i32) -> i32`: handles an [`ExExNotification`], JSON-encoded. Returns 0
+//!   when the notification was processed, any other value is an error.
+//!
+//! The host provides, in the `madara` import module:
+//! - `log(level: i32, ptr: i32, len: i32)`: logs an UTF-8 message, from level 1 (error) to 5 (trace).
+//! - `latest_block_number() -> i64`: the latest block number, -1 when there is no block yet.
+//! - `get_storage_at(contract_ptr: i32, key_ptr: i32, out_ptr: i32) -> i32`,
+//!   `get_nonce(contract_ptr: i32, out_ptr: i32) -> i32` and
+//!   `get_class_hash_at(contract_ptr: i32, out_ptr: i32) -> i32`: read-only queries on the latest state. Felts are
+//!   32 bytes big-endian. Returns 1 and writes the value at `out_ptr` when found, 0 when not found and -1 on error.
+//! - `submit_invoke_transaction(ptr: i32, len: i32) -> i32`: submits an invoke transaction, JSON-encoded as in
+//!   `starknet_addInvokeTransaction`. Transactions are submitted once the notification has been processed. Returns
+//!   0, or -1 when the transaction could not be decoded.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{ensure, Context};
+use futures::StreamExt;
+use mc_db::MadaraBackend;
+use mp_block::{BlockId, BlockTag};
+use mp_exex::{ExExContext, ExExEvent, ExExNotification};
+use starknet_core::types::{BroadcastedInvokeTransaction, Felt};
+use wasmtime::{Caller, Config, Engine, Extern, Linker, Memory, Module, Store, TypedFunc};
+
+/// Version of the ABI between the node and the WASM modules.
+const ABI_VERSION: i32 = 1;
+/// Import module of the host functions.
+const HOST_MODULE: &str = "madara";
+
+/// Engine running the WASM modules, metering their execution with fuel.
+pub fn wasm_engine() -> anyhow::Result<Engine> {
+    Engine::new(Config::new().consume_fuel(true)).context("Creating the WASM engine")
+}
+
+/// Compiles the `.wasm` files of the directory, returning them along with their ExEx id.
+pub fn load_wasm_modules(engine: &Engine, dir: &Path) -> anyhow::Result<Vec<(String, Module)>> {
+    let mut modules = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Reading WASM ExEx directory {}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension() != Some(OsStr::new("wasm")) {
+            continue;
+        }
+        let name = path.file_stem().context("WASM ExEx file has no name")?.to_string_lossy();
+        let module = Module::from_file(engine, &path).with_context(|| format!("Compiling {}", path.display()))?;
+        log::info!("🧩 Loaded WASM ExEx {name}");
+        modules.push((name.into_owned(), module));
+    }
+    Ok(modules)
+}
+
+struct HostState {
+    id: String,
+    backend: Arc<MadaraBackend>,
+    /// Transactions submitted by the module while processing the current notification.
+    submitted_txs: Vec<BroadcastedInvokeTransaction>,
+}
+
+/// An instantiated WASM module.
+pub struct WasmExEx {
+    store: Store<HostState>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    on_notification: TypedFunc<(i32, i32), i32>,
+    init: Option<TypedFunc<(i32, i32), i32>>,
+    /// Fuel given to each call into the module.
+    fuel: u64,
+}
+
+impl WasmExEx {
+    pub fn instantiate(
+        engine: &Engine,
+        module: &Module,
+        id: String,
+        backend: Arc<MadaraBackend>,
+        fuel: u64,
+    ) -> anyhow::Result<Self> {
+        let mut linker = Linker::new(engine);
+        add_host_functions(&mut linker)?;
+
+        let mut store = Store::new(engine, HostState { id, backend, submitted_txs: Vec::new() });
+        store.set_fuel(fuel)?;
+        let instance = linker.instantiate(&mut store, module).context("Instantiating the WASM module")?;
+
+        store.set_fuel(fuel)?;
+        let abi_version = instance
+            .get_typed_func::<(), i32>(&mut store, "madara_abi_version")
+            .context("Getting the `madara_abi_version` export")?
+            .call(&mut store, ())?;
+        ensure!(abi_version == ABI_VERSION, "Unsupported ABI version {abi_version}, the node implements {ABI_VERSION}");
+
+        let memory = instance.get_memory(&mut store, "memory").context("Getting the `memory` export")?;
+        let alloc = instance.get_typed_func(&mut store, "madara_alloc").context("Getting the `madara_alloc` export")?;
+        let on_notification = instance
+            .get_typed_func(&mut store, "madara_on_notification")
+            .context("Getting the `madara_on_notification` export")?;
+
+        let init = instance.get_typed_func(&mut store, "madara_init").ok();
+
+        Ok(Self { store, memory, alloc, on_notification, init, fuel })
+    }
+
+    /// Resets the fuel of the module before a call into it.
+    fn refuel(&mut self) -> anyhow::Result<()> {
+        self.store.set_fuel(self.fuel)
+    }
+
+    /// Hands its config to the module, when it expects one.
+    fn init(&mut self, config: &toml::Table) -> anyhow::Result<()> {
+        let Some(init) = self.init.clone() else { return Ok(()) };
+        self.refuel()?;
+        let (ptr, len) = self.write_bytes(&serde_json::to_vec(config)?)?;
+        let res = init.call(&mut self.store, (ptr, len))?;
+        ensure!(res == 0, "`madara_init` returned {res}");
+        Ok(())
+    }
+
+    /// Copies the bytes into memory allocated by the module.
+    fn write_bytes(&mut self, bytes: &[u8]) -> anyhow::Result<(i32, i32)> {
+        let len = i32::try_from(bytes.len()).context("Input too large")?;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        self.memory.write(&mut self.store, usize::try_from(ptr)?, bytes)?;
+        Ok((ptr, len))
+    }
+
+    /// Hands the notification to the module, and returns the transactions it submitted.
+    fn on_notification(
+        &mut self,
+        notification: &ExExNotification,
+    ) -> anyhow::Result<Vec<BroadcastedInvokeTransaction>> {
+        self.refuel()?;
+        let (ptr, len) = self.write_bytes(&serde_json::to_vec(notification)?)?;
+        let res = self.on_notification.call(&mut self.store, (ptr, len))?;
+        let submitted_txs = std::mem::take(&mut self.store.data_mut().submitted_txs);
+        ensure!(res == 0, "`madara_on_notification` returned {res}");
+        Ok(submitted_txs)
+    }
+}
+
+/// Runs a WASM module as an ExEx.
+pub async fn exex_wasm(
+    mut ctx: ExExContext,
+    engine: Engine,
+    module: Module,
+    id: String,
+    fuel: u64,
+) -> anyhow::Result<()> {
+    let mut exex = WasmExEx::instantiate(&engine, &module, id.clone(), Arc::clone(&ctx.backend), fuel)?;
+    exex.init(&ctx.config).with_context(|| format!("Initializing {id}"))?;
+
+    while let Some(notification) = ctx.notifications.next().await {
+        let block_number = notification.block_number();
+        // Modules run synchronously, and must not block the runtime.
+        let submitted_txs = tokio::task::block_in_place(|| exex.on_notification(&notification))
+            .with_context(|| format!("{id} failed to process the notification for block #{block_number}"))?;
+
+        for tx in submitted_txs {
+            if let Err(err) = ctx.starknet.add_transaction_provider.add_invoke_transaction(tx).await {
+                log::error!("🧩 [#{block_number}] {id}: Submitting a transaction failed: {err:?}");
+            }
+        }
+
+        if !matches!(notification, ExExNotification::Reverted { .. }) {
+            ctx.events.send(ExExEvent::FinishedHeight(block_number))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn memory(caller: &mut Caller<'_, HostState>) -> anyhow::Result<Memory> {
+    caller.get_export("memory").and_then(Extern::into_memory).context("The module does not export its `memory`")
+}
+
+fn read_bytes(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> anyhow::Result<Vec<u8>> {
+    let memory = memory(caller)?;
+    let mut bytes = vec![0u8; usize::try_from(len)?];
+    memory.read(&*caller, usize::try_from(ptr)?, &mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_felt(caller: &mut Caller<'_, HostState>, ptr: i32) -> anyhow::Result<Felt> {
+    Ok(Felt::from_bytes_be_slice(&read_bytes(caller, ptr, 32)?))
+}
+
+/// Runs a read-only query on the latest state, following the ABI return convention.
+fn felt_query(
+    caller: &mut Caller<'_, HostState>,
+    out_ptr: i32,
+    query: impl FnOnce(&MadaraBackend, &BlockId) -> anyhow::Result<Option<Felt>>,
+) -> anyhow::Result<i32> {
+    let backend = Arc::clone(&caller.data().backend);
+    match query(&backend, &BlockId::Tag(BlockTag::Latest)) {
+        Ok(Some(value)) => {
+            let memory = memory(caller)?;
+            memory.write(&mut *caller, usize::try_from(out_ptr)?, &value.to_bytes_be())?;
+            Ok(1)
+        }
+        Ok(None) => Ok(0),
+        Err(err) => {
+            log::warn!("🧩 {}: State query failed: {err:#}", caller.data().id);
+            Ok(-1)
+        }
+    }
+}
+
+fn add_host_functions(linker: &mut Linker<HostState>) -> anyhow::Result<()> {
+    linker.func_wrap(
+        HOST_MODULE,
+        "log",
+        |mut caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32| -> anyhow::Result<()> {
+            let message = read_bytes(&mut caller, ptr, len)?;
+            let level = match level {
+                1 => log::Level::Error,
+                2 => log::Level::Warn,
+                3 => log::Level::Info,
+                4 => log::Level::Debug,
+                _ => log::Level::Trace,
+            };
+            log::log!(level, "🧩 {}: {}", caller.data().id, String::from_utf8_lossy(&message));
+            Ok(())
+        },
+    )?;
+
+    linker.func_wrap(HOST_MODULE, "latest_block_number", |caller: Caller<'_, HostState>| -> anyhow::Result<i64> {
+        match caller.data().backend.get_latest_block_n()? {
+            Some(block_n) => Ok(i64::try_from(block_n)?),
+            None => Ok(-1),
+        }
+    })?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "get_storage_at",
+        |mut caller: Caller<'_, HostState>, contract_ptr: i32, key_ptr: i32, out_ptr: i32| -> anyhow::Result<i32> {
+            let contract_address = read_felt(&mut caller, contract_ptr)?;
+            let key = read_felt(&mut caller, key_ptr)?;
+            felt_query(&mut caller, out_ptr, |backend, block_id| {
+                Ok(backend.get_contract_storage_at(block_id, &contract_address, &key)?)
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "get_nonce",
+        |mut caller: Caller<'_, HostState>, contract_ptr: i32, out_ptr: i32| -> anyhow::Result<i32> {
+            let contract_address = read_felt(&mut caller, contract_ptr)?;
+            felt_query(&mut caller, out_ptr, |backend, block_id| {
+                Ok(backend.get_contract_nonce_at(block_id, &contract_address)?)
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "get_class_hash_at",
+        |mut caller: Caller<'_, HostState>, contract_ptr: i32, out_ptr: i32| -> anyhow::Result<i32> {
+            let contract_address = read_felt(&mut caller, contract_ptr)?;
+            felt_query(&mut caller, out_ptr, |backend, block_id| {
+                Ok(backend.get_contract_class_hash_at(block_id, &contract_address)?)
+            })
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "submit_invoke_transaction",
+        |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| -> anyhow::Result<i32> {
+            let bytes = read_bytes(&mut caller, ptr, len)?;
+            match serde_json::from_slice(&bytes) {
+                Ok(tx) => {
+                    caller.data_mut().submitted_txs.push(tx);
+                    Ok(0)
+                }
+                Err(err) => {
+                    log::warn!("🧩 {}: Invalid invoke transaction: {err}", caller.data().id);
+                    Ok(-1)
+                }
+            }
+        },
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use starknet_api::block::BlockNumber;
+
+    const FUEL: u64 = 1_000;
+
+    /// Implements the ABI with a bump allocator. `madara_init` loops forever, and `madara_on_notification` returns 0.
+    const MODULE: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "madara_abi_version") (result i32) i32.const 1)
+            (func (export "madara_alloc") (param $len i32) (result i32) (local $ptr i32)
+                global.get $next
+                local.set $ptr
+                global.get $next
+                local.get $len
+                i32.add
+                global.set $next
+                local.get $ptr)
+            (func (export "madara_init") (param i32 i32) (result i32)
+                (loop $forever br $forever)
+                i32.const 0)
+            (func (export "madara_on_notification") (param i32 i32) (result i32)
+                i32.const 0))
+    "#;
+
+    fn instantiate(wat: &str) -> anyhow::Result<WasmExEx> {
+        let engine = wasm_engine()?;
+        let module = Module::new(&engine, wat)?;
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        WasmExEx::instantiate(&engine, &module, "test".into(), backend, FUEL)
+    }
+
+    #[test]
+    fn test_abi_version_mismatch() {
+        let res = instantiate(&MODULE.replace("(result i32) i32.const 1)", "(result i32) i32.const 2)"));
+        assert!(format!("{:#}", res.err().unwrap()).contains("Unsupported ABI version 2"));
+    }
+
+    #[test]
+    fn test_out_of_fuel() {
+        let mut exex = instantiate(MODULE).unwrap();
+        let err = exex.init(&toml::Table::new()).unwrap_err();
+        assert_eq!(err.downcast_ref::<wasmtime::Trap>(), Some(&wasmtime::Trap::OutOfFuel));
+
+        // The module gets its fuel back for the next call.
+        let notification = ExExNotification::Reverted { from: BlockNumber(2), to: BlockNumber(1) };
+        assert!(exex.on_notification(&notification).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fuel_is_reset_between_calls() {
+        let mut exex = instantiate(MODULE).unwrap();
+        let notification = ExExNotification::Reverted { from: BlockNumber(2), to: BlockNumber(1) };
+        // The module runs out of fuel within these calls if its fuel is not reset.
+        for _ in 0..FUEL {
+            exex.on_notification(&notification).unwrap();
+        }
+        assert!(exex.store.get_fuel().unwrap() > 0);
+    }
+}
@@ -4,38 +4,60 @@
 
 mod cli;
 mod extensions;
+mod preflight;
 mod service;
 mod util;
 
 use anyhow::Context;
-use clap::Parser;
 use extensions::madara_exexs;
 use mc_block_import::BlockImporter;
 use mp_rpc::{AddTransactionProvider, Starknet};
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 
-use cli::{NetworkType, RunCmd};
+use cli::{Cli, NetworkType};
+use mc_db::fork_db::ForkedState;
 use mc_db::DatabaseService;
-use mc_mempool::{GasPriceProvider, L1DataProvider, Mempool};
+use mc_devnet::{DevnetDump, ForkedNetwork};
+use mc_gateway::server::gossip::{GossipAddTxProvider, GossipReceiver};
+use mc_mempool::{BlockClock, BuilderHandle, DevnetHandle, GasPriceProvider, L1DataProvider, Mempool};
 use mc_metrics::MetricsService;
-use mc_rpc::providers::{ForwardToProvider, MempoolAddTxProvider};
+use mc_rpc::devnet::Devnet;
+use mc_rpc::providers::{ForwardToProvider, HaltableAddTxProvider, MempoolAddTxProvider};
 use mc_telemetry::{SysInfo, TelemetryService};
 use mp_convert::ToFelt;
-use mp_exex::ExExLauncher;
-use mp_utils::service::{Service, ServiceGroup};
-use service::{BlockProductionService, GatewayService, L1SyncService, RpcService, SyncService};
+use mp_exex::{ExExLauncher, ExExMetrics};
+use mp_utils::service::{Service, ServiceGroup, ServiceStatuses};
+use service::{
+    BlockProductionService, GatewayService, L1SyncService, NodeMetricsService, ProverService, ReloadHandle,
+    ReloadService, RpcService, Standby, SyncService, SystemdService,
+};
 use starknet_providers::SequencerGatewayProvider;
 
+/// jemalloc reports the heap statistics served by `--prometheus-profiling`.
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
 const GREET_IMPL_NAME: &str = "Madara";
 const GREET_SUPPORT_URL: &str = "https://github.com/madara-alliance/madara/issues";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     crate::util::setup_logging()?;
-    crate::util::setup_rayon_threadpool()?;
-    crate::util::raise_fdlimit();
+    let fd_limit = crate::util::raise_fdlimit();
 
-    let mut run_cmd: RunCmd = RunCmd::parse();
+    let cli = Cli::parse_with_config_file()?;
+    if let Some(command) = cli.command {
+        return command.run();
+    }
+    let mut run_cmd = cli.run;
+    crate::util::setup_rayon_threadpools(&run_cmd.thread_pool_params)?;
+    if run_cmd.tokio_console {
+        crate::util::setup_tokio_console(run_cmd.tokio_console_addr)?;
+    }
+    crate::util::set_log_output(run_cmd.logging_params.log_output()?);
+    crate::util::set_log_filter(&run_cmd.logging_params.log_filter);
 
     // If it's a sequencer or a devnet we set the mandatory chain config. If it's a full node we set the chain config from the network or the custom chain config.
     let chain_config = if run_cmd.is_sequencer() {
@@ -72,8 +94,10 @@ async fn main() -> anyhow::Result<()> {
         run_cmd.prometheus_params.prometheus_external,
         run_cmd.prometheus_params.prometheus_port,
     )
-    .context("Initializing prometheus metrics service")?;
+    .context("Initializing prometheus metrics service")?
+    .with_profiling(run_cmd.prometheus_params.prometheus_profiling);
 
+    preflight::check_environment(&run_cmd, fd_limit)?;
     let db_service = DatabaseService::new(
         &run_cmd.db_params.base_path,
         run_cmd.db_params.backup_dir.clone(),
@@ -83,6 +107,23 @@ async fn main() -> anyhow::Result<()> {
     )
     .await
     .context("Initializing db service")?;
+    preflight::check_node(&run_cmd, &chain_config, db_service.backend()).await?;
+
+    if run_cmd.sync_params.no_global_tries && run_cmd.is_sequencer() {
+        anyhow::bail!("`--no-global-tries` is only available to full nodes, block production computes the state roots");
+    }
+    db_service
+        .backend()
+        .set_global_tries_disabled(run_cmd.sync_params.no_global_tries)
+        .context("Setting the global tries mode")?;
+
+    if let Some(fork_url) = &run_cmd.block_production_params.fork_url {
+        let fork_block_n = run_cmd.block_production_params.fork_block.or(db_service.backend().get_fork_block_n()?);
+        let forked_network =
+            ForkedNetwork::connect(fork_url.clone(), fork_block_n).await.context("Connecting to the fork network")?;
+        log::info!("🍴 Forking {} at block #{}", fork_url, forked_network.fork_block_n());
+        db_service.backend().set_forked_state(Arc::new(forked_network)).context("Forking the network")?;
+    }
 
     let importer = Arc::new(
         BlockImporter::new(
@@ -102,94 +143,182 @@ async fn main() -> anyhow::Result<()> {
         run_cmd.l1_sync_params.sync_l1_disabled = true;
         run_cmd.l1_sync_params.gas_price_sync_disabled = true;
     }
+    let reload_handle = ReloadHandle::new(&run_cmd, &l1_gas_setter).context("Initializing the reload handle")?;
 
+    let writes_halted = Arc::new(AtomicBool::new(false));
+    let block_clock = BlockClock::default();
+    let (devnet_handle, devnet_commands) = DevnetHandle::new();
+    let exex_statuses = ServiceStatuses::default();
+    let service_statuses = ServiceStatuses::default();
+    let node_metrics_service = NodeMetricsService::new(
+        Arc::clone(db_service.backend()),
+        l1_gas_setter.clone(),
+        exex_statuses.clone(),
+        prometheus_service.registry(),
+    )?;
     let l1_service = L1SyncService::new(
         &run_cmd.l1_sync_params,
+        &run_cmd.settlement_params,
         &db_service,
         prometheus_service.registry(),
         l1_gas_setter,
-        chain_config.chain_id.clone(),
-        chain_config.eth_core_contract_address,
+        &chain_config,
         run_cmd.is_sequencer(),
+        Arc::clone(&writes_halted),
     )
     .await
     .context("Initializing the l1 sync service")?;
 
     // Block provider startup.
     // `rpc_add_txs_method_provider` is a trait object that tells the RPC task where to put the transactions when using the Write endpoints.
-    let (block_provider_service, rpc_add_txs_method_provider): (_, Arc<dyn AddTransactionProvider>) = match run_cmd
-        .is_sequencer()
-    {
-        // Block production service. (authority)
-        true => {
-            let mempool = Arc::new(Mempool::new(Arc::clone(db_service.backend()), Arc::clone(&l1_data_provider)));
-            let mempool_provider = Arc::new(MempoolAddTxProvider::new(Arc::clone(&mempool)));
-            let starknet = Arc::new(Starknet::new(
-                Arc::clone(db_service.backend()),
-                chain_config.clone(),
-                mempool_provider.clone(),
-            ));
-
-            // Launch the ExEx manager for configured ExExs - if any.
-            let exex_manager = ExExLauncher::new(madara_exexs(), starknet).launch().await?;
-
-            let block_production_service = BlockProductionService::new(
-                &run_cmd.block_production_params,
-                &db_service,
-                Arc::clone(&mempool),
-                importer,
-                Arc::clone(&l1_data_provider),
-                run_cmd.devnet,
-                exex_manager,
-                prometheus_service.registry(),
-                telemetry_service.new_handle(),
-            )?;
-
-            (ServiceGroup::default().with(block_production_service), mempool_provider)
-        }
-        // Block sync service. (full node)
-        false => {
-            // TODO(rate-limit): we may get rate limited with this unconfigured provider?
-            let gateway_provider = Arc::new(ForwardToProvider::new(SequencerGatewayProvider::new(
-                run_cmd
-                    .network
-                    .context(
-                        "You should provide a `--network` argument to ensure you're syncing from the right gateway",
-                    )?
-                    .gateway(),
-                run_cmd
-                    .network
-                    .context("You should provide a `--network` argument to ensure you're syncing from the right FGW")?
-                    .feeder_gateway(),
-                chain_config.chain_id.to_felt(),
-            )));
-            let starknet = Arc::new(Starknet::new(
-                Arc::clone(db_service.backend()),
-                chain_config.clone(),
-                gateway_provider.clone(),
-            ));
-
-            // Launch the ExEx manager for configured ExExs - if any.
-            let exex_manager = ExExLauncher::new(madara_exexs(), starknet).launch().await?;
-
-            // Feeder gateway sync service.
-            let sync_service = SyncService::new(
-                &run_cmd.sync_params,
-                Arc::clone(&chain_config),
-                run_cmd
-                    .network
-                    .context("You should provide a `--network` argument to ensure you're syncing from the right FGW")?,
-                &db_service,
-                importer,
-                exex_manager,
-                telemetry_service.new_handle(),
-            )
-            .await
-            .context("Initializing sync service")?;
-
-            (ServiceGroup::default().with(sync_service), gateway_provider)
-        }
-    };
+    // `gossip_receiver` adds the transactions pushed by the other replicas of the sequencer to the local mempool.
+    // `block_builder` sends the blocks of the external block builder to the block production.
+    let mut gossip_receiver = None;
+    let mut block_builder = None;
+    let (block_provider_service, rpc_add_txs_method_provider): (Box<dyn Service>, Arc<dyn AddTransactionProvider>) =
+        match run_cmd.is_sequencer() {
+            // Block production service. (authority)
+            true => {
+                let mempool = Arc::new(
+                    Mempool::new(Arc::clone(db_service.backend()), Arc::clone(&l1_data_provider))
+                        .with_chain_id_mismatch_detection(run_cmd.block_production_params.detect_chain_id_mismatch),
+                );
+                let mempool_provider: Arc<dyn AddTransactionProvider> =
+                    Arc::new(MempoolAddTxProvider::new(Arc::clone(&mempool)));
+                let add_txs_provider: Arc<dyn AddTransactionProvider> = match run_cmd.gateway_params.gossip_config() {
+                    Some(config) => {
+                        log::info!("📡 Gossiping the mempool with {} peer(s)", config.peers.len());
+                        gossip_receiver =
+                            Some(GossipReceiver::new(config.secret.clone(), Arc::clone(&mempool_provider)));
+                        Arc::new(GossipAddTxProvider::new(mempool_provider, config))
+                    }
+                    None => mempool_provider,
+                };
+                let starknet = Arc::new(Starknet::new(
+                    Arc::clone(db_service.backend()),
+                    chain_config.clone(),
+                    Arc::clone(&add_txs_provider),
+                ));
+
+                // Launch the ExEx manager for configured ExExs - if any.
+                let exex_manager = ExExLauncher::new(
+                    madara_exexs(&run_cmd.exex_params)?,
+                    starknet,
+                    run_cmd.exex_params.manager_config(),
+                    ExExMetrics::register(prometheus_service.registry())?,
+                    exex_statuses.clone(),
+                    prometheus_service.registry().clone(),
+                )
+                .launch()
+                .await?;
+
+                let standby = run_cmd.ha_params.ha_config().map(|config| {
+                    log::info!("🗳️  High availability through the etcd lease {}", config.lease_key);
+                    Standby::new(
+                        config,
+                        Arc::clone(db_service.backend()),
+                        Arc::clone(&importer),
+                        run_cmd.sync_params.clone(),
+                        telemetry_service.new_handle(),
+                        reload_handle.pending_block_poll_interval(),
+                        exex_manager.clone(),
+                    )
+                });
+
+                let builder_blocks = run_cmd.block_production_params.external_block_builder.then(|| {
+                    let (handle, builder_blocks) = BuilderHandle::new(Arc::clone(db_service.backend()));
+                    block_builder = Some(handle);
+                    builder_blocks
+                });
+
+                let mut block_production_service = BlockProductionService::new(
+                    &run_cmd.block_production_params,
+                    &db_service,
+                    Arc::clone(&mempool),
+                    Arc::clone(&importer),
+                    Arc::clone(&l1_data_provider),
+                    run_cmd.devnet,
+                    block_clock.clone(),
+                    devnet_commands,
+                    builder_blocks,
+                    exex_manager,
+                    prometheus_service.registry(),
+                    telemetry_service.new_handle(),
+                )?;
+                if let Some(standby) = standby {
+                    block_production_service = block_production_service.with_high_availability(standby);
+                }
+
+                (Box::new(block_production_service), add_txs_provider)
+            }
+            // Block sync service. (full node)
+            false => {
+                if run_cmd.gateway_params.gossip_config().is_some() {
+                    anyhow::bail!("Mempool gossip is only available to sequencers");
+                }
+                if run_cmd.block_production_params.external_block_builder {
+                    anyhow::bail!("External block building is only available to sequencers");
+                }
+                // TODO(rate-limit): we may get rate limited with this unconfigured provider?
+                let gateway_provider = Arc::new(ForwardToProvider::new(SequencerGatewayProvider::new(
+                    run_cmd
+                        .network
+                        .context(
+                            "You should provide a `--network` argument to ensure you're syncing from the right gateway",
+                        )?
+                        .gateway(),
+                    run_cmd
+                        .network
+                        .context(
+                            "You should provide a `--network` argument to ensure you're syncing from the right FGW",
+                        )?
+                        .feeder_gateway(),
+                    chain_config.chain_id.to_felt(),
+                )));
+                let starknet = Arc::new(Starknet::new(
+                    Arc::clone(db_service.backend()),
+                    chain_config.clone(),
+                    gateway_provider.clone(),
+                ));
+
+                // Launch the ExEx manager for configured ExExs - if any.
+                let exex_manager = ExExLauncher::new(
+                    madara_exexs(&run_cmd.exex_params)?,
+                    starknet,
+                    run_cmd.exex_params.manager_config(),
+                    ExExMetrics::register(prometheus_service.registry())?,
+                    exex_statuses.clone(),
+                    prometheus_service.registry().clone(),
+                )
+                .launch()
+                .await?;
+
+                // Feeder gateway sync service.
+                let sync_service = SyncService::new(
+                    &run_cmd.sync_params,
+                    Arc::clone(&chain_config),
+                    run_cmd.network.context(
+                        "You should provide a `--network` argument to ensure you're syncing from the right FGW",
+                    )?,
+                    &db_service,
+                    Arc::clone(&importer),
+                    exex_manager,
+                    telemetry_service.new_handle(),
+                    reload_handle.pending_block_poll_interval(),
+                )
+                .await
+                .context("Initializing sync service")?;
+
+                (Box::new(sync_service), gateway_provider)
+            }
+        };
+
+    let rpc_add_txs_method_provider: Arc<dyn AddTransactionProvider> =
+        if run_cmd.l1_sync_params.halt_writes_on_state_root_mismatch {
+            Arc::new(HaltableAddTxProvider::new(rpc_add_txs_method_provider, writes_halted))
+        } else {
+            rpc_add_txs_method_provider
+        };
 
     let rpc_service = RpcService::new(
         &run_cmd.rpc_params,
@@ -197,23 +326,48 @@ async fn main() -> anyhow::Result<()> {
         Arc::clone(&chain_config),
         prometheus_service.registry(),
         Arc::clone(&rpc_add_txs_method_provider),
+        exex_statuses,
+        service_statuses.clone(),
+        run_cmd.devnet.then(|| Devnet::new(Arc::clone(db_service.backend()), block_clock, devnet_handle)),
+        block_builder,
+        importer,
+        &reload_handle,
     )
     .context("Initializing rpc service")?;
 
-    let gateway_service = GatewayService::new(&run_cmd.gateway_params, &db_service, rpc_add_txs_method_provider)
-        .await
-        .context("Initializing gateway service")?;
+    let gateway_service = GatewayService::new(
+        &run_cmd.gateway_params,
+        &db_service,
+        rpc_add_txs_method_provider,
+        gossip_receiver,
+        prometheus_service.registry(),
+    )
+    .await
+    .context("Initializing gateway service")?;
+
+    let prover_service = ProverService::new(&run_cmd.prover_params, &db_service, prometheus_service.registry())
+        .context("Initializing prover service")?;
 
     telemetry_service.send_connected(&node_name, node_version, &chain_config.chain_name, &sys_info);
 
-    let app = ServiceGroup::default()
+    let backend = Arc::clone(db_service.backend());
+    let mut app = ServiceGroup::default()
         .with(db_service)
         .with(l1_service)
         .with(block_provider_service)
         .with(rpc_service)
         .with(gateway_service)
+        .with(prover_service)
         .with(telemetry_service)
-        .with(prometheus_service);
+        .with(node_metrics_service)
+        .with(prometheus_service)
+        .with(ReloadService::new(reload_handle))
+        // Last, so that systemd is notified once every other service is started.
+        .with(SystemdService::from_env().context("Initializing systemd notifications")?)
+        .with_statuses(service_statuses);
+    for (name, policy) in run_cmd.service_params.restart_policies() {
+        app = app.with_restart_policy(name, policy);
+    }
 
     // Check if the devnet is running with the correct chain id.
     if run_cmd.devnet && chain_config.chain_id != NetworkType::Devnet.chain_id() {
@@ -223,10 +377,29 @@ async fn main() -> anyhow::Result<()> {
         } else {
             // This log is immediately flooded with devnet accounts and so this can be missed.
             // Should we add a delay here to make this clearly visisble?
-            log::warn!("You're running a devnet with the network config of {:?}. This means that devnet transactions can be replayed on the actual network.", run_cmd.network);
+            log::warn!("You're running a devnet with the network config of {:?}. This means that devnet transactions can be replayed on the actual network.", chain_config.chain_id);
         }
     }
 
-    app.start_and_drive_to_end().await?;
+    let shutdown_timeout = run_cmd.shutdown_timeout;
+    let res = tokio::select! {
+        res = app.start_and_drive_to_end() => res,
+        _ = async {
+            mp_utils::graceful_shutdown().await;
+            log::info!("🛑 Shutting down, waiting up to {shutdown_timeout:?} for the services to stop...");
+            tokio::time::sleep(shutdown_timeout).await;
+        } => {
+            log::warn!("Services did not stop within {shutdown_timeout:?}, exiting anyway");
+            Ok(())
+        }
+    };
+    // Tasks that are still running hold references to the backend, which is then not flushed on drop.
+    backend.maybe_flush(true).context("Flushing the database")?;
+    res?;
+
+    if let Some(path) = run_cmd.block_production_params.dump_on_exit.filter(|_| run_cmd.devnet) {
+        log::info!("💾 Dumping devnet state to {}", path.display());
+        DevnetDump::from_db(&backend)?.write_file(&path).context("Dumping devnet state")?;
+    }
     Ok(())
 }
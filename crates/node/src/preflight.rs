@@ -0,0 +1,321 @@
+//! Checks run before starting the services, so that a misconfigured node fails right away with a clear message
+//! instead of corrupting its database or dying hours later.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy::providers::{Provider, ProviderBuilder};
+use anyhow::Context;
+use mc_db::MadaraBackend;
+use mp_chain_config::{ChainConfig, SettlementLayer};
+use serde::Deserialize;
+use starknet_api::core::ChainId;
+use url::Url;
+
+use crate::cli::{L1SyncParams, RunCmd};
+
+/// RocksDB keeps many files open.
+const MIN_FD_LIMIT: u64 = 1024;
+const GATEWAY_TIMEOUT: Duration = Duration::from_secs(10);
+const GB: u64 = 1024 * 1024 * 1024;
+
+/// Checks that do not need the database, run before opening it.
+pub fn check_environment(run_cmd: &RunCmd, fd_limit: Option<u64>) -> anyhow::Result<()> {
+    if run_cmd.preflight_params.no_preflight_checks {
+        return Ok(());
+    }
+
+    check_fd_limit(fd_limit)?;
+    check_base_path(&run_cmd.db_params.base_path)?;
+    Ok(())
+}
+
+fn check_fd_limit(fd_limit: Option<u64>) -> anyhow::Result<()> {
+    if let Some(fd_limit) = fd_limit {
+        anyhow::ensure!(
+            fd_limit >= MIN_FD_LIMIT,
+            "The file-descriptor limit of the process is {fd_limit}, and madara needs at least {MIN_FD_LIMIT}. Raise it \
+             with `ulimit -n` or the `LimitNOFILE` setting of the systemd unit."
+        );
+    }
+    Ok(())
+}
+
+fn check_base_path(base_path: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(base_path)
+        .with_context(|| format!("Creating the data directory {}. Check `--base-path`.", base_path.display()))?;
+    let probe = base_path.join(".madara-preflight");
+    fs::write(&probe, b"")
+        .and_then(|_| fs::remove_file(&probe))
+        .with_context(|| format!("The data directory {} is not writable. Check `--base-path`.", base_path.display()))?;
+
+    Ok(())
+}
+
+/// Checks run once the database is open, before the services are started.
+pub async fn check_node(run_cmd: &RunCmd, chain_config: &ChainConfig, backend: &MadaraBackend) -> anyhow::Result<()> {
+    let params = &run_cmd.preflight_params;
+    if params.no_preflight_checks {
+        return Ok(());
+    }
+
+    let gateway_head = match run_cmd.network.filter(|_| !run_cmd.is_sequencer()) {
+        Some(network) => check_gateway_clock(network.feeder_gateway(), params.preflight_max_clock_skew).await?,
+        None => None,
+    };
+
+    let base_path = &run_cmd.db_params.base_path;
+    match available_space(base_path) {
+        Some(available) => {
+            let local_head = backend.get_latest_block_n()?;
+            let growth = expected_growth(local_head, gateway_head, || dir_size(&base_path.join("db")))?;
+            check_disk_space(base_path, available, growth, params.preflight_min_disk_space)?;
+        }
+        None => log::debug!("Could not find the disk of {}, skipping the disk space check", base_path.display()),
+    }
+
+    if let Some((endpoint, expected)) = l1_chain_id_to_check(&run_cmd.l1_sync_params, chain_config) {
+        check_l1_chain_id(&endpoint, expected, &chain_config.chain_name).await?;
+    }
+    Ok(())
+}
+
+/// Compares the system clock with the `Date` of a response of the feeder gateway. Returns the latest block number of
+/// the gateway, when it is reachable.
+async fn check_gateway_clock(feeder_gateway: Url, max_skew: Duration) -> anyhow::Result<Option<u64>> {
+    #[derive(Deserialize)]
+    struct LatestBlock {
+        block_number: u64,
+    }
+
+    let url = feeder_gateway.join("get_block?blockNumber=latest")?;
+    let response = match reqwest::Client::new().get(url).timeout(GATEWAY_TIMEOUT).send().await {
+        Ok(response) => response,
+        Err(err) => {
+            log::warn!("Skipping the clock skew check, the gateway is unreachable: {err:#}");
+            return Ok(None);
+        }
+    };
+    let date = response.headers().get(reqwest::header::DATE).and_then(|date| date.to_str().ok());
+    check_clock_skew(SystemTime::now(), date, max_skew)?;
+
+    Ok(response.json::<LatestBlock>().await.ok().map(|block| block.block_number))
+}
+
+/// Compares `now` with an HTTP `Date` header. An unparsable date is ignored.
+fn check_clock_skew(now: SystemTime, date: Option<&str>, max_skew: Duration) -> anyhow::Result<()> {
+    let gateway_time = date
+        .and_then(|date| chrono::DateTime::parse_from_rfc2822(date).ok())
+        .and_then(|date| u64::try_from(date.timestamp()).ok());
+    if let Some(gateway_time) = gateway_time {
+        let gateway_time = UNIX_EPOCH + Duration::from_secs(gateway_time);
+        let skew = now.duration_since(gateway_time).unwrap_or_else(|err| err.duration());
+        // The `Date` header has a resolution of one second.
+        anyhow::ensure!(
+            skew <= max_skew + Duration::from_secs(1),
+            "The system clock is {}s off from the clock of the gateway. Synchronize it, for example with NTP, or raise \
+             `--preflight-max-clock-skew`.",
+            skew.as_secs()
+        );
+    }
+    Ok(())
+}
+
+/// Space the database is expected to take to catch up with the gateway. Blocks are assumed to take as much space as
+/// the ones already synced.
+fn expected_growth(
+    local_head: Option<u64>,
+    gateway_head: Option<u64>,
+    db_size: impl FnOnce() -> io::Result<u64>,
+) -> anyhow::Result<u64> {
+    match (local_head, gateway_head) {
+        (Some(local_head), Some(gateway_head)) if local_head > 0 && gateway_head > local_head => {
+            let db_size = db_size().context("Measuring the size of the database")?;
+            Ok((db_size / local_head).saturating_mul(gateway_head - local_head))
+        }
+        _ => Ok(0),
+    }
+}
+
+fn check_disk_space(base_path: &Path, available: u64, expected_growth: u64, min_disk_space: u64) -> anyhow::Result<()> {
+    let required = expected_growth.max(min_disk_space.saturating_mul(GB));
+
+    anyhow::ensure!(
+        available >= required,
+        "Only {} GB of disk space is left in {}, and {} GB are needed: the database is expected to grow by {} GB to \
+         catch up with the network, and at least {min_disk_space} GB should stay free (`--preflight-min-disk-space`). \
+         Free some disk space or use another `--base-path`.",
+        available / GB,
+        base_path.display(),
+        required / GB,
+        expected_growth / GB,
+    );
+    Ok(())
+}
+
+/// Available space on the disk holding `path`.
+fn available_space(path: &Path) -> Option<u64> {
+    let path = path.canonicalize().ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() { dir_size(&entry.path())? } else { metadata.len() };
+    }
+    Ok(size)
+}
+
+/// The L1 endpoint and the chain id it is expected to be on, when it is used and the chain id is known.
+fn l1_chain_id_to_check(params: &L1SyncParams, chain_config: &ChainConfig) -> Option<(Url, u64)> {
+    if params.sovereign || params.sync_l1_disabled || chain_config.settlement_layer != SettlementLayer::Ethereum {
+        return None;
+    }
+    let endpoint = params.l1_endpoint.clone()?;
+    let expected = params.l1_chain_id.or(match chain_config.chain_id {
+        ChainId::Mainnet => Some(1),
+        ChainId::Sepolia | ChainId::IntegrationSepolia => Some(11155111),
+        _ => None,
+    })?;
+    Some((endpoint, expected))
+}
+
+async fn check_l1_chain_id(endpoint: &Url, expected: u64, chain_name: &str) -> anyhow::Result<()> {
+    let chain_id = ProviderBuilder::new()
+        .on_http(endpoint.clone())
+        .get_chain_id()
+        .await
+        .with_context(|| format!("Getting the chain id of the L1 endpoint {endpoint}"))?;
+    anyhow::ensure!(
+        chain_id == expected,
+        "The L1 endpoint {endpoint} is on chain id {chain_id}, but {} settles on chain id {expected}. Check \
+         `--l1-endpoint`, or `--l1-chain-id` for custom chains.",
+        chain_name
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+    use rstest::rstest;
+
+    fn l1_sync_params(args: &[&str]) -> L1SyncParams {
+        let args = ["madara", "--full", "--network", "mainnet"].iter().chain(args);
+        Cli::try_parse_from(args).unwrap().run.l1_sync_params
+    }
+
+    #[rstest]
+    #[case(Some(MIN_FD_LIMIT), true)]
+    #[case(None, true)]
+    #[case(Some(256), false)]
+    fn test_check_fd_limit(#[case] fd_limit: Option<u64>, #[case] ok: bool) {
+        assert_eq!(check_fd_limit(fd_limit).is_ok(), ok);
+    }
+
+    #[test]
+    fn test_check_base_path() {
+        let dir = tempfile::tempdir().unwrap();
+        check_base_path(&dir.path().join("madara")).unwrap();
+        assert!(dir.path().join("madara").is_dir());
+        assert!(!dir.path().join("madara/.madara-preflight").exists());
+
+        // The data directory can't be created under a file.
+        let file = dir.path().join("file");
+        fs::write(&file, b"").unwrap();
+        let err = check_base_path(&file.join("madara")).unwrap_err();
+        assert!(err.to_string().starts_with("Creating the data directory"), "{err:#}");
+    }
+
+    #[test]
+    fn test_check_clock_skew() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let max_skew = Duration::from_secs(30);
+        // Tue, 14 Nov 2023 22:13:20 GMT is 1_700_000_000.
+        check_clock_skew(now, Some("Tue, 14 Nov 2023 22:13:20 GMT"), max_skew).unwrap();
+        check_clock_skew(now, Some("Tue, 14 Nov 2023 22:13:51 GMT"), max_skew).unwrap();
+        check_clock_skew(now, Some("Tue, 14 Nov 2023 22:12:49 GMT"), max_skew).unwrap();
+        let err = check_clock_skew(now, Some("Tue, 14 Nov 2023 22:14:20 GMT"), max_skew).unwrap_err();
+        assert!(err.to_string().starts_with("The system clock is 60s off"), "{err:#}");
+        let err = check_clock_skew(now, Some("Tue, 14 Nov 2023 22:12:20 GMT"), max_skew).unwrap_err();
+        assert!(err.to_string().starts_with("The system clock is 60s off"), "{err:#}");
+
+        check_clock_skew(now, None, max_skew).unwrap();
+        check_clock_skew(now, Some("yesterday"), max_skew).unwrap();
+    }
+
+    #[tokio::test]
+    async fn unreachable_gateway_skips_the_clock_check() {
+        let gateway = Url::parse("http://127.0.0.1:1/feeder_gateway/").unwrap();
+        assert_eq!(check_gateway_clock(gateway, Duration::from_secs(30)).await.unwrap(), None);
+    }
+
+    #[rstest]
+    #[case(None, Some(100), 0)]
+    #[case(Some(0), Some(100), 0)]
+    #[case(Some(100), None, 0)]
+    #[case(Some(100), Some(100), 0)]
+    #[case(Some(100), Some(50), 0)]
+    #[case(Some(100), Some(300), 200 * GB)]
+    fn test_expected_growth(#[case] local_head: Option<u64>, #[case] gateway_head: Option<u64>, #[case] growth: u64) {
+        assert_eq!(expected_growth(local_head, gateway_head, || Ok(100 * GB)).unwrap(), growth);
+    }
+
+    #[test]
+    fn expected_growth_reports_the_database_size_errors() {
+        let err = expected_growth(Some(1), Some(2), || Err(io::ErrorKind::PermissionDenied.into())).unwrap_err();
+        assert_eq!(err.to_string(), "Measuring the size of the database");
+    }
+
+    #[rstest]
+    #[case(20 * GB, 0, 10, true)]
+    #[case(5 * GB, 0, 10, false)]
+    #[case(20 * GB, 30 * GB, 10, false)]
+    #[case(40 * GB, 30 * GB, 10, true)]
+    #[case(0, 0, 0, true)]
+    fn test_check_disk_space(#[case] available: u64, #[case] growth: u64, #[case] min: u64, #[case] ok: bool) {
+        assert_eq!(check_disk_space(Path::new("/data"), available, growth, min).is_ok(), ok);
+    }
+
+    #[test]
+    fn test_l1_chain_id_to_check() {
+        let endpoint = Url::parse("http://localhost:8545").unwrap();
+        let mainnet = ChainConfig::starknet_mainnet();
+        let sepolia = ChainConfig::starknet_sepolia();
+        let devnet = ChainConfig::madara_devnet();
+
+        let params = l1_sync_params(&["--l1-endpoint", "http://localhost:8545"]);
+        assert_eq!(l1_chain_id_to_check(&params, &mainnet), Some((endpoint.clone(), 1)));
+        assert_eq!(l1_chain_id_to_check(&params, &sepolia), Some((endpoint.clone(), 11155111)));
+        assert_eq!(l1_chain_id_to_check(&params, &devnet), None);
+
+        let params = l1_sync_params(&["--l1-endpoint", "http://localhost:8545", "--l1-chain-id", "31337"]);
+        assert_eq!(l1_chain_id_to_check(&params, &devnet), Some((endpoint, 31337)));
+
+        assert_eq!(l1_chain_id_to_check(&l1_sync_params(&[]), &mainnet), None);
+        assert_eq!(l1_chain_id_to_check(&l1_sync_params(&["--no-l1-sync"]), &mainnet), None);
+    }
+
+    #[tokio::test]
+    async fn unreachable_l1_is_rejected() {
+        let endpoint = Url::parse("http://127.0.0.1:1").unwrap();
+        let err = check_l1_chain_id(&endpoint, 1, "Starknet Mainnet").await.unwrap_err();
+        assert_eq!(err.to_string(), "Getting the chain id of the L1 endpoint http://127.0.0.1:1/");
+    }
+}
@@ -1,17 +1,23 @@
-use std::{io::Write, sync::Arc};
+use std::{io::Write, path::PathBuf, sync::Arc};
 
 use anyhow::Context;
 use mc_block_import::{BlockImporter, BlockValidationContext};
 use mc_db::{DatabaseService, MadaraBackend};
-use mc_devnet::{ChainGenesisDescription, DevnetKeys};
-use mc_mempool::{block_production::BlockProductionTask, L1DataProvider, Mempool};
+use mc_devnet::{ChainGenesisDescription, ClassManifest, DevnetDump, DevnetKeys, GenesisSpec};
+use mc_exec::metrics::BlockExecutionMetrics;
+use mc_mempool::{
+    block_production::BlockProductionTask, BlockClock, BuilderBlock, DevnetCommand, L1DataProvider, Mempool, MiningMode,
+};
 use mc_metrics::MetricsRegistry;
 use mc_telemetry::TelemetryHandle;
 use mp_exex::ExExManagerHandle;
+use mp_keystore::{KeySource, StarknetSigner};
 use mp_utils::service::Service;
+use tokio::sync::mpsc;
 use tokio::task::JoinSet;
 
 use crate::cli::block_production::BlockProductionParams;
+use crate::service::ha::Standby;
 
 struct StartParams {
     backend: Arc<MadaraBackend>,
@@ -20,11 +26,21 @@ struct StartParams {
     l1_data_provider: Arc<dyn L1DataProvider>,
     is_devnet: bool,
     n_devnet_contracts: u64,
+    class_manifest: ClassManifest,
+    load_state: Option<PathBuf>,
+    genesis: Option<GenesisSpec>,
+    mining_mode: MiningMode,
     exex_manager: Option<ExExManagerHandle>,
+    signing_key: Option<KeySource>,
+    clock: BlockClock,
+    devnet_commands: mpsc::Receiver<DevnetCommand>,
+    builder_blocks: Option<mpsc::Receiver<BuilderBlock>>,
+    metrics: BlockExecutionMetrics,
 }
 
 pub struct BlockProductionService {
     start: Option<StartParams>,
+    standby: Option<Standby>,
     enabled: bool,
 }
 impl BlockProductionService {
@@ -36,12 +52,15 @@ impl BlockProductionService {
         block_import: Arc<BlockImporter>,
         l1_data_provider: Arc<dyn L1DataProvider>,
         is_devnet: bool,
+        clock: BlockClock,
+        devnet_commands: mpsc::Receiver<DevnetCommand>,
+        builder_blocks: Option<mpsc::Receiver<BuilderBlock>>,
         exex_manager: Option<ExExManagerHandle>,
-        _metrics_handle: &MetricsRegistry,
+        metrics_handle: &MetricsRegistry,
         _telemetry: TelemetryHandle,
     ) -> anyhow::Result<Self> {
         if config.block_production_disabled {
-            return Ok(Self { start: None, enabled: false });
+            return Ok(Self { start: None, standby: None, enabled: false });
         }
 
         Ok(Self {
@@ -51,32 +70,51 @@ impl BlockProductionService {
                 mempool,
                 block_import,
                 n_devnet_contracts: config.devnet_contracts,
+                class_manifest: config.class_manifest().context("Loading the devnet class manifest")?,
+                load_state: config.load_state.clone(),
+                genesis: config.genesis_spec().context("Loading the genesis specification")?,
+                mining_mode: config.mining_mode.into(),
                 is_devnet,
                 exex_manager,
+                signing_key: config.signing_key_source().context("Loading the block signing key")?,
+                clock,
+                devnet_commands,
+                builder_blocks,
+                metrics: BlockExecutionMetrics::get_or_register(metrics_handle)
+                    .context("Registering the block execution metrics")?,
             }),
+            standby: None,
             enabled: true,
         })
     }
+
+    /// Only produces blocks once elected as the leader of a highly available sequencer, see [`Standby`].
+    pub fn with_high_availability(mut self, standby: Standby) -> Self {
+        self.standby = Some(standby);
+        self
+    }
 }
 
-#[async_trait::async_trait]
-impl Service for BlockProductionService {
-    // TODO(cchudant,2024-07-30): special threading requirements for the block production task
-    async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
-        if !self.enabled {
-            return Ok(());
-        }
+impl StartParams {
+    /// Deploys the devnet or the genesis block on a new chain.
+    async fn init_chain(&self) -> anyhow::Result<()> {
         let StartParams {
             backend,
-            l1_data_provider,
-            mempool,
+            block_import,
             is_devnet,
             n_devnet_contracts,
-            block_import,
-            exex_manager,
-        } = self.start.take().expect("Service already started");
+            class_manifest,
+            load_state,
+            genesis,
+            ..
+        } = self;
+
+        if *is_devnet {
+            if let Some(path) = load_state {
+                log::info!("📥 Loading devnet state from {}", path.display());
+                DevnetDump::read_file(path)?.load(backend, block_import).await.context("Loading devnet state")?;
+            }
 
-        if is_devnet {
             // DEVNET: we the genesis block for the devnet if not deployed, otherwise we only print the devnet keys.
 
             let keys = if backend.get_latest_block_n().context("Getting the latest block number in db")?.is_none() {
@@ -84,10 +122,15 @@ impl Service for BlockProductionService {
 
                 log::info!("⛏️  Deploying devnet genesis block");
 
-                let mut genesis_config =
-                    ChainGenesisDescription::base_config().context("Failed to create base genesis config")?;
+                let mut genesis_config = if backend.is_forked() {
+                    ChainGenesisDescription::fork_config()
+                } else {
+                    ChainGenesisDescription::base_config(backend.chain_config())
+                        .context("Failed to create base genesis config")?
+                };
+                genesis_config.add_classes(class_manifest).context("Failed to add devnet classes")?;
                 let contracts = genesis_config
-                    .add_devnet_contracts(n_devnet_contracts)
+                    .add_devnet_contracts(*n_devnet_contracts, backend.chain_config())
                     .context("Failed to add devnet contracts")?;
 
                 let genesis_block = genesis_config
@@ -102,28 +145,149 @@ impl Service for BlockProductionService {
                     .await
                     .context("Importing devnet genesis block")?;
 
-                contracts.save_to_db(&backend).context("Saving predeployed devnet contract keys to database")?;
+                contracts.save_to_db(backend).context("Saving predeployed devnet contract keys to database")?;
 
                 contracts
             } else {
-                DevnetKeys::from_db(&backend).context("Getting the devnet predeployed contract keys and balances")?
+                DevnetKeys::from_db(backend).context("Getting the devnet predeployed contract keys and balances")?
             };
 
             // display devnet welcome message :)
             // we display it to stdout instead of stderr
 
-            let msg = format!("{}", keys);
+            let msg = format!("{}", keys.display(backend.chain_config()));
 
             std::io::stdout().write(msg.as_bytes()).context("Writing devnet welcome message to stdout")?;
+        } else if let Some(genesis) = genesis {
+            if backend.get_latest_block_n().context("Getting the latest block number in db")?.is_none() {
+                log::info!("⛏️  Building the genesis block");
+                let genesis_block =
+                    genesis.build(backend.chain_config()).context("Building genesis block from specification")?;
+                block_import
+                    .add_block(
+                        genesis_block,
+                        BlockValidationContext::new(backend.chain_config().chain_id.clone()).trust_class_hashes(true),
+                    )
+                    .await
+                    .context("Importing genesis block")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Can be called again once stopped, when a highly available sequencer is elected again.
+    async fn produce_blocks(&mut self, signer: Option<Arc<dyn StarknetSigner>>) -> anyhow::Result<()> {
+        let StartParams {
+            backend,
+            l1_data_provider,
+            mempool,
+            is_devnet,
+            mining_mode,
+            block_import,
+            exex_manager,
+            clock,
+            devnet_commands,
+            builder_blocks,
+            metrics,
+            ..
+        } = self;
+
+        if signer.is_none() {
+            // Full nodes would otherwise expect signatures for the new blocks.
+            backend.write_sequencer_public_key(None).context("Clearing the sequencer public key")?;
+        }
+
+        let mut task = BlockProductionTask::new(
+            Arc::clone(backend),
+            Arc::clone(block_import),
+            Arc::clone(mempool),
+            Arc::clone(l1_data_provider),
+            exex_manager.clone(),
+        )?
+        .with_clock(clock.clone())?
+        .with_metrics(metrics.clone());
+        if let Some(signer) = signer {
+            log::info!("🔏 Signing blocks with public key {:#x}", signer.public_key());
+            task = task.with_signer(signer)?;
+        }
+        if *is_devnet {
+            // A devnet is never highly available, so block production is only started once.
+            let devnet_commands = std::mem::replace(devnet_commands, mpsc::channel(1).1);
+            task = task.with_devnet_commands(devnet_commands).with_mining_mode(*mining_mode);
+        }
+
+        // The task consumes the receiver of the builder blocks, which must outlive it to be given to the next one.
+        let (forwarded_sender, forwarded_builder_blocks) = mpsc::channel(1);
+        if builder_blocks.is_some() {
+            log::info!("🏗️  Accepting the blocks of an external block builder");
+            task = task.with_builder_blocks(forwarded_builder_blocks);
         }
+        let forward_builder_blocks = async {
+            if let Some(builder_blocks) = builder_blocks {
+                while let Some(builder_block) = builder_blocks.recv().await {
+                    if forwarded_sender.send(builder_block).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            std::future::pending::<anyhow::Result<()>>().await
+        };
 
-        join_set.spawn(async move {
-            BlockProductionTask::new(backend, block_import, mempool, l1_data_provider, exex_manager)?
-                .block_production_task()
-                .await?;
-            Ok(())
-        });
+        tokio::select! {
+            res = task.block_production_task() => res,
+            res = forward_builder_blocks => res,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for BlockProductionService {
+    // TODO(cchudant,2024-07-30): special threading requirements for the block production task
+    async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let mut params = self.start.take().expect("Service already started");
+
+        let signer = match params.signing_key.take() {
+            Some(source) => Some(source.starknet_signer().await.context("Loading the block signing key")?),
+            None => None,
+        };
+
+        match self.standby.take() {
+            Some(mut standby) => {
+                join_set.spawn(async move {
+                    loop {
+                        let Some(leadership) = standby.wait_for_leadership().await? else {
+                            return Ok(());
+                        };
+                        params.init_chain().await?;
+                        let lost = tokio::select! {
+                            res = params.produce_blocks(signer.clone()) => {
+                                res?;
+                                false
+                            }
+                            _ = leadership.hold() => true,
+                        };
+                        if !lost {
+                            leadership.release().await;
+                            return Ok(());
+                        }
+                        log::warn!("👋 Stepping down from the sequencer leadership");
+                        standby = leadership.step_down();
+                    }
+                });
+            }
+            None => {
+                params.init_chain().await?;
+                join_set.spawn(async move { params.produce_blocks(signer).await });
+            }
+        }
 
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "block_production"
+    }
 }
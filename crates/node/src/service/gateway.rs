@@ -1,5 +1,9 @@
 use crate::cli::GatewayParams;
+use anyhow::Context;
 use mc_db::{DatabaseService, MadaraBackend};
+use mc_exec::metrics::BlockExecutionMetrics;
+use mc_gateway::server::{gossip::GossipReceiver, metrics::GatewayMetrics, service::GatewayServerConfig};
+use mc_metrics::MetricsRegistry;
 use mp_rpc::AddTransactionProvider;
 use mp_utils::service::Service;
 use std::sync::Arc;
@@ -9,10 +13,9 @@ use tokio::task::JoinSet;
 pub struct GatewayService {
     db_backend: Arc<MadaraBackend>,
     add_transaction_provider: Arc<dyn AddTransactionProvider>,
-    feeder_gateway_enable: bool,
-    gateway_enable: bool,
-    gateway_external: bool,
-    gateway_port: u16,
+    gossip_receiver: Option<GossipReceiver>,
+    config: GatewayServerConfig,
+    metrics: GatewayMetrics,
 }
 
 impl GatewayService {
@@ -20,14 +23,17 @@ impl GatewayService {
         config: &GatewayParams,
         db: &DatabaseService,
         add_transaction_provider: Arc<dyn AddTransactionProvider>,
+        gossip_receiver: Option<GossipReceiver>,
+        metrics_handle: &MetricsRegistry,
     ) -> anyhow::Result<Self> {
+        // Recorded by `get_block_traces`.
+        BlockExecutionMetrics::get_or_register(metrics_handle).context("Registering block execution metrics")?;
         Ok(Self {
             db_backend: Arc::clone(db.backend()),
             add_transaction_provider,
-            feeder_gateway_enable: config.feeder_gateway_enable,
-            gateway_enable: config.gateway_enable,
-            gateway_external: config.gateway_external,
-            gateway_port: config.gateway_port,
+            gossip_receiver,
+            config: config.server_config(),
+            metrics: GatewayMetrics::register(metrics_handle).context("Registering gateway metrics")?,
         })
     }
 }
@@ -35,28 +41,29 @@ impl GatewayService {
 #[async_trait::async_trait]
 impl Service for GatewayService {
     async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
-        if self.feeder_gateway_enable || self.gateway_enable {
-            let GatewayService {
-                db_backend,
-                add_transaction_provider,
-                feeder_gateway_enable,
-                gateway_enable,
-                gateway_external,
-                gateway_port,
-            } = self.clone();
+        if self.config.feeder_gateway_enable || self.config.gateway_enable {
+            let GatewayService { db_backend, add_transaction_provider, gossip_receiver, config, metrics } =
+                self.clone();
 
             join_set.spawn(async move {
                 mc_gateway::server::service::start_server(
                     db_backend,
                     add_transaction_provider,
-                    feeder_gateway_enable,
-                    gateway_enable,
-                    gateway_external,
-                    gateway_port,
+                    gossip_receiver,
+                    config,
+                    metrics,
                 )
                 .await
             });
         }
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "gateway"
+    }
+
+    fn restartable(&self) -> bool {
+        true
+    }
 }
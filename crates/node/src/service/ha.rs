@@ -0,0 +1,527 @@
+//! High availability of the sequencer. The instances of a sequencer share the leadership through a lease in etcd: the
+//! leader produces the blocks, while the standbys sync them from its gateway, and take over once its lease expires.
+//!
+//! A leader which cannot renew its lease steps down before the lease expires, so that two instances never produce
+//! blocks at the same time. The leader publishes its latest block along with every renewal of its lease, and the
+//! standby taking over syncs up to it before producing blocks. Only the blocks produced since the last renewal of a
+//! leader which crashed can be lost.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use mc_block_import::BlockImporter;
+use mc_db::MadaraBackend;
+use mc_telemetry::TelemetryHandle;
+use mp_exex::ExExManagerHandle;
+use mp_utils::wait_or_graceful_shutdown;
+use tokio::sync::watch;
+use url::Url;
+
+use crate::cli::SyncParams;
+use etcd::EtcdLease;
+
+mod etcd;
+
+#[derive(Debug, Clone)]
+pub struct HaConfig {
+    pub etcd_endpoint: Url,
+    pub lease_key: String,
+    pub lease_ttl: Duration,
+    /// Gateway of this instance, which the standbys sync from while it is the leader.
+    pub advertised_gateway: Url,
+}
+
+/// Outcome of a campaign for the leadership.
+enum Campaign {
+    Elected,
+    /// Another node is the leader, and advertises this value. `None` when its lease expired in the meantime.
+    Standby {
+        leader: Option<String>,
+    },
+}
+
+/// Leadership lease shared by the instances of the sequencer.
+#[async_trait]
+trait Lease: Send + Sync {
+    fn ttl(&self) -> Duration;
+    /// Takes the leadership when no node holds it.
+    async fn campaign(&mut self) -> anyhow::Result<Campaign>;
+    /// Renews the lease, returns false when it already expired.
+    async fn keep_alive(&self) -> anyhow::Result<bool>;
+    /// Records the latest block of the leader, returns false when this node is not the leader anymore.
+    async fn publish_block(&self, block_n: u64) -> anyhow::Result<bool>;
+    /// Latest block published by the current or previous leader.
+    async fn published_block(&self) -> anyhow::Result<Option<u64>>;
+    /// Releases the leadership right away, instead of once the lease expires.
+    async fn revoke(&mut self) -> anyhow::Result<()>;
+}
+
+type SyncFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
+/// The local chain, which the standbys sync from the leader.
+#[async_trait]
+trait LocalChain: Send + Sync {
+    fn sync_from(&self, leader: &str) -> anyhow::Result<SyncFuture>;
+    fn latest_block_n(&self) -> anyhow::Result<Option<u64>>;
+    /// Waits for the blocks being imported, as aborting a sync does not abort the block it is writing.
+    async fn wait_for_imports(&self);
+}
+
+/// Syncs the blocks from the leader until this instance is elected.
+pub struct Standby {
+    lease: Box<dyn Lease>,
+    chain: Box<dyn LocalChain>,
+}
+
+impl Standby {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: HaConfig,
+        backend: Arc<MadaraBackend>,
+        block_importer: Arc<BlockImporter>,
+        sync_params: SyncParams,
+        telemetry: TelemetryHandle,
+        pending_block_poll_interval: watch::Receiver<Duration>,
+        exex_manager: Option<ExExManagerHandle>,
+    ) -> Self {
+        let lease =
+            EtcdLease::new(config.etcd_endpoint, config.lease_key, config.advertised_gateway.into(), config.lease_ttl);
+        let chain =
+            SyncedChain { backend, block_importer, sync_params, telemetry, pending_block_poll_interval, exex_manager };
+        Self { lease: Box::new(lease), chain: Box::new(chain) }
+    }
+
+    /// Returns `None` when the node is shutting down.
+    pub async fn wait_for_leadership(mut self) -> anyhow::Result<Option<Leadership>> {
+        let mut interval = tokio::time::interval(self.lease.ttl() / 4);
+        let mut leader: Option<String> = None;
+        let mut sync: Option<SyncFuture> = None;
+        log::info!("⏳ Standing by for the sequencer leadership");
+
+        loop {
+            if tick_while_syncing(&mut interval, &mut sync, &mut leader).await.is_none() {
+                return Ok(None);
+            }
+
+            match self.lease.campaign().await {
+                Ok(Campaign::Elected) => match self.catch_up(&mut interval, &mut sync, &mut leader).await {
+                    Ok(true) => break,
+                    Ok(false) => {
+                        self.revoke().await;
+                        return Ok(None);
+                    }
+                    Err(err) => {
+                        log::warn!("Stepping down from the sequencer leadership: {err:#}");
+                        self.revoke().await;
+                    }
+                },
+                Ok(Campaign::Standby { leader: Some(new_leader) }) if leader.as_ref() != Some(&new_leader) => {
+                    log::info!("🔁 Syncing from the sequencer leader {new_leader}");
+                    sync = self
+                        .chain
+                        .sync_from(&new_leader)
+                        .inspect_err(|err| log::warn!("Syncing from the sequencer leader: {err:#}"))
+                        .ok();
+                    leader = Some(new_leader);
+                }
+                Ok(Campaign::Standby { .. }) => {}
+                Err(err) => log::warn!("Campaigning for the sequencer leadership: {err:#}"),
+            }
+        }
+
+        drop(sync);
+        self.chain.wait_for_imports().await;
+        log::info!("👑 Elected sequencer leader");
+        Ok(Some(Leadership { standby: self }))
+    }
+
+    /// Keeps syncing from the previous leader up to the last block it published, as producing blocks on an older one
+    /// would fork the chain. Fails when the sync stalls for a whole lease time to live, so that another standby can
+    /// take over. Returns false when the node is shutting down.
+    async fn catch_up(
+        &self,
+        interval: &mut tokio::time::Interval,
+        sync: &mut Option<SyncFuture>,
+        leader: &mut Option<String>,
+    ) -> anyhow::Result<bool> {
+        let Some(published) = self.lease.published_block().await.context("Getting the published block")? else {
+            return Ok(true);
+        };
+        let mut latest = self.chain.latest_block_n()?;
+        let mut progressed_at = Instant::now();
+        if latest.map_or(true, |block_n| block_n < published) {
+            log::info!("⏩ Syncing up to block {published}, published by the previous sequencer leader");
+        }
+        while latest.map_or(true, |block_n| block_n < published) {
+            anyhow::ensure!(
+                progressed_at.elapsed() < self.lease.ttl(),
+                "block {published} published by the previous leader is not synced, the latest block is {latest:?}"
+            );
+            if tick_while_syncing(interval, sync, leader).await.is_none() {
+                return Ok(false);
+            }
+            anyhow::ensure!(self.lease.keep_alive().await?, "the lease expired");
+            let new_latest = self.chain.latest_block_n()?;
+            if new_latest != latest {
+                (latest, progressed_at) = (new_latest, Instant::now());
+            }
+        }
+        Ok(true)
+    }
+
+    async fn revoke(&mut self) {
+        if let Err(err) = self.lease.revoke().await {
+            log::warn!("Releasing the sequencer leadership: {err:#}");
+        }
+    }
+}
+
+/// Waits for the next tick while syncing from the leader, returns `None` when the node is shutting down.
+async fn tick_while_syncing(
+    interval: &mut tokio::time::Interval,
+    sync: &mut Option<SyncFuture>,
+    leader: &mut Option<String>,
+) -> Option<()> {
+    loop {
+        tokio::select! {
+            tick = wait_or_graceful_shutdown(interval.tick()) => return tick.map(|_| ()),
+            res = async { sync.as_mut().expect("Checked by the select condition").await }, if sync.is_some() => {
+                log::warn!("Sync from the leader {} stopped: {:?}", leader.as_deref().unwrap_or_default(), res);
+                (*sync, *leader) = (None, None);
+            }
+        }
+    }
+}
+
+struct SyncedChain {
+    backend: Arc<MadaraBackend>,
+    block_importer: Arc<BlockImporter>,
+    sync_params: SyncParams,
+    telemetry: TelemetryHandle,
+    pending_block_poll_interval: watch::Receiver<Duration>,
+    exex_manager: Option<ExExManagerHandle>,
+}
+
+#[async_trait]
+impl LocalChain for SyncedChain {
+    fn sync_from(&self, leader: &str) -> anyhow::Result<SyncFuture> {
+        let leader =
+            Url::parse(leader).with_context(|| format!("Invalid gateway URL advertised by the leader: {leader}"))?;
+        let fetch_config =
+            self.sync_params.leader_fetch_config(self.backend.chain_config().chain_id.clone(), &leader)?;
+        let backend = Arc::clone(&self.backend);
+        let block_importer = Arc::clone(&self.block_importer);
+        let backup_every_n_blocks = self.sync_params.backup_every_n_blocks;
+        let telemetry = self.telemetry.clone();
+        let pending_block_poll_interval = self.pending_block_poll_interval.clone();
+        let exex_manager = self.exex_manager.clone();
+        Ok(Box::pin(async move {
+            mc_sync::sync(
+                &backend,
+                block_importer,
+                fetch_config,
+                None,
+                backup_every_n_blocks,
+                telemetry,
+                pending_block_poll_interval,
+                exex_manager,
+            )
+            .await
+        }))
+    }
+
+    fn latest_block_n(&self) -> anyhow::Result<Option<u64>> {
+        self.backend.get_latest_block_n().context("Getting the latest block number")
+    }
+
+    async fn wait_for_imports(&self) {
+        self.block_importer.wait_for_imports().await;
+    }
+}
+
+/// Held by the leader while it produces blocks.
+pub struct Leadership {
+    standby: Standby,
+}
+
+impl Leadership {
+    /// Keeps the lease alive and publishes the latest block, and only returns once the leadership is lost: the lease
+    /// expired, or was not renewed for half of its time to live.
+    pub async fn hold(&self) {
+        let ttl = self.standby.lease.ttl();
+        let mut interval = tokio::time::interval(ttl / 4);
+        let mut renewed_at = Instant::now();
+        loop {
+            interval.tick().await;
+            match self.renew().await {
+                Ok(true) => renewed_at = Instant::now(),
+                Ok(false) => {
+                    log::warn!("Lost the sequencer leadership: the lease expired");
+                    return;
+                }
+                Err(err) => log::warn!("Renewing the sequencer lease: {err:#}"),
+            }
+            if renewed_at.elapsed() >= ttl / 2 {
+                log::warn!("Lost the sequencer leadership: the lease was not renewed for {:?}", renewed_at.elapsed());
+                return;
+            }
+        }
+    }
+
+    async fn renew(&self) -> anyhow::Result<bool> {
+        if !self.standby.lease.keep_alive().await? {
+            return Ok(false);
+        }
+        match self.standby.chain.latest_block_n()? {
+            Some(block_n) => self.standby.lease.publish_block(block_n).await,
+            None => Ok(true),
+        }
+    }
+
+    /// Stands by again once the leadership was lost, with block production stopped.
+    pub fn step_down(self) -> Standby {
+        self.standby
+    }
+
+    /// Releases the lease once block production stopped, so that a standby takes over right away.
+    pub async fn release(mut self) {
+        if let Err(err) = self.renew().await {
+            log::warn!("Publishing the latest block: {err:#}");
+        }
+        self.standby.revoke().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    const TTL: Duration = Duration::from_millis(200);
+
+    /// The etcd cluster shared by the instances.
+    #[derive(Default)]
+    struct MockEtcd(Mutex<MockEtcdState>);
+
+    #[derive(Default)]
+    struct MockEtcdState {
+        next_lease_id: u64,
+        leases: HashSet<u64>,
+        /// Lease and value of the leader.
+        leader: Option<(u64, String)>,
+        published_block: Option<u64>,
+    }
+
+    impl MockEtcd {
+        fn leader(&self) -> Option<String> {
+            self.0.lock().unwrap().leader.as_ref().map(|(_, value)| value.clone())
+        }
+
+        fn published_block(&self) -> Option<u64> {
+            self.0.lock().unwrap().published_block
+        }
+
+        fn expire_leases(&self) {
+            let mut state = self.0.lock().unwrap();
+            state.leases.clear();
+            state.leader = None;
+        }
+    }
+
+    struct MockLease {
+        etcd: Arc<MockEtcd>,
+        value: String,
+        lease_id: Option<u64>,
+    }
+
+    #[async_trait]
+    impl Lease for MockLease {
+        fn ttl(&self) -> Duration {
+            TTL
+        }
+
+        async fn campaign(&mut self) -> anyhow::Result<Campaign> {
+            let mut state = self.etcd.0.lock().unwrap();
+            let lease_id = match self.lease_id {
+                Some(lease_id) if state.leases.contains(&lease_id) => lease_id,
+                _ => {
+                    state.next_lease_id += 1;
+                    let lease_id = state.next_lease_id;
+                    state.leases.insert(lease_id);
+                    *self.lease_id.insert(lease_id)
+                }
+            };
+            match &state.leader {
+                Some((_, leader)) => Ok(Campaign::Standby { leader: Some(leader.clone()) }),
+                None => {
+                    state.leader = Some((lease_id, self.value.clone()));
+                    Ok(Campaign::Elected)
+                }
+            }
+        }
+
+        async fn keep_alive(&self) -> anyhow::Result<bool> {
+            let lease_id = self.lease_id.context("No lease granted")?;
+            Ok(self.etcd.0.lock().unwrap().leases.contains(&lease_id))
+        }
+
+        async fn publish_block(&self, block_n: u64) -> anyhow::Result<bool> {
+            let mut state = self.etcd.0.lock().unwrap();
+            if state.leader.as_ref().map(|(lease_id, _)| *lease_id) != self.lease_id {
+                return Ok(false);
+            }
+            state.published_block = Some(block_n);
+            Ok(true)
+        }
+
+        async fn published_block(&self) -> anyhow::Result<Option<u64>> {
+            Ok(self.etcd.published_block())
+        }
+
+        async fn revoke(&mut self) -> anyhow::Result<()> {
+            let lease_id = self.lease_id.take().context("No lease granted")?;
+            let mut state = self.etcd.0.lock().unwrap();
+            state.leases.remove(&lease_id);
+            if state.leader.as_ref().is_some_and(|(leader_lease_id, _)| *leader_lease_id == lease_id) {
+                state.leader = None;
+            }
+            Ok(())
+        }
+    }
+
+    /// A chain which syncs up to `sync_to` from any leader.
+    #[derive(Default)]
+    struct MockChain {
+        latest: Mutex<Option<u64>>,
+        sync_to: Mutex<Option<u64>>,
+        synced_from: Mutex<Vec<String>>,
+    }
+
+    impl MockChain {
+        fn latest(&self) -> Option<u64> {
+            *self.latest.lock().unwrap()
+        }
+
+        fn synced_from(&self) -> Vec<String> {
+            self.synced_from.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl LocalChain for Arc<MockChain> {
+        fn sync_from(&self, leader: &str) -> anyhow::Result<SyncFuture> {
+            self.synced_from.lock().unwrap().push(leader.into());
+            let chain = Arc::clone(self);
+            Ok(Box::pin(async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    if let Some(sync_to) = *chain.sync_to.lock().unwrap() {
+                        let mut latest = chain.latest.lock().unwrap();
+                        *latest = (*latest).max(Some(sync_to));
+                    }
+                }
+            }))
+        }
+
+        fn latest_block_n(&self) -> anyhow::Result<Option<u64>> {
+            Ok(self.latest())
+        }
+
+        async fn wait_for_imports(&self) {}
+    }
+
+    fn standby(etcd: &Arc<MockEtcd>, value: &str, chain: &Arc<MockChain>) -> Standby {
+        let lease = MockLease { etcd: Arc::clone(etcd), value: value.into(), lease_id: None };
+        Standby { lease: Box::new(lease), chain: Box::new(Arc::clone(chain)) }
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        tokio::time::timeout(TTL * 10, async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Timed out")
+    }
+
+    fn chain(latest: Option<u64>, sync_to: Option<u64>) -> Arc<MockChain> {
+        Arc::new(MockChain { latest: Mutex::new(latest), sync_to: Mutex::new(sync_to), ..Default::default() })
+    }
+
+    #[tokio::test]
+    async fn election() {
+        let etcd = Arc::new(MockEtcd::default());
+        let (chain_a, chain_b) = (chain(Some(3), None), chain(Some(1), Some(3)));
+
+        let leadership_a = standby(&etcd, "a", &chain_a).wait_for_leadership().await.unwrap().unwrap();
+        assert_eq!(etcd.leader().as_deref(), Some("a"));
+
+        let standby_b = tokio::spawn(standby(&etcd, "b", &chain_b).wait_for_leadership());
+        wait_until(|| chain_b.synced_from() == ["a"]).await;
+        tokio::time::sleep(TTL).await;
+        assert!(!standby_b.is_finished());
+
+        // The leader publishes its latest block when releasing the leadership.
+        leadership_a.release().await;
+        assert_eq!(etcd.published_block(), Some(3));
+        let _leadership_b = tokio::time::timeout(TTL * 10, standby_b).await.unwrap().unwrap().unwrap().unwrap();
+        assert_eq!(etcd.leader().as_deref(), Some("b"));
+        assert_eq!(chain_b.latest(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn failover() {
+        let etcd = Arc::new(MockEtcd::default());
+        let (chain_a, chain_b) = (chain(Some(5), None), chain(Some(2), None));
+
+        let leadership_a = standby(&etcd, "a", &chain_a).wait_for_leadership().await.unwrap().unwrap();
+        let leader_a = tokio::spawn(async move {
+            leadership_a.hold().await;
+            leadership_a.step_down()
+        });
+        wait_until(|| etcd.published_block() == Some(5)).await;
+
+        let standby_b = tokio::spawn(standby(&etcd, "b", &chain_b).wait_for_leadership());
+        wait_until(|| chain_b.synced_from() == ["a"]).await;
+
+        // The leader steps down once its lease expired.
+        etcd.expire_leases();
+        let standby_a = tokio::time::timeout(TTL * 10, leader_a).await.unwrap().unwrap();
+
+        // The standby does not produce blocks before syncing the blocks published by the previous leader, and gives up
+        // the leadership while its sync is stalled.
+        tokio::time::sleep(TTL * 3).await;
+        assert!(!standby_b.is_finished());
+        assert_eq!(chain_b.latest(), Some(2));
+
+        *chain_b.sync_to.lock().unwrap() = Some(5);
+        let _leadership_b = tokio::time::timeout(TTL * 10, standby_b).await.unwrap().unwrap().unwrap().unwrap();
+        assert_eq!(etcd.leader().as_deref(), Some("b"));
+        assert_eq!(chain_b.latest(), Some(5));
+
+        // The previous leader syncs from the new one.
+        let standby_a = tokio::spawn(standby_a.wait_for_leadership());
+        wait_until(|| chain_a.synced_from() == ["b"]).await;
+        assert!(!standby_a.is_finished());
+    }
+
+    #[tokio::test]
+    async fn lost_lease() {
+        let etcd = Arc::new(MockEtcd::default());
+        let chain = chain(Some(0), Some(0));
+
+        let leadership = standby(&etcd, "a", &chain).wait_for_leadership().await.unwrap().unwrap();
+        etcd.expire_leases();
+        tokio::time::timeout(TTL * 10, leadership.hold()).await.unwrap();
+
+        // Elected again with a new lease.
+        let _leadership = leadership.step_down().wait_for_leadership().await.unwrap().unwrap();
+        assert_eq!(etcd.leader().as_deref(), Some("a"));
+    }
+}
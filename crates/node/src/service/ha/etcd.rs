@@ -0,0 +1,180 @@
+//! Leadership lease held in etcd, through the JSON gateway of its v3 API. The leader holds a key attached to a lease
+//! it keeps alive, which etcd deletes once the lease expires, and the standbys try to create the key in the meantime.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+
+use super::{Campaign, Lease};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct EtcdLease {
+    client: reqwest::Client,
+    endpoint: Url,
+    key: String,
+    value: String,
+    ttl: Duration,
+    lease_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LeaseGrantResponse {
+    #[serde(rename = "ID")]
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct LeaseKeepAliveResponse {
+    result: LeaseKeepAliveResult,
+}
+
+#[derive(Deserialize)]
+struct LeaseKeepAliveResult {
+    /// Omitted once the lease expired.
+    #[serde(rename = "TTL", default)]
+    ttl: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TxnResponse {
+    /// Omitted when false.
+    #[serde(default)]
+    succeeded: bool,
+    #[serde(default)]
+    responses: Vec<TxnResponseOp>,
+}
+
+#[derive(Deserialize)]
+struct TxnResponseOp {
+    response_range: Option<RangeResponse>,
+}
+
+#[derive(Deserialize)]
+struct RangeResponse {
+    #[serde(default)]
+    kvs: Vec<KeyValue>,
+}
+
+#[derive(Deserialize)]
+struct KeyValue {
+    value: String,
+}
+
+impl EtcdLease {
+    /// The leader advertises `value` to the standbys under `key`.
+    pub fn new(endpoint: Url, key: String, value: String, ttl: Duration) -> Self {
+        Self { client: reqwest::Client::new(), endpoint, key, value, ttl, lease_id: None }
+    }
+
+    fn published_block_key(&self) -> String {
+        format!("{}/published_block", self.key)
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: serde_json::Value) -> anyhow::Result<T> {
+        let url = self.endpoint.join(path).with_context(|| format!("Invalid etcd endpoint {}", self.endpoint))?;
+        let response = self
+            .client
+            .post(url)
+            .json(&body)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Calling etcd {path}"))?;
+        response.json().await.with_context(|| format!("Parsing the response of etcd {path}"))
+    }
+}
+
+#[async_trait]
+impl Lease for EtcdLease {
+    fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Creates the key when no node holds it.
+    async fn campaign(&mut self) -> anyhow::Result<Campaign> {
+        let lease_id = match self.lease_id.clone() {
+            Some(lease_id) if self.keep_alive().await? => lease_id,
+            _ => {
+                let response: LeaseGrantResponse =
+                    self.post("v3/lease/grant", json!({ "TTL": self.ttl.as_secs() })).await?;
+                self.lease_id = Some(response.id.clone());
+                response.id
+            }
+        };
+
+        let key = BASE64_STANDARD.encode(&self.key);
+        let response: TxnResponse = self
+            .post(
+                "v3/kv/txn",
+                json!({
+                    "compare": [{ "key": key, "result": "EQUAL", "target": "CREATE", "create_revision": "0" }],
+                    "success": [{ "request_put": { "key": key, "value": BASE64_STANDARD.encode(&self.value), "lease": lease_id } }],
+                    "failure": [{ "request_range": { "key": key } }],
+                }),
+            )
+            .await?;
+        if response.succeeded {
+            return Ok(Campaign::Elected);
+        }
+
+        let Some(kv) = response
+            .responses
+            .into_iter()
+            .find_map(|op| op.response_range)
+            .and_then(|range| range.kvs.into_iter().next())
+        else {
+            return Ok(Campaign::Standby { leader: None });
+        };
+        let leader = decode(kv.value).context("Decoding the leader value")?;
+        Ok(Campaign::Standby { leader: Some(leader) })
+    }
+
+    async fn keep_alive(&self) -> anyhow::Result<bool> {
+        let lease_id = self.lease_id.as_ref().context("No lease granted")?;
+        let response: LeaseKeepAliveResponse = self.post("v3/lease/keepalive", json!({ "ID": lease_id })).await?;
+        Ok(response.result.ttl.is_some_and(|ttl| ttl != "0"))
+    }
+
+    /// The value is not attached to the lease, so that it outlives the leader.
+    async fn publish_block(&self, block_n: u64) -> anyhow::Result<bool> {
+        let lease_id = self.lease_id.as_ref().context("No lease granted")?;
+        let response: TxnResponse = self
+            .post(
+                "v3/kv/txn",
+                json!({
+                    "compare": [{ "key": BASE64_STANDARD.encode(&self.key), "result": "EQUAL", "target": "LEASE", "lease": lease_id }],
+                    "success": [{ "request_put": { "key": BASE64_STANDARD.encode(self.published_block_key()), "value": BASE64_STANDARD.encode(block_n.to_string()) } }],
+                }),
+            )
+            .await?;
+        Ok(response.succeeded)
+    }
+
+    async fn published_block(&self) -> anyhow::Result<Option<u64>> {
+        let response: RangeResponse =
+            self.post("v3/kv/range", json!({ "key": BASE64_STANDARD.encode(self.published_block_key()) })).await?;
+        let Some(kv) = response.kvs.into_iter().next() else {
+            return Ok(None);
+        };
+        let block_n = decode(kv.value).context("Decoding the published block")?;
+        Ok(Some(block_n.parse().context("Parsing the published block")?))
+    }
+
+    async fn revoke(&mut self) -> anyhow::Result<()> {
+        let lease_id = self.lease_id.take().context("No lease granted")?;
+        self.post::<serde_json::Value>("v3/lease/revoke", json!({ "ID": lease_id })).await?;
+        Ok(())
+    }
+}
+
+fn decode(value: String) -> anyhow::Result<String> {
+    Ok(String::from_utf8(BASE64_STANDARD.decode(value)?)?)
+}
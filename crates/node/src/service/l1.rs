@@ -1,78 +1,216 @@
-use crate::cli::l1::L1SyncParams;
+use crate::cli::l1::{GasPriceStrategy, L1SyncParams};
+use crate::cli::{SettlementDaMode, SettlementParams};
+use alloy::network::EthereumWallet;
 use alloy::primitives::Address;
 use anyhow::Context;
 use mc_db::{DatabaseService, MadaraBackend};
 use mc_eth::client::{EthereumClient, L1BlockMetrics};
+use mc_eth::l1_gas_price::{GasPriceBounds, GasPriceSource};
+use mc_eth::settlement::SettlementClient;
+use mc_eth::starknet_client::StarknetClient;
+use mc_eth::state_update_submission::SubmissionConfig;
+use mc_eth::strk_price::StrkPriceSource;
 use mc_mempool::GasPriceProvider;
 use mc_metrics::MetricsRegistry;
-use mp_block::H160;
+use mp_block::header::GasPrices;
+use mp_chain_config::{ChainConfig, SettlementLayer};
 use mp_convert::ToFelt;
 use mp_utils::service::Service;
 use starknet_api::core::ChainId;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::task::JoinSet;
 
+/// Interval at which sovereign chains mark their latest block as final.
+const LOCAL_FINALITY_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Clone)]
 pub struct L1SyncService {
     db_backend: Arc<MadaraBackend>,
-    eth_client: Option<EthereumClient>,
+    settlement_client: Option<Arc<dyn SettlementClient>>,
+    /// `None` when gas price sync is disabled.
+    gas_price_source: Option<GasPriceSource>,
+    gas_price_bounds: GasPriceBounds,
     l1_gas_provider: GasPriceProvider,
     chain_id: ChainId,
-    gas_price_sync_disabled: bool,
     gas_price_poll: Duration,
+    state_root_verification_interval: Option<Duration>,
+    /// Set while the local state root does not match the settled one. `None` when writes should not be halted.
+    writes_halted: Option<Arc<AtomicBool>>,
+    /// `None` when the produced blocks are not settled by this node.
+    state_update_submission: Option<(EthereumClient, EthereumWallet, SubmissionConfig)>,
+    /// Used to track L1->L2 message cancellations. `None` when not settling on Ethereum.
+    messaging_client: Option<EthereumClient>,
+    /// Sovereign chains have no settlement layer, and their blocks are final once stored locally.
+    sovereign: bool,
 }
 
 impl L1SyncService {
     pub async fn new(
         config: &L1SyncParams,
+        settlement_config: &SettlementParams,
         db: &DatabaseService,
         metrics_handle: &MetricsRegistry,
         l1_gas_provider: GasPriceProvider,
-        chain_id: ChainId,
-        l1_core_address: H160,
+        chain_config: &ChainConfig,
         authority: bool,
+        writes_halted: Arc<AtomicBool>,
     ) -> anyhow::Result<Self> {
-        let eth_client = if !config.sync_l1_disabled {
+        let (settlement_client, eth_client): (Option<Arc<dyn SettlementClient>>, _) = if config.sovereign {
+            (None, None)
+        } else if !config.sync_l1_disabled {
             if let Some(l1_rpc_url) = &config.l1_endpoint {
-                let core_address = Address::from_slice(l1_core_address.as_bytes());
                 let l1_block_metrics =
                     L1BlockMetrics::register(metrics_handle).expect("Registering prometheus metrics");
-                Some(
-                    EthereumClient::new(l1_rpc_url.clone(), core_address, l1_block_metrics)
+                match chain_config.settlement_layer {
+                    SettlementLayer::Ethereum => {
+                        if chain_config.eth_core_contract_address.is_zero() {
+                            anyhow::bail!(
+                                "Settling on Ethereum requires `eth_core_contract_address` to be set in the chain config. Chains without a settlement layer should be run with `--sovereign`."
+                            );
+                        }
+                        let core_address = Address::from_slice(chain_config.eth_core_contract_address.as_bytes());
+                        let eth_client = EthereumClient::new(
+                            l1_rpc_url.clone(),
+                            config.l1_ws_endpoint.clone(),
+                            core_address,
+                            l1_block_metrics,
+                        )
                         .await
-                        .context("Creating ethereum client")?,
-                )
+                        .context("Creating ethereum client")?;
+                        (Some(Arc::new(eth_client.clone())), Some(eth_client))
+                    }
+                    SettlementLayer::Starknet => {
+                        let core_address = chain_config.starknet_core_contract_address.context(
+                            "Settling on Starknet requires `starknet_core_contract_address` to be set in the chain config.",
+                        )?;
+                        let starknet_client = StarknetClient::new(l1_rpc_url.clone(), core_address, l1_block_metrics)
+                            .await
+                            .context("Creating starknet settlement client")?;
+                        (Some(Arc::new(starknet_client)), None)
+                    }
+                }
             } else {
                 anyhow::bail!(
-                    "No Ethereum endpoint provided. You need to provide one using --l1-endpoint <RPC URL> in order to verify the synced state or disable the l1 watcher using --no-l1-sync."
+                    "No settlement layer endpoint provided. You need to provide one using --l1-endpoint <RPC URL> in order to verify the synced state or disable the l1 watcher using --no-l1-sync."
                 );
             }
+        } else {
+            (None, None)
+        };
+
+        let messaging_client = eth_client.clone();
+
+        let state_update_submission = if settlement_config.settlement_enable {
+            if !authority {
+                anyhow::bail!(
+                    "Only sequencers can settle the blocks they produce. Remove the `--settlement-enable` argument."
+                );
+            }
+            mc_eth::state_update_submission::check_settlement_chain(&chain_config.chain_id)?;
+            let eth_client = eth_client.clone().context(
+                "Settling the produced blocks requires settling on Ethereum, with the l1 sync enabled. Remove the `--no-l1-sync` argument.",
+            )?;
+            let wallet =
+                settlement_config.key_source()?.ethereum_wallet().context("Loading the settlement private key")?;
+            let da_client = mc_da::create_da_client(&chain_config.da_layer, &settlement_config.da_client_config())
+                .context("Creating the DA layer client")?;
+            if da_client.is_some() && settlement_config.settlement_da_mode == SettlementDaMode::Blob {
+                anyhow::bail!(
+                    "Blob state updates publish the state diffs on Ethereum, which conflicts with the `da_layer` of the chain config. Remove the `--settlement-da-mode blob` argument."
+                );
+            }
+            if let Some(da_client) = &da_client {
+                log::info!("📦 State diffs will be published on {}", da_client.name());
+            }
+            let submission_config = SubmissionConfig {
+                interval: settlement_config.settlement_interval,
+                max_blocks_per_update: settlement_config.settlement_max_blocks,
+                confirmations: settlement_config.settlement_confirmations,
+                confirmation_timeout: settlement_config.settlement_confirmation_timeout,
+                da_mode: settlement_config.settlement_da_mode.into(),
+                max_blob_gas_price: settlement_config.settlement_max_blob_gas_price,
+                da_client,
+            };
+            Some((eth_client, wallet, submission_config))
         } else {
             None
         };
 
         let gas_price_sync_enabled = authority && !config.gas_price_sync_disabled;
         let gas_price_poll = config.gas_price_poll;
+        let gas_price_bounds = config.gas_price_bounds()?;
 
-        if gas_price_sync_enabled {
-            let eth_client = eth_client
-                .clone()
-                .context("L1 gas prices require the ethereum service to be enabled. Either disable gas prices syncing using `--no-gas-price-sync`, or remove the `--no-l1-sync` argument.")?;
+        let gas_price_source = if gas_price_sync_enabled {
+            let gas_price_source = match config.gas_price_strategy {
+                GasPriceStrategy::Fixed => GasPriceSource::Fixed(GasPrices {
+                    eth_l1_gas_price: config.fixed_gas_price.context("Missing `--fixed-gas-price`")?,
+                    strk_l1_gas_price: config.fixed_strk_gas_price.context("Missing `--fixed-strk-gas-price`")?,
+                    eth_l1_data_gas_price: config.fixed_data_gas_price.context("Missing `--fixed-data-gas-price`")?,
+                    strk_l1_data_gas_price: config
+                        .fixed_strk_data_gas_price
+                        .context("Missing `--fixed-strk-data-gas-price`")?,
+                }),
+                strategy @ (GasPriceStrategy::L1 | GasPriceStrategy::Oracle) => {
+                    if config.sovereign {
+                        anyhow::bail!("Sovereign chains have no L1 to sync gas prices from. Use `--gas-price-strategy fixed`, or disable gas prices syncing using `--no-gas-price-sync`.");
+                    }
+                    if chain_config.settlement_layer != SettlementLayer::Ethereum {
+                        anyhow::bail!("L1 gas prices can only be synced when settling on Ethereum. Use the fixed gas price strategy, or disable gas prices syncing using `--no-gas-price-sync`.");
+                    }
+                    let eth_client = eth_client
+                        .context("L1 gas prices require the ethereum service to be enabled. Either disable gas prices syncing using `--no-gas-price-sync`, or remove the `--no-l1-sync` argument.")?;
+                    match strategy {
+                        GasPriceStrategy::Oracle => GasPriceSource::Oracle {
+                            eth_client,
+                            oracle_address: config
+                                .gas_price_oracle_address
+                                .context("Missing `--gas-price-oracle-address`")?,
+                        },
+                        _ => {
+                            let strk_price_source = match (config.strk_price_feed_address, &config.strk_price_api_url) {
+                                (Some(feed_address), _) => {
+                                    Some(StrkPriceSource::Oracle { eth_client: eth_client.clone(), feed_address })
+                                }
+                                (None, Some(url)) => {
+                                    Some(StrkPriceSource::http(url.clone(), config.strk_price_api_json_pointer.clone()))
+                                }
+                                (None, None) => None,
+                            };
+                            GasPriceSource::L1BaseFee { eth_client, strk_price_source }
+                        }
+                    }
+                }
+            };
             // running at-least once before the block production service
-            log::info!("⏳ Getting initial L1 gas prices");
-            mc_eth::l1_gas_price::gas_price_worker_once(&eth_client, l1_gas_provider.clone(), gas_price_poll)
-                .await
-                .context("Getting initial ethereum gas prices")?;
-        }
+            log::info!("⏳ Getting initial gas prices");
+            mc_eth::l1_gas_price::gas_price_worker_once(
+                &gas_price_source,
+                &gas_price_bounds,
+                l1_gas_provider.clone(),
+                gas_price_poll,
+            )
+            .await
+            .context("Getting initial gas prices")?;
+            Some(gas_price_source)
+        } else {
+            None
+        };
 
         Ok(Self {
             db_backend: Arc::clone(db.backend()),
-            eth_client,
+            settlement_client,
+            gas_price_source,
+            gas_price_bounds,
             l1_gas_provider,
-            chain_id,
-            gas_price_sync_disabled: !gas_price_sync_enabled,
+            chain_id: chain_config.chain_id.clone(),
             gas_price_poll,
+            state_root_verification_interval: config.state_root_verification_interval,
+            writes_halted: config.halt_writes_on_state_root_mismatch.then_some(writes_halted),
+            state_update_submission,
+            messaging_client,
+            sovereign: config.sovereign,
         })
     }
 }
@@ -80,19 +218,74 @@ impl L1SyncService {
 #[async_trait::async_trait]
 impl Service for L1SyncService {
     async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
-        let L1SyncService { l1_gas_provider, chain_id, gas_price_sync_disabled, gas_price_poll, .. } = self.clone();
+        let L1SyncService { l1_gas_provider, chain_id, gas_price_poll, gas_price_source, gas_price_bounds, .. } =
+            self.clone();
+
+        if self.sovereign {
+            let db_backend = Arc::clone(&self.db_backend);
+            join_set.spawn(async move {
+                mc_eth::state_update::local_finality_worker(&db_backend, LOCAL_FINALITY_INTERVAL).await
+            });
+        }
 
-        if let Some(eth_client) = self.eth_client.take() {
+        if let Some(settlement_client) = self.settlement_client.take() {
             // enabled
 
+            if let Some(interval) = self.state_root_verification_interval {
+                let db_backend = Arc::clone(&self.db_backend);
+                let settlement_client = Arc::clone(&settlement_client);
+                let writes_halted = self.writes_halted.clone();
+                join_set.spawn(async move {
+                    mc_eth::state_root_verification::state_root_verification_worker(
+                        &db_backend,
+                        settlement_client.as_ref(),
+                        interval,
+                        writes_halted,
+                    )
+                    .await
+                });
+            }
+
+            if let Some((eth_client, wallet, submission_config)) = self.state_update_submission.take() {
+                let db_backend = Arc::clone(&self.db_backend);
+                join_set.spawn(async move {
+                    mc_eth::state_update_submission::state_update_submission_worker(
+                        &db_backend,
+                        &eth_client,
+                        wallet,
+                        submission_config,
+                    )
+                    .await
+                });
+            }
+
+            if let Some(messaging_client) = self.messaging_client.take() {
+                let db_backend = Arc::clone(&self.db_backend);
+                join_set.spawn(
+                    async move { mc_eth::l1_messaging::cancellation_sync(&db_backend, &messaging_client).await },
+                );
+            }
+
             let db_backend = Arc::clone(&self.db_backend);
             join_set.spawn(async move {
                 mc_eth::sync::l1_sync_worker(
                     &db_backend,
-                    &eth_client,
+                    settlement_client.as_ref(),
                     chain_id.to_felt(),
                     l1_gas_provider,
-                    gas_price_sync_disabled,
+                    gas_price_source.as_ref(),
+                    &gas_price_bounds,
+                    gas_price_poll,
+                )
+                .await
+            });
+        } else if let Some(gas_price_source) = gas_price_source {
+            // Gas prices that do not come from L1 can be synced without following the settlement layer.
+            join_set.spawn(async move {
+                mc_eth::l1_gas_price::gas_price_worker(
+                    &gas_price_source,
+                    &gas_price_bounds,
+                    l1_gas_provider,
                     gas_price_poll,
                 )
                 .await
@@ -101,4 +294,8 @@ impl Service for L1SyncService {
 
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "l1_sync"
+    }
 }
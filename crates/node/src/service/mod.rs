@@ -1,11 +1,21 @@
 mod block_production;
 mod gateway;
+mod ha;
 mod l1;
+mod node_metrics;
+mod prover;
+mod reload;
 mod rpc;
 mod sync;
+mod systemd;
 
 pub use block_production::BlockProductionService;
 pub use gateway::GatewayService;
+pub use ha::{HaConfig, Standby};
 pub use l1::L1SyncService;
+pub use node_metrics::NodeMetricsService;
+pub use prover::ProverService;
+pub use reload::{ReloadHandle, ReloadService};
 pub use rpc::RpcService;
 pub use sync::SyncService;
+pub use systemd::SystemdService;
@@ -0,0 +1,196 @@
+//! Node level health metrics, sampled periodically: the chain head and its lag behind the wall clock, the pending
+//! block, the L1 confirmed block, the gas prices in use, the `ExEx` restarts and the tokio runtime.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mc_mempool::{GasPriceProvider, L1DataProvider};
+use mc_metrics::{Gauge, GaugeVec, IntGaugeVec, MetricsRegistry, Opts, PrometheusError, F64};
+use mp_utils::service::{Service, ServiceStatuses};
+use mp_utils::wait_or_graceful_shutdown;
+use tokio::task::JoinSet;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug)]
+struct NodeMetrics {
+    head_block_number: Gauge<F64>,
+    head_block_timestamp: Gauge<F64>,
+    head_block_lag: Gauge<F64>,
+    pending_block_tx_count: Gauge<F64>,
+    l1_confirmed_block_number: Gauge<F64>,
+    gas_prices: GaugeVec<F64>,
+    exex_restarts: IntGaugeVec,
+    tokio_workers: Gauge<F64>,
+    tokio_scheduling_delay: Gauge<F64>,
+    #[cfg(tokio_unstable)]
+    tokio_alive_tasks: Gauge<F64>,
+    #[cfg(tokio_unstable)]
+    tokio_injection_queue_depth: Gauge<F64>,
+    #[cfg(tokio_unstable)]
+    tokio_blocking_threads: Gauge<F64>,
+}
+
+impl NodeMetrics {
+    fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            head_block_number: registry
+                .register(Gauge::new("madara_head_block_number", "Number of the latest closed block")?)?,
+            head_block_timestamp: registry
+                .register(Gauge::new("madara_head_block_timestamp", "Timestamp of the latest closed block")?)?,
+            head_block_lag: registry.register(Gauge::new(
+                "madara_head_block_lag_seconds",
+                "Seconds elapsed since the timestamp of the latest closed block",
+            )?)?,
+            pending_block_tx_count: registry.register(Gauge::new(
+                "madara_pending_block_transaction_count",
+                "Number of transactions in the pending block",
+            )?)?,
+            l1_confirmed_block_number: registry.register(Gauge::new(
+                "madara_l1_confirmed_block_number",
+                "Number of the latest block confirmed on the settlement layer",
+            )?)?,
+            gas_prices: registry.register(GaugeVec::new(
+                Opts::new("madara_gas_price", "L1 gas prices used for the blocks produced by this node"),
+                &["price"],
+            )?)?,
+            exex_restarts: registry.register(IntGaugeVec::new(
+                Opts::new("madara_exex_restarts", "Number of times the ExEx was restarted after crashing"),
+                &["exex"],
+            )?)?,
+            tokio_workers: registry
+                .register(Gauge::new("madara_tokio_workers", "Number of worker threads of the tokio runtime")?)?,
+            tokio_scheduling_delay: registry.register(Gauge::new(
+                "madara_tokio_scheduling_delay_seconds",
+                "Time taken by the tokio runtime to start polling a newly spawned task",
+            )?)?,
+            #[cfg(tokio_unstable)]
+            tokio_alive_tasks: registry
+                .register(Gauge::new("madara_tokio_alive_tasks", "Number of tasks alive in the tokio runtime")?)?,
+            #[cfg(tokio_unstable)]
+            tokio_injection_queue_depth: registry.register(Gauge::new(
+                "madara_tokio_injection_queue_depth",
+                "Number of tasks waiting in the global queue of the tokio runtime",
+            )?)?,
+            #[cfg(tokio_unstable)]
+            tokio_blocking_threads: registry.register(Gauge::new(
+                "madara_tokio_blocking_threads",
+                "Number of blocking threads spawned by the tokio runtime",
+            )?)?,
+        })
+    }
+
+    async fn sample(
+        &self,
+        backend: &MadaraBackend,
+        gas_price_provider: &GasPriceProvider,
+        exex_statuses: &ServiceStatuses,
+    ) -> anyhow::Result<()> {
+        let head = match backend.get_latest_block_n().context("Getting the latest block number")? {
+            Some(block_n) => {
+                backend.get_block_info(&DbBlockId::Number(block_n)).context("Getting the latest block info")?
+            }
+            None => None,
+        };
+        if let Some(info) = head.as_ref().and_then(|info| info.as_nonpending()) {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            self.head_block_number.set(info.header.block_number as f64);
+            self.head_block_timestamp.set(info.header.block_timestamp as f64);
+            self.head_block_lag.set(now.saturating_sub(info.header.block_timestamp) as f64);
+        }
+
+        let pending = backend.get_block_info(&DbBlockId::Pending).context("Getting the pending block info")?;
+        self.pending_block_tx_count.set(pending.map_or(0, |info| info.tx_hashes().len()) as f64);
+
+        if let Some(block_n) =
+            backend.get_l1_last_confirmed_block().context("Getting the latest l1 confirmed block number")?
+        {
+            self.l1_confirmed_block_number.set(block_n as f64);
+        }
+
+        let gas_prices = gas_price_provider.get_gas_prices();
+        for (price, value) in [
+            ("eth_l1_gas_price", gas_prices.eth_l1_gas_price),
+            ("eth_l1_data_gas_price", gas_prices.eth_l1_data_gas_price),
+            ("strk_l1_gas_price", gas_prices.strk_l1_gas_price),
+            ("strk_l1_data_gas_price", gas_prices.strk_l1_data_gas_price),
+        ] {
+            self.gas_prices.with_label_values(&[price]).set(value as f64);
+        }
+
+        for status in exex_statuses.get() {
+            self.exex_restarts.with_label_values(&[&status.name]).set(status.restarts.into());
+        }
+
+        let runtime_metrics = tokio::runtime::Handle::current().metrics();
+        self.tokio_workers.set(runtime_metrics.num_workers() as f64);
+        #[cfg(tokio_unstable)]
+        {
+            self.tokio_alive_tasks.set(runtime_metrics.active_tasks_count() as f64);
+            self.tokio_injection_queue_depth.set(runtime_metrics.injection_queue_depth() as f64);
+            self.tokio_blocking_threads.set(runtime_metrics.num_blocking_threads() as f64);
+        }
+        let spawned_at = Instant::now();
+        let delay =
+            tokio::spawn(async move { spawned_at.elapsed() }).await.context("Measuring the scheduling delay")?;
+        self.tokio_scheduling_delay.set(delay.as_secs_f64());
+
+        Ok(())
+    }
+}
+
+/// Samples the node level metrics every few seconds. Does nothing when prometheus is disabled.
+pub struct NodeMetricsService {
+    backend: Arc<MadaraBackend>,
+    gas_price_provider: GasPriceProvider,
+    exex_statuses: ServiceStatuses,
+    metrics: Option<NodeMetrics>,
+}
+
+impl NodeMetricsService {
+    pub fn new(
+        backend: Arc<MadaraBackend>,
+        gas_price_provider: GasPriceProvider,
+        exex_statuses: ServiceStatuses,
+        metrics_handle: &MetricsRegistry,
+    ) -> anyhow::Result<Self> {
+        let metrics = metrics_handle
+            .is_enabled()
+            .then(|| NodeMetrics::register(metrics_handle))
+            .transpose()
+            .context("Registering node metrics")?;
+        Ok(Self { backend, gas_price_provider, exex_statuses, metrics })
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for NodeMetricsService {
+    async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        let Some(metrics) = self.metrics.clone() else { return Ok(()) };
+        let backend = Arc::clone(&self.backend);
+        let gas_price_provider = self.gas_price_provider.clone();
+        let exex_statuses = self.exex_statuses.clone();
+
+        join_set.spawn(async move {
+            let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+            while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+                if let Err(err) = metrics.sample(&backend, &gas_price_provider, &exex_statuses).await {
+                    log::warn!("Sampling the node metrics: {err:#}");
+                }
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "node_metrics"
+    }
+
+    fn restartable(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,50 @@
+use crate::cli::ProverParams;
+use anyhow::Context;
+use mc_db::{DatabaseService, MadaraBackend};
+use mc_metrics::MetricsRegistry;
+use mc_prover::worker::{ProverMetrics, ProvingConfig, ProvingWorker};
+use mc_prover::ProverClient;
+use mp_utils::service::Service;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+#[derive(Clone)]
+pub struct ProverService {
+    db_backend: Arc<MadaraBackend>,
+    /// `None` when the blocks are not proven by this node.
+    prover: Option<(Arc<dyn ProverClient>, ProvingConfig, ProverMetrics)>,
+}
+
+impl ProverService {
+    pub fn new(config: &ProverParams, db: &DatabaseService, metrics_handle: &MetricsRegistry) -> anyhow::Result<Self> {
+        let prover = match (config.client_config(), config.proving_config()) {
+            (Some(client_config), Some(proving_config)) => {
+                let client = mc_prover::create_prover_client(&client_config)?;
+                let metrics = ProverMetrics::register(metrics_handle).context("Registering prover metrics")?;
+                Some((client, proving_config, metrics))
+            }
+            _ => None,
+        };
+        Ok(Self { db_backend: Arc::clone(db.backend()), prover })
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for ProverService {
+    async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        if let Some((client, config, metrics)) = self.prover.clone() {
+            // The jobs in progress are read back from the database, so that a restarted service resumes them.
+            let worker = ProvingWorker::new(Arc::clone(&self.db_backend), client, config, metrics)?;
+            join_set.spawn(worker.run());
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "prover"
+    }
+
+    fn restartable(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,169 @@
+//! Parameters that can be changed while the node is running. The command line and the config file are parsed again
+//! on SIGHUP, or with the `madara_reloadConfig` admin RPC method, and the reloadable parameters that changed are
+//! applied. The other parameters need a restart.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use mc_mempool::GasPriceProvider;
+use mc_rpc::madara::ConfigReloader;
+use mp_block::header::GasPrices;
+use mp_utils::channel_wait_or_graceful_shutdown;
+use mp_utils::service::Service;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+use crate::cli::l1::GasPriceStrategy;
+use crate::cli::{Cli, RunCmd};
+use crate::service::rpc::RateLimitSettings;
+
+#[derive(Debug, Clone, PartialEq)]
+struct ReloadableParams {
+    log_filter: String,
+    rpc_rate_limit: RateLimitSettings,
+    /// With the gas price bounds applied.
+    fixed_gas_prices: Option<GasPrices>,
+    pending_block_poll_interval: Duration,
+}
+
+impl ReloadableParams {
+    fn new(run_cmd: &RunCmd) -> anyhow::Result<Self> {
+        let l1 = &run_cmd.l1_sync_params;
+        let fixed_gas_prices = match (
+            l1.fixed_gas_price,
+            l1.fixed_data_gas_price,
+            l1.fixed_strk_gas_price,
+            l1.fixed_strk_data_gas_price,
+        ) {
+            (
+                Some(eth_l1_gas_price),
+                Some(eth_l1_data_gas_price),
+                Some(strk_l1_gas_price),
+                Some(strk_l1_data_gas_price),
+            ) => Some(l1.gas_price_bounds()?.apply(GasPrices {
+                eth_l1_gas_price,
+                strk_l1_gas_price,
+                eth_l1_data_gas_price,
+                strk_l1_data_gas_price,
+            })),
+            _ => None,
+        };
+
+        Ok(Self {
+            log_filter: run_cmd.logging_params.log_filter.clone(),
+            rpc_rate_limit: RateLimitSettings {
+                rate_limit: run_cmd.rpc_params.rpc_rate_limit,
+                whitelisted_ips: run_cmd.rpc_params.rpc_rate_limit_whitelisted_ips.clone(),
+            },
+            fixed_gas_prices,
+            pending_block_poll_interval: run_cmd.sync_params.pending_block_poll_interval,
+        })
+    }
+}
+
+struct ReloadHandleInner {
+    params: Mutex<ReloadableParams>,
+    rpc_rate_limit: watch::Sender<RateLimitSettings>,
+    pending_block_poll_interval: watch::Sender<Duration>,
+    /// Only set when the gas prices come from the fixed gas price strategy.
+    gas_price_provider: Option<GasPriceProvider>,
+}
+
+/// Gives the current value of the reloadable parameters to the services, and applies the new ones.
+#[derive(Clone)]
+pub struct ReloadHandle(Arc<ReloadHandleInner>);
+
+impl ReloadHandle {
+    pub fn new(run_cmd: &RunCmd, gas_price_provider: &GasPriceProvider) -> anyhow::Result<Self> {
+        let params = ReloadableParams::new(run_cmd)?;
+        let l1 = &run_cmd.l1_sync_params;
+        let fixed_gas_prices =
+            run_cmd.is_sequencer() && !l1.gas_price_sync_disabled && l1.gas_price_strategy == GasPriceStrategy::Fixed;
+
+        Ok(Self(Arc::new(ReloadHandleInner {
+            rpc_rate_limit: watch::channel(params.rpc_rate_limit.clone()).0,
+            pending_block_poll_interval: watch::channel(params.pending_block_poll_interval).0,
+            gas_price_provider: fixed_gas_prices.then(|| gas_price_provider.clone()),
+            params: Mutex::new(params),
+        })))
+    }
+
+    pub fn rpc_rate_limit(&self) -> watch::Receiver<RateLimitSettings> {
+        self.0.rpc_rate_limit.subscribe()
+    }
+
+    pub fn pending_block_poll_interval(&self) -> watch::Receiver<Duration> {
+        self.0.pending_block_poll_interval.subscribe()
+    }
+}
+
+impl ConfigReloader for ReloadHandle {
+    fn reload(&self) -> anyhow::Result<Vec<String>> {
+        let cli = Cli::try_parse_with_config_file()?;
+        let new = ReloadableParams::new(&cli.run)?;
+
+        let mut params = self.0.params.lock().expect("Poisoned lock");
+        let mut changed = vec![];
+        if new.log_filter != params.log_filter {
+            crate::util::set_log_filter(&new.log_filter);
+            changed.push("log-filter");
+        }
+        if new.rpc_rate_limit != params.rpc_rate_limit {
+            self.0.rpc_rate_limit.send_replace(new.rpc_rate_limit.clone());
+            changed.push("rpc-rate-limit");
+        }
+        if new.fixed_gas_prices != params.fixed_gas_prices {
+            if let (Some(provider), Some(gas_prices)) = (&self.0.gas_price_provider, &new.fixed_gas_prices) {
+                provider.set_gas_prices(gas_prices.clone());
+                changed.push("fixed-gas-prices");
+            }
+        }
+        if new.pending_block_poll_interval != params.pending_block_poll_interval {
+            self.0.pending_block_poll_interval.send_replace(new.pending_block_poll_interval);
+            changed.push("pending-block-poll-interval");
+        }
+        *params = new;
+
+        for name in &changed {
+            log::info!("🔄 Reloaded `{name}`");
+        }
+        Ok(changed.into_iter().map(String::from).collect())
+    }
+}
+
+/// Reloads the parameters on SIGHUP.
+pub struct ReloadService(ReloadHandle);
+
+impl ReloadService {
+    pub fn new(handle: ReloadHandle) -> Self {
+        Self(handle)
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for ReloadService {
+    async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        let handle = self.0.clone();
+        let mut sighup = signal(SignalKind::hangup()).context("Listening to SIGHUP")?;
+        join_set.spawn(async move {
+            while channel_wait_or_graceful_shutdown(sighup.recv()).await.is_some() {
+                log::info!("🔄 Received SIGHUP, reloading the parameters");
+                if let Err(err) = handle.reload() {
+                    log::error!("Reloading the parameters: {err:#}");
+                }
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "reload"
+    }
+
+    fn restartable(&self) -> bool {
+        true
+    }
+}
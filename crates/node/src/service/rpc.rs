@@ -4,38 +4,60 @@ use jsonrpsee::server::ServerHandle;
 use mp_rpc::{AddTransactionProvider, Starknet};
 use tokio::task::JoinSet;
 
-use mc_db::DatabaseService;
+use mc_block_import::BlockImporter;
+use mc_db::{DatabaseService, MadaraBackend};
+use mc_exec::metrics::BlockExecutionMetrics;
+use mc_mempool::BuilderHandle;
 use mc_metrics::MetricsRegistry;
+use mc_rpc::devnet::{Devnet, DevnetRpcApiServer};
+use mc_rpc::madara::{MadaraAdmin, MadaraAdminRpcApiServer};
 use mc_rpc::versioned_rpc_api;
 use mp_chain_config::ChainConfig;
-use mp_utils::service::Service;
+use mp_utils::service::{Service, ServiceStatuses};
 
+use api_keys::ApiKeys;
+use block_tag::BlockTagLayer;
 use metrics::RpcMetrics;
+use overload::{OverloadMonitor, OverloadThresholds};
 use server::{start_server, ServerConfig};
 
 use crate::cli::{RpcMethods, RpcParams};
+use crate::service::ReloadHandle;
 
+mod api_keys;
+mod block_tag;
 mod metrics;
 mod middleware;
+mod overload;
 mod server;
 
+pub use server::RateLimitSettings;
+
 pub struct RpcService {
     server_config: Option<ServerConfig>,
     server_handle: Option<ServerHandle>,
+    backend: Arc<MadaraBackend>,
 }
 impl RpcService {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: &RpcParams,
         db: &DatabaseService,
         chain_config: Arc<ChainConfig>,
         metrics_handle: &MetricsRegistry,
         add_txs_method_provider: Arc<dyn AddTransactionProvider>,
+        exex_statuses: ServiceStatuses,
+        service_statuses: ServiceStatuses,
+        devnet: Option<Devnet>,
+        block_builder: Option<BuilderHandle>,
+        block_importer: Arc<BlockImporter>,
+        reload_handle: &ReloadHandle,
     ) -> anyhow::Result<Self> {
         if config.rpc_disabled {
-            return Ok(Self { server_config: None, server_handle: None });
+            return Ok(Self { server_config: None, server_handle: None, backend: Arc::clone(db.backend()) });
         }
 
-        let (rpcs, _node_operator) = match (config.rpc_methods, config.rpc_external) {
+        let (rpcs, node_operator) = match (config.rpc_methods, config.rpc_external) {
             (RpcMethods::Safe, _) => (true, false),
             (RpcMethods::Unsafe, _) => (true, true),
             (RpcMethods::Auto, false) => (true, true),
@@ -48,8 +70,30 @@ impl RpcService {
             }
         };
         let (read, write, trace) = (rpcs, rpcs, rpcs);
-        let starknet = Starknet::new(Arc::clone(db.backend()), chain_config.clone(), add_txs_method_provider);
+        let starknet = Starknet::new(Arc::clone(db.backend()), chain_config.clone(), add_txs_method_provider)
+            .with_block_cache_size(config.rpc_block_cache_size);
         let metrics = RpcMetrics::register(metrics_handle)?;
+        // Recorded when tracing whole blocks.
+        BlockExecutionMetrics::get_or_register(metrics_handle)?;
+
+        let api_keys = ApiKeys::load(config.rpc_api_keys.as_deref(), config.rpc_api_key_required)?;
+
+        let mut rpc_api = versioned_rpc_api(&starknet, read, write, trace)?;
+        if node_operator {
+            let admin = MadaraAdmin {
+                backend: Arc::clone(db.backend()),
+                exex_statuses,
+                service_statuses,
+                config_reloader: Arc::new(reload_handle.clone()),
+                api_keys: Arc::new(api_keys.clone()),
+                block_builder,
+                block_importer,
+            };
+            rpc_api.merge(MadaraAdminRpcApiServer::into_rpc(admin))?;
+        }
+        if let Some(devnet) = devnet {
+            rpc_api.merge(DevnetRpcApiServer::into_rpc(devnet))?;
+        }
 
         Ok(Self {
             server_config: Some(ServerConfig {
@@ -60,14 +104,21 @@ impl RpcService {
                 max_payload_out_mb: config.rpc_max_response_size,
                 max_subs_per_conn: config.rpc_max_subscriptions_per_connection,
                 message_buffer_capacity: config.rpc_message_buffer_capacity_per_connection,
-                rpc_api: versioned_rpc_api(&starknet, read, write, trace)?,
+                rpc_api,
                 metrics,
                 cors: config.cors(),
-                rate_limit: config.rpc_rate_limit,
-                rate_limit_whitelisted_ips: config.rpc_rate_limit_whitelisted_ips.clone(),
+                rate_limit: reload_handle.rpc_rate_limit(),
                 rate_limit_trust_proxy_headers: config.rpc_rate_limit_trust_proxy_headers,
+                default_rpc_version: config.rpc_default_version,
+                api_keys,
+                overload: OverloadMonitor::new(OverloadThresholds {
+                    max_execution_wait: config.rpc_overload_max_execution_wait,
+                    max_db_latency: config.rpc_overload_max_db_latency,
+                }),
+                block_tags: BlockTagLayer::new(Arc::clone(db.backend())),
             }),
             server_handle: None,
+            backend: Arc::clone(db.backend()),
         })
     }
 }
@@ -78,8 +129,19 @@ impl Service for RpcService {
         if let Some(server_config) = &self.server_config {
             // rpc enabled
             self.server_handle = Some(start_server(server_config.clone(), join_set).await?);
+            if server_config.overload.is_enabled() {
+                join_set.spawn(server_config.overload.clone().run(Arc::clone(&self.backend)));
+            }
         }
 
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "rpc"
+    }
+
+    fn restartable(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,296 @@
+//! API keys of the RPC server. Calls made with a key are rate limited and metered with the limits of its tier,
+//! shared by all the connections using the key, instead of the limits of the anonymous traffic.
+//!
+//! The tiers and the keys are read from the `--rpc-api-keys` file, and keys can be added or removed with the
+//! `madara_setApiKey` and `madara_removeApiKey` admin RPC methods. These changes are only kept in memory: the file is
+//! not rewritten, and the node is back to the keys of the file when it restarts.
+//!
+//! ```toml
+//! [tiers.partner]
+//! rate_limit = 6000       # calls per minute
+//! daily_quota = 5000000   # calls per day, reset at midnight UTC
+//!
+//! [keys]
+//! "0b9f4c1e7a52" = "partner"
+//! ```
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use hyper::{Body, Request};
+use mc_rpc::madara::{ApiKeyInfo, ApiKeyManager};
+use serde::Deserialize;
+
+use super::middleware::RateLimit;
+
+const X_API_KEY: &str = "x-api-key";
+const API_KEY_QUERY_PARAM: &str = "api_key";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Tier {
+    /// Calls per minute, unlimited when not set.
+    rate_limit: Option<NonZeroU32>,
+    /// Calls per day, unlimited when not set.
+    daily_quota: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ApiKeysFile {
+    #[serde(default)]
+    tiers: BTreeMap<String, Tier>,
+    /// Tier of each key.
+    #[serde(default)]
+    keys: BTreeMap<String, String>,
+}
+
+/// Limits of an API key, shared by the requests made with it.
+#[derive(Debug)]
+pub struct ApiKey {
+    tier: String,
+    rate_limit: Option<RateLimit>,
+    daily_quota: Option<u64>,
+    /// Day since the unix epoch, and the number of calls made that day.
+    usage: Mutex<(u64, u64)>,
+}
+
+impl ApiKey {
+    fn new(tier_name: &str, tier: &Tier) -> Self {
+        Self {
+            tier: tier_name.into(),
+            rate_limit: tier.rate_limit.map(RateLimit::new),
+            daily_quota: tier.daily_quota,
+            usage: Mutex::new((0, 0)),
+        }
+    }
+
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.clone()
+    }
+
+    /// Counts a call made with the key. Returns false when the daily quota is used up, in which case the call is not
+    /// counted.
+    pub fn record_call(&self) -> bool {
+        self.record_call_on(today())
+    }
+
+    /// Counts a call made on `day`, in days since the unix epoch.
+    fn record_call_on(&self, day: u64) -> bool {
+        let mut usage = self.usage.lock().expect("Poisoned lock");
+        if usage.0 != day {
+            *usage = (day, 0);
+        }
+        if self.daily_quota.is_some_and(|quota| usage.1 >= quota) {
+            return false;
+        }
+        usage.1 += 1;
+        true
+    }
+
+    fn calls_on(&self, day: u64) -> u64 {
+        let usage = self.usage.lock().expect("Poisoned lock");
+        if usage.0 == day {
+            usage.1
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ApiKeysInner {
+    tiers: BTreeMap<String, Tier>,
+    keys: BTreeMap<String, Arc<ApiKey>>,
+}
+
+/// The API keys of the RPC server, which can be changed while it is running.
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeys {
+    inner: Arc<RwLock<ApiKeysInner>>,
+    /// Calls without a key are rejected.
+    required: bool,
+}
+
+/// Outcome of the authentication of a request.
+pub enum ApiKeyCheck {
+    Anonymous,
+    Key(Arc<ApiKey>),
+    /// The key is unknown, or a key is required and none was given.
+    Rejected,
+}
+
+impl ApiKeys {
+    pub fn load(path: Option<&Path>, required: bool) -> anyhow::Result<Self> {
+        anyhow::ensure!(!required || path.is_some(), "Requiring an API key requires `--rpc-api-keys`");
+        let file = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("Reading API keys file {}", path.display()))?;
+                toml::from_str(&content).with_context(|| format!("Parsing API keys file {}", path.display()))?
+            }
+            None => ApiKeysFile::default(),
+        };
+
+        let mut keys = BTreeMap::new();
+        for (key, tier_name) in file.keys {
+            let tier = file.tiers.get(&tier_name).with_context(|| format!("Unknown API key tier `{tier_name}`"))?;
+            keys.insert(key, Arc::new(ApiKey::new(&tier_name, tier)));
+        }
+        Ok(Self { inner: Arc::new(RwLock::new(ApiKeysInner { tiers: file.tiers, keys })), required })
+    }
+
+    /// Looks up the key of the request, given in the `X-Api-Key` header or in the `api_key` query parameter.
+    pub fn check(&self, req: &Request<Body>) -> ApiKeyCheck {
+        let key = req.headers().get(X_API_KEY).and_then(|v| v.to_str().ok()).or_else(|| {
+            req.uri().query()?.split('&').find_map(|param| param.strip_prefix(API_KEY_QUERY_PARAM)?.strip_prefix('='))
+        });
+        match key {
+            Some(key) => match self.inner.read().expect("Poisoned lock").keys.get(key) {
+                Some(api_key) => ApiKeyCheck::Key(Arc::clone(api_key)),
+                None => ApiKeyCheck::Rejected,
+            },
+            None if self.required => ApiKeyCheck::Rejected,
+            None => ApiKeyCheck::Anonymous,
+        }
+    }
+}
+
+impl ApiKeyManager for ApiKeys {
+    fn api_keys(&self) -> Vec<ApiKeyInfo> {
+        let inner = self.inner.read().expect("Poisoned lock");
+        inner
+            .keys
+            .iter()
+            .map(|(key, api_key)| ApiKeyInfo {
+                key: key.clone(),
+                tier: api_key.tier.clone(),
+                calls_today: api_key.calls_on(today()),
+            })
+            .collect()
+    }
+
+    fn set_api_key(&self, key: String, tier_name: &str) -> anyhow::Result<()> {
+        let mut inner = self.inner.write().expect("Poisoned lock");
+        let tier = inner.tiers.get(tier_name).with_context(|| format!("Unknown API key tier `{tier_name}`"))?;
+        let api_key = ApiKey::new(tier_name, tier);
+        // Keeps the usage of the day when the key changes tier.
+        if let Some(previous) = inner.keys.get(&key) {
+            *api_key.usage.lock().expect("Poisoned lock") = *previous.usage.lock().expect("Poisoned lock");
+        }
+        inner.keys.insert(key, Arc::new(api_key));
+        Ok(())
+    }
+
+    fn remove_api_key(&self, key: &str) -> bool {
+        self.inner.write().expect("Poisoned lock").keys.remove(key).is_some()
+    }
+}
+
+/// Days since the unix epoch, changing at midnight UTC.
+fn today() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / SECONDS_PER_DAY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const API_KEYS: &str = r#"
+        [tiers.partner]
+        rate_limit = 60
+        daily_quota = 2
+
+        [tiers.free]
+
+        [keys]
+        "partner-key" = "partner"
+        "free-key" = "free"
+    "#;
+
+    fn load(content: &str, required: bool) -> anyhow::Result<ApiKeys> {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("api_keys.toml");
+        std::fs::write(&path, content).unwrap();
+        ApiKeys::load(Some(&path), required)
+    }
+
+    fn request(uri: &str, header: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(key) = header {
+            builder = builder.header(X_API_KEY, key);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_load_unknown_tier() {
+        let err = load("[keys]\n\"key\" = \"gold\"\n", false).unwrap_err();
+        assert!(format!("{err:#}").contains("Unknown API key tier `gold`"), "{err:#}");
+    }
+
+    #[test]
+    fn test_load_required_without_file() {
+        assert!(ApiKeys::load(None, true).is_err());
+        assert!(ApiKeys::load(None, false).unwrap().api_keys().is_empty());
+    }
+
+    #[rstest]
+    #[case::header("/", Some("partner-key"), false, "partner")]
+    #[case::query("/?id=1&api_key=free-key", None, false, "free")]
+    #[case::header_over_query("/?api_key=free-key", Some("partner-key"), false, "partner")]
+    #[case::anonymous("/", None, false, "anonymous")]
+    #[case::required("/", None, true, "rejected")]
+    #[case::required_with_key("/", Some("free-key"), true, "free")]
+    #[case::unknown_key("/", Some("other-key"), false, "rejected")]
+    #[case::unknown_query_key("/?api_key=other-key", None, false, "rejected")]
+    #[case::other_query_param("/?api_keys=free-key", None, false, "anonymous")]
+    fn test_check(#[case] uri: &str, #[case] header: Option<&str>, #[case] required: bool, #[case] expected: &str) {
+        let api_keys = load(API_KEYS, required).unwrap();
+        let outcome = match api_keys.check(&request(uri, header)) {
+            ApiKeyCheck::Key(api_key) => api_key.tier.clone(),
+            ApiKeyCheck::Anonymous => "anonymous".into(),
+            ApiKeyCheck::Rejected => "rejected".into(),
+        };
+        assert_eq!(outcome, expected);
+    }
+
+    #[test]
+    fn test_record_call_quota_and_day_rollover() {
+        let api_key = ApiKey::new("partner", &Tier { rate_limit: None, daily_quota: Some(2) });
+        assert!(api_key.record_call_on(10));
+        assert!(api_key.record_call_on(10));
+        assert!(!api_key.record_call_on(10));
+        assert_eq!(api_key.calls_on(10), 2);
+
+        // The quota is reset the next day.
+        assert_eq!(api_key.calls_on(11), 0);
+        assert!(api_key.record_call_on(11));
+        assert_eq!(api_key.calls_on(11), 1);
+
+        let unlimited = ApiKey::new("free", &Tier { rate_limit: None, daily_quota: None });
+        assert!((0..100).all(|_| unlimited.record_call_on(10)));
+    }
+
+    #[test]
+    fn test_set_api_key_keeps_usage() {
+        let api_keys = load(API_KEYS, false).unwrap();
+        let ApiKeyCheck::Key(api_key) = api_keys.check(&request("/", Some("free-key"))) else { panic!("Unknown key") };
+        assert!(api_key.record_call());
+
+        assert!(api_keys.set_api_key("new-key".into(), "gold").is_err());
+        api_keys.set_api_key("free-key".into(), "partner").unwrap();
+        let info = api_keys.api_keys().into_iter().find(|info| info.key == "free-key").unwrap();
+        assert_eq!(info, ApiKeyInfo { key: "free-key".into(), tier: "partner".into(), calls_today: 1 });
+
+        assert!(api_keys.remove_api_key("free-key"));
+        assert!(!api_keys.remove_api_key("free-key"));
+        assert!(matches!(api_keys.check(&request("/", Some("free-key"))), ApiKeyCheck::Rejected));
+    }
+}
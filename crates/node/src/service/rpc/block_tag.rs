@@ -0,0 +1,188 @@
+//! The `l1_accepted` block tag, resolving to the last block confirmed on L1, as tracked by the L1 sync service. The
+//! read methods take the block ids of the Starknet specs, which only define the `latest` and `pending` tags, so the
+//! tag is replaced with the number of the block in the params of the calls before they are dispatched.
+//!
+//! Only the block id params of the methods taking one are replaced, so that the tag is left untouched in calldata or
+//! any other param.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use futures::future::{BoxFuture, FutureExt};
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::types::{ErrorObjectOwned, Request};
+use jsonrpsee::MethodResponse;
+use serde_json::value::RawValue;
+use serde_json::{json, Value};
+
+use mc_db::MadaraBackend;
+use mp_rpc::errors::StarknetRpcApiError;
+
+pub const L1_ACCEPTED_TAG: &str = "l1_accepted";
+
+#[derive(Debug, Clone)]
+pub struct BlockTagLayer {
+    backend: Arc<MadaraBackend>,
+}
+
+impl BlockTagLayer {
+    pub fn new(backend: Arc<MadaraBackend>) -> Self {
+        Self { backend }
+    }
+}
+
+impl<S> tower::Layer<S> for BlockTagLayer {
+    type Service = BlockTagMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        BlockTagMiddleware { service, backend: Arc::clone(&self.backend) }
+    }
+}
+
+pub struct BlockTagMiddleware<S> {
+    service: S,
+    backend: Arc<MadaraBackend>,
+}
+
+impl<S> BlockTagMiddleware<S> {
+    /// Returns `None` when no block id param is the `l1_accepted` tag, or when the params are not valid JSON, the
+    /// method rejects them then.
+    fn resolve_l1_accepted(&self, method: &str, params: &RawValue) -> Result<Option<Box<RawValue>>, ErrorObjectOwned> {
+        let Ok(mut params) = serde_json::from_str::<Value>(params.get()) else {
+            return Ok(None);
+        };
+        let tags: Vec<_> =
+            block_ids_mut(method, &mut params).into_iter().filter(|id| id.as_str() == Some(L1_ACCEPTED_TAG)).collect();
+        if tags.is_empty() {
+            return Ok(None);
+        }
+        let block_n = self
+            .backend
+            .get_l1_last_confirmed_block()
+            .map_err(|err| StarknetRpcApiError::ErrUnexpectedError {
+                data: format!("Getting the last block confirmed on L1: {err:#}"),
+            })?
+            .ok_or(StarknetRpcApiError::BlockNotFound)?;
+        for tag in tags {
+            *tag = json!({ "block_number": block_n });
+        }
+        Ok(RawValue::from_string(params.to_string()).ok())
+    }
+}
+
+impl<'a, S> RpcServiceT<'a> for BlockTagMiddleware<S>
+where
+    S: Send + Sync + RpcServiceT<'a> + Clone + 'static,
+{
+    type Future = BoxFuture<'a, MethodResponse>;
+
+    fn call(&self, mut req: Request<'a>) -> Self::Future {
+        if let Some(params) = req.params.as_deref().filter(|params| params.get().contains(L1_ACCEPTED_TAG)) {
+            match self.resolve_l1_accepted(req.method_name(), params) {
+                Ok(Some(params)) => req.params = Some(Cow::Owned(params)),
+                Ok(None) => {}
+                Err(err) => return futures::future::ready(MethodResponse::error(req.id, err)).boxed(),
+            }
+        }
+        let service = self.service.clone();
+        async move { service.call(req).await }.boxed()
+    }
+}
+
+/// Position of the `block_id` param of the Starknet methods taking one, by the name of the method without its
+/// namespace and version.
+fn block_id_position(name: &str) -> Option<usize> {
+    match name {
+        "getBlockWithTxHashes"
+        | "getBlockWithTxs"
+        | "getBlockWithReceipts"
+        | "getBlockTransactionCount"
+        | "getStateUpdate"
+        | "getTransactionByBlockIdAndIndex"
+        | "getClass"
+        | "getClassAt"
+        | "getClassHashAt"
+        | "getNonce"
+        | "simulateTransactions"
+        | "traceBlockTransactions" => Some(0),
+        "call" | "estimateMessageFee" => Some(1),
+        "getStorageAt" | "estimateFee" => Some(2),
+        _ => None,
+    }
+}
+
+/// The param `name`, at `position` when the params are passed by position.
+fn param_mut<'a>(params: &'a mut Value, name: &str, position: usize) -> Option<&'a mut Value> {
+    match params {
+        Value::Array(params) => params.get_mut(position),
+        Value::Object(params) => params.get_mut(name),
+        _ => None,
+    }
+}
+
+/// The block ids in the params of `method`. They are the `block_id` param, and the bounds of the filter of
+/// `starknet_getEvents`.
+fn block_ids_mut<'a>(method: &str, params: &'a mut Value) -> Vec<&'a mut Value> {
+    let Some(method) = method.strip_prefix("starknet_") else {
+        return vec![];
+    };
+    let name = method.rsplit('_').next().unwrap_or(method);
+    if name == "getEvents" {
+        let Some(Value::Object(filter)) = param_mut(params, "filter", 0) else {
+            return vec![];
+        };
+        return filter
+            .iter_mut()
+            .filter(|(key, _)| matches!(key.as_str(), "from_block" | "to_block"))
+            .map(|(_, block_id)| block_id)
+            .collect();
+    }
+    block_id_position(name).and_then(|position| param_mut(params, "block_id", position)).into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_chain_config::ChainConfig;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::positional("starknet_V0_7_1_getBlockWithTxs", json!(["l1_accepted"]), 1)]
+    #[case::named("starknet_V0_7_1_getNonce", json!({ "contract_address": "0x1", "block_id": "l1_accepted" }), 1)]
+    #[case::last_position("starknet_V0_7_1_getStorageAt", json!(["0x1", "0x2", "l1_accepted"]), 1)]
+    #[case::unversioned("starknet_getStateUpdate", json!(["l1_accepted"]), 1)]
+    #[case::other_tag("starknet_V0_7_1_getBlockWithTxs", json!(["latest"]), 0)]
+    #[case::calldata("starknet_V0_7_1_call", json!([{ "calldata": ["l1_accepted"] }, "latest"]), 0)]
+    #[case::calldata_and_block_id("starknet_V0_7_1_call", json!([{ "calldata": ["l1_accepted"] }, "l1_accepted"]), 1)]
+    #[case::events(
+        "starknet_V0_7_1_getEvents",
+        json!({ "filter": { "from_block": "l1_accepted", "to_block": "l1_accepted", "keys": [["l1_accepted"]] } }),
+        2
+    )]
+    #[case::no_block_id("starknet_V0_7_1_getTransactionByHash", json!(["l1_accepted"]), 0)]
+    #[case::other_namespace("madara_V0_1_0_getBlockWithTxs", json!(["l1_accepted"]), 0)]
+    fn test_l1_accepted_block_ids(#[case] method: &str, #[case] mut params: Value, #[case] count: usize) {
+        let block_ids = block_ids_mut(method, &mut params);
+        assert_eq!(block_ids.into_iter().filter(|id| id.as_str() == Some(L1_ACCEPTED_TAG)).count(), count);
+    }
+
+    #[test]
+    fn test_resolve_l1_accepted() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let middleware = BlockTagMiddleware { service: (), backend: Arc::clone(&backend) };
+        let calldata = RawValue::from_string(
+            json!([{ "contract_address": "0x1", "entry_point_selector": "0x2", "calldata": ["l1_accepted"] }, "latest"])
+                .to_string(),
+        )
+        .unwrap();
+        let block_id = RawValue::from_string(json!(["l1_accepted"]).to_string()).unwrap();
+
+        // The tag in the calldata is not a block id, the call does not need a block confirmed on L1.
+        assert!(middleware.resolve_l1_accepted("starknet_V0_7_1_call", &calldata).unwrap().is_none());
+        assert!(middleware.resolve_l1_accepted("starknet_V0_7_1_getBlockWithTxs", &block_id).is_err());
+
+        backend.write_last_confirmed_block(3).unwrap();
+        let params = middleware.resolve_l1_accepted("starknet_V0_7_1_getBlockWithTxs", &block_id).unwrap().unwrap();
+        assert_eq!(serde_json::from_str::<Value>(params.get()).unwrap(), json!([{ "block_number": 3 }]));
+    }
+}
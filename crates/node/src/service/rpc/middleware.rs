@@ -20,6 +20,10 @@ use serde_json::{json, Value};
 use tower::{Layer, Service};
 
 use mp_chain_config::{RpcVersion, RpcVersionError};
+use mp_rpc::{with_request_id, with_request_id_sync};
+
+use super::api_keys::ApiKey;
+use super::overload::OverloadMonitor;
 
 pub use super::metrics::{Metrics, RpcMetrics};
 
@@ -44,6 +48,9 @@ const MAX_RETRIES: usize = 10;
 pub struct MiddlewareLayer {
     rate_limit: Option<RateLimit>,
     metrics: Option<Metrics>,
+    api_key: Option<Arc<ApiKey>>,
+    request_id: Option<Arc<str>>,
+    overload: Option<OverloadMonitor>,
 }
 
 impl MiddlewareLayer {
@@ -53,12 +60,32 @@ impl MiddlewareLayer {
 
     /// Enable new rate limit middleware enforced per minute.
     pub fn with_rate_limit_per_minute(self, n: NonZeroU32) -> Self {
-        Self { rate_limit: Some(RateLimit::new(n)), metrics: self.metrics }
+        self.with_rate_limit(RateLimit::new(n))
+    }
+
+    /// Enable a rate limit middleware, which can be shared with other connections.
+    pub fn with_rate_limit(self, rate_limit: RateLimit) -> Self {
+        Self { rate_limit: Some(rate_limit), ..self }
     }
 
     /// Enable metrics middleware.
     pub fn with_metrics(self, metrics: Metrics) -> Self {
-        Self { rate_limit: self.rate_limit, metrics: Some(metrics) }
+        Self { metrics: Some(metrics), ..self }
+    }
+
+    /// Meter the calls against the daily quota of an API key.
+    pub fn with_api_key(self, api_key: Arc<ApiKey>) -> Self {
+        Self { api_key: Some(api_key), ..self }
+    }
+
+    /// Attach the id of the HTTP request, or of the websocket connection, to the logs of the calls.
+    pub fn with_request_id(self, request_id: Arc<str>) -> Self {
+        Self { request_id: Some(request_id), ..self }
+    }
+
+    /// Reject the low priority calls while the node is overloaded.
+    pub fn with_overload(self, overload: OverloadMonitor) -> Self {
+        Self { overload: Some(overload), ..self }
     }
 
     /// Register a new websocket connection.
@@ -80,7 +107,14 @@ impl<S> tower::Layer<S> for MiddlewareLayer {
     type Service = Middleware<S>;
 
     fn layer(&self, service: S) -> Self::Service {
-        Middleware { service, rate_limit: self.rate_limit.clone(), metrics: self.metrics.clone() }
+        Middleware {
+            service,
+            rate_limit: self.rate_limit.clone(),
+            metrics: self.metrics.clone(),
+            api_key: self.api_key.clone(),
+            request_id: self.request_id.clone(),
+            overload: self.overload.clone(),
+        }
     }
 }
 
@@ -88,6 +122,9 @@ pub struct Middleware<S> {
     service: S,
     rate_limit: Option<RateLimit>,
     metrics: Option<Metrics>,
+    api_key: Option<Arc<ApiKey>>,
+    request_id: Option<Arc<str>>,
+    overload: Option<OverloadMonitor>,
 }
 
 impl<'a, S> RpcServiceT<'a> for Middleware<S>
@@ -106,10 +143,27 @@ where
         let service = self.service.clone();
         let rate_limit = self.rate_limit.clone();
         let metrics = self.metrics.clone();
+        let api_key = self.api_key.clone();
+        let request_id = self.request_id.clone();
+        let overload = self.overload.clone();
 
         async move {
             let mut is_rate_limited = false;
 
+            if overload.as_ref().is_some_and(|overload| overload.should_shed(req.method_name())) {
+                return MethodResponse::error(
+                    req.id,
+                    ErrorObject::owned(-32996, "Server overloaded, retry later", None::<()>),
+                );
+            }
+
+            if api_key.as_ref().is_some_and(|api_key| !api_key.record_call()) {
+                return MethodResponse::error(
+                    req.id,
+                    ErrorObject::owned(-32998, "API key daily quota exceeded", None::<()>),
+                );
+            }
+
             if let Some(limit) = rate_limit.as_ref() {
                 let mut attempts = 0;
                 let jitter = Jitter::up_to(MAX_JITTER);
@@ -133,7 +187,13 @@ where
                 }
             }
 
-            let rp = service.call(req.clone()).await;
+            let rp = match request_id.clone() {
+                Some(request_id) => {
+                    let fut = with_request_id_sync(Arc::clone(&request_id), || service.call(req.clone()));
+                    with_request_id(request_id, fut).await
+                }
+                None => service.call(req.clone()).await,
+            };
 
             let method = req.method_name();
             let status = rp.as_error_code().unwrap_or(200);
@@ -145,7 +205,8 @@ where
                 method = method,
                 status = status,
                 res_len = res_len,
-                response_time = response_time.as_micros();
+                response_time = response_time.as_micros(),
+                request_id = request_id.as_deref().unwrap_or_default();
                 "{method} {status} {res_len} - {response_time:?}",
             );
 
@@ -162,6 +223,8 @@ where
 #[derive(Clone)]
 pub struct VersionMiddleware<S> {
     inner: S,
+    /// Version served on the paths without a version.
+    default_version: RpcVersion,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -193,19 +256,27 @@ impl From<RpcVersionError> for VersionMiddlewareError {
 }
 
 impl<S> VersionMiddleware<S> {
-    pub fn new(inner: S) -> Self {
-        Self { inner }
+    pub fn new(inner: S, default_version: RpcVersion) -> Self {
+        Self { inner, default_version }
     }
 }
 
 #[derive(Clone)]
-pub struct VersionMiddlewareLayer;
+pub struct VersionMiddlewareLayer {
+    default_version: RpcVersion,
+}
+
+impl VersionMiddlewareLayer {
+    pub fn new(default_version: RpcVersion) -> Self {
+        Self { default_version }
+    }
+}
 
 impl<S> Layer<S> for VersionMiddlewareLayer {
     type Service = VersionMiddleware<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        VersionMiddleware::new(inner)
+        VersionMiddleware::new(inner, self.default_version)
     }
 }
 
@@ -224,9 +295,10 @@ where
 
     fn call(&mut self, mut req: hyper::Request<Body>) -> Self::Future {
         let mut inner = self.inner.clone();
+        let default_version = self.default_version;
 
         Box::pin(async move {
-            match add_rpc_version_to_method(&mut req).await {
+            match add_rpc_version_to_method(&mut req, default_version).await {
                 Ok(()) => inner.call(req).await,
                 Err(e) => {
                     let error = match e {
@@ -262,17 +334,26 @@ where
     }
 }
 
-async fn add_rpc_version_to_method(req: &mut hyper::Request<Body>) -> Result<(), VersionMiddlewareError> {
+const MADARA_NAMESPACE_PREFIX: &str = "madara_";
+
+async fn add_rpc_version_to_method(
+    req: &mut hyper::Request<Body>,
+    default_version: RpcVersion,
+) -> Result<(), VersionMiddlewareError> {
     let path = req.uri().path().to_string();
-    let version = RpcVersion::from_request_path(&path)?;
+    let version = RpcVersion::from_request_path_or(&path, default_version)?;
 
     let whole_body = hyper::body::to_bytes(req.body_mut()).await?;
     let mut json: Value = serde_json::from_slice(&whole_body)?;
 
     if let Some(method) = json.get_mut("method").as_deref().and_then(Value::as_str) {
-        let new_method = format!("starknet_{}_{}", version.name(), method.strip_prefix("starknet_").unwrap_or(method));
+        // Madara specific methods are not versioned.
+        if !method.starts_with(MADARA_NAMESPACE_PREFIX) {
+            let new_method =
+                format!("starknet_{}_{}", version.name(), method.strip_prefix("starknet_").unwrap_or(method));
 
-        json["method"] = Value::String(new_method);
+            json["method"] = Value::String(new_method);
+        }
     } else {
         return Err(VersionMiddlewareError::InvalidRequestFormat);
     }
@@ -0,0 +1,128 @@
+//! Load shedding of the RPC server. The node is considered overloaded when the RPC calls executing transactions wait
+//! too long for a thread of the execution pool, or when reading from the database gets too slow. While it is, the
+//! low priority calls are rejected with a retryable error, so that the RPC traffic does not starve block production
+//! and sync.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mc_db::MadaraBackend;
+use mp_block::{BlockId, BlockTag};
+use mp_utils::{execution_queue_depth, execution_queue_wait, wait_or_graceful_shutdown};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Calls still served when the node is overloaded: the submission of transactions, and the cheap methods clients
+/// need to follow the chain and their transactions.
+const HIGH_PRIORITY_METHODS: &[&str] = &[
+    "addInvokeTransaction",
+    "addDeclareTransaction",
+    "addDeployAccountTransaction",
+    "addInvokeTransactionWithSimulation",
+    "blockNumber",
+    "blockHashAndNumber",
+    "chainId",
+    "specVersion",
+    "syncing",
+    "getNonce",
+    "getTransactionStatus",
+    "exexStatus",
+    "serviceStatus",
+    "reloadConfig",
+];
+
+#[derive(Debug, Clone)]
+pub struct OverloadThresholds {
+    /// Average time the execution tasks wait for a thread.
+    pub max_execution_wait: Option<Duration>,
+    /// Time taken to read the latest block header from the database.
+    pub max_db_latency: Option<Duration>,
+}
+
+/// Whether the node is overloaded, sampled in the background.
+#[derive(Debug, Clone)]
+pub struct OverloadMonitor {
+    thresholds: OverloadThresholds,
+    overloaded: Arc<AtomicBool>,
+}
+
+impl OverloadMonitor {
+    pub fn new(thresholds: OverloadThresholds) -> Self {
+        Self { thresholds, overloaded: Default::default() }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.thresholds.max_execution_wait.is_some() || self.thresholds.max_db_latency.is_some()
+    }
+
+    /// Whether the call should be rejected. Methods are versioned by the time they reach the middleware, such as
+    /// `starknet_V0_7_1_call`, so they are matched on their name without the namespace and version.
+    pub fn should_shed(&self, method: &str) -> bool {
+        let name = method.rsplit('_').next().unwrap_or(method);
+        self.overloaded.load(Ordering::Relaxed) && !HIGH_PRIORITY_METHODS.contains(&name)
+    }
+
+    pub async fn run(self, backend: Arc<MadaraBackend>) -> anyhow::Result<()> {
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+            // The average wait is only updated when a task starts, it is stale once the queue is empty.
+            let execution_wait = if execution_queue_depth() > 0 { execution_queue_wait() } else { Duration::ZERO };
+            let db_latency = match self.thresholds.max_db_latency {
+                Some(_) => match sample_db_latency(Arc::clone(&backend)).await {
+                    Ok(db_latency) => db_latency,
+                    Err(err) => {
+                        log::error!("Sampling the database latency: {err:#}");
+                        continue;
+                    }
+                },
+                None => Duration::ZERO,
+            };
+
+            let overloaded = self.thresholds.max_execution_wait.is_some_and(|max| execution_wait > max)
+                || self.thresholds.max_db_latency.is_some_and(|max| db_latency > max);
+            let was_overloaded = self.overloaded.swap(overloaded, Ordering::Relaxed);
+            if overloaded && !was_overloaded {
+                log::warn!(
+                    "RPC overloaded (execution wait {execution_wait:?}, database latency {db_latency:?}), shedding \
+                     the low priority calls"
+                );
+            } else if !overloaded && was_overloaded {
+                log::info!("🌐 RPC no longer overloaded, serving all the calls");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Time taken to read the latest block header, on a blocking thread.
+async fn sample_db_latency(backend: Arc<MadaraBackend>) -> anyhow::Result<Duration> {
+    tokio::task::spawn_blocking(move || -> anyhow::Result<Duration> {
+        let started = Instant::now();
+        backend.get_block_info(&BlockId::Tag(BlockTag::Latest))?;
+        Ok(started.elapsed())
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::versioned("starknet_V0_7_1_call", true)]
+    #[case::unversioned("starknet_call", true)]
+    #[case::high_priority("starknet_V0_7_1_blockNumber", false)]
+    #[case::add_transaction("starknet_V0_7_1_addInvokeTransaction", false)]
+    #[case::other_namespace("madara_V0_1_0_serviceStatus", false)]
+    #[case::no_namespace("getNonce", false)]
+    #[case::suffix("starknet_V0_7_1_getNonceAt", true)]
+    fn test_should_shed(#[case] method: &str, #[case] shed: bool) {
+        let monitor = OverloadMonitor::new(OverloadThresholds { max_execution_wait: None, max_db_latency: None });
+        assert!(!monitor.should_shed(method));
+        monitor.overloaded.store(true, Ordering::Relaxed);
+        assert_eq!(monitor.should_shed(method), shed);
+    }
+}
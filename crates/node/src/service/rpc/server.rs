@@ -2,13 +2,13 @@
 #![allow(clippy::borrow_interior_mutable_const)]
 
 use std::convert::Infallible;
-use std::net::{IpAddr, SocketAddr};
+use std::net::SocketAddr;
 use std::num::NonZeroU32;
-use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Context;
-use forwarded_header_value::ForwardedHeaderValue;
+use futures::FutureExt;
 use hyper::header::{HeaderName, HeaderValue};
 use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
@@ -18,18 +18,27 @@ use jsonrpsee::core::id_providers::RandomStringIdProvider;
 use jsonrpsee::server::middleware::http::HostFilterLayer;
 use jsonrpsee::server::middleware::rpc::RpcServiceBuilder;
 use jsonrpsee::server::{stop_channel, ws, BatchRequestConfig, PingConfig, StopHandle, TowerServiceBuilder};
+use jsonrpsee::types::ErrorObject;
 use jsonrpsee::{Methods, RpcModule};
 use tokio::net::TcpListener;
+use tokio::sync::watch;
 use tokio::task::JoinSet;
 use tower::Service;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 
+use mp_chain_config::RpcVersion;
+use mp_utils::http::get_proxy_ip;
 use mp_utils::wait_or_graceful_shutdown;
 
-use super::middleware::{Metrics, MiddlewareLayer, RpcMetrics, VersionMiddlewareLayer};
+use super::api_keys::{ApiKeyCheck, ApiKeys};
+use super::block_tag::BlockTagLayer;
+use super::middleware::{Metrics, MiddlewareLayer, RateLimit, RpcMetrics, VersionMiddlewareLayer};
+use super::overload::OverloadMonitor;
 
 const MEGABYTE: u32 = 1024 * 1024;
 
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 /// RPC server configuration.
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
@@ -44,12 +53,23 @@ pub struct ServerConfig {
     pub rpc_api: RpcModule<()>,
     /// Batch request config.
     pub batch_config: BatchRequestConfig,
+    /// Rate limit, which can be changed while the server is running.
+    pub rate_limit: watch::Receiver<RateLimitSettings>,
+    /// Trust proxy headers for rate limiting.
+    pub rate_limit_trust_proxy_headers: bool,
+    /// RPC version served on the paths without a version.
+    pub default_rpc_version: RpcVersion,
+    pub api_keys: ApiKeys,
+    pub overload: OverloadMonitor,
+    pub block_tags: BlockTagLayer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitSettings {
     /// Rate limit calls per minute.
     pub rate_limit: Option<NonZeroU32>,
     /// Disable rate limit for certain ips.
-    pub rate_limit_whitelisted_ips: Vec<IpNetwork>,
-    /// Trust proxy headers for rate limiting.
-    pub rate_limit_trust_proxy_headers: bool,
+    pub whitelisted_ips: Vec<IpNetwork>,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +77,7 @@ struct PerConnection<RpcMiddleware, HttpMiddleware> {
     methods: Methods,
     stop_handle: StopHandle,
     metrics: RpcMetrics,
+    block_tags: BlockTagLayer,
     service_builder: TowerServiceBuilder<RpcMiddleware, HttpMiddleware>,
 }
 
@@ -77,8 +98,11 @@ pub async fn start_server(
         message_buffer_capacity,
         rpc_api,
         rate_limit,
-        rate_limit_whitelisted_ips,
         rate_limit_trust_proxy_headers,
+        default_rpc_version,
+        api_keys,
+        overload,
+        block_tags,
     } = config;
 
     let std_listener = TcpListener::bind(addr)
@@ -92,7 +116,7 @@ pub async fn start_server(
 		.option_layer(host_filter)
 		// Proxy `GET /health` requests to internal `system_health` method.
 		// .layer(ProxyGetRequestLayer::new("/health", "system_health")?)
-        .layer(VersionMiddlewareLayer)
+        .layer(VersionMiddlewareLayer::new(default_rpc_version))
 		.layer(try_into_cors(cors.as_ref())?);
 
     let builder = jsonrpsee::server::Server::builder()
@@ -116,20 +140,36 @@ pub async fn start_server(
         methods: build_rpc_api(rpc_api).into(),
         service_builder: builder.to_service_builder(),
         metrics,
+        block_tags,
         stop_handle: stop_handle.clone(),
     };
 
     let make_service = make_service_fn(move |addr: &AddrStream| {
         let cfg = cfg.clone();
-        let rate_limit_whitelisted_ips = rate_limit_whitelisted_ips.clone();
+        let rate_limit = rate_limit.clone();
+        let api_keys = api_keys.clone();
+        let overload = overload.clone();
         let ip = addr.remote_addr().ip();
 
         async move {
             let cfg = cfg.clone();
-            let rate_limit_whitelisted_ips = rate_limit_whitelisted_ips.clone();
+            let rate_limit = rate_limit.clone();
 
             Ok::<_, Infallible>(service_fn(move |req| {
                 let proxy_ip = if rate_limit_trust_proxy_headers { get_proxy_ip(&req) } else { None };
+                let request_id = request_id(&req);
+
+                let RateLimitSettings { rate_limit, whitelisted_ips: rate_limit_whitelisted_ips } =
+                    rate_limit.borrow().clone();
+                let api_key = match api_keys.check(&req) {
+                    ApiKeyCheck::Anonymous => None,
+                    ApiKeyCheck::Key(api_key) => Some(api_key),
+                    ApiKeyCheck::Rejected if req.uri().path() != "/health" => {
+                        log::debug!(target: "rpc", "ip={ip}, proxy_ip={:?}, request_id={request_id} has an invalid or missing API key", proxy_ip);
+                        return async move { Ok(with_request_id_header(unauthorized(), &request_id)) }.boxed();
+                    }
+                    ApiKeyCheck::Rejected => None,
+                };
 
                 let rate_limit_cfg = if rate_limit_whitelisted_ips
                     .iter()
@@ -137,32 +177,41 @@ pub async fn start_server(
                 {
                     log::debug!(target: "rpc", "ip={ip}, proxy_ip={:?} is trusted, disabling rate-limit", proxy_ip);
                     None
+                } else if let Some(api_key) = &api_key {
+                    // The rate limit of the tier of the key, shared with the other connections using the key.
+                    api_key.rate_limit()
                 } else {
                     if !rate_limit_whitelisted_ips.is_empty() {
                         log::debug!(target: "rpc", "ip={ip}, proxy_ip={:?} is not trusted, rate-limit enabled", proxy_ip);
                     }
-                    rate_limit
+                    rate_limit.map(RateLimit::new)
                 };
 
-                let PerConnection { service_builder, metrics, stop_handle, methods } = cfg.clone();
+                let PerConnection { service_builder, metrics, block_tags, stop_handle, methods } = cfg.clone();
 
                 let is_websocket = ws::is_upgrade_request(&req);
                 let transport_label = if is_websocket { "ws" } else { "http" };
 
-                let middleware_layer = match rate_limit_cfg {
-                    None => MiddlewareLayer::new().with_metrics(Metrics::new(metrics, transport_label)),
-                    Some(rate_limit) => MiddlewareLayer::new()
-                        .with_metrics(Metrics::new(metrics, transport_label))
-                        .with_rate_limit_per_minute(rate_limit),
-                };
+                let mut middleware_layer = MiddlewareLayer::new()
+                    .with_metrics(Metrics::new(metrics, transport_label))
+                    .with_request_id(Arc::clone(&request_id));
+                if let Some(rate_limit) = rate_limit_cfg {
+                    middleware_layer = middleware_layer.with_rate_limit(rate_limit);
+                }
+                if let Some(api_key) = api_key {
+                    middleware_layer = middleware_layer.with_api_key(api_key);
+                }
+                if overload.is_enabled() {
+                    middleware_layer = middleware_layer.with_overload(overload.clone());
+                }
 
-                let rpc_middleware = RpcServiceBuilder::new().layer(middleware_layer.clone());
+                let rpc_middleware = RpcServiceBuilder::new().layer(middleware_layer.clone()).layer(block_tags);
 
                 let mut svc = service_builder.set_rpc_middleware(rpc_middleware).build(methods, stop_handle);
 
                 async move {
-                    if req.uri().path() == "/health" {
-                        Ok(Response::builder().status(StatusCode::OK).body(Body::from("OK"))?)
+                    let response = if req.uri().path() == "/health" {
+                        Response::builder().status(StatusCode::OK).body(Body::from("OK"))?
                     } else {
                         if is_websocket {
                             let on_disconnect = svc.on_session_closed();
@@ -176,9 +225,11 @@ pub async fn start_server(
                             });
                         }
 
-                        svc.call(req).await
-                    }
+                        svc.call(req).await?
+                    };
+                    Ok::<_, BoxError>(with_request_id_header(response, &request_id))
                 }
+                .boxed()
             }))
         }
     });
@@ -189,7 +240,7 @@ pub async fn start_server(
 
     join_set.spawn(async move {
         log::info!(
-            "📱 Running JSON-RPC server at {} (allowed origins={})",
+            "📱 Running JSON-RPC server at {} (allowed origins={}, default version={default_rpc_version})",
             local_addr.map_or_else(|| "unknown".to_string(), |a| a.to_string()),
             format_cors(cors.as_ref())
         );
@@ -204,9 +255,41 @@ pub async fn start_server(
     Ok(server_handle)
 }
 
-const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
-const X_REAL_IP: HeaderName = HeaderName::from_static("x-real-ip");
-const FORWARDED: HeaderName = HeaderName::from_static("forwarded");
+/// Id of the request, from its `X-Request-Id` header, or generated when it has none. Calls made over a websocket
+/// connection share the id of the request that opened it.
+fn request_id(req: &Request<Body>) -> Arc<str> {
+    match req.headers().get(&X_REQUEST_ID).and_then(|v| v.to_str().ok()) {
+        Some(id) if !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN => id.into(),
+        _ => format!("{:016x}", rand::random::<u64>()).into(),
+    }
+}
+
+/// Returns the id of the request to the caller, so that a failed call can be matched with the logs of the node.
+fn with_request_id_header(mut response: Response<Body>, request_id: &str) -> Response<Body> {
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response.headers_mut().insert(X_REQUEST_ID, value);
+    }
+    response
+}
+
+/// Response to the requests with an invalid API key, or without one when a key is required.
+fn unauthorized() -> Response<Body> {
+    let body = serde_json::json!({
+        "jsonrpc": "2.0",
+        "error": ErrorObject::owned(-32997, "Invalid or missing API key", None::<()>),
+        "id": 0
+    })
+    .to_string();
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap_or_else(|_| Response::new(Body::from("Unauthorized")))
+}
+
+const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+/// Longer request ids are replaced with a generated one.
+const MAX_REQUEST_ID_LEN: usize = 128;
 
 pub(crate) fn host_filtering(enabled: bool, addr: Option<SocketAddr>) -> Option<HostFilterLayer> {
     // If the local_addr failed, fallback to wildcard.
@@ -262,38 +345,3 @@ pub(crate) fn format_cors(maybe_cors: Option<&Vec<String>>) -> String {
         format!("{:?}", ["*"])
     }
 }
-
-/// Extracts the IP addr from the HTTP request.
-///
-/// It is extracted in the following order:
-/// 1. `Forwarded` header.
-/// 2. `X-Forwarded-For` header.
-/// 3. `X-Real-Ip`.
-pub(crate) fn get_proxy_ip(req: &Request<hyper::Body>) -> Option<IpAddr> {
-    if let Some(ip) = req
-        .headers()
-        .get(&FORWARDED)
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| ForwardedHeaderValue::from_forwarded(v).ok())
-        .and_then(|v| v.remotest_forwarded_for_ip())
-    {
-        return Some(ip);
-    }
-
-    if let Some(ip) = req
-        .headers()
-        .get(&X_FORWARDED_FOR)
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| ForwardedHeaderValue::from_x_forwarded_for(v).ok())
-        .and_then(|v| v.remotest_forwarded_for_ip())
-    {
-        return Some(ip);
-    }
-
-    if let Some(ip) = req.headers().get(&X_REAL_IP).and_then(|v| v.to_str().ok()).and_then(|v| IpAddr::from_str(v).ok())
-    {
-        return Some(ip);
-    }
-
-    None
-}
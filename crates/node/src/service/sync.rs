@@ -9,6 +9,7 @@ use mp_exex::ExExManagerHandle;
 use mp_utils::service::Service;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::watch;
 use tokio::task::JoinSet;
 
 #[derive(Clone)]
@@ -20,7 +21,7 @@ pub struct SyncService {
     starting_block: Option<u64>,
     start_params: Option<TelemetryHandle>,
     disabled: bool,
-    pending_block_poll_interval: Duration,
+    pending_block_poll_interval: watch::Receiver<Duration>,
     exex_manager: Option<ExExManagerHandle>,
 }
 
@@ -33,6 +34,7 @@ impl SyncService {
         block_importer: Arc<BlockImporter>,
         exex_manager: Option<ExExManagerHandle>,
         telemetry: TelemetryHandle,
+        pending_block_poll_interval: watch::Receiver<Duration>,
     ) -> anyhow::Result<Self> {
         let fetch_config = config.block_fetch_config(chain_config.chain_id.clone(), network);
 
@@ -46,7 +48,7 @@ impl SyncService {
             block_importer,
             start_params: Some(telemetry),
             disabled: config.sync_disabled,
-            pending_block_poll_interval: config.pending_block_poll_interval,
+            pending_block_poll_interval,
             exex_manager,
         })
     }
@@ -87,4 +89,8 @@ impl Service for SyncService {
 
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "sync"
+    }
 }
@@ -0,0 +1,92 @@
+//! systemd readiness notification (`Type=notify`) and watchdog (`WatchdogSec=`).
+//!
+//! Both are enabled by systemd through the `NOTIFY_SOCKET` and `WATCHDOG_USEC` environment variables, this service
+//! does nothing when they are not set.
+
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::{SocketAddr, UnixDatagram};
+use std::time::Duration;
+
+use anyhow::Context;
+use mp_utils::service::Service;
+use mp_utils::{graceful_shutdown, wait_or_graceful_shutdown};
+use tokio::task::JoinSet;
+
+struct Notifier {
+    socket: UnixDatagram,
+    addr: SocketAddr,
+}
+
+impl Notifier {
+    fn new(path: &OsStr) -> anyhow::Result<Self> {
+        let addr = match path.as_bytes().strip_prefix(b"@") {
+            #[cfg(target_os = "linux")]
+            Some(name) => <SocketAddr as std::os::linux::net::SocketAddrExt>::from_abstract_name(name),
+            #[cfg(not(target_os = "linux"))]
+            Some(_) => anyhow::bail!("Abstract unix sockets are not supported on this platform"),
+            None => SocketAddr::from_pathname(path),
+        }
+        .with_context(|| format!("Invalid systemd notify socket {path:?}"))?;
+        let socket = UnixDatagram::unbound().context("Creating the systemd notify socket")?;
+        Ok(Self { socket, addr })
+    }
+
+    fn notify(&self, state: &str) -> anyhow::Result<()> {
+        self.socket.send_to_addr(state.as_bytes(), &self.addr).with_context(|| format!("Notifying systemd {state}"))?;
+        Ok(())
+    }
+}
+
+/// Tells systemd that the node is ready once the services before it in the service group are started, pings the
+/// watchdog while the node is running, and tells systemd that the node is stopping on shutdown.
+pub struct SystemdService {
+    notifier: Option<Notifier>,
+    watchdog_period: Option<Duration>,
+}
+
+impl SystemdService {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let notifier = std::env::var_os("NOTIFY_SOCKET").map(|path| Notifier::new(&path)).transpose()?;
+
+        // The watchdog is meant for another process when its pid is not ours.
+        let watchdog_pid = std::env::var("WATCHDOG_PID").ok().and_then(|pid| pid.parse::<u32>().ok());
+        let watchdog_period = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse().ok())
+            .filter(|_| watchdog_pid.map_or(true, |pid| pid == std::process::id()))
+            .map(Duration::from_micros);
+
+        Ok(Self { notifier, watchdog_period })
+    }
+}
+
+#[async_trait::async_trait]
+impl Service for SystemdService {
+    async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        let Some(notifier) = self.notifier.take() else { return Ok(()) };
+        notifier.notify("READY=1")?;
+        log::debug!("Notified systemd that the node is ready");
+
+        let watchdog_period = self.watchdog_period;
+        join_set.spawn(async move {
+            if let Some(period) = watchdog_period {
+                log::debug!("Pinging the systemd watchdog every {:?}", period / 2);
+                let mut interval = tokio::time::interval(period / 2);
+                while wait_or_graceful_shutdown(interval.tick()).await.is_some() {
+                    if let Err(err) = notifier.notify("WATCHDOG=1") {
+                        log::warn!("{err:#}");
+                    }
+                }
+            } else {
+                graceful_shutdown().await;
+            }
+            notifier.notify("STOPPING=1")
+        });
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "systemd"
+    }
+}
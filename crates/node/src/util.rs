@@ -1,19 +1,53 @@
 use anyhow::Context;
 use chrono::Local;
 use clap::builder::styling::{AnsiColor, Color, Style};
-use log::{kv::Key, Level};
-use std::{io::Write, time::Duration};
+use env_logger::fmt::Formatter;
+use env_logger::{Target, WriteStyle};
+use log::kv::{self, Key, VisitSource};
+use log::{Level, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 
-pub fn setup_rayon_threadpool() -> anyhow::Result<()> {
+use crate::cli::ThreadPoolParams;
+
+/// The global rayon pool verifies the imported blocks and computes the commitments, and a dedicated pool executes the
+/// transactions of the RPC calls, see [`mp_utils::spawn_execution_task`].
+pub fn setup_rayon_threadpools(params: &ThreadPoolParams) -> anyhow::Result<()> {
     let available_parallelism = std::thread::available_parallelism()?;
     rayon::ThreadPoolBuilder::new()
         .thread_name(|thread_index| format!("rayon-{}", thread_index))
-        .num_threads(available_parallelism.get())
+        .num_threads(params.commitment_threads.unwrap_or(available_parallelism).get())
         .build_global()?;
+    mp_utils::set_execution_pool(
+        rayon::ThreadPoolBuilder::new()
+            .thread_name(|thread_index| format!("rayon-exec-{}", thread_index))
+            .num_threads(params.execution_threads.unwrap_or(available_parallelism).get())
+            .build()?,
+    )?;
+    Ok(())
+}
+
+/// Serves the tokio task instrumentation to `tokio-console`.
+pub fn setup_tokio_console(addr: SocketAddr) -> anyhow::Result<()> {
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    anyhow::ensure!(
+        cfg!(tokio_unstable),
+        "`--tokio-console` needs a madara build with `RUSTFLAGS=\"--cfg tokio_unstable\"`"
+    );
+    let layer = console_subscriber::ConsoleLayer::builder().server_addr(addr).spawn();
+    tracing_subscriber::registry().with(layer).try_init().context("Setting up the tokio-console instrumentation")?;
+    log::info!("🔍 tokio-console server listening on {addr}");
     Ok(())
 }
 
-pub fn raise_fdlimit() {
+/// Returns the file-descriptor limit of the process, when known.
+pub fn raise_fdlimit() -> Option<u64> {
     use fdlimit::Outcome;
     let recommended = 10000;
     match fdlimit::raise_fd_limit() {
@@ -21,24 +55,91 @@ pub fn raise_fdlimit() {
             log::warn!(
                     "The file-descriptor limit for the current process is {to}, which is lower than the recommended {recommended}."
                 );
+            Some(to)
         }
         Ok(Outcome::LimitRaised { to, .. }) => {
             log::debug!("File-descriptor limit was raised to {to}.");
+            Some(to)
         }
         Err(error) => {
             log::warn!(
                 "Error while trying to raise the file-descriptor limit for the process: {error:#}. The recommended file-descriptor limit is {recommended}."
             );
+            None
         }
         Ok(Outcome::Unsupported) => {
             log::debug!("Unsupported platform for raising file-descriptor limit.");
+            None
         }
     }
 }
 
+/// Where and how the logs are written.
+#[derive(Clone, Default)]
+pub struct LogOutput {
+    pub json: bool,
+    /// Written to stderr when not set.
+    pub file: Option<Arc<Mutex<RotatingFile>>>,
+}
+
+struct LoggerState {
+    logger: env_logger::Logger,
+    filter: String,
+    output: LogOutput,
+}
+
+/// Logger whose filter and output can be changed while the node is running.
+struct ReloadableLogger(RwLock<LoggerState>);
+
+impl Log for ReloadableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.read().expect("Poisoned lock").logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.0.read().expect("Poisoned lock").logger.log(record)
+    }
+
+    fn flush(&self) {
+        self.0.read().expect("Poisoned lock").logger.flush()
+    }
+}
+
+static LOGGER: OnceLock<ReloadableLogger> = OnceLock::new();
+
 // Todo: Setup tracing
 pub fn setup_logging() -> anyhow::Result<()> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+    let filter = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into());
+    let output = LogOutput::default();
+    let logger = build_logger(&filter, &output);
+    let max_level = logger.filter();
+    let logger = LOGGER.get_or_init(|| ReloadableLogger(RwLock::new(LoggerState { logger, filter, output })));
+    log::set_logger(logger).context("Setting up the logger")?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// Replaces the log filter, which uses the `RUST_LOG` syntax (e.g. `info,mc_sync=debug`).
+pub fn set_log_filter(filter: &str) {
+    update_logger(|state| state.filter = filter.into());
+}
+
+pub fn set_log_output(output: LogOutput) {
+    update_logger(|state| state.output = output);
+}
+
+fn update_logger(update: impl FnOnce(&mut LoggerState)) {
+    let Some(current) = LOGGER.get() else { return };
+    let mut state = current.0.write().expect("Poisoned lock");
+    update(&mut state);
+    state.logger = build_logger(&state.filter, &state.output);
+    log::set_max_level(state.logger.filter());
+}
+
+fn build_logger(filter: &str, output: &LogOutput) -> env_logger::Logger {
+    let mut builder = env_logger::Builder::from_env(env_logger::Env::new().write_style("RUST_LOG_STYLE"));
+    builder
+        .parse_filters(filter)
         .format(|fmt, record| {
             let ts = Local::now().format("%Y-%m-%d %H:%M:%S");
             let style = fmt.default_level_style(record.level());
@@ -56,6 +157,11 @@ pub fn setup_logging() -> anyhow::Result<()> {
                         Style::new().fg_color(Some(Color::Ansi(AnsiColor::Red)))
                     };
                     let response_time = Duration::from_micros(record.key_values().get(Key::from("response_time")).expect("No response time in record").to_u64().expect("Response time is not an int"));
+                    // Failed calls show their request id, for matching them with the error reported to the user.
+                    let request_id = match record.key_values().get(Key::from("request_id")) {
+                        Some(request_id) if status != 200 => format!(" (request id {request_id})"),
+                        _ => String::new(),
+                    };
                     let time_color = match response_time {
                         time if time <= Duration::from_millis(5) => {
                             Style::new()
@@ -71,7 +177,7 @@ pub fn setup_logging() -> anyhow::Result<()> {
 
                     writeln!(
                         fmt,
-                        "{brackets}[{brackets:#}{ts} {rpc_style}HTTP{rpc_style:#}{brackets}]{brackets:#} 🌐 {method} {status_color}{status}{status_color:#} {res_len} bytes - {time_color}{response_time:?}{time_color:#}",
+                        "{brackets}[{brackets:#}{ts} {rpc_style}HTTP{rpc_style:#}{brackets}]{brackets:#} 🌐 {method} {status_color}{status}{status_color:#} {res_len} bytes - {time_color}{response_time:?}{time_color:#}{request_id}",
                     )
                 }
                 Level::Info => {
@@ -123,10 +229,199 @@ pub fn setup_logging() -> anyhow::Result<()> {
                     )
                 }
             }
-        })
-        .init();
+        });
 
-    Ok(())
+    if output.json {
+        builder.format(format_json);
+    }
+    if let Some(file) = &output.file {
+        builder.target(Target::Pipe(Box::new(SharedFile(Arc::clone(file))))).write_style(WriteStyle::Never);
+    }
+    builder.build()
+}
+
+/// One json object per line, with the key-values of the record as additional fields.
+fn format_json(fmt: &mut Formatter, record: &Record) -> io::Result<()> {
+    serde_json::to_writer(&mut *fmt, &json_fields(record))?;
+    writeln!(fmt)
+}
+
+fn json_fields(record: &Record) -> serde_json::Map<String, serde_json::Value> {
+    let mut fields = serde_json::Map::new();
+    fields.insert("timestamp".into(), Local::now().to_rfc3339().into());
+    fields.insert("level".into(), record.level().as_str().into());
+    fields.insert("target".into(), record.target().into());
+    fields.insert("message".into(), record.args().to_string().into());
+    let _ = record.key_values().visit(&mut JsonFields(&mut fields));
+    fields
+}
+
+struct JsonFields<'a>(&'a mut serde_json::Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let value = if let Some(value) = value.to_i64() {
+            value.into()
+        } else if let Some(value) = value.to_u64() {
+            value.into()
+        } else if let Some(value) = value.to_f64() {
+            value.into()
+        } else if let Some(value) = value.to_bool() {
+            value.into()
+        } else {
+            value.to_string().into()
+        };
+        self.0.entry(key.as_str()).or_insert(value);
+        Ok(())
+    }
+}
+
+/// Log file, rotated to `<path>.1`, `<path>.2`... when it reaches `max_size` bytes or is older than `period`.
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    opened_at: Instant,
+    max_size: u64,
+    period: Option<Duration>,
+    /// Number of rotated files to keep.
+    keep: usize,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_size: u64, period: Option<Duration>, keep: usize) -> anyhow::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Opening log file {}", path.display()))?;
+        let size = file.metadata().with_context(|| format!("Reading log file {}", path.display()))?.len();
+        Ok(Self { path, file, size, opened_at: Instant::now(), max_size, period, keep })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep > 0 {
+            for index in (1..self.keep).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        self.size = 0;
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let too_large = self.size + buf.len() as u64 > self.max_size;
+        let too_old = self.period.is_some_and(|period| self.opened_at.elapsed() >= period);
+        if self.size > 0 && (too_large || too_old) {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+struct SharedFile(Arc<Mutex<RotatingFile>>);
+
+impl Write for SharedFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().expect("Poisoned lock").write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().expect("Poisoned lock").flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_fields() {
+        let key_values: &[(&str, kv::Value)] =
+            &[("status", 200.into()), ("method", "starknet_call".into()), ("level", "overridden".into())];
+        let fields = json_fields(
+            &Record::builder()
+                .args(format_args!("RPC call"))
+                .level(Level::Info)
+                .target("rpc_calls")
+                .key_values(&key_values)
+                .build(),
+        );
+
+        assert_eq!(fields["level"], "INFO");
+        assert_eq!(fields["target"], "rpc_calls");
+        assert_eq!(fields["message"], "RPC call");
+        assert_eq!(fields["status"], 200);
+        assert_eq!(fields["method"], "starknet_call");
+        assert!(fields["timestamp"].is_string());
+    }
+
+    #[test]
+    fn test_rotating_file_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("madara.log");
+        let mut file = RotatingFile::open(path.clone(), 10, None, 2).unwrap();
+
+        for line in ["line 1\n", "line 2\n", "line 3\n", "line 4\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        // Each line fills more than half of the file: every write after the first one rotates.
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line 4\n");
+        assert_eq!(fs::read_to_string(file.rotated_path(1)).unwrap(), "line 3\n");
+        assert_eq!(fs::read_to_string(file.rotated_path(2)).unwrap(), "line 2\n");
+        assert!(!file.rotated_path(3).exists());
+    }
+
+    #[test]
+    fn test_rotating_file_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("madara.log");
+        let mut file = RotatingFile::open(path.clone(), u64::MAX, Some(Duration::ZERO), 1).unwrap();
+
+        file.write_all(b"old\n").unwrap();
+        file.write_all(b"new\n").unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+        assert_eq!(fs::read_to_string(file.rotated_path(1)).unwrap(), "old\n");
+    }
+
+    #[test]
+    fn test_rotating_file_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("madara.log");
+        fs::write(&path, "before restart\n").unwrap();
+
+        let mut file = RotatingFile::open(path.clone(), 1024, None, 1).unwrap();
+        file.write_all(b"after restart\n").unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "before restart\nafter restart\n");
+        assert!(!file.rotated_path(1).exists());
+    }
 }
 
 /// Returns a random Pokémon name.
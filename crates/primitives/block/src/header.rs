@@ -194,7 +194,7 @@ impl Header {
     /// Compute the hash of the header.
     pub fn compute_hash(&self, chain_id: Felt) -> Felt {
         if self.protocol_version.is_pre_v0_7() {
-            self.compute_hash_inner_pre_v0_7(chain_id)
+            self.compute_hash_pre_v0_7(chain_id)
         } else if self.protocol_version < StarknetVersion::V0_13_2 {
             Pedersen::hash_array(&[
                 Felt::from(self.block_number),
@@ -237,7 +237,9 @@ impl Header {
         }
     }
 
-    fn compute_hash_inner_pre_v0_7(&self, chain_id: Felt) -> Felt {
+    /// Hash of the header with the formula of the blocks before protocol version 0.7.0, which commits to the chain id
+    /// and not to the sequencer address, timestamp and events.
+    pub fn compute_hash_pre_v0_7(&self, chain_id: Felt) -> Felt {
         Pedersen::hash_array(&[
             Felt::from(self.block_number),
             self.global_state_root,
@@ -15,6 +15,8 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 use blockifier::bouncer::{BouncerWeights, BuiltinCount};
+use blockifier::context::FeeTokenAddresses;
+use blockifier::transaction::objects::FeeType;
 use blockifier::{bouncer::BouncerConfig, versioned_constants::VersionedConstants};
 use lazy_static::__Deref;
 use primitive_types::H160;
@@ -50,6 +52,53 @@ lazy_static::lazy_static! {
         serde_json::from_slice(BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_0).unwrap();
 }
 
+/// The layer on which the core contract verifying the chain state is deployed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementLayer {
+    /// The chain settles on Ethereum (L2).
+    #[default]
+    Ethereum,
+    /// The chain settles on Starknet (L3).
+    Starknet,
+}
+
+/// The layer the state diffs are published on, for data availability.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaLayer {
+    /// The state diffs are published on the settlement layer, along with the state updates.
+    #[default]
+    Settlement,
+    /// The state diffs are published as Celestia blobs, in the given namespace (hex-encoded, at most 10 bytes).
+    Celestia { namespace: String },
+    /// The state diffs are published as Avail data submissions, under the given application id.
+    Avail { app_id: u32 },
+}
+
+/// Symbol and decimals of a fee token, used to display amounts and to fund the devnet accounts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeeToken {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl FeeToken {
+    pub fn strk() -> Self {
+        Self { symbol: "STRK".into(), decimals: 18 }
+    }
+
+    pub fn eth() -> Self {
+        Self { symbol: "ETH".into(), decimals: 18 }
+    }
+
+    /// Number of base units in `amount` whole tokens, `None` on overflow.
+    pub fn base_units(&self, amount: u128) -> Option<u128> {
+        10u128.checked_pow(self.decimals.into())?.checked_mul(amount)
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 #[error("Unsupported protocol version: {0}")]
 pub struct UnsupportedProtocolVersion(StarknetVersion);
@@ -65,6 +114,14 @@ pub struct ChainConfig {
     /// For starknet, this is the ETH ERC-20 contract on starknet.
     pub parent_fee_token_address: ContractAddress,
 
+    /// The native fee token pays the fees of V3 transactions. Appchains with their own fee token set its symbol and
+    /// decimals here, along with `native_fee_token_address`. Defaults to STRK.
+    #[serde(default = "FeeToken::strk")]
+    pub native_fee_token: FeeToken,
+    /// The parent fee token pays the fees of the older transactions. Defaults to ETH.
+    #[serde(default = "FeeToken::eth")]
+    pub parent_fee_token: FeeToken,
+
     /// BTreeMap ensures order.
     #[serde(default)]
     pub versioned_constants: ChainVersionedConstants,
@@ -72,6 +129,12 @@ pub struct ChainConfig {
     #[serde(deserialize_with = "deserialize_starknet_version")]
     pub latest_protocol_version: StarknetVersion,
 
+    /// Protocol version of the blocks, by the height from which it applies, so that the chain can upgrade at planned
+    /// heights and still re-execute its older blocks with their own versioned constants. When set, it must start at
+    /// block 0 and end with `latest_protocol_version`. All the blocks use `latest_protocol_version` when empty.
+    #[serde(default)]
+    pub protocol_upgrades: ProtocolUpgrades,
+
     /// Only used for block production.
     #[serde(deserialize_with = "deserialize_duration")]
     pub block_time: Duration,
@@ -100,8 +163,26 @@ pub struct ChainConfig {
     /// This number is the maximum nonce the invoke tx can have to qualify for the validation skip.
     pub max_nonce_for_validation_skip: u64,
 
-    /// The Starknet core contract address for the L1 watcher.
+    /// The Starknet core contract address for the L1 watcher. Not needed by sovereign chains.
+    #[serde(default)]
     pub eth_core_contract_address: H160,
+
+    /// The layer this chain settles on. Defaults to Ethereum.
+    #[serde(default)]
+    pub settlement_layer: SettlementLayer,
+
+    /// The core contract address on Starknet, used by the L1 watcher when settling on Starknet.
+    #[serde(default)]
+    pub starknet_core_contract_address: Option<Felt>,
+
+    /// The layer the state diffs are published on. Defaults to the settlement layer.
+    #[serde(default)]
+    pub da_layer: DaLayer,
+
+    /// Devnet: timestamp of the genesis block, in seconds since the UNIX epoch. Defaults to the time the devnet is
+    /// created.
+    #[serde(default)]
+    pub genesis_timestamp: Option<u64>,
 }
 
 impl ChainConfig {
@@ -164,9 +245,16 @@ impl ChainConfig {
                 ))
                 .unwrap(),
             ),
+            native_fee_token: FeeToken::strk(),
+            parent_fee_token: FeeToken::eth(),
             versioned_constants: ChainVersionedConstants::default(),
+            protocol_upgrades: ProtocolUpgrades::default(),
 
             eth_core_contract_address: eth_core_contract_address::MAINNET.parse().expect("parsing a constant"),
+            settlement_layer: SettlementLayer::Ethereum,
+            starknet_core_contract_address: None,
+            da_layer: DaLayer::Settlement,
+            genesis_timestamp: None,
 
             latest_protocol_version: StarknetVersion::V0_13_2,
             block_time: Duration::from_secs(30),
@@ -249,6 +337,33 @@ impl ChainConfig {
         (self.block_time.as_millis() / self.pending_block_update_time.as_millis()) as usize
     }
 
+    /// Fee token contracts, as used by the execution: the native token for V3 transactions, the parent token for the
+    /// older ones.
+    pub fn fee_token_addresses(&self) -> FeeTokenAddresses {
+        FeeTokenAddresses {
+            strk_fee_token_address: self.native_fee_token_address,
+            eth_fee_token_address: self.parent_fee_token_address,
+        }
+    }
+
+    /// The fee token paying the fees of a transaction, with its contract address.
+    pub fn fee_token(&self, fee_type: FeeType) -> (ContractAddress, &FeeToken) {
+        match fee_type {
+            FeeType::Strk => (self.native_fee_token_address, &self.native_fee_token),
+            FeeType::Eth => (self.parent_fee_token_address, &self.parent_fee_token),
+        }
+    }
+
+    /// Protocol version of the block `block_n`, following the protocol upgrades.
+    pub fn protocol_version_at(&self, block_n: u64) -> StarknetVersion {
+        self.protocol_upgrades
+            .0
+            .range(..=block_n)
+            .next_back()
+            .map(|(_, version)| *version)
+            .unwrap_or(self.latest_protocol_version)
+    }
+
     pub fn exec_constants_by_protocol_version(
         &self,
         version: StarknetVersion,
@@ -347,6 +462,32 @@ impl ChainVersionedConstants {
     }
 }
 
+/// Protocol versions by the block height from which they apply, see [`ChainConfig::protocol_upgrades`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProtocolUpgrades(pub BTreeMap<u64, StarknetVersion>);
+
+impl<'de> Deserialize<'de> for ProtocolUpgrades {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        BTreeMap::<u64, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(height, version)| Ok((height, version.parse().map_err(serde::de::Error::custom)?)))
+            .collect::<Result<_, _>>()
+            .map(ProtocolUpgrades)
+    }
+}
+
+impl Serialize for ProtocolUpgrades {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(height, version)| (height, version.to_string())))
+    }
+}
+
 pub fn deserialize_starknet_version<'de, D>(deserializer: D) -> Result<StarknetVersion, D::Error>
 where
     D: Deserializer<'de>,
@@ -403,6 +544,7 @@ mod tests {
         std::env::set_current_dir("../../../").expect("Failed to change directory");
         let chain_config: ChainConfig =
             ChainConfig::from_yaml(Path::new("configs/presets/mainnet.yaml")).expect("failed to get cfg");
+        chain_config.validate().unwrap();
 
         assert_eq!(chain_config.chain_name, "Starknet Mainnet");
         assert_eq!(chain_config.chain_id, ChainId::Mainnet);
@@ -413,6 +555,8 @@ mod tests {
             Felt::from_hex("0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7").unwrap();
         assert_eq!(chain_config.native_fee_token_address, ContractAddress::try_from(native_fee_token_address).unwrap());
         assert_eq!(chain_config.parent_fee_token_address, ContractAddress::try_from(parent_fee_token_address).unwrap());
+        assert_eq!(chain_config.native_fee_token, FeeToken::strk());
+        assert_eq!(chain_config.parent_fee_token, FeeToken::eth());
 
         // Check versioned constants
         // Load and parse the JSON file
@@ -556,4 +700,47 @@ mod tests {
         );
         assert!(chain_config.exec_constants_by_protocol_version(StarknetVersion::new(0, 0, 0, 0)).is_err(),);
     }
+
+    #[rstest]
+    fn test_protocol_upgrades() {
+        let mut chain_config = ChainConfig {
+            latest_protocol_version: StarknetVersion::V0_13_2,
+            protocol_upgrades: serde_yaml::from_str("{ 0: \"0.13.1\", 100: \"0.13.1.1\", 200: \"0.13.2\" }").unwrap(),
+            ..ChainConfig::madara_test()
+        };
+        chain_config.validate().unwrap();
+
+        assert_eq!(chain_config.protocol_version_at(0), StarknetVersion::V0_13_1);
+        assert_eq!(chain_config.protocol_version_at(99), StarknetVersion::V0_13_1);
+        assert_eq!(chain_config.protocol_version_at(100), StarknetVersion::V0_13_1_1);
+        assert_eq!(chain_config.protocol_version_at(200), StarknetVersion::V0_13_2);
+        assert_eq!(chain_config.protocol_version_at(u64::MAX), StarknetVersion::V0_13_2);
+
+        // The genesis block has no version.
+        chain_config.protocol_upgrades.0.remove(&0);
+        assert!(chain_config.validate().is_err());
+        // The last upgrade is not the latest version.
+        chain_config.protocol_upgrades.0.insert(0, StarknetVersion::V0_13_1);
+        chain_config.protocol_upgrades.0.remove(&200);
+        assert!(chain_config.validate().is_err());
+        // Downgrade.
+        chain_config.protocol_upgrades.0.insert(200, StarknetVersion::V0_13_0);
+        chain_config.protocol_upgrades.0.insert(300, StarknetVersion::V0_13_2);
+        assert!(chain_config.validate().is_err());
+
+        let chain_config = ChainConfig::madara_test();
+        assert_eq!(chain_config.protocol_version_at(1_000_000), chain_config.latest_protocol_version);
+    }
+
+    #[rstest]
+    fn test_fee_tokens() {
+        let token: FeeToken = serde_yaml::from_str("{ symbol: \"APP\", decimals: 6 }").unwrap();
+        assert_eq!(token.base_units(10), Some(10_000_000));
+        assert_eq!(FeeToken { symbol: "BIG".into(), decimals: 40 }.base_units(1), None);
+
+        let chain_config = ChainConfig { native_fee_token: token.clone(), ..ChainConfig::madara_test() };
+        assert_eq!(chain_config.fee_token(FeeType::Strk), (chain_config.native_fee_token_address, &token));
+        assert_eq!(chain_config.fee_token(FeeType::Eth).1, &FeeToken::eth());
+        assert_eq!(chain_config.fee_token_addresses().eth_fee_token_address, chain_config.parent_fee_token_address);
+    }
 }
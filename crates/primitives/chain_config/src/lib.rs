@@ -1,7 +1,9 @@
 mod chain_config;
 mod rpc_version;
 mod starknet_version;
+mod validation;
 
 pub use chain_config::*;
 pub use rpc_version::*;
 pub use starknet_version::*;
+pub use validation::*;
@@ -30,18 +30,23 @@ impl RpcVersion {
     }
 
     pub fn from_request_path(path: &str) -> Result<Self, RpcVersionError> {
+        Self::from_request_path_or(path, Self::RPC_VERSION_LATEST)
+    }
+
+    /// Like [`RpcVersion::from_request_path`], with `default` served on the paths without a version.
+    pub fn from_request_path_or(path: &str, default: RpcVersion) -> Result<Self, RpcVersionError> {
         let path = path.to_ascii_lowercase();
         let parts: Vec<&str> = path.split('/').collect();
 
-        // If we have an empty path or just "/", fallback to latest rpc version
+        // If we have an empty path or just "/", fallback to the default rpc version
         if parts.len() == 1 || (parts.len() == 2 && parts[1].is_empty()) {
-            return Ok(Self::RPC_VERSION_LATEST);
+            return Ok(default);
         }
 
         // Check if the path follows the correct format, i.e. /rpc/v[version].
-        // If not, fallback to the latest version
+        // If not, fallback to the default version
         if parts.len() != 3 || parts[1] != "rpc" || !parts[2].starts_with('v') {
-            return Ok(Self::RPC_VERSION_LATEST);
+            return Ok(default);
         }
 
         let version_str = &parts[2][1..]; // without the 'v' prefix
@@ -168,6 +173,14 @@ mod tests {
         assert_eq!(RpcVersion::from_request_path("/").unwrap(), RpcVersion::RPC_VERSION_LATEST);
     }
 
+    #[test]
+    fn test_from_request_path_or_default() {
+        let default = RpcVersion::new(0, 6, 0);
+        assert_eq!(RpcVersion::from_request_path_or("/", default).unwrap(), default);
+        assert_eq!(RpcVersion::from_request_path_or("/invalid/path", default).unwrap(), default);
+        assert_eq!(RpcVersion::from_request_path_or("/rpc/v0_7_1", default).unwrap(), RpcVersion::RPC_VERSION_0_7_1);
+    }
+
     #[test]
     fn test_from_request_path_invalid_format() {
         assert_eq!(RpcVersion::from_request_path("/invalid/path").unwrap(), RpcVersion::RPC_VERSION_LATEST);
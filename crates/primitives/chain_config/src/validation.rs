@@ -0,0 +1,299 @@
+//! Consistency checks of a chain config, run once it is loaded. Every issue is reported with the path of the field it
+//! concerns, so that a config can be fixed in one go instead of one error at a time.
+
+use std::fmt;
+
+use primitive_types::H160;
+use starknet_api::core::ChainId;
+
+use crate::{eth_core_contract_address, ChainConfig, DaLayer, FeeToken, SettlementLayer};
+
+/// Largest number of decimals for which one whole token fits in a `u128`.
+const MAX_FEE_TOKEN_DECIMALS: u8 = 38;
+/// Celestia version 0 namespace ids have at most 10 user-specified bytes.
+const MAX_CELESTIA_NAMESPACE_BYTES: usize = 10;
+
+/// A problem with one field of the chain config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainConfigIssue {
+    /// Path of the field in the chain config file, such as `bouncer_config.block_max_capacity.n_steps`.
+    pub field: String,
+    pub message: String,
+}
+
+impl ChainConfigIssue {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+impl fmt::Display for ChainConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// All the issues found in a chain config.
+#[derive(thiserror::Error, Debug)]
+pub struct ChainConfigErrors(pub Vec<ChainConfigIssue>);
+
+impl ChainConfigErrors {
+    /// Fails when there is at least one issue.
+    pub fn check(issues: Vec<ChainConfigIssue>) -> Result<(), Self> {
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(Self(issues))
+        }
+    }
+}
+
+impl fmt::Display for ChainConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let n = self.0.len();
+        write!(f, "Invalid chain config, {n} {}:", if n == 1 { "error" } else { "errors" })?;
+        for issue in &self.0 {
+            write!(f, "\n  - {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ChainConfig {
+    /// Checks the consistency of the chain config, reporting every issue found.
+    pub fn validate(&self) -> Result<(), ChainConfigErrors> {
+        ChainConfigErrors::check(self.issues())
+    }
+
+    /// Every consistency issue of the chain config. Callers with more context, such as the RPC limits of the node,
+    /// can add their own issues before calling [`ChainConfigErrors::check`].
+    pub fn issues(&self) -> Vec<ChainConfigIssue> {
+        let mut issues = Vec::new();
+        self.check_protocol_versions(&mut issues);
+        self.check_settlement(&mut issues);
+        self.check_block_production(&mut issues);
+        self.check_fee_tokens(&mut issues);
+        issues
+    }
+
+    fn check_protocol_versions(&self, issues: &mut Vec<ChainConfigIssue>) {
+        if self.exec_constants_by_protocol_version(self.latest_protocol_version).is_err() {
+            issues.push(ChainConfigIssue::new(
+                "latest_protocol_version",
+                format!(
+                    "No versioned constants for version {}. Add them with `versioned_constants_path`.",
+                    self.latest_protocol_version
+                ),
+            ));
+        }
+
+        let upgrades = &self.protocol_upgrades.0;
+        let Some((&first_height, _)) = upgrades.first_key_value() else { return };
+        if first_height != 0 {
+            issues.push(ChainConfigIssue::new(
+                "protocol_upgrades",
+                format!(
+                    "The first protocol upgrade is at block {first_height}. Add an upgrade at block 0, to give the \
+                     version of the genesis block."
+                ),
+            ));
+        }
+        for ((_, version), (height, next_version)) in upgrades.iter().zip(upgrades.iter().skip(1)) {
+            if next_version <= version {
+                issues.push(ChainConfigIssue::new(
+                    format!("protocol_upgrades.{height}"),
+                    format!("Goes from version {version} to {next_version}, protocol versions can only increase."),
+                ));
+            }
+        }
+        let (last_height, last_version) = upgrades.last_key_value().expect("Upgrades are not empty");
+        if *last_version != self.latest_protocol_version {
+            issues.push(ChainConfigIssue::new(
+                format!("protocol_upgrades.{last_height}"),
+                format!(
+                    "The last protocol upgrade is to version {last_version} instead of the latest protocol version {}.",
+                    self.latest_protocol_version
+                ),
+            ));
+        }
+        for (height, version) in upgrades {
+            if self.exec_constants_by_protocol_version(*version).is_err() {
+                issues.push(ChainConfigIssue::new(
+                    format!("protocol_upgrades.{height}"),
+                    format!("No versioned constants for version {version}. Add them with `versioned_constants_path`."),
+                ));
+            }
+        }
+    }
+
+    fn check_settlement(&self, issues: &mut Vec<ChainConfigIssue>) {
+        // Custom chains may follow the core contract of any L1 chain, the public networks only their own. A zero
+        // address means that the chain is sovereign.
+        let expected_core_contract = match self.chain_id {
+            ChainId::Mainnet => Some(eth_core_contract_address::MAINNET),
+            ChainId::Sepolia => Some(eth_core_contract_address::SEPOLIA_TESTNET),
+            ChainId::IntegrationSepolia => Some(eth_core_contract_address::SEPOLIA_INTEGRATION),
+            _ => None,
+        };
+        if let Some(expected) = expected_core_contract {
+            let expected: H160 = expected.parse().expect("Parsing a constant");
+            if self.settlement_layer == SettlementLayer::Ethereum
+                && !self.eth_core_contract_address.is_zero()
+                && self.eth_core_contract_address != expected
+            {
+                issues.push(ChainConfigIssue::new(
+                    "eth_core_contract_address",
+                    format!(
+                        "The core contract of {} on Ethereum is {expected:#x}, not {:#x}.",
+                        self.chain_id, self.eth_core_contract_address
+                    ),
+                ));
+            }
+        }
+
+        if let DaLayer::Celestia { namespace } = &self.da_layer {
+            let id = namespace.strip_prefix("0x").unwrap_or(namespace);
+            if id.is_empty()
+                || id.len() % 2 != 0
+                || id.len() > MAX_CELESTIA_NAMESPACE_BYTES * 2
+                || !id.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                issues.push(ChainConfigIssue::new(
+                    "da_layer.namespace",
+                    format!(
+                        "Invalid Celestia namespace {namespace:?}, expected an hex string of 1 to \
+                         {MAX_CELESTIA_NAMESPACE_BYTES} bytes."
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn check_block_production(&self, issues: &mut Vec<ChainConfigIssue>) {
+        if !self.block_time.is_zero()
+            && !self.pending_block_update_time.is_zero()
+            && self.pending_block_update_time > self.block_time
+        {
+            issues.push(ChainConfigIssue::new(
+                "pending_block_update_time",
+                format!(
+                    "The pending block is updated every {:?}, which is longer than the block time of {:?}.",
+                    self.pending_block_update_time, self.block_time
+                ),
+            ));
+        }
+        if self.execution_batch_size == 0 {
+            issues.push(ChainConfigIssue::new("execution_batch_size", "Must be at least 1."));
+        }
+
+        let capacity = &self.bouncer_config.block_max_capacity;
+        let limits = [
+            ("gas", capacity.gas),
+            ("n_steps", capacity.n_steps),
+            ("message_segment_length", capacity.message_segment_length),
+            ("n_events", capacity.n_events),
+            ("state_diff_size", capacity.state_diff_size),
+        ];
+        for (name, limit) in limits {
+            if limit == 0 {
+                issues.push(ChainConfigIssue::new(
+                    format!("bouncer_config.block_max_capacity.{name}"),
+                    "A limit of 0 leaves no room for any transaction in a block.",
+                ));
+            }
+        }
+    }
+
+    fn check_fee_tokens(&self, issues: &mut Vec<ChainConfigIssue>) {
+        let check_decimals = |field: &str, token: &FeeToken, issues: &mut Vec<ChainConfigIssue>| {
+            if token.decimals > MAX_FEE_TOKEN_DECIMALS {
+                issues.push(ChainConfigIssue::new(
+                    format!("{field}.decimals"),
+                    format!(
+                        "{} has {} decimals, at most {MAX_FEE_TOKEN_DECIMALS} are supported.",
+                        token.symbol, token.decimals
+                    ),
+                ));
+            }
+        };
+        check_decimals("native_fee_token", &self.native_fee_token, issues);
+        check_decimals("parent_fee_token", &self.parent_fee_token, issues);
+
+        if self.native_fee_token_address == self.parent_fee_token_address {
+            issues.push(ChainConfigIssue::new(
+                "parent_fee_token_address",
+                "The native and parent fee tokens must be different contracts.",
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::StarknetVersion;
+
+    #[rstest]
+    fn test_presets_are_valid() {
+        for config in [
+            ChainConfig::starknet_mainnet(),
+            ChainConfig::starknet_sepolia(),
+            ChainConfig::starknet_integration(),
+            ChainConfig::madara_devnet(),
+            ChainConfig::madara_test(),
+        ] {
+            config.validate().unwrap();
+        }
+    }
+
+    #[rstest]
+    fn test_reports_every_issue() {
+        let mut chain_config = ChainConfig {
+            chain_id: ChainId::Mainnet,
+            latest_protocol_version: StarknetVersion::V0_13_2,
+            protocol_upgrades: serde_yaml::from_str("{ 10: \"0.13.1\", 100: \"0.13.0\" }").unwrap(),
+            pending_block_update_time: Duration::from_secs(60),
+            native_fee_token: FeeToken { symbol: "APP".into(), decimals: 40 },
+            da_layer: DaLayer::Celestia { namespace: "0xmadara".into() },
+            ..ChainConfig::madara_test()
+        };
+        chain_config.bouncer_config.block_max_capacity.n_steps = 0;
+
+        let fields: Vec<_> = chain_config.issues().into_iter().map(|issue| issue.field).collect();
+        assert_eq!(
+            fields,
+            [
+                "protocol_upgrades",
+                "protocol_upgrades.100",
+                "protocol_upgrades.100",
+                "eth_core_contract_address",
+                "da_layer.namespace",
+                "pending_block_update_time",
+                "bouncer_config.block_max_capacity.n_steps",
+                "native_fee_token.decimals",
+            ]
+        );
+
+        let message = chain_config.validate().unwrap_err().to_string();
+        assert!(message.starts_with("Invalid chain config, 8 errors:\n  - protocol_upgrades: "), "{message}");
+    }
+
+    #[rstest]
+    fn test_core_contract_of_known_chain() {
+        let chain_config = ChainConfig {
+            eth_core_contract_address: eth_core_contract_address::SEPOLIA_INTEGRATION.parse().unwrap(),
+            ..ChainConfig::starknet_sepolia()
+        };
+        let issues = chain_config.issues();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "eth_core_contract_address");
+
+        // Sovereign chains have no core contract.
+        let chain_config = ChainConfig { eth_core_contract_address: H160::zero(), ..ChainConfig::starknet_sepolia() };
+        chain_config.validate().unwrap();
+    }
+}
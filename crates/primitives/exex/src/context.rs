@@ -1,6 +1,10 @@
 use std::sync::Arc;
 
+use anyhow::Context;
+use mc_db::MadaraBackend;
+use mc_metrics::MetricsRegistry;
 use mp_rpc::Starknet;
+use serde::de::DeserializeOwned;
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{notification::ExExNotifications, ExExEvent};
@@ -10,6 +14,13 @@ pub struct ExExContext {
     /// Starknet RPC
     pub starknet: Arc<Starknet>,
 
+    /// Database of the node, to query blocks, storage, events and classes directly.
+    ///
+    /// # Important
+    ///
+    /// The database is shared with the rest of the node: `ExEx`'s must only read from it.
+    pub backend: Arc<MadaraBackend>,
+
     /// Channel used to send [`ExExEvent`]s to the rest of the node.
     ///
     /// # Important
@@ -26,4 +37,22 @@ pub struct ExExContext {
     /// Once an [`ExExNotification`] is sent over the channel, it is
     /// considered delivered by the node.
     pub notifications: ExExNotifications,
+
+    /// The section of the ExEx config file dedicated to this `ExEx`. Empty when there is none.
+    pub config: toml::Table,
+
+    /// Prometheus registry of the node, for the `ExEx` to export its own metrics.
+    ///
+    /// # Important
+    ///
+    /// The same registry is handed to the `ExEx` every time it is restarted, and registering a metric twice fails:
+    /// metrics should only be registered once.
+    pub metrics: MetricsRegistry,
+}
+
+impl ExExContext {
+    /// Parses the config section of the `ExEx`.
+    pub fn config<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        toml::Value::Table(self.config.clone()).try_into().context("Parsing the ExEx config")
+    }
 }
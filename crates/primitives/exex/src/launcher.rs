@@ -1,33 +1,62 @@
 use std::future::Future;
 use std::sync::Arc;
 
-use futures::{
-    future::{self, BoxFuture},
-    FutureExt,
-};
+use anyhow::Context;
+use futures::{future::BoxFuture, FutureExt};
+use mc_db::BlockRevert;
+use mc_metrics::MetricsRegistry;
 use mp_rpc::Starknet;
+use mp_utils::service::{RestartPolicy, ServiceStatuses};
+use starknet_api::block::BlockNumber;
+use tokio::sync::broadcast;
 
-use crate::{context::ExExContext, ExExHandle, ExExManager, ExExManagerHandle};
+use crate::{
+    context::ExExContext, replay_blocks, supervisor::Supervised, ExExHandle, ExExManager, ExExManagerConfig,
+    ExExManagerHandle, ExExMetrics, ExExNotification,
+};
 
-const DEFAULT_EXEX_MANAGER_CAPACITY: usize = 16;
+/// An `ExEx` to launch.
+pub struct InstalledExEx {
+    /// Unique id of the `ExEx`.
+    pub id: String,
+    /// Config section of the `ExEx`, handed to it in its [`ExExContext`].
+    pub config: toml::Table,
+    /// First block to notify the `ExEx` of, to backfill it with the blocks already in the database. Only used
+    /// the first time the `ExEx` is launched: afterwards, it resumes after the last height it finished.
+    pub start_block: Option<u64>,
+    /// How the `ExEx` is restarted when it crashes.
+    pub restart_policy: RestartPolicy,
+    pub exex: Box<dyn BoxedLaunchExEx>,
+}
 
 pub struct ExExLauncher {
-    extensions: Vec<(String, Box<dyn BoxedLaunchExEx>)>,
+    extensions: Vec<InstalledExEx>,
     starknet: Arc<Starknet>,
+    manager_config: ExExManagerConfig,
+    metrics: ExExMetrics,
+    statuses: ServiceStatuses,
+    registry: MetricsRegistry,
 }
 
 impl ExExLauncher {
     /// Create a new `ExExLauncher` with the given extensions.
-    pub const fn new(extensions: Vec<(String, Box<dyn BoxedLaunchExEx>)>, starknet: Arc<Starknet>) -> Self {
-        Self { extensions, starknet }
+    pub const fn new(
+        extensions: Vec<InstalledExEx>,
+        starknet: Arc<Starknet>,
+        manager_config: ExExManagerConfig,
+        metrics: ExExMetrics,
+        statuses: ServiceStatuses,
+        registry: MetricsRegistry,
+    ) -> Self {
+        Self { extensions, starknet, manager_config, metrics, statuses, registry }
     }
 
     /// Launches all execution extensions.
     ///
-    /// Spawns all extensions and returns the handle to the exex manager if any extensions are
-    /// installed.
+    /// Spawns all extensions under supervision, and returns the handle to the exex manager if any extensions are
+    /// installed. Their health is reported in the `statuses`.
     pub async fn launch(self) -> anyhow::Result<Option<ExExManagerHandle>> {
-        let Self { extensions, starknet } = self;
+        let Self { extensions, starknet, manager_config, metrics, statuses, registry } = self;
 
         if extensions.is_empty() {
             // nothing to launch
@@ -35,41 +64,78 @@ impl ExExLauncher {
         }
 
         let mut exex_handles = Vec::with_capacity(extensions.len());
-        let mut exexes = Vec::with_capacity(extensions.len());
 
-        for (id, exex) in extensions {
+        // The ExExs are launched before blocks are produced or synced: live notifications start after this block.
+        let latest_block_n = starknet.backend.get_latest_block_n().context("Getting the latest block number")?;
+
+        for InstalledExEx { id, config, start_block, restart_policy, exex } in extensions {
             // create a new exex handle
-            let (handle, events, notifications) = ExExHandle::new(id.clone());
+            let (handle, events, mut notifications) = ExExHandle::new(id.clone());
             exex_handles.push(handle);
 
-            // create the launch context for the exex
-            let context = ExExContext { starknet: starknet.clone(), events, notifications };
-
-            exexes.push(async move {
-                // init the exex
-                let exex = exex.launch(context).await.unwrap();
-                tokio::spawn(async move {
-                    match exex.await {
-                        Ok(_) => panic!("ExEx {id} finished. ExExes should run indefinitely"),
-                        Err(err) => panic!("ExEx {id} crashed: {err}"),
-                    }
-                });
-            });
-        }
+            // replay the blocks the exex missed while the node was stopped, or the history it asked for
+            let finished_height =
+                starknet.backend.get_exex_finished_height(&id).context("Getting the ExEx finished height")?;
+            if let (Some(replay_start), Some(latest_block_n)) =
+                (replay_start(finished_height, start_block), latest_block_n)
+            {
+                if replay_start <= latest_block_n {
+                    log::info!("🔁 Replaying blocks #{replay_start} to #{latest_block_n} to ExEx {id}");
+                    notifications = notifications
+                        .with_replay(replay_blocks(Arc::clone(&starknet.backend), replay_start..=latest_block_n));
+                }
+            }
 
-        future::join_all(exexes).await;
+            let supervised = Supervised {
+                id,
+                exex,
+                restart_policy,
+                starknet: Arc::clone(&starknet),
+                config,
+                events,
+                statuses: statuses.clone(),
+                registry: registry.clone(),
+            };
+            tokio::spawn(supervised.run(notifications));
+        }
 
-        let exex_manager = ExExManager::new(exex_handles, DEFAULT_EXEX_MANAGER_CAPACITY);
+        let exex_manager = ExExManager::new(exex_handles, manager_config, Arc::clone(&starknet.backend), metrics);
         let handle = exex_manager.handle();
         tokio::spawn(async move {
             if let Err(e) = exex_manager.await {
                 eprintln!("ExExManager error: {:?}", e);
             }
         });
+        tokio::spawn(forward_block_reverts(starknet.backend.subscribe_block_reverts(), handle.clone()));
         Ok(Some(handle))
     }
 }
 
+/// First block to replay to an `ExEx`, if any. A persisted finished height takes precedence over the configured
+/// start block, so that backfilling is not restarted from scratch on every launch.
+fn replay_start(finished_height: Option<u64>, start_block: Option<u64>) -> Option<u64> {
+    finished_height.map(|height| height + 1).or(start_block)
+}
+
+/// Forwards the blocks rolled back by the backend to the `ExEx`'s.
+async fn forward_block_reverts(mut reverts: broadcast::Receiver<BlockRevert>, handle: ExExManagerHandle) {
+    loop {
+        match reverts.recv().await {
+            Ok(BlockRevert { from, to }) => {
+                let notification = ExExNotification::Reverted { from: BlockNumber(from), to: BlockNumber(to) };
+                if handle.send(notification).is_err() {
+                    // The manager is gone.
+                    return;
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                log::error!("ExExs missed {n} block revert notifications and may have diverged from the chain")
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
 /// A trait for launching an `ExEx`.
 pub trait LaunchExEx: Send {
     /// Launches the `ExEx`.
@@ -87,20 +153,21 @@ pub type BoxExEx = BoxFuture<'static, anyhow::Result<()>>;
 
 /// A version of [`LaunchExEx`] that returns a boxed future. Makes the trait object-safe.
 pub trait BoxedLaunchExEx: Send + Sync {
-    /// Launches the `ExEx` and returns a boxed future.
-    fn launch(self: Box<Self>, ctx: ExExContext) -> BoxFuture<'static, anyhow::Result<BoxExEx>>;
+    /// Launches the `ExEx` and returns a boxed future. Called again each time the `ExEx` is restarted.
+    fn launch(&self, ctx: ExExContext) -> BoxFuture<'static, anyhow::Result<BoxExEx>>;
 }
 
-/// Implements [`BoxedLaunchExEx`] for any [`LaunchExEx`] that is [Send] and `'static`.
+/// Implements [`BoxedLaunchExEx`] for any [`LaunchExEx`] that is [Clone], [Send] and `'static`.
 ///
 /// Returns a [`BoxFuture`] that resolves to a [`BoxExEx`].
 impl<E> BoxedLaunchExEx for E
 where
-    E: LaunchExEx + Send + Sync + 'static,
+    E: LaunchExEx + Clone + Send + Sync + 'static,
 {
-    fn launch(self: Box<Self>, ctx: ExExContext) -> BoxFuture<'static, anyhow::Result<BoxExEx>> {
+    fn launch(&self, ctx: ExExContext) -> BoxFuture<'static, anyhow::Result<BoxExEx>> {
+        let launcher = self.clone();
         async move {
-            let exex = LaunchExEx::launch(*self, ctx).await?;
+            let exex = LaunchExEx::launch(launcher, ctx).await?;
             Ok(Box::pin(exex) as BoxExEx)
         }
         .boxed()
@@ -122,3 +189,16 @@ where
         self(ctx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_resumes_after_finished_height() {
+        assert_eq!(replay_start(None, None), None);
+        assert_eq!(replay_start(None, Some(0)), Some(0));
+        assert_eq!(replay_start(Some(10), None), Some(11));
+        assert_eq!(replay_start(Some(10), Some(0)), Some(11));
+    }
+}
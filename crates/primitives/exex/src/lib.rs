@@ -10,11 +10,16 @@ pub mod event;
 pub mod head;
 pub mod launcher;
 pub mod manager;
+pub mod metrics;
 pub mod notification;
+pub mod replay;
+mod supervisor;
 
 pub use context::ExExContext;
 pub use event::ExExEvent;
 pub use head::{ExExHead, FinishedExExHeight};
-pub use launcher::{BoxExEx, BoxedLaunchExEx, ExExLauncher, LaunchExEx};
-pub use manager::{ExExHandle, ExExManager, ExExManagerHandle};
+pub use launcher::{BoxExEx, BoxedLaunchExEx, ExExLauncher, InstalledExEx, LaunchExEx};
+pub use manager::{ExExHandle, ExExManager, ExExManagerConfig, ExExManagerHandle, SlowConsumerPolicy};
+pub use metrics::ExExMetrics;
 pub use notification::{ExExNotification, ExExNotifications};
+pub use replay::replay_blocks;
@@ -16,8 +16,37 @@ use tokio::sync::{
 };
 use tokio_util::sync::{PollSendError, PollSender, ReusableBoxFuture};
 
-use crate::ExExNotifications;
+use mc_db::MadaraBackend;
+
 use crate::{event::ExExEvent, head::FinishedExExHeight, notification::ExExNotification};
+use crate::{ExExMetrics, ExExNotifications};
+
+/// What the [`ExExManager`] does when an `ExEx` cannot keep up with the notifications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlowConsumerPolicy {
+    /// Buffer the notifications until the `ExEx` catches up, without bound.
+    Buffer,
+    /// Keep at most the buffer capacity of notifications, dropping the oldest ones. [`ExExNotification::Reverted`]
+    /// notifications are never dropped.
+    DropOldest,
+    /// Pause block production and sync until the `ExEx` catches up.
+    #[default]
+    Pause,
+}
+
+/// Buffering of the [`ExExManager`].
+#[derive(Debug, Clone, Copy)]
+pub struct ExExManagerConfig {
+    /// Capacity of the notification buffer. Notifications over capacity are handled following the `policy`.
+    pub capacity: usize,
+    pub policy: SlowConsumerPolicy,
+}
+
+impl Default for ExExManagerConfig {
+    fn default() -> Self {
+        Self { capacity: 16, policy: SlowConsumerPolicy::default() }
+    }
+}
 
 /// The execution extension manager.
 ///
@@ -28,7 +57,6 @@ use crate::{event::ExExEvent, head::FinishedExExHeight, notification::ExExNotifi
 /// - Backpressure
 /// - Error handling
 /// - Monitoring
-#[derive(Debug)]
 pub struct ExExManager {
     /// Handles to communicate with the `ExEx`'s.
     pub exex_handles: Vec<ExExHandle>,
@@ -36,8 +64,6 @@ pub struct ExExManager {
     /// [`ExExNotification`] channel from the [`ExExManagerHandle`]s.
     pub handle_rx: UnboundedReceiver<ExExNotification>,
 
-    /// The minimum notification ID currently present in the buffer.
-    min_id: usize,
     /// Monotonically increasing ID for [`ExExNotification`]s.
     next_id: usize,
 
@@ -48,6 +74,10 @@ pub struct ExExManager {
     buffer: VecDeque<(usize, ExExNotification)>,
     /// Max size of the internal state notifications buffer.
     max_capacity: usize,
+    /// What to do when the buffer is full.
+    policy: SlowConsumerPolicy,
+    /// Block number of the latest notification.
+    tip: Option<u64>,
     /// Current state notifications buffer capacity.
     ///
     /// Used to inform the execution stage of possible batch sizes.
@@ -61,6 +91,11 @@ pub struct ExExManager {
 
     /// A handle to the `ExEx` manager.
     handle: ExExManagerHandle,
+
+    /// Where the finished height of each `ExEx` is persisted, to replay the blocks they missed on restart.
+    backend: Arc<MadaraBackend>,
+
+    metrics: ExExMetrics,
 }
 
 impl ExExManager {
@@ -69,9 +104,15 @@ impl ExExManager {
     /// You must provide an [`ExExHandle`] for each `ExEx` and the maximum capacity of the
     /// notification buffer in the manager.
     ///
-    /// When the capacity is exceeded (which can happen if an `ExEx` is slow) no one can send
-    /// notifications over [`ExExManagerHandle`]s until there is capacity again.
-    pub fn new(handles: Vec<ExExHandle>, max_capacity: usize) -> Self {
+    /// When the capacity is exceeded (which can happen if an `ExEx` is slow), notifications are handled
+    /// following the [`SlowConsumerPolicy`] of the config.
+    pub fn new(
+        handles: Vec<ExExHandle>,
+        config: ExExManagerConfig,
+        backend: Arc<MadaraBackend>,
+        metrics: ExExMetrics,
+    ) -> Self {
+        let ExExManagerConfig { capacity: max_capacity, policy } = config;
         let num_exexs = handles.len();
 
         let (handle_tx, handle_rx) = mpsc::unbounded_channel();
@@ -86,10 +127,11 @@ impl ExExManager {
 
             handle_rx,
 
-            min_id: 0,
             next_id: 0,
             buffer: VecDeque::with_capacity(max_capacity),
             max_capacity,
+            policy,
+            tip: None,
             current_capacity: Arc::clone(&current_capacity),
 
             is_ready: is_ready_tx,
@@ -102,7 +144,11 @@ impl ExExManager {
                 is_ready: ReusableBoxFuture::new(make_wait_future(is_ready_rx)),
                 current_capacity,
                 finished_height: finished_height_rx,
+                policy,
             },
+
+            backend,
+            metrics,
         }
     }
 
@@ -124,10 +170,47 @@ impl ExExManager {
     /// Pushes a new notification into the managers internal buffer, assigning the notification a
     /// unique ID.
     pub fn push_notification(&mut self, notification: ExExNotification) {
+        self.tip = Some(notification.block_number().0);
         let next_id = self.next_id;
         self.buffer.push_back((next_id, notification));
         self.next_id += 1;
     }
+
+    /// Drops the oldest notifications over capacity, skipping them for the `ExEx`'s that did not receive them yet.
+    ///
+    /// Reverted notifications are kept: an `ExEx` missing one would keep the side effects of the reverted blocks.
+    fn drop_oldest_notifications(&mut self) {
+        while self.buffer.len() > self.max_capacity {
+            let Some(index) = self
+                .buffer
+                .iter()
+                .position(|(_, notification)| !matches!(notification, ExExNotification::Reverted { .. }))
+            else {
+                break;
+            };
+            let Some((dropped_id, notification)) = self.buffer.remove(index) else { break };
+            for exex in &self.exex_handles {
+                if exex.next_notification_id <= dropped_id {
+                    self.metrics.dropped_notifications.with_label_values(&[&exex.id]).inc();
+                    log::warn!(
+                        "ExEx {} cannot keep up, dropped its notification for block #{}",
+                        exex.id,
+                        notification.block_number()
+                    );
+                }
+            }
+        }
+    }
+
+    fn update_metrics(&self) {
+        for exex in &self.exex_handles {
+            let queue_depth = self.next_id.saturating_sub(exex.next_notification_id);
+            self.metrics.queue_depth.with_label_values(&[&exex.id]).set(queue_depth as i64);
+            if let (Some(tip), Some(finished_height)) = (self.tip, exex.finished_height) {
+                self.metrics.lag.with_label_values(&[&exex.id]).set(tip.saturating_sub(finished_height.0) as i64);
+            }
+        }
+    }
 }
 
 impl std::future::Future for ExExManager {
@@ -149,19 +232,30 @@ impl std::future::Future for ExExManager {
         for exex in &mut this.exex_handles {
             while let Poll::Ready(Some(event)) = exex.receiver.poll_recv(cx) {
                 match event {
-                    ExExEvent::FinishedHeight(height) => exex.finished_height = Some(height),
+                    ExExEvent::FinishedHeight(height) => {
+                        if exex.finished_height != Some(height) {
+                            if let Err(err) = this.backend.write_exex_finished_height(&exex.id, height.0) {
+                                log::error!("Failed to persist the finished height of ExEx {}: {err:#}", exex.id);
+                            }
+                        }
+                        exex.finished_height = Some(height)
+                    }
                 }
             }
         }
 
-        // Drain handle notifications
-        while this.buffer.len() < this.max_capacity {
+        // Drain handle notifications. Only the pause policy leaves them in the handle channel when the buffer is full,
+        // as its senders wait for capacity.
+        while this.policy != SlowConsumerPolicy::Pause || this.buffer.len() < this.max_capacity {
             if let Poll::Ready(Some(notification)) = this.handle_rx.poll_recv(cx) {
                 this.push_notification(notification);
                 continue;
             }
             break;
         }
+        if this.policy == SlowConsumerPolicy::DropOldest {
+            this.drop_oldest_notifications();
+        }
 
         // Update capacity
         this.update_capacity();
@@ -171,12 +265,8 @@ impl std::future::Future for ExExManager {
         for idx in (0..this.exex_handles.len()).rev() {
             let mut exex = this.exex_handles.swap_remove(idx);
 
-            // It is a logic error for this to ever underflow since the manager manages the
-            // notification IDs
-            let notification_index = exex
-                .next_notification_id
-                .checked_sub(this.min_id)
-                .expect("exex expected notification ID outside the manager's range");
+            // The buffer is sorted by ID, and may skip the IDs of dropped notifications
+            let notification_index = this.buffer.partition_point(|&(id, _)| id < exex.next_notification_id);
             if let Some(notification) = this.buffer.get(notification_index) {
                 if let Poll::Ready(Err(err)) = exex.send(cx, notification) {
                     // The channel was closed, which is irrecoverable for the manager
@@ -189,7 +279,6 @@ impl std::future::Future for ExExManager {
 
         // Remove processed buffered notifications
         this.buffer.retain(|&(id, _)| id >= min_id);
-        this.min_id = min_id;
 
         // Update capacity
         this.update_capacity();
@@ -204,6 +293,8 @@ impl std::future::Future for ExExManager {
             let _ = this.finished_height.send(FinishedExExHeight::Height(BlockNumber(finished_height)));
         }
 
+        this.update_metrics();
+
         Poll::Pending
     }
 }
@@ -227,9 +318,20 @@ pub struct ExExManagerHandle {
     current_capacity: Arc<AtomicUsize>,
     /// The finished height of all `ExEx`'s.
     finished_height: watch::Receiver<FinishedExExHeight>,
+    /// What the manager does when an `ExEx` cannot keep up.
+    policy: SlowConsumerPolicy,
 }
 
 impl ExExManagerHandle {
+    /// Sends a notification to all execution extensions, following the [`SlowConsumerPolicy`] of the manager: with
+    /// [`SlowConsumerPolicy::Pause`], waits until the manager has capacity.
+    pub async fn notify(&mut self, notification: ExExNotification) -> Result<(), SendError<ExExNotification>> {
+        match self.policy {
+            SlowConsumerPolicy::Pause => self.send_async(notification).await,
+            SlowConsumerPolicy::Buffer | SlowConsumerPolicy::DropOldest => self.send(notification),
+        }
+    }
+
     /// Synchronously send a notification over the channel to all execution extensions.
     ///
     /// Senders should call [`Self::has_capacity`] first.
@@ -291,6 +393,7 @@ impl Clone for ExExManagerHandle {
             is_ready: ReusableBoxFuture::new(make_wait_future(self.is_ready_receiver.clone())),
             current_capacity: self.current_capacity.clone(),
             finished_height: self.finished_height.clone(),
+            policy: self.policy,
         }
     }
 }
@@ -364,3 +467,60 @@ async fn make_wait_future(mut rx: watch::Receiver<bool>) -> watch::Receiver<bool
     let _ = rx.wait_for(|ready| *ready).await;
     rx
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{future::poll_fn, StreamExt};
+    use mc_metrics::MetricsRegistry;
+    use mp_chain_config::ChainConfig;
+    use std::future::Future;
+
+    fn reverted(to: u64) -> ExExNotification {
+        ExExNotification::Reverted { from: BlockNumber(to + 1), to: BlockNumber(to) }
+    }
+
+    fn synced(block_n: u64) -> ExExNotification {
+        ExExNotification::BlockSynced { block_number: BlockNumber(block_n), block: None, state_diff: None }
+    }
+
+    /// Sends the notifications to a manager with a single `ExEx` and a buffer of 2 notifications, and polls it once.
+    async fn drop_oldest(sent: Vec<ExExNotification>) -> ExExNotifications {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let (handle, _events, notifications) = ExExHandle::new("test".into());
+        let config = ExExManagerConfig { capacity: 2, policy: SlowConsumerPolicy::DropOldest };
+        let metrics = ExExMetrics::register(&MetricsRegistry::dummy()).unwrap();
+        let manager = ExExManager::new(vec![handle], config, backend, metrics);
+        let manager_handle = manager.handle();
+
+        for notification in sent {
+            manager_handle.send(notification).unwrap();
+        }
+        let mut manager = std::pin::pin!(manager);
+        poll_fn(|cx| {
+            let _ = manager.as_mut().poll(cx);
+            Poll::Ready(())
+        })
+        .await;
+        notifications
+    }
+
+    #[test]
+    fn default_policy_does_not_drop() {
+        assert_eq!(ExExManagerConfig::default().policy, SlowConsumerPolicy::Pause);
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_skips_undelivered_notifications() {
+        let mut notifications = drop_oldest((0..4).map(synced).collect()).await;
+
+        assert_eq!(notifications.next().await.unwrap().block_number(), BlockNumber(2));
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_keeps_reverted_notifications() {
+        let mut notifications = drop_oldest(vec![reverted(0), synced(1), synced(2), synced(3)]).await;
+
+        assert!(matches!(notifications.next().await.unwrap(), ExExNotification::Reverted { to: BlockNumber(0), .. }));
+    }
+}
@@ -0,0 +1,30 @@
+use mc_metrics::{CounterVec, IntGaugeVec, MetricsRegistry, Opts, PrometheusError, U64};
+
+#[derive(Clone, Debug)]
+pub struct ExExMetrics {
+    /// Notifications waiting to be delivered, per `ExEx`.
+    pub queue_depth: IntGaugeVec,
+    /// Number of blocks between the latest notified block and the finished height, per `ExEx`.
+    pub lag: IntGaugeVec,
+    /// Notifications dropped because the `ExEx` could not keep up, per `ExEx`.
+    pub dropped_notifications: CounterVec<U64>,
+}
+
+impl ExExMetrics {
+    pub fn register(registry: &MetricsRegistry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            queue_depth: registry.register(IntGaugeVec::new(
+                Opts::new("exex_queue_depth", "Notifications waiting to be delivered to the ExEx"),
+                &["exex"],
+            )?)?,
+            lag: registry.register(IntGaugeVec::new(
+                Opts::new("exex_lag", "Number of blocks the ExEx has yet to process"),
+                &["exex"],
+            )?)?,
+            dropped_notifications: registry.register(CounterVec::new(
+                Opts::new("exex_dropped_notifications", "Notifications dropped because the ExEx could not keep up"),
+                &["exex"],
+            )?)?,
+        })
+    }
+}
@@ -1,32 +1,87 @@
 use std::{
+    fmt,
     pin::Pin,
-    task::{Context, Poll},
+    task::{ready, Context, Poll},
 };
 
-use futures::Stream;
-use mp_block::MadaraPendingBlock;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use mp_block::{MadaraBlock, MadaraPendingBlock};
+use mp_state_update::StateDiff;
 use starknet_api::block::BlockNumber;
 use tokio::sync::mpsc::Receiver;
 
 /// Notifications sent to an `ExEx`.
+///
+/// Notifications carry the block data the pipeline already had in hand when it is available, so that extensions
+/// do not have to query it back from the backend.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum ExExNotification {
     /// A new block got produced by the Block Production task.
-    BlockProduced { block: Box<MadaraPendingBlock>, block_number: BlockNumber },
+    BlockProduced {
+        block: Box<MadaraPendingBlock>,
+        block_number: BlockNumber,
+        /// State diff of the block.
+        state_diff: Option<Box<StateDiff>>,
+    },
     /// A new block got synced by the full node.
-    BlockSynced { block_number: BlockNumber },
+    BlockSynced {
+        block_number: BlockNumber,
+        /// The imported block, with its transactions and receipts.
+        block: Option<Box<MadaraBlock>>,
+        /// State diff of the block.
+        state_diff: Option<Box<StateDiff>>,
+    },
+    /// Blocks `to + 1..=from` were rolled back by the backend, and `to` is the new chain tip. Extensions
+    /// maintaining external state should undo the side effects of the reverted blocks.
+    Reverted { from: BlockNumber, to: BlockNumber },
+}
+
+impl ExExNotification {
+    /// Number of the block this notification is about. For reverts, this is the new chain tip.
+    pub fn block_number(&self) -> BlockNumber {
+        match self {
+            Self::BlockProduced { block_number, .. } | Self::BlockSynced { block_number, .. } => *block_number,
+            Self::Reverted { to, .. } => *to,
+        }
+    }
+
+    /// State diff of the block, if the notification carries it.
+    pub fn state_diff(&self) -> Option<&StateDiff> {
+        match self {
+            Self::BlockProduced { state_diff, .. } | Self::BlockSynced { state_diff, .. } => state_diff.as_deref(),
+            Self::Reverted { .. } => None,
+        }
+    }
 }
 
 /// A stream of [`ExExNotification`]s. The stream will emit notifications for all blocks.
-#[derive(Debug)]
+///
+/// Replayed notifications, for blocks the `ExEx` missed, are emitted before the live ones.
 pub struct ExExNotifications {
+    replay: Option<BoxStream<'static, ExExNotification>>,
     notifications: Receiver<ExExNotification>,
 }
 
+impl fmt::Debug for ExExNotifications {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExExNotifications")
+            .field("replaying", &self.replay.is_some())
+            .field("notifications", &self.notifications)
+            .finish()
+    }
+}
+
 impl ExExNotifications {
     /// Creates a new instance of [`ExExNotifications`].
     pub const fn new(notifications: Receiver<ExExNotification>) -> Self {
-        Self { notifications }
+        Self { replay: None, notifications }
+    }
+
+    /// Emits the `replay` notifications before the live ones.
+    pub fn with_replay(mut self, replay: BoxStream<'static, ExExNotification>) -> Self {
+        self.replay = Some(replay);
+        self
     }
 }
 
@@ -34,6 +89,36 @@ impl Stream for ExExNotifications {
     type Item = ExExNotification;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.get_mut().notifications.poll_recv(cx)
+        let this = self.get_mut();
+        if let Some(replay) = &mut this.replay {
+            match ready!(replay.poll_next_unpin(cx)) {
+                Some(notification) => return Poll::Ready(Some(notification)),
+                None => this.replay = None,
+            }
+        }
+        this.notifications.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    fn reverted(to: u64) -> ExExNotification {
+        ExExNotification::Reverted { from: BlockNumber(to + 1), to: BlockNumber(to) }
+    }
+
+    #[tokio::test]
+    async fn replayed_notifications_come_first() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        sender.send(reverted(3)).await.unwrap();
+        drop(sender);
+
+        let notifications =
+            ExExNotifications::new(receiver).with_replay(stream::iter([reverted(1), reverted(2)]).boxed());
+        let block_numbers: Vec<_> = notifications.map(|notification| notification.block_number().0).collect().await;
+
+        assert_eq!(block_numbers, vec![1, 2, 3]);
     }
 }
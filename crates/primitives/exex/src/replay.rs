@@ -0,0 +1,44 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use anyhow::Context;
+use futures::stream::{self, BoxStream, StreamExt};
+use mc_db::db_block_id::DbBlockId;
+use mc_db::MadaraBackend;
+use mp_block::MadaraBlock;
+use starknet_api::block::BlockNumber;
+
+use crate::ExExNotification;
+
+/// Streams notifications for blocks already in the backend, as if they had just been synced.
+///
+/// The replay stops at the first block that cannot be loaded.
+pub fn replay_blocks(backend: Arc<MadaraBackend>, blocks: RangeInclusive<u64>) -> BoxStream<'static, ExExNotification> {
+    stream::unfold(blocks, move |mut blocks| {
+        let backend = Arc::clone(&backend);
+        async move {
+            let block_n = blocks.next()?;
+            match load_block(&backend, block_n) {
+                Ok(notification) => Some((notification, blocks)),
+                Err(err) => {
+                    log::error!("Failed to replay block #{block_n} to the ExExs: {err:#}");
+                    None
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+fn load_block(backend: &MadaraBackend, block_n: u64) -> anyhow::Result<ExExNotification> {
+    let block_id = DbBlockId::Number(block_n);
+    let block = backend.get_block(&block_id)?.context("Block not found")?;
+    let block = MadaraBlock::try_from(block).ok().context("Block is pending")?;
+    let state_diff = backend.get_block_state_diff(&block_id)?.context("Block state diff not found")?;
+
+    Ok(ExExNotification::BlockSynced {
+        block_number: BlockNumber(block_n),
+        block: Some(Box::new(block)),
+        state_diff: Some(Box::new(state_diff)),
+    })
+}
@@ -0,0 +1,325 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+use mc_db::MadaraBackend;
+use mc_metrics::MetricsRegistry;
+use mp_rpc::Starknet;
+use mp_utils::service::{RestartPolicy, ServiceStatuses, Supervisor};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::task::JoinError;
+
+use crate::{replay_blocks, BoxExEx, BoxedLaunchExEx, ExExContext, ExExEvent, ExExNotification, ExExNotifications};
+
+/// Everything needed to (re)launch an `ExEx`.
+pub(crate) struct Supervised {
+    pub id: String,
+    pub exex: Box<dyn BoxedLaunchExEx>,
+    pub restart_policy: RestartPolicy,
+    pub starknet: Arc<Starknet>,
+    pub config: toml::Table,
+    pub events: UnboundedSender<ExExEvent>,
+    pub statuses: ServiceStatuses,
+    pub registry: MetricsRegistry,
+}
+
+impl Supervised {
+    /// Runs the `ExEx`, restarting it following its [`RestartPolicy`] whenever it crashes.
+    ///
+    /// The notifications stay with the supervisor across restarts: a notification that was not handed to a crashed
+    /// `ExEx` goes to the next run, and the blocks it received but did not report as finished are replayed.
+    pub async fn run(self, mut notifications: ExExNotifications) {
+        let backend = Arc::clone(&self.starknet.backend);
+        let mut pending = None;
+        let mut delivered = Delivered::default();
+        let mut supervisor = Supervisor::new(self.id.clone(), self.restart_policy, self.statuses.clone());
+
+        loop {
+            supervisor.starting();
+            let (sender, receiver) = mpsc::channel(1);
+            let mut run_notifications = ExExNotifications::new(receiver);
+            if supervisor.restarts() > 0 {
+                if let Some(replay) = delivered.unfinished(&backend, &self.id) {
+                    log::info!("🔁 Replaying blocks #{} to #{} to ExEx {}", replay.start(), replay.end(), self.id);
+                    run_notifications = run_notifications.with_replay(replay_blocks(Arc::clone(&backend), replay));
+                }
+            }
+            let context = ExExContext {
+                starknet: Arc::clone(&self.starknet),
+                backend: Arc::clone(&backend),
+                events: self.events.clone(),
+                notifications: run_notifications,
+                config: self.config.clone(),
+                metrics: self.registry.clone(),
+            };
+
+            let result = match self.exex.launch(context).await {
+                Ok(exex) => {
+                    supervisor.running();
+                    forward(exex, &mut notifications, sender, &mut pending, &mut delivered).await
+                }
+                Err(err) => Err(err.context("Launching the ExEx")),
+            };
+
+            let err = match result {
+                Ok(()) => {
+                    log::warn!("🧩 ExEx {} finished. ExExes should run indefinitely", self.id);
+                    supervisor.stopped();
+                    break;
+                }
+                Err(err) => err,
+            };
+            let Some(backoff) = supervisor.failed(&err) else {
+                log::error!("🧩 ExEx {} crashed and will not be restarted again: {err:#}", self.id);
+                break;
+            };
+            log::error!("🧩 ExEx {} crashed, restarting it in {backoff:?}: {err:#}", self.id);
+            tokio::time::sleep(backoff).await;
+        }
+
+        // Keep the notifications flowing, so that the other ExExs and the node are not held back.
+        while notifications.next().await.is_some() {}
+    }
+}
+
+/// Range of the block notifications handed to the `ExEx`.
+#[derive(Default)]
+struct Delivered {
+    first: Option<u64>,
+    last: Option<u64>,
+}
+
+impl Delivered {
+    fn record(&mut self, notification: &ExExNotification) {
+        if let ExExNotification::BlockProduced { block_number, .. }
+        | ExExNotification::BlockSynced { block_number, .. } = notification
+        {
+            self.first.get_or_insert(block_number.0);
+            self.last = Some(block_number.0);
+        }
+    }
+
+    /// Blocks that were handed to the `ExEx`, but that it did not report as finished.
+    fn unfinished(&self, backend: &MadaraBackend, id: &str) -> Option<std::ops::RangeInclusive<u64>> {
+        let finished_height = match backend.get_exex_finished_height(id) {
+            Ok(finished_height) => finished_height,
+            Err(err) => {
+                log::error!("Failed to get the finished height of ExEx {id}: {err:#}");
+                return None;
+            }
+        };
+        let start = finished_height.map(|height| height + 1).or(self.first)?;
+        let end = self.last?;
+        (start <= end).then_some(start..=end)
+    }
+}
+
+/// Result of a run of an `ExEx`, a panic being reported as a crash.
+fn run_result(result: Result<anyhow::Result<()>, JoinError>) -> anyhow::Result<()> {
+    match result {
+        Ok(result) => result,
+        Err(err) if err.is_panic() => Err(anyhow::Error::new(err).context("ExEx panicked")),
+        Err(err) => Err(anyhow::Error::new(err).context("ExEx task cancelled")),
+    }
+}
+
+/// Forwards the notifications to a run of an `ExEx` until it ends. A notification that could not be handed to the
+/// `ExEx` is left in `pending`.
+///
+/// The `ExEx` runs in its own task, so that it cannot block the supervisor and its panics are caught.
+async fn forward(
+    exex: BoxExEx,
+    notifications: &mut ExExNotifications,
+    sender: mpsc::Sender<ExExNotification>,
+    pending: &mut Option<ExExNotification>,
+    delivered: &mut Delivered,
+) -> anyhow::Result<()> {
+    let mut exex = tokio::spawn(exex);
+    loop {
+        let notification = match pending.take() {
+            Some(notification) => notification,
+            None => tokio::select! {
+                res = &mut exex => return run_result(res),
+                notification = notifications.next() => match notification {
+                    Some(notification) => notification,
+                    // The node is shutting down.
+                    None => {
+                        drop(sender);
+                        return run_result(exex.await);
+                    }
+                },
+            },
+        };
+
+        tokio::select! {
+            res = &mut exex => {
+                *pending = Some(notification);
+                return run_result(res);
+            }
+            permit = sender.reserve() => match permit {
+                Ok(permit) => {
+                    delivered.record(&notification);
+                    permit.send(notification);
+                }
+                Err(_) => {
+                    // The ExEx stopped listening to its notifications.
+                    *pending = Some(notification);
+                    return run_result(exex.await);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use jsonrpsee::core::RpcResult;
+    use mp_chain_config::ChainConfig;
+    use mp_rpc::errors::StarknetRpcApiError;
+    use mp_rpc::AddTransactionProvider;
+    use mp_utils::service::{ServiceHealth, ServiceStatus};
+    use starknet_core::types::{
+        BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction,
+        DeclareTransactionResult, DeployAccountTransactionResult, InvokeTransactionResult,
+    };
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// Rejects every transaction.
+    struct NoTransactionProvider;
+
+    #[jsonrpsee::core::async_trait]
+    impl AddTransactionProvider for NoTransactionProvider {
+        async fn add_declare_transaction(
+            &self,
+            _declare_transaction: BroadcastedDeclareTransaction,
+        ) -> RpcResult<DeclareTransactionResult> {
+            Err(StarknetRpcApiError::UnimplementedMethod.into())
+        }
+        async fn add_deploy_account_transaction(
+            &self,
+            _deploy_account_transaction: BroadcastedDeployAccountTransaction,
+        ) -> RpcResult<DeployAccountTransactionResult> {
+            Err(StarknetRpcApiError::UnimplementedMethod.into())
+        }
+        async fn add_invoke_transaction(
+            &self,
+            _invoke_transaction: BroadcastedInvokeTransaction,
+        ) -> RpcResult<InvokeTransactionResult> {
+            Err(StarknetRpcApiError::UnimplementedMethod.into())
+        }
+    }
+
+    /// Crashes on its first `crashes` launches, by returning an error or by panicking, and then runs until its
+    /// notifications end.
+    #[derive(Clone)]
+    struct Crashing {
+        launches: Arc<AtomicU32>,
+        crashes: u32,
+        panic: bool,
+    }
+
+    impl BoxedLaunchExEx for Crashing {
+        fn launch(&self, mut ctx: ExExContext) -> BoxFuture<'static, anyhow::Result<BoxExEx>> {
+            let launch = self.launches.fetch_add(1, Ordering::SeqCst);
+            let (crash, panic) = (launch < self.crashes, self.panic);
+            Box::pin(async move {
+                Ok(Box::pin(async move {
+                    if crash && panic {
+                        panic!("Crash #{launch}");
+                    }
+                    anyhow::ensure!(!crash, "Crash #{launch}");
+                    while ctx.notifications.next().await.is_some() {}
+                    Ok(())
+                }) as BoxExEx)
+            })
+        }
+    }
+
+    fn policy(max_restarts: u32, reset_after: Duration) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts: Some(max_restarts),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            reset_after,
+        }
+    }
+
+    /// Runs the `ExEx` under supervision until `until` returns true for its status.
+    async fn supervise(
+        exex: Crashing,
+        restart_policy: RestartPolicy,
+        until: impl Fn(&ServiceStatus) -> bool,
+    ) -> ServiceStatus {
+        let chain_config = Arc::new(ChainConfig::madara_test());
+        let backend = MadaraBackend::open_for_testing(Arc::clone(&chain_config));
+        let starknet = Arc::new(Starknet::new(backend, chain_config, Arc::new(NoTransactionProvider)));
+        let statuses = ServiceStatuses::default();
+        let supervised = Supervised {
+            id: "crashing".into(),
+            exex: Box::new(exex),
+            restart_policy,
+            starknet,
+            config: Default::default(),
+            events: mpsc::unbounded_channel().0,
+            statuses: statuses.clone(),
+            registry: MetricsRegistry::dummy(),
+        };
+        let (notifications_tx, notifications_rx) = mpsc::channel(1);
+        let supervisor = tokio::spawn(supervised.run(ExExNotifications::new(notifications_rx)));
+
+        let status = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(status) = statuses.get().into_iter().find(&until) {
+                    return status;
+                }
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("Timeout waiting for the ExEx status");
+
+        drop(notifications_tx);
+        supervisor.await.unwrap();
+        status
+    }
+
+    #[tokio::test]
+    async fn crashed_exex_is_restarted() {
+        let launches = Arc::new(AtomicU32::new(0));
+        let exex = Crashing { launches: Arc::clone(&launches), crashes: 2, panic: false };
+        let status = supervise(exex, policy(2, Duration::MAX), |status| {
+            status.health == ServiceHealth::Running && status.restarts == 2
+        })
+        .await;
+
+        assert_eq!(launches.load(Ordering::SeqCst), 3);
+        assert_eq!(status.restarts, 2);
+    }
+
+    #[tokio::test]
+    async fn panicking_exex_is_given_up_on() {
+        let launches = Arc::new(AtomicU32::new(0));
+        let exex = Crashing { launches: Arc::clone(&launches), crashes: 2, panic: true };
+        let status = supervise(exex, policy(1, Duration::MAX), |status| status.health == ServiceHealth::Failed).await;
+
+        assert_eq!(launches.load(Ordering::SeqCst), 2);
+        assert_eq!(status.restarts, 1);
+        assert!(status.last_error.unwrap().contains("ExEx panicked"));
+    }
+
+    #[tokio::test]
+    async fn healthy_run_resets_restarts() {
+        let launches = Arc::new(AtomicU32::new(0));
+        let exex = Crashing { launches: Arc::clone(&launches), crashes: 3, panic: false };
+        // Every run is healthy: the ExEx is restarted past `max_restarts`.
+        let status = supervise(exex, policy(1, Duration::ZERO), |status| {
+            status.health == ServiceHealth::Running && launches.load(Ordering::SeqCst) == 4
+        })
+        .await;
+
+        assert_eq!(launches.load(Ordering::SeqCst), 4);
+        assert_eq!(status.restarts, 1);
+    }
+}
@@ -259,6 +259,14 @@ pub enum BlockStatus {
     AcceptedOnL1,
 }
 
+/// Signature of a block by the sequencer, as returned by the `get_signature` endpoint.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderBlockSignature {
+    pub block_hash: Felt,
+    pub signature: Vec<Felt>,
+}
+
 fn starknet_version(version: StarknetVersion) -> Option<String> {
     match version {
         version if version < StarknetVersion::V0_9_1 => None,
@@ -273,3 +281,22 @@ fn receipts(receipts: Vec<mp_receipt::TransactionReceipt>) -> Vec<ConfirmedRecei
         .map(|(index, receipt)| ConfirmedReceipt::new(receipt, None, index as u64))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn block_signature_matches_the_feeder_gateway() {
+        let feeder = json!({
+            "block_hash": "0x47c3637b57c2b079b93c61539950c17e868a28f46cdef28f88521067f21e943",
+            "signature": [
+                "0x2f4b8a4bbf6b1ae51e4b9a3a3bb56dff0ccdcde0d87c14bf0c7a55bd9a16b0c",
+                "0x61e7a4c9c2bc1d8b3dbe5c0a36b1e63c4e1ba52b3bde53f4fdb7d4a7d6c3d45"
+            ]
+        });
+        let signature: ProviderBlockSignature = serde_json::from_value(feeder.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&signature).unwrap(), feeder);
+    }
+}
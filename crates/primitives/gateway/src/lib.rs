@@ -1,5 +1,6 @@
 pub mod block;
 pub mod receipt;
 pub mod state_update;
+pub mod trace;
 pub mod transaction;
 pub mod user_transaction;
@@ -146,7 +146,10 @@ pub struct ExecutionResources {
     pub builtin_instance_counter: BuiltinCounters,
     pub n_steps: u64,
     pub n_memory_holes: u64,
+    /// Not reported for the function invocations of the traces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub data_availability: Option<L1Gas>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub total_gas_consumed: Option<L1Gas>,
 }
 
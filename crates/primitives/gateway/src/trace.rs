@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use starknet_types_core::felt::Felt;
+
+use crate::receipt::{BuiltinCounters, ExecutionResources};
+
+/// Response of the `get_block_traces` feeder gateway endpoint.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct BlockTraces {
+    pub traces: Vec<TransactionTrace>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionTrace {
+    pub validate_invocation: Option<FunctionInvocation>,
+    /// The `__execute__` call of invoke transactions, the constructor call of deploy account transactions, and the
+    /// handler call of l1 handler transactions. `None` for declare and reverted transactions.
+    pub function_invocation: Option<FunctionInvocation>,
+    pub fee_transfer_invocation: Option<FunctionInvocation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revert_error: Option<String>,
+    pub signature: Vec<Felt>,
+    pub transaction_hash: Felt,
+}
+
+impl TransactionTrace {
+    pub fn new(trace: starknet_core::types::TransactionTraceWithHash, signature: Vec<Felt>) -> Self {
+        use starknet_core::types::{ExecuteInvocation, TransactionTrace as Trace};
+
+        let (validate_invocation, function_invocation, fee_transfer_invocation, revert_error) = match trace.trace_root {
+            Trace::Invoke(trace) => {
+                let (function_invocation, revert_error) = match trace.execute_invocation {
+                    ExecuteInvocation::Success(invocation) => (Some(invocation), None),
+                    ExecuteInvocation::Reverted(reverted) => (None, Some(reverted.revert_reason)),
+                };
+                (trace.validate_invocation, function_invocation, trace.fee_transfer_invocation, revert_error)
+            }
+            Trace::Declare(trace) => (trace.validate_invocation, None, trace.fee_transfer_invocation, None),
+            Trace::DeployAccount(trace) => {
+                (trace.validate_invocation, Some(trace.constructor_invocation), trace.fee_transfer_invocation, None)
+            }
+            Trace::L1Handler(trace) => (None, Some(trace.function_invocation), None, None),
+        };
+
+        Self {
+            validate_invocation: validate_invocation.map(Into::into),
+            function_invocation: function_invocation.map(Into::into),
+            fee_transfer_invocation: fee_transfer_invocation.map(Into::into),
+            revert_error,
+            signature,
+            transaction_hash: trace.transaction_hash,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct FunctionInvocation {
+    pub caller_address: Felt,
+    pub contract_address: Felt,
+    pub calldata: Vec<Felt>,
+    pub call_type: CallType,
+    pub class_hash: Felt,
+    pub selector: Felt,
+    pub entry_point_type: EntryPointType,
+    pub result: Vec<Felt>,
+    pub execution_resources: ExecutionResources,
+    pub internal_calls: Vec<FunctionInvocation>,
+    pub events: Vec<OrderedEvent>,
+    pub messages: Vec<OrderedMessage>,
+}
+
+impl From<starknet_core::types::FunctionInvocation> for FunctionInvocation {
+    fn from(invocation: starknet_core::types::FunctionInvocation) -> Self {
+        let resources = invocation.execution_resources;
+        Self {
+            caller_address: invocation.caller_address,
+            contract_address: invocation.contract_address,
+            calldata: invocation.calldata,
+            call_type: invocation.call_type.into(),
+            class_hash: invocation.class_hash,
+            selector: invocation.entry_point_selector,
+            entry_point_type: invocation.entry_point_type.into(),
+            result: invocation.result,
+            execution_resources: ExecutionResources {
+                builtin_instance_counter: BuiltinCounters {
+                    pedersen_builtin: resources.pedersen_builtin_applications.unwrap_or(0),
+                    range_check_builtin: resources.range_check_builtin_applications.unwrap_or(0),
+                    ecdsa_builtin: resources.ecdsa_builtin_applications.unwrap_or(0),
+                    bitwise_builtin: resources.bitwise_builtin_applications.unwrap_or(0),
+                    ec_op_builtin: resources.ec_op_builtin_applications.unwrap_or(0),
+                    keccak_builtin: resources.keccak_builtin_applications.unwrap_or(0),
+                    poseidon_builtin: resources.poseidon_builtin_applications.unwrap_or(0),
+                    segment_arena_builtin: resources.segment_arena_builtin.unwrap_or(0),
+                    ..Default::default()
+                },
+                n_steps: resources.steps,
+                n_memory_holes: resources.memory_holes.unwrap_or(0),
+                data_availability: None,
+                total_gas_consumed: None,
+            },
+            internal_calls: invocation.calls.into_iter().map(Into::into).collect(),
+            events: invocation.events.into_iter().map(Into::into).collect(),
+            messages: invocation.messages.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CallType {
+    Call,
+    Delegate,
+}
+
+impl From<starknet_core::types::CallType> for CallType {
+    fn from(call_type: starknet_core::types::CallType) -> Self {
+        match call_type {
+            starknet_core::types::CallType::Call => Self::Call,
+            // Library calls are executed in the context of the caller, which the feeder gateway reports as delegate
+            // calls.
+            starknet_core::types::CallType::LibraryCall | starknet_core::types::CallType::Delegate => Self::Delegate,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EntryPointType {
+    External,
+    L1Handler,
+    Constructor,
+}
+
+impl From<starknet_core::types::EntryPointType> for EntryPointType {
+    fn from(entry_point_type: starknet_core::types::EntryPointType) -> Self {
+        match entry_point_type {
+            starknet_core::types::EntryPointType::External => Self::External,
+            starknet_core::types::EntryPointType::L1Handler => Self::L1Handler,
+            starknet_core::types::EntryPointType::Constructor => Self::Constructor,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrderedEvent {
+    pub order: u64,
+    pub keys: Vec<Felt>,
+    pub data: Vec<Felt>,
+}
+
+impl From<starknet_core::types::OrderedEvent> for OrderedEvent {
+    fn from(event: starknet_core::types::OrderedEvent) -> Self {
+        Self { order: event.order, keys: event.keys, data: event.data }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct OrderedMessage {
+    pub order: u64,
+    pub to_address: Felt,
+    pub payload: Vec<Felt>,
+}
+
+impl From<starknet_core::types::OrderedMessage> for OrderedMessage {
+    fn from(message: starknet_core::types::OrderedMessage) -> Self {
+        Self { order: message.order, to_address: message.to_address, payload: message.payload }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ACCOUNT: &str = "0x4270219d365d6b017231b52e92b3fb5d7c8378b05e9abc97724537a80e93b0f";
+    const TOKEN: &str = "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+    const VALIDATE: &str = "0x162da33a4585851fe8d3af3c2a9c60b557814e221e0d4f30ff0b2189d9c7775";
+    const EXECUTE: &str = "0x15d40a3d6ca2ac30f4031e42be28da9b056fef9bb7357ac5e85627ee876e5ad";
+    const TRANSFER: &str = "0x83afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e";
+
+    fn invocation(contract_address: &str, selector: &str, internal_calls: Vec<serde_json::Value>) -> serde_json::Value {
+        json!({
+            "caller_address": "0x0",
+            "contract_address": contract_address,
+            "calldata": ["0x1", TOKEN],
+            "call_type": "CALL",
+            "class_hash": "0x29927c8af6bccf3f6fda035981e765a7bdbf18a2dc0d630494f8758aa908e2b",
+            "selector": selector,
+            "entry_point_type": "EXTERNAL",
+            "result": ["0x1"],
+            "execution_resources": {
+                "builtin_instance_counter": { "range_check_builtin": 2, "pedersen_builtin": 1 },
+                "n_steps": 89,
+                "n_memory_holes": 3
+            },
+            "internal_calls": internal_calls,
+            "events": [{ "order": 0, "keys": ["0x99cd8bde557814842a3121e8ddfd433a539b8c9f14bf31ebf108d12e6196e9"], "data": ["0x2a"] }],
+            "messages": [{ "order": 0, "to_address": "0xae0ee0a63a2ce6baeeffe56e7714fb4efe48d419", "payload": ["0xc"] }]
+        })
+    }
+
+    /// A `get_block_traces` response of the feeder gateway, with a successful invoke, a reverted invoke and an l1
+    /// handler transaction.
+    fn feeder_block_traces() -> serde_json::Value {
+        let mut l1_handler = invocation(TOKEN, TRANSFER, vec![]);
+        l1_handler["entry_point_type"] = json!("L1_HANDLER");
+        l1_handler["call_type"] = json!("DELEGATE");
+        json!({
+            "traces": [
+                {
+                    "validate_invocation": invocation(ACCOUNT, VALIDATE, vec![]),
+                    "function_invocation": invocation(ACCOUNT, EXECUTE, vec![invocation(TOKEN, TRANSFER, vec![])]),
+                    "fee_transfer_invocation": invocation(TOKEN, TRANSFER, vec![]),
+                    "signature": ["0x1c3c2e1d5e4f0a6b", "0x5d36e1a6b4b2c7f8"],
+                    "transaction_hash": "0x2a3f8a6c1c7b4d2e9b7c6e0f1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c"
+                },
+                {
+                    "validate_invocation": invocation(ACCOUNT, VALIDATE, vec![]),
+                    "function_invocation": null,
+                    "fee_transfer_invocation": invocation(TOKEN, TRANSFER, vec![]),
+                    "revert_error": "Error in the called contract",
+                    "signature": [],
+                    "transaction_hash": "0x1b2c3d"
+                },
+                {
+                    "validate_invocation": null,
+                    "function_invocation": l1_handler,
+                    "fee_transfer_invocation": null,
+                    "signature": [],
+                    "transaction_hash": "0x4e5f"
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn block_traces_match_the_feeder_gateway() {
+        let feeder = feeder_block_traces();
+        let traces: BlockTraces = serde_json::from_value(feeder.clone()).unwrap();
+        assert_eq!(traces.traces[1].revert_error.as_deref(), Some("Error in the called contract"));
+        assert_eq!(traces.traces[2].function_invocation.as_ref().unwrap().entry_point_type, EntryPointType::L1Handler);
+        assert_eq!(serde_json::to_value(&traces).unwrap(), feeder);
+    }
+
+    #[test]
+    fn unknown_trace_fields_are_rejected() {
+        let mut feeder = feeder_block_traces();
+        feeder["traces"][0]["state_diff"] = json!({});
+        assert!(serde_json::from_value::<BlockTraces>(feeder).is_err());
+    }
+
+    #[test]
+    fn library_calls_are_reported_as_delegate_calls() {
+        assert_eq!(CallType::from(starknet_core::types::CallType::LibraryCall), CallType::Delegate);
+        assert_eq!(serde_json::to_value(CallType::Delegate).unwrap(), json!("DELEGATE"));
+    }
+}
@@ -7,7 +7,8 @@ use starknet_core::types::{
     BroadcastedDeclareTransaction, BroadcastedDeclareTransactionV1, BroadcastedDeclareTransactionV2,
     BroadcastedDeclareTransactionV3, BroadcastedDeployAccountTransaction, BroadcastedDeployAccountTransactionV1,
     BroadcastedDeployAccountTransactionV3, BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1,
-    BroadcastedInvokeTransactionV3, BroadcastedTransaction,
+    BroadcastedInvokeTransactionV3, BroadcastedTransaction, DeclareTransactionResult, DeployAccountTransactionResult,
+    InvokeTransactionResult,
 };
 use starknet_types_core::felt::Felt;
 
@@ -15,7 +16,7 @@ use starknet_types_core::felt::Felt;
 #[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
 #[serde(deny_unknown_fields)]
 pub enum UserTransaction {
-    DeclareV1(UserDeclareTransaction),
+    Declare(UserDeclareTransaction),
     InvokeFunction(UserInvokeFunctionTransaction),
     DeployAccount(UserDeployAccountTransaction),
 }
@@ -23,13 +24,65 @@ pub enum UserTransaction {
 impl From<UserTransaction> for BroadcastedTransaction {
     fn from(transaction: UserTransaction) -> Self {
         match transaction {
-            UserTransaction::DeclareV1(v1) => BroadcastedTransaction::Declare(v1.into()),
+            UserTransaction::Declare(v1) => BroadcastedTransaction::Declare(v1.into()),
             UserTransaction::InvokeFunction(v1) => BroadcastedTransaction::Invoke(v1.into()),
             UserTransaction::DeployAccount(v1) => BroadcastedTransaction::DeployAccount(v1.into()),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AddTransactionCode {
+    TransactionReceived,
+}
+
+/// Response of the `add_transaction` gateway endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddTransactionResult {
+    pub code: AddTransactionCode,
+    pub transaction_hash: Felt,
+    /// Declared class, for declare transactions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_hash: Option<Felt>,
+    /// Deployed account, for deploy account transactions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<Felt>,
+}
+
+impl From<InvokeTransactionResult> for AddTransactionResult {
+    fn from(result: InvokeTransactionResult) -> Self {
+        Self {
+            code: AddTransactionCode::TransactionReceived,
+            transaction_hash: result.transaction_hash,
+            class_hash: None,
+            address: None,
+        }
+    }
+}
+
+impl From<DeclareTransactionResult> for AddTransactionResult {
+    fn from(result: DeclareTransactionResult) -> Self {
+        Self {
+            code: AddTransactionCode::TransactionReceived,
+            transaction_hash: result.transaction_hash,
+            class_hash: Some(result.class_hash),
+            address: None,
+        }
+    }
+}
+
+impl From<DeployAccountTransactionResult> for AddTransactionResult {
+    fn from(result: DeployAccountTransactionResult) -> Self {
+        Self {
+            code: AddTransactionCode::TransactionReceived,
+            transaction_hash: result.transaction_hash,
+            class_hash: None,
+            address: Some(result.contract_address),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "version")]
 pub enum UserDeclareTransaction {
@@ -58,6 +111,7 @@ pub struct UserDeclareV1Transaction {
     pub max_fee: Felt,
     pub signature: Vec<Felt>,
     pub nonce: Felt,
+    #[serde(default)]
     pub is_query: bool,
 }
 
@@ -82,6 +136,7 @@ pub struct UserDeclareV2Transaction {
     pub max_fee: Felt,
     pub signature: Vec<Felt>,
     pub nonce: Felt,
+    #[serde(default)]
     pub is_query: bool,
 }
 
@@ -112,6 +167,7 @@ pub struct UserDeclareV3Transaction {
     pub tip: u64,
     pub paymaster_data: Vec<Felt>,
     pub account_deployment_data: Vec<Felt>,
+    #[serde(default)]
     pub is_query: bool,
 }
 
@@ -159,6 +215,7 @@ pub struct UserInvokeFunctionV1Transaction {
     pub signature: Vec<Felt>,
     pub max_fee: Felt,
     pub nonce: Felt,
+    #[serde(default)]
     pub is_query: bool,
 }
 
@@ -187,6 +244,7 @@ pub struct UserInvokeFunctionV3Transaction {
     pub tip: u64,
     pub paymaster_data: Vec<Felt>,
     pub account_deployment_data: Vec<Felt>,
+    #[serde(default)]
     pub is_query: bool,
 }
 
@@ -234,6 +292,7 @@ pub struct UserDeployAccountV1Transaction {
     pub max_fee: Felt,
     pub signature: Vec<Felt>,
     pub nonce: Felt,
+    #[serde(default)]
     pub is_query: bool,
 }
 
@@ -263,6 +322,7 @@ pub struct UserDeployAccountV3Transaction {
     pub resource_bounds: ResourceBoundsMapping,
     pub tip: u64,
     pub paymaster_data: Vec<Felt>,
+    #[serde(default)]
     pub is_query: bool,
 }
 
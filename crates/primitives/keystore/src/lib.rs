@@ -0,0 +1,234 @@
+//! Keys of the sequencer: the key signing the produced blocks, the L1 key submitting the state updates and the keys
+//! of the accounts used by the ExExs.
+//!
+//! Keys are loaded from a [`KeySource`]:
+//! - an encrypted JSON keystore, with its password given directly, in an environment variable or in a file;
+//! - a hex private key injected in an environment variable, for secret managers, or given directly;
+//! - a [remote signer](RemoteSigner), for keys held in an HSM or a signing service. Only Starknet keys can be remote.
+//!
+//! Starknet keys are used through the [`StarknetSigner`] trait, so that the private key never has to be in the memory
+//! of the node.
+
+mod remote;
+
+pub use remote::RemoteSigner;
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use alloy::network::EthereumWallet;
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{bail, Context};
+use starknet_core::crypto::Signature;
+use starknet_core::types::Felt;
+use starknet_signers::SigningKey;
+use url::Url;
+
+/// A string kept out of the logs.
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Where the password of an encrypted keystore is read from.
+#[derive(Clone, Debug)]
+pub enum Password {
+    Value(Secret),
+    /// Name of an environment variable holding the password.
+    Env(String),
+    /// File holding the password. A trailing newline is ignored.
+    File(PathBuf),
+}
+
+impl Password {
+    pub fn read(&self) -> anyhow::Result<String> {
+        match self {
+            Self::Value(password) => Ok(password.expose().to_owned()),
+            Self::Env(var) => std::env::var(var).with_context(|| format!("Reading the keystore password from ${var}")),
+            Self::File(path) => Ok(std::fs::read_to_string(path)
+                .with_context(|| format!("Reading the keystore password from {}", path.display()))?
+                .trim_end_matches(['\r', '\n'])
+                .to_owned()),
+        }
+    }
+}
+
+/// Where a private key is loaded from.
+#[derive(Clone, Debug)]
+pub enum KeySource {
+    /// Encrypted JSON keystore.
+    Keystore { path: PathBuf, password: Password },
+    /// Name of an environment variable holding the hex private key.
+    Env(String),
+    /// Hex private key.
+    PrivateKey(Secret),
+    /// Signing service holding the key, see [`RemoteSigner`], and the bearer token it requires.
+    Remote { url: Url, auth_token: Option<Password> },
+}
+
+impl KeySource {
+    /// Picks the source of a key from the options of a config, `None` when none of them is set.
+    pub fn from_options(
+        keystore: Option<PathBuf>,
+        password: Option<Password>,
+        private_key_env: Option<String>,
+        remote_signer: Option<Url>,
+        remote_signer_token: Option<Password>,
+    ) -> anyhow::Result<Option<Self>> {
+        if remote_signer.is_none() && remote_signer_token.is_some() {
+            bail!("A remote signer token requires a remote signer");
+        }
+        match (keystore, private_key_env, remote_signer) {
+            (Some(path), None, None) => {
+                let password = password.context("Decrypting the keystore requires a password")?;
+                Ok(Some(Self::Keystore { path, password }))
+            }
+            (None, Some(var), None) => Ok(Some(Self::Env(var))),
+            (None, None, Some(url)) => Ok(Some(Self::Remote { url, auth_token: remote_signer_token })),
+            (None, None, None) => Ok(None),
+            _ => bail!("Only one of a keystore, a private key environment variable and a remote signer can be set"),
+        }
+    }
+
+    /// Loads a Starknet key. Remote signers are queried for their public key.
+    pub async fn starknet_signer(&self) -> anyhow::Result<Arc<dyn StarknetSigner>> {
+        let signer: Arc<dyn StarknetSigner> = match self {
+            Self::Keystore { path, password } => Arc::new(
+                SigningKey::from_keystore(path, &password.read()?)
+                    .with_context(|| format!("Decrypting the keystore {}", path.display()))?,
+            ),
+            Self::Env(var) => {
+                let private_key = Felt::from_hex(&read_env_private_key(var)?)
+                    .with_context(|| format!("Parsing the private key of ${var}"))?;
+                Arc::new(SigningKey::from_secret_scalar(private_key))
+            }
+            Self::PrivateKey(private_key) => {
+                let private_key = Felt::from_hex(private_key.expose().trim()).context("Parsing the private key")?;
+                Arc::new(SigningKey::from_secret_scalar(private_key))
+            }
+            Self::Remote { url, auth_token } => {
+                let auth_token = auth_token
+                    .as_ref()
+                    .map(|token| token.read().map(Secret::new))
+                    .transpose()
+                    .context("Reading the remote signer token")?;
+                Arc::new(RemoteSigner::connect(url.clone(), auth_token).await?)
+            }
+        };
+        Ok(signer)
+    }
+
+    /// Loads an Ethereum key, as a wallet signing L1 transactions.
+    pub fn ethereum_wallet(&self) -> anyhow::Result<EthereumWallet> {
+        let signer: PrivateKeySigner = match self {
+            Self::Keystore { path, password } => PrivateKeySigner::decrypt_keystore(path, password.read()?)
+                .with_context(|| format!("Decrypting the keystore {}", path.display()))?,
+            Self::Env(var) => {
+                read_env_private_key(var)?.parse().with_context(|| format!("Parsing the private key of ${var}"))?
+            }
+            Self::PrivateKey(private_key) => private_key.expose().trim().parse().context("Parsing the private key")?,
+            Self::Remote { .. } => bail!("Remote signers are only supported for Starknet keys"),
+        };
+        Ok(signer.into())
+    }
+}
+
+fn read_env_private_key(var: &str) -> anyhow::Result<String> {
+    let private_key = std::env::var(var).with_context(|| format!("Reading the private key from ${var}"))?;
+    Ok(private_key.trim().to_owned())
+}
+
+/// Signs hashes with a Starknet key. Implemented by local keys and [`RemoteSigner`]; other backends, like an HSM,
+/// only need to implement this trait.
+#[async_trait::async_trait]
+pub trait StarknetSigner: Send + Sync {
+    fn public_key(&self) -> Felt;
+
+    async fn sign(&self, hash: &Felt) -> anyhow::Result<Signature>;
+}
+
+#[async_trait::async_trait]
+impl StarknetSigner for SigningKey {
+    fn public_key(&self) -> Felt {
+        self.verifying_key().scalar()
+    }
+
+    async fn sign(&self, hash: &Felt) -> anyhow::Result<Signature> {
+        Ok(SigningKey::sign(self, hash)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use starknet_core::crypto::ecdsa_verify;
+
+    #[tokio::test]
+    async fn test_keystore_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = dir.path().join("keystore.json");
+        let password_file = dir.path().join("password");
+        let key = SigningKey::from_secret_scalar(Felt::from_hex_unchecked("0x1234"));
+        key.save_as_keystore(&keystore, "hunter2").unwrap();
+        std::fs::write(&password_file, "hunter2\n").unwrap();
+
+        let source =
+            KeySource::from_options(Some(keystore), Some(Password::File(password_file)), None, None).unwrap().unwrap();
+        let signer = source.starknet_signer().await.unwrap();
+        assert_eq!(signer.public_key(), key.verifying_key().scalar());
+
+        let hash = Felt::from(42);
+        let signature = signer.sign(&hash).await.unwrap();
+        assert!(ecdsa_verify(&signer.public_key(), &hash, &signature).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_env_key() {
+        std::env::set_var("MP_KEYSTORE_TEST_KEY", " 0x1234\n");
+        let source = KeySource::from_options(None, None, Some("MP_KEYSTORE_TEST_KEY".into()), None).unwrap().unwrap();
+        let signer = source.starknet_signer().await.unwrap();
+        assert_eq!(
+            signer.public_key(),
+            SigningKey::from_secret_scalar(Felt::from_hex_unchecked("0x1234")).verifying_key().scalar()
+        );
+
+        assert!(KeySource::Env("MP_KEYSTORE_TEST_MISSING_KEY".into()).starknet_signer().await.is_err());
+    }
+
+    #[test]
+    fn test_key_source_options() {
+        assert!(KeySource::from_options(None, None, None, None, None).unwrap().is_none());
+        assert!(KeySource::from_options(Some("keystore.json".into()), None, None, None, None).is_err());
+        assert!(KeySource::from_options(
+            Some("keystore.json".into()),
+            Some(Password::Value(Secret::new("password"))),
+            Some("KEY".into()),
+            None,
+            None
+        )
+        .is_err());
+
+        let url = Url::parse("http://localhost:8080").unwrap();
+        let token = Password::Env("MP_KEYSTORE_TEST_TOKEN".into());
+        assert!(KeySource::from_options(None, None, None, None, Some(token.clone())).is_err());
+        assert!(matches!(
+            KeySource::from_options(None, None, None, Some(url), Some(token)).unwrap(),
+            Some(KeySource::Remote { auth_token: Some(Password::Env(_)), .. })
+        ));
+    }
+}
@@ -0,0 +1,97 @@
+//! Client of a signing service holding a Starknet key, such as a proxy in front of an HSM or a cloud KMS.
+//!
+//! The service exposes two endpoints, with felts encoded as hex strings:
+//! - `GET /public_key` returns `{ "public_key": "0x..." }`;
+//! - `POST /sign` with `{ "hash": "0x..." }` returns `{ "r": "0x...", "s": "0x..." }`.
+//!
+//! When the service requires authentication, a bearer token is sent in the `Authorization` header of every request.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use starknet_core::crypto::{ecdsa_verify, Signature};
+use starknet_core::types::Felt;
+use url::Url;
+
+use crate::{Secret, StarknetSigner};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+struct PublicKeyResponse {
+    public_key: Felt,
+}
+
+#[derive(Serialize)]
+struct SignRequest {
+    hash: Felt,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    r: Felt,
+    s: Felt,
+}
+
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: Url,
+    public_key: Felt,
+}
+
+impl RemoteSigner {
+    /// Connects to the signing service and fetches its public key. The `auth_token` is sent as a bearer token.
+    pub async fn connect(url: Url, auth_token: Option<Secret>) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        if let Some(auth_token) = auth_token {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", auth_token.expose()))
+                .context("The remote signer token is not a valid header value")?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).default_headers(headers).build()?;
+        let PublicKeyResponse { public_key } = client
+            .get(url.join("public_key")?)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .with_context(|| format!("Getting the public key of the remote signer {url}"))?
+            .json()
+            .await
+            .context("Parsing the public key of the remote signer")?;
+        log::debug!("Connected to the remote signer {url}, with public key {public_key:#x}");
+        Ok(Self { client, url, public_key })
+    }
+}
+
+#[async_trait::async_trait]
+impl StarknetSigner for RemoteSigner {
+    fn public_key(&self) -> Felt {
+        self.public_key
+    }
+
+    async fn sign(&self, hash: &Felt) -> anyhow::Result<Signature> {
+        let SignResponse { r, s } = self
+            .client
+            .post(self.url.join("sign")?)
+            .json(&SignRequest { hash: *hash })
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .with_context(|| format!("Signing with the remote signer {}", self.url))?
+            .json()
+            .await
+            .context("Parsing the signature of the remote signer")?;
+
+        // Catches a misconfigured service before its signatures are published.
+        let signature = Signature { r, s };
+        anyhow::ensure!(
+            ecdsa_verify(&self.public_key, hash, &signature).unwrap_or(false),
+            "The remote signer {} returned a signature that does not match its public key",
+            self.url
+        );
+        Ok(signature)
+    }
+}
@@ -0,0 +1,152 @@
+//! In-memory cache of the most recent closed blocks, so that the calls following the chain tip, such as
+//! `getBlockWithTxs` on the latest block or `getTransactionReceipt` on fresh transactions, do not read and deserialize
+//! the same blocks from the database over and over.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use mc_db::{BlockRevert, MadaraBackend};
+use mp_block::MadaraBlock;
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::{broadcast, watch};
+
+/// Number of blocks cached by default.
+pub const DEFAULT_BLOCK_CACHE_SIZE: usize = 64;
+
+/// Cache of the last `capacity` closed blocks, with their transactions and receipts. The pending block is never
+/// cached.
+pub struct BlockCache {
+    capacity: usize,
+    inner: Mutex<BlockCacheInner>,
+}
+
+struct BlockCacheInner {
+    blocks: BTreeMap<u64, MadaraBlock>,
+    closed_blocks: watch::Receiver<Option<u64>>,
+    reverts: broadcast::Receiver<BlockRevert>,
+}
+
+impl BlockCache {
+    /// A `capacity` of 0 disables the cache.
+    pub fn new(backend: &MadaraBackend, capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(BlockCacheInner {
+                blocks: BTreeMap::new(),
+                closed_blocks: backend.subscribe_closed_blocks(),
+                reverts: backend.subscribe_block_reverts(),
+            }),
+        }
+    }
+
+    pub fn get(&self, block_n: u64) -> Option<MadaraBlock> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut inner = self.inner.lock().expect("Poisoned lock");
+        inner.invalidate(self.capacity);
+        inner.blocks.get(&block_n).cloned()
+    }
+
+    /// Caches the block when it is one of the last `capacity` blocks of the chain.
+    pub fn insert(&self, block: MadaraBlock) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().expect("Poisoned lock");
+        inner.invalidate(self.capacity);
+        let Some(latest) = *inner.closed_blocks.borrow() else { return };
+        let block_n = block.info.header.block_number;
+        if block_n <= latest && latest - block_n < self.capacity as u64 {
+            inner.blocks.insert(block_n, block);
+        }
+    }
+}
+
+impl BlockCacheInner {
+    /// Drops the reverted blocks, and the blocks no longer among the last `capacity` blocks.
+    fn invalidate(&mut self, capacity: usize) {
+        loop {
+            match self.reverts.try_recv() {
+                Ok(BlockRevert { to, .. }) => {
+                    self.blocks.split_off(&(to + 1));
+                }
+                // The reverts we missed are unknown.
+                Err(TryRecvError::Lagged(_)) => self.blocks.clear(),
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            }
+        }
+
+        if self.closed_blocks.has_changed().unwrap_or(false) {
+            if let Some(latest) = *self.closed_blocks.borrow_and_update() {
+                // A block imported at a height already cached replaces a reverted block.
+                self.blocks.split_off(&latest);
+                self.blocks = self.blocks.split_off(&(latest + 1).saturating_sub(capacity as u64));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mp_block::header::Header;
+    use mp_block::{MadaraBlockInfo, MadaraBlockInner};
+    use mp_chain_config::ChainConfig;
+    use mp_state_update::StateDiff;
+    use rstest::rstest;
+    use starknet_core::types::Felt;
+    use std::sync::Arc;
+
+    fn block(block_n: u64, block_hash: u64) -> MadaraBlock {
+        MadaraBlock {
+            info: MadaraBlockInfo {
+                header: Header { block_number: block_n, ..Default::default() },
+                block_hash: Felt::from(block_hash),
+                tx_hashes: vec![],
+            },
+            inner: MadaraBlockInner::default(),
+        }
+    }
+
+    fn store_block(backend: &MadaraBackend, block_n: u64, block_hash: u64) {
+        backend.store_block(block(block_n, block_hash).into(), StateDiff::default(), vec![]).unwrap();
+    }
+
+    #[rstest]
+    #[case(0, 3, None)]
+    #[case(4, 3, Some(3))]
+    #[case(4, 0, None)]
+    #[case(4, 4, Some(4))]
+    #[case(2, 2, None)]
+    fn only_the_last_blocks_are_cached(#[case] capacity: usize, #[case] block_n: u64, #[case] expected: Option<u64>) {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let cache = BlockCache::new(&backend, capacity);
+        for block_n in 0..=4 {
+            store_block(&backend, block_n, block_n);
+        }
+
+        cache.insert(block(block_n, block_n));
+        assert_eq!(cache.get(block_n).map(|block| block.info.header.block_number), expected);
+    }
+
+    #[test]
+    fn reverted_blocks_are_dropped() {
+        let backend = MadaraBackend::open_for_testing(Arc::new(ChainConfig::madara_test()));
+        let cache = BlockCache::new(&backend, 8);
+        for block_n in 0..=5 {
+            store_block(&backend, block_n, block_n);
+            cache.insert(block(block_n, block_n));
+        }
+
+        backend.revert_to(3).unwrap();
+        backend.notify_block_revert(BlockRevert { from: 5, to: 3 });
+        assert!(cache.get(3).is_some());
+        assert!(cache.get(4).is_none());
+        assert!(cache.get(5).is_none());
+
+        store_block(&backend, 4, 40);
+        cache.insert(block(4, 40));
+        assert_eq!(cache.get(4).unwrap().info.block_hash, Felt::from(40));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod block_cache;
 pub mod errors;
 pub mod utils;
 
@@ -5,17 +6,30 @@ pub use utils::*;
 
 use std::sync::Arc;
 
+use block_cache::{BlockCache, DEFAULT_BLOCK_CACHE_SIZE};
 use errors::{StarknetRpcApiError, StarknetRpcResult};
 use jsonrpsee::core::{async_trait, RpcResult};
-use mc_db::{db_block_id::DbBlockIdResolvable, MadaraBackend};
-use mp_block::{MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
+use mc_db::block_db::TxIndex;
+use mc_db::db_block_id::{DbBlockId, DbBlockIdResolvable};
+use mc_db::MadaraBackend;
+use mp_block::{MadaraBlock, MadaraMaybePendingBlock, MadaraMaybePendingBlockInfo};
 use mp_chain_config::{ChainConfig, RpcVersion};
 use mp_convert::ToFelt;
+use serde::{Deserialize, Serialize};
 use starknet_core::types::{
     BroadcastedDeclareTransaction, BroadcastedDeployAccountTransaction, BroadcastedInvokeTransaction,
-    DeclareTransactionResult, DeployAccountTransactionResult, Felt, InvokeTransactionResult,
+    BroadcastedTransaction, DeclareTransactionResult, DeployAccountTransactionResult, Felt, InvokeTransactionResult,
 };
 
+/// Outcome of a bundle of transactions included in a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionBundleResult {
+    /// The block the transactions were included in, consecutively.
+    pub block_number: u64,
+    /// Hashes of the transactions, in the order of the bundle.
+    pub transaction_hashes: Vec<Felt>,
+}
+
 #[async_trait]
 pub trait AddTransactionProvider: Send + Sync {
     async fn add_declare_transaction(
@@ -32,6 +46,15 @@ pub trait AddTransactionProvider: Send + Sync {
         &self,
         invoke_transaction: BroadcastedInvokeTransaction,
     ) -> RpcResult<InvokeTransactionResult>;
+
+    /// Adds transactions which must be included consecutively in one block, or not at all. Returns once the bundle
+    /// was included or rejected. Only supported by sequencers.
+    async fn add_transaction_bundle(
+        &self,
+        _transactions: Vec<BroadcastedTransaction>,
+    ) -> RpcResult<TransactionBundleResult> {
+        Err(StarknetRpcApiError::UnimplementedMethod.into())
+    }
 }
 
 /// A Starknet RPC server for Madara
@@ -40,6 +63,7 @@ pub struct Starknet {
     pub backend: Arc<MadaraBackend>,
     pub chain_config: Arc<ChainConfig>,
     pub add_transaction_provider: Arc<dyn AddTransactionProvider>,
+    block_cache: Arc<BlockCache>,
 }
 
 impl Starknet {
@@ -48,7 +72,14 @@ impl Starknet {
         chain_config: Arc<ChainConfig>,
         add_transaction_provider: Arc<dyn AddTransactionProvider>,
     ) -> Self {
-        Self { backend, add_transaction_provider, chain_config }
+        let block_cache = Arc::new(BlockCache::new(&backend, DEFAULT_BLOCK_CACHE_SIZE));
+        Self { backend, add_transaction_provider, chain_config, block_cache }
+    }
+
+    /// Caches the last `size` blocks in memory, 0 disables the cache.
+    pub fn with_block_cache_size(mut self, size: usize) -> Self {
+        self.block_cache = Arc::new(BlockCache::new(&self.backend, size));
+        self
     }
 
     pub fn clone_backend(&self) -> Arc<MadaraBackend> {
@@ -73,10 +104,46 @@ impl Starknet {
     }
 
     pub fn get_block(&self, block_id: &impl DbBlockIdResolvable) -> StarknetRpcResult<MadaraMaybePendingBlock> {
-        self.backend
-            .get_block(block_id)
-            .or_internal_server_error("Error getting block from storage")?
-            .ok_or(StarknetRpcApiError::BlockNotFound)
+        let block_id = self
+            .backend
+            .resolve_block_id(block_id)
+            .or_internal_server_error("Error resolving block id")?
+            .ok_or(StarknetRpcApiError::BlockNotFound)?;
+        self.get_block_from_db_id(block_id)?.ok_or(StarknetRpcApiError::BlockNotFound)
+    }
+
+    /// Returns the block of the transaction, and the index of the transaction in the block.
+    pub fn find_tx_hash_block(&self, tx_hash: &Felt) -> StarknetRpcResult<Option<(MadaraMaybePendingBlock, TxIndex)>> {
+        let Some(block_n) =
+            self.backend.tx_hash_to_block_n(tx_hash).or_internal_server_error("Error getting block from tx hash")?
+        else {
+            // The transaction may be in the pending block.
+            return self
+                .backend
+                .find_tx_hash_block(tx_hash)
+                .or_internal_server_error("Error getting block from tx hash");
+        };
+        let Some(block) = self.get_block_from_db_id(DbBlockId::Number(block_n))? else { return Ok(None) };
+        let Some(tx_index) = block.info.tx_hashes().iter().position(|hash| hash == tx_hash) else { return Ok(None) };
+        Ok(Some((block, TxIndex(tx_index as _))))
+    }
+
+    fn get_block_from_db_id(&self, block_id: DbBlockId) -> StarknetRpcResult<Option<MadaraMaybePendingBlock>> {
+        let DbBlockId::Number(block_n) = block_id else {
+            return self.backend.get_block(&block_id).or_internal_server_error("Error getting block from storage");
+        };
+        if let Some(block) = self.block_cache.get(block_n) {
+            return Ok(Some(block.into()));
+        }
+        let Some(block) =
+            self.backend.get_block(&block_id).or_internal_server_error("Error getting block from storage")?
+        else {
+            return Ok(None);
+        };
+        if let MadaraMaybePendingBlockInfo::NotPending(info) = &block.info {
+            self.block_cache.insert(MadaraBlock { info: info.clone(), inner: block.inner.clone() });
+        }
+        Ok(Some(block))
     }
 
     pub fn chain_id(&self) -> Felt {
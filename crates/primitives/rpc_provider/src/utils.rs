@@ -1,9 +1,36 @@
 use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
 
 use crate::errors::StarknetRpcApiError;
 
+tokio::task_local! {
+    /// Id of the RPC request being served, returned to the caller in the `X-Request-Id` header.
+    static REQUEST_ID: Arc<str>;
+}
+
+/// Serves a request with its id, making it available to [`current_request_id`]. The synchronous RPC methods run when
+/// the request is dispatched, and the asynchronous ones when the returned future is polled, so both need it.
+pub fn with_request_id_sync<R>(request_id: Arc<str>, f: impl FnOnce() -> R) -> R {
+    REQUEST_ID.sync_scope(request_id, f)
+}
+
+/// See [`with_request_id_sync`].
+pub async fn with_request_id<F: Future>(request_id: Arc<str>, fut: F) -> F::Output {
+    REQUEST_ID.scope(request_id, fut).await
+}
+
+/// Id of the RPC request being served by the current task. Work moved to other threads, such as the execution of
+/// transactions, does not have it.
+pub fn current_request_id() -> Option<Arc<str>> {
+    REQUEST_ID.try_with(Arc::clone).ok()
+}
+
 pub fn display_internal_server_error(err: impl fmt::Display) {
-    log::error!(target: "rpc_errors", "{:#}", err);
+    match current_request_id() {
+        Some(request_id) => log::error!(target: "rpc_errors", "{:#} (request id {request_id})", err),
+        None => log::error!(target: "rpc_errors", "{:#}", err),
+    }
 }
 
 #[macro_export]
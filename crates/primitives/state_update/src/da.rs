@@ -0,0 +1,212 @@
+//! Aggregation of state diffs, and their encoding for data availability.
+
+use crate::{
+    ContractStorageDiffItem, DeclaredClassItem, DeployedContractItem, NonceUpdate, ReplacedClassItem, StateDiff,
+    StorageEntry,
+};
+use starknet_types_core::felt::Felt;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Offset of the class flag in the packed contract summary.
+const CLASS_FLAG_SHIFT: u32 = 128;
+/// Offset of the nonce in the packed contract summary.
+const NONCE_SHIFT: u32 = 64;
+
+impl StateDiff {
+    /// Merges a state diff applied after this one into it. Entries of `next` take precedence.
+    ///
+    /// The result is sorted.
+    pub fn merge(&mut self, next: StateDiff) {
+        let mut storage: BTreeMap<Felt, BTreeMap<Felt, Felt>> = BTreeMap::new();
+        for diff in self.storage_diffs.drain(..).chain(next.storage_diffs) {
+            let entries = storage.entry(diff.address).or_default();
+            entries.extend(diff.storage_entries.into_iter().map(|entry| (entry.key, entry.value)));
+        }
+        self.storage_diffs = storage
+            .into_iter()
+            .map(|(address, entries)| ContractStorageDiffItem {
+                address,
+                storage_entries: entries.into_iter().map(|(key, value)| StorageEntry { key, value }).collect(),
+            })
+            .collect();
+
+        let deprecated: BTreeSet<Felt> =
+            self.deprecated_declared_classes.drain(..).chain(next.deprecated_declared_classes).collect();
+        self.deprecated_declared_classes = deprecated.into_iter().collect();
+
+        let declared: BTreeMap<Felt, Felt> = self
+            .declared_classes
+            .drain(..)
+            .chain(next.declared_classes)
+            .map(|item| (item.class_hash, item.compiled_class_hash))
+            .collect();
+        self.declared_classes = declared
+            .into_iter()
+            .map(|(class_hash, compiled_class_hash)| DeclaredClassItem { class_hash, compiled_class_hash })
+            .collect();
+
+        // A contract deployed and then replaced within the merged range is reported as deployed with its last class.
+        let mut deployed: BTreeMap<Felt, Felt> = self
+            .deployed_contracts
+            .drain(..)
+            .chain(next.deployed_contracts)
+            .map(|d| (d.address, d.class_hash))
+            .collect();
+        let mut replaced: BTreeMap<Felt, Felt> = BTreeMap::new();
+        for item in self.replaced_classes.drain(..).chain(next.replaced_classes) {
+            match deployed.get_mut(&item.contract_address) {
+                Some(class_hash) => *class_hash = item.class_hash,
+                None => {
+                    replaced.insert(item.contract_address, item.class_hash);
+                }
+            }
+        }
+        self.deployed_contracts =
+            deployed.into_iter().map(|(address, class_hash)| DeployedContractItem { address, class_hash }).collect();
+        self.replaced_classes = replaced
+            .into_iter()
+            .map(|(contract_address, class_hash)| ReplacedClassItem { contract_address, class_hash })
+            .collect();
+
+        let nonces: BTreeMap<Felt, Felt> =
+            self.nonces.drain(..).chain(next.nonces).map(|n| (n.contract_address, n.nonce)).collect();
+        self.nonces =
+            nonces.into_iter().map(|(contract_address, nonce)| NonceUpdate { contract_address, nonce }).collect();
+    }
+
+    /// Addresses of all the contracts touched by this state diff, sorted.
+    pub fn touched_contracts(&self) -> BTreeSet<Felt> {
+        self.storage_diffs
+            .iter()
+            .map(|diff| diff.address)
+            .chain(self.deployed_contracts.iter().map(|item| item.address))
+            .chain(self.replaced_classes.iter().map(|item| item.contract_address))
+            .chain(self.nonces.iter().map(|item| item.contract_address))
+            .collect()
+    }
+
+    /// Encodes the state diff in the format published by the Starknet OS for data availability.
+    ///
+    /// ```text
+    /// [n_contracts,
+    ///     (address, class_flag << 128 | nonce << 64 | n_storage_updates, [class_hash], (key, value)*)*,
+    ///  n_declared_classes,
+    ///     (class_hash, compiled_class_hash)*]
+    /// ```
+    ///
+    /// The nonce of every touched contract is part of the encoding: contracts without a nonce update are
+    /// encoded with a nonce of zero, so callers should include their current nonce in `nonces`.
+    pub fn encode_da(&self) -> Vec<Felt> {
+        let storage: BTreeMap<Felt, &[StorageEntry]> =
+            self.storage_diffs.iter().map(|diff| (diff.address, diff.storage_entries.as_slice())).collect();
+        let classes: BTreeMap<Felt, Felt> = self
+            .deployed_contracts
+            .iter()
+            .map(|item| (item.address, item.class_hash))
+            .chain(self.replaced_classes.iter().map(|item| (item.contract_address, item.class_hash)))
+            .collect();
+        let nonces: BTreeMap<Felt, Felt> = self.nonces.iter().map(|item| (item.contract_address, item.nonce)).collect();
+
+        let touched_contracts = self.touched_contracts();
+        let mut encoded = vec![Felt::from(touched_contracts.len() as u64)];
+
+        for address in touched_contracts {
+            let entries = storage.get(&address).copied().unwrap_or_default();
+            let class_hash = classes.get(&address);
+            let nonce = nonces.get(&address).copied().unwrap_or_default();
+
+            let class_flag = if class_hash.is_some() { Felt::ONE } else { Felt::ZERO };
+            let summary = class_flag * Felt::TWO.pow(CLASS_FLAG_SHIFT)
+                + nonce * Felt::TWO.pow(NONCE_SHIFT)
+                + Felt::from(entries.len() as u64);
+
+            encoded.push(address);
+            encoded.push(summary);
+            encoded.extend(class_hash);
+            encoded.extend(entries.iter().flat_map(|entry| [entry.key, entry.value]));
+        }
+
+        let mut declared_classes = self.declared_classes.clone();
+        declared_classes.sort_by_key(|item| item.class_hash);
+        encoded.push(Felt::from(declared_classes.len() as u64));
+        encoded.extend(declared_classes.into_iter().flat_map(|item| [item.class_hash, item.compiled_class_hash]));
+
+        encoded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::dummy_state_diff;
+
+    #[test]
+    fn test_merge() {
+        let mut state_diff = dummy_state_diff();
+        state_diff.merge(StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: Felt::from(1),
+                storage_entries: vec![
+                    StorageEntry { key: Felt::from(2), value: Felt::from(100) },
+                    StorageEntry { key: Felt::from(101), value: Felt::from(102) },
+                ],
+            }],
+            replaced_classes: vec![ReplacedClassItem { contract_address: Felt::from(17), class_hash: Felt::from(103) }],
+            nonces: vec![NonceUpdate { contract_address: Felt::from(25), nonce: Felt::from(104) }],
+            ..Default::default()
+        });
+
+        assert_eq!(
+            state_diff.storage_diffs[0],
+            ContractStorageDiffItem {
+                address: Felt::from(1),
+                storage_entries: vec![
+                    StorageEntry { key: Felt::from(2), value: Felt::from(100) },
+                    StorageEntry { key: Felt::from(4), value: Felt::from(5) },
+                    StorageEntry { key: Felt::from(101), value: Felt::from(102) },
+                ],
+            }
+        );
+        assert_eq!(
+            state_diff.deployed_contracts[0],
+            DeployedContractItem { address: Felt::from(17), class_hash: Felt::from(103) }
+        );
+        assert_eq!(state_diff.replaced_classes.len(), 2);
+        assert_eq!(state_diff.nonces[0], NonceUpdate { contract_address: Felt::from(25), nonce: Felt::from(104) });
+        assert_eq!(state_diff.len(), 15);
+    }
+
+    #[test]
+    fn test_encode_da() {
+        let state_diff = StateDiff {
+            storage_diffs: vec![ContractStorageDiffItem {
+                address: Felt::from(1),
+                storage_entries: vec![StorageEntry { key: Felt::from(2), value: Felt::from(3) }],
+            }],
+            declared_classes: vec![DeclaredClassItem { class_hash: Felt::from(4), compiled_class_hash: Felt::from(5) }],
+            deployed_contracts: vec![DeployedContractItem { address: Felt::from(6), class_hash: Felt::from(7) }],
+            nonces: vec![NonceUpdate { contract_address: Felt::from(1), nonce: Felt::from(8) }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            state_diff.encode_da(),
+            vec![
+                Felt::from(2),
+                // contract 1: nonce 8, 1 storage update
+                Felt::from(1),
+                Felt::from((8u128 << 64) + 1),
+                Felt::from(2),
+                Felt::from(3),
+                // contract 6: deployed, no storage update
+                Felt::from(6),
+                Felt::TWO.pow(128u32),
+                Felt::from(7),
+                // declared classes
+                Felt::from(1),
+                Felt::from(4),
+                Felt::from(5),
+            ]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+mod da;
 mod from_provider;
 mod into_starknet_core;
 
@@ -189,6 +189,16 @@ impl Transaction {
         matches!(self, Transaction::L1Handler(_))
     }
 
+    /// L1 handler and deploy transactions are not signed, and have an empty signature.
+    pub fn signature(&self) -> &[Felt] {
+        match self {
+            Transaction::Invoke(tx) => tx.signature(),
+            Transaction::Declare(tx) => tx.signature(),
+            Transaction::DeployAccount(tx) => tx.signature(),
+            Transaction::L1Handler(_) | Transaction::Deploy(_) => &[],
+        }
+    }
+
     /// Account transactions means everything except L1Handler.
     pub fn is_account(&self) -> bool {
         !matches!(self, Transaction::L1Handler(_))
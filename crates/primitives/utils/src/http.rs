@@ -0,0 +1,68 @@
+//! Helpers shared by the HTTP servers of the node.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use forwarded_header_value::ForwardedHeaderValue;
+use hyper::header::FORWARDED;
+use hyper::{Body, Request};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_REAL_IP: &str = "x-real-ip";
+
+/// Extracts the IP addr of the client from the headers set by a reverse proxy.
+///
+/// It is extracted in the following order:
+/// 1. `Forwarded` header.
+/// 2. `X-Forwarded-For` header.
+/// 3. `X-Real-Ip`.
+pub fn get_proxy_ip(req: &Request<Body>) -> Option<IpAddr> {
+    let headers = req.headers();
+    headers
+        .get(FORWARDED)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| ForwardedHeaderValue::from_forwarded(v).ok())
+        .and_then(|v| v.remotest_forwarded_for_ip())
+        .or_else(|| {
+            headers
+                .get(X_FORWARDED_FOR)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| ForwardedHeaderValue::from_x_forwarded_for(v).ok())
+                .and_then(|v| v.remotest_forwarded_for_ip())
+        })
+        .or_else(|| headers.get(X_REAL_IP).and_then(|v| v.to_str().ok()).and_then(|v| IpAddr::from_str(v).ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn request(headers: &[(&str, &str)]) -> Request<Body> {
+        let mut builder = Request::builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_get_proxy_ip() {
+        let ip = |n| Some(IpAddr::V4(Ipv4Addr::new(n, n, n, n)));
+        assert_eq!(get_proxy_ip(&request(&[])), None);
+        assert_eq!(get_proxy_ip(&request(&[("x-real-ip", "3.3.3.3")])), ip(3));
+        assert_eq!(
+            get_proxy_ip(&request(&[("x-forwarded-for", "2.2.2.2, 10.0.0.1"), ("x-real-ip", "3.3.3.3")])),
+            ip(2)
+        );
+        assert_eq!(
+            get_proxy_ip(&request(&[
+                ("forwarded", "for=1.1.1.1;proto=https"),
+                ("x-forwarded-for", "2.2.2.2"),
+                ("x-real-ip", "3.3.3.3")
+            ])),
+            ip(1)
+        );
+        assert_eq!(get_proxy_ip(&request(&[("x-real-ip", "not an ip")])), None);
+    }
+}
@@ -1,14 +1,16 @@
 #![allow(clippy::new_without_default)]
 
+pub mod http;
 pub mod parsers;
 pub mod serde;
 pub mod service;
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::OnceLock;
 use std::time::{Duration, Instant};
 
 use futures::Future;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, Notify};
 
 /// Prefer this compared to [`tokio::spawn_blocking`], as spawn_blocking creates new OS threads and
 /// we don't really need that
@@ -26,9 +28,91 @@ where
     rx.await.expect("tokio channel closed")
 }
 
+static EXECUTION_POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+/// Sets the rayon pool used by [`spawn_execution_task`]. Defaults to a pool with one thread per core.
+pub fn set_execution_pool(pool: rayon::ThreadPool) -> anyhow::Result<()> {
+    EXECUTION_POOL.set(pool).map_err(|_| anyhow::anyhow!("The execution thread pool is already set up"))
+}
+
+fn execution_pool() -> &'static rayon::ThreadPool {
+    EXECUTION_POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .thread_name(|thread_index| format!("rayon-exec-{}", thread_index))
+            .build()
+            .expect("Building the execution thread pool")
+    })
+}
+
+/// Number of execution tasks waiting for a thread of the execution pool.
+static EXECUTION_QUEUE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+/// Moving average of the time the execution tasks waited for a thread, in microseconds.
+static EXECUTION_QUEUE_WAIT_MICROS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of tasks of [`spawn_execution_task`] waiting for a thread.
+pub fn execution_queue_depth() -> usize {
+    EXECUTION_QUEUE_DEPTH.load(Ordering::Relaxed)
+}
+
+/// Moving average of the time the tasks of [`spawn_execution_task`] waited for a thread. It is only updated when a
+/// task starts, so it is only meaningful while tasks are queued.
+pub fn execution_queue_wait() -> Duration {
+    Duration::from_micros(EXECUTION_QUEUE_WAIT_MICROS.load(Ordering::Relaxed))
+}
+
+fn record_execution_queue_wait(wait: Duration) {
+    let sample = u64::try_from(wait.as_micros()).unwrap_or(u64::MAX);
+    let _ = EXECUTION_QUEUE_WAIT_MICROS.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+        Some((average - average / 8).saturating_add(sample / 8))
+    });
+}
+
+/// Runs blockifier execution on its dedicated rayon pool. Block import and the trie/commitment hashing run on the
+/// global pool, so that RPC calls executing transactions do not hold them back, and the other way around.
+pub async fn spawn_execution_task<F, R>(func: F) -> R
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    let queued_at = Instant::now();
+    EXECUTION_QUEUE_DEPTH.fetch_add(1, Ordering::Relaxed);
+    execution_pool().spawn_fifo(move || {
+        EXECUTION_QUEUE_DEPTH.fetch_sub(1, Ordering::Relaxed);
+        record_execution_queue_wait(queued_at.elapsed());
+        let _result = tx.send(std::panic::catch_unwind(std::panic::AssertUnwindSafe(func)));
+    });
+
+    match rx.await.expect("tokio channel closed") {
+        Ok(res) => res,
+        // We bubble up the panics to the tokio pool.
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
 static CTRL_C: AtomicBool = AtomicBool::new(false);
 
+fn shutdown_requested() -> &'static Notify {
+    static SHUTDOWN_REQUESTED: OnceLock<Notify> = OnceLock::new();
+    SHUTDOWN_REQUESTED.get_or_init(Notify::new)
+}
+
+/// Stop the services as if the node received a SIGTERM: they finish their current step and return.
+pub fn request_shutdown() {
+    CTRL_C.store(true, Ordering::SeqCst);
+    shutdown_requested().notify_waiters();
+}
+
 async fn graceful_shutdown_inner() {
+    let requested = shutdown_requested().notified();
+    tokio::pin!(requested);
+    // Registered before checking the flag, so that a concurrent `request_shutdown` is not missed.
+    requested.as_mut().enable();
+    if CTRL_C.load(Ordering::SeqCst) {
+        return;
+    }
+
     let sigterm = async {
         match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
             Ok(mut signal) => signal.recv().await,
@@ -39,6 +123,7 @@ async fn graceful_shutdown_inner() {
     tokio::select! {
         _ = tokio::signal::ctrl_c() => {},
         _ = sigterm => {},
+        _ = requested => {},
     };
     CTRL_C.store(true, Ordering::SeqCst);
 }
@@ -36,7 +36,8 @@ pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
         "ms" => Ok(Duration::from_millis(value)),
         "s" => Ok(Duration::from_secs(value)),
         "min" => Ok(Duration::from_secs(value * 60)),
-        _ => bail!("Invalid duration suffix: {}. Expected 'ms', 's', or 'min'.", suffix),
+        "h" => Ok(Duration::from_secs(value * 60 * 60)),
+        _ => bail!("Invalid duration suffix: {}. Expected 'ms', 's', 'min' or 'h'.", suffix),
     }
 }
 
@@ -51,10 +52,10 @@ mod tests {
         assert_eq!(parse_duration("200ms").unwrap(), Duration::from_millis(200));
         assert_eq!(parse_duration("5min").unwrap(), Duration::from_secs(300));
         assert_eq!(parse_duration("1 min").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_duration("24h").unwrap(), Duration::from_secs(86400));
         assert_eq!(parse_duration("10 s").unwrap(), Duration::from_secs(10));
         assert!(parse_duration("2x").is_err());
         assert!(parse_duration("200").is_err());
-        assert!(parse_duration("5h").is_err());
         assert!(parse_duration("ms200").is_err());
         assert!(parse_duration("-5s").is_err());
         assert!(parse_duration("5.5s").is_err());
@@ -1,7 +1,12 @@
 //! Service trait and combinators.
 
+use crate::serde::deserialize_duration;
 use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::panic;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio::task::JoinSet;
 
 /// The app is divided into services, with each service having a different responsability within the app.
@@ -15,6 +20,18 @@ pub trait Service: 'static + Send + Sync {
         Ok(())
     }
 
+    /// Name of the service in the [`ServiceStatuses`] and in the logs.
+    fn name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    /// Whether [`Self::start`] can be called again after the tasks of the service failed, which is needed for a
+    /// [`RestartPolicy`].
+    fn restartable(&self) -> bool {
+        false
+    }
+
     async fn start_and_drive_to_end(mut self) -> anyhow::Result<()>
     where
         Self: Sized,
@@ -25,20 +42,205 @@ pub trait Service: 'static + Send + Sync {
     }
 }
 
+#[async_trait::async_trait]
+impl Service for Box<dyn Service> {
+    async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+        (**self).start(join_set).await
+    }
+
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn restartable(&self) -> bool {
+        (**self).restartable()
+    }
+}
+
+/// How a failed service is restarted, see [`Supervisor`]. The services of a [`ServiceGroup`] without a restart
+/// policy shut the node down when they fail.
+///
+/// The service is started again after waiting `initial_backoff`, doubling the delay on every following restart up to
+/// `max_backoff`. It is given up on once it was restarted `max_restarts` times; `None` restarts it indefinitely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RestartPolicy {
+    /// Number of restarts after which the service is given up on. `None` restarts it indefinitely.
+    pub max_restarts: Option<u32>,
+    /// Delay before the first restart, doubled on every following one.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between restarts.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub max_backoff: Duration,
+    /// A run lasting at least this long is considered healthy, and resets the restart count.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub reset_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: Some(5),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            reset_after: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Never restarts the service.
+    pub const NEVER: Self = Self {
+        max_restarts: Some(0),
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        reset_after: Duration::MAX,
+    };
+
+    /// Delay before restarting a service that was already restarted `restarts` times, or `None` if it should not be
+    /// restarted.
+    pub fn backoff(&self, restarts: u32) -> Option<Duration> {
+        if self.max_restarts.is_some_and(|max_restarts| restarts >= max_restarts) {
+            return None;
+        }
+        Some(self.initial_backoff.saturating_mul(2u32.saturating_pow(restarts)).min(self.max_backoff))
+    }
+}
+
+/// Health of a supervised service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceHealth {
+    /// The service is being started.
+    Starting,
+    /// The tasks of the service are running.
+    Running,
+    /// A task of the service failed, and the service is waiting to be restarted.
+    Restarting,
+    /// A task of the service failed, and the service was not restarted.
+    Failed,
+    /// The service has no task running: they finished, or the service does not run any.
+    Stopped,
+}
+
+/// Status of a service, as reported by `madara_serviceStatus` and `madara_exexStatus`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub name: String,
+    pub health: ServiceHealth,
+    /// Number of times the service was restarted after failing.
+    pub restarts: u32,
+    /// Error of the latest failure.
+    pub last_error: Option<String>,
+}
+
+/// Statuses of supervised services, shared with the RPC.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceStatuses(Arc<RwLock<BTreeMap<String, ServiceStatus>>>);
+
+impl ServiceStatuses {
+    /// Statuses of all the services, sorted by name.
+    pub fn get(&self) -> Vec<ServiceStatus> {
+        self.0.read().expect("Poisoned lock").values().cloned().collect()
+    }
+
+    fn set(&self, status: ServiceStatus) {
+        self.0.write().expect("Poisoned lock").insert(status.name.clone(), status);
+    }
+}
+
+/// Keeps the restart count of a service following its [`RestartPolicy`], and reports its health to the
+/// [`ServiceStatuses`]. Used for the services of a [`ServiceGroup`] and for the `ExEx`'s.
+#[derive(Debug)]
+pub struct Supervisor {
+    name: String,
+    restart_policy: RestartPolicy,
+    statuses: ServiceStatuses,
+    restarts: u32,
+    last_error: Option<String>,
+    started_at: Instant,
+}
+
+impl Supervisor {
+    pub fn new(name: impl Into<String>, restart_policy: RestartPolicy, statuses: ServiceStatuses) -> Self {
+        Self { name: name.into(), restart_policy, statuses, restarts: 0, last_error: None, started_at: Instant::now() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of times the service was restarted.
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
+    fn set_status(&self, health: ServiceHealth) {
+        self.statuses.set(ServiceStatus {
+            name: self.name.clone(),
+            health,
+            restarts: self.restarts,
+            last_error: self.last_error.clone(),
+        });
+    }
+
+    /// The service is being started, or restarted.
+    pub fn starting(&mut self) {
+        self.started_at = Instant::now();
+        self.last_error = None;
+        self.set_status(ServiceHealth::Starting);
+    }
+
+    /// The service started.
+    pub fn running(&self) {
+        self.set_status(ServiceHealth::Running);
+    }
+
+    /// The service is not running anymore, and will not be restarted.
+    pub fn stopped(&self) {
+        self.set_status(ServiceHealth::Stopped);
+    }
+
+    /// The service failed. Returns the delay before restarting it, or `None` if it is given up on. A run lasting at
+    /// least [`RestartPolicy::reset_after`] resets the restart count first.
+    pub fn failed(&mut self, err: &anyhow::Error) -> Option<Duration> {
+        if self.started_at.elapsed() >= self.restart_policy.reset_after {
+            self.restarts = 0;
+        }
+        self.last_error = Some(format!("{err:#}"));
+
+        let Some(backoff) = self.restart_policy.backoff(self.restarts) else {
+            self.set_status(ServiceHealth::Failed);
+            return None;
+        };
+        self.set_status(ServiceHealth::Restarting);
+        self.restarts += 1;
+        Some(backoff)
+    }
+}
+
 pub struct ServiceGroup {
     services: Vec<Box<dyn Service>>,
+    restart_policies: BTreeMap<String, RestartPolicy>,
+    statuses: ServiceStatuses,
     join_set: Option<JoinSet<anyhow::Result<()>>>,
 }
 
 impl Default for ServiceGroup {
     fn default() -> Self {
-        Self { services: vec![], join_set: Some(Default::default()) }
+        Self::new(vec![])
     }
 }
 
 impl ServiceGroup {
     pub fn new(services: Vec<Box<dyn Service>>) -> Self {
-        Self { services, join_set: Some(Default::default()) }
+        Self {
+            services,
+            restart_policies: Default::default(),
+            statuses: Default::default(),
+            join_set: Some(Default::default()),
+        }
     }
 
     /// Add a new service to the service group.
@@ -53,6 +255,18 @@ impl ServiceGroup {
         self.push(value);
         self
     }
+
+    /// Sets the restart policy of the service named `name`. Services without one shut the node down when they fail.
+    pub fn with_restart_policy(mut self, name: impl Into<String>, policy: RestartPolicy) -> Self {
+        self.restart_policies.insert(name.into(), policy);
+        self
+    }
+
+    /// Report the health of the services of the group to `statuses`.
+    pub fn with_statuses(mut self, statuses: ServiceStatuses) -> Self {
+        self.statuses = statuses;
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -60,19 +274,98 @@ impl Service for ServiceGroup {
     async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
         // drive the join set as a nested task
         let mut own_join_set = self.join_set.take().expect("Service has already been started.");
-        for svc in self.services.iter_mut() {
-            svc.start(&mut own_join_set).await.context("Starting service")?;
+
+        for (name, policy) in &self.restart_policies {
+            let service = self
+                .services
+                .iter()
+                .find(|svc| svc.name() == name)
+                .with_context(|| format!("Cannot set the restart policy of unknown service {name}"))?;
+            anyhow::ensure!(
+                *policy == RestartPolicy::NEVER || service.restartable(),
+                "Service {name} cannot be restarted"
+            );
+        }
+
+        for mut service in self.services.drain(..) {
+            let restart_policy = self.restart_policies.get(service.name()).copied();
+            let mut supervisor =
+                Supervisor::new(service.name(), restart_policy.unwrap_or(RestartPolicy::NEVER), self.statuses.clone());
+            supervisor.starting();
+            let mut service_join_set = JoinSet::new();
+            service
+                .start(&mut service_join_set)
+                .await
+                .with_context(|| format!("Starting service {}", service.name()))?;
+            own_join_set.spawn(run_supervised(supervisor, restart_policy.is_some(), service, service_join_set));
         }
 
         join_set.spawn(drive_joinset(own_join_set));
         Ok(())
     }
+
+    fn name(&self) -> &'static str {
+        "group"
+    }
 }
 
+/// Drives the tasks of a started service of a [`ServiceGroup`]. With `restart_on_failure`, the service is restarted
+/// following the restart policy of its `supervisor` whenever one of its tasks fails.
+async fn run_supervised(
+    mut supervisor: Supervisor,
+    restart_on_failure: bool,
+    mut service: Box<dyn Service>,
+    mut join_set: JoinSet<anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    let mut started = Ok(());
+
+    loop {
+        let result = match started {
+            Ok(()) => {
+                supervisor.running();
+                if restart_on_failure {
+                    drive_joinset_until_failure(join_set).await
+                } else {
+                    drive_joinset(join_set).await
+                }
+            }
+            Err(err) => Err(err),
+        };
+        let err = match result {
+            Ok(()) => {
+                supervisor.stopped();
+                return Ok(());
+            }
+            Err(err) => err,
+        };
+
+        let Some(backoff) = supervisor.failed(&err) else {
+            return Err(err).with_context(|| format!("Service {}", supervisor.name()));
+        };
+        log::error!("Service {} failed, restarting it in {backoff:?}: {err:#}", supervisor.name());
+        if crate::wait_or_graceful_shutdown(tokio::time::sleep(backoff)).await.is_none() {
+            supervisor.stopped();
+            return Ok(());
+        }
+
+        supervisor.starting();
+        join_set = JoinSet::new();
+        started = service.start(&mut join_set).await.context("Restarting service");
+    }
+}
+
+/// When a service fails, the other services are asked to shut down gracefully instead of being aborted, and the first
+/// error is returned once they have stopped.
 async fn drive_joinset(mut join_set: JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+    let mut res = Ok(());
     while let Some(result) = join_set.join_next().await {
         match result {
-            Ok(result) => result?,
+            Ok(Ok(())) => {}
+            Ok(Err(err)) if res.is_ok() => {
+                crate::request_shutdown();
+                res = Err(err);
+            }
+            Ok(Err(err)) => log::error!("Service stopped with an error during shutdown: {err:#}"),
             Err(panic_error) if panic_error.is_panic() => {
                 // bubble up panics too
                 panic::resume_unwind(panic_error.into_panic());
@@ -81,5 +374,136 @@ async fn drive_joinset(mut join_set: JoinSet<anyhow::Result<()>>) -> anyhow::Res
         }
     }
 
+    res
+}
+
+/// Used for the services that are restarted on failure: the first failure aborts the other tasks of the service, and is
+/// returned right away. Panics are reported as failures.
+async fn drive_joinset_until_failure(mut join_set: JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+    while let Some(result) = join_set.join_next().await {
+        let err = match result {
+            Ok(Ok(())) => continue,
+            Ok(Err(err)) => err,
+            Err(panic_error) if panic_error.is_panic() => anyhow::Error::new(panic_error).context("Task panicked"),
+            Err(_task_cancelled_error) => continue,
+        };
+        join_set.shutdown().await;
+        return Err(err);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails on its first `failures` starts.
+    struct Flaky {
+        starts: Arc<AtomicU32>,
+        failures: u32,
+    }
+
+    #[async_trait::async_trait]
+    impl Service for Flaky {
+        async fn start(&mut self, join_set: &mut JoinSet<anyhow::Result<()>>) -> anyhow::Result<()> {
+            let start = self.starts.fetch_add(1, Ordering::SeqCst);
+            let failures = self.failures;
+            join_set.spawn(async move {
+                anyhow::ensure!(start >= failures, "Failure #{start}");
+                Ok(())
+            });
+            Ok(())
+        }
+
+        fn restartable(&self) -> bool {
+            true
+        }
+    }
+
+    fn on_failure(max_restarts: Option<u32>) -> RestartPolicy {
+        RestartPolicy {
+            max_restarts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            reset_after: Duration::MAX,
+        }
+    }
+
+    #[test]
+    fn test_restart_policy_backoff() {
+        assert_eq!(RestartPolicy::NEVER.backoff(0), None);
+        let policy = on_failure(Some(5));
+        assert_eq!(policy.backoff(0), Some(Duration::from_millis(1)));
+        assert_eq!(policy.backoff(1), Some(Duration::from_millis(2)));
+        assert_eq!(policy.backoff(4), Some(Duration::from_millis(4)));
+        assert_eq!(policy.backoff(5), None);
+        assert_eq!(on_failure(None).backoff(u32::MAX), Some(Duration::from_millis(4)));
+    }
+
+    #[tokio::test]
+    async fn test_service_restarted_on_failure() {
+        let starts = Arc::new(AtomicU32::new(0));
+        let statuses = ServiceStatuses::default();
+        ServiceGroup::default()
+            .with(Flaky { starts: Arc::clone(&starts), failures: 2 })
+            .with_restart_policy("Flaky", on_failure(Some(2)))
+            .with_statuses(statuses.clone())
+            .start_and_drive_to_end()
+            .await
+            .unwrap();
+
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+        assert_eq!(
+            statuses.get(),
+            [ServiceStatus { name: "Flaky".into(), health: ServiceHealth::Stopped, restarts: 2, last_error: None }]
+        );
+    }
+
+    #[test]
+    fn test_supervisor_restarts() {
+        let statuses = ServiceStatuses::default();
+        let mut supervisor = Supervisor::new("service", on_failure(Some(1)), statuses.clone());
+        let err = anyhow::anyhow!("Failure");
+
+        supervisor.starting();
+        supervisor.running();
+        assert_eq!(supervisor.failed(&err), Some(Duration::from_millis(1)));
+        assert_eq!(
+            statuses.get(),
+            [ServiceStatus {
+                name: "service".into(),
+                health: ServiceHealth::Restarting,
+                restarts: 0,
+                last_error: Some("Failure".into())
+            }]
+        );
+
+        supervisor.starting();
+        assert_eq!(supervisor.restarts(), 1);
+        assert_eq!(statuses.get()[0].last_error, None);
+        assert_eq!(supervisor.failed(&err), None);
+        assert_eq!(statuses.get()[0].health, ServiceHealth::Failed);
+    }
+
+    #[test]
+    fn test_supervisor_healthy_run_resets_restarts() {
+        let statuses = ServiceStatuses::default();
+        let policy = RestartPolicy { reset_after: Duration::ZERO, ..on_failure(Some(1)) };
+        let mut supervisor = Supervisor::new("service", policy, statuses.clone());
+        let err = anyhow::anyhow!("Failure");
+
+        for _ in 0..3 {
+            supervisor.starting();
+            assert_eq!(supervisor.failed(&err), Some(Duration::from_millis(1)));
+            assert_eq!(supervisor.restarts(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restart_policy_unknown_service() {
+        let res =
+            ServiceGroup::default().with_restart_policy("unknown", on_failure(None)).start_and_drive_to_end().await;
+        assert!(res.is_err());
+    }
+}